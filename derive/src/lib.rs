@@ -0,0 +1,422 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, Data, DeriveInput, Expr, Fields, GenericArgument, LitStr, PathArguments,
+    Type,
+};
+
+/// Derives `rtsyn_plugin::config::UIConfig` for a struct with named fields.
+///
+/// Field attributes (all optional) under `#[ui(...)]`:
+/// `label = "..."`, `hint = "..."`, `default = <expr>`, `min = <expr>`,
+/// `max = <expr>`, `step = <expr>`, `unit = "..."`, `precision = <expr>`,
+/// `max_length = <expr>`, `multiline`, `filepath`, `read_only`, `skip`.
+///
+/// On the struct, `#[ui(schema_version = <expr>)]` sets
+/// `UISchema::schema_version`; bump it together with the plugin's
+/// `migrate_config` when fields are renamed or restructured.
+#[proc_macro_derive(UIConfig, attributes(ui))]
+pub fn derive_ui_config(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Derives port index constants for a struct whose `f64` fields mirror
+/// ports, like `rtsyn_plugin::static_ports!` does for a module.
+///
+/// Fields marked `#[port(input)]` or `#[port(output)]` become ports, named
+/// after the field unless `name = "..."` is given. The struct gets
+/// `IN_<FIELD>`/`OUT_<FIELD>` index constants, `INPUTS`/`OUTPUTS`,
+/// `static_ports()` building the matching `StaticPorts`, and
+/// `read_inputs`/`write_outputs` copying values between the two by index.
+#[proc_macro_derive(StaticPorts, attributes(port))]
+pub fn derive_static_ports(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_ports(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    label: Option<LitStr>,
+    hint: Option<LitStr>,
+    default: Option<Expr>,
+    min: Option<Expr>,
+    max: Option<Expr>,
+    step: Option<Expr>,
+    unit: Option<LitStr>,
+    precision: Option<Expr>,
+    max_length: Option<Expr>,
+    multiline: bool,
+    filepath: bool,
+    read_only: bool,
+    skip: bool,
+}
+
+enum Kind {
+    Integer,
+    Float,
+    Boolean,
+    Text,
+    FilePath,
+    List,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "UIConfig requires a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "UIConfig can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut schema_fields = Vec::new();
+    if let Some(version) = parse_schema_version(&input)? {
+        schema_fields.push(quote! { .schema_version(#version) });
+    }
+    let mut initializers = Vec::new();
+
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let attrs = parse_attrs(field)?;
+
+        if attrs.skip {
+            initializers.push(quote! { #ident: ::core::default::Default::default() });
+            continue;
+        }
+
+        let key = ident.to_string();
+        let label = attrs
+            .label
+            .as_ref()
+            .map(LitStr::value)
+            .unwrap_or_else(|| key.clone());
+        let kind = if attrs.filepath {
+            Kind::FilePath
+        } else {
+            kind_of(&field.ty)
+        };
+
+        let constructor = match kind {
+            Kind::Integer => quote!(integer),
+            Kind::Float => quote!(float),
+            Kind::Boolean => quote!(boolean),
+            Kind::Text => quote!(text),
+            Kind::FilePath => quote!(filepath),
+            Kind::List => quote!(dynamic_list),
+        };
+
+        let mut builder = quote! {
+            ::rtsyn_plugin::ui::ConfigField::#constructor(#key, #label)
+        };
+        if let Some(hint) = &attrs.hint {
+            builder = quote! { #builder.hint(#hint) };
+        }
+        if attrs.read_only {
+            builder = quote! { #builder.read_only() };
+        }
+        if let Some(default) = &attrs.default {
+            builder = quote! {
+                #builder.default_value(::rtsyn_plugin::__private::serde_json::json!(#default))
+            };
+        }
+        match kind {
+            Kind::Integer => {
+                if let Some(min) = &attrs.min {
+                    builder = quote! { #builder.min((#min) as i64) };
+                }
+                if let Some(max) = &attrs.max {
+                    builder = quote! { #builder.max((#max) as i64) };
+                }
+                if let Some(step) = &attrs.step {
+                    builder = quote! { #builder.step((#step) as i64) };
+                }
+                if let Some(unit) = &attrs.unit {
+                    builder = quote! { #builder.unit(#unit) };
+                }
+            }
+            Kind::Float => {
+                if let Some(min) = &attrs.min {
+                    builder = quote! { #builder.min_f((#min) as f64) };
+                }
+                if let Some(max) = &attrs.max {
+                    builder = quote! { #builder.max_f((#max) as f64) };
+                }
+                if let Some(step) = &attrs.step {
+                    builder = quote! { #builder.step_f((#step) as f64) };
+                }
+                if let Some(unit) = &attrs.unit {
+                    builder = quote! { #builder.unit(#unit) };
+                }
+                if let Some(precision) = &attrs.precision {
+                    builder = quote! { #builder.display_precision((#precision) as u32) };
+                }
+            }
+            Kind::Text => {
+                if let Some(max_length) = &attrs.max_length {
+                    builder = quote! { #builder.max_length((#max_length) as usize) };
+                }
+                if attrs.multiline {
+                    builder = quote! { #builder.multiline() };
+                }
+            }
+            _ => {}
+        }
+        schema_fields.push(quote! { .field(#builder) });
+
+        let fallback = match &attrs.default {
+            Some(default) => quote! {
+                ::rtsyn_plugin::__private::serde_json::from_value(
+                    ::rtsyn_plugin::__private::serde_json::json!(#default),
+                )
+                .map_err(|e| ::rtsyn_plugin::PluginError::Config(format!("{}: {}", #key, e)))?
+            },
+            None => quote! { ::core::default::Default::default() },
+        };
+        initializers.push(quote! {
+            #ident: match value.get(#key) {
+                Some(v) => ::rtsyn_plugin::__private::serde_json::from_value(v.clone())
+                    .map_err(|e| ::rtsyn_plugin::PluginError::Config(format!("{}: {}", #key, e)))?,
+                None => #fallback,
+            }
+        });
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::rtsyn_plugin::config::UIConfig for #name #ty_generics #where_clause {
+            fn ui_schema() -> ::rtsyn_plugin::ui::UISchema {
+                ::rtsyn_plugin::ui::UISchema::new()
+                    #(#schema_fields)*
+            }
+
+            fn from_config(
+                value: &::rtsyn_plugin::__private::serde_json::Value,
+            ) -> ::core::result::Result<Self, ::rtsyn_plugin::PluginError> {
+                ::core::result::Result::Ok(Self {
+                    #(#initializers,)*
+                })
+            }
+        }
+    })
+}
+
+fn expand_ports(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "StaticPorts requires a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "StaticPorts can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let Some((output, port)) = parse_port_attr(field)? else {
+            continue;
+        };
+        let port = port.unwrap_or_else(|| LitStr::new(&ident.to_string(), ident.span()));
+        let (prefix, list) = if output {
+            ("OUT", &mut outputs)
+        } else {
+            ("IN", &mut inputs)
+        };
+        let constant = format_ident!("{}_{}", prefix, ident.to_string().to_uppercase());
+        list.push((ident, constant, port));
+    }
+
+    let in_count = inputs.len();
+    let out_count = outputs.len();
+    let in_consts = inputs.iter().enumerate().map(|(index, (_, constant, _))| {
+        quote! { pub const #constant: usize = #index; }
+    });
+    let out_consts = outputs.iter().enumerate().map(|(index, (_, constant, _))| {
+        quote! { pub const #constant: usize = #index; }
+    });
+    let in_names: Vec<_> = inputs.iter().map(|(_, _, port)| port).collect();
+    let out_names: Vec<_> = outputs.iter().map(|(_, _, port)| port).collect();
+    let reads = inputs.iter().map(|(ident, constant, _)| {
+        quote! { self.#ident = ports.input(Self::#constant); }
+    });
+    let writes = outputs.iter().map(|(ident, constant, _)| {
+        quote! { ports.set_output(Self::#constant, self.#ident); }
+    });
+
+    Ok(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            #(#in_consts)*
+            #(#out_consts)*
+
+            pub const INPUTS: &'static [&'static str] = &[#(#in_names),*];
+            pub const OUTPUTS: &'static [&'static str] = &[#(#out_names),*];
+
+            pub fn static_ports() -> ::rtsyn_plugin::ports::StaticPorts<#in_count, #out_count> {
+                ::rtsyn_plugin::ports::StaticPorts::new([#(#in_names),*], [#(#out_names),*])
+            }
+
+            pub fn read_inputs(
+                &mut self,
+                ports: &::rtsyn_plugin::ports::StaticPorts<#in_count, #out_count>,
+            ) {
+                #(#reads)*
+            }
+
+            pub fn write_outputs(
+                &self,
+                ports: &mut ::rtsyn_plugin::ports::StaticPorts<#in_count, #out_count>,
+            ) {
+                #(#writes)*
+            }
+        }
+    })
+}
+
+// `Some((is_output, name))` for fields marked `#[port(...)]`.
+fn parse_port_attr(field: &syn::Field) -> syn::Result<Option<(bool, Option<LitStr>)>> {
+    let mut direction = None;
+    let mut port = None;
+    for attr in &field.attrs {
+        if !attr.path().is_ident("port") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("input") {
+                direction = Some(false);
+            } else if meta.path.is_ident("output") {
+                direction = Some(true);
+            } else if meta.path.is_ident("name") {
+                port = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("unknown port attribute"));
+            }
+            Ok(())
+        })?;
+        if direction.is_none() {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "expected `input` or `output`",
+            ));
+        }
+    }
+    Ok(direction.map(|output| (output, port)))
+}
+
+fn parse_attrs(field: &syn::Field) -> syn::Result<FieldAttrs> {
+    let mut attrs = FieldAttrs::default();
+    for attr in &field.attrs {
+        if !attr.path().is_ident("ui") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("label") {
+                attrs.label = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("hint") {
+                attrs.hint = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("default") {
+                attrs.default = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("min") {
+                attrs.min = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("max") {
+                attrs.max = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("step") {
+                attrs.step = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("unit") {
+                attrs.unit = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("precision") {
+                attrs.precision = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("max_length") {
+                attrs.max_length = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("multiline") {
+                attrs.multiline = true;
+            } else if meta.path.is_ident("filepath") {
+                attrs.filepath = true;
+            } else if meta.path.is_ident("read_only") {
+                attrs.read_only = true;
+            } else if meta.path.is_ident("skip") {
+                attrs.skip = true;
+            } else {
+                return Err(meta.error("unknown ui attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(attrs)
+}
+
+fn parse_schema_version(input: &DeriveInput) -> syn::Result<Option<Expr>> {
+    let mut version = None;
+    for attr in &input.attrs {
+        if !attr.path().is_ident("ui") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("schema_version") {
+                version = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unknown ui attribute"))
+            }
+        })?;
+    }
+    Ok(version)
+}
+
+fn kind_of(ty: &Type) -> Kind {
+    let Type::Path(path) = ty else {
+        return Kind::Text;
+    };
+    let Some(segment) = path.path.segments.last() else {
+        return Kind::Text;
+    };
+    match segment.ident.to_string().as_str() {
+        "f32" | "f64" => Kind::Float,
+        "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize" => {
+            Kind::Integer
+        }
+        "bool" => Kind::Boolean,
+        "PathBuf" => Kind::FilePath,
+        "Vec" => Kind::List,
+        "Option" => match &segment.arguments {
+            PathArguments::AngleBracketed(args) => match args.args.first() {
+                Some(GenericArgument::Type(inner)) => kind_of(inner),
+                _ => Kind::Text,
+            },
+            _ => Kind::Text,
+        },
+        _ => Kind::Text,
+    }
+}
@@ -11,8 +11,28 @@ typedef struct {
     char output_path[256];
 } ExamplePlugin;
 
+// Hands a NUL-terminated string to the host, which releases it
+static RTSynPluginString plugin_string(const char* s) {
+    return rtsyn_plugin_string_new((const uint8_t*)s, strlen(s));
+}
+
+// Same for strings returned by rtsyn_* functions
+static RTSynPluginString take_rtsyn_string(char* s) {
+    if (!s) {
+        RTSynPluginString none = {NULL, 0, 0};
+        return none;
+    }
+    RTSynPluginString out = plugin_string(s);
+    rtsyn_string_free(s);
+    return out;
+}
+
+static int name_is(const uint8_t* name, size_t len, const char* expected) {
+    return len == strlen(expected) && memcmp(name, expected, len) == 0;
+}
+
 // Create plugin instance
-void* create(uint64_t id) {
+static void* create(uint64_t id) {
     ExamplePlugin* plugin = (ExamplePlugin*)malloc(sizeof(ExamplePlugin));
     if (!plugin) return NULL;
     
@@ -25,40 +45,42 @@ void* create(uint64_t id) {
 }
 
 // Destroy plugin instance
-void destroy(void* instance) {
+static void destroy(void* instance) {
     if (instance) {
         free(instance);
     }
 }
 
 // Get plugin metadata
-char* meta_json(void* instance) {
-    const char* json = "{"
+static RTSynPluginString meta_json(void* instance) {
+    (void)instance;
+    return plugin_string("{"
         "\"name\":\"Example C Plugin\","
         "\"fixed_vars\":[],"
         "\"default_vars\":["
             "{\"amplitude\":1.0},"
             "{\"frequency\":440.0}"
         "]"
-    "}";
-    return strdup(json);
+    "}");
 }
 
 // Get inputs
-char* inputs_json(void* instance) {
-    return strdup("[]");
+static RTSynPluginString inputs_json(void* instance) {
+    (void)instance;
+    return plugin_string("[]");
 }
 
 // Get outputs
-char* outputs_json(void* instance) {
-    const char* json = "["
+static RTSynPluginString outputs_json(void* instance) {
+    (void)instance;
+    return plugin_string("["
         "{\"id\":\"signal\"}"
-    "]";
-    return strdup(json);
+    "]");
 }
 
 // NEW: Get UI schema
-char* ui_schema_json(void* instance) {
+static RTSynPluginString ui_schema_json(void* instance) {
+    (void)instance;
     // Create schema
     RTSynUISchema* schema = rtsyn_ui_schema_new();
     
@@ -97,47 +119,70 @@ char* ui_schema_json(void* instance) {
     // Free schema (fields are consumed)
     rtsyn_ui_schema_free(schema);
     
-    return json;
+    return take_rtsyn_string(json);
 }
 
 // NEW: Get behavior
-char* behavior_json(void* instance) {
-    return rtsyn_behavior_to_json(
+static RTSynPluginString behavior_json(void* instance) {
+    (void)instance;
+    return take_rtsyn_string(rtsyn_behavior_to_json(
         1,  // supports_start_stop
         1,  // supports_restart
         RTSYN_EXTENDABLE_NONE,  // extendable_inputs_type
         NULL,  // extendable_inputs_pattern
         1,  // loads_started
         0   // connection_dependent
-    );
+    ));
 }
 
-// Process function
-int process(void* instance, uint64_t tick, double period_seconds) {
-    // Processing logic here
-    return 0;
+// Set config from JSON
+static RtsynStatus set_config_json(void* instance, const uint8_t* data, size_t len) {
+    // Parse JSON and update plugin config
+    // For simplicity, this example doesn't parse JSON
+    (void)data;
+    (void)len;
+    return instance ? RTSYN_STATUS_OK : RTSYN_STATUS_INVALID_ARGUMENT;
 }
 
 // Set input value
-void set_input(void* instance, const char* port, double value) {
+static void set_input(void* instance, const uint8_t* name, size_t len, double value) {
     // No inputs for this example
+    (void)instance;
+    (void)name;
+    (void)len;
+    (void)value;
+}
+
+// Process function
+static RtsynStatus process(void* instance, uint64_t tick, double period_seconds) {
+    // Processing logic here
+    (void)tick;
+    (void)period_seconds;
+    return instance ? RTSYN_STATUS_OK : RTSYN_STATUS_INVALID_ARGUMENT;
 }
 
 // Get output value
-double get_output(void* instance, const char* port) {
+static double get_output(void* instance, const uint8_t* name, size_t len) {
     ExamplePlugin* plugin = (ExamplePlugin*)instance;
-    if (strcmp(port, "signal") == 0) {
+    if (name_is(name, len, "signal")) {
         // Generate simple sine wave
         return plugin->amplitude;
     }
     return 0.0;
 }
 
-// Set config from JSON
-int set_config_json(void* instance, const char* json) {
-    // Parse JSON and update plugin config
-    // For simplicity, this example doesn't parse JSON
-    return 0;
+// Optional: reject names that are not an output
+static RtsynStatus get_output_checked(
+    void* instance,
+    const uint8_t* name,
+    size_t len,
+    double* out_value
+) {
+    if (!name_is(name, len, "signal")) {
+        return RTSYN_STATUS_UNKNOWN_PORT;
+    }
+    *out_value = get_output(instance, name, len);
+    return RTSYN_STATUS_OK;
 }
 
 // Export plugin API
@@ -147,30 +192,23 @@ int set_config_json(void* instance, const char* json) {
 #define EXPORT __attribute__((visibility("default")))
 #endif
 
-typedef struct {
-    void* (*create)(uint64_t);
-    void (*destroy)(void*);
-    char* (*meta_json)(void*);
-    char* (*inputs_json)(void*);
-    char* (*outputs_json)(void*);
-    int (*process)(void*, uint64_t, double);
-    void (*set_input)(void*, const char*, double);
-    double (*get_output)(void*, const char*);
-    int (*set_config_json)(void*, const char*);
-    char* (*ui_schema_json)(void*);
-    char* (*behavior_json)(void*);
-} PluginApi;
-
-EXPORT PluginApi rtsyn_plugin_api = {
+// Optional entries left out here are NULL
+static const RTSynPluginApi api = {
+    .struct_size = sizeof(RTSynPluginApi),
     .create = create,
     .destroy = destroy,
     .meta_json = meta_json,
     .inputs_json = inputs_json,
     .outputs_json = outputs_json,
-    .process = process,
+    .behavior_json = behavior_json,
+    .ui_schema_json = ui_schema_json,
+    .set_config_json = set_config_json,
     .set_input = set_input,
+    .process = process,
     .get_output = get_output,
-    .set_config_json = set_config_json,
-    .ui_schema_json = ui_schema_json,
-    .behavior_json = behavior_json,
+    .get_output_checked = get_output_checked,
 };
+
+EXPORT const RTSynPluginApi* rtsyn_plugin_api_v2(void) {
+    return &api;
+}
@@ -1,6 +1,8 @@
 #ifndef RTSYN_PLUGIN_UI_H
 #define RTSYN_PLUGIN_UI_H
 
+#include <stdbool.h>
+#include <stddef.h>
 #include <stdint.h>
 
 #ifdef __cplusplus
@@ -16,11 +18,45 @@ typedef struct RTSynConfigField RTSynConfigField;
 #define RTSYN_FILE_MODE_SAVE 1
 #define RTSYN_FILE_MODE_FOLDER 2
 
+// Widget hint constants for numeric fields
+#define RTSYN_WIDGET_SLIDER 0
+#define RTSYN_WIDGET_LOG_SLIDER 1
+#define RTSYN_WIDGET_KNOB 2
+#define RTSYN_WIDGET_LOG_KNOB 3
+#define RTSYN_WIDGET_STEPPER 4
+
 // Extendable inputs type constants
 #define RTSYN_EXTENDABLE_NONE 0
 #define RTSYN_EXTENDABLE_MANUAL 1
 #define RTSYN_EXTENDABLE_AUTO 2
 
+// Status codes.
+// Functions without a result return one directly. Functions returning a
+// pointer return NULL on failure; rtsyn_last_status() then has the code.
+typedef int32_t RtsynStatus;
+
+#define RTSYN_STATUS_OK 0
+#define RTSYN_STATUS_INVALID_ARGUMENT 1
+#define RTSYN_STATUS_UTF8_ERROR 2
+#define RTSYN_STATUS_SERIALIZATION_ERROR 3
+#define RTSYN_STATUS_PANIC 4
+// Checked port calls of RTSynPluginApi
+#define RTSYN_STATUS_UNKNOWN_PORT 5
+#define RTSYN_STATUS_WRONG_DIRECTION 6
+// Plugin errors: RTSYN_STATUS_PLUGIN_ERROR + one of RTSYN_PLUGIN_ERROR_*
+#define RTSYN_STATUS_PLUGIN_ERROR 100
+
+#define RTSYN_PLUGIN_ERROR_CONFIG 0
+#define RTSYN_PLUGIN_ERROR_IO 1
+#define RTSYN_PLUGIN_ERROR_DEVICE 2
+#define RTSYN_PLUGIN_ERROR_FATAL 3
+#define RTSYN_PLUGIN_ERROR_RECOVERABLE 4
+
+/**
+ * Status of the last rtsyn call made on the calling thread.
+ */
+RtsynStatus rtsyn_last_status(void);
+
 // === UI Schema Functions ===
 
 /**
@@ -36,9 +72,9 @@ void rtsyn_ui_schema_free(RTSynUISchema* schema);
 
 /**
  * Add a field to the schema.
- * The field is consumed and should not be freed separately.
+ * The field is consumed, also on failure, and should not be freed separately.
  */
-void rtsyn_ui_schema_add_field(RTSynUISchema* schema, RTSynConfigField* field);
+RtsynStatus rtsyn_ui_schema_add_field(RTSynUISchema* schema, RTSynConfigField* field);
 
 /**
  * Convert schema to JSON string.
@@ -58,6 +94,44 @@ RTSynConfigField* rtsyn_ui_field_text(
     const char* default_value
 );
 
+/**
+ * Create a secret field, e.g. a password or API token.
+ * Hosts render it masked and keep its value out of logs.
+ */
+RTSynConfigField* rtsyn_ui_field_secret(const char* key, const char* label);
+
+/**
+ * Create a block of Markdown text, e.g. usage notes or a warning.
+ * label can be NULL for a block without a heading. It holds no
+ * configuration value.
+ */
+RTSynConfigField* rtsyn_ui_field_info(
+    const char* key,
+    const char* label,
+    const char* markdown
+);
+
+/**
+ * Create a read-only field showing the live value of output source_output,
+ * e.g. a measured sample rate. It holds no configuration value.
+ */
+RTSynConfigField* rtsyn_ui_field_computed(
+    const char* key,
+    const char* label,
+    const char* source_output
+);
+
+/**
+ * Create an action button. Pressing it calls the plugin's invoke_action
+ * entry with `action_id`. `confirm` (nullable) is asked before invoking it.
+ */
+RTSynConfigField* rtsyn_ui_field_action(
+    const char* key,
+    const char* label,
+    const char* action_id,
+    const char* confirm
+);
+
 /**
  * Create an integer field with min/max bounds.
  */
@@ -80,6 +154,32 @@ RTSynConfigField* rtsyn_ui_field_float(
     double max
 );
 
+/**
+ * Set the preferred editor of an integer or float field to one of
+ * RTSYN_WIDGET_*. Log variants need a positive minimum.
+ * Returns RTSYN_STATUS_INVALID_ARGUMENT if field is not numeric.
+ */
+RtsynStatus rtsyn_ui_field_set_widget(RTSynConfigField* field, int widget);
+
+/**
+ * Set the unit shown after the value of an integer or float field,
+ * e.g. "Hz". unit can be NULL to clear it.
+ * display_precision: decimal places for float fields, negative to let the
+ * host decide; ignored for integer fields.
+ * Returns RTSYN_STATUS_INVALID_ARGUMENT if field is not numeric.
+ */
+RtsynStatus rtsyn_ui_field_set_unit(
+    RTSynConfigField* field,
+    const char* unit,
+    int display_precision
+);
+
+/**
+ * Show a field without letting the user edit it (read_only non-zero).
+ * Its value stays part of the configuration.
+ */
+RtsynStatus rtsyn_ui_field_set_read_only(RTSynConfigField* field, int read_only);
+
 /**
  * Create a boolean field.
  * default_value: 0 = false, non-zero = true
@@ -102,6 +202,54 @@ RTSynConfigField* rtsyn_ui_field_filepath(
     int mode
 );
 
+/**
+ * Create a choice (dropdown) field with no options.
+ * default_value is the stored value of the initially selected option, can be NULL.
+ */
+RTSynConfigField* rtsyn_ui_field_choice(
+    const char* key,
+    const char* label,
+    const char* default_value
+);
+
+/**
+ * Append an option to a choice field.
+ * value is stored in the config; label is shown to the user.
+ * label can be NULL, in which case value is shown.
+ * Returns RTSYN_STATUS_INVALID_ARGUMENT if field is not a choice field.
+ */
+RtsynStatus rtsyn_ui_field_choice_add_option(
+    RTSynConfigField* field,
+    const char* value,
+    const char* label
+);
+
+/**
+ * Have the host ask the plugin's options_json export for the options of a
+ * choice field each time it is shown, e.g. to list attached devices.
+ * Options added with rtsyn_ui_field_choice_add_option become a fallback.
+ * Returns RTSYN_STATUS_INVALID_ARGUMENT if field is not a choice field.
+ */
+RtsynStatus rtsyn_ui_field_choice_options_from_plugin(RTSynConfigField* field);
+
+/**
+ * Create a collapsible group of fields.
+ * Grouping is presentational only: child keys stay top-level in the config.
+ * collapsed: 0 = expanded, non-zero = collapsed initially
+ */
+RTSynConfigField* rtsyn_ui_field_group(
+    const char* key,
+    const char* label,
+    int collapsed
+);
+
+/**
+ * Add a field to a group.
+ * The field is consumed, also on failure, and should not be freed separately.
+ * Returns RTSYN_STATUS_INVALID_ARGUMENT if group is not a group field.
+ */
+RtsynStatus rtsyn_ui_group_add_field(RTSynConfigField* group, RTSynConfigField* field);
+
 /**
  * Free a config field (only if not added to schema).
  */
@@ -131,6 +279,228 @@ char* rtsyn_behavior_to_json(
  */
 void rtsyn_string_free(char* s);
 
+// === Plugin API Table ===
+
+/**
+ * String handed between plugin and host. Strings a plugin returns are
+ * released by the host, so create them with rtsyn_plugin_string_new().
+ * A NULL ptr means "no value".
+ */
+typedef struct RTSynPluginString {
+    uint8_t* ptr;
+    size_t len;
+    size_t cap;
+} RTSynPluginString;
+
+/**
+ * Copy len bytes of UTF-8 into a string the host can release.
+ */
+RTSynPluginString rtsyn_plugin_string_new(const uint8_t* data, size_t len);
+
+/**
+ * Release a string, e.g. one a plugin created but did not return.
+ */
+void rtsyn_plugin_string_free(RTSynPluginString value);
+
+/**
+ * Borrowed UTF-8 port name, as passed in arrays to set_inputs/get_outputs.
+ */
+typedef struct RTSynPortName {
+    const uint8_t* ptr;
+    size_t len;
+} RTSynPortName;
+
+// Returned by resolve_input/resolve_output for unknown ports.
+#define RTSYN_INVALID_PORT_HANDLE UINT32_MAX
+
+// Host services passed to create_with_host, see src/host.rs. Entries are
+// appended only; ignore any entry at or past struct_size, and the whole
+// table when struct_size does not reach log.
+typedef struct RTSynHostApi {
+    size_t struct_size;
+    void* user_data;
+    void (*log)(void* user_data, uint64_t plugin, uint8_t level, const uint8_t* msg, size_t len);
+    uint64_t (*now_ns)(void* user_data, uint64_t plugin);
+    uint8_t* (*alloc)(void* user_data, uint64_t plugin, size_t size, size_t align);
+    void (*free)(void* user_data, uint64_t plugin, uint8_t* ptr, size_t size, size_t align);
+    bool (*post_event)(
+        void* user_data,
+        uint64_t plugin,
+        const uint8_t* name,
+        size_t name_len,
+        const uint8_t* data,
+        size_t data_len);
+    size_t (*resolve_resource)(
+        void* user_data,
+        uint64_t plugin,
+        const uint8_t* name,
+        size_t name_len,
+        uint8_t* out,
+        size_t out_len);
+    uint64_t (*submit_job)(void* user_data, uint64_t plugin, const uint8_t* name, size_t name_len);
+    void (*job_progress)(
+        void* user_data,
+        uint64_t plugin,
+        uint64_t job,
+        const uint8_t* status,
+        size_t status_len);
+    uint8_t (*job_control)(void* user_data, uint64_t plugin, uint64_t job);
+    size_t (*state_path)(
+        void* user_data,
+        uint64_t plugin,
+        const uint8_t* name,
+        size_t name_len,
+        uint8_t* out,
+        size_t out_len);
+} RTSynHostApi;
+
+/**
+ * Table returned by the exported function
+ *     const RTSynPluginApi* rtsyn_plugin_api_v2(void);
+ * Entries after get_output are optional and may be NULL. Set struct_size to
+ * sizeof(RTSynPluginApi); the host treats entries past it as absent.
+ * Entries that can fail return an RtsynStatus and hand back their result,
+ * if any, through an out parameter. JSON is passed as UTF-8 bytes with a
+ * length and is not NUL-terminated.
+ */
+typedef struct RTSynPluginApi {
+    size_t struct_size;
+    void* (*create)(uint64_t id);
+    void (*destroy)(void* handle);
+    RTSynPluginString (*meta_json)(void* handle);
+    RTSynPluginString (*inputs_json)(void* handle);
+    RTSynPluginString (*outputs_json)(void* handle);
+    RTSynPluginString (*behavior_json)(void* handle);
+    RTSynPluginString (*ui_schema_json)(void* handle);
+    RtsynStatus (*set_config_json)(void* handle, const uint8_t* data, size_t len);
+    void (*set_input)(void* handle, const uint8_t* name, size_t len, double value);
+    RtsynStatus (*process)(void* handle, uint64_t tick, double period_seconds);
+    double (*get_output)(void* handle, const uint8_t* name, size_t len);
+
+    RTSynPluginString (*get_state)(void* handle);
+    RtsynStatus (*set_state)(void* handle, const uint8_t* data, size_t len);
+    RtsynStatus (*start)(void* handle);
+    RtsynStatus (*stop)(void* handle);
+    RtsynStatus (*pause)(void* handle);
+    RtsynStatus (*resume)(void* handle);
+    RtsynStatus (*reset)(void* handle);
+    // Bit i set when output i changed since the last call.
+    uint64_t (*changed_outputs_mask)(void* handle);
+    RtsynStatus (*set_host_info_json)(void* handle, const uint8_t* data, size_t len);
+    // Set *done once shutdown has completed.
+    RtsynStatus (*begin_shutdown)(void* handle, bool* done);
+    RtsynStatus (*poll_shutdown)(void* handle, bool* done);
+    void* (*create_with_params)(uint64_t id, const uint8_t* data, size_t len);
+    // Bit i set when input/output i is connected.
+    void (*set_connections)(void* handle, uint64_t inputs, uint64_t outputs);
+    // host may be NULL.
+    void* (*create_with_host)(uint64_t id, const RTSynHostApi* host);
+    // Stores the JSON result in *out, or leaves it NULL for unknown actions.
+    RtsynStatus (*action_json)(
+        void* handle,
+        const uint8_t* name,
+        size_t name_len,
+        const uint8_t* args,
+        size_t args_len,
+        RTSynPluginString* out
+    );
+    // Raw integers in the port's numeric format.
+    void (*set_input_raw)(void* handle, const uint8_t* name, size_t len, int64_t value);
+    int64_t (*get_output_raw)(void* handle, const uint8_t* name, size_t len);
+    RtsynStatus (*on_event_json)(void* handle, const uint8_t* data, size_t len);
+    // JSON array of the events emitted since the last call.
+    RTSynPluginString (*take_events_json)(void* handle);
+    uint64_t (*latency_ticks)(void* handle);
+    RTSynPluginString (*features_json)(void* handle);
+    RtsynStatus (*set_feature_enabled)(
+        void* handle,
+        const uint8_t* name,
+        size_t len,
+        bool enabled
+    );
+    // Unknown names are skipped, read as 0.0 and reported as
+    // RTSYN_STATUS_UNKNOWN_PORT once all known ports were handled.
+    RtsynStatus (*set_inputs)(
+        void* handle,
+        const RTSynPortName* names,
+        const double* values,
+        size_t count
+    );
+    RtsynStatus (*get_outputs)(
+        void* handle,
+        const RTSynPortName* names,
+        double* out_values,
+        size_t count
+    );
+    RTSynPluginString (*preview_json)(void* handle);
+    // Port handles for the *_by_handle calls, or RTSYN_INVALID_PORT_HANDLE.
+    uint32_t (*resolve_input)(void* handle, const uint8_t* name, size_t len);
+    uint32_t (*resolve_output)(void* handle, const uint8_t* name, size_t len);
+    RtsynStatus (*set_input_by_handle)(void* handle, uint32_t port, double value);
+    double (*get_output_by_handle)(void* handle, uint32_t port);
+    RtsynStatus (*prepare)(void* handle, double sample_rate, size_t block_size);
+    RtsynStatus (*on_overrun)(void* handle, uint64_t missed);
+    RTSynPluginString (*templates_json)(void* handle);
+    // Return RTSYN_STATUS_UNKNOWN_PORT or RTSYN_STATUS_WRONG_DIRECTION for
+    // names that are not an input/output. *out_value is left untouched on
+    // failure.
+    RtsynStatus (*set_input_checked)(
+        void* handle,
+        const uint8_t* name,
+        size_t len,
+        double value
+    );
+    RtsynStatus (*get_output_checked)(
+        void* handle,
+        const uint8_t* name,
+        size_t len,
+        double* out_value
+    );
+    // Stores the migrated JSON configuration in *out.
+    RtsynStatus (*migrate_config_json)(
+        void* handle,
+        uint32_t from_version,
+        const uint8_t* data,
+        size_t len,
+        RTSynPluginString* out
+    );
+    RTSynPluginString (*presets_json)(void* handle);
+    RtsynStatus (*automate)(
+        void* handle,
+        const uint8_t* key,
+        size_t len,
+        double target,
+        uint64_t ramp_ticks
+    );
+    RtsynStatus (*set_input_vec)(
+        void* handle,
+        const uint8_t* name,
+        size_t len,
+        const double* values,
+        size_t count
+    );
+    // Returns the full length, also when it exceeds cap.
+    size_t (*get_output_vec)(
+        void* handle,
+        const uint8_t* name,
+        size_t len,
+        double* out,
+        size_t cap
+    );
+    RTSynPluginString (*get_output_string)(void* handle, const uint8_t* name, size_t len);
+    RTSynPluginString (*metrics_json)(void* handle);
+    RTSynPluginString (*options_json)(void* handle, const uint8_t* key, size_t len);
+    // {"message": "...", "code": 0} for the last call that returned
+    // RTSYN_STATUS_PLUGIN_ERROR + kind.
+    RTSynPluginString (*last_error_json)(void* handle);
+    // A pressed action button, see rtsyn_ui_field_action().
+    RtsynStatus (*invoke_action)(void* handle, const uint8_t* action_id, size_t len);
+    // Outputs added or removed by the user, for plugins with extendable
+    // outputs.
+    RtsynStatus (*on_output_added)(void* handle, const uint8_t* port, size_t len);
+    RtsynStatus (*on_output_removed)(void* handle, const uint8_t* port, size_t len);
+} RTSynPluginApi;
+
 #ifdef __cplusplus
 }
 #endif
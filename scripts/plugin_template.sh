@@ -289,7 +289,7 @@ EOF
 if [ "$MODEL" = "1" ]; then
     cat >"$SRC_DIR/lib.rs" <<EOF
 use rtsyn_plugin::{
-    Plugin, PluginContext, PluginError, PluginId, PluginMeta, Port, PortId,
+    Plugin, PluginCategory, PluginContext, PluginError, PluginId, PluginMeta, Port,
 };
 use serde_json::Value;
 
@@ -318,8 +318,6 @@ impl PluginState {
         Self {
             id: PluginId(id),
             meta: PluginMeta {
-                name: "$PLUGIN_NAME".to_string(),
-                fixed_vars: Vec::new(),
                 default_vars: vec![
 $(
         i=0
@@ -330,18 +328,21 @@ $(
         done
     )
                 ],
+                ..PluginMeta::new("$PLUGIN_NAME")
+                    .version("0.1.0")
+                    .category(PluginCategory::Other)
             },
             inputs: vec![
 $(
         for x in $INPUTS; do
-            echo "                Port { id: PortId(\"$x\".to_string()) },"
+            echo "                Port::new(\"$x\"),"
         done
     )
             ],
             outputs: vec![
 $(
         for x in $OUTPUTS; do
-            echo "                Port { id: PortId(\"$x\".to_string()) },"
+            echo "                Port::new(\"$x\"),"
         done
     )
             ],
@@ -396,10 +397,11 @@ fi
 
 if [ "$MODEL" = "2" ] && [ "$LANG" = "rust" ]; then
     cat >"$SRC_DIR/lib.rs" <<EOF
+use rtsyn_plugin::ui::ffi::{ErrorDetails, RtsynStatus};
 use rtsyn_plugin::{
-    PluginApi, PluginString,
+    PluginApi, PluginString, PortName, PortStatus,
     Plugin, PluginContext, PluginError,
-    PluginId, PluginMeta, Port, PortId,
+    PluginCategory, PluginId, PluginMeta, Port,
 };
 use serde_json::Value;
 use std::ffi::c_void;
@@ -449,8 +451,6 @@ impl PluginImpl {
         Self {
             id: PluginId(id),
             meta: PluginMeta {
-                name: "$PLUGIN_NAME".to_string(),
-                fixed_vars: Vec::new(),
                 default_vars: vec![
 $(
         i=0
@@ -461,12 +461,15 @@ $(
         done
     )
                 ],
+                ..PluginMeta::new("$PLUGIN_NAME")
+                    .version("0.1.0")
+                    .category(PluginCategory::Other)
             },
             inputs: vec![
-$(for x in $INPUTS; do echo "                Port { id: PortId(\"$x\".to_string()) },"; done)
+$(for x in $INPUTS; do echo "                Port::new(\"$x\"),"; done)
             ],
             outputs: vec![
-$(for x in $OUTPUTS; do echo "                Port { id: PortId(\"$x\".to_string()) },"; done)
+$(for x in $OUTPUTS; do echo "                Port::new(\"$x\"),"; done)
             ],
 $(
         i=0
@@ -520,6 +523,15 @@ impl Plugin for PluginImpl {
 struct PluginState {
     plugin: PluginImpl,
     ctx: PluginContext,
+    last_error: Option<ErrorDetails>,
+}
+
+impl PluginState {
+    // Keeps the message and device code for last_error_json.
+    fn fail(&mut self, error: PluginError) -> i32 {
+        self.last_error = Some(ErrorDetails::from(&error));
+        RtsynStatus::from(error).code()
+    }
 }
 
 // ============================
@@ -530,6 +542,7 @@ extern "C" fn create(id: u64) -> *mut c_void {
     let state = PluginState {
         plugin: PluginImpl::new(id),
         ctx: PluginContext::default(),
+        last_error: None,
     };
     Box::into_raw(Box::new(state)) as *mut c_void
 }
@@ -558,76 +571,128 @@ extern "C" fn outputs_json(_: *mut c_void) -> PluginString {
     PluginString::from_string(serde_json::to_string(OUTPUTS).unwrap())
 }
 
-extern "C" fn set_config_json(handle: *mut c_void, data: *const u8, len: usize) {
+extern "C" fn set_config_json(handle: *mut c_void, data: *const u8, len: usize) -> i32 {
     if handle.is_null() || data.is_null() {
-        return;
+        return RtsynStatus::InvalidArgument.code();
     }
 
     let state = unsafe { &mut *(handle as *mut PluginState) };
     let bytes = unsafe { slice::from_raw_parts(data, len) };
 
-    if let Ok(json) = serde_json::from_slice::<Value>(bytes) {
+    let Ok(json) = serde_json::from_slice::<Value>(bytes) else {
+        return RtsynStatus::SerializationError.code();
+    };
 $(
         i=0
         for v in $VAR_NAMES; do
             ctype=$(echo "$VAR_TYPES" | awk "{print \$$((i + 1))}")
             case "$ctype" in
             double)
-                echo "        if let Some(val) = json.get(\"$v\").and_then(|v| v.as_f64()) { state.plugin.$v = val; }"
+                echo "    if let Some(val) = json.get(\"$v\").and_then(|v| v.as_f64()) { state.plugin.$v = val; }"
                 ;;
             size_t)
-                echo "        if let Some(val) = json.get(\"$v\").and_then(|v| v.as_u64()) { state.plugin.$v = val as usize; }"
+                echo "    if let Some(val) = json.get(\"$v\").and_then(|v| v.as_u64()) { state.plugin.$v = val as usize; }"
                 ;;
             int64_t)
-                echo "        if let Some(val) = json.get(\"$v\").and_then(|v| v.as_i64()) { state.plugin.$v = val; }"
+                echo "    if let Some(val) = json.get(\"$v\").and_then(|v| v.as_i64()) { state.plugin.$v = val; }"
                 ;;
             esac
             i=$((i + 1))
         done
     )
-    }
+    RtsynStatus::Ok.code()
 }
 
-extern "C" fn set_input(handle: *mut c_void, port: *const u8, len: usize, value: f64) {
+extern "C" fn set_input_checked(handle: *mut c_void, port: *const u8, len: usize, value: f64) -> i32 {
     if handle.is_null() || port.is_null() {
-        return;
+        return RtsynStatus::InvalidArgument.code();
     }
 
     let state = unsafe { &mut *(handle as *mut PluginState) };
-    let name = unsafe { slice::from_raw_parts(port, len) };
+    let name = match unsafe { PortName { ptr: port, len }.to_str() } {
+        Ok(name) => name,
+        Err(_) => return RtsynStatus::Utf8Error.code(),
+    };
 
-    match str::from_utf8(name) {
+    let status = match name {
 $(for x in $INPUTS; do
-        echo "        Ok(\"$x\") => state.plugin.$x = value,"
+        echo "        \"$x\" => {"
+        echo "            state.plugin.$x = value;"
+        echo "            PortStatus::Ok"
+        echo "        }"
     done)
-        _ => {}
-    }
+$(for x in $OUTPUTS; do
+        echo "        \"$x\" => PortStatus::WrongDirection,"
+    done)
+        _ => PortStatus::UnknownPort,
+    };
+    status.code()
+}
+
+extern "C" fn set_input(handle: *mut c_void, port: *const u8, len: usize, value: f64) {
+    set_input_checked(handle, port, len, value);
 }
 
-extern "C" fn process(handle: *mut c_void, tick: u64, period_seconds: f64) {
+extern "C" fn process(handle: *mut c_void, tick: u64, period_seconds: f64) -> i32 {
     if handle.is_null() {
-        return;
+        return RtsynStatus::InvalidArgument.code();
     }
 
     let state = unsafe { &mut *(handle as *mut PluginState) };
     state.ctx.tick = tick;
     state.ctx.period_seconds = period_seconds;
-    let _ = state.plugin.process(&mut state.ctx);
+    match state.plugin.process(&mut state.ctx) {
+        Ok(()) => RtsynStatus::Ok.code(),
+        Err(e) => state.fail(e),
+    }
 }
 
-extern "C" fn get_output(handle: *mut c_void, port: *const u8, len: usize) -> f64 {
-    if handle.is_null() || port.is_null() {
-        return 0.0;
+extern "C" fn get_output_checked(
+    handle: *mut c_void,
+    port: *const u8,
+    len: usize,
+    out: *mut f64,
+) -> i32 {
+    if handle.is_null() || port.is_null() || out.is_null() {
+        return RtsynStatus::InvalidArgument.code();
     }
 
     let state = unsafe { &*(handle as *mut PluginState) };
-    let name = unsafe { slice::from_raw_parts(port, len) };
+    let name = match unsafe { PortName { ptr: port, len }.to_str() } {
+        Ok(name) => name,
+        Err(_) => return RtsynStatus::Utf8Error.code(),
+    };
 
-    match str::from_utf8(name) {
+    let status = match name {
 $(for x in $OUTPUTS; do
-        echo "        Ok(\"$x\") => state.plugin.$x,"
+        echo "        \"$x\" => {"
+        echo "            unsafe { *out = state.plugin.$x };"
+        echo "            PortStatus::Ok"
+        echo "        }"
     done)
-        _ => 0.0,
+$(for x in $INPUTS; do
+        echo "        \"$x\" => PortStatus::WrongDirection,"
+    done)
+        _ => PortStatus::UnknownPort,
+    };
+    status.code()
+}
+
+extern "C" fn get_output(handle: *mut c_void, port: *const u8, len: usize) -> f64 {
+    let mut value = 0.0;
+    get_output_checked(handle, port, len, &mut value);
+    value
+}
+
+extern "C" fn last_error_json(handle: *mut c_void) -> PluginString {
+    if handle.is_null() {
+        return PluginString::null();
+    }
+
+    let state = unsafe { &*(handle as *mut PluginState) };
+    match &state.last_error {
+        Some(details) => PluginString::from_string(serde_json::to_string(details).unwrap()),
+        None => PluginString::null(),
     }
 }
 
@@ -636,17 +701,22 @@ $(for x in $OUTPUTS; do
 // ============================
 
 #[no_mangle]
-pub extern "C" fn rtsyn_plugin_api() -> *const PluginApi {
+pub extern "C" fn rtsyn_plugin_api_v2() -> *const PluginApi {
     static API: PluginApi = PluginApi {
-        create,
-        destroy,
-        meta_json,
-        inputs_json,
-        outputs_json,
-        set_config_json,
-        set_input,
-        process,
-        get_output,
+        last_error_json: Some(last_error_json),
+        set_input_checked: Some(set_input_checked),
+        get_output_checked: Some(get_output_checked),
+        ..PluginApi::new(
+            create,
+            destroy,
+            meta_json,
+            inputs_json,
+            outputs_json,
+            set_config_json,
+            set_input,
+            process,
+            get_output,
+        )
     };
     &API
 }
@@ -874,6 +944,7 @@ if [ "$MODEL" = "2" ] && { [ "$LANG" = "c" ] || [ "$LANG" = "cpp" ]; }; then
     CORE="$(to_snake_case "$PLUGIN_SLUG")"
 
     cat >"$SRC_DIR/lib.rs" <<EOF
+use rtsyn_plugin::ui::ffi::RtsynStatus;
 use rtsyn_plugin::{PluginApi, PluginString};
 use serde_json::Value;
 use std::ffi::c_void;
@@ -964,30 +1035,32 @@ extern "C" fn outputs_json(_: *mut c_void) -> PluginString {
     PluginString::from_string(serde_json::to_string(OUTPUTS).unwrap())
 }
 
-extern "C" fn set_config_json(handle: *mut c_void, data: *const u8, len: usize) {
+extern "C" fn set_config_json(handle: *mut c_void, data: *const u8, len: usize) -> i32 {
     if handle.is_null() || data.is_null() || len == 0 {
-        return;
+        return RtsynStatus::InvalidArgument.code();
     }
 
     let state = handle as *mut CoreState;
     let bytes = unsafe { slice::from_raw_parts(data, len) };
 
-    if let Ok(json) = serde_json::from_slice::<Value>(bytes) {
-        if let Some(map) = json.as_object() {
-            for (key, value) in map {
-                if let Some(v) = value.as_f64() {
-                    unsafe {
-                        ${CORE}_set_config(
-                            state,
-                            key.as_bytes().as_ptr(),
-                            key.len(),
-                            v,
-                        );
-                    }
+    let Ok(json) = serde_json::from_slice::<Value>(bytes) else {
+        return RtsynStatus::SerializationError.code();
+    };
+    if let Some(map) = json.as_object() {
+        for (key, value) in map {
+            if let Some(v) = value.as_f64() {
+                unsafe {
+                    ${CORE}_set_config(
+                        state,
+                        key.as_bytes().as_ptr(),
+                        key.len(),
+                        v,
+                    );
                 }
             }
         }
     }
+    RtsynStatus::Ok.code()
 }
 
 extern "C" fn set_input(handle: *mut c_void, name: *const u8, len: usize, value: f64) {
@@ -1005,13 +1078,14 @@ extern "C" fn set_input(handle: *mut c_void, name: *const u8, len: usize, value:
     }
 }
 
-extern "C" fn process(handle: *mut c_void, _tick: u64, period_seconds: f64) {
+extern "C" fn process(handle: *mut c_void, _tick: u64, period_seconds: f64) -> i32 {
     if handle.is_null() {
-        return;
+        return RtsynStatus::InvalidArgument.code();
     }
     unsafe {
         ${CORE}_process(handle as *mut CoreState, period_seconds);
     }
+    RtsynStatus::Ok.code()
 }
 
 extern "C" fn get_output(handle: *mut c_void, name: *const u8, len: usize) -> f64 {
@@ -1031,8 +1105,8 @@ $(for x in $OUTPUTS; do
 }
 
 #[no_mangle]
-pub extern "C" fn rtsyn_plugin_api() -> *const PluginApi {
-    static API: PluginApi = PluginApi {
+pub extern "C" fn rtsyn_plugin_api_v2() -> *const PluginApi {
+    static API: PluginApi = PluginApi::new(
         create,
         destroy,
         meta_json,
@@ -1042,7 +1116,7 @@ pub extern "C" fn rtsyn_plugin_api() -> *const PluginApi {
         set_input,
         process,
         get_output,
-    };
+    );
     &API
 }
 EOF
@@ -0,0 +1,463 @@
+use crate::config::ConfigDelta;
+use crate::event::Event;
+use crate::features::FeatureFlag;
+use crate::preset::Preset;
+use crate::preview::Preview;
+use crate::template::WorkspaceTemplate;
+use crate::ui::{ChoiceOption, ConnectionBehavior, PluginBehavior, UISchema};
+use crate::{Plugin, PluginContext, PluginError, PluginId, PluginMeta, Port, ShutdownStatus};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AggregateKind {
+    Mean,
+    Min,
+    Max,
+    Rms,
+}
+
+impl AggregateKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AggregateKind::Mean => "mean",
+            AggregateKind::Min => "min",
+            AggregateKind::Max => "max",
+            AggregateKind::Rms => "rms",
+        }
+    }
+}
+
+/// Derived output declared on a port with `Port::aggregate`: `kind` over the
+/// last `window_ticks` values, computed by [`Aggregated`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Aggregate {
+    pub kind: AggregateKind,
+    pub window_ticks: u32,
+}
+
+impl Aggregate {
+    /// Id of the derived port, e.g. `rms.mean_100` for the 100-tick mean of
+    /// `rms`.
+    pub fn port_id(&self, source: &str) -> String {
+        format!("{source}.{}_{}", self.kind.as_str(), self.window_ticks)
+    }
+}
+
+/// Sliding window over the last `window_ticks` values. Mean and RMS keep a
+/// running sum; min and max a monotonic queue, so each push is O(1)
+/// amortized.
+#[derive(Debug, Clone)]
+pub struct AggregateWindow {
+    aggregate: Aggregate,
+    values: VecDeque<f64>,
+    sum: f64,
+    // (push index, value) candidates for min/max, best first.
+    extrema: VecDeque<(u64, f64)>,
+    pushed: u64,
+}
+
+impl AggregateWindow {
+    pub fn new(aggregate: Aggregate) -> Self {
+        let window = aggregate.window_ticks.max(1) as usize;
+        Self {
+            aggregate,
+            values: VecDeque::with_capacity(window),
+            sum: 0.0,
+            extrema: VecDeque::new(),
+            pushed: 0,
+        }
+    }
+
+    pub fn push(&mut self, value: f64) {
+        let window = self.aggregate.window_ticks.max(1) as usize;
+        let kind = self.aggregate.kind;
+        let term = |value: f64| match kind {
+            AggregateKind::Rms => value * value,
+            _ => value,
+        };
+        if self.values.len() == window {
+            if let Some(old) = self.values.pop_front() {
+                self.sum -= term(old);
+            }
+        }
+        self.sum += term(value);
+        self.values.push_back(value);
+
+        let keep = |candidate: f64| match kind {
+            AggregateKind::Min => candidate < value,
+            _ => candidate > value,
+        };
+        while self.extrema.back().is_some_and(|(_, v)| !keep(*v)) {
+            self.extrema.pop_back();
+        }
+        self.extrema.push_back((self.pushed, value));
+        self.pushed += 1;
+        let oldest = self.pushed.saturating_sub(window as u64);
+        while self
+            .extrema
+            .front()
+            .is_some_and(|(index, _)| *index < oldest)
+        {
+            self.extrema.pop_front();
+        }
+    }
+
+    /// Aggregate over the values pushed so far, `0.0` before the first.
+    pub fn value(&self) -> f64 {
+        if self.values.is_empty() {
+            return 0.0;
+        }
+        let len = self.values.len() as f64;
+        match self.aggregate.kind {
+            AggregateKind::Mean => self.sum / len,
+            AggregateKind::Rms => (self.sum.max(0.0) / len).sqrt(),
+            AggregateKind::Min | AggregateKind::Max => {
+                self.extrema.front().map_or(0.0, |(_, value)| *value)
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.values.clear();
+        self.extrema.clear();
+        self.sum = 0.0;
+    }
+}
+
+struct Derived {
+    source: String,
+    id: String,
+    window: AggregateWindow,
+}
+
+/// Wraps a plugin and adds the aggregate outputs its ports declare.
+///
+/// After every `process` the wrapper reads each source output through
+/// `get_output` and updates the windows; the derived ports follow the
+/// plugin's own outputs in [`Plugin::outputs`] and are read with
+/// [`Aggregated::get_output`].
+pub struct Aggregated<P> {
+    inner: P,
+    get_output: fn(&P, &str) -> f64,
+    outputs: Vec<Port>,
+    derived: Vec<Derived>,
+}
+
+impl<P: Plugin> Aggregated<P> {
+    pub fn new(inner: P, get_output: fn(&P, &str) -> f64) -> Self {
+        let mut aggregated = Self {
+            inner,
+            get_output,
+            outputs: Vec::new(),
+            derived: Vec::new(),
+        };
+        aggregated.refresh();
+        aggregated
+    }
+
+    /// Rebuilds the derived ports after the plugin's outputs changed. Window
+    /// contents are discarded.
+    pub fn refresh(&mut self) {
+        self.outputs = self.inner.outputs().to_vec();
+        self.derived.clear();
+        for port in self.inner.outputs() {
+            for aggregate in &port.aggregates {
+                let id = aggregate.port_id(&port.id.0);
+                let mut derived = Port::new(id.clone());
+                derived.clock = port.clock.clone();
+                self.outputs.push(derived);
+                self.derived.push(Derived {
+                    source: port.id.0.clone(),
+                    id,
+                    window: AggregateWindow::new(*aggregate),
+                });
+            }
+        }
+    }
+
+    /// Value of a plugin output or a derived aggregate.
+    pub fn get_output(&self, name: &str) -> f64 {
+        match self.derived.iter().find(|derived| derived.id == name) {
+            Some(derived) => derived.window.value(),
+            None => (self.get_output)(&self.inner, name),
+        }
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut P {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> P {
+        self.inner
+    }
+
+    fn derived_mask(&self) -> u64 {
+        let own = self.inner.outputs().len();
+        (own..own + self.derived.len()).fold(0, |mask, index| {
+            mask | crate::ports::ChangedOutputs::bit(index)
+        })
+    }
+}
+
+impl<P: Plugin> Plugin for Aggregated<P> {
+    fn id(&self) -> PluginId {
+        self.inner.id()
+    }
+
+    fn meta(&self) -> &PluginMeta {
+        self.inner.meta()
+    }
+
+    fn inputs(&self) -> &[Port] {
+        self.inner.inputs()
+    }
+
+    fn outputs(&self) -> &[Port] {
+        &self.outputs
+    }
+
+    fn process(&mut self, ctx: &mut PluginContext) -> Result<(), PluginError> {
+        self.inner.process(ctx)?;
+        for derived in &mut self.derived {
+            let value = (self.get_output)(&self.inner, &derived.source);
+            derived.window.push(value);
+        }
+        Ok(())
+    }
+
+    fn ui_schema(&self) -> Option<UISchema> {
+        self.inner.ui_schema()
+    }
+
+    fn behavior(&self) -> PluginBehavior {
+        self.inner.behavior()
+    }
+
+    fn connection_behavior(&self) -> ConnectionBehavior {
+        self.inner.connection_behavior()
+    }
+
+    fn init_params(&mut self, params: Value) -> Result<(), PluginError> {
+        self.inner.init_params(params)
+    }
+
+    fn on_input_added(&mut self, port: &str) -> Result<(), PluginError> {
+        self.inner.on_input_added(port)
+    }
+
+    fn on_input_removed(&mut self, port: &str) -> Result<(), PluginError> {
+        self.inner.on_input_removed(port)
+    }
+
+    fn on_output_added(&mut self, port: &str) -> Result<(), PluginError> {
+        self.inner.on_output_added(port)
+    }
+
+    fn on_output_removed(&mut self, port: &str) -> Result<(), PluginError> {
+        self.inner.on_output_removed(port)
+    }
+
+    fn on_config_changed(&mut self, changed: &ConfigDelta) -> Result<(), PluginError> {
+        self.inner.on_config_changed(changed)
+    }
+
+    fn save_state(&self) -> Result<Value, PluginError> {
+        self.inner.save_state()
+    }
+
+    fn restore_state(&mut self, state: Value) -> Result<(), PluginError> {
+        self.inner.restore_state(state)
+    }
+
+    fn migrate_config(&self, from_version: u32, old: Value) -> Result<Value, PluginError> {
+        self.inner.migrate_config(from_version, old)
+    }
+
+    fn options_for(&self, key: &str) -> Vec<ChoiceOption> {
+        self.inner.options_for(key)
+    }
+
+    fn action(&mut self, name: &str, args: &Value) -> Result<Option<Value>, PluginError> {
+        self.inner.action(name, args)
+    }
+
+    fn on_action(&mut self, action_id: &str) -> Result<(), PluginError> {
+        self.inner.on_action(action_id)
+    }
+
+    fn on_event(&mut self, event: &Event) -> Result<(), PluginError> {
+        self.inner.on_event(event)
+    }
+
+    fn latency_ticks(&self) -> u64 {
+        self.inner.latency_ticks()
+    }
+
+    fn features(&self) -> Vec<FeatureFlag> {
+        self.inner.features()
+    }
+
+    fn set_feature_enabled(&mut self, name: &str, enabled: bool) -> Result<(), PluginError> {
+        self.inner.set_feature_enabled(name, enabled)
+    }
+
+    fn templates(&self) -> Vec<WorkspaceTemplate> {
+        self.inner.templates()
+    }
+
+    fn presets(&self) -> Vec<Preset> {
+        self.inner.presets()
+    }
+
+    fn preview(&self) -> Option<Preview> {
+        self.inner.preview()
+    }
+
+    fn prepare(&mut self, sample_rate: f64, block_size: usize) -> Result<(), PluginError> {
+        self.inner.prepare(sample_rate, block_size)
+    }
+
+    fn on_overrun(&mut self, missed: u64) -> Result<(), PluginError> {
+        self.inner.on_overrun(missed)
+    }
+
+    fn on_start(&mut self) -> Result<(), PluginError> {
+        self.inner.on_start()
+    }
+
+    fn on_stop(&mut self) -> Result<(), PluginError> {
+        self.inner.on_stop()
+    }
+
+    fn on_pause(&mut self) -> Result<(), PluginError> {
+        self.inner.on_pause()
+    }
+
+    fn on_resume(&mut self) -> Result<(), PluginError> {
+        self.inner.on_resume()
+    }
+
+    fn reset(&mut self) -> Result<(), PluginError> {
+        for derived in &mut self.derived {
+            derived.window.reset();
+        }
+        self.inner.reset()
+    }
+
+    // Aggregates move with every tick, so their bits are always set.
+    fn take_changed_outputs(&mut self) -> u64 {
+        self.inner.take_changed_outputs() | self.derived_mask()
+    }
+
+    fn set_input_vec(&mut self, name: &str, values: &[f64]) -> Result<(), PluginError> {
+        self.inner.set_input_vec(name, values)
+    }
+
+    fn get_output_vec(&self, name: &str, out: &mut [f64]) -> usize {
+        self.inner.get_output_vec(name, out)
+    }
+
+    fn get_output_string(&self, name: &str) -> Option<String> {
+        self.inner.get_output_string(name)
+    }
+
+    fn begin_shutdown(&mut self) -> Result<ShutdownStatus, PluginError> {
+        self.inner.begin_shutdown()
+    }
+
+    fn poll_shutdown(&mut self) -> Result<ShutdownStatus, PluginError> {
+        self.inner.poll_shutdown()
+    }
+
+    // The copy has the plugin's own outputs only; wrap it again to get the
+    // aggregates back.
+    fn duplicate(&self) -> Option<Box<dyn Plugin>> {
+        self.inner.duplicate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockHost;
+
+    struct Counter {
+        meta: PluginMeta,
+        outputs: Vec<Port>,
+        value: f64,
+    }
+
+    impl Plugin for Counter {
+        fn id(&self) -> PluginId {
+            PluginId(1)
+        }
+        fn meta(&self) -> &PluginMeta {
+            &self.meta
+        }
+        fn inputs(&self) -> &[Port] {
+            &[]
+        }
+        fn outputs(&self) -> &[Port] {
+            &self.outputs
+        }
+        fn process(&mut self, _ctx: &mut PluginContext) -> Result<(), PluginError> {
+            self.value += 1.0;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn windows_slide() {
+        let run = |kind, values: &[f64]| {
+            let mut window = AggregateWindow::new(Aggregate {
+                kind,
+                window_ticks: 3,
+            });
+            values
+                .iter()
+                .map(|value| {
+                    window.push(*value);
+                    window.value()
+                })
+                .collect::<Vec<_>>()
+        };
+        let values = [3.0, 1.0, 2.0, 5.0, 0.0];
+        assert_eq!(
+            run(AggregateKind::Mean, &values),
+            [3.0, 2.0, 2.0, 8.0 / 3.0, 7.0 / 3.0]
+        );
+        assert_eq!(run(AggregateKind::Min, &values), [3.0, 1.0, 1.0, 1.0, 0.0]);
+        assert_eq!(run(AggregateKind::Max, &values), [3.0, 3.0, 3.0, 5.0, 5.0]);
+        assert_eq!(run(AggregateKind::Rms, &[3.0, -4.0])[1], 12.5f64.sqrt());
+    }
+
+    #[test]
+    fn wrapper_exposes_derived_ports() {
+        let counter = Counter {
+            meta: PluginMeta::new("counter"),
+            outputs: vec![Port::new("count")
+                .aggregate(AggregateKind::Mean, 4)
+                .aggregate(AggregateKind::Max, 2)],
+            value: 0.0,
+        };
+        let plugin = Aggregated::new(counter, |p: &Counter, _| p.value);
+        let ids: Vec<_> = plugin.outputs().iter().map(|p| p.id.0.as_str()).collect();
+        assert_eq!(ids, ["count", "count.mean_4", "count.max_2"]);
+
+        let mut host = MockHost::new(plugin).io(|_, _, _| {}, |p, name| p.get_output(name));
+        host.run(5).unwrap();
+        assert_eq!(host.output_series("count"), [1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(
+            host.output_series("count.mean_4"),
+            [1.0, 1.5, 2.0, 2.5, 3.5]
+        );
+        assert_eq!(host.output_series("count.max_2"), [1.0, 2.0, 3.0, 4.0, 5.0]);
+    }
+}
@@ -0,0 +1,1048 @@
+//! Machine-readable description of the C ABI and the JSON wire formats.
+//!
+//! The report for this build is [`current`]; [`diff`] compares two reports so
+//! that a release can be checked for unintended breaking changes, and
+//! [`unversioned_changes`] lists the ones the crate version does not account
+//! for.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiReport {
+    pub crate_version: String,
+    /// `PluginApi` entries in declaration order.
+    pub abi: Vec<AbiEntry>,
+    pub types: BTreeMap<String, TypeShape>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AbiEntry {
+    pub name: String,
+    pub signature: String,
+    /// Entry is an `Option` and may be null.
+    #[serde(default)]
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TypeShape {
+    Struct {
+        fields: BTreeMap<String, FieldShape>,
+    },
+    Enum {
+        /// Internal tag key, or `None` for plain string variants.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tag: Option<String>,
+        variants: BTreeMap<String, BTreeMap<String, FieldShape>>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldShape {
+    #[serde(rename = "type")]
+    pub ty: String,
+    /// May be absent on the wire.
+    #[serde(default)]
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Additive,
+    Breaking,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiChange {
+    pub kind: ChangeKind,
+    pub path: String,
+    pub description: String,
+}
+
+// `"type?"` marks a field that may be absent on the wire.
+macro_rules! shape {
+    ($($name:literal : $ty:literal),* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut fields = BTreeMap::new();
+        $(
+            fields.insert($name.to_string(), field_shape($ty));
+        )*
+        fields
+    }};
+}
+
+fn field_shape(ty: &str) -> FieldShape {
+    match ty.strip_suffix('?') {
+        Some(ty) => FieldShape {
+            ty: ty.to_string(),
+            optional: true,
+        },
+        None => FieldShape {
+            ty: ty.to_string(),
+            optional: false,
+        },
+    }
+}
+
+fn structure(fields: BTreeMap<String, FieldShape>) -> TypeShape {
+    TypeShape::Struct { fields }
+}
+
+fn tagged<const N: usize>(
+    tag: &str,
+    variants: [(&str, BTreeMap<String, FieldShape>); N],
+) -> TypeShape {
+    TypeShape::Enum {
+        tag: Some(tag.to_string()),
+        variants: variants
+            .into_iter()
+            .map(|(name, fields)| (name.to_string(), fields))
+            .collect(),
+    }
+}
+
+fn unit_enum(variants: &[&str]) -> TypeShape {
+    TypeShape::Enum {
+        tag: None,
+        variants: variants
+            .iter()
+            .map(|name| (name.to_string(), BTreeMap::new()))
+            .collect(),
+    }
+}
+
+fn abi(name: &str, signature: &str, optional: bool) -> AbiEntry {
+    AbiEntry {
+        name: name.to_string(),
+        signature: signature.to_string(),
+        optional,
+    }
+}
+
+/// Report describing this build of the crate.
+pub fn current() -> ApiReport {
+    let abi = vec![
+        abi("struct_size", "usize", false),
+        abi("create", "fn(u64) -> handle", false),
+        abi("destroy", "fn(handle)", false),
+        abi("meta_json", "fn(handle) -> PluginString", false),
+        abi("inputs_json", "fn(handle) -> PluginString", false),
+        abi("outputs_json", "fn(handle) -> PluginString", false),
+        abi("behavior_json", "fn(handle) -> PluginString", true),
+        abi("ui_schema_json", "fn(handle) -> PluginString", true),
+        abi(
+            "set_config_json",
+            "fn(handle, *const u8, usize) -> RtsynStatus",
+            false,
+        ),
+        abi("set_input", "fn(handle, *const u8, usize, f64)", false),
+        abi("process", "fn(handle, u64, f64) -> RtsynStatus", false),
+        abi("get_output", "fn(handle, *const u8, usize) -> f64", false),
+        abi("get_state", "fn(handle) -> PluginString", true),
+        abi(
+            "set_state",
+            "fn(handle, *const u8, usize) -> RtsynStatus",
+            true,
+        ),
+        abi("start", "fn(handle) -> RtsynStatus", true),
+        abi("stop", "fn(handle) -> RtsynStatus", true),
+        abi("pause", "fn(handle) -> RtsynStatus", true),
+        abi("resume", "fn(handle) -> RtsynStatus", true),
+        abi("reset", "fn(handle) -> RtsynStatus", true),
+        abi("changed_outputs_mask", "fn(handle) -> u64", true),
+        abi(
+            "set_host_info_json",
+            "fn(handle, *const u8, usize) -> RtsynStatus",
+            true,
+        ),
+        abi(
+            "begin_shutdown",
+            "fn(handle, *mut bool) -> RtsynStatus",
+            true,
+        ),
+        abi(
+            "poll_shutdown",
+            "fn(handle, *mut bool) -> RtsynStatus",
+            true,
+        ),
+        abi(
+            "create_with_params",
+            "fn(u64, *const u8, usize) -> handle",
+            true,
+        ),
+        abi("set_connections", "fn(handle, u64, u64)", true),
+        abi(
+            "create_with_host",
+            "fn(u64, *const RTSynHostApi) -> handle",
+            true,
+        ),
+        abi(
+            "action_json",
+            "fn(handle, *const u8, usize, *const u8, usize, *mut PluginString) -> RtsynStatus",
+            true,
+        ),
+        abi("set_input_raw", "fn(handle, *const u8, usize, i64)", true),
+        abi(
+            "get_output_raw",
+            "fn(handle, *const u8, usize) -> i64",
+            true,
+        ),
+        abi(
+            "on_event_json",
+            "fn(handle, *const u8, usize) -> RtsynStatus",
+            true,
+        ),
+        abi("take_events_json", "fn(handle) -> PluginString", true),
+        abi("latency_ticks", "fn(handle) -> u64", true),
+        abi("features_json", "fn(handle) -> PluginString", true),
+        abi(
+            "set_feature_enabled",
+            "fn(handle, *const u8, usize, bool) -> RtsynStatus",
+            true,
+        ),
+        abi(
+            "set_inputs",
+            "fn(handle, *const PortName, *const f64, usize) -> RtsynStatus",
+            true,
+        ),
+        abi(
+            "get_outputs",
+            "fn(handle, *const PortName, *mut f64, usize) -> RtsynStatus",
+            true,
+        ),
+        abi("preview_json", "fn(handle) -> PluginString", true),
+        abi("resolve_input", "fn(handle, *const u8, usize) -> u32", true),
+        abi(
+            "resolve_output",
+            "fn(handle, *const u8, usize) -> u32",
+            true,
+        ),
+        abi(
+            "set_input_by_handle",
+            "fn(handle, u32, f64) -> RtsynStatus",
+            true,
+        ),
+        abi("get_output_by_handle", "fn(handle, u32) -> f64", true),
+        abi("prepare", "fn(handle, f64, usize) -> RtsynStatus", true),
+        abi("on_overrun", "fn(handle, u64) -> RtsynStatus", true),
+        abi("templates_json", "fn(handle) -> PluginString", true),
+        abi(
+            "set_input_checked",
+            "fn(handle, *const u8, usize, f64) -> RtsynStatus",
+            true,
+        ),
+        abi(
+            "get_output_checked",
+            "fn(handle, *const u8, usize, *mut f64) -> RtsynStatus",
+            true,
+        ),
+        abi(
+            "migrate_config_json",
+            "fn(handle, u32, *const u8, usize, *mut PluginString) -> RtsynStatus",
+            true,
+        ),
+        abi("presets_json", "fn(handle) -> PluginString", true),
+        abi(
+            "automate",
+            "fn(handle, *const u8, usize, f64, u64) -> RtsynStatus",
+            true,
+        ),
+        abi(
+            "set_input_vec",
+            "fn(handle, *const u8, usize, *const f64, usize) -> RtsynStatus",
+            true,
+        ),
+        abi(
+            "get_output_vec",
+            "fn(handle, *const u8, usize, *mut f64, usize) -> usize",
+            true,
+        ),
+        abi(
+            "get_output_string",
+            "fn(handle, *const u8, usize) -> PluginString",
+            true,
+        ),
+        abi("metrics_json", "fn(handle) -> PluginString", true),
+        abi(
+            "options_json",
+            "fn(handle, *const u8, usize) -> PluginString",
+            true,
+        ),
+        abi("last_error_json", "fn(handle) -> PluginString", true),
+        abi(
+            "invoke_action",
+            "fn(handle, *const u8, usize) -> RtsynStatus",
+            true,
+        ),
+        abi(
+            "on_output_added",
+            "fn(handle, *const u8, usize) -> RtsynStatus",
+            true,
+        ),
+        abi(
+            "on_output_removed",
+            "fn(handle, *const u8, usize) -> RtsynStatus",
+            true,
+        ),
+    ];
+
+    let mut types = BTreeMap::new();
+    let mut add = |name: &str, shape: TypeShape| {
+        types.insert(name.to_string(), shape);
+    };
+
+    add(
+        "PluginMeta",
+        structure(shape! {
+            "name": "string",
+            "uid": "string?",
+            "fixed_vars": "array<[string, json]>",
+            "default_vars": "array<[string, json]>",
+            "version": "string?",
+            "author": "string?",
+            "description": "string?",
+            "license": "string?",
+            "homepage": "string?",
+            "category": "PluginCategory?",
+            "tags": "array<string>?",
+            "capabilities": "array<Capability>?",
+            "display": "DisplayMeta?",
+            "translations": "map<string, map<string, string>>?",
+        }),
+    );
+    add(
+        "DisplayMeta",
+        structure(shape! { "icon": "Icon?", "color": "string?" }),
+    );
+    add(
+        "Icon",
+        tagged(
+            "kind",
+            [
+                ("glyph", shape! { "name": "string" }),
+                ("svg", shape! { "data": "string" }),
+                ("png", shape! { "data": "array<u8>" }),
+            ],
+        ),
+    );
+    add(
+        "PluginCategory",
+        unit_enum(&[
+            "generator",
+            "filter",
+            "analyzer",
+            "sink",
+            "device",
+            "logger",
+            "utility",
+            "other",
+        ]),
+    );
+    add(
+        "Capability",
+        unit_enum(&[
+            "file_read",
+            "file_write",
+            "network",
+            "serial_port",
+            "shared_memory",
+            "spawn_threads",
+            "unknown",
+        ]),
+    );
+    add(
+        "Port",
+        structure(shape! {
+            "id": "string",
+            "clock": "ClockDomain?",
+            "resampling": "Resampling?",
+            "aliases": "array<string>?",
+            "format": "NumericFormat?",
+            "aggregates": "array<Aggregate>?",
+            "gap": "GapPolicy?",
+            "kind": "PortKind?",
+            "description": "string?",
+            "unit": "string?",
+            "range": "[f64, f64]?",
+            "group": "string?",
+            "group_index": "u32?",
+            "required": "bool?",
+            "max_connections": "usize?",
+        }),
+    );
+    add(
+        "Aggregate",
+        structure(shape! {
+            "kind": "AggregateKind",
+            "window_ticks": "u32",
+        }),
+    );
+    add("AggregateKind", unit_enum(&["mean", "min", "max", "rms"]));
+    add("NumericFormat", unit_enum(&["float", "q15", "q31"]));
+    add("GapPolicy", unit_enum(&["hold", "linear", "nan"]));
+    add(
+        "PortKind",
+        unit_enum(&["scalar", "complex", "vector", "text"]),
+    );
+    add(
+        "HostInfo",
+        structure(shape! {
+            "name": "string",
+            "version": "string",
+            "platform": "string",
+            "features": "array<string>?",
+        }),
+    );
+    add("ShutdownStatus", unit_enum(&["pending", "done"]));
+    add(
+        "PortStatus",
+        unit_enum(&["ok", "unknown_port", "wrong_direction"]),
+    );
+    add(
+        "FeatureFlag",
+        structure(shape! {
+            "name": "string",
+            "description": "string?",
+            "default": "bool?",
+            "experimental": "bool?",
+        }),
+    );
+    add(
+        "Event",
+        structure(shape! {
+            "source": "u64",
+            "topic": "string",
+            "payload": "json?",
+            "timestamp": "f64?",
+        }),
+    );
+    add(
+        "Preview",
+        structure(shape! {
+            "period_seconds": "f64",
+            "traces": "array<PreviewTrace>",
+        }),
+    );
+    add(
+        "PreviewTrace",
+        structure(shape! {
+            "output": "string",
+            "samples": "array<f64>",
+        }),
+    );
+    add(
+        "MeasurableQuantity",
+        structure(shape! {
+            "id": "string",
+            "label": "string",
+            "unit": "string",
+            "good_below": "f64?",
+            "bad_above": "f64?",
+        }),
+    );
+    add(
+        "MeasurementReport",
+        structure(shape! {
+            "quantity": "string",
+            "unit": "string",
+            "channels": "array<ChannelResult>",
+        }),
+    );
+    add(
+        "ChannelResult",
+        structure(shape! {
+            "channel": "string",
+            "value": "f64?",
+            "status": "ChannelStatus",
+        }),
+    );
+    add(
+        "ChannelStatus",
+        unit_enum(&["good", "marginal", "bad", "failed"]),
+    );
+    add(
+        "WorkspaceTemplate",
+        structure(shape! {
+            "name": "string",
+            "description": "string?",
+            "graph": "GraphInfo",
+        }),
+    );
+    add(
+        "GraphInfo",
+        structure(shape! {
+            "nodes": "array<NodeInfo>",
+            "connections": "array<ConnectionInfo>?",
+        }),
+    );
+    add(
+        "NodeInfo",
+        structure(shape! {
+            "key": "string",
+            "plugin": "string",
+            "label": "string?",
+            "config": "json?",
+        }),
+    );
+    add(
+        "ConnectionInfo",
+        structure(shape! {
+            "from": "PortRef",
+            "to": "PortRef",
+        }),
+    );
+    add(
+        "PortRef",
+        structure(shape! {
+            "node": "string",
+            "port": "string",
+        }),
+    );
+    add(
+        "PresetBundle",
+        structure(shape! {
+            "format": "u32",
+            "name": "string",
+            "plugins": "map<string, PluginPresets>",
+        }),
+    );
+    add(
+        "PluginPresets",
+        structure(shape! {
+            "schema_version": "u32?",
+            "plugin_version": "string?",
+            "presets": "array<Preset>",
+        }),
+    );
+    add(
+        "Preset",
+        structure(shape! { "name": "string", "values": "json" }),
+    );
+    add(
+        "ParamChange",
+        structure(shape! { "key": "string", "target": "f64", "ramp_ticks": "u64?" }),
+    );
+    add(
+        "LogRecord",
+        structure(shape! {
+            "timestamp_ns": "u64",
+            "severity": "LogLevel",
+            "source": "u64",
+            "message": "string?",
+            "fields": "json?",
+        }),
+    );
+    add(
+        "LogLevel",
+        unit_enum(&["error", "warn", "info", "debug", "trace"]),
+    );
+    add(
+        "DeviceInfo",
+        structure(shape! {
+            "id": "string",
+            "name": "string",
+            "kind": "DeviceKind?",
+            "vendor": "string?",
+            "serial_number": "string?",
+            "properties": "json?",
+        }),
+    );
+    add(
+        "DeviceKind",
+        unit_enum(&["serial", "daq", "camera", "audio", "network", "other"]),
+    );
+    add(
+        "ErrorSeverity",
+        unit_enum(&["recoverable", "reconfigure", "fatal"]),
+    );
+    add(
+        "ClockDomain",
+        structure(shape! { "name": "string", "rate_hz": "f64" }),
+    );
+    add(
+        "Resampling",
+        tagged(
+            "type",
+            [
+                ("sample_hold", shape! {}),
+                ("interpolate", shape! {}),
+                ("fifo", shape! { "capacity": "usize" }),
+            ],
+        ),
+    );
+    add(
+        "PluginBehavior",
+        structure(shape! {
+            "supports_start_stop": "bool",
+            "supports_restart": "bool",
+            "extendable_inputs": "ExtendableInputs",
+            "extendable_outputs": "ExtendableOutputs?",
+            "loads_started": "bool",
+            "max_shutdown_seconds": "f64?",
+            "comparison": "ComparisonMode?",
+            "threading": "ThreadingModel?",
+            "side_effects": "array<SideEffect>?",
+            "stateless": "bool?",
+            "latency_ticks": "u64?",
+            "realtime_safe": "bool?",
+            "may_block": "bool?",
+            "allocates_in_process": "bool?",
+            "strict_ports": "bool?",
+            "supports_automation": "bool?",
+        }),
+    );
+    add(
+        "SideEffect",
+        unit_enum(&["filesystem", "network", "hardware"]),
+    );
+    add("ComparisonMode", unit_enum(&["none", "shadow"]));
+    add("ThreadingModel", unit_enum(&["any", "pinned"]));
+    add(
+        "ExtendableInputs",
+        tagged(
+            "type",
+            [
+                ("none", shape! {}),
+                ("manual", shape! { "min": "usize?", "max": "usize?" }),
+                (
+                    "auto",
+                    shape! { "pattern": "string", "min": "usize?", "max": "usize?" },
+                ),
+            ],
+        ),
+    );
+    add(
+        "ExtendableOutputs",
+        tagged(
+            "type",
+            [
+                ("none", shape! {}),
+                ("manual", shape! {}),
+                ("auto", shape! { "pattern": "string" }),
+            ],
+        ),
+    );
+    add(
+        "ConnectionBehavior",
+        structure(shape! { "dependent": "bool" }),
+    );
+    add(
+        "UISchema",
+        structure(shape! {
+            "fields": "array<ConfigField>",
+            "schema_version": "u32?",
+            "translations": "map<string, map<string, string>>?",
+        }),
+    );
+    add(
+        "ConfigField",
+        structure(shape! {
+            "key": "string",
+            "label": "string",
+            "type": "FieldType",
+            "default": "json?",
+            "hint": "string?",
+            "rules": "array<ValidationRule>?",
+            "visible_if": "FieldCondition?",
+            "enabled_if": "FieldCondition?",
+            "widget": "WidgetHint?",
+            "read_only": "bool?",
+        }),
+    );
+    add(
+        "WidgetHint",
+        tagged(
+            "kind",
+            [
+                ("slider", shape! { "scale": "SliderScale?" }),
+                ("knob", shape! { "scale": "SliderScale?" }),
+                ("stepper", shape! {}),
+            ],
+        ),
+    );
+    add("SliderScale", unit_enum(&["linear", "log"]));
+    add("OptionsSource", unit_enum(&["static", "plugin"]));
+    add(
+        "FieldType",
+        tagged(
+            "kind",
+            [
+                (
+                    "integer",
+                    shape! { "min": "i64?", "max": "i64?", "step": "i64", "unit": "string?" },
+                ),
+                (
+                    "float",
+                    shape! {
+                        "min": "f64?",
+                        "max": "f64?",
+                        "step": "f64",
+                        "unit": "string?",
+                        "display_precision": "u32?",
+                    },
+                ),
+                (
+                    "text",
+                    shape! { "multiline": "bool", "max_length": "usize?" },
+                ),
+                ("boolean", shape! {}),
+                ("secret", shape! {}),
+                (
+                    "duration",
+                    shape! { "unit": "TimeUnit", "min": "u64?", "max": "u64?" },
+                ),
+                ("datetime", shape! {}),
+                (
+                    "action",
+                    shape! { "action_id": "string", "confirm": "string?" },
+                ),
+                ("computed", shape! { "source_output": "string" }),
+                ("info", shape! { "markdown": "string" }),
+                (
+                    "filepath",
+                    shape! { "mode": "FileMode", "filters": "array<[string, string]>" },
+                ),
+                (
+                    "dynamiclist",
+                    shape! {
+                        "item_type": "FieldType",
+                        "add_label": "string",
+                        "min_items": "u64?",
+                        "max_items": "u64?",
+                    },
+                ),
+                (
+                    "choice",
+                    shape! {
+                        "options": "array<ChoiceOption>",
+                        "options_source": "OptionsSource?",
+                    },
+                ),
+                (
+                    "table",
+                    shape! {
+                        "columns": "array<ColumnSpec>",
+                        "min_rows": "u64?",
+                        "max_rows": "u64?",
+                    },
+                ),
+                (
+                    "group",
+                    shape! {
+                        "label": "string",
+                        "collapsed": "bool",
+                        "fields": "array<ConfigField>",
+                    },
+                ),
+            ],
+        ),
+    );
+    add("TimeUnit", unit_enum(&["ns", "us", "ms", "s", "min", "h"]));
+    add(
+        "ColumnSpec",
+        structure(shape! { "key": "string", "label": "string", "type": "FieldType" }),
+    );
+    add(
+        "ChoiceOption",
+        structure(shape! { "value": "string", "label": "string" }),
+    );
+    add(
+        "FieldCondition",
+        tagged(
+            "op",
+            [
+                ("equals", shape! { "field": "string", "value": "json" }),
+                ("not_equals", shape! { "field": "string", "value": "json" }),
+                (
+                    "one_of",
+                    shape! { "field": "string", "values": "array<json>" },
+                ),
+                ("is_true", shape! { "field": "string" }),
+                ("all", shape! { "conditions": "array<FieldCondition>" }),
+                ("any", shape! { "conditions": "array<FieldCondition>" }),
+                ("not", shape! { "condition": "FieldCondition" }),
+            ],
+        ),
+    );
+    add(
+        "FileMode",
+        unit_enum(&["openfile", "savefile", "selectfolder"]),
+    );
+    add(
+        "ValidationRule",
+        tagged(
+            "rule",
+            [
+                ("required", shape! {}),
+                ("regex", shape! { "pattern": "string" }),
+                ("range", shape! { "min": "f64?", "max": "f64?" }),
+                ("one_of", shape! { "values": "array<json>" }),
+                ("min_length", shape! { "min": "usize" }),
+                ("custom", shape! { "key": "string" }),
+                (
+                    "requires_when",
+                    shape! { "field": "string", "equals": "json" },
+                ),
+                ("less_than", shape! { "other_field": "string" }),
+                ("greater_than", shape! { "other_field": "string" }),
+            ],
+        ),
+    );
+    add(
+        "ConfigChange",
+        structure(shape! { "key": "string", "old": "json", "new": "json" }),
+    );
+    add(
+        "ConfigDelta",
+        structure(shape! { "changes": "array<ConfigChange>" }),
+    );
+    add(
+        "MetricsSnapshot",
+        structure(shape! { "samples": "array<MetricSample>" }),
+    );
+    add(
+        "MetricSample",
+        tagged(
+            "type",
+            [
+                (
+                    "counter",
+                    shape! { "name": "string", "help": "string?", "value": "u64" },
+                ),
+                (
+                    "gauge",
+                    shape! { "name": "string", "help": "string?", "value": "f64" },
+                ),
+                (
+                    "histogram",
+                    shape! {
+                        "name": "string",
+                        "help": "string?",
+                        "buckets": "array<[f64, u64]>",
+                        "sum": "f64",
+                        "count": "u64",
+                    },
+                ),
+            ],
+        ),
+    );
+
+    ApiReport {
+        crate_version: env!("CARGO_PKG_VERSION").to_string(),
+        abi,
+        types,
+    }
+}
+
+/// Changes needed to get from `old` to `new`.
+///
+/// Removals, type changes and newly required fields are breaking; new
+/// optional fields, variants, types and appended ABI entries are additive.
+pub fn diff(old: &ApiReport, new: &ApiReport) -> Vec<ApiChange> {
+    let mut changes = Vec::new();
+    let mut push = |kind, path: String, description: &str| {
+        changes.push(ApiChange {
+            kind,
+            path,
+            description: description.to_string(),
+        })
+    };
+
+    for (index, entry) in old.abi.iter().enumerate() {
+        let path = format!("abi.{}", entry.name);
+        match new.abi.get(index) {
+            Some(current) if current == entry => {}
+            Some(current) if current.name == entry.name => {
+                push(ChangeKind::Breaking, path, "signature changed")
+            }
+            _ => push(ChangeKind::Breaking, path, "entry removed or moved"),
+        }
+    }
+    for entry in new.abi.iter().skip(old.abi.len()) {
+        let kind = if entry.optional {
+            ChangeKind::Additive
+        } else {
+            ChangeKind::Breaking
+        };
+        push(kind, format!("abi.{}", entry.name), "entry appended");
+    }
+
+    for (name, old_shape) in &old.types {
+        let Some(new_shape) = new.types.get(name) else {
+            push(ChangeKind::Breaking, name.clone(), "type removed");
+            continue;
+        };
+        match (old_shape, new_shape) {
+            (TypeShape::Struct { fields: old }, TypeShape::Struct { fields: new }) => {
+                diff_fields(name, old, new, &mut push);
+            }
+            (
+                TypeShape::Enum {
+                    tag: old_tag,
+                    variants: old,
+                },
+                TypeShape::Enum {
+                    tag: new_tag,
+                    variants: new,
+                },
+            ) => {
+                if old_tag != new_tag {
+                    push(ChangeKind::Breaking, name.clone(), "enum tag changed");
+                }
+                for (variant, old_fields) in old {
+                    let path = format!("{name}::{variant}");
+                    match new.get(variant) {
+                        Some(new_fields) => diff_fields(&path, old_fields, new_fields, &mut push),
+                        None => push(ChangeKind::Breaking, path, "variant removed"),
+                    }
+                }
+                for variant in new.keys().filter(|v| !old.contains_key(*v)) {
+                    push(
+                        ChangeKind::Additive,
+                        format!("{name}::{variant}"),
+                        "variant added",
+                    );
+                }
+            }
+            _ => push(ChangeKind::Breaking, name.clone(), "type kind changed"),
+        }
+    }
+    for name in new.types.keys().filter(|n| !old.types.contains_key(*n)) {
+        push(ChangeKind::Additive, name.clone(), "type added");
+    }
+    changes
+}
+
+/// Changes from `old` to `new` that the version bump between them does not
+/// allow under semver.
+///
+/// Breaking changes need an incompatible version (a new major, or a new
+/// minor before 1.0); additive ones need any newer version. Unparsable
+/// versions allow nothing.
+pub fn unversioned_changes(old: &ApiReport, new: &ApiReport) -> Vec<ApiChange> {
+    let old_version = parse_version(&old.crate_version);
+    let new_version = parse_version(&new.crate_version);
+    let (breaking_ok, additive_ok) = match (old_version, new_version) {
+        (Some(old), Some(new)) => (compat_key(new) > compat_key(old), new > old),
+        _ => (false, false),
+    };
+    diff(old, new)
+        .into_iter()
+        .filter(|change| match change.kind {
+            ChangeKind::Breaking => !breaking_ok,
+            ChangeKind::Additive => !additive_ok,
+        })
+        .collect()
+}
+
+// `major.minor.patch`, ignoring pre-release and build suffixes.
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version.split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|part| part.parse().ok());
+    let version = (parts.next()??, parts.next()??, parts.next()??);
+    parts.next().is_none().then_some(version)
+}
+
+// Versions with the same key are semver compatible.
+fn compat_key((major, minor, patch): (u64, u64, u64)) -> (u64, u64, u64) {
+    match (major, minor) {
+        (0, 0) => (0, 0, patch),
+        (0, minor) => (0, minor, 0),
+        (major, _) => (major, 0, 0),
+    }
+}
+
+fn diff_fields(
+    path: &str,
+    old: &BTreeMap<String, FieldShape>,
+    new: &BTreeMap<String, FieldShape>,
+    push: &mut impl FnMut(ChangeKind, String, &str),
+) {
+    for (field, old_shape) in old {
+        let field_path = format!("{path}.{field}");
+        match new.get(field) {
+            None => push(ChangeKind::Breaking, field_path, "field removed"),
+            Some(new_shape) if new_shape.ty != old_shape.ty => {
+                push(ChangeKind::Breaking, field_path, "field type changed")
+            }
+            Some(new_shape) if old_shape.optional && !new_shape.optional => {
+                push(ChangeKind::Breaking, field_path, "field became required")
+            }
+            Some(new_shape) if !old_shape.optional && new_shape.optional => {
+                push(ChangeKind::Additive, field_path, "field became optional")
+            }
+            Some(_) => {}
+        }
+    }
+    for (field, shape) in new.iter().filter(|(f, _)| !old.contains_key(*f)) {
+        let kind = if shape.optional {
+            ChangeKind::Additive
+        } else {
+            ChangeKind::Breaking
+        };
+        push(kind, format!("{path}.{field}"), "field added");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_reports_have_no_changes() {
+        assert!(diff(&current(), &current()).is_empty());
+    }
+
+    #[test]
+    fn classifies_changes() {
+        let old = current();
+        let mut new = current();
+        if let Some(TypeShape::Struct { fields }) = new.types.get_mut("PluginMeta") {
+            fields.remove("author");
+            fields.insert(
+                "icon".into(),
+                FieldShape {
+                    ty: "string".into(),
+                    optional: true,
+                },
+            );
+        }
+        new.abi.push(abi("extra", "fn(handle)", true));
+
+        let changes = diff(&old, &new);
+        let changes: Vec<(ChangeKind, &str)> =
+            changes.iter().map(|c| (c.kind, c.path.as_str())).collect();
+        assert_eq!(
+            changes,
+            [
+                (ChangeKind::Additive, "abi.extra"),
+                (ChangeKind::Breaking, "PluginMeta.author"),
+                (ChangeKind::Additive, "PluginMeta.icon"),
+            ]
+        );
+    }
+
+    #[test]
+    fn breaking_changes_need_an_incompatible_version() {
+        let mut old = current();
+        old.crate_version = "0.2.0".into();
+        let mut new = current();
+        new.abi.push(abi("extra", "fn(handle)", true));
+        new.abi[1].signature = "fn(u32) -> handle".into();
+        let mut unversioned = |version: &str| {
+            new.crate_version = version.into();
+            unversioned_changes(&old, &new)
+                .into_iter()
+                .map(|c| c.path)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(unversioned("0.2.0"), ["abi.create", "abi.extra"]);
+        assert_eq!(unversioned("0.2.1"), ["abi.create"]);
+        assert!(unversioned("0.3.0").is_empty());
+        assert_eq!(unversioned("garbage"), ["abi.create", "abi.extra"]);
+    }
+}
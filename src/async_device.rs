@@ -0,0 +1,329 @@
+//! Drivers for network- and serial-backed devices written against tokio.
+//!
+//! [`AsyncDeviceAdapter`] runs an [`AsyncDeviceDriver`] on its own thread
+//! with a current-thread runtime. `process` only drains the frames read so
+//! far, so a slow device never blocks the realtime thread; it just leaves
+//! the outputs at their last values.
+
+use crate::ports::port_index;
+use crate::{DeviceDriver, Plugin, PluginContext, PluginError, PluginId, PluginMeta, Port};
+use std::future::Future;
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError, TrySendError};
+use std::thread::JoinHandle;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+/// Frames buffered between the driver thread and `process`. Frames beyond
+/// that are dropped until `process` catches up.
+pub const ASYNC_FRAME_CAPACITY: usize = 64;
+
+pub trait AsyncDeviceDriver: Send + 'static {
+    fn meta(&self) -> &PluginMeta;
+    fn outputs(&self) -> &[Port];
+
+    fn open(&mut self) -> impl Future<Output = Result<(), PluginError>> + Send;
+    fn close(&mut self) -> impl Future<Output = Result<(), PluginError>> + Send;
+
+    /// Waits for the next frame, one value per output. Must be cancel safe:
+    /// the adapter drops a pending read to handle `close`. After an error
+    /// the adapter stops reading until the next `open`.
+    fn read(&mut self) -> impl Future<Output = Result<Vec<f64>, PluginError>> + Send;
+}
+
+enum Command {
+    Open(mpsc::Sender<Result<(), PluginError>>),
+    Close(mpsc::Sender<Result<(), PluginError>>),
+}
+
+/// Runs an [`AsyncDeviceDriver`] as a synchronous [`DeviceDriver`].
+pub struct AsyncDeviceAdapter {
+    id: PluginId,
+    meta: PluginMeta,
+    outputs: Vec<Port>,
+    values: Vec<f64>,
+    commands: Option<UnboundedSender<Command>>,
+    frames: Receiver<Result<Vec<f64>, PluginError>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl AsyncDeviceAdapter {
+    pub fn new<D: AsyncDeviceDriver>(id: PluginId, driver: D) -> Result<Self, PluginError> {
+        let meta = driver.meta().clone();
+        let outputs = driver.outputs().to_vec();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let (command_tx, command_rx) = unbounded_channel();
+        let (frame_tx, frame_rx) = mpsc::sync_channel(ASYNC_FRAME_CAPACITY);
+        let thread = std::thread::Builder::new()
+            .name(format!("rtsyn-device-{}", id.0))
+            .spawn(move || runtime.block_on(drive(driver, command_rx, frame_tx)))?;
+        Ok(Self {
+            id,
+            meta,
+            values: vec![0.0; outputs.len()],
+            outputs,
+            commands: Some(command_tx),
+            frames: frame_rx,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn output(&self, index: usize) -> f64 {
+        self.values[index]
+    }
+
+    /// Unknown names read as `0.0`.
+    pub fn output_by_name(&self, name: &str) -> f64 {
+        port_index(&self.outputs, name).map_or(0.0, |index| self.values[index])
+    }
+
+    // Blocks until the driver thread has run the command.
+    fn request(
+        &self,
+        command: fn(mpsc::Sender<Result<(), PluginError>>) -> Command,
+    ) -> Result<(), PluginError> {
+        let (reply_tx, reply_rx) = mpsc::channel();
+        self.commands
+            .as_ref()
+            .and_then(|commands| commands.send(command(reply_tx)).ok())
+            .ok_or_else(driver_stopped)?;
+        reply_rx.recv().map_err(|_| driver_stopped())?
+    }
+}
+
+fn driver_stopped() -> PluginError {
+    PluginError::Fatal("device driver thread stopped".to_string())
+}
+
+async fn drive<D: AsyncDeviceDriver>(
+    mut driver: D,
+    mut commands: UnboundedReceiver<Command>,
+    frames: SyncSender<Result<Vec<f64>, PluginError>>,
+) {
+    let mut open = false;
+    // Cleared by a failed read so a broken device is not polled in a tight
+    // loop; the next `Open` resumes reading.
+    let mut reading = false;
+    loop {
+        let command = if reading {
+            tokio::select! {
+                command = commands.recv() => command,
+                frame = driver.read() => {
+                    reading = frame.is_ok();
+                    match frames.try_send(frame) {
+                        Ok(()) | Err(TrySendError::Full(_)) => continue,
+                        Err(TrySendError::Disconnected(_)) => None,
+                    }
+                }
+            }
+        } else {
+            commands.recv().await
+        };
+        match command {
+            Some(Command::Open(reply)) => {
+                let result = if open { Ok(()) } else { driver.open().await };
+                open = result.is_ok();
+                reading = open;
+                let _ = reply.send(result);
+            }
+            Some(Command::Close(reply)) => {
+                let result = if open { driver.close().await } else { Ok(()) };
+                open = false;
+                reading = false;
+                let _ = reply.send(result);
+            }
+            None => break,
+        }
+    }
+    if open {
+        let _ = driver.close().await;
+    }
+}
+
+impl Plugin for AsyncDeviceAdapter {
+    fn id(&self) -> PluginId {
+        self.id
+    }
+
+    fn meta(&self) -> &PluginMeta {
+        &self.meta
+    }
+
+    fn inputs(&self) -> &[Port] {
+        &[]
+    }
+
+    fn outputs(&self) -> &[Port] {
+        &self.outputs
+    }
+
+    /// Takes the newest frame read since the last tick. Read errors are
+    /// returned once all frames before them were consumed.
+    fn process(&mut self, _ctx: &mut PluginContext) -> Result<(), PluginError> {
+        loop {
+            match self.frames.try_recv() {
+                Ok(Ok(frame)) => {
+                    for (slot, value) in self.values.iter_mut().zip(frame) {
+                        *slot = value;
+                    }
+                }
+                Ok(Err(err)) => return Err(err),
+                Err(TryRecvError::Empty) => return Ok(()),
+                Err(TryRecvError::Disconnected) => return Err(driver_stopped()),
+            }
+        }
+    }
+}
+
+impl DeviceDriver for AsyncDeviceAdapter {
+    fn open(&mut self) -> Result<(), PluginError> {
+        self.request(Command::Open)
+    }
+
+    fn close(&mut self) -> Result<(), PluginError> {
+        self.request(Command::Close)
+    }
+}
+
+impl Drop for AsyncDeviceAdapter {
+    // Closing the command channel ends the driver task, which closes the
+    // device if it is still open.
+    fn drop(&mut self) {
+        self.commands = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    // Counts up by one per read, as if samples arrived over a socket.
+    struct Counter {
+        meta: PluginMeta,
+        outputs: Vec<Port>,
+        next: f64,
+        closed: Arc<AtomicBool>,
+    }
+
+    impl AsyncDeviceDriver for Counter {
+        fn meta(&self) -> &PluginMeta {
+            &self.meta
+        }
+
+        fn outputs(&self) -> &[Port] {
+            &self.outputs
+        }
+
+        async fn open(&mut self) -> Result<(), PluginError> {
+            self.next = 1.0;
+            Ok(())
+        }
+
+        async fn close(&mut self) -> Result<(), PluginError> {
+            self.closed.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn read(&mut self) -> Result<Vec<f64>, PluginError> {
+            tokio::task::yield_now().await;
+            let value = self.next;
+            self.next += 1.0;
+            if value > 3.0 {
+                // Stalls like a device that stopped sending.
+                std::future::pending::<()>().await;
+            }
+            Ok(vec![value])
+        }
+    }
+
+    #[test]
+    fn frames_reach_process_without_blocking() {
+        let closed = Arc::new(AtomicBool::new(false));
+        let mut adapter = AsyncDeviceAdapter::new(
+            PluginId(7),
+            Counter {
+                meta: PluginMeta::new("counter"),
+                outputs: vec![Port::new("count")],
+                next: 0.0,
+                closed: closed.clone(),
+            },
+        )
+        .unwrap();
+        let mut ctx = PluginContext::default();
+        adapter.process(&mut ctx).unwrap();
+        assert_eq!(adapter.output(0), 0.0);
+
+        adapter.open().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while adapter.output_by_name("count") < 3.0 && Instant::now() < deadline {
+            adapter.process(&mut ctx).unwrap();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(adapter.output(0), 3.0);
+
+        // The stalled read is dropped to handle the close.
+        adapter.close().unwrap();
+        assert!(closed.load(Ordering::SeqCst));
+        drop(adapter);
+    }
+
+    // Fails every read, like a serial port that was unplugged.
+    struct Unplugged {
+        meta: PluginMeta,
+        reads: Arc<AtomicUsize>,
+    }
+
+    impl AsyncDeviceDriver for Unplugged {
+        fn meta(&self) -> &PluginMeta {
+            &self.meta
+        }
+
+        fn outputs(&self) -> &[Port] {
+            &[]
+        }
+
+        async fn open(&mut self) -> Result<(), PluginError> {
+            Ok(())
+        }
+
+        async fn close(&mut self) -> Result<(), PluginError> {
+            Ok(())
+        }
+
+        async fn read(&mut self) -> Result<Vec<f64>, PluginError> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            Err(PluginError::Fatal("device unplugged".to_string()))
+        }
+    }
+
+    #[test]
+    fn read_errors_pause_reading_until_reopened() {
+        let reads = Arc::new(AtomicUsize::new(0));
+        let mut adapter = AsyncDeviceAdapter::new(
+            PluginId(8),
+            Unplugged {
+                meta: PluginMeta::new("unplugged"),
+                reads: reads.clone(),
+            },
+        )
+        .unwrap();
+        let mut ctx = PluginContext::default();
+
+        adapter.open().unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(reads.load(Ordering::SeqCst), 1);
+        assert!(adapter.process(&mut ctx).is_err());
+        adapter.process(&mut ctx).unwrap();
+
+        adapter.open().unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(reads.load(Ordering::SeqCst), 2);
+        assert!(adapter.process(&mut ctx).is_err());
+    }
+}
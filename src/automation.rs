@@ -0,0 +1,110 @@
+//! Parameter automation: hosts ramp numeric config values such as gain or
+//! frequency over a number of ticks instead of stepping them through a
+//! configuration change.
+//!
+//! Plugins declaring `PluginBehavior::supports_automation` find the changes
+//! starting on a tick in `PluginContext::automation` and usually feed them
+//! to a [`ParamRamp`] per parameter.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParamChange {
+    /// Config key of the parameter.
+    pub key: String,
+    pub target: f64,
+    /// Ticks to reach `target`; `0` jumps immediately.
+    #[serde(default)]
+    pub ramp_ticks: u64,
+}
+
+impl ParamChange {
+    pub fn new(key: impl Into<String>, target: f64, ramp_ticks: u64) -> Self {
+        Self {
+            key: key.into(),
+            target,
+            ramp_ticks,
+        }
+    }
+}
+
+/// Linear ramp of one parameter, advanced once per tick.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParamRamp {
+    value: f64,
+    target: f64,
+    step: f64,
+    remaining: u64,
+}
+
+impl ParamRamp {
+    pub fn new(value: f64) -> Self {
+        Self {
+            value,
+            target: value,
+            step: 0.0,
+            remaining: 0,
+        }
+    }
+
+    /// Ramps from the current value, replacing any ramp in progress.
+    pub fn start(&mut self, change: &ParamChange) {
+        self.target = change.target;
+        self.remaining = change.ramp_ticks;
+        if change.ramp_ticks == 0 {
+            self.value = change.target;
+            self.step = 0.0;
+        } else {
+            self.step = (change.target - self.value) / change.ramp_ticks as f64;
+        }
+    }
+
+    /// Jumps to `value`, e.g. on a regular configuration change.
+    pub fn set(&mut self, value: f64) {
+        *self = Self::new(value);
+    }
+
+    /// Advances one tick and returns the value for it.
+    pub fn tick(&mut self) -> f64 {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            // The last step lands exactly on the target.
+            self.value = if self.remaining == 0 {
+                self.target
+            } else {
+                self.value + self.step
+            };
+        }
+        self.value
+    }
+
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    pub fn target(&self) -> f64 {
+        self.target
+    }
+
+    pub fn is_ramping(&self) -> bool {
+        self.remaining > 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ramps_linearly_to_target() {
+        let mut gain = ParamRamp::new(1.0);
+        gain.start(&ParamChange::new("gain", 2.0, 4));
+        let values: Vec<f64> = (0..5).map(|_| gain.tick()).collect();
+        assert_eq!(values, [1.25, 1.5, 1.75, 2.0, 2.0]);
+        assert!(!gain.is_ramping());
+
+        gain.start(&ParamChange::new("gain", 0.0, 0));
+        assert_eq!(gain.value(), 0.0);
+        assert_eq!(gain.tick(), 0.0);
+    }
+}
@@ -0,0 +1,387 @@
+use crate::ui::validation::is_rfc3339;
+use crate::ui::{ConfigField, FieldType, UISchema};
+use serde_json::{Map, Value};
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum CliError {
+    #[error("unknown option: {0}")]
+    UnknownOption(String),
+    #[error("missing value for {0}")]
+    MissingValue(String),
+    #[error("invalid value {value:?} for {source_name}: {reason}")]
+    InvalidValue {
+        source_name: String,
+        value: String,
+        reason: String,
+    },
+}
+
+/// Maps a [`UISchema`] onto command-line flags and environment variables.
+///
+/// A field with key `sample_rate` is read from `--sample-rate <value>` (or
+/// `--sample-rate=<value>`) and from `<PREFIX>_SAMPLE_RATE`. Flags win over
+/// environment variables, which win over schema defaults.
+pub struct CliConfig<'a> {
+    schema: &'a UISchema,
+    env_prefix: String,
+}
+
+impl<'a> CliConfig<'a> {
+    pub fn new(schema: &'a UISchema, env_prefix: impl Into<String>) -> Self {
+        Self {
+            schema,
+            env_prefix: env_prefix.into(),
+        }
+    }
+
+    pub fn flag_name(&self, field: &ConfigField) -> String {
+        format!("--{}", field.key.replace('_', "-"))
+    }
+
+    pub fn env_name(&self, field: &ConfigField) -> String {
+        let key = field.key.to_uppercase().replace('-', "_");
+        if self.env_prefix.is_empty() {
+            key
+        } else {
+            format!("{}_{}", self.env_prefix, key)
+        }
+    }
+
+    pub fn defaults(&self) -> Value {
+        Value::Object(self.schema.defaults())
+    }
+
+    pub fn parse_env<I, K, V>(&self, vars: I) -> Result<Value, CliError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let vars: Vec<(K, V)> = vars.into_iter().collect();
+        let mut config = Map::new();
+        for field in self.schema.value_fields() {
+            let name = self.env_name(field);
+            if let Some((_, raw)) = vars.iter().find(|(k, _)| k.as_ref() == name) {
+                let value = match &field.field_type {
+                    // Structured items would not survive splitting on commas,
+                    // so those lists are a JSON array.
+                    FieldType::DynamicList { item_type, .. }
+                        if matches!(**item_type, FieldType::Group { .. }) =>
+                    {
+                        match serde_json::from_str(raw.as_ref()) {
+                            Ok(Value::Array(items)) => Value::Array(items),
+                            _ => {
+                                return Err(CliError::InvalidValue {
+                                    source_name: name,
+                                    value: raw.as_ref().to_string(),
+                                    reason: "expected a JSON array".into(),
+                                })
+                            }
+                        }
+                    }
+                    FieldType::DynamicList { item_type, .. } => {
+                        let mut items = Vec::new();
+                        for item in raw.as_ref().split(',').filter(|s| !s.is_empty()) {
+                            items.push(parse_scalar(item_type, item.trim(), &name)?);
+                        }
+                        Value::Array(items)
+                    }
+                    field_type => parse_scalar(field_type, raw.as_ref(), &name)?,
+                };
+                config.insert(field.key.clone(), value);
+            }
+        }
+        Ok(Value::Object(config))
+    }
+
+    pub fn parse_args<I, S>(&self, args: I) -> Result<Value, CliError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut config = Map::new();
+        let mut args = args.into_iter().map(|a| a.as_ref().to_string());
+
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg.clone(), None),
+            };
+
+            if let Some(field) = self.find_flag(&flag) {
+                let value = match (&field.field_type, inline) {
+                    (FieldType::Boolean, None) => "true".to_string(),
+                    (_, Some(value)) => value,
+                    (_, None) => args
+                        .next()
+                        .ok_or_else(|| CliError::MissingValue(flag.clone()))?,
+                };
+                match &field.field_type {
+                    FieldType::DynamicList { item_type, .. } => {
+                        let item = parse_scalar(item_type, &value, &flag)?;
+                        let entry = config
+                            .entry(field.key.clone())
+                            .or_insert_with(|| Value::Array(Vec::new()));
+                        if let Value::Array(items) = entry {
+                            items.push(item);
+                        }
+                    }
+                    field_type => {
+                        config.insert(field.key.clone(), parse_scalar(field_type, &value, &flag)?);
+                    }
+                }
+                continue;
+            }
+
+            // `--no-<flag>` clears a boolean
+            if let Some(field) = flag
+                .strip_prefix("--no-")
+                .and_then(|name| self.find_flag(&format!("--{name}")))
+                .filter(|f| matches!(f.field_type, FieldType::Boolean))
+            {
+                config.insert(field.key.clone(), Value::Bool(false));
+                continue;
+            }
+
+            return Err(CliError::UnknownOption(arg));
+        }
+        Ok(Value::Object(config))
+    }
+
+    /// Defaults, overridden by environment variables, overridden by flags.
+    pub fn parse<A, S, E, K, V>(&self, args: A, env: E) -> Result<Value, CliError>
+    where
+        A: IntoIterator<Item = S>,
+        S: AsRef<str>,
+        E: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut config = self.defaults();
+        merge(&mut config, self.parse_env(env)?);
+        merge(&mut config, self.parse_args(args)?);
+        Ok(config)
+    }
+
+    pub fn usage(&self) -> String {
+        let mut out = String::new();
+        for field in self.schema.value_fields() {
+            out.push_str(&format!(
+                "  {:<24} {} [env: {}]",
+                self.flag_name(field),
+                field.label,
+                self.env_name(field)
+            ));
+            if let Some(default) = field
+                .default
+                .as_ref()
+                .filter(|_| !matches!(field.field_type, FieldType::Secret))
+            {
+                out.push_str(&format!(" [default: {default}]"));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    fn find_flag(&self, flag: &str) -> Option<&ConfigField> {
+        self.schema
+            .value_fields()
+            .into_iter()
+            .find(|field| self.flag_name(field) == flag)
+    }
+}
+
+fn merge(target: &mut Value, source: Value) {
+    if let (Value::Object(target), Value::Object(source)) = (target, source) {
+        target.extend(source);
+    }
+}
+
+fn parse_scalar(field_type: &FieldType, raw: &str, source_name: &str) -> Result<Value, CliError> {
+    let invalid = |reason: String| CliError::InvalidValue {
+        source_name: source_name.to_string(),
+        value: raw.to_string(),
+        reason,
+    };
+
+    match field_type {
+        FieldType::Integer { min, max, .. } => {
+            let value: i64 = raw
+                .parse()
+                .map_err(|_| invalid("expected an integer".into()))?;
+            if min.is_some_and(|min| value < min) || max.is_some_and(|max| value > max) {
+                return Err(invalid(format!("out of range {min:?}..={max:?}")));
+            }
+            Ok(Value::from(value))
+        }
+        FieldType::Float { min, max, .. } => {
+            let value: f64 = raw
+                .parse()
+                .map_err(|_| invalid("expected a number".into()))?;
+            if min.is_some_and(|min| value < min) || max.is_some_and(|max| value > max) {
+                return Err(invalid(format!("out of range {min:?}..={max:?}")));
+            }
+            Ok(Value::from(value))
+        }
+        FieldType::Boolean => match raw.to_ascii_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => Ok(Value::Bool(true)),
+            "false" | "0" | "no" | "off" => Ok(Value::Bool(false)),
+            _ => Err(invalid("expected a boolean".into())),
+        },
+        FieldType::Text { max_length, .. } => {
+            if max_length.is_some_and(|max| raw.chars().count() > max) {
+                return Err(invalid(format!(
+                    "longer than {} characters",
+                    max_length.unwrap()
+                )));
+            }
+            Ok(Value::String(raw.to_string()))
+        }
+        FieldType::Choice {
+            options,
+            options_source,
+        } => {
+            if !options_source.is_static() || options.iter().any(|o| o.value == raw) {
+                Ok(Value::String(raw.to_string()))
+            } else {
+                let values: Vec<&str> = options.iter().map(|o| o.value.as_str()).collect();
+                Err(invalid(format!("expected one of {}", values.join(", "))))
+            }
+        }
+        FieldType::FilePath { .. } | FieldType::DynamicList { .. } | FieldType::Secret => {
+            Ok(Value::String(raw.to_string()))
+        }
+        FieldType::Duration { unit, min, max } => {
+            let value: u64 = raw
+                .parse()
+                .map_err(|_| invalid(format!("expected a whole number of {}", unit.symbol())))?;
+            if min.is_some_and(|min| value < min) || max.is_some_and(|max| value > max) {
+                return Err(invalid(format!("out of range {min:?}..={max:?}")));
+            }
+            Ok(Value::from(value))
+        }
+        FieldType::DateTime => {
+            if is_rfc3339(raw) {
+                Ok(Value::String(raw.to_string()))
+            } else {
+                Err(invalid("expected an RFC 3339 timestamp".into()))
+            }
+        }
+        FieldType::Table { .. } => match serde_json::from_str(raw) {
+            Ok(Value::Array(rows)) => Ok(Value::Array(rows)),
+            _ => Err(invalid("expected a JSON array of rows".into())),
+        },
+        FieldType::Action { .. } => Err(invalid("actions do not hold values".into())),
+        FieldType::Computed { .. } => Err(invalid("computed fields do not hold values".into())),
+        FieldType::Info { .. } => Err(invalid("info blocks do not hold values".into())),
+        // Items of structured lists are given as JSON objects.
+        FieldType::Group { .. } => match serde_json::from_str(raw) {
+            Ok(Value::Object(item)) => Ok(Value::Object(item)),
+            _ => Err(invalid("expected a JSON object".into())),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> UISchema {
+        UISchema::new()
+            .field(
+                ConfigField::integer("count", "Count")
+                    .min(0)
+                    .max(10)
+                    .default_value(json!(3)),
+            )
+            .field(ConfigField::float("sample_rate", "Sample rate"))
+            .section(
+                "flags",
+                "Flags",
+                [ConfigField::boolean("enabled", "Enabled")],
+            )
+            .field(
+                ConfigField::choice("mode", "Mode")
+                    .option("a", "A")
+                    .option("b", "B"),
+            )
+            .field(ConfigField::dynamic_list("columns", "Columns"))
+    }
+
+    #[test]
+    fn names_follow_key() {
+        let schema = schema();
+        let cli = CliConfig::new(&schema, "RTSYN_PLUGIN");
+        assert_eq!(cli.flag_name(&schema.fields[1]), "--sample-rate");
+        assert_eq!(cli.env_name(&schema.fields[1]), "RTSYN_PLUGIN_SAMPLE_RATE");
+    }
+
+    #[test]
+    fn args_override_env_and_defaults() {
+        let schema = schema();
+        let cli = CliConfig::new(&schema, "RTSYN_PLUGIN");
+        let config = cli
+            .parse(
+                [
+                    "--sample-rate=1000",
+                    "--enabled",
+                    "--columns",
+                    "x",
+                    "--columns",
+                    "y",
+                ],
+                [
+                    ("RTSYN_PLUGIN_SAMPLE_RATE", "10"),
+                    ("RTSYN_PLUGIN_MODE", "b"),
+                ],
+            )
+            .unwrap();
+
+        assert_eq!(
+            config,
+            json!({
+                "count": 3,
+                "sample_rate": 1000.0,
+                "enabled": true,
+                "mode": "b",
+                "columns": ["x", "y"],
+            })
+        );
+    }
+
+    #[test]
+    fn env_lists_are_comma_separated() {
+        let schema = schema();
+        let cli = CliConfig::new(&schema, "P");
+        let config = cli.parse_env([("P_COLUMNS", "a, b")]).unwrap();
+        assert_eq!(config["columns"], json!(["a", "b"]));
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        let schema = schema();
+        let cli = CliConfig::new(&schema, "P");
+        assert_eq!(
+            cli.parse_args(["--bogus"]),
+            Err(CliError::UnknownOption("--bogus".to_string()))
+        );
+        assert_eq!(
+            cli.parse_args(["--count"]),
+            Err(CliError::MissingValue("--count".to_string()))
+        );
+        assert!(matches!(
+            cli.parse_args(["--count", "11"]),
+            Err(CliError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            cli.parse_env([("P_MODE", "c")]),
+            Err(CliError::InvalidValue { .. })
+        ));
+        assert_eq!(
+            cli.parse_args(["--no-enabled"]).unwrap()["enabled"],
+            json!(false)
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// A named sample clock shared by every port that runs at the same rate.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClockDomain {
+    pub name: String,
+    pub rate_hz: f64,
+}
+
+impl ClockDomain {
+    pub fn new(name: impl Into<String>, rate_hz: f64) -> Self {
+        Self {
+            name: name.into(),
+            rate_hz,
+        }
+    }
+
+    pub fn crosses(&self, other: &ClockDomain) -> bool {
+        self.name != other.name
+    }
+}
+
+/// How an input port wants values from a foreign clock domain to be adapted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Resampling {
+    #[default]
+    SampleHold,
+    Interpolate,
+    Fifo {
+        capacity: usize,
+    },
+}
+
+impl Resampling {
+    pub fn adapter(self) -> Box<dyn ClockAdapter> {
+        match self {
+            Resampling::SampleHold => Box::new(SampleHold::new()),
+            Resampling::Interpolate => Box::new(Interpolate::new()),
+            Resampling::Fifo { capacity } => Box::new(Fifo::new(capacity)),
+        }
+    }
+}
+
+/// Moves values from a producer clock domain to a consumer clock domain.
+///
+/// The producer calls `push` at its own rate, the consumer calls `read` at its
+/// rate. Times are in seconds on a clock shared by both sides.
+pub trait ClockAdapter: Send {
+    fn push(&mut self, time: f64, value: f64);
+    fn read(&mut self, time: f64) -> Option<f64>;
+}
+
+/// Holds the most recent producer value.
+#[derive(Debug, Clone, Default)]
+pub struct SampleHold {
+    last: Option<f64>,
+}
+
+impl SampleHold {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ClockAdapter for SampleHold {
+    fn push(&mut self, _time: f64, value: f64) {
+        self.last = Some(value);
+    }
+
+    fn read(&mut self, _time: f64) -> Option<f64> {
+        self.last
+    }
+}
+
+/// Linear interpolation between the two most recent producer samples.
+///
+/// Reads past the newest sample hold its value rather than extrapolating.
+#[derive(Debug, Clone, Default)]
+pub struct Interpolate {
+    previous: Option<(f64, f64)>,
+    latest: Option<(f64, f64)>,
+}
+
+impl Interpolate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ClockAdapter for Interpolate {
+    fn push(&mut self, time: f64, value: f64) {
+        self.previous = self.latest;
+        self.latest = Some((time, value));
+    }
+
+    fn read(&mut self, time: f64) -> Option<f64> {
+        match (self.previous, self.latest) {
+            (Some((t0, v0)), Some((t1, v1))) if t1 > t0 => {
+                let t = time.clamp(t0, t1);
+                Some(v0 + (v1 - v0) * (t - t0) / (t1 - t0))
+            }
+            (_, Some((_, v))) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+/// Bounded queue delivering every producer sample exactly once.
+///
+/// When full, the oldest sample is dropped and counted in `overflows`.
+#[derive(Debug, Clone)]
+pub struct Fifo {
+    queue: VecDeque<f64>,
+    capacity: usize,
+    overflows: u64,
+}
+
+impl Fifo {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            queue: VecDeque::with_capacity(capacity),
+            capacity,
+            overflows: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn overflows(&self) -> u64 {
+        self.overflows
+    }
+}
+
+impl ClockAdapter for Fifo {
+    fn push(&mut self, _time: f64, value: f64) {
+        if self.queue.len() == self.capacity {
+            self.queue.pop_front();
+            self.overflows += 1;
+        }
+        self.queue.push_back(value);
+    }
+
+    fn read(&mut self, _time: f64) -> Option<f64> {
+        self.queue.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_hold_keeps_last_value() {
+        let mut adapter = SampleHold::new();
+        assert_eq!(adapter.read(0.0), None);
+        adapter.push(0.0, 1.0);
+        adapter.push(0.1, 2.0);
+        assert_eq!(adapter.read(0.5), Some(2.0));
+        assert_eq!(adapter.read(0.6), Some(2.0));
+    }
+
+    #[test]
+    fn interpolate_between_samples() {
+        let mut adapter = Interpolate::new();
+        adapter.push(0.0, 0.0);
+        assert_eq!(adapter.read(0.5), Some(0.0));
+        adapter.push(1.0, 10.0);
+        assert_eq!(adapter.read(0.25), Some(2.5));
+        assert_eq!(adapter.read(2.0), Some(10.0));
+    }
+
+    #[test]
+    fn fifo_drops_oldest_when_full() {
+        let mut adapter = Fifo::new(2);
+        adapter.push(0.0, 1.0);
+        adapter.push(0.0, 2.0);
+        adapter.push(0.0, 3.0);
+        assert_eq!(adapter.overflows(), 1);
+        assert_eq!(adapter.read(0.0), Some(2.0));
+        assert_eq!(adapter.read(0.0), Some(3.0));
+        assert_eq!(adapter.read(0.0), None);
+    }
+
+    #[test]
+    fn resampling_serialization() {
+        let json = serde_json::to_string(&Resampling::Fifo { capacity: 8 }).unwrap();
+        assert_eq!(json, r#"{"type":"fifo","capacity":8}"#);
+        let parsed: Resampling = serde_json::from_str(r#"{"type":"interpolate"}"#).unwrap();
+        assert_eq!(parsed, Resampling::Interpolate);
+    }
+
+    #[test]
+    fn domains_cross_by_name() {
+        let camera = ClockDomain::new("camera", 30.0);
+        let daq = ClockDomain::new("daq", 20_000.0);
+        assert!(camera.crosses(&daq));
+        assert!(!camera.crosses(&ClockDomain::new("camera", 30.0)));
+    }
+}
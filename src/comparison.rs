@@ -0,0 +1,328 @@
+use crate::config::ConfigDelta;
+use crate::ui::ComparisonMode;
+use crate::{Plugin, PluginContext, PluginError, Port, PortId};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Suffix appended to output ports of the shadow instance.
+pub const SHADOW_SUFFIX: &str = "_b";
+
+/// Runs a plugin (A) next to a shadow instance (B) with an alternate
+/// configuration so the host can compare their outputs.
+///
+/// Only plugins whose behavior declares [`ComparisonMode::Shadow`] can be
+/// compared. Both instances receive the same inputs; the outputs of the
+/// inactive one are exposed under the original port names plus
+/// [`SHADOW_SUFFIX`]. The shadow runs with its own [`PluginContext`], so
+/// its events never reach the host and its metrics stay separate.
+pub struct AbComparison<P> {
+    primary: P,
+    shadow: P,
+    set_input: fn(&mut P, &str, f64),
+    get_output: fn(&P, &str) -> f64,
+    shadow_ctx: PluginContext,
+    shadow_outputs: Vec<Port>,
+    swapped: bool,
+}
+
+impl<P: Plugin> AbComparison<P> {
+    /// `shadow` must be in the same state as `primary`, e.g. a copy from
+    /// [`Plugin::duplicate`] or a second instance with `config` applied;
+    /// it is switched to `alternate`. The accessors work as in
+    /// `CompositeBuilder::child`.
+    pub fn new(
+        primary: P,
+        mut shadow: P,
+        set_input: fn(&mut P, &str, f64),
+        get_output: fn(&P, &str) -> f64,
+        config: &Value,
+        alternate: &Value,
+    ) -> Result<Self, PluginError> {
+        if primary.behavior().comparison != ComparisonMode::Shadow {
+            return Err(PluginError::Config(
+                "plugin does not support A/B comparison".to_string(),
+            ));
+        }
+        shadow.on_config_changed(&ConfigDelta::between(config, alternate))?;
+
+        let shadow_outputs = primary
+            .outputs()
+            .iter()
+            .map(|port| Port {
+                id: shadow_port(&port.id),
+                ..port.clone()
+            })
+            .collect();
+        Ok(Self {
+            primary,
+            shadow,
+            set_input,
+            get_output,
+            shadow_ctx: PluginContext::default(),
+            shadow_outputs,
+            swapped: false,
+        })
+    }
+
+    /// Feeds `value` to input `name` of both instances.
+    pub fn set_input(&mut self, name: &str, value: f64) {
+        (self.set_input)(&mut self.primary, name, value);
+        (self.set_input)(&mut self.shadow, name, value);
+    }
+
+    /// Output `name` of the active instance, or of the inactive one for
+    /// names from [`AbComparison::shadow_outputs`].
+    pub fn get_output(&self, name: &str) -> f64 {
+        match name.strip_suffix(SHADOW_SUFFIX) {
+            Some(port) if self.shadow_outputs.iter().any(|p| p.id.0 == name) => {
+                (self.get_output)(self.inactive(), port)
+            }
+            _ => (self.get_output)(self.active(), name),
+        }
+    }
+
+    /// Processes both instances on the same tick.
+    pub fn process(&mut self, ctx: &mut PluginContext) -> Result<(), PluginError> {
+        self.primary.process(ctx)?;
+        let shadow_ctx = &mut self.shadow_ctx;
+        shadow_ctx.tick = ctx.tick;
+        shadow_ctx.period_seconds = ctx.period_seconds;
+        shadow_ctx.wall_time = ctx.wall_time;
+        shadow_ctx.monotonic_ns = ctx.monotonic_ns;
+        shadow_ctx.missed_ticks = ctx.missed_ticks;
+        shadow_ctx.jitter_ns = ctx.jitter_ns;
+        shadow_ctx.sample_rate = ctx.sample_rate;
+        shadow_ctx.block_size = ctx.block_size;
+        shadow_ctx.connected_inputs = ctx.connected_inputs;
+        shadow_ctx.connected_outputs = ctx.connected_outputs;
+        shadow_ctx
+            .input_update_ticks
+            .clone_from(&ctx.input_update_ticks);
+        shadow_ctx.automation.clone_from(&ctx.automation);
+        let result = self.shadow.process(shadow_ctx);
+        shadow_ctx.events.clear();
+        result
+    }
+
+    /// The shadow's own context, e.g. for its metrics.
+    pub fn shadow_context_mut(&mut self) -> &mut PluginContext {
+        &mut self.shadow_ctx
+    }
+
+    /// The instance currently driving the original output ports.
+    pub fn active(&self) -> &P {
+        if self.swapped {
+            &self.shadow
+        } else {
+            &self.primary
+        }
+    }
+
+    /// The instance currently behind the suffixed output ports.
+    pub fn inactive(&self) -> &P {
+        if self.swapped {
+            &self.primary
+        } else {
+            &self.shadow
+        }
+    }
+
+    /// Toggles which configuration drives the original output ports.
+    pub fn swap(&mut self) {
+        self.swapped = !self.swapped;
+    }
+
+    pub fn is_swapped(&self) -> bool {
+        self.swapped
+    }
+
+    pub fn shadow_outputs(&self) -> &[Port] {
+        &self.shadow_outputs
+    }
+
+    /// Ends the comparison and keeps the instance that is currently active.
+    pub fn finish(self) -> P {
+        if self.swapped {
+            self.shadow
+        } else {
+            self.primary
+        }
+    }
+}
+
+pub fn shadow_port(id: &PortId) -> PortId {
+    PortId(format!("{}{SHADOW_SUFFIX}", id.0))
+}
+
+/// Running statistics of the difference `b - a` between paired outputs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ComparisonStats {
+    pub count: u64,
+    pub mean_a: f64,
+    pub mean_b: f64,
+    pub mean_diff: f64,
+    pub max_abs_diff: f64,
+    // Sum of squared deviations of the difference (Welford).
+    m2_diff: f64,
+    sum_sq_diff: f64,
+}
+
+impl ComparisonStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, a: f64, b: f64) {
+        let diff = b - a;
+        self.count += 1;
+        let n = self.count as f64;
+        self.mean_a += (a - self.mean_a) / n;
+        self.mean_b += (b - self.mean_b) / n;
+        let delta = diff - self.mean_diff;
+        self.mean_diff += delta / n;
+        self.m2_diff += delta * (diff - self.mean_diff);
+        self.sum_sq_diff += diff * diff;
+        self.max_abs_diff = self.max_abs_diff.max(diff.abs());
+    }
+
+    /// Sample standard deviation of the difference.
+    pub fn std_dev_diff(&self) -> f64 {
+        if self.count < 2 {
+            0.0
+        } else {
+            (self.m2_diff / (self.count - 1) as f64).sqrt()
+        }
+    }
+
+    pub fn rms_diff(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            (self.sum_sq_diff / self.count as f64).sqrt()
+        }
+    }
+
+    /// Paired t statistic of the difference; large magnitudes mean the two
+    /// configurations produce systematically different outputs.
+    pub fn t_statistic(&self) -> Option<f64> {
+        let std_dev = self.std_dev_diff();
+        (self.count >= 2 && std_dev > 0.0)
+            .then(|| self.mean_diff / (std_dev / (self.count as f64).sqrt()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::Event;
+    use crate::ports::StaticPorts;
+    use crate::ui::PluginBehavior;
+    use crate::{PluginId, PluginMeta};
+    use serde_json::json;
+
+    struct Gain {
+        meta: PluginMeta,
+        ports: StaticPorts<1, 1>,
+        gain: f64,
+        comparison: ComparisonMode,
+    }
+
+    impl Gain {
+        fn new(comparison: ComparisonMode) -> Self {
+            Self {
+                meta: PluginMeta::new("gain"),
+                ports: StaticPorts::new(["in"], ["out"]),
+                gain: 1.0,
+                comparison,
+            }
+        }
+    }
+
+    impl Plugin for Gain {
+        fn id(&self) -> PluginId {
+            PluginId(1)
+        }
+        fn meta(&self) -> &PluginMeta {
+            &self.meta
+        }
+        fn inputs(&self) -> &[Port] {
+            self.ports.inputs()
+        }
+        fn outputs(&self) -> &[Port] {
+            self.ports.outputs()
+        }
+        fn process(&mut self, ctx: &mut PluginContext) -> Result<(), PluginError> {
+            let value = self.ports.input(0) * self.gain;
+            self.ports.set_output(0, value);
+            ctx.emit(Event::new(PluginId(1), "processed", Value::from(value)));
+            Ok(())
+        }
+        fn behavior(&self) -> PluginBehavior {
+            PluginBehavior {
+                comparison: self.comparison,
+                ..PluginBehavior::default()
+            }
+        }
+        fn on_config_changed(&mut self, changed: &ConfigDelta) -> Result<(), PluginError> {
+            if let Some(gain) = changed.get("gain").and_then(|c| c.new.as_ref()) {
+                self.gain = gain.as_f64().unwrap_or(1.0);
+            }
+            Ok(())
+        }
+    }
+
+    fn compare(comparison: ComparisonMode) -> Result<AbComparison<Gain>, PluginError> {
+        AbComparison::new(
+            Gain::new(comparison),
+            Gain::new(comparison),
+            |p, name, v| p.ports.set_input_by_name(name, v),
+            |p, name| p.ports.output_by_name(name),
+            &json!({ "gain": 1.0 }),
+            &json!({ "gain": 2.0 }),
+        )
+    }
+
+    #[test]
+    fn shadow_runs_with_alternate_config() {
+        let mut ab = compare(ComparisonMode::Shadow).unwrap();
+        assert_eq!(ab.shadow_outputs()[0].id, PortId("out_b".to_string()));
+
+        let mut ctx = PluginContext {
+            tick: 3,
+            ..PluginContext::default()
+        };
+        ab.set_input("in", 1.5);
+        ab.process(&mut ctx).unwrap();
+        assert_eq!(ab.get_output("out"), 1.5);
+        assert_eq!(ab.get_output("out_b"), 3.0);
+        assert_eq!(ctx.events.len(), 1);
+        assert_eq!(ab.shadow_context_mut().tick, 3);
+        assert!(ab.shadow_context_mut().events.is_empty());
+
+        ab.swap();
+        assert_eq!(ab.get_output("out"), 3.0);
+        assert_eq!(ab.get_output("out_b"), 1.5);
+        assert_eq!(ab.finish().gain, 2.0);
+    }
+
+    #[test]
+    fn requires_shadow_capability() {
+        assert!(matches!(
+            compare(ComparisonMode::None),
+            Err(PluginError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn stats_summarize_differences() {
+        let mut stats = ComparisonStats::new();
+        for (a, b) in [(1.0, 2.0), (2.0, 4.0), (3.0, 6.0)] {
+            stats.record(a, b);
+        }
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.mean_diff, 2.0);
+        assert_eq!(stats.max_abs_diff, 3.0);
+        assert!((stats.std_dev_diff() - 1.0).abs() < 1e-12);
+        assert!((stats.t_statistic().unwrap() - 2.0 * 3f64.sqrt()).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,266 @@
+//! Complex signals such as I/Q pairs from a lock-in amplifier.
+//!
+//! A port declared with [`Port::complex`](crate::Port::complex) carries two
+//! scalar channels, `<id>.re` and `<id>.im`, so it still crosses the scalar
+//! `set_input`/`get_output` calls. [`ComplexIn`] only takes a new value once
+//! both channels were written, and [`ComplexOut`] only writes both at once,
+//! so a pipeline never sees the real part of one tick with the imaginary
+//! part of another.
+
+use crate::Port;
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// Suffixes of the two channels of a complex port.
+pub const RE_SUFFIX: &str = ".re";
+pub const IM_SUFFIX: &str = ".im";
+
+/// Whether a port carries one value, a complex pair, a vector or text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PortKind {
+    #[default]
+    Scalar,
+    Complex,
+    /// Variable-length frames, only reachable through the vector calls.
+    Vector,
+    /// Text outputs, read with `Plugin::get_output_string`.
+    Text,
+}
+
+impl PortKind {
+    pub fn is_scalar(&self) -> bool {
+        *self == PortKind::Scalar
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Channel {
+    Re,
+    Im,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub const ZERO: Complex = Complex { re: 0.0, im: 0.0 };
+
+    pub const fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    pub fn from_polar(magnitude: f64, phase: f64) -> Self {
+        Self::new(magnitude * phase.cos(), magnitude * phase.sin())
+    }
+
+    /// `e^(i·phase)`, e.g. a reference oscillator for demodulation.
+    pub fn unit(phase: f64) -> Self {
+        Self::from_polar(1.0, phase)
+    }
+
+    pub fn magnitude(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    /// Phase in radians, in `-π..=π`.
+    pub fn phase(self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    pub fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    pub fn scale(self, factor: f64) -> Self {
+        Self::new(self.re * factor, self.im * factor)
+    }
+
+    /// Rotates by `phase` radians.
+    pub fn rotate(self, phase: f64) -> Self {
+        self * Self::unit(phase)
+    }
+
+    pub fn channel(self, channel: Channel) -> f64 {
+        match channel {
+            Channel::Re => self.re,
+            Channel::Im => self.im,
+        }
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, rhs: Complex) -> Complex {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, rhs: Complex) -> Complex {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, rhs: Complex) -> Complex {
+        Complex::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl Neg for Complex {
+    type Output = Complex;
+
+    fn neg(self) -> Complex {
+        Complex::new(-self.re, -self.im)
+    }
+}
+
+/// Scalar channel names of a complex port.
+pub fn channel_names(id: &str) -> [String; 2] {
+    [format!("{id}{RE_SUFFIX}"), format!("{id}{IM_SUFFIX}")]
+}
+
+/// Splits `iq.re` into `("iq", Channel::Re)`.
+pub fn split_channel(name: &str) -> Option<(&str, Channel)> {
+    if let Some(id) = name.strip_suffix(RE_SUFFIX) {
+        Some((id, Channel::Re))
+    } else {
+        name.strip_suffix(IM_SUFFIX).map(|id| (id, Channel::Im))
+    }
+}
+
+/// The names hosts pass to the scalar port calls: the id of scalar ports
+/// and both channels of complex ones. Vector and text ports have none.
+pub fn scalar_channels(ports: &[Port]) -> Vec<String> {
+    ports
+        .iter()
+        .flat_map(|port| match port.kind {
+            PortKind::Scalar => vec![port.id.0.clone()],
+            PortKind::Complex => channel_names(&port.id.0).to_vec(),
+            PortKind::Vector | PortKind::Text => Vec::new(),
+        })
+        .collect()
+}
+
+/// A complex input. Channel writes are staged until both halves arrived and
+/// [`ComplexIn::latch`] is called at the start of `process`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ComplexIn {
+    value: Complex,
+    pending: [Option<f64>; 2],
+}
+
+impl ComplexIn {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_channel(&mut self, channel: Channel, value: f64) {
+        self.pending[channel as usize] = Some(value);
+    }
+
+    /// Writes both halves at once.
+    pub fn set(&mut self, value: Complex) {
+        self.pending = [Some(value.re), Some(value.im)];
+    }
+
+    /// Takes the staged pair if both halves were written, and reports
+    /// whether the value changed. A lone half stays staged for its partner.
+    pub fn latch(&mut self) -> bool {
+        let [Some(re), Some(im)] = self.pending else {
+            return false;
+        };
+        self.pending = [None, None];
+        let value = Complex::new(re, im);
+        let changed = value != self.value;
+        self.value = value;
+        changed
+    }
+
+    /// The last complete pair.
+    pub fn value(&self) -> Complex {
+        self.value
+    }
+}
+
+/// A complex output; both channels always come from the same `set`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ComplexOut {
+    value: Complex,
+}
+
+impl ComplexOut {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&mut self, value: Complex) {
+        self.value = value;
+    }
+
+    pub fn value(&self) -> Complex {
+        self.value
+    }
+
+    pub fn channel(&self, channel: Channel) -> f64 {
+        self.value.channel(channel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    #[test]
+    fn math() {
+        let z = Complex::new(3.0, 4.0);
+        assert_eq!(z.magnitude(), 5.0);
+        assert_eq!(z * z.conj(), Complex::new(25.0, 0.0));
+        assert_eq!(z - z, Complex::ZERO);
+        assert_eq!(-z + z.scale(2.0), z);
+        let rotated = Complex::new(1.0, 0.0).rotate(FRAC_PI_2);
+        assert!(rotated.re.abs() < 1e-12 && (rotated.im - 1.0).abs() < 1e-12);
+        assert!((Complex::from_polar(2.0, 0.5).phase() - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn inputs_never_tear() {
+        let mut input = ComplexIn::new();
+        input.set_channel(Channel::Re, 1.0);
+        assert!(!input.latch());
+        assert_eq!(input.value(), Complex::ZERO);
+
+        input.set_channel(Channel::Im, 2.0);
+        assert!(input.latch());
+        assert_eq!(input.value(), Complex::new(1.0, 2.0));
+        assert!(!input.latch());
+    }
+
+    #[test]
+    fn ports_expand_to_channels() {
+        let ports = [Port::new("ref"), Port::complex("iq"), Port::vector("bins")];
+        assert_eq!(scalar_channels(&ports), ["ref", "iq.re", "iq.im"]);
+        assert_eq!(split_channel("iq.im"), Some(("iq", Channel::Im)));
+        assert_eq!(split_channel("iq"), None);
+
+        let json = serde_json::to_value(&ports[1]).unwrap();
+        assert_eq!(json["kind"], "complex");
+        assert!(serde_json::to_value(&ports[0])
+            .unwrap()
+            .get("kind")
+            .is_none());
+    }
+}
@@ -0,0 +1,940 @@
+//! A sub-graph of plugins packaged as one [`Plugin`].
+//!
+//! Children are wired with `"child.port"` endpoints, e.g.
+//! `connect("lowpass.out", "gain.in")`, and processed in dependency order
+//! every tick, each output read right before the input it feeds is written.
+//! The `Plugin` trait has no value accessors, so every child comes with the
+//! functions that write its inputs and read its outputs, as for
+//! `testing::MockHost::io`.
+//!
+//! Each child sees its own connection masks and input update ticks in the
+//! context while it runs, so it behaves as it would directly in a host.
+//! Configuration keys, actions and saved state are namespaced by child
+//! name: `"gain.factor"` reaches key `factor` of child `gain`, and the
+//! composite's schema holds one section per child with keys named that way.
+
+use crate::config::{ConfigChange, ConfigDelta};
+use crate::event::Event;
+use crate::ports::port_index;
+use crate::ui::{
+    ChoiceOption, ConfigField, FieldCondition, FieldType, PluginBehavior, ThreadingModel, UISchema,
+    ValidationRule,
+};
+use crate::{Plugin, PluginContext, PluginError, PluginId, PluginMeta, Port, ShutdownStatus};
+use serde_json::{Map, Value};
+
+/// A child plugin and its value accessors.
+pub(crate) trait Node: Send {
+    fn plugin(&self) -> &dyn Plugin;
+    fn plugin_mut(&mut self) -> &mut dyn Plugin;
+    fn set_input(&mut self, name: &str, value: f64);
+    fn get_output(&self, name: &str) -> f64;
+}
+
+struct Child<P> {
+    plugin: P,
+    set_input: fn(&mut P, &str, f64),
+    get_output: fn(&P, &str) -> f64,
+}
+
+impl<P: Plugin> Node for Child<P> {
+    fn plugin(&self) -> &dyn Plugin {
+        &self.plugin
+    }
+
+    fn plugin_mut(&mut self) -> &mut dyn Plugin {
+        &mut self.plugin
+    }
+
+    fn set_input(&mut self, name: &str, value: f64) {
+        (self.set_input)(&mut self.plugin, name, value)
+    }
+
+    fn get_output(&self, name: &str) -> f64 {
+        (self.get_output)(&self.plugin, name)
+    }
+}
+
+pub(crate) fn node<P: Plugin + 'static>(
+    plugin: P,
+    set_input: fn(&mut P, &str, f64),
+    get_output: fn(&P, &str) -> f64,
+) -> Box<dyn Node> {
+    Box::new(Child {
+        plugin,
+        set_input,
+        get_output,
+    })
+}
+
+/// A child port, resolved from a `"child.port"` endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Endpoint {
+    pub node: usize,
+    /// Current id of the port, even when the endpoint named an alias.
+    pub port: String,
+    pub index: usize,
+}
+
+// A connection, stored with the node it feeds.
+struct Route {
+    from: Endpoint,
+    to_port: String,
+    to_index: usize,
+}
+
+struct Slot {
+    name: String,
+    node: Box<dyn Node>,
+    connected_inputs: u64,
+    connected_outputs: u64,
+    input_update_ticks: Vec<Option<u64>>,
+    shutting_down: bool,
+}
+
+fn port_bit(index: usize) -> u64 {
+    1 << index.min(63)
+}
+
+/// Children, connections and processing order shared by
+/// [`CompositePlugin`] and [`Pipeline`](crate::pipeline::Pipeline).
+pub(crate) struct Graph {
+    slots: Vec<Slot>,
+    // Connections into each node, parallel to `slots`.
+    routes: Vec<Vec<Route>>,
+    order: Vec<usize>,
+}
+
+impl Graph {
+    /// Resolves the connections and orders the nodes so every node runs
+    /// after the nodes feeding it.
+    pub fn new(
+        nodes: Vec<(String, Box<dyn Node>)>,
+        connections: &[(String, String)],
+    ) -> Result<Self, PluginError> {
+        let mut names: Vec<&str> = Vec::with_capacity(nodes.len());
+        for (name, _) in &nodes {
+            if name.is_empty() || name.contains('.') {
+                return Err(PluginError::Config(format!("invalid child name {name:?}")));
+            }
+            if names.contains(&name.as_str()) {
+                return Err(PluginError::Config(format!("duplicate child {name}")));
+            }
+            names.push(name);
+        }
+        let mut graph = Graph {
+            routes: nodes.iter().map(|_| Vec::new()).collect(),
+            slots: nodes
+                .into_iter()
+                .map(|(name, node)| Slot {
+                    name,
+                    node,
+                    connected_inputs: 0,
+                    connected_outputs: 0,
+                    input_update_ticks: Vec::new(),
+                    shutting_down: false,
+                })
+                .collect(),
+            order: Vec::new(),
+        };
+        for (from, to) in connections {
+            let from = graph.output(from)?;
+            let to_endpoint = graph.input(to)?;
+            let routes = &mut graph.routes[to_endpoint.node];
+            if routes
+                .iter()
+                .any(|route| route.to_index == to_endpoint.index)
+            {
+                return Err(PluginError::Config(format!(
+                    "input {to} is connected twice"
+                )));
+            }
+            graph.slots[from.node].connected_outputs |= port_bit(from.index);
+            graph.slots[to_endpoint.node].connected_inputs |= port_bit(to_endpoint.index);
+            routes.push(Route {
+                from,
+                to_port: to_endpoint.port,
+                to_index: to_endpoint.index,
+            });
+        }
+        graph.order = graph.sort()?;
+        Ok(graph)
+    }
+
+    // Kahn's algorithm; ties keep the order the nodes were added in.
+    fn sort(&self) -> Result<Vec<usize>, PluginError> {
+        let mut pending: Vec<usize> = self.routes.iter().map(Vec::len).collect();
+        let mut order = Vec::with_capacity(self.slots.len());
+        let mut done = vec![false; self.slots.len()];
+        while order.len() < self.slots.len() {
+            let Some(next) = (0..self.slots.len()).find(|&n| !done[n] && pending[n] == 0) else {
+                let stuck = (0..self.slots.len()).find(|&n| !done[n]).unwrap_or(0);
+                return Err(PluginError::Config(format!(
+                    "connections form a cycle through {}",
+                    self.slots[stuck].name
+                )));
+            };
+            done[next] = true;
+            order.push(next);
+            for (node, routes) in self.routes.iter().enumerate() {
+                pending[node] -= routes.iter().filter(|r| r.from.node == next).count();
+            }
+        }
+        Ok(order)
+    }
+
+    fn endpoint(&self, endpoint: &str, output: bool) -> Result<Endpoint, PluginError> {
+        let (child, port) = endpoint
+            .split_once('.')
+            .ok_or_else(|| PluginError::Config(format!("endpoint {endpoint} is not child.port")))?;
+        let node = self
+            .slots
+            .iter()
+            .position(|slot| slot.name == child)
+            .ok_or_else(|| PluginError::Config(format!("unknown child {child}")))?;
+        let plugin = self.slots[node].node.plugin();
+        let (ports, direction) = if output {
+            (plugin.outputs(), "output")
+        } else {
+            (plugin.inputs(), "input")
+        };
+        let index = port_index(ports, port)
+            .ok_or_else(|| PluginError::Config(format!("{child} has no {direction} {port}")))?;
+        Ok(Endpoint {
+            node,
+            port: ports[index].id.0.clone(),
+            index,
+        })
+    }
+
+    pub fn input(&self, endpoint: &str) -> Result<Endpoint, PluginError> {
+        self.endpoint(endpoint, false)
+    }
+
+    pub fn output(&self, endpoint: &str) -> Result<Endpoint, PluginError> {
+        self.endpoint(endpoint, true)
+    }
+
+    /// Marks ports driven or read from outside the graph as connected.
+    pub fn expose(&mut self, endpoint: &Endpoint, output: bool) {
+        let slot = &mut self.slots[endpoint.node];
+        if output {
+            slot.connected_outputs |= port_bit(endpoint.index);
+        } else {
+            slot.connected_inputs |= port_bit(endpoint.index);
+        }
+    }
+
+    pub fn set_input(&mut self, endpoint: &Endpoint, value: f64) {
+        self.slots[endpoint.node]
+            .node
+            .set_input(&endpoint.port, value);
+    }
+
+    pub fn get_output(&self, endpoint: &Endpoint) -> f64 {
+        self.slots[endpoint.node].node.get_output(&endpoint.port)
+    }
+
+    /// Records an input written from outside the graph on `tick`.
+    pub fn mark_updated(&mut self, endpoint: &Endpoint, tick: Option<u64>) {
+        let ticks = &mut self.slots[endpoint.node].input_update_ticks;
+        if ticks.len() <= endpoint.index {
+            ticks.resize(endpoint.index + 1, None);
+        }
+        ticks[endpoint.index] = tick;
+    }
+
+    /// Routes values and processes every node once, in dependency order.
+    pub fn process(&mut self, ctx: &mut PluginContext) -> Result<(), PluginError> {
+        for position in 0..self.order.len() {
+            let node = self.order[position];
+            for route in &self.routes[node] {
+                let value = self.slots[route.from.node]
+                    .node
+                    .get_output(&route.from.port);
+                let slot = &mut self.slots[node];
+                slot.node.set_input(&route.to_port, value);
+                if slot.input_update_ticks.len() <= route.to_index {
+                    slot.input_update_ticks.resize(route.to_index + 1, None);
+                }
+                slot.input_update_ticks[route.to_index] = Some(ctx.tick);
+            }
+            let slot = &mut self.slots[node];
+            swap_context(ctx, slot);
+            let result = slot.node.plugin_mut().process(ctx);
+            swap_context(ctx, slot);
+            result?;
+        }
+        Ok(())
+    }
+
+    /// Calls `call` on every node in processing order, stopping at the first
+    /// error.
+    pub fn for_each(
+        &mut self,
+        mut call: impl FnMut(&str, &mut dyn Plugin) -> Result<(), PluginError>,
+    ) -> Result<(), PluginError> {
+        for &node in &self.order {
+            let slot = &mut self.slots[node];
+            call(&slot.name, slot.node.plugin_mut())?;
+        }
+        Ok(())
+    }
+
+    pub fn plugin(&self, name: &str) -> Option<&dyn Plugin> {
+        self.slots
+            .iter()
+            .find(|slot| slot.name == name)
+            .map(|slot| slot.node.plugin())
+    }
+
+    pub fn plugin_mut(&mut self, name: &str) -> Option<&mut dyn Plugin> {
+        self.slots
+            .iter_mut()
+            .find(|slot| slot.name == name)
+            .map(|slot| slot.node.plugin_mut())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.order
+            .iter()
+            .map(|&node| self.slots[node].name.as_str())
+    }
+
+    /// Ticks from the inputs of `node` to its outputs, through the slowest
+    /// chain feeding it.
+    pub fn latency_ticks(&self, node: usize) -> u64 {
+        let mut latency = vec![0u64; self.slots.len()];
+        for &n in &self.order {
+            let upstream = self.routes[n]
+                .iter()
+                .map(|route| latency[route.from.node])
+                .max()
+                .unwrap_or(0);
+            latency[n] = upstream + self.slots[n].node.plugin().latency_ticks();
+        }
+        latency[node]
+    }
+
+    pub fn begin_shutdown(&mut self) -> Result<ShutdownStatus, PluginError> {
+        for slot in &mut self.slots {
+            slot.shutting_down = !slot.node.plugin_mut().begin_shutdown()?.is_done();
+        }
+        Ok(self.shutdown_status())
+    }
+
+    pub fn poll_shutdown(&mut self) -> Result<ShutdownStatus, PluginError> {
+        for slot in self.slots.iter_mut().filter(|slot| slot.shutting_down) {
+            slot.shutting_down = !slot.node.plugin_mut().poll_shutdown()?.is_done();
+        }
+        Ok(self.shutdown_status())
+    }
+
+    fn shutdown_status(&self) -> ShutdownStatus {
+        if self.slots.iter().any(|slot| slot.shutting_down) {
+            ShutdownStatus::Pending
+        } else {
+            ShutdownStatus::Done
+        }
+    }
+}
+
+// Swaps the per-port fields of the context with those of the child, once
+// before and once after it runs.
+fn swap_context(ctx: &mut PluginContext, slot: &mut Slot) {
+    std::mem::swap(&mut ctx.connected_inputs, &mut slot.connected_inputs);
+    std::mem::swap(&mut ctx.connected_outputs, &mut slot.connected_outputs);
+    std::mem::swap(&mut ctx.input_update_ticks, &mut slot.input_update_ticks);
+}
+
+/// Builds a [`CompositePlugin`]; see [`CompositePlugin::builder`].
+pub struct CompositeBuilder {
+    id: PluginId,
+    meta: PluginMeta,
+    nodes: Vec<(String, Box<dyn Node>)>,
+    connections: Vec<(String, String)>,
+    inputs: Vec<(Port, String)>,
+    outputs: Vec<(Port, String)>,
+}
+
+impl CompositeBuilder {
+    /// Adds a child named `name`, which must not contain a dot.
+    pub fn child<P: Plugin + 'static>(
+        mut self,
+        name: impl Into<String>,
+        plugin: P,
+        set_input: fn(&mut P, &str, f64),
+        get_output: fn(&P, &str) -> f64,
+    ) -> Self {
+        self.nodes
+            .push((name.into(), node(plugin, set_input, get_output)));
+        self
+    }
+
+    /// Feeds output `from` into input `to`, both `"child.port"`.
+    pub fn connect(mut self, from: &str, to: &str) -> Self {
+        self.connections.push((from.to_string(), to.to_string()));
+        self
+    }
+
+    /// Re-exports the child input `target` as input `port` of the composite.
+    pub fn input(mut self, port: Port, target: &str) -> Self {
+        self.inputs.push((port, target.to_string()));
+        self
+    }
+
+    /// Re-exports the child output `source` as output `port`.
+    pub fn output(mut self, port: Port, source: &str) -> Self {
+        self.outputs.push((port, source.to_string()));
+        self
+    }
+
+    /// Fails for unknown children or ports, inputs fed twice and cycles.
+    pub fn build(self) -> Result<CompositePlugin, PluginError> {
+        let mut graph = Graph::new(self.nodes, &self.connections)?;
+        let mut inputs = Vec::with_capacity(self.inputs.len());
+        let mut input_targets = Vec::with_capacity(self.inputs.len());
+        for (port, target) in self.inputs {
+            let endpoint = graph.input(&target)?;
+            graph.expose(&endpoint, false);
+            inputs.push(port);
+            input_targets.push(endpoint);
+        }
+        let mut outputs = Vec::with_capacity(self.outputs.len());
+        let mut output_sources = Vec::with_capacity(self.outputs.len());
+        for (port, source) in self.outputs {
+            let endpoint = graph.output(&source)?;
+            graph.expose(&endpoint, true);
+            outputs.push(port);
+            output_sources.push(endpoint);
+        }
+        Ok(CompositePlugin {
+            id: self.id,
+            meta: self.meta,
+            graph,
+            inputs,
+            input_targets,
+            outputs,
+            output_sources,
+        })
+    }
+}
+
+/// Child plugins wired into a processing chain that hosts load as a single
+/// plugin.
+pub struct CompositePlugin {
+    id: PluginId,
+    meta: PluginMeta,
+    graph: Graph,
+    inputs: Vec<Port>,
+    input_targets: Vec<Endpoint>,
+    outputs: Vec<Port>,
+    output_sources: Vec<Endpoint>,
+}
+
+impl CompositePlugin {
+    pub fn builder(id: PluginId, meta: PluginMeta) -> CompositeBuilder {
+        CompositeBuilder {
+            id,
+            meta,
+            nodes: Vec::new(),
+            connections: Vec::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Writes the child input behind input `name`. Unknown names are ignored.
+    pub fn set_input(&mut self, name: &str, value: f64) {
+        if let Some(index) = port_index(&self.inputs, name) {
+            self.graph.set_input(&self.input_targets[index], value);
+        }
+    }
+
+    /// Reads the child output behind output `name`. Unknown names read as
+    /// `0.0`.
+    pub fn get_output(&self, name: &str) -> f64 {
+        port_index(&self.outputs, name).map_or(0.0, |index| {
+            self.graph.get_output(&self.output_sources[index])
+        })
+    }
+
+    pub fn child(&self, name: &str) -> Option<&dyn Plugin> {
+        self.graph.plugin(name)
+    }
+
+    /// Child names in processing order.
+    pub fn children(&self) -> impl Iterator<Item = &str> {
+        self.graph.names()
+    }
+}
+
+// Moves a field of child `child` under `child.key` keys, along with the
+// fields its conditions and rules refer to and its action. Computed fields
+// are dropped unless the composite re-exports their output.
+fn nest_field(
+    composite: &CompositePlugin,
+    child: &str,
+    mut field: ConfigField,
+) -> Option<ConfigField> {
+    let nested = |key: &mut String| *key = format!("{child}.{key}");
+    nested(&mut field.key);
+    for condition in field.visible_if.iter_mut().chain(&mut field.enabled_if) {
+        nest_condition(child, condition);
+    }
+    for rule in &mut field.rules {
+        match rule {
+            ValidationRule::RequiresWhen { field, .. } => nested(field),
+            ValidationRule::LessThan { other_field }
+            | ValidationRule::GreaterThan { other_field } => nested(other_field),
+            _ => {}
+        }
+    }
+    match &mut field.field_type {
+        FieldType::Action { action_id, .. } => nested(action_id),
+        FieldType::Computed { source_output } => {
+            let source = composite
+                .graph
+                .output(&format!("{child}.{source_output}"))
+                .ok()?;
+            let index = composite.output_sources.iter().position(|s| *s == source)?;
+            *source_output = composite.outputs[index].id.0.clone();
+        }
+        FieldType::Group { fields, .. } => {
+            *fields = std::mem::take(fields)
+                .into_iter()
+                .filter_map(|field| nest_field(composite, child, field))
+                .collect();
+        }
+        _ => {}
+    }
+    Some(field)
+}
+
+fn nest_condition(child: &str, condition: &mut FieldCondition) {
+    match condition {
+        FieldCondition::Equals { field, .. }
+        | FieldCondition::NotEquals { field, .. }
+        | FieldCondition::OneOf { field, .. }
+        | FieldCondition::IsTrue { field } => *field = format!("{child}.{field}"),
+        FieldCondition::All { conditions } | FieldCondition::Any { conditions } => {
+            for condition in conditions {
+                nest_condition(child, condition);
+            }
+        }
+        FieldCondition::Not { condition } => nest_condition(child, condition),
+    }
+}
+
+// Splits a delta on `child.key` into the keys of each child.
+fn child_delta(changed: &ConfigDelta, child: &str) -> ConfigDelta {
+    ConfigDelta {
+        changes: changed
+            .iter()
+            .filter_map(|change| {
+                let key = change.key.strip_prefix(child)?.strip_prefix('.')?;
+                Some(ConfigChange {
+                    key: key.to_string(),
+                    ..change.clone()
+                })
+            })
+            .collect(),
+    }
+}
+
+impl Plugin for CompositePlugin {
+    fn id(&self) -> PluginId {
+        self.id
+    }
+
+    fn meta(&self) -> &PluginMeta {
+        &self.meta
+    }
+
+    fn inputs(&self) -> &[Port] {
+        &self.inputs
+    }
+
+    fn outputs(&self) -> &[Port] {
+        &self.outputs
+    }
+
+    fn process(&mut self, ctx: &mut PluginContext) -> Result<(), PluginError> {
+        for (index, target) in self.input_targets.iter().enumerate() {
+            self.graph.mark_updated(target, ctx.last_update_tick(index));
+        }
+        self.graph.process(ctx)
+    }
+
+    // One section per child with a schema, keyed by child name.
+    fn ui_schema(&self) -> Option<UISchema> {
+        let mut schema = UISchema::new();
+        for name in self.graph.names() {
+            let Some(child) = self.graph.plugin(name) else {
+                continue;
+            };
+            let Some(child_schema) = child.ui_schema() else {
+                continue;
+            };
+            let fields: Vec<_> = child_schema
+                .fields
+                .into_iter()
+                .filter_map(|field| nest_field(self, name, field))
+                .collect();
+            schema
+                .translations
+                .extend_prefixed(&format!("{name}."), &child_schema.translations);
+            schema = schema.section(name, child.meta().name.clone(), fields);
+        }
+        (!schema.fields.is_empty()).then_some(schema)
+    }
+
+    // As strict as the strictest child: realtime only if every child is,
+    // blocking or allocating if any child is, and every child's side effects.
+    fn behavior(&self) -> PluginBehavior {
+        let mut behavior = PluginBehavior {
+            side_effects: Some(Vec::new()),
+            latency_ticks: self.latency_ticks(),
+            ..PluginBehavior::default()
+        };
+        for name in self.graph.names() {
+            let Some(child) = self.graph.plugin(name).map(|child| child.behavior()) else {
+                continue;
+            };
+            behavior.realtime_safe &= child.realtime_safe;
+            behavior.may_block |= child.may_block;
+            behavior.allocates_in_process |= child.allocates_in_process;
+            if child.threading == ThreadingModel::Pinned {
+                behavior.threading = ThreadingModel::Pinned;
+            }
+            behavior.side_effects = match (behavior.side_effects, child.side_effects) {
+                (Some(mut effects), Some(more)) => {
+                    for effect in more {
+                        if !effects.contains(&effect) {
+                            effects.push(effect);
+                        }
+                    }
+                    Some(effects)
+                }
+                _ => None,
+            };
+        }
+        behavior
+    }
+
+    fn options_for(&self, key: &str) -> Vec<ChoiceOption> {
+        let Some((child, key)) = key.split_once('.') else {
+            return Vec::new();
+        };
+        self.graph
+            .plugin(child)
+            .map_or_else(Vec::new, |child| child.options_for(key))
+    }
+
+    fn on_action(&mut self, action_id: &str) -> Result<(), PluginError> {
+        let child = action_id
+            .split_once('.')
+            .and_then(|(child, action)| Some((self.graph.plugin_mut(child)?, action)));
+        match child {
+            Some((child, action)) => child.on_action(action),
+            None => Err(PluginError::Config(format!("unknown action {action_id}"))),
+        }
+    }
+
+    fn on_config_changed(&mut self, changed: &ConfigDelta) -> Result<(), PluginError> {
+        self.graph.for_each(|name, child| {
+            let delta = child_delta(changed, name);
+            if delta.is_empty() {
+                return Ok(());
+            }
+            child.on_config_changed(&delta)
+        })
+    }
+
+    fn save_state(&self) -> Result<Value, PluginError> {
+        let mut state = Map::new();
+        for name in self.graph.names() {
+            if let Some(child) = self.graph.plugin(name) {
+                state.insert(name.to_string(), child.save_state()?);
+            }
+        }
+        Ok(Value::Object(state))
+    }
+
+    fn restore_state(&mut self, state: Value) -> Result<(), PluginError> {
+        self.graph.for_each(|name, child| match state.get(name) {
+            Some(child_state) => child.restore_state(child_state.clone()),
+            None => Ok(()),
+        })
+    }
+
+    fn on_event(&mut self, event: &Event) -> Result<(), PluginError> {
+        self.graph.for_each(|_, child| child.on_event(event))
+    }
+
+    // Outputs are re-exported child ports, fixed when the composite is built.
+    fn on_output_added(&mut self, port: &str) -> Result<(), PluginError> {
+        Err(PluginError::Config(format!("cannot add output {port}")))
+    }
+
+    fn on_output_removed(&mut self, port: &str) -> Result<(), PluginError> {
+        Err(PluginError::Config(format!("cannot remove output {port}")))
+    }
+
+    // The slowest path from any input to any re-exported output.
+    fn latency_ticks(&self) -> u64 {
+        self.output_sources
+            .iter()
+            .map(|source| self.graph.latency_ticks(source.node))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn prepare(&mut self, sample_rate: f64, block_size: usize) -> Result<(), PluginError> {
+        self.graph
+            .for_each(|_, child| child.prepare(sample_rate, block_size))
+    }
+
+    fn on_overrun(&mut self, missed: u64) -> Result<(), PluginError> {
+        self.graph.for_each(|_, child| child.on_overrun(missed))
+    }
+
+    fn on_start(&mut self) -> Result<(), PluginError> {
+        self.graph.for_each(|_, child| child.on_start())
+    }
+
+    fn on_stop(&mut self) -> Result<(), PluginError> {
+        self.graph.for_each(|_, child| child.on_stop())
+    }
+
+    fn on_pause(&mut self) -> Result<(), PluginError> {
+        self.graph.for_each(|_, child| child.on_pause())
+    }
+
+    fn on_resume(&mut self) -> Result<(), PluginError> {
+        self.graph.for_each(|_, child| child.on_resume())
+    }
+
+    fn reset(&mut self) -> Result<(), PluginError> {
+        self.graph.for_each(|_, child| child.reset())
+    }
+
+    fn begin_shutdown(&mut self) -> Result<ShutdownStatus, PluginError> {
+        self.graph.begin_shutdown()
+    }
+
+    fn poll_shutdown(&mut self) -> Result<ShutdownStatus, PluginError> {
+        self.graph.poll_shutdown()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ports::StaticPorts;
+    use crate::testing::MockHost;
+    use crate::ui::SideEffect;
+
+    // Multiplies its input by `factor` and reports `latency` ticks.
+    struct Gain {
+        meta: PluginMeta,
+        ports: StaticPorts<1, 1>,
+        factor: f64,
+        latency: u64,
+    }
+
+    impl Gain {
+        fn new(factor: f64, latency: u64) -> Self {
+            Self {
+                meta: PluginMeta::new("gain"),
+                ports: StaticPorts::new(["in"], ["out"]),
+                factor,
+                latency,
+            }
+        }
+
+        fn set(&mut self, name: &str, value: f64) {
+            self.ports.set_input_by_name(name, value)
+        }
+
+        fn get(&self, name: &str) -> f64 {
+            self.ports.output_by_name(name)
+        }
+    }
+
+    impl Plugin for Gain {
+        fn id(&self) -> PluginId {
+            PluginId(0)
+        }
+        fn meta(&self) -> &PluginMeta {
+            &self.meta
+        }
+        fn inputs(&self) -> &[Port] {
+            self.ports.inputs()
+        }
+        fn outputs(&self) -> &[Port] {
+            self.ports.outputs()
+        }
+        fn process(&mut self, _ctx: &mut PluginContext) -> Result<(), PluginError> {
+            self.ports.set_output(0, self.ports.input(0) * self.factor);
+            Ok(())
+        }
+        fn on_config_changed(&mut self, changed: &ConfigDelta) -> Result<(), PluginError> {
+            if let Some(factor) = changed.get("factor").and_then(|c| c.new.as_ref()) {
+                self.factor = factor.as_f64().unwrap_or(self.factor);
+            }
+            Ok(())
+        }
+        fn save_state(&self) -> Result<Value, PluginError> {
+            Ok(Value::from(self.factor))
+        }
+        fn latency_ticks(&self) -> u64 {
+            self.latency
+        }
+        fn ui_schema(&self) -> Option<UISchema> {
+            Some(
+                UISchema::new()
+                    .field(ConfigField::boolean("enabled", "Enabled"))
+                    .field(
+                        ConfigField::float("factor", "Factor")
+                            .visible_if(FieldCondition::is_true("enabled")),
+                    )
+                    .field(ConfigField::computed("level", "Level", "out"))
+                    .field(ConfigField::action("zero", "Zero", "zero"))
+                    .translation("de", "factor.label", "Faktor"),
+            )
+        }
+        // Slow gains stand in for a child that logs to disk.
+        fn behavior(&self) -> PluginBehavior {
+            let slow = self.latency > 1;
+            PluginBehavior {
+                may_block: slow,
+                threading: if slow {
+                    ThreadingModel::Pinned
+                } else {
+                    ThreadingModel::Any
+                },
+                side_effects: Some(if slow {
+                    vec![SideEffect::Filesystem]
+                } else {
+                    Vec::new()
+                }),
+                ..PluginBehavior::default()
+            }
+        }
+        fn on_action(&mut self, action_id: &str) -> Result<(), PluginError> {
+            match action_id {
+                "zero" => {
+                    self.factor = 0.0;
+                    Ok(())
+                }
+                _ => Err(PluginError::Config(format!("unknown action {action_id}"))),
+            }
+        }
+    }
+
+    fn chain() -> CompositeBuilder {
+        // Added out of order on purpose: `second` depends on `first`.
+        CompositePlugin::builder(PluginId(1), PluginMeta::new("chain"))
+            .child("second", Gain::new(3.0, 1), Gain::set, Gain::get)
+            .child("first", Gain::new(2.0, 2), Gain::set, Gain::get)
+            .connect("first.out", "second.in")
+            .input(Port::new("in"), "first.in")
+            .output(Port::new("out"), "second.out")
+    }
+
+    #[test]
+    fn processes_children_in_dependency_order() {
+        let composite = chain().build().unwrap();
+        assert_eq!(
+            composite.children().collect::<Vec<_>>(),
+            ["first", "second"]
+        );
+        assert_eq!(composite.latency_ticks(), 3);
+
+        let mut host =
+            MockHost::new(composite).io(CompositePlugin::set_input, CompositePlugin::get_output);
+        host.set_input("in", 1.0);
+        assert_eq!(host.run(1).unwrap()[0].output("out"), Some(6.0));
+
+        let composite = host.plugin_mut();
+        let delta = ConfigDelta::between(
+            &serde_json::json!({}),
+            &serde_json::json!({ "second.factor": 10.0 }),
+        );
+        composite.on_config_changed(&delta).unwrap();
+        assert_eq!(
+            composite.save_state().unwrap(),
+            serde_json::json!({ "first": 2.0, "second": 10.0 })
+        );
+        assert_eq!(host.run(1).unwrap()[0].output("out"), Some(20.0));
+    }
+
+    #[test]
+    fn merges_child_schemas_and_behavior() {
+        let mut composite = chain().build().unwrap();
+        let schema = composite.ui_schema().unwrap();
+        let keys: Vec<_> = schema
+            .value_fields()
+            .iter()
+            .map(|f| f.key.as_str())
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                "first.enabled",
+                "first.factor",
+                "second.enabled",
+                "second.factor"
+            ]
+        );
+        assert!(matches!(
+            &schema.value_fields()[1].visible_if,
+            Some(FieldCondition::IsTrue { field }) if field == "first.enabled"
+        ));
+        // Only the output of `second` is re-exported.
+        let computed = schema.computed_fields();
+        assert_eq!(computed.len(), 1);
+        assert_eq!(computed[0].key, "second.level");
+        assert!(matches!(
+            &computed[0].field_type,
+            FieldType::Computed { source_output } if source_output == "out"
+        ));
+        assert_eq!(
+            schema.translations.get("de", "first.factor.label"),
+            Some("Faktor")
+        );
+
+        let behavior = composite.behavior();
+        assert!(behavior.may_block);
+        assert_eq!(behavior.threading, ThreadingModel::Pinned);
+        assert_eq!(behavior.side_effects, Some(vec![SideEffect::Filesystem]));
+        assert_eq!(behavior.latency_ticks, 3);
+
+        let FieldType::Action { action_id, .. } = &schema.action_fields()[0].field_type else {
+            unreachable!()
+        };
+        composite.on_action(action_id).unwrap();
+        assert_eq!(composite.save_state().unwrap()["first"], 0.0);
+        assert!(composite.on_action("third.zero").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_wiring() {
+        let error = |builder: CompositeBuilder| match builder.build() {
+            Ok(_) => panic!("wiring accepted"),
+            Err(err) => err.to_string(),
+        };
+        let cycle = error(chain().connect("second.out", "first.in"));
+        assert_eq!(
+            cycle,
+            "invalid configuration: connections form a cycle through second"
+        );
+        let twice = error(chain().connect("second.out", "second.in"));
+        assert!(twice.contains("input second.in is connected twice"));
+        let unknown = error(chain().connect("first.out", "third.in"));
+        assert!(unknown.contains("unknown child third"));
+        let port = error(chain().output(Port::new("x"), "first.in"));
+        assert!(port.contains("first has no output in"));
+    }
+}
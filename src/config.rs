@@ -0,0 +1,270 @@
+use crate::ui::UISchema;
+use crate::PluginError;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::VecDeque;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "derive")]
+pub use rtsyn_plugin_derive::UIConfig;
+
+/// A typed configuration struct with a matching UI schema.
+///
+/// Usually derived with `#[derive(UIConfig)]` so the schema and the struct
+/// cannot drift apart.
+pub trait UIConfig: Sized {
+    fn ui_schema() -> UISchema;
+    fn from_config(value: &Value) -> Result<Self, PluginError>;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigChange {
+    pub key: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+/// Top-level keys that differ between two configuration objects.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConfigDelta {
+    pub changes: Vec<ConfigChange>,
+}
+
+impl ConfigDelta {
+    pub fn between(old: &Value, new: &Value) -> Self {
+        let empty = Map::new();
+        let old = old.as_object().unwrap_or(&empty);
+        let new = new.as_object().unwrap_or(&empty);
+
+        let mut changes = Vec::new();
+        for (key, new_value) in new {
+            let old_value = old.get(key);
+            if old_value != Some(new_value) {
+                changes.push(ConfigChange {
+                    key: key.clone(),
+                    old: old_value.cloned(),
+                    new: Some(new_value.clone()),
+                });
+            }
+        }
+        for (key, old_value) in old {
+            if !new.contains_key(key) {
+                changes.push(ConfigChange {
+                    key: key.clone(),
+                    old: Some(old_value.clone()),
+                    new: None,
+                });
+            }
+        }
+        Self { changes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn changed(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&ConfigChange> {
+        self.changes.iter().find(|c| c.key == key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ConfigChange> {
+        self.changes.iter()
+    }
+}
+
+/// Holds the current configuration and turns incoming `set_config_json`
+/// payloads into a [`ConfigDelta`].
+///
+/// Payloads are merged key by key, matching how hosts send partial updates.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigTracker {
+    current: Map<String, Value>,
+}
+
+impl ConfigTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn current(&self) -> &Map<String, Value> {
+        &self.current
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.current.get(key)
+    }
+
+    pub fn apply(&mut self, update: &Value) -> ConfigDelta {
+        let mut changes = Vec::new();
+        if let Some(update) = update.as_object() {
+            for (key, value) in update {
+                let old = self.current.insert(key.clone(), value.clone());
+                if old.as_ref() != Some(value) {
+                    changes.push(ConfigChange {
+                        key: key.clone(),
+                        old,
+                        new: Some(value.clone()),
+                    });
+                }
+            }
+        }
+        ConfigDelta { changes }
+    }
+}
+
+/// Name of the standard action returning the config history, see
+/// [`ConfigHistory::action`].
+pub const CONFIG_HISTORY_ACTION: &str = "config_history";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigSnapshot {
+    /// Milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    pub config: Value,
+}
+
+/// The last `capacity` applied configurations with timestamps.
+///
+/// Opt-in: a plugin records into it whenever it applies a config, stores it
+/// under [`ConfigHistory::STATE_KEY`] of its saved state, and answers
+/// [`CONFIG_HISTORY_ACTION`] with [`ConfigHistory::action`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConfigHistory {
+    capacity: usize,
+    entries: VecDeque<ConfigSnapshot>,
+}
+
+impl ConfigHistory {
+    pub const STATE_KEY: &'static str = "config_history";
+
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Records `config` unless it equals the most recent entry.
+    pub fn record(&mut self, config: &Value, timestamp_ms: u64) {
+        if self.capacity == 0 || self.latest().is_some_and(|last| &last.config == config) {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(ConfigSnapshot {
+            timestamp_ms,
+            config: config.clone(),
+        });
+    }
+
+    pub fn record_now(&mut self, config: &Value) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        self.record(config, now);
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &ConfigSnapshot> {
+        self.entries.iter()
+    }
+
+    pub fn latest(&self) -> Option<&ConfigSnapshot> {
+        self.entries.back()
+    }
+
+    /// The configuration that was in effect at `timestamp_ms`.
+    pub fn at(&self, timestamp_ms: u64) -> Option<&ConfigSnapshot> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| entry.timestamp_ms <= timestamp_ms)
+    }
+
+    /// Stores the history in `state`, which must be an object or null.
+    pub fn save_into(&self, state: &mut Value) {
+        if state.is_null() {
+            *state = Value::Object(Map::new());
+        }
+        if let Some(state) = state.as_object_mut() {
+            state.insert(
+                Self::STATE_KEY.to_string(),
+                serde_json::to_value(self).unwrap_or(Value::Null),
+            );
+        }
+    }
+
+    /// Reads a history stored by [`ConfigHistory::save_into`], or `None` if
+    /// `state` does not hold one.
+    pub fn restore_from(state: &Value) -> Option<Self> {
+        serde_json::from_value(state.get(Self::STATE_KEY)?.clone()).ok()
+    }
+
+    /// Result of [`CONFIG_HISTORY_ACTION`]: every entry, or only the one in
+    /// effect at `{"at_ms": ...}` when given.
+    pub fn action(&self, args: &Value) -> Value {
+        match args.get("at_ms").and_then(Value::as_u64) {
+            Some(at) => serde_json::to_value(self.at(at)).unwrap_or(Value::Null),
+            None => serde_json::to_value(&self.entries).unwrap_or(Value::Null),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn delta_between_reports_added_changed_removed() {
+        let old = json!({ "path": "a.csv", "rate": 10, "gone": true });
+        let new = json!({ "path": "b.csv", "rate": 10, "fresh": 1 });
+        let delta = ConfigDelta::between(&old, &new);
+
+        assert_eq!(delta.changes.len(), 3);
+        assert!(delta.changed("path"));
+        assert!(!delta.changed("rate"));
+        assert_eq!(delta.get("fresh").unwrap().old, None);
+        assert_eq!(delta.get("gone").unwrap().new, None);
+        assert_eq!(delta.get("path").unwrap().old, Some(json!("a.csv")));
+    }
+
+    #[test]
+    fn tracker_merges_partial_updates() {
+        let mut tracker = ConfigTracker::new();
+        let first = tracker.apply(&json!({ "path": "a.csv", "rate": 10 }));
+        assert_eq!(first.changes.len(), 2);
+
+        let second = tracker.apply(&json!({ "rate": 10 }));
+        assert!(second.is_empty());
+
+        let third = tracker.apply(&json!({ "path": "b.csv" }));
+        assert!(third.changed("path"));
+        assert_eq!(tracker.get("rate"), Some(&json!(10)));
+    }
+
+    #[test]
+    fn history_keeps_last_configs() {
+        let mut history = ConfigHistory::new(2);
+        history.record(&json!({ "gain": 1 }), 1_000);
+        history.record(&json!({ "gain": 1 }), 2_000);
+        history.record(&json!({ "gain": 2 }), 3_000);
+        history.record(&json!({ "gain": 3 }), 4_000);
+
+        assert_eq!(history.entries().count(), 2);
+        assert_eq!(history.at(3_500).unwrap().config, json!({ "gain": 2 }));
+        assert!(history.at(2_000).is_none());
+        assert_eq!(
+            history.action(&json!({ "at_ms": 4_000 }))["config"],
+            json!({ "gain": 3 })
+        );
+
+        let mut state = Value::Null;
+        history.save_into(&mut state);
+        assert_eq!(ConfigHistory::restore_from(&state), Some(history));
+    }
+}
@@ -0,0 +1,247 @@
+use crate::ui::UISchema;
+use serde_json::Value;
+use std::fmt;
+use std::path::Path;
+
+/// 1-based position in a config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    #[cfg(feature = "toml")]
+    fn from_offset(source: &str, offset: usize) -> Self {
+        let before = &source[..offset.min(source.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        Self { line, column }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// A single schema violation found in a config file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    pub key: String,
+    pub message: String,
+    pub location: Option<Location>,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.location {
+            Some(location) => write!(f, "{location}: {}: {}", self.key, self.message),
+            None => write!(f, "{}: {}", self.key, self.message),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigFileError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("unsupported config file extension: {0}")]
+    UnsupportedFormat(String),
+    #[error("{}syntax error: {message}", location.map(|l| format!("{l}: ")).unwrap_or_default())]
+    Syntax {
+        message: String,
+        location: Option<Location>,
+    },
+    #[error("{}", issues.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n"))]
+    Invalid { issues: Vec<ConfigIssue> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    #[cfg(feature = "toml")]
+    Toml,
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            #[cfg(feature = "toml")]
+            "toml" => Some(Format::Toml),
+            #[cfg(feature = "yaml")]
+            "yaml" | "yml" => Some(Format::Yaml),
+            _ => None,
+        }
+    }
+
+    fn key_separator(self) -> char {
+        match self {
+            #[cfg(feature = "toml")]
+            Format::Toml => '=',
+            #[cfg(feature = "yaml")]
+            Format::Yaml => ':',
+        }
+    }
+}
+
+/// Reads a config file, picking the format from its extension.
+pub fn load(path: impl AsRef<Path>, schema: &UISchema) -> Result<Value, ConfigFileError> {
+    let path = path.as_ref();
+    let format = Format::from_path(path)
+        .ok_or_else(|| ConfigFileError::UnsupportedFormat(path.display().to_string()))?;
+    let source = std::fs::read_to_string(path)?;
+    from_str(&source, format, schema)
+}
+
+/// Parses `source` into a config object and checks it against `schema`.
+///
+/// Every schema violation is reported, not just the first one.
+pub fn from_str(source: &str, format: Format, schema: &UISchema) -> Result<Value, ConfigFileError> {
+    let config = parse(source, format)?;
+    let errors = schema.validate(&config);
+    if errors.is_empty() {
+        return Ok(config);
+    }
+    let issues = errors
+        .into_iter()
+        .map(|error| ConfigIssue {
+            location: locate_key(source, &error.key, format.key_separator()),
+            key: error.key,
+            message: error.message,
+        })
+        .collect();
+    Err(ConfigFileError::Invalid { issues })
+}
+
+#[cfg(feature = "toml")]
+pub fn from_toml_str(source: &str, schema: &UISchema) -> Result<Value, ConfigFileError> {
+    from_str(source, Format::Toml, schema)
+}
+
+#[cfg(feature = "yaml")]
+pub fn from_yaml_str(source: &str, schema: &UISchema) -> Result<Value, ConfigFileError> {
+    from_str(source, Format::Yaml, schema)
+}
+
+fn parse(source: &str, format: Format) -> Result<Value, ConfigFileError> {
+    let config = match format {
+        #[cfg(feature = "toml")]
+        Format::Toml => {
+            let table: toml::Table =
+                toml::from_str(source).map_err(|e| ConfigFileError::Syntax {
+                    message: e.message().to_string(),
+                    location: e
+                        .span()
+                        .map(|span| Location::from_offset(source, span.start)),
+                })?;
+            serde_json::to_value(table).map_err(|e| ConfigFileError::Syntax {
+                message: e.to_string(),
+                location: None,
+            })?
+        }
+        #[cfg(feature = "yaml")]
+        Format::Yaml => {
+            let value: Value =
+                serde_yaml::from_str(source).map_err(|e| ConfigFileError::Syntax {
+                    message: e.to_string(),
+                    location: e.location().map(|l| Location {
+                        line: l.line(),
+                        column: l.column(),
+                    }),
+                })?;
+            // An empty YAML document is an empty config
+            if value.is_null() {
+                Value::Object(Default::default())
+            } else {
+                value
+            }
+        }
+    };
+    if !config.is_object() {
+        return Err(ConfigFileError::Syntax {
+            message: "expected a mapping of config keys at the top level".to_string(),
+            location: Some(Location { line: 1, column: 1 }),
+        });
+    }
+    Ok(config)
+}
+
+/// Finds the line that assigns a top-level `key`.
+fn locate_key(source: &str, key: &str, separator: char) -> Option<Location> {
+    source.lines().enumerate().find_map(|(index, line)| {
+        let rest = line.strip_prefix(key)?;
+        rest.trim_start()
+            .starts_with(separator)
+            .then_some(Location {
+                line: index + 1,
+                column: 1,
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::ConfigField;
+    use serde_json::json;
+
+    fn schema() -> UISchema {
+        UISchema::new()
+            .field(ConfigField::integer("count", "Count").min(0).max(10))
+            .field(ConfigField::float("rate", "Rate").required())
+            .field(
+                ConfigField::choice("mode", "Mode")
+                    .option("a", "A")
+                    .option("b", "B"),
+            )
+            .field(ConfigField::dynamic_list("columns", "Columns"))
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_round_trip_and_locations() {
+        let config =
+            from_toml_str("count = 3\nrate = 1000\ncolumns = [\"x\"]\n", &schema()).unwrap();
+        assert_eq!(
+            config,
+            json!({ "count": 3, "rate": 1000, "columns": ["x"] })
+        );
+
+        let err = from_toml_str("rate = 1.0\n\ncount = 42\n", &schema()).unwrap_err();
+        assert_eq!(err.to_string(), "3:1: count: 42 is out of range 0..=10");
+
+        let err = from_toml_str("rate = \n", &schema()).unwrap_err();
+        assert!(matches!(
+            err,
+            ConfigFileError::Syntax {
+                location: Some(Location { line: 1, .. }),
+                ..
+            }
+        ));
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_round_trip_and_locations() {
+        let config = from_yaml_str("rate: 2.5\nmode: b\ncolumns:\n  - x\n", &schema()).unwrap();
+        assert_eq!(
+            config,
+            json!({ "rate": 2.5, "mode": "b", "columns": ["x"] })
+        );
+
+        let err = from_yaml_str("rate: 1.0\nmode: z\n", &schema()).unwrap_err();
+        assert_eq!(err.to_string(), "2:1: mode: expected one of a, b");
+
+        let err = from_yaml_str("rate: [1\n", &schema()).unwrap_err();
+        assert!(matches!(
+            err,
+            ConfigFileError::Syntax {
+                location: Some(_),
+                ..
+            }
+        ));
+    }
+}
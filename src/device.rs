@@ -0,0 +1,320 @@
+//! Device discovery and recovery for [`DeviceDriver`]s, so hosts can offer a picker of
+//! serial ports, DAQ cards or cameras instead of a free-text path field.
+//!
+//! Across the FFI boundary drivers route [`DEVICES_LIST_ACTION`] and
+//! [`DEVICES_SELECT_ACTION`] to [`device_action`] from `Plugin::action`.
+//! Devices that drop mid-run are recovered with [`reconnect`].
+
+use crate::ui::ConfigField;
+use crate::{DeviceDriver, PluginError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Action returning the [`DeviceInfo`] list of `DeviceDriver::enumerate`.
+pub const DEVICES_LIST_ACTION: &str = "devices_list";
+/// Action calling `DeviceDriver::select` with a [`DeviceInfo`] as arguments.
+pub const DEVICES_SELECT_ACTION: &str = "devices_select";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceKind {
+    Serial,
+    Daq,
+    Camera,
+    Audio,
+    Network,
+    #[default]
+    #[serde(other)]
+    Other,
+}
+
+/// A device found by `DeviceDriver::enumerate`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceInfo {
+    /// Stable address the driver can reopen, e.g. `/dev/ttyUSB0` or a
+    /// serial number.
+    pub id: String,
+    /// Human-readable name for the picker.
+    pub name: String,
+    #[serde(default)]
+    pub kind: DeviceKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vendor: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serial_number: Option<String>,
+    /// Driver-specific details, e.g. channel count or supported rates.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, Value>,
+}
+
+impl DeviceInfo {
+    pub fn new(id: impl Into<String>, name: impl Into<String>, kind: DeviceKind) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            kind,
+            vendor: None,
+            serial_number: None,
+            properties: BTreeMap::new(),
+        }
+    }
+
+    pub fn vendor(mut self, vendor: impl Into<String>) -> Self {
+        self.vendor = Some(vendor.into());
+        self
+    }
+
+    pub fn serial_number(mut self, serial_number: impl Into<String>) -> Self {
+        self.serial_number = Some(serial_number.into());
+        self
+    }
+
+    pub fn property(mut self, key: impl Into<String>, value: Value) -> Self {
+        self.properties.insert(key.into(), value);
+        self
+    }
+}
+
+/// Retries of `DeviceDriver::open` after the device was lost. The delay
+/// before each retry starts at `backoff` and doubles up to `max_backoff`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    pub retries: u32,
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 5,
+            backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Gives up on the first failure.
+    pub fn never() -> Self {
+        Self {
+            retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before retry `attempt`, counted from 0, or `None` once the
+    /// retries are used up.
+    pub fn delay(&self, attempt: u32) -> Option<Duration> {
+        if attempt >= self.retries {
+            return None;
+        }
+        let factor = 1u32.checked_shl(attempt).unwrap_or(u32::MAX);
+        Some(self.backoff.saturating_mul(factor).min(self.max_backoff))
+    }
+}
+
+/// Recovers a driver whose device was lost: notifies it, then retries `open`
+/// according to its `reconnect_policy`, calling `sleep` between attempts.
+/// Blocks, so hosts run it off the realtime thread.
+pub fn reconnect<D: DeviceDriver + ?Sized>(
+    driver: &mut D,
+    mut sleep: impl FnMut(Duration),
+) -> Result<(), PluginError> {
+    driver.on_device_lost()?;
+    let policy = driver.reconnect_policy();
+    let mut attempt = 0;
+    loop {
+        match driver.open() {
+            Ok(()) => return driver.on_device_available(),
+            Err(err) => match policy.delay(attempt) {
+                Some(delay) => sleep(delay),
+                None => return Err(err),
+            },
+        }
+        attempt += 1;
+    }
+}
+
+pub(crate) fn selection_unsupported() -> PluginError {
+    PluginError::Config("device selection is not supported".to_string())
+}
+
+/// Choice field listing `devices` by name, storing the device id. The first
+/// device is the default.
+pub fn device_choice_field(
+    key: impl Into<String>,
+    label: impl Into<String>,
+    devices: &[DeviceInfo],
+) -> ConfigField {
+    let field = devices
+        .iter()
+        .fold(ConfigField::choice(key, label), |field, device| {
+            field.option(device.id.clone(), device.name.clone())
+        });
+    match devices.first() {
+        Some(device) => field.default_value(Value::String(device.id.clone())),
+        None => field,
+    }
+}
+
+/// Answers the device actions for `driver`, or `None` for any other action.
+pub fn device_action<D: DeviceDriver + ?Sized>(
+    driver: &mut D,
+    name: &str,
+    args: &Value,
+) -> Option<Result<Value, PluginError>> {
+    match name {
+        DEVICES_LIST_ACTION => Some(
+            serde_json::to_value(driver.enumerate()).map_err(|e| PluginError::Fatal(e.to_string())),
+        ),
+        DEVICES_SELECT_ACTION => Some(
+            serde_json::from_value::<DeviceInfo>(args.clone())
+                .map_err(|e| PluginError::Config(format!("invalid device: {e}")))
+                .and_then(|device| driver.select(&device))
+                .map(|()| Value::Null),
+        ),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Plugin, PluginContext, PluginId, PluginMeta, Port};
+    use serde_json::json;
+
+    struct SerialDriver {
+        meta: PluginMeta,
+        selected: Option<String>,
+        // Opens fail while this is non-zero.
+        unplugged_for: u32,
+        events: Vec<&'static str>,
+    }
+
+    impl SerialDriver {
+        fn new() -> Self {
+            Self {
+                meta: PluginMeta::new("serial"),
+                selected: None,
+                unplugged_for: 0,
+                events: Vec::new(),
+            }
+        }
+    }
+
+    impl Plugin for SerialDriver {
+        fn id(&self) -> PluginId {
+            PluginId(1)
+        }
+        fn meta(&self) -> &PluginMeta {
+            &self.meta
+        }
+        fn inputs(&self) -> &[Port] {
+            &[]
+        }
+        fn outputs(&self) -> &[Port] {
+            &[]
+        }
+        fn process(&mut self, _ctx: &mut PluginContext) -> Result<(), PluginError> {
+            Ok(())
+        }
+        fn action(&mut self, name: &str, args: &Value) -> Result<Option<Value>, PluginError> {
+            device_action(self, name, args).transpose()
+        }
+    }
+
+    impl DeviceDriver for SerialDriver {
+        fn open(&mut self) -> Result<(), PluginError> {
+            self.events.push("open");
+            if self.unplugged_for > 0 {
+                self.unplugged_for -= 1;
+                return Err(PluginError::Device {
+                    code: 19,
+                    message: "no such device".to_string(),
+                });
+            }
+            Ok(())
+        }
+        fn close(&mut self) -> Result<(), PluginError> {
+            Ok(())
+        }
+        fn enumerate(&self) -> Vec<DeviceInfo> {
+            vec![
+                DeviceInfo::new("/dev/ttyUSB0", "FTDI USB serial", DeviceKind::Serial)
+                    .vendor("FTDI")
+                    .property("baud_rates", json!([9600, 115200])),
+                DeviceInfo::new("/dev/ttyACM0", "Arduino Uno", DeviceKind::Serial),
+            ]
+        }
+        fn select(&mut self, device: &DeviceInfo) -> Result<(), PluginError> {
+            self.selected = Some(device.id.clone());
+            Ok(())
+        }
+        fn on_device_lost(&mut self) -> Result<(), PluginError> {
+            self.events.push("lost");
+            Ok(())
+        }
+        fn on_device_available(&mut self) -> Result<(), PluginError> {
+            self.events.push("available");
+            Ok(())
+        }
+        fn reconnect_policy(&self) -> ReconnectPolicy {
+            ReconnectPolicy {
+                retries: 3,
+                backoff: Duration::from_millis(100),
+                max_backoff: Duration::from_millis(300),
+            }
+        }
+    }
+
+    #[test]
+    fn devices_cross_as_actions() {
+        let mut driver = SerialDriver::new();
+        let list = driver
+            .action(DEVICES_LIST_ACTION, &Value::Null)
+            .unwrap()
+            .unwrap();
+        assert_eq!(list[0]["kind"], "serial");
+        assert_eq!(list[1].get("vendor"), None);
+
+        driver.action(DEVICES_SELECT_ACTION, &list[1]).unwrap();
+        assert_eq!(driver.selected.as_deref(), Some("/dev/ttyACM0"));
+        assert!(driver
+            .action(DEVICES_SELECT_ACTION, &json!({ "id": 3 }))
+            .is_err());
+    }
+
+    #[test]
+    fn reconnect_retries_with_backoff() {
+        let mut driver = SerialDriver::new();
+        driver.unplugged_for = 3;
+        let mut delays = Vec::new();
+        reconnect(&mut driver, |delay| delays.push(delay.as_millis())).unwrap();
+        assert_eq!(delays, [100, 200, 300]);
+        assert_eq!(
+            driver.events,
+            ["lost", "open", "open", "open", "open", "available"]
+        );
+
+        driver.unplugged_for = 10;
+        driver.events.clear();
+        assert!(reconnect(&mut driver, |_| {}).is_err());
+        assert_eq!(driver.events.len(), 5);
+        assert_eq!(ReconnectPolicy::never().delay(0), None);
+    }
+
+    #[test]
+    fn choice_field_lists_devices() {
+        let devices = [
+            DeviceInfo::new("cam0", "Front camera", DeviceKind::Camera),
+            DeviceInfo::new("cam1", "Rear camera", DeviceKind::Camera),
+        ];
+        let field = device_choice_field("device", "Device", &devices);
+        assert_eq!(field.default, Some(json!("cam0")));
+        let json = serde_json::to_value(&field).unwrap();
+        assert_eq!(json["type"]["options"][1]["label"], "Rear camera");
+    }
+}
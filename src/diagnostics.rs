@@ -0,0 +1,380 @@
+//! Standard diagnostic measurements for device drivers, e.g. the electrode
+//! impedance check of a bio-amplifier.
+//!
+//! Drivers implement [`DiagnosticMeasurement`] and route
+//! [`DIAGNOSTICS_LIST_ACTION`] and [`DIAGNOSTICS_RUN_ACTION`] to
+//! [`diagnostics_action`] from `Plugin::action`, so every vendor's check
+//! shows up in the same host screen, including across the FFI boundary.
+
+use crate::ui::{ConfigField, UISchema};
+use crate::{DeviceDriver, PluginError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Action returning the [`MeasurableQuantity`] list as JSON.
+pub const DIAGNOSTICS_LIST_ACTION: &str = "diagnostics_list";
+/// Action running `{"quantity": id}` and returning the [`MeasurementReport`].
+pub const DIAGNOSTICS_RUN_ACTION: &str = "diagnostics_run";
+/// Prefix of the schema keys [`UISchema::diagnostics_section`] generates.
+pub const DIAGNOSTICS_KEY_PREFIX: &str = "diagnostics:";
+
+/// Something a driver can measure per channel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MeasurableQuantity {
+    pub id: String,
+    pub label: String,
+    pub unit: String,
+    /// Values up to this are [`ChannelStatus::Good`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub good_below: Option<f64>,
+    /// Values above this are [`ChannelStatus::Bad`]; in between is marginal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bad_above: Option<f64>,
+}
+
+impl MeasurableQuantity {
+    pub fn new(id: impl Into<String>, label: impl Into<String>, unit: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            label: label.into(),
+            unit: unit.into(),
+            good_below: None,
+            bad_above: None,
+        }
+    }
+
+    /// Electrode impedance in kΩ, good up to 10 and bad above 50.
+    pub fn impedance() -> Self {
+        Self::new("impedance", "Impedance", "kOhm").thresholds(10.0, 50.0)
+    }
+
+    /// Electrode DC offset in mV.
+    pub fn dc_offset() -> Self {
+        Self::new("dc_offset", "DC offset", "mV")
+    }
+
+    /// Channel noise in µV RMS.
+    pub fn noise() -> Self {
+        Self::new("noise", "Noise", "uV")
+    }
+
+    pub fn thresholds(mut self, good_below: f64, bad_above: f64) -> Self {
+        self.good_below = Some(good_below);
+        self.bad_above = Some(bad_above);
+        self
+    }
+
+    /// Status of `value` by the thresholds; without them every value is good.
+    pub fn classify(&self, value: f64) -> ChannelStatus {
+        if !value.is_finite() {
+            ChannelStatus::Failed
+        } else if self.bad_above.is_some_and(|bad| value > bad) {
+            ChannelStatus::Bad
+        } else if self.good_below.is_some_and(|good| value > good) {
+            ChannelStatus::Marginal
+        } else {
+            ChannelStatus::Good
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChannelStatus {
+    Good,
+    Marginal,
+    Bad,
+    /// The channel could not be measured, e.g. a lead is off.
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelResult {
+    pub channel: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    pub status: ChannelStatus,
+}
+
+/// Per-channel results of one measurement run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MeasurementReport {
+    pub quantity: String,
+    pub unit: String,
+    pub channels: Vec<ChannelResult>,
+}
+
+impl MeasurementReport {
+    pub fn new(quantity: &MeasurableQuantity) -> Self {
+        Self {
+            quantity: quantity.id.clone(),
+            unit: quantity.unit.clone(),
+            channels: Vec::new(),
+        }
+    }
+
+    /// Adds a measured value, classified by the thresholds of `quantity`.
+    pub fn measured(
+        mut self,
+        quantity: &MeasurableQuantity,
+        channel: impl Into<String>,
+        value: f64,
+    ) -> Self {
+        self.channels.push(ChannelResult {
+            channel: channel.into(),
+            value: Some(value),
+            status: quantity.classify(value),
+        });
+        self
+    }
+
+    pub fn failed(mut self, channel: impl Into<String>) -> Self {
+        self.channels.push(ChannelResult {
+            channel: channel.into(),
+            value: None,
+            status: ChannelStatus::Failed,
+        });
+        self
+    }
+
+    /// Whether every channel is [`ChannelStatus::Good`].
+    pub fn all_good(&self) -> bool {
+        self.channels
+            .iter()
+            .all(|channel| channel.status == ChannelStatus::Good)
+    }
+}
+
+/// Progress reported while a measurement runs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeasurementProgress<'a> {
+    /// Between 0 and 1.
+    pub fraction: f64,
+    /// Channel being measured, if the driver goes channel by channel.
+    pub channel: Option<&'a str>,
+}
+
+/// Diagnostic checks of a device, run outside normal acquisition.
+pub trait DiagnosticMeasurement: DeviceDriver {
+    fn measurable_quantities(&self) -> Vec<MeasurableQuantity>;
+
+    /// Runs the measurement of `quantity`, calling `progress` as it goes.
+    /// When `progress` returns `false` the driver stops and returns
+    /// [`measurement_cancelled`].
+    fn run_measurement(
+        &mut self,
+        quantity: &str,
+        progress: &mut dyn FnMut(MeasurementProgress) -> bool,
+    ) -> Result<MeasurementReport, PluginError>;
+}
+
+pub fn measurement_cancelled() -> PluginError {
+    PluginError::Recoverable("measurement cancelled".to_string())
+}
+
+/// Answers the diagnostics actions for `driver`, or `None` for any other
+/// action. Runs without progress reporting.
+pub fn diagnostics_action<D: DiagnosticMeasurement + ?Sized>(
+    driver: &mut D,
+    name: &str,
+    args: &Value,
+) -> Option<Result<Value, PluginError>> {
+    match name {
+        DIAGNOSTICS_LIST_ACTION => Some(
+            serde_json::to_value(driver.measurable_quantities())
+                .map_err(|e| PluginError::Fatal(e.to_string())),
+        ),
+        DIAGNOSTICS_RUN_ACTION => {
+            let Some(quantity) = args.get("quantity").and_then(Value::as_str) else {
+                return Some(Err(PluginError::Config(
+                    "diagnostics_run needs a quantity".to_string(),
+                )));
+            };
+            Some(
+                driver
+                    .run_measurement(quantity, &mut |_| true)
+                    .and_then(|report| {
+                        serde_json::to_value(report).map_err(|e| PluginError::Fatal(e.to_string()))
+                    }),
+            )
+        }
+        _ => None,
+    }
+}
+
+impl UISchema {
+    /// Adds a collapsed `diagnostics` group with a quantity selector and,
+    /// for quantities with thresholds, an editable limit
+    /// (`diagnostics:<id>_limit`) defaulting to `bad_above`.
+    pub fn diagnostics_section(mut self, quantities: &[MeasurableQuantity]) -> Self {
+        if quantities.is_empty() {
+            return self;
+        }
+        let selector = quantities.iter().fold(
+            ConfigField::choice(format!("{DIAGNOSTICS_KEY_PREFIX}quantity"), "Measurement")
+                .default_value(Value::String(quantities[0].id.clone())),
+            |field, quantity| field.option(quantity.id.clone(), quantity.label.clone()),
+        );
+        let limits = quantities.iter().filter_map(|quantity| {
+            let bad = quantity.bad_above?;
+            Some(
+                ConfigField::float(
+                    format!("{DIAGNOSTICS_KEY_PREFIX}{}_limit", quantity.id),
+                    format!("{} limit", quantity.label),
+                )
+                .min_f(0.0)
+                .default_value(bad.into())
+                .hint(quantity.unit.clone()),
+            )
+        });
+        let group = std::iter::once(selector).chain(limits).fold(
+            ConfigField::group("diagnostics", "Diagnostics").collapsed(),
+            ConfigField::child,
+        );
+        self.fields.push(group);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Plugin, PluginContext, PluginId, PluginMeta, Port};
+    use serde_json::json;
+
+    struct Amplifier {
+        meta: PluginMeta,
+        impedances: Vec<Option<f64>>,
+    }
+
+    impl Plugin for Amplifier {
+        fn id(&self) -> PluginId {
+            PluginId(1)
+        }
+        fn meta(&self) -> &PluginMeta {
+            &self.meta
+        }
+        fn inputs(&self) -> &[Port] {
+            &[]
+        }
+        fn outputs(&self) -> &[Port] {
+            &[]
+        }
+        fn process(&mut self, _ctx: &mut PluginContext) -> Result<(), PluginError> {
+            Ok(())
+        }
+        fn action(&mut self, name: &str, args: &Value) -> Result<Option<Value>, PluginError> {
+            diagnostics_action(self, name, args).transpose()
+        }
+    }
+
+    impl DeviceDriver for Amplifier {
+        fn open(&mut self) -> Result<(), PluginError> {
+            Ok(())
+        }
+        fn close(&mut self) -> Result<(), PluginError> {
+            Ok(())
+        }
+    }
+
+    impl DiagnosticMeasurement for Amplifier {
+        fn measurable_quantities(&self) -> Vec<MeasurableQuantity> {
+            vec![MeasurableQuantity::impedance()]
+        }
+
+        fn run_measurement(
+            &mut self,
+            quantity: &str,
+            progress: &mut dyn FnMut(MeasurementProgress) -> bool,
+        ) -> Result<MeasurementReport, PluginError> {
+            let quantity = self
+                .measurable_quantities()
+                .into_iter()
+                .find(|q| q.id == quantity)
+                .ok_or_else(|| PluginError::Config(format!("unknown quantity {quantity}")))?;
+            let mut report = MeasurementReport::new(&quantity);
+            let count = self.impedances.len();
+            for (index, value) in self.impedances.iter().enumerate() {
+                let channel = format!("ch{index}");
+                if !progress(MeasurementProgress {
+                    fraction: index as f64 / count as f64,
+                    channel: Some(&channel),
+                }) {
+                    return Err(measurement_cancelled());
+                }
+                report = match value {
+                    Some(value) => report.measured(&quantity, channel, *value),
+                    None => report.failed(channel),
+                };
+            }
+            Ok(report)
+        }
+    }
+
+    fn amplifier() -> Amplifier {
+        Amplifier {
+            meta: PluginMeta::new("amp"),
+            impedances: vec![Some(4.0), Some(20.0), Some(80.0), None],
+        }
+    }
+
+    #[test]
+    fn measurement_classifies_channels() {
+        let mut amp = amplifier();
+        let mut seen = Vec::new();
+        let report = amp
+            .run_measurement("impedance", &mut |progress| {
+                seen.push(progress.channel.unwrap().to_string());
+                true
+            })
+            .unwrap();
+        assert_eq!(seen, ["ch0", "ch1", "ch2", "ch3"]);
+        let statuses: Vec<_> = report.channels.iter().map(|c| c.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                ChannelStatus::Good,
+                ChannelStatus::Marginal,
+                ChannelStatus::Bad,
+                ChannelStatus::Failed
+            ]
+        );
+        assert!(!report.all_good());
+
+        let cancelled = amp.run_measurement("impedance", &mut |progress| progress.fraction < 0.5);
+        assert!(matches!(cancelled, Err(PluginError::Recoverable(_))));
+    }
+
+    #[test]
+    fn actions_cross_as_json() {
+        let mut amp = amplifier();
+        let list = amp.action(DIAGNOSTICS_LIST_ACTION, &Value::Null).unwrap();
+        assert_eq!(list.unwrap()[0]["unit"], "kOhm");
+
+        let report = amp
+            .action(DIAGNOSTICS_RUN_ACTION, &json!({ "quantity": "impedance" }))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            report["channels"][3],
+            json!({ "channel": "ch3", "status": "failed" })
+        );
+        assert!(amp.action("other", &Value::Null).unwrap().is_none());
+
+        amp.on_action(DIAGNOSTICS_LIST_ACTION).unwrap();
+        assert!(matches!(
+            amp.on_action("other"),
+            Err(PluginError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn schema_preset_has_selector_and_limits() {
+        let schema = UISchema::new()
+            .diagnostics_section(&[MeasurableQuantity::impedance(), MeasurableQuantity::noise()]);
+        let defaults = schema.defaults();
+        assert_eq!(defaults["diagnostics:quantity"], "impedance");
+        assert_eq!(defaults["diagnostics:impedance_limit"], 50.0);
+        assert!(!defaults.contains_key("diagnostics:noise_limit"));
+    }
+}
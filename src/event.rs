@@ -0,0 +1,183 @@
+use crate::{Plugin, PluginContext, PluginError, PluginId};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+
+/// Sporadic message between plugins, e.g. "threshold crossed" or "device
+/// disconnected", for things scalar ports cannot express.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    pub source: PluginId,
+    pub topic: String,
+    #[serde(default)]
+    pub payload: Value,
+    /// Seconds on the host timeline. Left `None`, [`PluginContext::emit`]
+    /// fills in the time of the current tick.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<f64>,
+}
+
+impl Event {
+    pub fn new(source: PluginId, topic: impl Into<String>, payload: Value) -> Self {
+        Self {
+            source,
+            topic: topic.into(),
+            payload,
+            timestamp: None,
+        }
+    }
+
+    pub fn at(mut self, timestamp: f64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Whether the topic equals `filter`, or starts with it when `filter`
+    /// ends in `*` (`device.*` matches `device.lost`).
+    pub fn matches(&self, filter: &str) -> bool {
+        match filter.strip_suffix('*') {
+            Some(prefix) => self.topic.starts_with(prefix),
+            None => self.topic == filter,
+        }
+    }
+}
+
+/// Host-side queue that collects events emitted by plugins and delivers
+/// them to the others.
+///
+/// When full, the oldest events are dropped and counted in
+/// [`EventBus::dropped`], so a chatty plugin cannot grow memory without
+/// bound.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    queue: VecDeque<Event>,
+    capacity: usize,
+    dropped: u64,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            queue: VecDeque::new(),
+            capacity: capacity.max(1),
+            dropped: 0,
+        }
+    }
+
+    pub fn publish(&mut self, event: Event) {
+        if self.queue.len() == self.capacity {
+            self.queue.pop_front();
+            self.dropped += 1;
+        }
+        self.queue.push_back(event);
+    }
+
+    /// Moves the events emitted during the last call out of `ctx`.
+    pub fn collect(&mut self, ctx: &mut PluginContext) {
+        for event in ctx.events.drain(..) {
+            self.publish(event);
+        }
+    }
+
+    /// Calls `on_event` for every queued event not emitted by `plugin`
+    /// itself. Stops at the first error.
+    pub fn deliver(&self, plugin: &mut dyn Plugin) -> Result<(), PluginError> {
+        let id = plugin.id();
+        for event in self.queue.iter().filter(|event| event.source != id) {
+            plugin.on_event(event)?;
+        }
+        Ok(())
+    }
+
+    /// Removes and returns everything queued, e.g. once every plugin has
+    /// been delivered to.
+    pub fn drain(&mut self) -> Vec<Event> {
+        self.queue.drain(..).collect()
+    }
+
+    pub fn pending(&self) -> &VecDeque<Event> {
+        &self.queue
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PluginMeta, Port};
+    use serde_json::json;
+
+    struct Listener {
+        meta: PluginMeta,
+        seen: Vec<String>,
+    }
+
+    impl Plugin for Listener {
+        fn id(&self) -> PluginId {
+            PluginId(2)
+        }
+        fn meta(&self) -> &PluginMeta {
+            &self.meta
+        }
+        fn inputs(&self) -> &[Port] {
+            &[]
+        }
+        fn outputs(&self) -> &[Port] {
+            &[]
+        }
+        fn process(&mut self, _ctx: &mut PluginContext) -> Result<(), PluginError> {
+            Ok(())
+        }
+        fn on_event(&mut self, event: &Event) -> Result<(), PluginError> {
+            if event.matches("device.*") {
+                self.seen.push(event.topic.clone());
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn bus_routes_emitted_events() {
+        let mut ctx = PluginContext {
+            tick: 10,
+            period_seconds: 0.5,
+            ..PluginContext::default()
+        };
+        ctx.emit(Event::new(PluginId(1), "device.lost", json!({ "port": 3 })));
+        ctx.emit(Event::new(PluginId(1), "threshold", Value::Null).at(1.0));
+        ctx.emit(Event::new(PluginId(2), "device.own", Value::Null));
+        assert_eq!(ctx.events[0].timestamp, Some(5.0));
+        assert_eq!(ctx.events[1].timestamp, Some(1.0));
+
+        let mut bus = EventBus::new(8);
+        bus.collect(&mut ctx);
+        assert!(ctx.events.is_empty());
+
+        let mut listener = Listener {
+            meta: PluginMeta::new("listener"),
+            seen: Vec::new(),
+        };
+        bus.deliver(&mut listener).unwrap();
+        assert_eq!(listener.seen, ["device.lost"]);
+        assert_eq!(bus.drain().len(), 3);
+    }
+
+    #[test]
+    fn full_bus_drops_oldest() {
+        let mut bus = EventBus::new(2);
+        for topic in ["a", "b", "c"] {
+            bus.publish(Event::new(PluginId(1), topic, Value::Null));
+        }
+        assert_eq!(bus.dropped(), 1);
+        assert_eq!(bus.pending()[0].topic, "b");
+    }
+}
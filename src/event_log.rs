@@ -0,0 +1,283 @@
+//! Structured records handed to [`EventLogger`](crate::EventLogger)s, so
+//! loggers get timestamps, severity and typed fields directly instead of
+//! reconstructing them from scalar inputs, and the [`FlushPolicy`] hosts
+//! follow to let loggers batch their writes.
+
+use crate::host::LogLevel;
+use crate::logging::LogValue;
+use crate::{EventLogger, PluginError, PluginId};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Owned counterpart of [`LogValue`]. Serialized as the plain JSON value;
+/// non-negative integers read back as `U64`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RecordValue {
+    Bool(bool),
+    U64(u64),
+    I64(i64),
+    F64(f64),
+    Str(String),
+}
+
+impl From<LogValue<'_>> for RecordValue {
+    fn from(value: LogValue<'_>) -> Self {
+        match value {
+            LogValue::Bool(v) => RecordValue::Bool(v),
+            LogValue::I64(v) => RecordValue::I64(v),
+            LogValue::U64(v) => RecordValue::U64(v),
+            LogValue::F64(v) => RecordValue::F64(v),
+            LogValue::Str(v) => RecordValue::Str(v.to_string()),
+        }
+    }
+}
+
+macro_rules! record_value_from {
+    ($($ty:ty => $variant:ident),*) => {
+        $(impl From<$ty> for RecordValue {
+            fn from(value: $ty) -> Self {
+                RecordValue::$variant(value.into())
+            }
+        })*
+    };
+}
+
+record_value_from!(bool => Bool, i64 => I64, u64 => U64, f64 => F64, String => Str, &str => Str);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogRecord {
+    /// Wall-clock time in nanoseconds since the Unix epoch, normally
+    /// `PluginContext::wall_time` of the tick that produced the record.
+    pub timestamp_ns: u64,
+    pub severity: LogLevel,
+    pub source: PluginId,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub message: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, RecordValue>,
+}
+
+impl LogRecord {
+    pub fn new(timestamp_ns: u64, severity: LogLevel, source: PluginId) -> Self {
+        Self {
+            timestamp_ns,
+            severity,
+            source,
+            message: String::new(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    /// Copies a realtime [`logging::LogRecord`](crate::logging::LogRecord),
+    /// e.g. in a `LogSink` that feeds an event logger.
+    pub fn from_log(record: &crate::logging::LogRecord, timestamp_ns: u64) -> Self {
+        Self {
+            message: record.message.to_string(),
+            fields: record
+                .fields
+                .iter()
+                .map(|(key, value)| (key.to_string(), (*value).into()))
+                .collect(),
+            ..Self::new(timestamp_ns, record.level, record.plugin)
+        }
+    }
+
+    pub fn message(mut self, message: impl Into<String>) -> Self {
+        self.message = message.into();
+        self
+    }
+
+    pub fn field(mut self, key: impl Into<String>, value: impl Into<RecordValue>) -> Self {
+        self.fields.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// When the host calls `EventLogger::flush`. A flush is due as soon as any
+/// enabled condition holds, so `every_n_records` also bounds how much a
+/// batching logger keeps in memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlushPolicy {
+    /// Flush once `EventLogger::pending` reaches this many records.
+    pub every_n_records: Option<usize>,
+    pub every_duration: Option<Duration>,
+    /// Flush when the plugin is stopped.
+    pub on_stop: bool,
+}
+
+impl Default for FlushPolicy {
+    fn default() -> Self {
+        Self {
+            every_n_records: Some(1024),
+            every_duration: Some(Duration::from_secs(1)),
+            on_stop: true,
+        }
+    }
+}
+
+impl FlushPolicy {
+    /// Flush after every record, for loggers that do not batch.
+    pub fn immediate() -> Self {
+        Self {
+            every_n_records: Some(1),
+            ..Self::default()
+        }
+    }
+
+    pub fn is_due(&self, pending: usize, since_last_flush: Duration) -> bool {
+        if pending == 0 {
+            return false;
+        }
+        self.every_n_records.is_some_and(|n| pending >= n)
+            || self.every_duration.is_some_and(|d| since_last_flush >= d)
+    }
+}
+
+/// Host-side bookkeeping for a logger's [`FlushPolicy`].
+#[derive(Debug, Clone, Copy)]
+pub struct FlushTimer {
+    last_flush: Instant,
+}
+
+impl FlushTimer {
+    pub fn new(now: Instant) -> Self {
+        Self { last_flush: now }
+    }
+
+    /// Flushes `logger` if its policy says so; call after delivering records
+    /// or once per tick. Returns whether it flushed.
+    pub fn poll<L: EventLogger + ?Sized>(
+        &mut self,
+        logger: &mut L,
+        now: Instant,
+    ) -> Result<bool, PluginError> {
+        let since = now.saturating_duration_since(self.last_flush);
+        if !logger.flush_policy().is_due(logger.pending(), since) {
+            return Ok(false);
+        }
+        self.last_flush = now;
+        logger.flush()?;
+        Ok(true)
+    }
+
+    /// Call when the plugin is stopped.
+    pub fn stop<L: EventLogger + ?Sized>(
+        &mut self,
+        logger: &mut L,
+        now: Instant,
+    ) -> Result<(), PluginError> {
+        if logger.flush_policy().on_stop {
+            self.last_flush = now;
+            logger.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Plugin, PluginContext, PluginMeta, Port};
+    use serde_json::json;
+
+    // Buffers records until flushed.
+    struct Batching {
+        meta: PluginMeta,
+        buffer: Vec<LogRecord>,
+        flushes: usize,
+    }
+
+    impl Plugin for Batching {
+        fn id(&self) -> PluginId {
+            PluginId(1)
+        }
+        fn meta(&self) -> &PluginMeta {
+            &self.meta
+        }
+        fn inputs(&self) -> &[Port] {
+            &[]
+        }
+        fn outputs(&self) -> &[Port] {
+            &[]
+        }
+        fn process(&mut self, _ctx: &mut PluginContext) -> Result<(), PluginError> {
+            Ok(())
+        }
+    }
+
+    impl EventLogger for Batching {
+        fn flush(&mut self) -> Result<(), PluginError> {
+            self.buffer.clear();
+            self.flushes += 1;
+            Ok(())
+        }
+        fn log_event(&mut self, record: LogRecord) -> Result<(), PluginError> {
+            self.buffer.push(record);
+            Ok(())
+        }
+        fn flush_policy(&self) -> FlushPolicy {
+            FlushPolicy {
+                every_n_records: Some(3),
+                every_duration: Some(Duration::from_millis(500)),
+                on_stop: true,
+            }
+        }
+        fn pending(&self) -> usize {
+            self.buffer.len()
+        }
+    }
+
+    #[test]
+    fn timer_honors_flush_policy() {
+        let start = Instant::now();
+        let mut timer = FlushTimer::new(start);
+        let mut logger = Batching {
+            meta: PluginMeta::new("batching"),
+            buffer: Vec::new(),
+            flushes: 0,
+        };
+        let record = LogRecord::new(0, LogLevel::Info, PluginId(2));
+
+        for _ in 0..2 {
+            logger.log_event(record.clone()).unwrap();
+            assert!(!timer.poll(&mut logger, start).unwrap());
+        }
+        logger.log_event(record.clone()).unwrap();
+        assert!(timer.poll(&mut logger, start).unwrap());
+        assert_eq!(logger.pending(), 0);
+
+        // Nothing pending: the interval alone does not flush.
+        let later = start + Duration::from_secs(1);
+        assert!(!timer.poll(&mut logger, later).unwrap());
+        logger.log_event(record).unwrap();
+        assert!(timer.poll(&mut logger, later).unwrap());
+
+        timer.stop(&mut logger, later).unwrap();
+        assert_eq!(logger.flushes, 3);
+    }
+
+    #[test]
+    fn records_keep_field_types() {
+        let fields = [("channel", LogValue::U64(3)), ("unit", LogValue::Str("mV"))];
+        let realtime = crate::logging::LogRecord {
+            level: LogLevel::Warn,
+            plugin: PluginId(4),
+            message: "clipping",
+            fields: &fields,
+        };
+        let record = LogRecord::from_log(&realtime, 1_700_000_000_000_000_000)
+            .field("peak", -1.5)
+            .field("latched", true);
+
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["severity"], "warn");
+        assert_eq!(
+            json["fields"],
+            json!({ "channel": 3, "latched": true, "peak": -1.5, "unit": "mV" })
+        );
+        let back: LogRecord = serde_json::from_value(json).unwrap();
+        assert_eq!(back, record);
+    }
+}
@@ -0,0 +1,197 @@
+use crate::ui::{ConfigField, FieldType, UISchema};
+use crate::PluginError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Prefix of the schema keys [`UISchema::feature_section`] generates, so
+/// hosts can tell feature toggles apart from configuration values.
+pub const FEATURE_KEY_PREFIX: &str = "feature:";
+
+/// Optional behavior a plugin can switch at runtime, e.g. an experimental
+/// algorithm shipped next to the stable one.
+///
+/// Unlike configuration, flags are not part of presets and can be flipped
+/// while the plugin runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeatureFlag {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    #[serde(default)]
+    pub default: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub experimental: bool,
+}
+
+impl FeatureFlag {
+    pub fn new(name: impl Into<String>, default: bool) -> Self {
+        Self {
+            name: name.into(),
+            description: String::new(),
+            default,
+            experimental: false,
+        }
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    pub fn experimental(mut self) -> Self {
+        self.experimental = true;
+        self
+    }
+}
+
+pub fn feature_key(name: &str) -> String {
+    format!("{FEATURE_KEY_PREFIX}{name}")
+}
+
+/// Feature name of a key generated by [`feature_key`].
+pub fn feature_name(key: &str) -> Option<&str> {
+    key.strip_prefix(FEATURE_KEY_PREFIX)
+}
+
+/// Enabled state of a plugin's declared flags.
+///
+/// Plugins usually keep one, return [`FeatureSet::flags`] from
+/// `Plugin::features` and forward `Plugin::set_feature_enabled` to
+/// [`FeatureSet::set`].
+#[derive(Debug, Clone, Default)]
+pub struct FeatureSet {
+    flags: Vec<FeatureFlag>,
+    enabled: BTreeMap<String, bool>,
+}
+
+impl FeatureSet {
+    pub fn new(flags: impl IntoIterator<Item = FeatureFlag>) -> Self {
+        let flags: Vec<FeatureFlag> = flags.into_iter().collect();
+        let enabled = flags
+            .iter()
+            .map(|flag| (flag.name.clone(), flag.default))
+            .collect();
+        Self { flags, enabled }
+    }
+
+    pub fn flags(&self) -> &[FeatureFlag] {
+        &self.flags
+    }
+
+    /// Unknown names read as disabled.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.enabled.get(name).copied().unwrap_or(false)
+    }
+
+    pub fn set(&mut self, name: &str, enabled: bool) -> Result<(), PluginError> {
+        match self.enabled.get_mut(name) {
+            Some(state) => {
+                *state = enabled;
+                Ok(())
+            }
+            None => Err(unknown_feature(name)),
+        }
+    }
+
+    /// Names of the enabled flags, in name order.
+    pub fn enabled(&self) -> Vec<&str> {
+        self.enabled
+            .iter()
+            .filter(|(_, enabled)| **enabled)
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+}
+
+pub(crate) fn unknown_feature(name: &str) -> PluginError {
+    PluginError::Config(format!("unknown feature {name}"))
+}
+
+impl UISchema {
+    /// Adds a checkbox per flag to the collapsed `advanced` group, creating
+    /// the group if needed. Keys are [`feature_key`]s; experimental flags are
+    /// marked in the hint.
+    pub fn feature_section(mut self, flags: &[FeatureFlag]) -> Self {
+        if flags.is_empty() {
+            return self;
+        }
+        let fields = flags.iter().map(|flag| {
+            let mut hint = flag.description.clone();
+            if flag.experimental {
+                hint = if hint.is_empty() {
+                    "Experimental".to_string()
+                } else {
+                    format!("Experimental: {hint}")
+                };
+            }
+            let field = ConfigField::boolean(feature_key(&flag.name), flag.name.clone())
+                .default_value(Value::Bool(flag.default));
+            if hint.is_empty() {
+                field
+            } else {
+                field.hint(hint)
+            }
+        });
+        let existing = self.fields.iter_mut().find(|field| {
+            field.key == "advanced" && matches!(field.field_type, FieldType::Group { .. })
+        });
+        match existing {
+            Some(ConfigField {
+                field_type: FieldType::Group { fields: group, .. },
+                ..
+            }) => group.extend(fields),
+            _ => {
+                let group = fields.fold(
+                    ConfigField::group("advanced", "Advanced").collapsed(),
+                    ConfigField::child,
+                );
+                self.fields.push(group);
+            }
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feature_set_tracks_state() {
+        let mut set = FeatureSet::new([
+            FeatureFlag::new("fast_path", true),
+            FeatureFlag::new("ml_detector", false).experimental(),
+        ]);
+        assert_eq!(set.enabled(), ["fast_path"]);
+        set.set("ml_detector", true).unwrap();
+        assert!(set.is_enabled("ml_detector"));
+        assert!(matches!(set.set("nope", true), Err(PluginError::Config(_))));
+    }
+
+    #[test]
+    fn flags_surface_in_advanced_section() {
+        let flags = [FeatureFlag::new("ml_detector", false)
+            .description("Neural spike detector")
+            .experimental()];
+        let schema = UISchema::new()
+            .section(
+                "advanced",
+                "Advanced",
+                [ConfigField::boolean("verbose", "Verbose")],
+            )
+            .feature_section(&flags);
+
+        assert_eq!(schema.fields.len(), 1);
+        let field = schema.find_field("feature:ml_detector").unwrap();
+        assert_eq!(
+            field.hint.as_deref(),
+            Some("Experimental: Neural spike detector")
+        );
+        assert_eq!(feature_name(&field.key), Some("ml_detector"));
+        assert_eq!(
+            schema.defaults().get("feature:ml_detector"),
+            Some(&Value::Bool(false))
+        );
+    }
+}
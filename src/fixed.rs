@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+macro_rules! fixed_type {
+    ($name:ident, $bits:ty, $wide:ty, $frac:expr) => {
+        /// Signed fixed-point fraction in `[-1, 1)`.
+        ///
+        /// Arithmetic saturates instead of wrapping. Multiplication truncates
+        /// the product towards negative infinity, matching the usual DSP
+        /// `(a * b) >> n` with saturation.
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(pub $bits);
+
+        impl $name {
+            pub const FRAC_BITS: u32 = $frac;
+            pub const ZERO: Self = Self(0);
+            pub const MIN: Self = Self(<$bits>::MIN);
+            pub const MAX: Self = Self(<$bits>::MAX);
+
+            pub const fn from_bits(bits: $bits) -> Self {
+                Self(bits)
+            }
+
+            pub const fn to_bits(self) -> $bits {
+                self.0
+            }
+
+            /// Rounds to the nearest step and saturates; NaN becomes zero.
+            pub fn from_f64(value: f64) -> Self {
+                let scaled = (value * (1u64 << $frac) as f64).round();
+                // `as` saturates float-to-int casts and maps NaN to 0.
+                Self(scaled as $bits)
+            }
+
+            pub fn to_f64(self) -> f64 {
+                self.0 as f64 / (1u64 << $frac) as f64
+            }
+
+            pub fn saturating_add(self, rhs: Self) -> Self {
+                Self(self.0.saturating_add(rhs.0))
+            }
+
+            pub fn saturating_sub(self, rhs: Self) -> Self {
+                Self(self.0.saturating_sub(rhs.0))
+            }
+
+            pub fn saturating_mul(self, rhs: Self) -> Self {
+                let product = (self.0 as $wide * rhs.0 as $wide) >> $frac;
+                Self(product.clamp(<$bits>::MIN as $wide, <$bits>::MAX as $wide) as $bits)
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                self.saturating_add(rhs)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                self.saturating_sub(rhs)
+            }
+        }
+
+        impl Mul for $name {
+            type Output = Self;
+
+            fn mul(self, rhs: Self) -> Self {
+                self.saturating_mul(rhs)
+            }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self(self.0.saturating_neg())
+            }
+        }
+
+        impl From<$name> for f64 {
+            fn from(value: $name) -> f64 {
+                value.to_f64()
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", self.to_f64())
+            }
+        }
+    };
+}
+
+fixed_type!(Q15, i16, i32, 15);
+fixed_type!(Q31, i32, i64, 31);
+
+/// Representation a port's values have on the target.
+///
+/// Values still travel as `f64` through `set_input`/`get_output`; for
+/// fixed-point ports the host can use the raw integer entries of
+/// `PluginApi` instead so no rounding happens on the way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NumericFormat {
+    #[default]
+    Float,
+    Q15,
+    Q31,
+}
+
+impl NumericFormat {
+    pub fn is_float(&self) -> bool {
+        *self == NumericFormat::Float
+    }
+
+    /// Raw integer carried for `value`. `Float` passes the bit pattern of
+    /// the `f64`, so it round-trips exactly as well.
+    pub fn to_raw(self, value: f64) -> i64 {
+        match self {
+            NumericFormat::Float => value.to_bits() as i64,
+            NumericFormat::Q15 => Q15::from_f64(value).to_bits().into(),
+            NumericFormat::Q31 => Q31::from_f64(value).to_bits().into(),
+        }
+    }
+
+    /// Inverse of [`NumericFormat::to_raw`]; out-of-range raw values
+    /// saturate.
+    pub fn from_raw(self, raw: i64) -> f64 {
+        match self {
+            NumericFormat::Float => f64::from_bits(raw as u64),
+            NumericFormat::Q15 => Q15(raw.clamp(i16::MIN.into(), i16::MAX.into()) as i16).to_f64(),
+            NumericFormat::Q31 => Q31(raw.clamp(i32::MIN.into(), i32::MAX.into()) as i32).to_f64(),
+        }
+    }
+
+    /// `value` as the target would see it, e.g. to compare float results
+    /// with a fixed-point reference.
+    pub fn quantize(self, value: f64) -> f64 {
+        self.from_raw(self.to_raw(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn q15_conversion_and_saturation() {
+        assert_eq!(Q15::from_f64(0.5).to_bits(), 0x4000);
+        assert_eq!(Q15::from_f64(1.0), Q15::MAX);
+        assert_eq!(Q15::from_f64(-2.0), Q15::MIN);
+        assert_eq!(Q15::from_f64(f64::NAN), Q15::ZERO);
+        assert_eq!(Q15::from_bits(-16384).to_f64(), -0.5);
+
+        assert_eq!(Q15::MAX + Q15::from_f64(0.5), Q15::MAX);
+        assert_eq!(Q15::MIN * Q15::MIN, Q15::MAX);
+        assert_eq!(-Q15::MIN, Q15::MAX);
+        assert_eq!((Q15::from_f64(0.5) * Q15::from_f64(0.5)).to_f64(), 0.25);
+        // 3 * 3 >> 15 truncates to zero, -3 * 3 >> 15 to -1.
+        assert_eq!(Q15(3) * Q15(3), Q15(0));
+        assert_eq!(Q15(-3) * Q15(3), Q15(-1));
+    }
+
+    #[test]
+    fn q31_matches_reference() {
+        let a = Q31::from_f64(0.7);
+        let b = Q31::from_f64(-0.3);
+        let expected = ((a.0 as i64 * b.0 as i64) >> 31) as i32;
+        assert_eq!((a * b).to_bits(), expected);
+        assert_eq!(Q31::MIN * Q31::MIN, Q31::MAX);
+    }
+
+    #[test]
+    fn raw_round_trip() {
+        for format in [NumericFormat::Float, NumericFormat::Q15, NumericFormat::Q31] {
+            let value = format.quantize(0.123_456_789);
+            assert_eq!(format.from_raw(format.to_raw(value)), value);
+        }
+        assert_eq!(NumericFormat::Q15.to_raw(0.5), 0x4000);
+        assert_eq!(NumericFormat::Q15.from_raw(1 << 20), Q15::MAX.to_f64());
+        assert_eq!(NumericFormat::Float.quantize(0.1), 0.1);
+        assert_eq!(
+            serde_json::to_value(NumericFormat::Q31).unwrap(),
+            serde_json::json!("q31")
+        );
+    }
+}
@@ -0,0 +1,633 @@
+use crate::job::{FileCheckpoints, JobControl, JobStatus};
+use serde::{Deserialize, Serialize};
+use std::alloc::Layout;
+use std::ffi::c_void;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+    Trace = 5,
+}
+
+impl LogLevel {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(LogLevel::Error),
+            2 => Some(LogLevel::Warn),
+            3 => Some(LogLevel::Info),
+            4 => Some(LogLevel::Debug),
+            5 => Some(LogLevel::Trace),
+            _ => None,
+        }
+    }
+}
+
+/// Services the host exposes to plugins, passed to `create_with_host`.
+///
+/// Every callback receives `user_data` as its first argument and the id of
+/// the calling plugin as its second. Any entry may be `None` when the host
+/// does not provide that service. The table stays valid until the plugin
+/// has been destroyed.
+///
+/// New entries are only ever appended. Plugins read no further than
+/// `struct_size`, so entries a host built against an older crate does not
+/// know about are treated as unset.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct RTSynHostApi {
+    // `size_of::<RTSynHostApi>()` of the crate version the host was built with.
+    pub struct_size: usize,
+    pub user_data: *mut c_void,
+    pub log: Option<
+        extern "C" fn(user_data: *mut c_void, plugin: u64, level: u8, msg: *const u8, len: usize),
+    >,
+    // Monotonic host time in nanoseconds.
+    pub now_ns: Option<extern "C" fn(user_data: *mut c_void, plugin: u64) -> u64>,
+    // Returns null on failure; memory must be released through `free` with
+    // the same size and alignment.
+    pub alloc: Option<
+        extern "C" fn(user_data: *mut c_void, plugin: u64, size: usize, align: usize) -> *mut u8,
+    >,
+    pub free: Option<
+        extern "C" fn(user_data: *mut c_void, plugin: u64, ptr: *mut u8, size: usize, align: usize),
+    >,
+    // Returns `false` when the event was not accepted, e.g. a full queue.
+    pub post_event: Option<
+        extern "C" fn(
+            user_data: *mut c_void,
+            plugin: u64,
+            name: *const u8,
+            name_len: usize,
+            data: *const u8,
+            data_len: usize,
+        ) -> bool,
+    >,
+    // Absolute path of a bundled resource as UTF-8, written to `out` up to
+    // `out_len` bytes. Returns the full length, or 0 when not found.
+    pub resolve_resource: Option<
+        extern "C" fn(
+            user_data: *mut c_void,
+            plugin: u64,
+            name: *const u8,
+            name_len: usize,
+            out: *mut u8,
+            out_len: usize,
+        ) -> usize,
+    >,
+    // Registers a `job::BatchJob`, e.g. for the host's job list. Returns its
+    // id, or 0 when the host does not take jobs.
+    pub submit_job: Option<
+        extern "C" fn(user_data: *mut c_void, plugin: u64, name: *const u8, name_len: usize) -> u64,
+    >,
+    // Latest `job::JobStatus` of job `job` as JSON.
+    pub job_progress: Option<
+        extern "C" fn(
+            user_data: *mut c_void,
+            plugin: u64,
+            job: u64,
+            status: *const u8,
+            status_len: usize,
+        ),
+    >,
+    // What the user asked of job `job`, a `job::JobControl`.
+    pub job_control: Option<extern "C" fn(user_data: *mut c_void, plugin: u64, job: u64) -> u8>,
+    // Where the plugin may keep file `name` across host restarts, written
+    // like `resolve_resource`.
+    pub state_path: Option<
+        extern "C" fn(
+            user_data: *mut c_void,
+            plugin: u64,
+            name: *const u8,
+            name_len: usize,
+            out: *mut u8,
+            out_len: usize,
+        ) -> usize,
+    >,
+}
+
+impl RTSynHostApi {
+    /// Size of the required fields. Tables reporting a smaller
+    /// `struct_size` are ignored.
+    pub const MIN_SIZE: usize = std::mem::offset_of!(RTSynHostApi, log);
+
+    /// Table with every service unset.
+    pub const fn new(user_data: *mut c_void) -> Self {
+        Self {
+            struct_size: std::mem::size_of::<RTSynHostApi>(),
+            user_data,
+            log: None,
+            now_ns: None,
+            alloc: None,
+            free: None,
+            post_event: None,
+            resolve_resource: None,
+            submit_job: None,
+            job_progress: None,
+            job_control: None,
+            state_path: None,
+        }
+    }
+}
+
+type PathEntry = extern "C" fn(*mut c_void, u64, *const u8, usize, *mut u8, usize) -> usize;
+
+/// Plugin-side handle to the host services of one instance.
+///
+/// Calls into services the host did not provide are no-ops.
+#[derive(Clone, Copy)]
+pub struct HostServices {
+    api: RTSynHostApi,
+    plugin: u64,
+}
+
+// The host promises the table may be used from any thread for the lifetime of
+// the plugin, like the `PluginApi` table itself.
+unsafe impl Send for HostServices {}
+unsafe impl Sync for HostServices {}
+
+impl HostServices {
+    /// # Safety
+    ///
+    /// `api` must be null or point to a table that starts with `struct_size`,
+    /// is valid for reads of that many bytes and whose `user_data` outlives
+    /// every use of the returned handle, as guaranteed for the pointer passed
+    /// to `create_with_host`.
+    ///
+    /// Returns `None` for a null table or one shorter than
+    /// [`RTSynHostApi::MIN_SIZE`].
+    pub unsafe fn from_raw(api: *const RTSynHostApi, plugin: u64) -> Option<Self> {
+        if api.is_null() {
+            return None;
+        }
+        let size = std::ptr::read(api as *const usize);
+        if size < RTSynHostApi::MIN_SIZE {
+            return None;
+        }
+        // An all-zero `Option<extern "C" fn>` is `None`.
+        let mut table = std::mem::MaybeUninit::<RTSynHostApi>::zeroed();
+        std::ptr::copy_nonoverlapping(
+            api as *const u8,
+            table.as_mut_ptr() as *mut u8,
+            size.min(std::mem::size_of::<RTSynHostApi>()),
+        );
+        Some(Self {
+            api: table.assume_init(),
+            plugin,
+        })
+    }
+
+    // Entries past the host's `struct_size` were left unset in `from_raw`.
+    fn api(&self) -> &RTSynHostApi {
+        &self.api
+    }
+
+    pub fn log(&self, level: LogLevel, msg: &str) {
+        if let Some(log) = self.api().log {
+            log(
+                self.api().user_data,
+                self.plugin,
+                level as u8,
+                msg.as_ptr(),
+                msg.len(),
+            );
+        }
+    }
+
+    pub fn now_ns(&self) -> Option<u64> {
+        self.api()
+            .now_ns
+            .map(|now_ns| now_ns(self.api().user_data, self.plugin))
+    }
+
+    /// Allocates from the host, e.g. a realtime-safe pool. Returns `None`
+    /// when the host has no allocator or the allocation failed.
+    pub fn alloc(&self, layout: Layout) -> Option<*mut u8> {
+        let alloc = self.api().alloc?;
+        let ptr = alloc(
+            self.api().user_data,
+            self.plugin,
+            layout.size(),
+            layout.align(),
+        );
+        (!ptr.is_null()).then_some(ptr)
+    }
+
+    /// # Safety
+    ///
+    /// `ptr` must come from [`HostServices::alloc`] with the same `layout`.
+    pub unsafe fn free(&self, ptr: *mut u8, layout: Layout) {
+        if let Some(free) = self.api().free {
+            free(
+                self.api().user_data,
+                self.plugin,
+                ptr,
+                layout.size(),
+                layout.align(),
+            );
+        }
+    }
+
+    pub fn post_event(&self, name: &str, data: &[u8]) -> bool {
+        match self.api().post_event {
+            Some(post_event) => post_event(
+                self.api().user_data,
+                self.plugin,
+                name.as_ptr(),
+                name.len(),
+                data.as_ptr(),
+                data.len(),
+            ),
+            None => false,
+        }
+    }
+
+    /// Path of a data file shipped with the plugin, e.g.
+    /// `coeffs/lowpass.bin`, as located by the host.
+    pub fn resource_path(&self, name: &str) -> Option<PathBuf> {
+        self.path(self.api().resolve_resource?, name)
+    }
+
+    /// Registers a batch job under `name`. `None` when the host does not
+    /// take jobs.
+    pub fn submit_job(&self, name: &str) -> Option<u64> {
+        let submit_job = self.api().submit_job?;
+        let job = submit_job(self.api().user_data, self.plugin, name.as_ptr(), name.len());
+        (job != 0).then_some(job)
+    }
+
+    pub fn job_progress(&self, job: u64, status: &JobStatus) {
+        let Some(job_progress) = self.api().job_progress else {
+            return;
+        };
+        if let Ok(status) = serde_json::to_vec(status) {
+            job_progress(
+                self.api().user_data,
+                self.plugin,
+                job,
+                status.as_ptr(),
+                status.len(),
+            );
+        }
+    }
+
+    pub fn job_control(&self, job: u64) -> Option<JobControl> {
+        let job_control = self.api().job_control?;
+        JobControl::from_u8(job_control(self.api().user_data, self.plugin, job))
+    }
+
+    /// Where the plugin may keep file `name` across host restarts.
+    pub fn state_path(&self, name: &str) -> Option<PathBuf> {
+        self.path(self.api().state_path?, name)
+    }
+
+    /// Checkpoint store of job `job` under the host's state path.
+    pub fn checkpoints(&self, job: &str) -> Option<FileCheckpoints> {
+        self.state_path(&format!("{job}.checkpoint"))
+            .map(FileCheckpoints::new)
+    }
+
+    fn path(&self, resolve: PathEntry, name: &str) -> Option<PathBuf> {
+        let mut out = vec![0u8; 512];
+        loop {
+            let len = resolve(
+                self.api().user_data,
+                self.plugin,
+                name.as_ptr(),
+                name.len(),
+                out.as_mut_ptr(),
+                out.len(),
+            );
+            if len == 0 {
+                return None;
+            }
+            if len <= out.len() {
+                out.truncate(len);
+                return String::from_utf8(out).ok().map(PathBuf::from);
+            }
+            out.resize(len, 0);
+        }
+    }
+}
+
+/// Host-side implementation of the services in [`RTSynHostApi`].
+pub trait Host: Send + Sync {
+    fn log(&self, plugin: u64, level: LogLevel, msg: &str);
+
+    fn now_ns(&self, plugin: u64) -> u64;
+
+    // Defaults to the global allocator.
+    fn alloc(&self, _plugin: u64, layout: Layout) -> *mut u8 {
+        if layout.size() == 0 {
+            return std::ptr::null_mut();
+        }
+        unsafe { std::alloc::alloc(layout) }
+    }
+
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by [`Host::alloc`] with the same `layout`.
+    unsafe fn free(&self, _plugin: u64, ptr: *mut u8, layout: Layout) {
+        std::alloc::dealloc(ptr, layout)
+    }
+
+    fn post_event(&self, _plugin: u64, _name: &str, _data: &[u8]) -> bool {
+        false
+    }
+
+    // Typically answered from the `resources::ResourceBundle` of the
+    // library the plugin was loaded from.
+    fn resolve_resource(&self, _plugin: u64, _name: &str) -> Option<PathBuf> {
+        None
+    }
+
+    fn submit_job(&self, _plugin: u64, _name: &str) -> Option<u64> {
+        None
+    }
+
+    fn job_progress(&self, _plugin: u64, _job: u64, _status: &JobStatus) {}
+
+    fn job_control(&self, _plugin: u64, _job: u64) -> JobControl {
+        JobControl::Run
+    }
+
+    // Typically a file in a per-plugin directory next to the host's
+    // project state.
+    fn state_path(&self, _plugin: u64, _name: &str) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// Owns an [`RTSynHostApi`] table that forwards to a [`Host`].
+///
+/// The table must outlive every plugin created with it; the loader keeps an
+/// `Arc` of it inside each instance for that reason.
+pub struct HostApiTable {
+    api: Box<RTSynHostApi>,
+    _host: Box<Arc<dyn Host>>,
+}
+
+unsafe impl Send for HostApiTable {}
+unsafe impl Sync for HostApiTable {}
+
+impl HostApiTable {
+    pub fn new(host: Arc<dyn Host>) -> Self {
+        let host = Box::new(host);
+        let api = Box::new(RTSynHostApi {
+            log: Some(host_log),
+            now_ns: Some(host_now_ns),
+            alloc: Some(host_alloc),
+            free: Some(host_free),
+            post_event: Some(host_post_event),
+            resolve_resource: Some(host_resolve_resource),
+            submit_job: Some(host_submit_job),
+            job_progress: Some(host_job_progress),
+            job_control: Some(host_job_control),
+            state_path: Some(host_state_path),
+            ..RTSynHostApi::new(&*host as *const Arc<dyn Host> as *mut c_void)
+        });
+        Self { api, _host: host }
+    }
+
+    pub fn as_ptr(&self) -> *const RTSynHostApi {
+        &*self.api
+    }
+
+    /// Services handle for an in-process plugin with id `plugin`.
+    pub fn services(&self, plugin: u64) -> HostServices {
+        HostServices {
+            api: *self.api,
+            plugin,
+        }
+    }
+}
+
+fn host<'a>(user_data: *mut c_void) -> &'a dyn Host {
+    unsafe { &**(user_data as *const Arc<dyn Host>) }
+}
+
+fn str_from_raw<'a>(ptr: *const u8, len: usize) -> &'a str {
+    if ptr.is_null() {
+        return "";
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(ptr, len) };
+    std::str::from_utf8(bytes).unwrap_or("")
+}
+
+extern "C" fn host_log(user_data: *mut c_void, plugin: u64, level: u8, msg: *const u8, len: usize) {
+    let level = LogLevel::from_u8(level).unwrap_or(LogLevel::Info);
+    host(user_data).log(plugin, level, str_from_raw(msg, len));
+}
+
+extern "C" fn host_now_ns(user_data: *mut c_void, plugin: u64) -> u64 {
+    host(user_data).now_ns(plugin)
+}
+
+extern "C" fn host_alloc(
+    user_data: *mut c_void,
+    plugin: u64,
+    size: usize,
+    align: usize,
+) -> *mut u8 {
+    match Layout::from_size_align(size, align) {
+        Ok(layout) => host(user_data).alloc(plugin, layout),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+extern "C" fn host_free(
+    user_data: *mut c_void,
+    plugin: u64,
+    ptr: *mut u8,
+    size: usize,
+    align: usize,
+) {
+    if ptr.is_null() {
+        return;
+    }
+    if let Ok(layout) = Layout::from_size_align(size, align) {
+        unsafe { host(user_data).free(plugin, ptr, layout) }
+    }
+}
+
+extern "C" fn host_post_event(
+    user_data: *mut c_void,
+    plugin: u64,
+    name: *const u8,
+    name_len: usize,
+    data: *const u8,
+    data_len: usize,
+) -> bool {
+    let data = if data.is_null() {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(data, data_len) }
+    };
+    host(user_data).post_event(plugin, str_from_raw(name, name_len), data)
+}
+
+extern "C" fn host_resolve_resource(
+    user_data: *mut c_void,
+    plugin: u64,
+    name: *const u8,
+    name_len: usize,
+    out: *mut u8,
+    out_len: usize,
+) -> usize {
+    let path = host(user_data).resolve_resource(plugin, str_from_raw(name, name_len));
+    write_path(path, out, out_len)
+}
+
+extern "C" fn host_submit_job(
+    user_data: *mut c_void,
+    plugin: u64,
+    name: *const u8,
+    name_len: usize,
+) -> u64 {
+    host(user_data)
+        .submit_job(plugin, str_from_raw(name, name_len))
+        .unwrap_or(0)
+}
+
+extern "C" fn host_job_progress(
+    user_data: *mut c_void,
+    plugin: u64,
+    job: u64,
+    status: *const u8,
+    status_len: usize,
+) {
+    if let Ok(status) = serde_json::from_str(str_from_raw(status, status_len)) {
+        host(user_data).job_progress(plugin, job, &status);
+    }
+}
+
+extern "C" fn host_job_control(user_data: *mut c_void, plugin: u64, job: u64) -> u8 {
+    host(user_data).job_control(plugin, job) as u8
+}
+
+extern "C" fn host_state_path(
+    user_data: *mut c_void,
+    plugin: u64,
+    name: *const u8,
+    name_len: usize,
+    out: *mut u8,
+    out_len: usize,
+) -> usize {
+    let path = host(user_data).state_path(plugin, str_from_raw(name, name_len));
+    write_path(path, out, out_len)
+}
+
+fn write_path(path: Option<PathBuf>, out: *mut u8, out_len: usize) -> usize {
+    let Some(path) = path else {
+        return 0;
+    };
+    let Some(path) = path.to_str() else {
+        return 0;
+    };
+    if !out.is_null() && path.len() <= out_len {
+        unsafe { std::ptr::copy_nonoverlapping(path.as_ptr(), out, path.len()) };
+    }
+    path.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingHost {
+        logs: Mutex<Vec<(u64, LogLevel, String)>>,
+        events: Mutex<Vec<(String, Vec<u8>)>>,
+    }
+
+    impl Host for RecordingHost {
+        fn log(&self, plugin: u64, level: LogLevel, msg: &str) {
+            self.logs
+                .lock()
+                .unwrap()
+                .push((plugin, level, msg.to_string()));
+        }
+
+        fn now_ns(&self, _plugin: u64) -> u64 {
+            42
+        }
+
+        fn resolve_resource(&self, _plugin: u64, name: &str) -> Option<PathBuf> {
+            (name == "coeffs.bin").then(|| PathBuf::from("/opt/rtsyn/fir/".repeat(40)).join(name))
+        }
+
+        fn post_event(&self, _plugin: u64, name: &str, data: &[u8]) -> bool {
+            self.events
+                .lock()
+                .unwrap()
+                .push((name.to_string(), data.to_vec()));
+            true
+        }
+    }
+
+    #[test]
+    fn services_call_back_into_host() {
+        let host = Arc::new(RecordingHost::default());
+        let table = HostApiTable::new(host.clone());
+        let services = unsafe { HostServices::from_raw(table.as_ptr(), 7) }.unwrap();
+
+        services.log(LogLevel::Warn, "overrun");
+        assert_eq!(services.now_ns(), Some(42));
+        assert!(services.post_event("threshold", b"1.5"));
+        let path = services.resource_path("coeffs.bin").unwrap();
+        assert!(path.ends_with("fir/coeffs.bin"));
+        assert!(path.as_os_str().len() > 512);
+        assert_eq!(services.resource_path("missing"), None);
+
+        let layout = Layout::from_size_align(64, 16).unwrap();
+        let ptr = services.alloc(layout).unwrap();
+        assert_eq!(ptr as usize % 16, 0);
+        unsafe { services.free(ptr, layout) };
+
+        assert_eq!(
+            host.logs.lock().unwrap().as_slice(),
+            [(7, LogLevel::Warn, "overrun".to_string())]
+        );
+        assert_eq!(
+            host.events.lock().unwrap().as_slice(),
+            [("threshold".to_string(), b"1.5".to_vec())]
+        );
+    }
+
+    #[test]
+    fn missing_services_are_no_ops() {
+        let api = RTSynHostApi::new(std::ptr::null_mut());
+        let services = unsafe { HostServices::from_raw(&api, 1) }.unwrap();
+        services.log(LogLevel::Error, "ignored");
+        assert_eq!(services.now_ns(), None);
+        assert!(!services.post_event("e", &[]));
+        assert_eq!(services.resource_path("coeffs.bin"), None);
+        assert_eq!(services.submit_job("fft"), None);
+        assert!(services.checkpoints("fft").is_none());
+        assert!(services.alloc(Layout::new::<u64>()).is_none());
+        assert!(unsafe { HostServices::from_raw(std::ptr::null(), 1) }.is_none());
+    }
+
+    #[test]
+    fn entries_past_struct_size_are_unset() {
+        let host = Arc::new(RecordingHost::default());
+        let table = HostApiTable::new(host.clone());
+        let mut api = unsafe { *table.as_ptr() };
+        api.struct_size = std::mem::offset_of!(RTSynHostApi, post_event);
+        let services = unsafe { HostServices::from_raw(&api, 3) }.unwrap();
+        assert_eq!(services.now_ns(), Some(42));
+        assert!(!services.post_event("threshold", b"1.5"));
+        assert_eq!(services.resource_path("coeffs.bin"), None);
+        assert!(host.events.lock().unwrap().is_empty());
+
+        api.struct_size = RTSynHostApi::MIN_SIZE - 1;
+        assert!(unsafe { HostServices::from_raw(&api, 3) }.is_none());
+    }
+}
@@ -0,0 +1,233 @@
+//! Translated labels for plugins shipped to labs working in other languages.
+//!
+//! A [`Translations`] table maps a locale such as `"de"` or `"pt-BR"` to
+//! message keys and their text. Schemas and metadata keep their English
+//! strings as the fallback; hosts call [`UISchema::localized`] and
+//! [`PluginMeta::localized`] with the user's locale before showing them.
+//!
+//! Message keys follow the config keys:
+//!
+//! - `gain.label` and `gain.hint` for field or group `gain`
+//! - `filter.option.lp` for option `lp` of choice `filter`
+//! - `routing.column.ch` for column `ch` of table `routing`
+//! - `channels.add_label` for the add button of list `channels`, and
+//!   `channels.gain.label` for field `gain` of its structured items
+//! - `zero.confirm` for the confirmation of action `zero`
+//! - `wiring.markdown` for the text of info block `wiring`
+//! - `name` and `description` for the plugin metadata
+
+use crate::ui::{ConfigField, FieldType, UISchema};
+use crate::PluginMeta;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Translations(BTreeMap<String, BTreeMap<String, String>>);
+
+impl Translations {
+    pub const fn new() -> Self {
+        Self(BTreeMap::new())
+    }
+
+    pub fn insert(
+        &mut self,
+        locale: impl Into<String>,
+        key: impl Into<String>,
+        text: impl Into<String>,
+    ) {
+        self.0
+            .entry(locale.into())
+            .or_default()
+            .insert(key.into(), text.into());
+    }
+
+    /// Text of `key` in `locale`, falling back from a regional locale such
+    /// as `"de-AT"` to its language `"de"`.
+    pub fn get(&self, locale: &str, key: &str) -> Option<&str> {
+        let lookup = |locale: &str| self.0.get(locale)?.get(key).map(String::as_str);
+        lookup(locale).or_else(|| lookup(locale.split(['-', '_']).next()?))
+    }
+
+    pub fn locales(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Adds every message of `other` with `prefix` in front of its key, for
+    /// fields nested under namespaced keys such as `"gain.factor"`.
+    pub fn extend_prefixed(&mut self, prefix: &str, other: &Translations) {
+        for (locale, messages) in &other.0 {
+            let target = self.0.entry(locale.clone()).or_default();
+            for (key, text) in messages {
+                target.insert(format!("{prefix}{key}"), text.clone());
+            }
+        }
+    }
+
+    fn resolve(&self, locale: &str, key: &str, text: &mut String) {
+        if let Some(translated) = self.get(locale, key) {
+            *text = translated.to_string();
+        }
+    }
+}
+
+impl UISchema {
+    pub fn translation(
+        mut self,
+        locale: impl Into<String>,
+        key: impl Into<String>,
+        text: impl Into<String>,
+    ) -> Self {
+        self.translations.insert(locale, key, text);
+        self
+    }
+
+    /// Copy with labels, hints and option labels in `locale` where a
+    /// translation exists. Keys and stored values are unchanged, so a
+    /// configuration edited in the localized form fits the original schema.
+    pub fn localized(&self, locale: &str) -> UISchema {
+        let mut schema = self.clone();
+        localize_fields(&self.translations, locale, "", &mut schema.fields);
+        schema
+    }
+}
+
+fn localize_fields(
+    translations: &Translations,
+    locale: &str,
+    prefix: &str,
+    fields: &mut [ConfigField],
+) {
+    for field in fields {
+        let key = format!("{prefix}{}", field.key);
+        let message = |name: &str| format!("{key}.{name}");
+        translations.resolve(locale, &message("label"), &mut field.label);
+        if let Some(hint) = &mut field.hint {
+            translations.resolve(locale, &message("hint"), hint);
+        }
+        match &mut field.field_type {
+            FieldType::Choice { options, .. } => {
+                for option in options {
+                    let name = format!("option.{}", option.value);
+                    translations.resolve(locale, &message(&name), &mut option.label);
+                }
+            }
+            FieldType::Table { columns, .. } => {
+                for column in columns {
+                    let name = format!("column.{}", column.key);
+                    translations.resolve(locale, &message(&name), &mut column.label);
+                }
+            }
+            FieldType::DynamicList {
+                item_type,
+                add_label,
+                ..
+            } => {
+                translations.resolve(locale, &message("add_label"), add_label);
+                if let FieldType::Group { fields, .. } = &mut **item_type {
+                    localize_fields(translations, locale, &format!("{key}."), fields);
+                }
+            }
+            FieldType::Action {
+                confirm: Some(confirm),
+                ..
+            } => translations.resolve(locale, &message("confirm"), confirm),
+            FieldType::Info { markdown } => {
+                translations.resolve(locale, &message("markdown"), markdown)
+            }
+            // Group children keep top-level keys, like their config values.
+            FieldType::Group { label, fields, .. } => {
+                translations.resolve(locale, &message("label"), label);
+                localize_fields(translations, locale, prefix, fields);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl PluginMeta {
+    pub fn translation(
+        mut self,
+        locale: impl Into<String>,
+        key: impl Into<String>,
+        text: impl Into<String>,
+    ) -> Self {
+        self.translations.insert(locale, key, text);
+        self
+    }
+
+    /// Copy with the name and description in `locale`, for display only:
+    /// hosts keep identifying the plugin by its untranslated name.
+    pub fn localized(&self, locale: &str) -> PluginMeta {
+        let mut meta = self.clone();
+        self.translations.resolve(locale, "name", &mut meta.name);
+        if let Some(description) = &mut meta.description {
+            self.translations
+                .resolve(locale, "description", description);
+        }
+        meta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::ColumnSpec;
+
+    #[test]
+    fn localizes_labels_with_language_fallback() {
+        let schema = UISchema::new()
+            .field(
+                ConfigField::choice("filter", "Filter")
+                    .option("lp", "Low-pass")
+                    .option("hp", "High-pass")
+                    .hint("Applied before the gain"),
+            )
+            .field(ConfigField::group("advanced", "Advanced").child(
+                ConfigField::table("routing", "Routing").column(ColumnSpec::text("ch", "Channel")),
+            ))
+            .translation("de", "filter.label", "Filter")
+            .translation("de", "filter.hint", "Wird vor der Verstärkung angewendet")
+            .translation("de", "filter.option.lp", "Tiefpass")
+            .translation("de", "advanced.label", "Erweitert")
+            .translation("de", "routing.column.ch", "Kanal")
+            .translation("de-AT", "filter.option.lp", "Tiefpassfilter");
+
+        let german = schema.localized("de-DE");
+        let FieldType::Choice { options, .. } = &german.fields[0].field_type else {
+            panic!("expected a choice field");
+        };
+        assert_eq!(options[0].label, "Tiefpass");
+        assert_eq!(options[1].label, "High-pass");
+        assert_eq!(
+            german.fields[0].hint.as_deref(),
+            Some("Wird vor der Verstärkung angewendet")
+        );
+        assert_eq!(german.fields[1].label, "Erweitert");
+        let FieldType::Group { fields, .. } = &german.fields[1].field_type else {
+            panic!("expected a group");
+        };
+        let FieldType::Table { columns, .. } = &fields[0].field_type else {
+            panic!("expected a table");
+        };
+        assert_eq!(columns[0].label, "Kanal");
+
+        let austrian = schema.localized("de-AT");
+        let FieldType::Choice { options, .. } = &austrian.fields[0].field_type else {
+            panic!("expected a choice field");
+        };
+        assert_eq!(options[0].label, "Tiefpassfilter");
+        assert_eq!(schema.localized("fr").fields[1].label, "Advanced");
+
+        let meta = PluginMeta::new("Gain")
+            .description("Scales its input")
+            .translation("de", "name", "Verstärkung");
+        let localized = meta.localized("de");
+        assert_eq!(localized.name, "Verstärkung");
+        assert_eq!(localized.description.as_deref(), Some("Scales its input"));
+    }
+}
@@ -0,0 +1,664 @@
+//! Long-running offline work, e.g. a batch FFT over hours of recordings.
+//!
+//! A [`BatchJob`] does its work in short steps. [`JobRunner::submit`] runs
+//! it on its own thread and returns a [`JobHandle`] for progress, pause,
+//! resume and cancel. Between steps the runner saves the job's checkpoint
+//! as a [`BlobKind::Checkpoint`] state blob in a [`CheckpointStore`], so a
+//! job interrupted by a host restart resumes where it left off.
+//!
+//! [`JobRunner::submit_to_host`] hands the job to the host instead: the
+//! checkpoint lives under the host's state path and the host sees the
+//! job's progress and can pause, resume or cancel it, all through
+//! [`HostServices`].
+
+use crate::host::HostServices;
+use crate::state::{BlobKind, StateBlob};
+use crate::{PluginError, PluginMeta};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+pub enum JobStep {
+    Continue,
+    /// The job finished with this result.
+    Done(Value),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct JobProgress {
+    /// Between 0 and 1.
+    pub fraction: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+pub trait BatchJob: Send + 'static {
+    /// Its uid ties saved checkpoints to the plugin that wrote them.
+    fn meta(&self) -> &PluginMeta;
+
+    /// Called once before the first step, with the checkpoint to resume
+    /// from if there is one.
+    fn start(&mut self, params: &Value, checkpoint: Option<Value>) -> Result<(), PluginError>;
+
+    /// Does a slice of the work. Steps should take well under a second so
+    /// pause and cancel stay responsive.
+    fn step(&mut self) -> Result<JobStep, PluginError>;
+
+    fn progress(&self) -> JobProgress;
+
+    /// Everything needed to resume after the last step.
+    fn checkpoint(&self) -> Result<Value, PluginError>;
+}
+
+/// Where a job's checkpoint survives host restarts.
+pub trait CheckpointStore: Send + 'static {
+    fn load(&mut self) -> Result<Option<Vec<u8>>, PluginError>;
+    fn save(&mut self, blob: &[u8]) -> Result<(), PluginError>;
+    /// Called once the job is done or cancelled.
+    fn clear(&mut self) -> Result<(), PluginError>;
+}
+
+/// Keeps the checkpoint in one file, replaced atomically on every save.
+#[derive(Debug, Clone)]
+pub struct FileCheckpoints {
+    path: PathBuf,
+}
+
+impl FileCheckpoints {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl CheckpointStore for FileCheckpoints {
+    fn load(&mut self) -> Result<Option<Vec<u8>>, PluginError> {
+        match std::fs::read(&self.path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&mut self, blob: &[u8]) -> Result<(), PluginError> {
+        let temp = self.path.with_extension("tmp");
+        std::fs::write(&temp, blob)?;
+        Ok(std::fs::rename(&temp, &self.path)?)
+    }
+
+    fn clear(&mut self) -> Result<(), PluginError> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    Paused,
+    Done,
+    Failed,
+    Cancelled,
+    /// Stopped because its handle was dropped; the checkpoint is kept.
+    Stopped,
+}
+
+impl JobState {
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, JobState::Running | JobState::Paused)
+    }
+}
+
+/// What the host asks of a job submitted with [`JobRunner::submit_to_host`].
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobControl {
+    Run = 0,
+    Pause = 1,
+    Cancel = 2,
+}
+
+impl JobControl {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(JobControl::Run),
+            1 => Some(JobControl::Pause),
+            2 => Some(JobControl::Cancel),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobStatus {
+    pub state: JobState,
+    pub progress: JobProgress,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+pub fn job_cancelled() -> PluginError {
+    PluginError::Recoverable("job cancelled".to_string())
+}
+
+fn job_stopped() -> PluginError {
+    PluginError::Recoverable("job stopped".to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Run,
+    Pause,
+    Cancel,
+    Stop,
+}
+
+// How often a paused job asks the host whether to resume.
+const HOST_POLL: Duration = Duration::from_millis(50);
+
+struct Shared {
+    status: Mutex<JobStatus>,
+    command: Mutex<Command>,
+    wake: Condvar,
+}
+
+impl Shared {
+    fn status(&self) -> MutexGuard<'_, JobStatus> {
+        self.status.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn command(&self) -> MutexGuard<'_, Command> {
+        self.command.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set_command(&self, command: Command) {
+        let mut current = self.command();
+        // Cancel and stop are final.
+        if matches!(*current, Command::Run | Command::Pause) {
+            *current = command;
+        }
+        self.wake.notify_all();
+    }
+
+    fn set_state(&self, state: JobState) {
+        self.status().state = state;
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct JobRunner {
+    checkpoint_interval: Duration,
+}
+
+impl Default for JobRunner {
+    fn default() -> Self {
+        Self {
+            checkpoint_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+impl JobRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How often a running job is checkpointed. Pausing always checkpoints.
+    pub fn checkpoint_every(mut self, interval: Duration) -> Self {
+        self.checkpoint_interval = interval;
+        self
+    }
+
+    /// Starts `job` on its own thread, resuming from the checkpoint in
+    /// `store` if it holds one written by the same plugin.
+    pub fn submit<J: BatchJob, S: CheckpointStore>(
+        &self,
+        job: J,
+        params: Value,
+        store: S,
+    ) -> Result<JobHandle, PluginError> {
+        self.spawn(job, params, store, None)
+    }
+
+    /// Like [`JobRunner::submit`], with the checkpoint kept under the host's
+    /// state path. The job reports its status to the host after every step
+    /// and follows the host's pause, resume and cancel requests as well as
+    /// those of the returned handle.
+    pub fn submit_to_host<J: BatchJob>(
+        &self,
+        job: J,
+        params: Value,
+        host: HostServices,
+    ) -> Result<JobHandle, PluginError> {
+        let name = job.meta().name.clone();
+        let store = host.checkpoints(&name).ok_or_else(|| {
+            PluginError::Config("host has no state path for job checkpoints".to_string())
+        })?;
+        let host = host.submit_job(&name).map(|id| (host, id));
+        self.spawn(job, params, store, host)
+    }
+
+    fn spawn<J: BatchJob, S: CheckpointStore>(
+        &self,
+        job: J,
+        params: Value,
+        store: S,
+        host: Option<(HostServices, u64)>,
+    ) -> Result<JobHandle, PluginError> {
+        let shared = Arc::new(Shared {
+            status: Mutex::new(JobStatus {
+                state: JobState::Running,
+                progress: JobProgress::default(),
+                error: None,
+            }),
+            command: Mutex::new(Command::Run),
+            wake: Condvar::new(),
+        });
+        let mut worker = Worker {
+            job,
+            store,
+            shared: shared.clone(),
+            interval: self.checkpoint_interval,
+            host,
+            host_control: JobControl::Run,
+        };
+        let thread = std::thread::Builder::new()
+            .name(format!("rtsyn-job-{}", worker.job.meta().name))
+            .spawn(move || {
+                let result = worker.run(&params);
+                if let Err(err) = &result {
+                    let mut status = worker.shared.status();
+                    if status.state == JobState::Running {
+                        status.state = JobState::Failed;
+                        status.error = Some(err.to_string());
+                    }
+                }
+                worker.report();
+                result
+            })?;
+        Ok(JobHandle {
+            shared,
+            thread: Some(thread),
+        })
+    }
+}
+
+struct Worker<J, S> {
+    job: J,
+    store: S,
+    shared: Arc<Shared>,
+    interval: Duration,
+    host: Option<(HostServices, u64)>,
+    // Last request seen from the host, so the handle's own requests are
+    // not overridden on every poll.
+    host_control: JobControl,
+}
+
+impl<J: BatchJob, S: CheckpointStore> Worker<J, S> {
+    fn run(&mut self, params: &Value) -> Result<Value, PluginError> {
+        let checkpoint = self.load()?;
+        self.job.start(params, checkpoint)?;
+        let mut saved_at = Instant::now();
+        loop {
+            self.sync_host();
+            let mut command = self.shared.command();
+            if *command == Command::Pause {
+                drop(command);
+                self.save()?;
+                self.shared.set_state(JobState::Paused);
+                self.report();
+                command = self.shared.command();
+                while *command == Command::Pause {
+                    if self.host.is_none() {
+                        command = self
+                            .shared
+                            .wake
+                            .wait(command)
+                            .unwrap_or_else(|e| e.into_inner());
+                        continue;
+                    }
+                    let (paused, _) = self
+                        .shared
+                        .wake
+                        .wait_timeout(command, HOST_POLL)
+                        .unwrap_or_else(|e| e.into_inner());
+                    drop(paused);
+                    self.sync_host();
+                    command = self.shared.command();
+                }
+                self.shared.set_state(JobState::Running);
+                saved_at = Instant::now();
+            }
+            let current = *command;
+            drop(command);
+            match current {
+                Command::Cancel => {
+                    self.store.clear()?;
+                    self.shared.set_state(JobState::Cancelled);
+                    return Err(job_cancelled());
+                }
+                Command::Stop => {
+                    self.save()?;
+                    self.shared.set_state(JobState::Stopped);
+                    return Err(job_stopped());
+                }
+                Command::Run | Command::Pause => {}
+            }
+
+            let step = self.job.step()?;
+            self.shared.status().progress = self.job.progress();
+            self.report();
+            if let JobStep::Done(result) = step {
+                self.store.clear()?;
+                let mut status = self.shared.status();
+                status.state = JobState::Done;
+                status.progress.fraction = 1.0;
+                return Ok(result);
+            }
+            if saved_at.elapsed() >= self.interval {
+                self.save()?;
+                saved_at = Instant::now();
+            }
+        }
+    }
+
+    fn report(&self) {
+        if let Some((host, job)) = &self.host {
+            host.job_progress(*job, &self.shared.status());
+        }
+    }
+
+    // Applies the host's latest request if it changed since the last poll.
+    fn sync_host(&mut self) {
+        let Some((host, job)) = &self.host else {
+            return;
+        };
+        let Some(control) = host.job_control(*job) else {
+            return;
+        };
+        if control == self.host_control {
+            return;
+        }
+        self.host_control = control;
+        self.shared.set_command(match control {
+            JobControl::Run => Command::Run,
+            JobControl::Pause => Command::Pause,
+            JobControl::Cancel => Command::Cancel,
+        });
+    }
+
+    fn load(&mut self) -> Result<Option<Value>, PluginError> {
+        let Some(bytes) = self.store.load()? else {
+            return Ok(None);
+        };
+        let blob = StateBlob::decode(&bytes)?;
+        if blob.kind != BlobKind::Checkpoint || blob.plugin != self.job.meta().uid {
+            return Ok(None);
+        }
+        Ok(Some(blob.value))
+    }
+
+    fn save(&mut self) -> Result<(), PluginError> {
+        let blob = StateBlob::for_plugin(
+            BlobKind::Checkpoint,
+            self.job.meta(),
+            self.job.checkpoint()?,
+        );
+        self.store.save(&blob.encode()?)
+    }
+}
+
+/// Controls a submitted job. Dropping the handle stops the job after its
+/// current step and keeps its checkpoint for the next submit.
+pub struct JobHandle {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<Result<Value, PluginError>>>,
+}
+
+impl JobHandle {
+    pub fn status(&self) -> JobStatus {
+        self.shared.status().clone()
+    }
+
+    /// Checkpoints and waits after the current step.
+    pub fn pause(&self) {
+        self.shared.set_command(Command::Pause);
+    }
+
+    pub fn resume(&self) {
+        self.shared.set_command(Command::Run);
+    }
+
+    /// Stops the job and discards its checkpoint.
+    pub fn cancel(&self) {
+        self.shared.set_command(Command::Cancel);
+    }
+
+    /// Blocks until the job finishes and returns its result.
+    pub fn wait(mut self) -> Result<Value, PluginError> {
+        let thread = self.thread.take().expect("job thread is joined once");
+        thread
+            .join()
+            .unwrap_or_else(|_| Err(PluginError::Fatal("job panicked".to_string())))
+    }
+}
+
+impl Drop for JobHandle {
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            self.shared.set_command(Command::Stop);
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::{Host, HostApiTable, LogLevel};
+    use serde_json::json;
+
+    // Sums 0..count one number per step.
+    struct Sum {
+        meta: PluginMeta,
+        count: u64,
+        next: u64,
+        total: u64,
+        delay: Duration,
+    }
+
+    impl Sum {
+        fn new(delay: Duration) -> Self {
+            Self {
+                meta: PluginMeta::new("sum").uid("com.example.sum"),
+                count: 0,
+                next: 0,
+                total: 0,
+                delay,
+            }
+        }
+    }
+
+    impl BatchJob for Sum {
+        fn meta(&self) -> &PluginMeta {
+            &self.meta
+        }
+
+        fn start(&mut self, params: &Value, checkpoint: Option<Value>) -> Result<(), PluginError> {
+            self.count = params["count"].as_u64().unwrap_or(0);
+            if let Some(checkpoint) = checkpoint {
+                self.next = checkpoint["next"].as_u64().unwrap_or(0);
+                self.total = checkpoint["total"].as_u64().unwrap_or(0);
+            }
+            Ok(())
+        }
+
+        fn step(&mut self) -> Result<JobStep, PluginError> {
+            if self.next == self.count {
+                return Ok(JobStep::Done(json!(self.total)));
+            }
+            std::thread::sleep(self.delay);
+            self.total += self.next;
+            self.next += 1;
+            Ok(JobStep::Continue)
+        }
+
+        fn progress(&self) -> JobProgress {
+            JobProgress {
+                fraction: self.next as f64 / self.count.max(1) as f64,
+                message: None,
+            }
+        }
+
+        fn checkpoint(&self) -> Result<Value, PluginError> {
+            Ok(json!({ "next": self.next, "total": self.total }))
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct Memory(Arc<Mutex<Option<Vec<u8>>>>);
+
+    impl CheckpointStore for Memory {
+        fn load(&mut self) -> Result<Option<Vec<u8>>, PluginError> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+        fn save(&mut self, blob: &[u8]) -> Result<(), PluginError> {
+            *self.0.lock().unwrap() = Some(blob.to_vec());
+            Ok(())
+        }
+        fn clear(&mut self) -> Result<(), PluginError> {
+            *self.0.lock().unwrap() = None;
+            Ok(())
+        }
+    }
+
+    fn wait_for(handle: &JobHandle, done: impl Fn(&JobStatus) -> bool) -> JobStatus {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let status = handle.status();
+            if done(&status) || Instant::now() > deadline {
+                return status;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn pauses_and_resumes_from_checkpoint() {
+        let store = Memory::default();
+        let params = json!({ "count": 10_000 });
+        let handle = JobRunner::new()
+            .submit(
+                Sum::new(Duration::from_millis(1)),
+                params.clone(),
+                store.clone(),
+            )
+            .unwrap();
+        wait_for(&handle, |s| s.progress.fraction > 0.0);
+        handle.pause();
+        let status = wait_for(&handle, |s| s.state == JobState::Paused);
+        assert_eq!(status.state, JobState::Paused);
+        let saved = StateBlob::decode(&store.0.lock().unwrap().clone().unwrap()).unwrap();
+        assert_eq!(saved.kind, BlobKind::Checkpoint);
+        let resumed_from = saved.value["next"].as_u64().unwrap();
+        assert!(resumed_from > 0);
+
+        // A host restart: the job stops with its checkpoint kept.
+        drop(handle);
+        let handle = JobRunner::new()
+            .submit(Sum::new(Duration::ZERO), params, store.clone())
+            .unwrap();
+        assert_eq!(handle.wait().unwrap(), json!(10_000u64 * 9_999 / 2));
+        assert!(store.0.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn cancel_discards_checkpoint() {
+        let store = Memory::default();
+        let handle = JobRunner::new()
+            .checkpoint_every(Duration::ZERO)
+            .submit(
+                Sum::new(Duration::from_millis(1)),
+                json!({ "count": 10_000 }),
+                store.clone(),
+            )
+            .unwrap();
+        wait_for(&handle, |s| s.progress.fraction > 0.0);
+        handle.cancel();
+        let status = wait_for(&handle, |s| s.state.is_finished());
+        assert_eq!(status.state, JobState::Cancelled);
+        assert!(handle.wait().is_err());
+        assert!(store.0.lock().unwrap().is_none());
+    }
+
+    struct JobHost {
+        dir: PathBuf,
+        control: Mutex<JobControl>,
+        statuses: Mutex<Vec<JobStatus>>,
+    }
+
+    impl Host for JobHost {
+        fn log(&self, _plugin: u64, _level: LogLevel, _msg: &str) {}
+
+        fn now_ns(&self, _plugin: u64) -> u64 {
+            0
+        }
+
+        fn submit_job(&self, _plugin: u64, name: &str) -> Option<u64> {
+            (name == "sum").then_some(9)
+        }
+
+        fn job_progress(&self, _plugin: u64, job: u64, status: &JobStatus) {
+            assert_eq!(job, 9);
+            self.statuses.lock().unwrap().push(status.clone());
+        }
+
+        fn job_control(&self, _plugin: u64, _job: u64) -> JobControl {
+            *self.control.lock().unwrap()
+        }
+
+        fn state_path(&self, plugin: u64, name: &str) -> Option<PathBuf> {
+            Some(self.dir.join(format!("{plugin}-{name}")))
+        }
+    }
+
+    #[test]
+    fn host_pauses_and_keeps_checkpoints() {
+        let dir = std::env::temp_dir().join(format!("rtsyn-job-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let host = Arc::new(JobHost {
+            dir: dir.clone(),
+            control: Mutex::new(JobControl::Run),
+            statuses: Mutex::default(),
+        });
+        let table = HostApiTable::new(host.clone());
+        let handle = JobRunner::new()
+            .submit_to_host(
+                Sum::new(Duration::from_millis(1)),
+                json!({ "count": 200 }),
+                table.services(4),
+            )
+            .unwrap();
+        wait_for(&handle, |s| s.progress.fraction > 0.0);
+        *host.control.lock().unwrap() = JobControl::Pause;
+        let status = wait_for(&handle, |s| s.state == JobState::Paused);
+        assert_eq!(status.state, JobState::Paused);
+        let checkpoint = dir.join("4-sum.checkpoint");
+        let saved = StateBlob::decode(&std::fs::read(&checkpoint).unwrap()).unwrap();
+        assert_eq!(saved.kind, BlobKind::Checkpoint);
+
+        *host.control.lock().unwrap() = JobControl::Run;
+        assert_eq!(handle.wait().unwrap(), json!(200u64 * 199 / 2));
+        assert!(!checkpoint.exists());
+        let statuses = host.statuses.lock().unwrap();
+        assert!(statuses.iter().any(|s| s.state == JobState::Paused));
+        assert_eq!(statuses.last().unwrap().state, JobState::Done);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,89 @@
+use crate::Plugin;
+use std::collections::VecDeque;
+
+/// Total delay of plugins connected in series.
+pub fn chain_latency(plugins: &[&dyn Plugin]) -> u64 {
+    plugins.iter().map(|plugin| plugin.latency_ticks()).sum()
+}
+
+/// Extra delay for each parallel branch so that all of them line up with
+/// the slowest one.
+pub fn compensation(branch_latencies: &[u64]) -> Vec<u64> {
+    let max = branch_latencies.iter().copied().max().unwrap_or(0);
+    branch_latencies
+        .iter()
+        .map(|latency| max - latency)
+        .collect()
+}
+
+/// Delays a signal by a whole number of ticks, e.g. to apply a
+/// [`compensation`] to a faster branch. Reads `0.0` until filled.
+#[derive(Debug, Clone)]
+pub struct DelayLine {
+    buffer: VecDeque<f64>,
+    delay: usize,
+}
+
+impl DelayLine {
+    pub fn new(delay_ticks: u64) -> Self {
+        let delay = delay_ticks as usize;
+        Self {
+            buffer: std::iter::repeat_n(0.0, delay).collect(),
+            delay,
+        }
+    }
+
+    pub fn delay_ticks(&self) -> u64 {
+        self.delay as u64
+    }
+
+    /// Pushes this tick's value and returns the one from `delay_ticks` ago.
+    pub fn push(&mut self, value: f64) -> f64 {
+        if self.delay == 0 {
+            return value;
+        }
+        self.buffer.push_back(value);
+        self.buffer.pop_front().unwrap_or(0.0)
+    }
+
+    /// Changes the delay, keeping the most recent values where possible.
+    pub fn set_delay(&mut self, delay_ticks: u64) {
+        let delay = delay_ticks as usize;
+        while self.buffer.len() > delay {
+            self.buffer.pop_front();
+        }
+        while self.buffer.len() < delay {
+            self.buffer.push_front(0.0);
+        }
+        self.delay = delay;
+    }
+
+    pub fn reset(&mut self) {
+        self.buffer.iter_mut().for_each(|value| *value = 0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compensation_aligns_branches() {
+        assert_eq!(compensation(&[64, 0, 16]), [0, 64, 48]);
+        assert!(compensation(&[]).is_empty());
+    }
+
+    #[test]
+    fn delay_line_delays_by_ticks() {
+        let mut line = DelayLine::new(2);
+        let out: Vec<f64> = [1.0, 2.0, 3.0, 4.0]
+            .into_iter()
+            .map(|v| line.push(v))
+            .collect();
+        assert_eq!(out, [0.0, 0.0, 1.0, 2.0]);
+
+        line.set_delay(1);
+        assert_eq!(line.push(5.0), 4.0);
+        assert_eq!(DelayLine::new(0).push(7.0), 7.0);
+    }
+}
@@ -1,8 +1,61 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::sync::OnceLock;
+use std::time::{Instant, SystemTime};
+use ui::ffi::RtsynStatus;
 
+pub mod aggregate;
+pub mod api_report;
+#[cfg(feature = "tokio")]
+pub mod async_device;
+pub mod automation;
+pub mod cli;
+pub mod clock;
+pub mod comparison;
+pub mod complex;
+pub mod composite;
+pub mod config;
+#[cfg(any(feature = "toml", feature = "yaml"))]
+pub mod config_file;
+pub mod device;
+pub mod diagnostics;
+pub mod event;
+pub mod event_log;
+pub mod features;
+pub mod fixed;
+pub mod host;
+pub mod i18n;
+pub mod job;
+pub mod latency;
+#[cfg(feature = "loader")]
+pub mod loader;
+#[cfg(feature = "loggers")]
+pub mod loggers;
+pub mod logging;
+pub mod metrics;
+pub mod null;
+#[cfg(feature = "otel")]
+pub mod otel;
+pub mod pipeline;
+pub mod port_key;
+pub mod ports;
 pub mod prelude;
+pub mod preset;
+pub mod preview;
+pub mod pure;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod remote;
+pub mod replay;
+#[cfg(feature = "loader")]
+pub mod resources;
+pub mod schema_compat;
+pub mod state;
+pub mod template;
+pub mod testing;
 pub mod ui;
+#[cfg(any(feature = "wasm", feature = "wasmtime"))]
+pub mod wasm;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct PluginId(pub u64);
@@ -10,28 +63,702 @@ pub struct PluginId(pub u64);
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct PortId(pub String);
 
+impl PortId {
+    /// Interned form for per-tick lookups, see [`port_key`].
+    pub fn key(&self) -> port_key::PortKey {
+        port_key::PortKey::from(self)
+    }
+}
+
+/// Stable reverse-domain identifier of a plugin type, e.g. `com.example.lowpass`.
+///
+/// Unlike [`PluginId`], which the host assigns per instance, the uid is chosen
+/// by the plugin author and is the same on every machine, so it is what preset
+/// files, saved graphs and state migrations refer to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct PluginUid(pub String);
+
+impl PluginUid {
+    pub fn new(uid: impl Into<String>) -> Self {
+        Self(uid.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    // At least two dot-separated segments of ASCII letters, digits, `_` or `-`,
+    // each starting with a letter.
+    pub fn is_valid(&self) -> bool {
+        let mut segments = 0;
+        for segment in self.0.split('.') {
+            let mut chars = segment.chars();
+            if !chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+                || !chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                return false;
+            }
+            segments += 1;
+        }
+        segments >= 2
+    }
+}
+
+impl std::fmt::Display for PluginUid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Port {
     pub id: PortId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clock: Option<clock::ClockDomain>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resampling: Option<clock::Resampling>,
+    /// Former ids of a renamed port, still accepted when saved connections
+    /// are restored. See `ports::resolve_port`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "fixed::NumericFormat::is_float")]
+    pub format: fixed::NumericFormat,
+    /// Derived outputs computed by `aggregate::Aggregated`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aggregates: Vec<aggregate::Aggregate>,
+    /// How offline runs fill ticks the recording has no sample for.
+    #[serde(default, skip_serializing_if = "replay::GapPolicy::is_hold")]
+    pub gap: replay::GapPolicy,
+    #[serde(default, skip_serializing_if = "complex::PortKind::is_scalar")]
+    pub kind: complex::PortKind,
+    /// Tooltip text for graph editors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    /// Expected `(min, max)` of the values, inclusive. Advisory: hosts warn
+    /// about connections that may exceed it but never clamp.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<(f64, f64)>,
+    /// Bus this port belongs to, e.g. `"accel"` for `x`, `y` and `z`.
+    /// Editors draw the members together and can wire them in one step with
+    /// `ports::connect_bus`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Position within `group`. Members without one follow in declaration
+    /// order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_index: Option<u32>,
+    /// Input that must be connected before the graph may start.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub required: bool,
+    /// Most connections an input accepts. `None` means one, so fan-in is
+    /// only offered where the plugin sums or merges its sources.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<usize>,
+}
+
+impl Port {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: PortId(id.into()),
+            clock: None,
+            resampling: None,
+            aliases: Vec::new(),
+            format: fixed::NumericFormat::Float,
+            aggregates: Vec::new(),
+            gap: replay::GapPolicy::Hold,
+            kind: complex::PortKind::Scalar,
+            description: None,
+            unit: None,
+            range: None,
+            group: None,
+            group_index: None,
+            required: false,
+            max_connections: None,
+        }
+    }
+
+    /// A complex port, carried as the channels `<id>.re` and `<id>.im`.
+    pub fn complex(id: impl Into<String>) -> Self {
+        Self {
+            kind: complex::PortKind::Complex,
+            ..Self::new(id)
+        }
+    }
+
+    /// A port exchanging whole frames through `Plugin::set_input_vec` and
+    /// `Plugin::get_output_vec`.
+    pub fn vector(id: impl Into<String>) -> Self {
+        Self {
+            kind: complex::PortKind::Vector,
+            ..Self::new(id)
+        }
+    }
+
+    /// An output read with `Plugin::get_output_string`.
+    pub fn text(id: impl Into<String>) -> Self {
+        Self {
+            kind: complex::PortKind::Text,
+            ..Self::new(id)
+        }
+    }
+
+    pub fn alias(mut self, old_id: impl Into<String>) -> Self {
+        self.aliases.push(old_id.into());
+        self
+    }
+
+    /// Whether `name` is the id or one of the aliases of this port.
+    pub fn matches(&self, name: &str) -> bool {
+        self.id.0 == name || self.aliases.iter().any(|alias| alias == name)
+    }
+
+    pub fn clock(mut self, domain: clock::ClockDomain) -> Self {
+        self.clock = Some(domain);
+        self
+    }
+
+    pub fn resampling(mut self, resampling: clock::Resampling) -> Self {
+        self.resampling = Some(resampling);
+        self
+    }
+
+    pub fn format(mut self, format: fixed::NumericFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Declares a `kind` aggregate over the last `window_ticks` values as an
+    /// extra output, e.g. `rms.mean_100`.
+    pub fn aggregate(mut self, kind: aggregate::AggregateKind, window_ticks: u32) -> Self {
+        self.aggregates
+            .push(aggregate::Aggregate { kind, window_ticks });
+        self
+    }
+
+    pub fn gap(mut self, policy: replay::GapPolicy) -> Self {
+        self.gap = policy;
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn unit(mut self, unit: impl Into<String>) -> Self {
+        self.unit = Some(unit.into());
+        self
+    }
+
+    pub fn range(mut self, min: f64, max: f64) -> Self {
+        self.range = Some((min, max));
+        self
+    }
+
+    pub fn group(mut self, group: impl Into<String>, index: u32) -> Self {
+        self.group = Some(group.into());
+        self.group_index = Some(index);
+        self
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn max_connections(mut self, max: usize) -> Self {
+        self.max_connections = Some(max);
+        self
+    }
+
+    /// Whether this input may have `count` connections.
+    pub fn accepts_connections(&self, count: usize) -> bool {
+        count <= self.max_connections.unwrap_or(1) && (count > 0 || !self.required)
+    }
+
+    /// Whether every value this output is declared to produce lies in the
+    /// declared range of `input`. True when either range is unknown, so
+    /// hosts only warn about connections known to be risky.
+    pub fn range_fits(&self, input: &Port) -> bool {
+        match (self.range, input.range) {
+            (Some((min, max)), Some((input_min, input_max))) => {
+                min >= input_min && max <= input_max
+            }
+            _ => true,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PluginMeta {
     pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<PluginUid>,
     pub fixed_vars: Vec<(String, Value)>,
     pub default_vars: Vec<(String, Value)>,
+    /// Semver version of the plugin itself, e.g. `"1.4.0"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// SPDX license expression.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    #[serde(default)]
+    pub category: PluginCategory,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// What the plugin will do outside its ports, for hosts to show before
+    /// loading third-party plugins and to check against a policy.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub capabilities: Vec<Capability>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display: Option<DisplayMeta>,
+    /// Name and description in other languages, see `PluginMeta::localized`.
+    #[serde(default, skip_serializing_if = "i18n::Translations::is_empty")]
+    pub translations: i18n::Translations,
+}
+
+/// How node editors draw the plugin. Hosts fall back to their own styling
+/// per `PluginCategory` for anything left unset.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisplayMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<Icon>,
+    /// Accent color as `#rrggbb`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
 }
 
-#[derive(Debug, Default)]
+impl DisplayMeta {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn icon(mut self, icon: Icon) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
+    /// Ignores anything but `#rrggbb`, so a typo leaves the host default
+    /// rather than an unreadable node.
+    pub fn color(mut self, color: impl Into<String>) -> Self {
+        let color = color.into();
+        let is_hex = color.len() == 7
+            && color.starts_with('#')
+            && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+        if is_hex {
+            self.color = Some(color);
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Icon {
+    /// A glyph from the host's icon set, e.g. `"filter"` or `"usb"`.
+    Glyph { name: String },
+    /// SVG document text.
+    Svg { data: String },
+    /// PNG file bytes, ideally square and at least 32 px.
+    Png { data: Vec<u8> },
+}
+
+/// Coarse grouping used by host plugin browsers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginCategory {
+    Generator,
+    Filter,
+    Analyzer,
+    Sink,
+    Device,
+    Logger,
+    Utility,
+    // Also used for categories added by newer plugins.
+    #[default]
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    FileRead,
+    FileWrite,
+    Network,
+    SerialPort,
+    SharedMemory,
+    SpawnThreads,
+    // Capabilities added by newer plugins; only allowed when listed
+    // explicitly, so an older host never grants them by accident.
+    #[serde(other)]
+    Unknown,
+}
+
+impl std::fmt::Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Capability::FileRead => "file_read",
+            Capability::FileWrite => "file_write",
+            Capability::Network => "network",
+            Capability::SerialPort => "serial_port",
+            Capability::SharedMemory => "shared_memory",
+            Capability::SpawnThreads => "spawn_threads",
+            Capability::Unknown => "unknown",
+        })
+    }
+}
+
+impl PluginMeta {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            uid: None,
+            fixed_vars: Vec::new(),
+            default_vars: Vec::new(),
+            version: None,
+            author: None,
+            description: None,
+            license: None,
+            homepage: None,
+            category: PluginCategory::Other,
+            tags: Vec::new(),
+            capabilities: Vec::new(),
+            display: None,
+            translations: i18n::Translations::new(),
+        }
+    }
+
+    pub fn uid(mut self, uid: impl Into<String>) -> Self {
+        self.uid = Some(PluginUid(uid.into()));
+        self
+    }
+
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    pub fn author(mut self, author: impl Into<String>) -> Self {
+        self.author = Some(author.into());
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn license(mut self, license: impl Into<String>) -> Self {
+        self.license = Some(license.into());
+        self
+    }
+
+    pub fn homepage(mut self, homepage: impl Into<String>) -> Self {
+        self.homepage = Some(homepage.into());
+        self
+    }
+
+    pub fn category(mut self, category: PluginCategory) -> Self {
+        self.category = category;
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    pub fn capability(mut self, capability: Capability) -> Self {
+        if !self.capabilities.contains(&capability) {
+            self.capabilities.push(capability);
+        }
+        self
+    }
+
+    pub fn display(mut self, display: DisplayMeta) -> Self {
+        self.display = Some(display);
+        self
+    }
+
+    /// Declared capabilities missing from `allowed`; empty when the plugin
+    /// stays within it.
+    pub fn excess_capabilities(&self, allowed: &[Capability]) -> Vec<Capability> {
+        self.capabilities
+            .iter()
+            .filter(|capability| !allowed.contains(capability))
+            .copied()
+            .collect()
+    }
+
+    // Case-insensitive.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    // Plugins without a version never satisfy a constraint.
+    pub fn version_at_least(&self, major: u64, minor: u64, patch: u64) -> bool {
+        self.version
+            .as_deref()
+            .is_some_and(|version| parse_version(version) >= (major, minor, patch))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HostInfo {
+    pub name: String,
+    pub version: String,
+    pub platform: String,
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+impl HostInfo {
+    pub fn new(name: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+            platform: format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH),
+            features: Vec::new(),
+        }
+    }
+
+    pub fn feature(mut self, feature: impl Into<String>) -> Self {
+        self.features.push(feature.into());
+        self
+    }
+
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+
+    pub fn version_at_least(&self, major: u64, minor: u64, patch: u64) -> bool {
+        parse_version(&self.version) >= (major, minor, patch)
+    }
+}
+
+// Missing or non-numeric version components compare as 0.
+fn parse_version(version: &str) -> (u64, u64, u64) {
+    let mut parts = version
+        .split(['.', '-', '+'])
+        .map(|p| p.parse::<u64>().unwrap_or(0));
+    (
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShutdownStatus {
+    Pending,
+    Done,
+}
+
+impl ShutdownStatus {
+    pub fn is_done(self) -> bool {
+        self == ShutdownStatus::Done
+    }
+}
+
+#[derive(Debug)]
 pub struct PluginContext {
     pub tick: u64,
     pub period_seconds: f64,
+    // Time of the current tick, taken once by the host so plugins never read
+    // the OS clock on the realtime path. `wall_time` is for timestamps that
+    // leave the process, `monotonic_ns` for intervals. See `stamp_now`.
+    pub wall_time: SystemTime,
+    pub monotonic_ns: u64,
+    // Ticks the host skipped right before this one because it fell behind
+    // (also reported through `Plugin::on_overrun`), and how late this tick
+    // started relative to its deadline, negative when early.
+    pub missed_ticks: u64,
+    pub jitter_ns: i64,
+    // Samples per second and per `process` call, announced through
+    // `Plugin::prepare`. Zero when the host does not set them; see
+    // `effective_sample_rate`.
+    pub sample_rate: f64,
+    pub block_size: usize,
+    pub host: HostInfo,
+    // Bit `i` is set while port `i` has at least one connection. Ports 63 and
+    // above share bit 63, as in `ports::ChangedOutputs`. Maintained by the host.
+    pub connected_inputs: u64,
+    pub connected_outputs: u64,
+    // Tick of the last `set_input` per input index, `None` until the first
+    // update. Maintained by the host through `mark_input_updated`.
+    pub input_update_ticks: Vec<Option<u64>>,
+    pub logger: logging::Logger,
+    // Events emitted during the current call, drained by the host afterwards
+    // (see `event::EventBus::collect`).
+    pub events: Vec<event::Event>,
+    // Parameter ramps starting on this tick, for plugins whose behavior sets
+    // `supports_automation`. Set by the host before `process`, cleared after.
+    pub automation: Vec<automation::ParamChange>,
+    // Counters, gauges and histograms for the host dashboard. They persist
+    // across ticks; hosts read `take_changed` after `process`.
+    pub metrics: metrics::Metrics,
+}
+
+impl Default for PluginContext {
+    fn default() -> Self {
+        Self {
+            tick: 0,
+            period_seconds: 0.0,
+            wall_time: SystemTime::UNIX_EPOCH,
+            monotonic_ns: 0,
+            missed_ticks: 0,
+            jitter_ns: 0,
+            sample_rate: 0.0,
+            block_size: 0,
+            host: HostInfo::default(),
+            connected_inputs: 0,
+            connected_outputs: 0,
+            input_update_ticks: Vec::new(),
+            logger: logging::Logger::default(),
+            events: Vec::new(),
+            automation: Vec::new(),
+            metrics: metrics::Metrics::new(),
+        }
+    }
+}
+
+impl PluginContext {
+    /// Sets `wall_time` and `monotonic_ns` from the OS clocks. Monotonic time
+    /// counts from the first call in the process.
+    pub fn stamp_now(&mut self) {
+        static START: OnceLock<Instant> = OnceLock::new();
+        let start = *START.get_or_init(Instant::now);
+        self.wall_time = SystemTime::now();
+        self.monotonic_ns = start.elapsed().as_nanos() as u64;
+    }
+
+    /// `wall_time` as nanoseconds since the Unix epoch.
+    pub fn wall_time_ns(&self) -> u64 {
+        self.wall_time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |since| since.as_nanos() as u64)
+    }
+
+    pub fn connected_input_count(&self) -> u32 {
+        self.connected_inputs.count_ones()
+    }
+
+    pub fn connected_output_count(&self) -> u32 {
+        self.connected_outputs.count_ones()
+    }
+
+    pub fn is_input_connected(&self, index: usize) -> bool {
+        self.connected_inputs & ports::ChangedOutputs::bit(index) != 0
+    }
+
+    pub fn is_output_connected(&self, index: usize) -> bool {
+        self.connected_outputs & ports::ChangedOutputs::bit(index) != 0
+    }
+
+    /// `sample_rate`, or the tick rate `1 / period_seconds` when unset.
+    pub fn effective_sample_rate(&self) -> f64 {
+        if self.sample_rate > 0.0 {
+            self.sample_rate
+        } else if self.period_seconds > 0.0 {
+            1.0 / self.period_seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// Records that input `index` received a value on the current tick.
+    pub fn mark_input_updated(&mut self, index: usize) {
+        if self.input_update_ticks.len() <= index {
+            self.input_update_ticks.resize(index + 1, None);
+        }
+        self.input_update_ticks[index] = Some(self.tick);
+    }
+
+    pub fn last_update_tick(&self, index: usize) -> Option<u64> {
+        self.input_update_ticks.get(index).copied().flatten()
+    }
+
+    /// Ticks since input `index` was last updated.
+    pub fn input_age_ticks(&self, index: usize) -> Option<u64> {
+        self.last_update_tick(index)
+            .map(|tick| self.tick.saturating_sub(tick))
+    }
+
+    /// True if input `index` has not been updated within `max_age_seconds`,
+    /// or never at all.
+    pub fn is_input_stale(&self, index: usize, max_age_seconds: f64) -> bool {
+        self.input_age_ticks(index)
+            .is_none_or(|age| age as f64 * self.period_seconds > max_age_seconds)
+    }
+
+    /// The ramp starting on this tick for parameter `key`, if any.
+    pub fn automation_for(&self, key: &str) -> Option<&automation::ParamChange> {
+        self.automation
+            .iter()
+            .rev()
+            .find(|change| change.key == key)
+    }
+
+    /// Queues `event` for other plugins, stamped with the current tick's
+    /// time unless it already has a timestamp.
+    pub fn emit(&mut self, mut event: event::Event) {
+        if event.timestamp.is_none() {
+            event.timestamp = Some(self.tick as f64 * self.period_seconds);
+        }
+        self.events.push(event);
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
 pub enum PluginError {
-    #[error("processing failed")]
-    ProcessingFailed,
+    #[error("invalid configuration: {0}")]
+    Config(String),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("device error {code}: {message}")]
+    Device { code: i32, message: String },
+    #[error("fatal: {0}")]
+    Fatal(String),
+    #[error("{0}")]
+    Recoverable(String),
+}
+
+/// How the host should react to a [`PluginError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorSeverity {
+    /// Retry on the next tick.
+    Recoverable,
+    /// Keep the plugin loaded but ask the user to fix its configuration.
+    Reconfigure,
+    /// Stop and unload the plugin.
+    Fatal,
+}
+
+impl PluginError {
+    pub fn severity(&self) -> ErrorSeverity {
+        match self {
+            PluginError::Config(_) => ErrorSeverity::Reconfigure,
+            PluginError::Fatal(_) => ErrorSeverity::Fatal,
+            PluginError::Io(_) | PluginError::Device { .. } | PluginError::Recoverable(_) => {
+                ErrorSeverity::Recoverable
+            }
+        }
+    }
+
+    pub fn is_fatal(&self) -> bool {
+        self.severity() == ErrorSeverity::Fatal
+    }
 }
 
 pub trait Plugin: Send {
@@ -56,6 +783,12 @@ pub trait Plugin: Send {
         ui::ConnectionBehavior::default()
     }
 
+    // Instance-specific parameters supplied by the host at creation,
+    // before any configuration is applied
+    fn init_params(&mut self, _params: Value) -> Result<(), PluginError> {
+        Ok(())
+    }
+
     // NEW: Dynamic input management
     fn on_input_added(&mut self, _port: &str) -> Result<(), PluginError> {
         Ok(())
@@ -64,17 +797,225 @@ pub trait Plugin: Send {
     fn on_input_removed(&mut self, _port: &str) -> Result<(), PluginError> {
         Ok(())
     }
+
+    fn on_output_added(&mut self, _port: &str) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    fn on_output_removed(&mut self, _port: &str) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    // Incremental configuration updates
+    fn on_config_changed(&mut self, _changed: &config::ConfigDelta) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    // Internal state persistence across sessions and hot reloads
+    fn save_state(&self) -> Result<Value, PluginError> {
+        Ok(Value::Null)
+    }
+
+    fn restore_state(&mut self, _state: Value) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    // Upgrades a configuration saved under an older `UISchema::schema_version`
+    // to the current layout, e.g. when importing a `preset::PresetBundle`.
+    fn migrate_config(&self, _from_version: u32, old: Value) -> Result<Value, PluginError> {
+        Ok(old)
+    }
+
+    // Current options of the choice field `key` declared with
+    // `ConfigField::options_from_plugin`, e.g. the attached audio devices.
+    // Called from the UI thread whenever the dropdown is opened.
+    fn options_for(&self, _key: &str) -> Vec<ui::ChoiceOption> {
+        Vec::new()
+    }
+
+    // Named operations triggered by the host, e.g. `config::CONFIG_HISTORY_ACTION`.
+    // Returns `None` for actions the plugin does not know.
+    fn action(&mut self, _name: &str, _args: &Value) -> Result<Option<Value>, PluginError> {
+        Ok(None)
+    }
+
+    // Pressing a `ui::FieldType::Action` button. The default runs `action`
+    // with empty arguments and fails for actions it does not know.
+    fn on_action(&mut self, action_id: &str) -> Result<(), PluginError> {
+        match self.action(action_id, &Value::Object(Default::default()))? {
+            Some(_) => Ok(()),
+            None => Err(PluginError::Config(format!("unknown action {action_id}"))),
+        }
+    }
+
+    // Events emitted by other plugins through `PluginContext::emit`, delivered
+    // between ticks.
+    fn on_event(&mut self, _event: &event::Event) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    // Current output delay in ticks. Override when it depends on the
+    // configuration; the host re-reads it after `on_config_changed`.
+    fn latency_ticks(&self) -> u64 {
+        self.behavior().latency_ticks
+    }
+
+    // Runtime toggles, separate from configuration (see `features::FeatureSet`).
+    fn features(&self) -> Vec<features::FeatureFlag> {
+        Vec::new()
+    }
+
+    fn set_feature_enabled(&mut self, name: &str, _enabled: bool) -> Result<(), PluginError> {
+        Err(features::unknown_feature(name))
+    }
+
+    // Workspace snippets built around this plugin, offered by hosts in a
+    // "new from template" menu.
+    fn templates(&self) -> Vec<template::WorkspaceTemplate> {
+        Vec::new()
+    }
+
+    // Factory presets offered next to the user's own, see `preset::apply_preset`.
+    fn presets(&self) -> Vec<preset::Preset> {
+        Vec::new()
+    }
+
+    // Sample output for the host's plugin palette. Must be cheap: hosts go
+    // through `preview::checked_preview`, which enforces the bounds.
+    fn preview(&self) -> Option<preview::Preview> {
+        None
+    }
+
+    // Called before the first `process` and whenever the sample rate or block
+    // size changes, so filters can compute their coefficients once.
+    fn prepare(&mut self, _sample_rate: f64, _block_size: usize) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    // The host fell behind and skipped `missed` ticks; called before the next
+    // `process`, e.g. so integrators can account for the lost time.
+    fn on_overrun(&mut self, _missed: u64) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    // Lifecycle notifications from the host
+    fn on_start(&mut self) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    fn on_stop(&mut self) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    fn on_pause(&mut self) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    fn on_resume(&mut self) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    fn reset(&mut self) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    // Bitmask of outputs changed since the last call (see `ports::ChangedOutputs`).
+    // The default reports every output as changed so hosts keep polling.
+    fn take_changed_outputs(&mut self) -> u64 {
+        u64::MAX
+    }
+
+    // Whole frames for ports declared with `Port::vector`, e.g. FFT bins or
+    // multi-channel samples. Inputs that do not take vectors fail.
+    fn set_input_vec(&mut self, name: &str, _values: &[f64]) -> Result<(), PluginError> {
+        Err(PluginError::Config(format!(
+            "input {name} does not take vectors"
+        )))
+    }
+
+    // Copies up to `out.len()` elements of output `name` and returns its full
+    // length, so callers can retry with a larger buffer (`ports::write_vec`).
+    // Unknown and scalar outputs have length 0.
+    fn get_output_vec(&self, _name: &str, _out: &mut [f64]) -> usize {
+        0
+    }
+
+    // Current text of an output declared with `Port::text`, e.g. a status
+    // message or a decoded packet. `None` for unknown and numeric outputs.
+    fn get_output_string(&self, _name: &str) -> Option<String> {
+        None
+    }
+
+    // Two-phase shutdown: the host calls `begin_shutdown` once, then keeps
+    // calling `poll_shutdown` until it reports `Done` or the deadline in
+    // `PluginBehavior::max_shutdown_seconds` elapses.
+    fn begin_shutdown(&mut self) -> Result<ShutdownStatus, PluginError> {
+        Ok(ShutdownStatus::Done)
+    }
+
+    fn poll_shutdown(&mut self) -> Result<ShutdownStatus, PluginError> {
+        Ok(ShutdownStatus::Done)
+    }
+
+    // Independent copy with the same configuration and state, used for
+    // shadow instances. Plugins that can duplicate themselves should also
+    // declare `ComparisonMode::Shadow` in their behavior.
+    fn duplicate(&self) -> Option<Box<dyn Plugin>> {
+        None
+    }
 }
 
 pub trait DeviceDriver: Plugin {
     fn open(&mut self) -> Result<(), PluginError>;
     fn close(&mut self) -> Result<(), PluginError>;
+
+    // Devices the driver can talk to right now, for the host's device picker.
+    fn enumerate(&self) -> Vec<device::DeviceInfo> {
+        Vec::new()
+    }
+
+    // Chooses the device the next `open` connects to.
+    fn select(&mut self, _device: &device::DeviceInfo) -> Result<(), PluginError> {
+        Err(device::selection_unsupported())
+    }
+
+    // The device was unplugged or stopped answering mid-run. Drivers drop
+    // their handles and keep producing placeholder outputs until reconnected.
+    fn on_device_lost(&mut self) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    // The device is back, reported by the OS or after a successful reopen.
+    fn on_device_available(&mut self) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    // How `device::reconnect` retries `open` after a loss.
+    fn reconnect_policy(&self) -> device::ReconnectPolicy {
+        device::ReconnectPolicy::default()
+    }
 }
 
 pub trait ProcessingUnit: Plugin {}
 
 pub trait EventLogger: Plugin {
     fn flush(&mut self) -> Result<(), PluginError>;
+
+    // Structured records from the host or other plugins, alongside the values
+    // arriving on the inputs. Loggers that only record inputs ignore them.
+    fn log_event(&mut self, _record: event_log::LogRecord) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    // When the host should call `flush`, see `event_log::FlushTimer`.
+    fn flush_policy(&self) -> event_log::FlushPolicy {
+        event_log::FlushPolicy::default()
+    }
+
+    // Records buffered since the last `flush`.
+    fn pending(&self) -> usize {
+        0
+    }
 }
 
 #[repr(C)]
@@ -85,6 +1026,15 @@ pub struct PluginString {
 }
 
 impl PluginString {
+    /// Empty value with a null pointer, e.g. for "no result".
+    pub fn null() -> Self {
+        PluginString {
+            ptr: std::ptr::null_mut(),
+            len: 0,
+            cap: 0,
+        }
+    }
+
     pub fn from_string(value: String) -> Self {
         let mut bytes = value.into_bytes();
         let out = PluginString {
@@ -96,12 +1046,120 @@ impl PluginString {
         out
     }
 
+    /// # Safety
+    ///
+    /// `self` must have been produced by [`PluginString::from_string`] and not
+    /// already consumed or freed.
     pub unsafe fn into_string(self) -> String {
         let bytes = Vec::from_raw_parts(self.ptr, self.len, self.cap);
         String::from_utf8_lossy(&bytes).into_owned()
     }
 }
 
+/// Returned by `resolve_input`/`resolve_output` of [`PluginApi`] for unknown
+/// port names.
+pub const INVALID_PORT_HANDLE: u32 = u32::MAX;
+
+/// Outcome of a checked port access. The checked calls of [`PluginApi`]
+/// return it as the matching [`RtsynStatus`] code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PortStatus {
+    Ok,
+    /// No port of either direction has this name or alias.
+    UnknownPort,
+    /// The name belongs to a port of the other direction, e.g. writing to
+    /// an output.
+    WrongDirection,
+}
+
+impl PortStatus {
+    pub fn code(self) -> i32 {
+        RtsynStatus::from(self).code()
+    }
+
+    /// Codes other than the port ones and `RTSYN_STATUS_OK` are reported as
+    /// `UnknownPort`.
+    pub fn from_code(code: i32) -> Self {
+        match RtsynStatus::from_code(code) {
+            RtsynStatus::Ok => PortStatus::Ok,
+            RtsynStatus::WrongDirection => PortStatus::WrongDirection,
+            _ => PortStatus::UnknownPort,
+        }
+    }
+
+    pub fn is_ok(self) -> bool {
+        self == PortStatus::Ok
+    }
+
+    /// `Ok` for `PortStatus::Ok`, otherwise a configuration error naming
+    /// `port`.
+    pub fn check(self, port: &str) -> Result<(), PluginError> {
+        RtsynStatus::from(self).into_result(port)
+    }
+}
+
+impl From<PortStatus> for RtsynStatus {
+    fn from(status: PortStatus) -> Self {
+        match status {
+            PortStatus::Ok => RtsynStatus::Ok,
+            PortStatus::UnknownPort => RtsynStatus::UnknownPort,
+            PortStatus::WrongDirection => RtsynStatus::WrongDirection,
+        }
+    }
+}
+
+/// Borrowed UTF-8 port name, as passed in arrays to the batch calls of
+/// [`PluginApi`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PortName {
+    pub ptr: *const u8,
+    pub len: usize,
+}
+
+impl PortName {
+    /// Borrows `name`; the result must not outlive it.
+    pub fn new(name: &str) -> Self {
+        PortName {
+            ptr: name.as_ptr(),
+            len: name.len(),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The name must still be alive and valid UTF-8.
+    pub unsafe fn as_str<'a>(&self) -> &'a str {
+        std::str::from_utf8_unchecked(std::slice::from_raw_parts(self.ptr, self.len))
+    }
+
+    /// Like [`PortName::as_str`], but checks the bytes, so FFI entries can
+    /// answer `RTSYN_STATUS_UTF8_ERROR` instead of trusting the caller.
+    ///
+    /// # Safety
+    ///
+    /// The name must still be alive.
+    pub unsafe fn to_str<'a>(&self) -> Result<&'a str, std::str::Utf8Error> {
+        std::str::from_utf8(std::slice::from_raw_parts(self.ptr, self.len))
+    }
+}
+
+/// Copies `len` bytes at `data` into a [`PluginString`], for plugins that
+/// are not written in Rust. Invalid UTF-8 is replaced with U+FFFD.
+///
+/// # Safety
+///
+/// `data` must be null or valid for reads of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rtsyn_plugin_string_new(data: *const u8, len: usize) -> PluginString {
+    if data.is_null() {
+        return PluginString::null();
+    }
+    let bytes = std::slice::from_raw_parts(data, len);
+    PluginString::from_string(String::from_utf8_lossy(bytes).into_owned())
+}
+
 #[no_mangle]
 pub extern "C" fn rtsyn_plugin_string_free(value: PluginString) {
     if value.ptr.is_null() {
@@ -112,8 +1170,19 @@ pub extern "C" fn rtsyn_plugin_string_free(value: PluginString) {
     }
 }
 
+/// Function table a plugin library exports through [`RTSYN_PLUGIN_API_SYMBOL`].
+///
+/// Entries are only ever appended, and every entry after `get_output` is
+/// optional. Hosts read `struct_size` first and treat entries past it as
+/// absent, so a plugin built against an older, shorter table still loads.
+/// Build tables with [`PluginApi::new`] to get the size right.
+///
+/// Entries that can fail return an [`RtsynStatus`] code and hand back
+/// their result, if any, through an out-parameter.
 #[repr(C)]
 pub struct PluginApi {
+    // `size_of::<PluginApi>()` of the crate version the plugin was built with.
+    pub struct_size: usize,
     pub create: extern "C" fn(id: u64) -> *mut std::ffi::c_void,
     pub destroy: extern "C" fn(handle: *mut std::ffi::c_void),
     pub meta_json: extern "C" fn(handle: *mut std::ffi::c_void) -> PluginString,
@@ -121,12 +1190,318 @@ pub struct PluginApi {
     pub outputs_json: extern "C" fn(handle: *mut std::ffi::c_void) -> PluginString,
     pub behavior_json: Option<extern "C" fn(handle: *mut std::ffi::c_void) -> PluginString>,
     pub ui_schema_json: Option<extern "C" fn(handle: *mut std::ffi::c_void) -> PluginString>,
-    pub set_config_json: extern "C" fn(handle: *mut std::ffi::c_void, data: *const u8, len: usize),
+    pub set_config_json:
+        extern "C" fn(handle: *mut std::ffi::c_void, data: *const u8, len: usize) -> i32,
     pub set_input:
         extern "C" fn(handle: *mut std::ffi::c_void, name: *const u8, len: usize, value: f64),
-    pub process: extern "C" fn(handle: *mut std::ffi::c_void, tick: u64, period_seconds: f64),
+    pub process:
+        extern "C" fn(handle: *mut std::ffi::c_void, tick: u64, period_seconds: f64) -> i32,
     pub get_output:
         extern "C" fn(handle: *mut std::ffi::c_void, name: *const u8, len: usize) -> f64,
+    pub get_state: Option<extern "C" fn(handle: *mut std::ffi::c_void) -> PluginString>,
+    pub set_state:
+        Option<extern "C" fn(handle: *mut std::ffi::c_void, data: *const u8, len: usize) -> i32>,
+    pub start: Option<extern "C" fn(handle: *mut std::ffi::c_void) -> i32>,
+    pub stop: Option<extern "C" fn(handle: *mut std::ffi::c_void) -> i32>,
+    pub pause: Option<extern "C" fn(handle: *mut std::ffi::c_void) -> i32>,
+    pub resume: Option<extern "C" fn(handle: *mut std::ffi::c_void) -> i32>,
+    pub reset: Option<extern "C" fn(handle: *mut std::ffi::c_void) -> i32>,
+    pub changed_outputs_mask: Option<extern "C" fn(handle: *mut std::ffi::c_void) -> u64>,
+    pub set_host_info_json:
+        Option<extern "C" fn(handle: *mut std::ffi::c_void, data: *const u8, len: usize) -> i32>,
+    // Set `done` once shutdown has completed.
+    pub begin_shutdown:
+        Option<extern "C" fn(handle: *mut std::ffi::c_void, done: *mut bool) -> i32>,
+    pub poll_shutdown: Option<extern "C" fn(handle: *mut std::ffi::c_void, done: *mut bool) -> i32>,
+    pub create_with_params:
+        Option<extern "C" fn(id: u64, data: *const u8, len: usize) -> *mut std::ffi::c_void>,
+    // Connection bitmasks as in `PluginContext::connected_inputs`, sent before
+    // `process` whenever they change.
+    pub set_connections:
+        Option<extern "C" fn(handle: *mut std::ffi::c_void, inputs: u64, outputs: u64)>,
+    // Like `create`, with host services the plugin may keep using until it is
+    // destroyed. `host` may be null.
+    pub create_with_host:
+        Option<extern "C" fn(id: u64, host: *const host::RTSynHostApi) -> *mut std::ffi::c_void>,
+    // Runs `Plugin::action` with JSON `args` and stores the JSON result in
+    // `out`, or leaves it null for unknown actions.
+    pub action_json: Option<
+        extern "C" fn(
+            handle: *mut std::ffi::c_void,
+            name: *const u8,
+            name_len: usize,
+            args: *const u8,
+            args_len: usize,
+            out: *mut PluginString,
+        ) -> i32,
+    >,
+    // Port values as raw integers in the port's `NumericFormat`, for
+    // bit-exact fixed-point pipelines.
+    pub set_input_raw: Option<
+        extern "C" fn(handle: *mut std::ffi::c_void, name: *const u8, len: usize, value: i64),
+    >,
+    pub get_output_raw:
+        Option<extern "C" fn(handle: *mut std::ffi::c_void, name: *const u8, len: usize) -> i64>,
+    // Delivers one JSON `event::Event`.
+    pub on_event_json:
+        Option<extern "C" fn(handle: *mut std::ffi::c_void, data: *const u8, len: usize) -> i32>,
+    // JSON array of the events emitted since the last call, polled after
+    // `process`.
+    pub take_events_json: Option<extern "C" fn(handle: *mut std::ffi::c_void) -> PluginString>,
+    // `Plugin::latency_ticks`; without it the host uses the behavior field.
+    pub latency_ticks: Option<extern "C" fn(handle: *mut std::ffi::c_void) -> u64>,
+    // JSON array of `features::FeatureFlag`.
+    pub features_json: Option<extern "C" fn(handle: *mut std::ffi::c_void) -> PluginString>,
+    pub set_feature_enabled: Option<
+        extern "C" fn(
+            handle: *mut std::ffi::c_void,
+            name: *const u8,
+            len: usize,
+            enabled: bool,
+        ) -> i32,
+    >,
+    // One call per tick instead of one per port: `count` names and values.
+    // Unknown names are skipped, read as `0.0` and reported as
+    // `RTSYN_STATUS_UNKNOWN_PORT` once all known ports were handled.
+    pub set_inputs: Option<
+        extern "C" fn(
+            handle: *mut std::ffi::c_void,
+            names: *const PortName,
+            values: *const f64,
+            count: usize,
+        ) -> i32,
+    >,
+    pub get_outputs: Option<
+        extern "C" fn(
+            handle: *mut std::ffi::c_void,
+            names: *const PortName,
+            out_values: *mut f64,
+            count: usize,
+        ) -> i32,
+    >,
+    // JSON `preview::Preview`, or a null string for none.
+    pub preview_json: Option<extern "C" fn(handle: *mut std::ffi::c_void) -> PluginString>,
+    // Port names resolved once to integer handles for the `*_by_handle`
+    // calls, or `INVALID_PORT_HANDLE`. Handles stay valid until the port
+    // set changes.
+    pub resolve_input:
+        Option<extern "C" fn(handle: *mut std::ffi::c_void, name: *const u8, len: usize) -> u32>,
+    pub resolve_output:
+        Option<extern "C" fn(handle: *mut std::ffi::c_void, name: *const u8, len: usize) -> u32>,
+    pub set_input_by_handle:
+        Option<extern "C" fn(handle: *mut std::ffi::c_void, port: u32, value: f64) -> i32>,
+    pub get_output_by_handle:
+        Option<extern "C" fn(handle: *mut std::ffi::c_void, port: u32) -> f64>,
+    // `Plugin::prepare`.
+    pub prepare: Option<
+        extern "C" fn(handle: *mut std::ffi::c_void, sample_rate: f64, block_size: usize) -> i32,
+    >,
+    // `Plugin::on_overrun`.
+    pub on_overrun: Option<extern "C" fn(handle: *mut std::ffi::c_void, missed: u64) -> i32>,
+    // JSON array of `template::WorkspaceTemplate`.
+    pub templates_json: Option<extern "C" fn(handle: *mut std::ffi::c_void) -> PluginString>,
+    // `set_input`/`get_output` returning `RTSYN_STATUS_UNKNOWN_PORT` or
+    // `RTSYN_STATUS_WRONG_DIRECTION` (see `PortStatus::code`) instead of
+    // ignoring unknown names. `out_value` is left untouched on failure.
+    pub set_input_checked: Option<
+        extern "C" fn(
+            handle: *mut std::ffi::c_void,
+            name: *const u8,
+            len: usize,
+            value: f64,
+        ) -> i32,
+    >,
+    pub get_output_checked: Option<
+        extern "C" fn(
+            handle: *mut std::ffi::c_void,
+            name: *const u8,
+            len: usize,
+            out_value: *mut f64,
+        ) -> i32,
+    >,
+    // `Plugin::migrate_config`; stores the migrated JSON configuration in
+    // `out`.
+    pub migrate_config_json: Option<
+        extern "C" fn(
+            handle: *mut std::ffi::c_void,
+            from_version: u32,
+            data: *const u8,
+            len: usize,
+            out: *mut PluginString,
+        ) -> i32,
+    >,
+    // JSON array of `preset::Preset`, the factory presets.
+    pub presets_json: Option<extern "C" fn(handle: *mut std::ffi::c_void) -> PluginString>,
+    // One `automation::ParamChange`, delivered before the `process` call of
+    // the tick it starts on.
+    pub automate: Option<
+        extern "C" fn(
+            handle: *mut std::ffi::c_void,
+            key: *const u8,
+            len: usize,
+            target: f64,
+            ramp_ticks: u64,
+        ) -> i32,
+    >,
+    // Vector ports, see `Plugin::set_input_vec` and `Plugin::get_output_vec`.
+    // `get_output_vec` returns the full length even when it exceeds `cap`.
+    pub set_input_vec: Option<
+        extern "C" fn(
+            handle: *mut std::ffi::c_void,
+            name: *const u8,
+            len: usize,
+            values: *const f64,
+            count: usize,
+        ) -> i32,
+    >,
+    pub get_output_vec: Option<
+        extern "C" fn(
+            handle: *mut std::ffi::c_void,
+            name: *const u8,
+            len: usize,
+            out: *mut f64,
+            cap: usize,
+        ) -> usize,
+    >,
+    // Text outputs; a null `PluginString` for unknown and numeric outputs.
+    pub get_output_string: Option<
+        extern "C" fn(handle: *mut std::ffi::c_void, name: *const u8, len: usize) -> PluginString,
+    >,
+    // JSON `metrics::MetricsSnapshot` of the plugin's `PluginContext::metrics`,
+    // read by the host on demand rather than every tick.
+    pub metrics_json: Option<extern "C" fn(handle: *mut std::ffi::c_void) -> PluginString>,
+    // JSON array of `ui::ChoiceOption`, see `Plugin::options_for`.
+    pub options_json: Option<
+        extern "C" fn(handle: *mut std::ffi::c_void, key: *const u8, len: usize) -> PluginString,
+    >,
+    // JSON `ui::ffi::ErrorDetails` of the last call that returned a plugin
+    // error status, read by the host right after that call.
+    pub last_error_json: Option<extern "C" fn(handle: *mut std::ffi::c_void) -> PluginString>,
+    // `Plugin::on_action`, for a pressed `ui::FieldType::Action` button.
+    pub invoke_action: Option<
+        extern "C" fn(handle: *mut std::ffi::c_void, action_id: *const u8, len: usize) -> i32,
+    >,
+    // `Plugin::on_output_added` and `on_output_removed`, for plugins with
+    // `ui::ExtendableOutputs`.
+    pub on_output_added:
+        Option<extern "C" fn(handle: *mut std::ffi::c_void, port: *const u8, len: usize) -> i32>,
+    pub on_output_removed:
+        Option<extern "C" fn(handle: *mut std::ffi::c_void, port: *const u8, len: usize) -> i32>,
 }
 
-pub const RTSYN_PLUGIN_API_SYMBOL: &str = "rtsyn_plugin_api";
+impl PluginApi {
+    /// Size of the required entries. Tables reporting a smaller
+    /// `struct_size` are rejected.
+    pub const MIN_SIZE: usize = std::mem::offset_of!(PluginApi, get_state);
+
+    /// Table with the required entries and every optional one unset.
+    /// Optional entries are filled in with struct update syntax:
+    ///
+    /// ```ignore
+    /// static API: PluginApi = PluginApi {
+    ///     ui_schema_json: Some(ui_schema_json),
+    ///     ..PluginApi::new(create, destroy, meta_json, inputs_json, outputs_json,
+    ///                      set_config_json, set_input, process, get_output)
+    /// };
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub const fn new(
+        create: extern "C" fn(id: u64) -> *mut std::ffi::c_void,
+        destroy: extern "C" fn(handle: *mut std::ffi::c_void),
+        meta_json: extern "C" fn(handle: *mut std::ffi::c_void) -> PluginString,
+        inputs_json: extern "C" fn(handle: *mut std::ffi::c_void) -> PluginString,
+        outputs_json: extern "C" fn(handle: *mut std::ffi::c_void) -> PluginString,
+        set_config_json: extern "C" fn(
+            handle: *mut std::ffi::c_void,
+            data: *const u8,
+            len: usize,
+        ) -> i32,
+        set_input: extern "C" fn(
+            handle: *mut std::ffi::c_void,
+            name: *const u8,
+            len: usize,
+            value: f64,
+        ),
+        process: extern "C" fn(
+            handle: *mut std::ffi::c_void,
+            tick: u64,
+            period_seconds: f64,
+        ) -> i32,
+        get_output: extern "C" fn(
+            handle: *mut std::ffi::c_void,
+            name: *const u8,
+            len: usize,
+        ) -> f64,
+    ) -> Self {
+        Self {
+            struct_size: std::mem::size_of::<PluginApi>(),
+            create,
+            destroy,
+            meta_json,
+            inputs_json,
+            outputs_json,
+            behavior_json: None,
+            ui_schema_json: None,
+            set_config_json,
+            set_input,
+            process,
+            get_output,
+            get_state: None,
+            set_state: None,
+            start: None,
+            stop: None,
+            pause: None,
+            resume: None,
+            reset: None,
+            changed_outputs_mask: None,
+            set_host_info_json: None,
+            begin_shutdown: None,
+            poll_shutdown: None,
+            create_with_params: None,
+            set_connections: None,
+            create_with_host: None,
+            action_json: None,
+            set_input_raw: None,
+            get_output_raw: None,
+            on_event_json: None,
+            take_events_json: None,
+            latency_ticks: None,
+            features_json: None,
+            set_feature_enabled: None,
+            set_inputs: None,
+            get_outputs: None,
+            preview_json: None,
+            resolve_input: None,
+            resolve_output: None,
+            set_input_by_handle: None,
+            get_output_by_handle: None,
+            prepare: None,
+            on_overrun: None,
+            templates_json: None,
+            set_input_checked: None,
+            get_output_checked: None,
+            migrate_config_json: None,
+            presets_json: None,
+            automate: None,
+            set_input_vec: None,
+            get_output_vec: None,
+            get_output_string: None,
+            metrics_json: None,
+            options_json: None,
+            last_error_json: None,
+            invoke_action: None,
+            on_output_added: None,
+            on_output_removed: None,
+        }
+    }
+}
+
+/// Name of the exported `extern "C" fn() -> *const PluginApi`. The suffix
+/// changes whenever the layout of the table changes incompatibly, so a host
+/// never reads a table in the wrong layout.
+pub const RTSYN_PLUGIN_API_SYMBOL: &str = "rtsyn_plugin_api_v2";
+
+#[doc(hidden)]
+pub mod __private {
+    pub use serde_json;
+}
@@ -0,0 +1,1917 @@
+use crate::automation::ParamChange;
+use crate::event::Event;
+use crate::features::FeatureFlag;
+use crate::fixed::NumericFormat;
+use crate::host::HostApiTable;
+use crate::metrics::MetricsSnapshot;
+use crate::port_key::PortKey;
+use crate::ports::{missing_port, resolve_port};
+use crate::preset::Preset;
+use crate::preview::Preview;
+use crate::replay::{self, Replay};
+use crate::resources::ResourceBundle;
+use crate::template::WorkspaceTemplate;
+use crate::ui::ffi::RtsynStatus;
+use crate::ui::{ChoiceOption, ConnectionBehavior, PluginBehavior, ThreadingModel, UISchema};
+use crate::{
+    Capability, HostInfo, Plugin, PluginApi, PluginContext, PluginError, PluginId, PluginMeta,
+    PluginString, PluginUid, Port, PortName, ShutdownStatus, INVALID_PORT_HANDLE,
+    RTSYN_PLUGIN_API_SYMBOL,
+};
+use libloading::{Library, Symbol};
+use serde_json::Value;
+use std::ffi::{c_void, OsStr};
+use std::marker::PhantomData;
+use std::path::Path;
+use std::ptr::NonNull;
+use std::sync::Arc;
+
+#[derive(thiserror::Error, Debug)]
+pub enum LoaderError {
+    #[error("failed to load library: {0}")]
+    Library(#[from] libloading::Error),
+    #[error("plugin api symbol returned null")]
+    NullApi,
+    #[error("plugin api table is too small ({0} bytes)")]
+    TruncatedApi(usize),
+    #[error("plugin was built against rtsyn_plugin 0.2 or older and must be rebuilt")]
+    LegacyApi,
+    #[error("plugin create returned null")]
+    CreateFailed,
+    #[error("plugin is pinned to the thread that created it")]
+    ThreadBound,
+    #[error("plugin requests capabilities that are not allowed: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    CapabilitiesDenied(Vec<Capability>),
+}
+
+/// A shared library exporting the [`RTSYN_PLUGIN_API_SYMBOL`] function.
+///
+/// The library stays loaded for as long as the `PluginLibrary` or any
+/// [`LoadedPlugin`] created from it is alive.
+pub struct PluginLibrary {
+    library: Arc<Library>,
+    api: Arc<PluginApi>,
+    resources: ResourceBundle,
+}
+
+impl PluginLibrary {
+    /// # Safety
+    ///
+    /// Loading a library runs its initialisers, and the library must export
+    /// [`RTSYN_PLUGIN_API_SYMBOL`] with the signature
+    /// `extern "C" fn() -> *const PluginApi`.
+    ///
+    /// Libraries that only export the unversioned table of older releases
+    /// fail with [`LoaderError::LegacyApi`] instead of being misread.
+    pub unsafe fn open(path: impl AsRef<OsStr>) -> Result<Self, LoaderError> {
+        let resources = ResourceBundle::for_library(Path::new(path.as_ref()));
+        let library = Library::new(path)?;
+        let api = {
+            let symbol: Symbol<extern "C" fn() -> *const PluginApi> =
+                match library.get(RTSYN_PLUGIN_API_SYMBOL.as_bytes()) {
+                    Ok(symbol) => symbol,
+                    Err(_) if library.get::<*const ()>(LEGACY_API_SYMBOL).is_ok() => {
+                        return Err(LoaderError::LegacyApi)
+                    }
+                    Err(err) => return Err(err.into()),
+                };
+            symbol()
+        };
+        if api.is_null() {
+            return Err(LoaderError::NullApi);
+        }
+        Ok(Self {
+            api: Arc::new(read_api(api)?),
+            library: Arc::new(library),
+            resources,
+        })
+    }
+
+    pub fn api(&self) -> &PluginApi {
+        &self.api
+    }
+
+    /// Resource directories found next to the library file.
+    pub fn resources(&self) -> &ResourceBundle {
+        &self.resources
+    }
+
+    /// Fails with [`LoaderError::ThreadBound`] for plugins that declare
+    /// [`ThreadingModel::Pinned`]; use [`PluginLibrary::create_handle`] for
+    /// those.
+    pub fn instantiate(&self, id: u64) -> Result<LoadedPlugin, LoaderError> {
+        self.wrap(id, (self.api().create)(id), None)
+    }
+
+    /// Like [`PluginLibrary::instantiate`], but fails with
+    /// [`LoaderError::CapabilitiesDenied`] when the plugin declares
+    /// capabilities outside `allowed`. The check reads the instance's meta,
+    /// so the rejected instance has already been created; it is destroyed
+    /// before this returns.
+    pub fn instantiate_allowing(
+        &self,
+        id: u64,
+        allowed: &[Capability],
+    ) -> Result<LoadedPlugin, LoaderError> {
+        let plugin = self.instantiate(id)?;
+        let excess = plugin.meta().excess_capabilities(allowed);
+        if !excess.is_empty() {
+            return Err(LoaderError::CapabilitiesDenied(excess));
+        }
+        Ok(plugin)
+    }
+
+    /// Creates an instance with host-assigned parameters.
+    ///
+    /// Falls back to plain `create` when the library does not export
+    /// `create_with_params`; the parameters are dropped in that case.
+    pub fn instantiate_with_params(
+        &self,
+        id: u64,
+        params: &Value,
+    ) -> Result<LoadedPlugin, LoaderError> {
+        let handle = match self.api().create_with_params {
+            Some(create_with_params) => {
+                let data = params.to_string();
+                create_with_params(id, data.as_ptr(), data.len())
+            }
+            None => (self.api().create)(id),
+        };
+        self.wrap(id, handle, None)
+    }
+
+    /// Creates an instance that can call back into `host`.
+    ///
+    /// The table is kept alive by the returned plugin. Falls back to plain
+    /// `create` when the library does not export `create_with_host`.
+    pub fn instantiate_with_host(
+        &self,
+        id: u64,
+        host: Arc<HostApiTable>,
+    ) -> Result<LoadedPlugin, LoaderError> {
+        let handle = match self.api().create_with_host {
+            Some(create_with_host) => create_with_host(id, host.as_ptr()),
+            None => (self.api().create)(id),
+        };
+        self.wrap(id, handle, Some(host))
+    }
+
+    /// Creates a bare instance without checking its threading model.
+    ///
+    /// The returned handle cannot leave the current thread; convert it with
+    /// [`OwnedPluginHandle::into_sendable`] if the plugin allows that.
+    pub fn create_handle(&self, id: u64) -> Result<OwnedPluginHandle<ThreadBound>, LoaderError> {
+        self.own((self.api().create)(id), None)
+    }
+
+    fn own(
+        &self,
+        handle: *mut c_void,
+        host: Option<Arc<HostApiTable>>,
+    ) -> Result<OwnedPluginHandle<ThreadBound>, LoaderError> {
+        let handle = NonNull::new(handle).ok_or(LoaderError::CreateFailed)?;
+        Ok(OwnedPluginHandle {
+            raw: RawHandle {
+                api: Arc::clone(&self.api),
+                handle,
+                _host: host,
+                _library: Arc::clone(&self.library),
+            },
+            _model: PhantomData,
+        })
+    }
+
+    fn wrap(
+        &self,
+        id: u64,
+        handle: *mut c_void,
+        host: Option<Arc<HostApiTable>>,
+    ) -> Result<LoadedPlugin, LoaderError> {
+        let handle = self
+            .own(handle, host)?
+            .into_sendable()
+            .map_err(|_| LoaderError::ThreadBound)?;
+        Ok(LoadedPlugin::new(PluginId(id), handle))
+    }
+}
+
+// Exported by plugins built before the table gained `struct_size`. Their
+// layout cannot be told apart from the current one, so they are refused.
+const LEGACY_API_SYMBOL: &[u8] = b"rtsyn_plugin_api";
+
+/// Copies the table at `api`, leaving entries past its `struct_size` unset.
+///
+/// # Safety
+///
+/// `api` must point to a table that starts with `struct_size` and is valid for
+/// reads of that many bytes.
+unsafe fn read_api(api: *const PluginApi) -> Result<PluginApi, LoaderError> {
+    let size = std::ptr::read(api as *const usize);
+    if size < PluginApi::MIN_SIZE {
+        return Err(LoaderError::TruncatedApi(size));
+    }
+    // An all-zero `Option<extern "C" fn>` is `None`.
+    let mut table = std::mem::MaybeUninit::<PluginApi>::zeroed();
+    std::ptr::copy_nonoverlapping(
+        api as *const u8,
+        table.as_mut_ptr() as *mut u8,
+        size.min(std::mem::size_of::<PluginApi>()),
+    );
+    Ok(table.assume_init())
+}
+
+/// Marker for handles that may be moved to another thread.
+pub enum Sendable {}
+
+/// Marker for handles that must stay on the thread that created them.
+pub enum ThreadBound {}
+
+/// Sole owner of a plugin instance created through a [`PluginApi`].
+///
+/// Dropping the handle calls `destroy` exactly once and keeps the library
+/// mapped until then. The handle is only `Send` as
+/// `OwnedPluginHandle<Sendable>`, which [`OwnedPluginHandle::into_sendable`]
+/// hands out when the plugin does not declare [`ThreadingModel::Pinned`].
+pub struct OwnedPluginHandle<M = Sendable> {
+    raw: RawHandle,
+    _model: PhantomData<M>,
+}
+
+// Fields drop in declaration order, so `destroy` runs before the host table
+// and the library are released.
+struct RawHandle {
+    api: Arc<PluginApi>,
+    handle: NonNull<c_void>,
+    _host: Option<Arc<HostApiTable>>,
+    _library: Arc<Library>,
+}
+
+impl Drop for RawHandle {
+    fn drop(&mut self) {
+        let destroy = self.api.destroy;
+        destroy(self.handle.as_ptr());
+    }
+}
+
+// The plugin declared that it may be called from any thread, and the handle is
+// only reachable through `&self`/`&mut self`, which serializes calls.
+unsafe impl Send for OwnedPluginHandle<Sendable> {}
+
+impl OwnedPluginHandle<ThreadBound> {
+    pub fn into_sendable(self) -> Result<OwnedPluginHandle<Sendable>, Self> {
+        if self.threading() != ThreadingModel::Any {
+            return Err(self);
+        }
+        Ok(OwnedPluginHandle {
+            raw: self.raw,
+            _model: PhantomData,
+        })
+    }
+}
+
+impl<M> OwnedPluginHandle<M> {
+    pub fn api(&self) -> &PluginApi {
+        &self.raw.api
+    }
+
+    /// Raw handle for calls this wrapper does not cover. It stays owned by
+    /// `self` and must not be destroyed by the caller.
+    pub fn as_ptr(&self) -> *mut c_void {
+        self.raw.handle.as_ptr()
+    }
+
+    pub fn meta_json(&self) -> Value {
+        read_json((self.api().meta_json)(self.as_ptr()))
+    }
+
+    pub fn inputs_json(&self) -> Value {
+        read_json((self.api().inputs_json)(self.as_ptr()))
+    }
+
+    pub fn outputs_json(&self) -> Value {
+        read_json((self.api().outputs_json)(self.as_ptr()))
+    }
+
+    pub fn behavior_json(&self) -> Option<Value> {
+        let behavior_json = self.api().behavior_json?;
+        Some(read_json(behavior_json(self.as_ptr())))
+    }
+
+    pub fn ui_schema_json(&self) -> Option<Value> {
+        let ui_schema_json = self.api().ui_schema_json?;
+        Some(read_json(ui_schema_json(self.as_ptr())))
+    }
+
+    pub fn threading(&self) -> ThreadingModel {
+        self.behavior_json()
+            .map(|value| behavior_from_json(&value).0.threading)
+            .unwrap_or_default()
+    }
+
+    pub fn set_host_info(&mut self, host: &HostInfo) -> Result<(), PluginError> {
+        let Some(set_host_info_json) = self.api().set_host_info_json else {
+            return Ok(());
+        };
+        let data = serde_json::to_string(host).unwrap_or_default();
+        self.check(
+            set_host_info_json(self.as_ptr(), data.as_ptr(), data.len()),
+            "set_host_info_json",
+        )
+    }
+
+    pub fn set_config(&mut self, config: &Value) -> Result<(), PluginError> {
+        let data = config.to_string();
+        let code = (self.api().set_config_json)(self.as_ptr(), data.as_ptr(), data.len());
+        self.check(code, "set_config_json")
+    }
+
+    pub fn set_input(&mut self, name: &str, value: f64) {
+        (self.api().set_input)(self.as_ptr(), name.as_ptr(), name.len(), value);
+    }
+
+    pub fn get_output(&self, name: &str) -> f64 {
+        (self.api().get_output)(self.as_ptr(), name.as_ptr(), name.len())
+    }
+
+    /// `None` when the plugin does not export `set_input_checked`.
+    pub fn set_input_checked(&mut self, name: &str, value: f64) -> Option<RtsynStatus> {
+        let set_input_checked = self.api().set_input_checked?;
+        let code = set_input_checked(self.as_ptr(), name.as_ptr(), name.len(), value);
+        Some(RtsynStatus::from_code(code))
+    }
+
+    /// `None` when the plugin does not export `get_output_checked`.
+    pub fn get_output_checked(&self, name: &str) -> Option<Result<f64, RtsynStatus>> {
+        let get_output_checked = self.api().get_output_checked?;
+        let mut value = 0.0;
+        let code = get_output_checked(self.as_ptr(), name.as_ptr(), name.len(), &mut value);
+        Some(match RtsynStatus::from_code(code) {
+            RtsynStatus::Ok => Ok(value),
+            status => Err(status),
+        })
+    }
+
+    /// `None` when the plugin does not export `set_input_vec`.
+    pub fn set_input_vec(&mut self, name: &str, values: &[f64]) -> Option<Result<(), PluginError>> {
+        let set_input_vec = self.api().set_input_vec?;
+        let code = set_input_vec(
+            self.as_ptr(),
+            name.as_ptr(),
+            name.len(),
+            values.as_ptr(),
+            values.len(),
+        );
+        Some(self.check(code, format_args!("set_input_vec {name}")))
+    }
+
+    /// `None` when the plugin does not export `get_output_vec`.
+    pub fn get_output_vec(&self, name: &str, out: &mut [f64]) -> Option<usize> {
+        let get_output_vec = self.api().get_output_vec?;
+        Some(get_output_vec(
+            self.as_ptr(),
+            name.as_ptr(),
+            name.len(),
+            out.as_mut_ptr(),
+            out.len(),
+        ))
+    }
+
+    /// `None` when the plugin does not export `get_output_string` or returns
+    /// null for `name`.
+    pub fn get_output_string(&self, name: &str) -> Option<String> {
+        let get_output_string = self.api().get_output_string?;
+        let value = get_output_string(self.as_ptr(), name.as_ptr(), name.len());
+        if value.ptr.is_null() {
+            return None;
+        }
+        Some(unsafe { value.into_string() })
+    }
+
+    /// Returns `false` when the plugin does not export `set_input_raw`.
+    pub fn set_input_raw(&mut self, name: &str, value: i64) -> bool {
+        let Some(set_input_raw) = self.api().set_input_raw else {
+            return false;
+        };
+        set_input_raw(self.as_ptr(), name.as_ptr(), name.len(), value);
+        true
+    }
+
+    /// `None` when the plugin does not export `get_output_raw`.
+    pub fn get_output_raw(&self, name: &str) -> Option<i64> {
+        let get_output_raw = self.api().get_output_raw?;
+        Some(get_output_raw(self.as_ptr(), name.as_ptr(), name.len()))
+    }
+
+    /// `None` when the plugin does not export `resolve_input` or does not
+    /// know the port.
+    pub fn resolve_input(&self, name: &str) -> Option<u32> {
+        let resolve_input = self.api().resolve_input?;
+        Some(resolve_input(self.as_ptr(), name.as_ptr(), name.len()))
+            .filter(|port| *port != INVALID_PORT_HANDLE)
+    }
+
+    pub fn resolve_output(&self, name: &str) -> Option<u32> {
+        let resolve_output = self.api().resolve_output?;
+        Some(resolve_output(self.as_ptr(), name.as_ptr(), name.len()))
+            .filter(|port| *port != INVALID_PORT_HANDLE)
+    }
+
+    /// `None` when the plugin does not export `set_input_by_handle`.
+    pub fn set_input_by_handle(
+        &mut self,
+        port: u32,
+        value: f64,
+    ) -> Option<Result<(), PluginError>> {
+        let set_input_by_handle = self.api().set_input_by_handle?;
+        let code = set_input_by_handle(self.as_ptr(), port, value);
+        Some(self.check(code, format_args!("set_input_by_handle {port}")))
+    }
+
+    /// `None` when the plugin does not export `get_output_by_handle`.
+    pub fn get_output_by_handle(&self, port: u32) -> Option<f64> {
+        let get_output_by_handle = self.api().get_output_by_handle?;
+        Some(get_output_by_handle(self.as_ptr(), port))
+    }
+
+    /// `None` when the plugin does not export `set_inputs`.
+    ///
+    /// # Panics
+    ///
+    /// If `names` and `values` differ in length.
+    pub fn set_inputs(
+        &mut self,
+        names: &[PortName],
+        values: &[f64],
+    ) -> Option<Result<(), PluginError>> {
+        assert_eq!(names.len(), values.len());
+        let set_inputs = self.api().set_inputs?;
+        let code = set_inputs(self.as_ptr(), names.as_ptr(), values.as_ptr(), names.len());
+        Some(self.check(code, "set_inputs"))
+    }
+
+    /// `None` when the plugin does not export `get_outputs`.
+    ///
+    /// # Panics
+    ///
+    /// If `names` and `out` differ in length.
+    pub fn get_outputs(
+        &self,
+        names: &[PortName],
+        out: &mut [f64],
+    ) -> Option<Result<(), PluginError>> {
+        assert_eq!(names.len(), out.len());
+        let get_outputs = self.api().get_outputs?;
+        let code = get_outputs(self.as_ptr(), names.as_ptr(), out.as_mut_ptr(), names.len());
+        Some(self.check(code, "get_outputs"))
+    }
+
+    pub fn set_connections(&mut self, inputs: u64, outputs: u64) {
+        if let Some(set_connections) = self.api().set_connections {
+            set_connections(self.as_ptr(), inputs, outputs);
+        }
+    }
+
+    pub fn process(&mut self, tick: u64, period_seconds: f64) -> Result<(), PluginError> {
+        let code = (self.api().process)(self.as_ptr(), tick, period_seconds);
+        self.check(code, "process")
+    }
+
+    pub fn prepare(&mut self, sample_rate: f64, block_size: usize) -> Result<(), PluginError> {
+        match self.api().prepare {
+            Some(prepare) => self.check(prepare(self.as_ptr(), sample_rate, block_size), "prepare"),
+            None => Ok(()),
+        }
+    }
+
+    pub fn on_overrun(&mut self, missed: u64) -> Result<(), PluginError> {
+        match self.api().on_overrun {
+            Some(on_overrun) => self.check(on_overrun(self.as_ptr(), missed), "on_overrun"),
+            None => Ok(()),
+        }
+    }
+
+    /// `None` when the plugin does not export `get_state`.
+    pub fn state(&self) -> Option<Value> {
+        let get_state = self.api().get_state?;
+        Some(read_json(get_state(self.as_ptr())))
+    }
+
+    pub fn set_state(&mut self, state: &Value) -> Result<(), PluginError> {
+        let Some(set_state) = self.api().set_state else {
+            return Ok(());
+        };
+        let data = state.to_string();
+        self.check(
+            set_state(self.as_ptr(), data.as_ptr(), data.len()),
+            "set_state",
+        )
+    }
+
+    /// `Ok(None)` when the plugin does not export `action_json` or does not
+    /// know the action.
+    pub fn action(&mut self, name: &str, args: &Value) -> Result<Option<Value>, PluginError> {
+        let Some(action_json) = self.api().action_json else {
+            return Ok(None);
+        };
+        let data = args.to_string();
+        let mut result = PluginString::null();
+        let code = action_json(
+            self.as_ptr(),
+            name.as_ptr(),
+            name.len(),
+            data.as_ptr(),
+            data.len(),
+            &mut result,
+        );
+        let value = (!result.ptr.is_null()).then(|| read_json(result));
+        self.check(code, format_args!("action {name}"))
+            .map(|()| value)
+    }
+
+    /// Falls back to `action_json` with empty arguments when the plugin does
+    /// not export `invoke_action`, like the default `Plugin::on_action`.
+    pub fn invoke_action(&mut self, action_id: &str) -> Result<(), PluginError> {
+        let Some(invoke_action) = self.api().invoke_action else {
+            return match self.action(action_id, &Value::Object(Default::default()))? {
+                Some(_) => Ok(()),
+                None => Err(PluginError::Config(format!("unknown action {action_id}"))),
+            };
+        };
+        let code = invoke_action(self.as_ptr(), action_id.as_ptr(), action_id.len());
+        self.check(code, format_args!("action {action_id}"))
+    }
+
+    /// `None` when the plugin does not export `migrate_config_json`.
+    pub fn migrate_config(
+        &self,
+        from_version: u32,
+        old: &Value,
+    ) -> Option<Result<Value, PluginError>> {
+        let migrate_config_json = self.api().migrate_config_json?;
+        let data = old.to_string();
+        let mut result = PluginString::null();
+        let code = migrate_config_json(
+            self.as_ptr(),
+            from_version,
+            data.as_ptr(),
+            data.len(),
+            &mut result,
+        );
+        let value = read_json(result);
+        Some(
+            self.check(
+                code,
+                format_args!("migrating configuration from schema version {from_version}"),
+            )
+            .map(|()| value),
+        )
+    }
+
+    /// `None` when the plugin does not export `automate`.
+    pub fn automate(&mut self, change: &ParamChange) -> Option<Result<(), PluginError>> {
+        let automate = self.api().automate?;
+        let code = automate(
+            self.as_ptr(),
+            change.key.as_ptr(),
+            change.key.len(),
+            change.target,
+            change.ramp_ticks,
+        );
+        Some(self.check(code, format_args!("automate {}", change.key)))
+    }
+
+    pub fn on_event(&mut self, event: &Event) -> Result<(), PluginError> {
+        let Some(on_event_json) = self.api().on_event_json else {
+            return Ok(());
+        };
+        let data = serde_json::to_string(event).unwrap_or_default();
+        self.check(
+            on_event_json(self.as_ptr(), data.as_ptr(), data.len()),
+            format_args!("on_event {}", event.topic),
+        )
+    }
+
+    /// Empty when the plugin does not export `take_events_json`; malformed
+    /// entries are skipped.
+    pub fn take_events(&mut self) -> Vec<Event> {
+        let Some(take_events_json) = self.api().take_events_json else {
+            return Vec::new();
+        };
+        match read_json(take_events_json(self.as_ptr())) {
+            Value::Array(events) => events
+                .into_iter()
+                .filter_map(|event| serde_json::from_value(event).ok())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn start(&mut self) -> Result<(), PluginError> {
+        self.notify(self.api().start, "start")
+    }
+
+    pub fn stop(&mut self) -> Result<(), PluginError> {
+        self.notify(self.api().stop, "stop")
+    }
+
+    pub fn pause(&mut self) -> Result<(), PluginError> {
+        self.notify(self.api().pause, "pause")
+    }
+
+    pub fn resume(&mut self) -> Result<(), PluginError> {
+        self.notify(self.api().resume, "resume")
+    }
+
+    pub fn reset(&mut self) -> Result<(), PluginError> {
+        self.notify(self.api().reset, "reset")
+    }
+
+    /// Empty when the plugin does not export `features_json`.
+    pub fn features(&self) -> Vec<FeatureFlag> {
+        let Some(features_json) = self.api().features_json else {
+            return Vec::new();
+        };
+        serde_json::from_value(read_json(features_json(self.as_ptr()))).unwrap_or_default()
+    }
+
+    /// Fails when the plugin does not export `on_output_added` or rejects
+    /// the port.
+    pub fn on_output_added(&mut self, port: &str) -> Result<(), PluginError> {
+        let Some(on_output_added) = self.api().on_output_added else {
+            return Err(PluginError::Config(format!("cannot add output {port}")));
+        };
+        self.check(
+            on_output_added(self.as_ptr(), port.as_ptr(), port.len()),
+            format_args!("on_output_added {port}"),
+        )
+    }
+
+    /// Fails when the plugin does not export `on_output_removed` or rejects
+    /// the port.
+    pub fn on_output_removed(&mut self, port: &str) -> Result<(), PluginError> {
+        let Some(on_output_removed) = self.api().on_output_removed else {
+            return Err(PluginError::Config(format!("cannot remove output {port}")));
+        };
+        self.check(
+            on_output_removed(self.as_ptr(), port.as_ptr(), port.len()),
+            format_args!("on_output_removed {port}"),
+        )
+    }
+
+    /// Fails when the plugin does not export `set_feature_enabled` or
+    /// rejects the feature.
+    pub fn set_feature_enabled(&mut self, name: &str, enabled: bool) -> Result<(), PluginError> {
+        let Some(set_feature_enabled) = self.api().set_feature_enabled else {
+            return Err(PluginError::Config(format!("unknown feature {name}")));
+        };
+        self.check(
+            set_feature_enabled(self.as_ptr(), name.as_ptr(), name.len(), enabled),
+            format_args!("set_feature_enabled {name}"),
+        )
+    }
+
+    /// Empty when the plugin does not export `templates_json`.
+    pub fn templates(&self) -> Vec<WorkspaceTemplate> {
+        let Some(templates_json) = self.api().templates_json else {
+            return Vec::new();
+        };
+        serde_json::from_value(read_json(templates_json(self.as_ptr()))).unwrap_or_default()
+    }
+
+    /// Empty when the plugin does not export `presets_json`.
+    pub fn presets(&self) -> Vec<Preset> {
+        let Some(presets_json) = self.api().presets_json else {
+            return Vec::new();
+        };
+        serde_json::from_value(read_json(presets_json(self.as_ptr()))).unwrap_or_default()
+    }
+
+    /// Empty when the plugin does not export `options_json`.
+    pub fn options_for(&self, key: &str) -> Vec<ChoiceOption> {
+        let Some(options_json) = self.api().options_json else {
+            return Vec::new();
+        };
+        serde_json::from_value(read_json(options_json(
+            self.as_ptr(),
+            key.as_ptr(),
+            key.len(),
+        )))
+        .unwrap_or_default()
+    }
+
+    /// `None` when the plugin does not export `preview_json` or has no
+    /// preview.
+    pub fn preview(&self) -> Option<Preview> {
+        let preview_json = self.api().preview_json?;
+        serde_json::from_value(read_json(preview_json(self.as_ptr()))).ok()
+    }
+
+    /// `None` when the plugin does not export `latency_ticks`.
+    pub fn latency_ticks(&self) -> Option<u64> {
+        let latency_ticks = self.api().latency_ticks?;
+        Some(latency_ticks(self.as_ptr()))
+    }
+
+    /// `None` when the plugin does not export `metrics_json`.
+    pub fn metrics(&self) -> Option<MetricsSnapshot> {
+        let metrics_json = self.api().metrics_json?;
+        serde_json::from_value(read_json(metrics_json(self.as_ptr()))).ok()
+    }
+
+    /// All bits set when the plugin does not track changed outputs.
+    pub fn changed_outputs_mask(&mut self) -> u64 {
+        match self.api().changed_outputs_mask {
+            Some(changed_outputs_mask) => changed_outputs_mask(self.as_ptr()),
+            None => u64::MAX,
+        }
+    }
+
+    pub fn begin_shutdown(&mut self) -> Result<ShutdownStatus, PluginError> {
+        self.shutdown_step(self.api().begin_shutdown, "begin_shutdown")
+    }
+
+    pub fn poll_shutdown(&mut self) -> Result<ShutdownStatus, PluginError> {
+        self.shutdown_step(self.api().poll_shutdown, "poll_shutdown")
+    }
+
+    fn shutdown_step(
+        &self,
+        step: Option<extern "C" fn(handle: *mut c_void, done: *mut bool) -> i32>,
+        context: &str,
+    ) -> Result<ShutdownStatus, PluginError> {
+        let Some(step) = step else {
+            return Ok(ShutdownStatus::Done);
+        };
+        let mut done = false;
+        self.check(step(self.as_ptr(), &mut done), context)?;
+        Ok(if done {
+            ShutdownStatus::Done
+        } else {
+            ShutdownStatus::Pending
+        })
+    }
+
+    // Turns the status code of a fallible `PluginApi` entry into a `Result`.
+    // Plugin errors carry the plugin's own message when it exports
+    // `last_error_json`; otherwise `call` names the failed entry.
+    fn check(&self, code: i32, call: impl std::fmt::Display) -> Result<(), PluginError> {
+        let status = match RtsynStatus::from_code(code) {
+            RtsynStatus::Ok => return Ok(()),
+            status => status,
+        };
+        let details = match (status, self.api().last_error_json) {
+            (RtsynStatus::PluginError(_), Some(last_error_json)) => {
+                serde_json::from_value(read_json(last_error_json(self.as_ptr()))).ok()
+            }
+            _ => None,
+        };
+        status.into_result_with(&format!("{call} failed"), details)
+    }
+
+    fn notify(
+        &self,
+        hook: Option<extern "C" fn(handle: *mut c_void) -> i32>,
+        context: &str,
+    ) -> Result<(), PluginError> {
+        match hook {
+            Some(hook) => self.check(hook(self.as_ptr()), context),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A port resolved once by [`LoadedPlugin::input_handle`] or
+/// [`LoadedPlugin::output_handle`]. Plugins without handle support are
+/// still addressed by name. Handles are `Copy`: the name is kept as an
+/// interned [`PortKey`], looked up once so the per-tick calls never touch
+/// the key registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PortHandle {
+    key: PortKey,
+    name: &'static str,
+    index: Option<u32>,
+}
+
+impl PortHandle {
+    fn new(name: &str, index: Option<u32>) -> Self {
+        let key = PortKey::intern(name);
+        PortHandle {
+            key,
+            name: key.as_str(),
+            index,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn key(&self) -> PortKey {
+        self.key
+    }
+
+    /// The plugin's integer handle, if it has one for this port.
+    pub fn index(&self) -> Option<u32> {
+        self.index
+    }
+}
+
+/// Port names prepared once for [`LoadedPlugin::set_inputs`] and
+/// [`LoadedPlugin::get_outputs`], so the per-tick calls neither resolve
+/// aliases nor allocate.
+#[derive(Debug, Default)]
+pub struct PortBatch {
+    names: Vec<String>,
+    raw: Vec<PortName>,
+}
+
+impl PortBatch {
+    fn new(names: Vec<String>) -> Self {
+        // The `String` buffers do not move when `names` does.
+        let raw = names.iter().map(|name| PortName::new(name)).collect();
+        Self { names, raw }
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+// `raw` only points into `names`, which the batch owns.
+unsafe impl Send for PortBatch {}
+unsafe impl Sync for PortBatch {}
+
+/// A plugin instance living behind an [`OwnedPluginHandle`].
+///
+/// Metadata, ports, behavior and UI schema are read once on creation and
+/// cached; call [`LoadedPlugin::refresh`] after anything that may change them.
+pub struct LoadedPlugin {
+    handle: OwnedPluginHandle,
+    id: PluginId,
+    meta: PluginMeta,
+    inputs: Vec<Port>,
+    outputs: Vec<Port>,
+    behavior: PluginBehavior,
+    connection_behavior: ConnectionBehavior,
+    ui_schema: Option<UISchema>,
+    // Last masks sent through `set_connections`.
+    connections: Option<(u64, u64)>,
+}
+
+impl LoadedPlugin {
+    pub fn new(id: PluginId, handle: OwnedPluginHandle) -> Self {
+        let mut plugin = Self {
+            handle,
+            id,
+            meta: PluginMeta::new(""),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            behavior: PluginBehavior::default(),
+            connection_behavior: ConnectionBehavior::default(),
+            ui_schema: None,
+            connections: None,
+        };
+        plugin.refresh();
+        plugin
+    }
+
+    pub fn handle(&self) -> *mut c_void {
+        self.handle.as_ptr()
+    }
+
+    pub fn owned_handle(&self) -> &OwnedPluginHandle {
+        &self.handle
+    }
+
+    pub fn owned_handle_mut(&mut self) -> &mut OwnedPluginHandle {
+        &mut self.handle
+    }
+
+    pub fn into_handle(self) -> OwnedPluginHandle {
+        self.handle
+    }
+
+    pub fn refresh(&mut self) {
+        self.meta = meta_from_json(&self.handle.meta_json());
+        self.inputs = ports_from_json(&self.handle.inputs_json());
+        self.outputs = ports_from_json(&self.handle.outputs_json());
+        if let Some(value) = self.handle.behavior_json() {
+            let (behavior, connection_behavior) = behavior_from_json(&value);
+            self.behavior = behavior;
+            self.connection_behavior = connection_behavior;
+        }
+        self.ui_schema = self
+            .handle
+            .ui_schema_json()
+            .and_then(|value| serde_json::from_value(value).ok());
+    }
+
+    /// Forwards host information to plugins that export `set_host_info_json`.
+    pub fn set_host_info(&mut self, host: &HostInfo) -> Result<(), PluginError> {
+        self.handle.set_host_info(host)
+    }
+
+    pub fn set_config(&mut self, config: &Value) -> Result<(), PluginError> {
+        self.handle.set_config(config)
+    }
+
+    /// `name` may be a deprecated alias; the plugin always receives the
+    /// current port id.
+    pub fn set_input(&mut self, name: &str, value: f64) {
+        let name = resolve_port(&self.inputs, name).map_or(name, |p| p.port.id.0.as_str());
+        self.handle.set_input(name, value);
+    }
+
+    pub fn get_output(&self, name: &str) -> f64 {
+        let name = resolve_port(&self.outputs, name).map_or(name, |p| p.port.id.0.as_str());
+        self.handle.get_output(name)
+    }
+
+    /// Runs recorded inputs through the plugin, one tick per sample, with
+    /// gaps filled by the inputs' `GapPolicy`. `after_tick` can read the
+    /// outputs of each tick.
+    pub fn replay<'a>(
+        &mut self,
+        tracks: impl IntoIterator<Item = (&'a str, &'a [Option<f64>])>,
+        ctx: &mut PluginContext,
+        mut after_tick: impl FnMut(&Self),
+    ) -> Result<(), PluginError> {
+        let replay = Replay::new(&self.inputs, tracks)?;
+        replay::run(
+            self,
+            &replay,
+            ctx,
+            |plugin, port, value| plugin.set_input(port, value),
+            |plugin, _| after_tick(plugin),
+        )
+    }
+
+    /// The plugin's metrics, fetched on demand since a native plugin keeps
+    /// its own `PluginContext`.
+    pub fn metrics(&self) -> Option<MetricsSnapshot> {
+        self.handle.metrics()
+    }
+
+    /// Plugins asking for this in their behavior expect the host to use the
+    /// checked port calls and surface their errors.
+    pub fn strict_ports(&self) -> bool {
+        self.behavior.strict_ports
+    }
+
+    /// Like [`LoadedPlugin::set_input`], failing for names that are not an
+    /// input. Plugins without `set_input_checked` are checked against the
+    /// cached port list.
+    pub fn set_input_checked(&mut self, name: &str, value: f64) -> Result<(), PluginError> {
+        let port = resolve_port(&self.inputs, name);
+        let id = port.map_or(name, |p| p.port.id.0.as_str());
+        let status = match self.handle.set_input_checked(id, value) {
+            Some(status) => status,
+            None if port.is_some() => {
+                self.handle.set_input(id, value);
+                RtsynStatus::Ok
+            }
+            None => missing_port(name, &self.outputs).into(),
+        };
+        status.into_result(name)
+    }
+
+    pub fn get_output_checked(&self, name: &str) -> Result<f64, PluginError> {
+        let port = resolve_port(&self.outputs, name);
+        let id = port.map_or(name, |p| p.port.id.0.as_str());
+        let (status, value) = match self.handle.get_output_checked(id) {
+            Some(Ok(value)) => (RtsynStatus::Ok, value),
+            Some(Err(status)) => (status, 0.0),
+            None if port.is_some() => (RtsynStatus::Ok, self.handle.get_output(id)),
+            None => (missing_port(name, &self.inputs).into(), 0.0),
+        };
+        status.into_result(name).map(|()| value)
+    }
+
+    /// Resolves `name`, which may be an alias, for
+    /// [`LoadedPlugin::set_input_by_handle`]. `None` when the plugin declares
+    /// no such input, so only declared names are ever interned. Resolve again
+    /// after [`LoadedPlugin::refresh`] reports a different port set.
+    pub fn input_handle(&self, name: &str) -> Option<PortHandle> {
+        let name = resolve_port(&self.inputs, name)?.port.id.0.as_str();
+        Some(PortHandle::new(name, self.handle.resolve_input(name)))
+    }
+
+    pub fn output_handle(&self, name: &str) -> Option<PortHandle> {
+        let name = resolve_port(&self.outputs, name)?.port.id.0.as_str();
+        Some(PortHandle::new(name, self.handle.resolve_output(name)))
+    }
+
+    pub fn set_input_by_handle(
+        &mut self,
+        port: &PortHandle,
+        value: f64,
+    ) -> Result<(), PluginError> {
+        match port
+            .index
+            .and_then(|index| self.handle.set_input_by_handle(index, value))
+        {
+            Some(result) => result,
+            None => {
+                self.handle.set_input(port.name(), value);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn get_output_by_handle(&self, port: &PortHandle) -> f64 {
+        port.index
+            .and_then(|index| self.handle.get_output_by_handle(index))
+            .unwrap_or_else(|| self.handle.get_output(port.name()))
+    }
+
+    /// Batch of input ports for [`LoadedPlugin::set_inputs`], with aliases
+    /// resolved as in [`LoadedPlugin::set_input`].
+    pub fn input_batch<'a>(&self, names: impl IntoIterator<Item = &'a str>) -> PortBatch {
+        PortBatch::new(
+            names
+                .into_iter()
+                .map(|name| {
+                    resolve_port(&self.inputs, name)
+                        .map_or(name, |p| p.port.id.0.as_str())
+                        .to_string()
+                })
+                .collect(),
+        )
+    }
+
+    pub fn output_batch<'a>(&self, names: impl IntoIterator<Item = &'a str>) -> PortBatch {
+        PortBatch::new(
+            names
+                .into_iter()
+                .map(|name| {
+                    resolve_port(&self.outputs, name)
+                        .map_or(name, |p| p.port.id.0.as_str())
+                        .to_string()
+                })
+                .collect(),
+        )
+    }
+
+    /// Writes `values[i]` to the `i`th port of `batch` in one call, or one
+    /// call per port for plugins without `set_inputs`. Only the batch call
+    /// reports unknown ports.
+    ///
+    /// # Panics
+    ///
+    /// If `batch` and `values` differ in length.
+    pub fn set_inputs(&mut self, batch: &PortBatch, values: &[f64]) -> Result<(), PluginError> {
+        if let Some(result) = self.handle.set_inputs(&batch.raw, values) {
+            return result;
+        }
+        for (name, value) in batch.names.iter().zip(values) {
+            self.handle.set_input(name, *value);
+        }
+        Ok(())
+    }
+
+    /// Reads the ports of `batch` into `out`, falling back to one call per
+    /// port like [`LoadedPlugin::set_inputs`].
+    ///
+    /// # Panics
+    ///
+    /// If `batch` and `out` differ in length.
+    pub fn get_outputs(&self, batch: &PortBatch, out: &mut [f64]) -> Result<(), PluginError> {
+        if let Some(result) = self.handle.get_outputs(&batch.raw, out) {
+            return result;
+        }
+        for (name, value) in batch.names.iter().zip(out) {
+            *value = self.handle.get_output(name);
+        }
+        Ok(())
+    }
+
+    /// Writes a raw value in the port's `NumericFormat`. Plugins without
+    /// `set_input_raw` receive it converted to `f64`.
+    pub fn set_input_raw(&mut self, name: &str, value: i64) {
+        let (name, format) = match resolve_port(&self.inputs, name) {
+            Some(resolved) => (resolved.port.id.0.as_str(), resolved.port.format),
+            None => (name, NumericFormat::Float),
+        };
+        if !self.handle.set_input_raw(name, value) {
+            self.handle.set_input(name, format.from_raw(value));
+        }
+    }
+
+    pub fn get_output_raw(&self, name: &str) -> i64 {
+        let (name, format) = match resolve_port(&self.outputs, name) {
+            Some(resolved) => (resolved.port.id.0.as_str(), resolved.port.format),
+            None => (name, NumericFormat::Float),
+        };
+        self.handle
+            .get_output_raw(name)
+            .unwrap_or_else(|| format.to_raw(self.handle.get_output(name)))
+    }
+}
+
+impl Plugin for LoadedPlugin {
+    fn id(&self) -> PluginId {
+        self.id
+    }
+
+    fn meta(&self) -> &PluginMeta {
+        &self.meta
+    }
+
+    fn inputs(&self) -> &[Port] {
+        &self.inputs
+    }
+
+    fn outputs(&self) -> &[Port] {
+        &self.outputs
+    }
+
+    fn process(&mut self, ctx: &mut PluginContext) -> Result<(), PluginError> {
+        let connections = (ctx.connected_inputs, ctx.connected_outputs);
+        if self.connections != Some(connections) {
+            self.handle.set_connections(connections.0, connections.1);
+            self.connections = Some(connections);
+        }
+        for change in &ctx.automation {
+            self.handle.automate(change).transpose()?;
+        }
+        let result = self.handle.process(ctx.tick, ctx.period_seconds);
+        for event in self.handle.take_events() {
+            ctx.emit(event);
+        }
+        result
+    }
+
+    fn ui_schema(&self) -> Option<UISchema> {
+        self.ui_schema.clone()
+    }
+
+    fn behavior(&self) -> PluginBehavior {
+        self.behavior.clone()
+    }
+
+    fn connection_behavior(&self) -> ConnectionBehavior {
+        self.connection_behavior.clone()
+    }
+
+    fn save_state(&self) -> Result<Value, PluginError> {
+        Ok(self.handle.state().unwrap_or(Value::Null))
+    }
+
+    fn restore_state(&mut self, state: Value) -> Result<(), PluginError> {
+        self.handle.set_state(&state)
+    }
+
+    fn migrate_config(&self, from_version: u32, old: Value) -> Result<Value, PluginError> {
+        self.handle
+            .migrate_config(from_version, &old)
+            .unwrap_or(Ok(old))
+    }
+
+    fn options_for(&self, key: &str) -> Vec<ChoiceOption> {
+        self.handle.options_for(key)
+    }
+
+    fn action(&mut self, name: &str, args: &Value) -> Result<Option<Value>, PluginError> {
+        self.handle.action(name, args)
+    }
+
+    fn on_action(&mut self, action_id: &str) -> Result<(), PluginError> {
+        self.handle.invoke_action(action_id)
+    }
+
+    fn on_event(&mut self, event: &Event) -> Result<(), PluginError> {
+        self.handle.on_event(event)
+    }
+
+    fn features(&self) -> Vec<FeatureFlag> {
+        self.handle.features()
+    }
+
+    fn set_feature_enabled(&mut self, name: &str, enabled: bool) -> Result<(), PluginError> {
+        self.handle.set_feature_enabled(name, enabled)
+    }
+
+    fn on_output_added(&mut self, port: &str) -> Result<(), PluginError> {
+        self.handle.on_output_added(port)
+    }
+
+    fn on_output_removed(&mut self, port: &str) -> Result<(), PluginError> {
+        self.handle.on_output_removed(port)
+    }
+
+    fn templates(&self) -> Vec<WorkspaceTemplate> {
+        self.handle.templates()
+    }
+
+    fn presets(&self) -> Vec<Preset> {
+        self.handle.presets()
+    }
+
+    fn preview(&self) -> Option<Preview> {
+        self.handle.preview()
+    }
+
+    fn latency_ticks(&self) -> u64 {
+        self.handle
+            .latency_ticks()
+            .unwrap_or(self.behavior.latency_ticks)
+    }
+
+    fn prepare(&mut self, sample_rate: f64, block_size: usize) -> Result<(), PluginError> {
+        self.handle.prepare(sample_rate, block_size)
+    }
+
+    fn on_overrun(&mut self, missed: u64) -> Result<(), PluginError> {
+        self.handle.on_overrun(missed)
+    }
+
+    fn on_start(&mut self) -> Result<(), PluginError> {
+        self.handle.start()
+    }
+
+    fn on_stop(&mut self) -> Result<(), PluginError> {
+        self.handle.stop()
+    }
+
+    fn on_pause(&mut self) -> Result<(), PluginError> {
+        self.handle.pause()
+    }
+
+    fn on_resume(&mut self) -> Result<(), PluginError> {
+        self.handle.resume()
+    }
+
+    fn reset(&mut self) -> Result<(), PluginError> {
+        self.handle.reset()
+    }
+
+    fn take_changed_outputs(&mut self) -> u64 {
+        self.handle.changed_outputs_mask()
+    }
+
+    // Aliases resolve as in `LoadedPlugin::set_input`.
+    fn set_input_vec(&mut self, name: &str, values: &[f64]) -> Result<(), PluginError> {
+        let name = resolve_port(&self.inputs, name).map_or(name, |p| p.port.id.0.as_str());
+        self.handle.set_input_vec(name, values).unwrap_or_else(|| {
+            Err(PluginError::Config(format!(
+                "input {name} does not take vectors"
+            )))
+        })
+    }
+
+    fn get_output_vec(&self, name: &str, out: &mut [f64]) -> usize {
+        let name = resolve_port(&self.outputs, name).map_or(name, |p| p.port.id.0.as_str());
+        self.handle.get_output_vec(name, out).unwrap_or(0)
+    }
+
+    fn get_output_string(&self, name: &str) -> Option<String> {
+        let name = resolve_port(&self.outputs, name).map_or(name, |p| p.port.id.0.as_str());
+        self.handle.get_output_string(name)
+    }
+
+    fn begin_shutdown(&mut self) -> Result<ShutdownStatus, PluginError> {
+        self.handle.begin_shutdown()
+    }
+
+    fn poll_shutdown(&mut self) -> Result<ShutdownStatus, PluginError> {
+        self.handle.poll_shutdown()
+    }
+}
+
+fn read_json(value: PluginString) -> Value {
+    if value.ptr.is_null() {
+        return Value::Null;
+    }
+    let text = unsafe { value.into_string() };
+    serde_json::from_str(&text).unwrap_or(Value::Null)
+}
+
+// Plugins in the wild emit metadata loosely: variables may be `[name, value]`
+// pairs or single-key objects, and either list may be missing.
+fn meta_from_json(value: &Value) -> PluginMeta {
+    PluginMeta {
+        name: value
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        uid: string_from_json(value.get("uid")).map(PluginUid),
+        fixed_vars: vars_from_json(value.get("fixed_vars")),
+        default_vars: vars_from_json(value.get("default_vars")),
+        version: string_from_json(value.get("version")),
+        author: string_from_json(value.get("author")),
+        description: string_from_json(value.get("description")),
+        license: string_from_json(value.get("license")),
+        homepage: string_from_json(value.get("homepage")),
+        category: value
+            .get("category")
+            .and_then(|c| serde_json::from_value(c.clone()).ok())
+            .unwrap_or_default(),
+        tags: value
+            .get("tags")
+            .and_then(Value::as_array)
+            .map(|tags| {
+                tags.iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        capabilities: capabilities_from_json(value.get("capabilities")),
+        display: value
+            .get("display")
+            .and_then(|display| serde_json::from_value(display.clone()).ok()),
+        translations: value
+            .get("translations")
+            .and_then(|translations| serde_json::from_value(translations.clone()).ok())
+            .unwrap_or_default(),
+    }
+}
+
+// Unlike the descriptive fields, a malformed entry must not vanish and let
+// the plugin past a capability check, so it becomes `Capability::Unknown`.
+fn capabilities_from_json(value: Option<&Value>) -> Vec<Capability> {
+    match value {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::Array(entries)) => entries
+            .iter()
+            .map(|entry| serde_json::from_value(entry.clone()).unwrap_or(Capability::Unknown))
+            .collect(),
+        Some(_) => vec![Capability::Unknown],
+    }
+}
+
+fn string_from_json(value: Option<&Value>) -> Option<String> {
+    value.and_then(Value::as_str).map(str::to_string)
+}
+
+fn vars_from_json(value: Option<&Value>) -> Vec<(String, Value)> {
+    let Some(Value::Array(items)) = value else {
+        return Vec::new();
+    };
+    let mut vars = Vec::new();
+    for item in items {
+        match item {
+            Value::Array(pair) if pair.len() == 2 => {
+                if let Some(name) = pair[0].as_str() {
+                    vars.push((name.to_string(), pair[1].clone()));
+                }
+            }
+            Value::Object(map) => {
+                vars.extend(map.iter().map(|(k, v)| (k.clone(), v.clone())));
+            }
+            _ => {}
+        }
+    }
+    vars
+}
+
+// Ports may be plain names or serialized `Port` objects.
+fn ports_from_json(value: &Value) -> Vec<Port> {
+    let Value::Array(items) = value else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .filter_map(|item| match item {
+            Value::String(name) => Some(Port::new(name.clone())),
+            Value::Object(map) => serde_json::from_value(item.clone()).ok().or_else(|| {
+                map.get("id")
+                    .and_then(Value::as_str)
+                    .map(|id| Port::new(id.to_string()))
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+// Accepts both a bare `PluginBehavior` and the combined object produced by
+// `rtsyn_behavior_to_json`.
+fn behavior_from_json(value: &Value) -> (PluginBehavior, ConnectionBehavior) {
+    let (behavior, dependent) = match value.get("behavior") {
+        Some(inner) => (
+            inner,
+            value
+                .get("connection_dependent")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+        ),
+        None => (value, false),
+    };
+    (
+        serde_json::from_value(behavior.clone()).unwrap_or_default(),
+        ConnectionBehavior { dependent },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::ffi::ErrorDetails;
+    use crate::ui::ExtendableInputs;
+    use crate::{PluginCategory, PortStatus};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn meta_accepts_pairs_and_objects() {
+        let meta = meta_from_json(&json!({
+            "name": "Example",
+            "fixed_vars": [["a", 1]],
+            "default_vars": [{"b": 2.0}, {"c": "x"}],
+            "version": "0.3.1",
+            "license": "MIT",
+            "category": "filter",
+            "tags": ["audio", 3],
+        }));
+        assert_eq!(meta.name, "Example");
+        assert_eq!(meta.version.as_deref(), Some("0.3.1"));
+        assert_eq!(meta.license.as_deref(), Some("MIT"));
+        assert_eq!(meta.category, PluginCategory::Filter);
+        assert_eq!(meta.tags, vec!["audio".to_string()]);
+        assert_eq!(meta.fixed_vars, vec![("a".to_string(), json!(1))]);
+        assert_eq!(meta.default_vars.len(), 2);
+        assert_eq!(meta.default_vars[0].0, "b");
+    }
+
+    #[test]
+    fn meta_tolerates_missing_vars() {
+        let meta = meta_from_json(&json!({ "name": "Bare", "kind": "bare" }));
+        assert_eq!(meta.name, "Bare");
+        assert!(meta.fixed_vars.is_empty());
+        assert!(meta.default_vars.is_empty());
+    }
+
+    #[test]
+    fn ports_accept_names_and_objects() {
+        let ports = ports_from_json(&json!(["in_0", {"id": "in_1", "aliases": ["in1"]}, 3]));
+        assert_eq!(ports.len(), 2);
+        assert_eq!(ports[0].id.0, "in_0");
+        assert_eq!(ports[1].id.0, "in_1");
+        assert!(ports[1].matches("in1"));
+    }
+
+    #[test]
+    fn behavior_accepts_combined_c_form() {
+        let (behavior, connection) = behavior_from_json(&json!({
+            "behavior": {
+                "supports_start_stop": false,
+                "supports_restart": true,
+                "extendable_inputs": {"type": "manual"},
+                "loads_started": false,
+            },
+            "connection_dependent": true,
+        }));
+        assert!(!behavior.supports_start_stop);
+        assert_eq!(behavior.extendable_inputs, ExtendableInputs::manual());
+        assert!(connection.dependent);
+    }
+
+    static DESTROYED: AtomicUsize = AtomicUsize::new(0);
+
+    extern "C" fn fake_create(id: u64) -> *mut c_void {
+        Box::into_raw(Box::new(id)) as *mut c_void
+    }
+
+    extern "C" fn fake_destroy(handle: *mut c_void) {
+        drop(unsafe { Box::from_raw(handle as *mut u64) });
+        DESTROYED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    extern "C" fn fake_json(_handle: *mut c_void) -> PluginString {
+        PluginString::from_string("[]".to_string())
+    }
+
+    // Instances created with an even id declare themselves pinned.
+    extern "C" fn fake_behavior(handle: *mut c_void) -> PluginString {
+        let id = unsafe { *(handle as *const u64) };
+        let behavior = PluginBehavior {
+            threading: if id % 2 == 0 {
+                ThreadingModel::Pinned
+            } else {
+                ThreadingModel::Any
+            },
+            ..PluginBehavior::default()
+        };
+        PluginString::from_string(serde_json::to_string(&behavior).unwrap())
+    }
+
+    extern "C" fn fake_set(_handle: *mut c_void, _data: *const u8, _len: usize) -> i32 {
+        RtsynStatus::Ok.code()
+    }
+    extern "C" fn fake_set_input(_handle: *mut c_void, _n: *const u8, _l: usize, _v: f64) {}
+    extern "C" fn fake_process(_handle: *mut c_void, _tick: u64, _period: f64) -> i32 {
+        RtsynStatus::Ok.code()
+    }
+    extern "C" fn fake_get_output(_handle: *mut c_void, _n: *const u8, _l: usize) -> f64 {
+        0.0
+    }
+
+    const FAKE_API: PluginApi = PluginApi {
+        behavior_json: Some(fake_behavior),
+        ..PluginApi::new(
+            fake_create,
+            fake_destroy,
+            fake_json,
+            fake_json,
+            fake_json,
+            fake_set,
+            fake_set_input,
+            fake_process,
+            fake_get_output,
+        )
+    };
+
+    #[cfg(unix)]
+    #[test]
+    fn owned_handles_destroy_once_and_respect_threading() {
+        let library = PluginLibrary {
+            library: Arc::new(libloading::os::unix::Library::this().into()),
+            api: Arc::new(FAKE_API),
+            resources: ResourceBundle::default(),
+        };
+
+        let plugin = library.instantiate(1).unwrap();
+        let handle = plugin.into_handle();
+        std::thread::spawn(move || drop(handle)).join().unwrap();
+        assert_eq!(DESTROYED.load(Ordering::SeqCst), 1);
+
+        assert!(matches!(
+            library.instantiate(2),
+            Err(LoaderError::ThreadBound)
+        ));
+        assert_eq!(DESTROYED.load(Ordering::SeqCst), 2);
+
+        let pinned = library.create_handle(4).unwrap();
+        assert_eq!(pinned.threading(), ThreadingModel::Pinned);
+        let Err(pinned) = pinned.into_sendable() else {
+            panic!("pinned handle became sendable");
+        };
+        drop(pinned);
+        assert_eq!(DESTROYED.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn malformed_capabilities_are_unknown() {
+        let meta = meta_from_json(&serde_json::json!({
+            "name": "probe",
+            "capabilities": ["network", "teleport", 7],
+        }));
+        assert_eq!(
+            meta.capabilities,
+            [
+                Capability::Network,
+                Capability::Unknown,
+                Capability::Unknown
+            ]
+        );
+        assert_eq!(
+            meta.excess_capabilities(&[Capability::Network]),
+            [Capability::Unknown, Capability::Unknown]
+        );
+        let meta = meta_from_json(&serde_json::json!({ "capabilities": "all" }));
+        assert_eq!(meta.capabilities, [Capability::Unknown]);
+    }
+
+    extern "C" fn batch_destroy(handle: *mut c_void) {
+        drop(unsafe { Box::from_raw(handle as *mut u64) });
+    }
+
+    // Stores the sum of the inputs; each output reads it plus its name length.
+    extern "C" fn batch_set(
+        handle: *mut c_void,
+        _n: *const PortName,
+        v: *const f64,
+        count: usize,
+    ) -> i32 {
+        let values = unsafe { std::slice::from_raw_parts(v, count) };
+        unsafe { *(handle as *mut u64) = values.iter().sum::<f64>() as u64 };
+        RtsynStatus::Ok.code()
+    }
+
+    // Names longer than two bytes are unknown.
+    extern "C" fn batch_get(
+        handle: *mut c_void,
+        n: *const PortName,
+        out: *mut f64,
+        count: usize,
+    ) -> i32 {
+        let names = unsafe { std::slice::from_raw_parts(n, count) };
+        let out = unsafe { std::slice::from_raw_parts_mut(out, count) };
+        let sum = unsafe { *(handle as *const u64) } as f64;
+        let mut status = RtsynStatus::Ok;
+        for (name, value) in names.iter().zip(out) {
+            let len = match unsafe { name.to_str() } {
+                Ok(name) => name.len(),
+                Err(_) => return RtsynStatus::Utf8Error.code(),
+            };
+            if len > 2 {
+                status = RtsynStatus::UnknownPort;
+            }
+            *value = sum + len as f64;
+        }
+        status.code()
+    }
+
+    // Ports named `p<n>` have handle `n`.
+    extern "C" fn batch_resolve(_handle: *mut c_void, n: *const u8, len: usize) -> u32 {
+        let Ok(name) = (unsafe { PortName { ptr: n, len }.to_str() }) else {
+            return INVALID_PORT_HANDLE;
+        };
+        name.strip_prefix('p')
+            .and_then(|index| index.parse().ok())
+            .unwrap_or(INVALID_PORT_HANDLE)
+    }
+
+    extern "C" fn batch_ports(_handle: *mut c_void) -> PluginString {
+        PluginString::from_string(r#"["p1", "p3", "x"]"#.to_string())
+    }
+
+    extern "C" fn batch_set_by_handle(handle: *mut c_void, port: u32, value: f64) -> i32 {
+        unsafe { *(handle as *mut u64) = value as u64 * 10 + port as u64 };
+        RtsynStatus::Ok.code()
+    }
+
+    extern "C" fn batch_get_by_handle(handle: *mut c_void, port: u32) -> f64 {
+        (unsafe { *(handle as *const u64) } + port as u64) as f64
+    }
+
+    const BATCH_API: PluginApi = PluginApi {
+        destroy: batch_destroy,
+        inputs_json: batch_ports,
+        outputs_json: batch_ports,
+        behavior_json: None,
+        set_inputs: Some(batch_set),
+        get_outputs: Some(batch_get),
+        resolve_input: Some(batch_resolve),
+        resolve_output: Some(batch_resolve),
+        set_input_by_handle: Some(batch_set_by_handle),
+        get_output_by_handle: Some(batch_get_by_handle),
+        ..FAKE_API
+    };
+
+    #[cfg(unix)]
+    #[test]
+    fn batch_calls_cover_all_ports() {
+        let library = PluginLibrary {
+            library: Arc::new(libloading::os::unix::Library::this().into()),
+            api: Arc::new(BATCH_API),
+            resources: ResourceBundle::default(),
+        };
+        let mut plugin = library.instantiate(1).unwrap();
+        let inputs = plugin.input_batch(["a", "b", "c"]);
+        let outputs = plugin.output_batch(["x", "yy"]);
+        assert_eq!(inputs.len(), 3);
+
+        plugin.set_inputs(&inputs, &[1.0, 2.0, 3.0]).unwrap();
+        let mut values = [0.0; 2];
+        plugin.get_outputs(&outputs, &mut values).unwrap();
+        assert_eq!(values, [7.0, 8.0]);
+        let unknown = plugin.output_batch(["zzz"]);
+        assert!(plugin.get_outputs(&unknown, &mut values[..1]).is_err());
+
+        let input = plugin.input_handle("p3").unwrap();
+        assert_eq!(input.index(), Some(3));
+        assert_eq!(plugin.input_handle("x").unwrap().index(), None);
+        plugin.set_input_by_handle(&input, 4.0).unwrap();
+        let output = plugin.output_handle("p1").unwrap();
+        assert_eq!(plugin.get_output_by_handle(&output), 44.0);
+
+        assert!(plugin.input_handle("batch_undeclared").is_none());
+        assert!(plugin.output_handle("batch_undeclared").is_none());
+        assert!(PortKey::get("batch_undeclared").is_none());
+    }
+
+    // One input `in` and one output `out`, which reads the input back.
+    extern "C" fn checked_set(handle: *mut c_void, n: *const u8, len: usize, v: f64) -> i32 {
+        let status = match unsafe { PortName { ptr: n, len }.to_str() } {
+            Ok("in") => {
+                unsafe { *(handle as *mut u64) = v as u64 };
+                PortStatus::Ok
+            }
+            Ok("out") => PortStatus::WrongDirection,
+            Ok(_) => PortStatus::UnknownPort,
+            Err(_) => return RtsynStatus::Utf8Error.code(),
+        };
+        status.code()
+    }
+
+    extern "C" fn checked_get(handle: *mut c_void, n: *const u8, len: usize, out: *mut f64) -> i32 {
+        let status = match unsafe { PortName { ptr: n, len }.to_str() } {
+            Ok("out") => {
+                unsafe { *out = *(handle as *const u64) as f64 };
+                PortStatus::Ok
+            }
+            Ok("in") => PortStatus::WrongDirection,
+            Ok(_) => PortStatus::UnknownPort,
+            Err(_) => return RtsynStatus::Utf8Error.code(),
+        };
+        status.code()
+    }
+
+    const CHECKED_API: PluginApi = PluginApi {
+        destroy: batch_destroy,
+        behavior_json: None,
+        set_input_checked: Some(checked_set),
+        get_output_checked: Some(checked_get),
+        ..FAKE_API
+    };
+
+    const UNCHECKED_API: PluginApi = PluginApi {
+        set_input_checked: None,
+        get_output_checked: None,
+        ..CHECKED_API
+    };
+
+    #[cfg(unix)]
+    #[test]
+    fn checked_calls_report_miswiring() {
+        let mut library = PluginLibrary {
+            library: Arc::new(libloading::os::unix::Library::this().into()),
+            api: Arc::new(CHECKED_API),
+            resources: ResourceBundle::default(),
+        };
+        let mut plugin = library.instantiate(1).unwrap();
+        plugin.set_input_checked("in", 5.0).unwrap();
+        assert_eq!(plugin.get_output_checked("out").unwrap(), 5.0);
+        let err = plugin.set_input_checked("out", 1.0).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid configuration: port out is used in the wrong direction"
+        );
+        assert!(plugin.get_output_checked("missing").is_err());
+        let mut state = 0u64;
+        let invalid = [0xff, 0xfe];
+        assert_eq!(
+            checked_set(
+                &mut state as *mut u64 as *mut c_void,
+                invalid.as_ptr(),
+                2,
+                1.0
+            ),
+            RtsynStatus::Utf8Error.code()
+        );
+
+        // Without the checked entries the cached (here empty) port list decides.
+        library.api = Arc::new(UNCHECKED_API);
+        let mut plugin = library.instantiate(1).unwrap();
+        let err = plugin.set_input_checked("in", 5.0).unwrap_err();
+        assert_eq!(err.to_string(), "invalid configuration: unknown port in");
+    }
+
+    extern "C" fn fake_start(_handle: *mut c_void) -> i32 {
+        RtsynStatus::Ok.code()
+    }
+
+    #[test]
+    fn entries_past_struct_size_are_unset() {
+        let older = PluginApi {
+            struct_size: PluginApi::MIN_SIZE,
+            start: Some(fake_start),
+            ..FAKE_API
+        };
+        let api = unsafe { read_api(&older) }.unwrap();
+        assert_eq!(api.struct_size, PluginApi::MIN_SIZE);
+        assert!(api.behavior_json.is_some());
+        assert!(api.start.is_none());
+
+        let current = PluginApi {
+            start: Some(fake_start),
+            ..FAKE_API
+        };
+        assert!(unsafe { read_api(&current) }.unwrap().start.is_some());
+
+        let truncated = PluginApi {
+            struct_size: PluginApi::MIN_SIZE - 1,
+            ..FAKE_API
+        };
+        assert!(matches!(
+            unsafe { read_api(&truncated) },
+            Err(LoaderError::TruncatedApi(_))
+        ));
+    }
+
+    fn unplugged() -> PluginError {
+        PluginError::Device {
+            code: 3,
+            message: "sensor unplugged".into(),
+        }
+    }
+
+    extern "C" fn failing_process(_handle: *mut c_void, _tick: u64, _period: f64) -> i32 {
+        RtsynStatus::from(unplugged()).code()
+    }
+
+    extern "C" fn failing_last_error(_handle: *mut c_void) -> PluginString {
+        let details = ErrorDetails::from(&unplugged());
+        PluginString::from_string(serde_json::to_string(&details).unwrap())
+    }
+
+    extern "C" fn pending_shutdown(_handle: *mut c_void, done: *mut bool) -> i32 {
+        unsafe { *done = false };
+        RtsynStatus::Ok.code()
+    }
+
+    extern "C" fn add_numbered_output(_handle: *mut c_void, port: *const u8, len: usize) -> i32 {
+        let port = unsafe { std::slice::from_raw_parts(port, len) };
+        match port.strip_prefix(b"out") {
+            Some(n) if !n.is_empty() && n.iter().all(u8::is_ascii_digit) => RtsynStatus::Ok,
+            _ => RtsynStatus::from(PluginError::Config(String::new())),
+        }
+        .code()
+    }
+
+    extern "C" fn rejecting_migrate(
+        _handle: *mut c_void,
+        _from_version: u32,
+        _data: *const u8,
+        _len: usize,
+        _out: *mut PluginString,
+    ) -> i32 {
+        RtsynStatus::from(PluginError::Config(String::new())).code()
+    }
+
+    const FAILING_API: PluginApi = PluginApi {
+        destroy: batch_destroy,
+        behavior_json: None,
+        process: failing_process,
+        start: Some(fake_start),
+        begin_shutdown: Some(pending_shutdown),
+        migrate_config_json: Some(rejecting_migrate),
+        ..FAKE_API
+    };
+
+    #[cfg(unix)]
+    #[test]
+    fn status_codes_become_errors() {
+        let library = PluginLibrary {
+            library: Arc::new(libloading::os::unix::Library::this().into()),
+            api: Arc::new(FAILING_API),
+            resources: ResourceBundle::default(),
+        };
+        let mut plugin = library.instantiate(1).unwrap();
+        let err = plugin.process(&mut PluginContext::default()).unwrap_err();
+        assert_eq!(err.to_string(), "device error 0: process failed");
+        assert!(plugin.on_start().is_ok());
+        assert_eq!(plugin.begin_shutdown().unwrap(), ShutdownStatus::Pending);
+        let err = plugin.migrate_config(1, json!({})).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid configuration: migrating configuration from schema version 1 failed"
+        );
+        assert!(plugin.on_output_added("out2").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn output_hooks_reach_the_plugin() {
+        let library = PluginLibrary {
+            library: Arc::new(libloading::os::unix::Library::this().into()),
+            api: Arc::new(PluginApi {
+                on_output_added: Some(add_numbered_output),
+                on_output_removed: Some(add_numbered_output),
+                ..FAILING_API
+            }),
+            resources: ResourceBundle::default(),
+        };
+        let mut plugin = library.instantiate(1).unwrap();
+        plugin.on_output_added("out2").unwrap();
+        plugin.on_output_removed("out2").unwrap();
+        let err = plugin.on_output_added("gain").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid configuration: on_output_added gain failed"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn last_error_details_replace_the_generic_message() {
+        let library = PluginLibrary {
+            library: Arc::new(libloading::os::unix::Library::this().into()),
+            api: Arc::new(PluginApi {
+                last_error_json: Some(failing_last_error),
+                ..FAILING_API
+            }),
+            resources: ResourceBundle::default(),
+        };
+        let mut plugin = library.instantiate(1).unwrap();
+        let err = plugin.process(&mut PluginContext::default()).unwrap_err();
+        assert!(matches!(err, PluginError::Device { code: 3, .. }));
+        assert_eq!(err.to_string(), "device error 3: sensor unplugged");
+    }
+
+    #[test]
+    fn open_missing_library_fails() {
+        let result = unsafe { PluginLibrary::open("/nonexistent/librtsyn_missing.so") };
+        assert!(matches!(result, Err(LoaderError::Library(_))));
+    }
+}
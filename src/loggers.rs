@@ -0,0 +1,466 @@
+//! Ready-made [`EventLogger`]s.
+//!
+//! [`CsvLoggerPlugin`] writes one row per tick with the value of every
+//! configured column, plus one row per structured record whose fields are
+//! matched to the columns by name. Rows are buffered until the host flushes
+//! according to the logger's [`FlushPolicy`].
+
+use crate::config::ConfigDelta;
+use crate::event_log::{FlushPolicy, LogRecord, RecordValue};
+use crate::ports::port_index;
+use crate::ui::{ConfigField, FieldCondition, FileMode, UISchema};
+use crate::{
+    EventLogger, Plugin, PluginCategory, PluginContext, PluginError, PluginId, PluginMeta, Port,
+};
+use serde_json::{json, Value};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Header of the first column, holding the row time in seconds since the
+/// Unix epoch.
+pub const CSV_TIME_COLUMN: &str = "time";
+
+/// When a log file is moved aside: once writing the next batch would take it
+/// past `max_bytes`. `log.csv` becomes `log.1.csv`, `log.1.csv` becomes
+/// `log.2.csv` and so on; files beyond `keep` are deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rotation {
+    pub max_bytes: u64,
+    pub keep: usize,
+}
+
+/// Logs its inputs, one per column, to a CSV file. Configured through the
+/// `path`, `separator`, `columns`, `rotate_bytes` and `keep_files` keys.
+///
+/// Appending to a file whose header does not match the current columns
+/// moves that file aside first, so every file has a single layout.
+pub struct CsvLoggerPlugin {
+    id: PluginId,
+    meta: PluginMeta,
+    path: PathBuf,
+    separator: String,
+    columns: Vec<String>,
+    inputs: Vec<Port>,
+    values: Vec<f64>,
+    rotation: Option<Rotation>,
+    policy: FlushPolicy,
+    rows: String,
+    pending: usize,
+    file: Option<File>,
+    file_bytes: u64,
+}
+
+impl CsvLoggerPlugin {
+    pub fn new(id: PluginId, path: impl Into<PathBuf>) -> Self {
+        Self {
+            id,
+            meta: PluginMeta::new("CSV logger")
+                .uid("rtsyn.csv_logger")
+                .category(PluginCategory::Logger),
+            path: path.into(),
+            separator: ",".to_string(),
+            columns: Vec::new(),
+            inputs: Vec::new(),
+            values: Vec::new(),
+            rotation: None,
+            policy: FlushPolicy::default(),
+            rows: String::new(),
+            pending: 0,
+            file: None,
+            file_bytes: 0,
+        }
+    }
+
+    pub fn separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    pub fn columns<S: AsRef<str>>(mut self, columns: &[S]) -> Self {
+        self.set_columns(columns.iter().map(|c| c.as_ref().to_string()).collect());
+        self
+    }
+
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = Some(rotation);
+        self
+    }
+
+    /// Replaces the default [`FlushPolicy`] reported to the host.
+    pub fn batching(mut self, policy: FlushPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn set_input(&mut self, index: usize, value: f64) {
+        self.values[index] = value;
+    }
+
+    /// Unknown names are ignored.
+    pub fn set_input_by_name(&mut self, name: &str, value: f64) {
+        if let Some(index) = port_index(&self.inputs, name) {
+            self.values[index] = value;
+        }
+    }
+
+    fn set_columns(&mut self, columns: Vec<String>) {
+        self.inputs = columns.iter().map(|c| Port::new(c.as_str())).collect();
+        self.values = vec![0.0; columns.len()];
+        self.columns = columns;
+    }
+
+    fn header(&self) -> String {
+        let mut header = quote(CSV_TIME_COLUMN, &self.separator);
+        for column in &self.columns {
+            header.push_str(&self.separator);
+            header.push_str(&quote(column, &self.separator));
+        }
+        header
+    }
+
+    fn push_row(&mut self, seconds: f64, cells: impl IntoIterator<Item = String>) {
+        self.rows.push_str(&seconds.to_string());
+        for cell in cells {
+            self.rows.push_str(&self.separator);
+            self.rows.push_str(&cell);
+        }
+        self.rows.push('\n');
+        self.pending += 1;
+    }
+
+    // Flushes what was buffered under the old settings, then closes the file
+    // so the next flush reopens it under the new ones.
+    fn reopen_after(&mut self, change: impl FnOnce(&mut Self)) -> Result<(), PluginError> {
+        self.flush()?;
+        self.file = None;
+        change(self);
+        Ok(())
+    }
+
+    fn open(&mut self) -> Result<(), PluginError> {
+        let header = self.header();
+        if let Ok(existing) = File::open(&self.path) {
+            let mut first = String::new();
+            BufReader::new(existing).read_line(&mut first)?;
+            if !first.is_empty() && first.trim_end_matches(['\r', '\n']) != header {
+                self.rotate()?;
+            }
+        }
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.file_bytes = file.metadata()?.len();
+        if self.file_bytes == 0 {
+            writeln!(file, "{header}")?;
+            self.file_bytes = header.len() as u64 + 1;
+        }
+        self.file = Some(file);
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<(), PluginError> {
+        self.file = None;
+        let keep = self.rotation.map_or(1, |rotation| rotation.keep);
+        if keep == 0 {
+            return Ok(fs::remove_file(&self.path)?);
+        }
+        for index in (1..keep).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        Ok(fs::rename(&self.path, self.rotated_path(1))?)
+    }
+
+    /// Where the `index`-th most recent rotated file is kept.
+    pub fn rotated_path(&self, index: usize) -> PathBuf {
+        let stem = self
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let name = match self.path.extension() {
+            Some(ext) => format!("{stem}.{index}.{}", ext.to_string_lossy()),
+            None => format!("{stem}.{index}"),
+        };
+        self.path.with_file_name(name)
+    }
+}
+
+// Quotes a cell that would otherwise break the row.
+fn quote(text: &str, separator: &str) -> String {
+    if text.contains(separator) || text.contains(['"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn cell(value: Option<&RecordValue>, separator: &str) -> String {
+    match value {
+        None => String::new(),
+        Some(RecordValue::Bool(v)) => v.to_string(),
+        Some(RecordValue::U64(v)) => v.to_string(),
+        Some(RecordValue::I64(v)) => v.to_string(),
+        Some(RecordValue::F64(v)) => v.to_string(),
+        Some(RecordValue::Str(v)) => quote(v, separator),
+    }
+}
+
+fn config_error(key: &str, expected: &str) -> PluginError {
+    PluginError::Config(format!("{key} must be {expected}"))
+}
+
+impl Plugin for CsvLoggerPlugin {
+    fn id(&self) -> PluginId {
+        self.id
+    }
+
+    fn meta(&self) -> &PluginMeta {
+        &self.meta
+    }
+
+    fn inputs(&self) -> &[Port] {
+        &self.inputs
+    }
+
+    fn outputs(&self) -> &[Port] {
+        &[]
+    }
+
+    fn process(&mut self, ctx: &mut PluginContext) -> Result<(), PluginError> {
+        let seconds = ctx
+            .wall_time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+        let values = std::mem::take(&mut self.values);
+        self.push_row(seconds, values.iter().map(f64::to_string));
+        self.values = values;
+        Ok(())
+    }
+
+    fn ui_schema(&self) -> Option<UISchema> {
+        Some(
+            UISchema::new()
+                .field(
+                    ConfigField::filepath("path", "File")
+                        .mode(FileMode::SaveFile)
+                        .filter("CSV files", "*.csv")
+                        .required(),
+                )
+                .field(
+                    ConfigField::text("separator", "Separator")
+                        .max_length(1)
+                        .default_value(json!(",")),
+                )
+                .field(ConfigField::dynamic_list("columns", "Columns").add_label("Add column"))
+                .field(
+                    ConfigField::integer("rotate_bytes", "Rotate after (bytes)")
+                        .min(0)
+                        .default_value(json!(0))
+                        .hint("0 keeps a single file"),
+                )
+                .field(
+                    ConfigField::integer("keep_files", "Rotated files to keep")
+                        .min(0)
+                        .default_value(json!(5))
+                        .visible_if(FieldCondition::not_equals("rotate_bytes", 0)),
+                ),
+        )
+    }
+
+    fn on_config_changed(&mut self, changed: &ConfigDelta) -> Result<(), PluginError> {
+        let new = |key: &str| changed.get(key).and_then(|c| c.new.clone());
+        if let Some(path) = new("path") {
+            let path = path
+                .as_str()
+                .ok_or_else(|| config_error("path", "a string"))?
+                .to_string();
+            self.reopen_after(|logger| logger.path = path.into())?;
+        }
+        if let Some(separator) = new("separator") {
+            let separator = separator
+                .as_str()
+                .filter(|s| !s.is_empty())
+                .ok_or_else(|| config_error("separator", "a non-empty string"))?
+                .to_string();
+            self.reopen_after(|logger| logger.separator = separator)?;
+        }
+        if let Some(columns) = new("columns") {
+            let columns = columns
+                .as_array()
+                .and_then(|items| {
+                    items
+                        .iter()
+                        .map(|item| item.as_str().map(str::to_string))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or_else(|| config_error("columns", "a list of names"))?;
+            self.reopen_after(|logger| logger.set_columns(columns))?;
+        }
+        if changed.changed("rotate_bytes") || changed.changed("keep_files") {
+            let number = |key: &str, default: u64| match new(key) {
+                None | Some(Value::Null) => Ok(default),
+                Some(value) => value
+                    .as_u64()
+                    .ok_or_else(|| config_error(key, "a non-negative integer")),
+            };
+            let max_bytes = number("rotate_bytes", self.rotation.map_or(0, |r| r.max_bytes))?;
+            let keep = number("keep_files", self.rotation.map_or(5, |r| r.keep as u64))?;
+            self.rotation = (max_bytes > 0).then_some(Rotation {
+                max_bytes,
+                keep: keep as usize,
+            });
+        }
+        Ok(())
+    }
+
+    fn on_stop(&mut self) -> Result<(), PluginError> {
+        self.flush()?;
+        self.file = None;
+        Ok(())
+    }
+}
+
+impl EventLogger for CsvLoggerPlugin {
+    fn flush(&mut self) -> Result<(), PluginError> {
+        if self.pending == 0 {
+            return Ok(());
+        }
+        if self.file.is_none() {
+            self.open()?;
+        }
+        if let Some(rotation) = self.rotation {
+            let header_only = self.header().len() as u64 + 1;
+            let size = self.file_bytes + self.rows.len() as u64;
+            if self.file_bytes > header_only && size > rotation.max_bytes {
+                self.rotate()?;
+                self.open()?;
+            }
+        }
+        if let Some(file) = self.file.as_mut() {
+            file.write_all(self.rows.as_bytes())?;
+            file.flush()?;
+        }
+        self.file_bytes += self.rows.len() as u64;
+        self.rows.clear();
+        self.pending = 0;
+        Ok(())
+    }
+
+    fn log_event(&mut self, record: LogRecord) -> Result<(), PluginError> {
+        let cells: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| cell(record.fields.get(column), &self.separator))
+            .collect();
+        self.push_row(record.timestamp_ns as f64 / 1e9, cells);
+        Ok(())
+    }
+
+    fn flush_policy(&self) -> FlushPolicy {
+        self.policy
+    }
+
+    fn pending(&self) -> usize {
+        self.pending
+    }
+}
+
+impl Drop for CsvLoggerPlugin {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::LogLevel;
+    use std::time::{Duration, SystemTime};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rtsyn-loggers-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn writes_rows_and_records() {
+        let dir = temp_dir("rows");
+        let mut logger = CsvLoggerPlugin::new(PluginId(1), dir.join("run.csv"))
+            .separator(";")
+            .columns(&["emg", "note;1"]);
+        let mut ctx = PluginContext {
+            wall_time: SystemTime::UNIX_EPOCH + Duration::from_millis(1500),
+            ..PluginContext::default()
+        };
+        logger.set_input_by_name("emg", 0.25);
+        logger.process(&mut ctx).unwrap();
+        logger
+            .log_event(
+                LogRecord::new(2_000_000_000, LogLevel::Info, PluginId(2))
+                    .field("note;1", "a;b")
+                    .field("other", 1u64),
+            )
+            .unwrap();
+        assert_eq!(logger.pending(), 2);
+        logger.flush().unwrap();
+        assert_eq!(logger.pending(), 0);
+
+        let text = fs::read_to_string(logger.path()).unwrap();
+        assert_eq!(text, "time;emg;\"note;1\"\n1.5;0.25;0\n2;;\"a;b\"\n");
+    }
+
+    #[test]
+    fn column_changes_and_size_rotate_files() {
+        let dir = temp_dir("rotate");
+        let path = dir.join("log.csv");
+        let mut logger = CsvLoggerPlugin::new(PluginId(1), &path)
+            .columns(&["x"])
+            .rotation(Rotation {
+                max_bytes: 16,
+                keep: 2,
+            });
+        let mut ctx = PluginContext::default();
+        for _ in 0..3 {
+            logger.process(&mut ctx).unwrap();
+            logger.flush().unwrap();
+        }
+        // Rows are 4 bytes after the 7 byte header; the third one does not fit.
+        assert_eq!(fs::read_to_string(&path).unwrap(), "time,x\n0,0\n");
+        assert_eq!(
+            fs::read_to_string(logger.rotated_path(1)).unwrap(),
+            "time,x\n0,0\n0,0\n"
+        );
+
+        logger
+            .on_config_changed(&ConfigDelta::between(
+                &json!({ "columns": ["x"] }),
+                &json!({ "columns": ["x", "y"] }),
+            ))
+            .unwrap();
+        assert_eq!(logger.inputs().len(), 2);
+        logger.process(&mut ctx).unwrap();
+        logger.on_stop().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "time,x,y\n0,0,0\n");
+        assert_eq!(
+            fs::read_to_string(logger.rotated_path(2)).unwrap(),
+            "time,x\n0,0\n0,0\n"
+        );
+        assert!(!logger.rotated_path(3).exists());
+    }
+}
@@ -0,0 +1,348 @@
+use crate::host::{HostServices, LogLevel};
+use crate::{PluginContext, PluginId};
+use std::fmt;
+use std::sync::Arc;
+
+/// Value of a structured log field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogValue<'a> {
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Str(&'a str),
+}
+
+impl fmt::Display for LogValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogValue::Bool(v) => write!(f, "{v}"),
+            LogValue::I64(v) => write!(f, "{v}"),
+            LogValue::U64(v) => write!(f, "{v}"),
+            LogValue::F64(v) => write!(f, "{v}"),
+            LogValue::Str(v) => write!(f, "{v:?}"),
+        }
+    }
+}
+
+impl From<bool> for LogValue<'_> {
+    fn from(value: bool) -> Self {
+        LogValue::Bool(value)
+    }
+}
+
+impl From<i64> for LogValue<'_> {
+    fn from(value: i64) -> Self {
+        LogValue::I64(value)
+    }
+}
+
+impl From<u64> for LogValue<'_> {
+    fn from(value: u64) -> Self {
+        LogValue::U64(value)
+    }
+}
+
+impl From<usize> for LogValue<'_> {
+    fn from(value: usize) -> Self {
+        LogValue::U64(value as u64)
+    }
+}
+
+impl From<f64> for LogValue<'_> {
+    fn from(value: f64) -> Self {
+        LogValue::F64(value)
+    }
+}
+
+impl<'a> From<&'a str> for LogValue<'a> {
+    fn from(value: &'a str) -> Self {
+        LogValue::Str(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LogRecord<'a> {
+    pub level: LogLevel,
+    pub plugin: PluginId,
+    pub message: &'a str,
+    pub fields: &'a [(&'a str, LogValue<'a>)],
+}
+
+impl fmt::Display for LogRecord<'_> {
+    /// `message key=value ...`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message)?;
+        for (key, value) in self.fields {
+            write!(f, " {key}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Destination of plugin log records, provided by the host.
+///
+/// `log` is called on the realtime thread; implementations should hand the
+/// record off without blocking.
+pub trait LogSink: Send + Sync {
+    fn log(&self, record: &LogRecord);
+}
+
+/// Handle plugins use to send diagnostics to the host.
+///
+/// Available as `PluginContext::logger`. The default logger has no sink and
+/// discards everything.
+#[derive(Clone)]
+pub struct Logger {
+    sink: Option<Arc<dyn LogSink>>,
+    plugin: PluginId,
+    max_level: LogLevel,
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self {
+            sink: None,
+            plugin: PluginId(0),
+            max_level: LogLevel::Info,
+        }
+    }
+}
+
+impl fmt::Debug for Logger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Logger")
+            .field("plugin", &self.plugin)
+            .field("max_level", &self.max_level)
+            .field("has_sink", &self.sink.is_some())
+            .finish()
+    }
+}
+
+impl Logger {
+    pub fn new(sink: Arc<dyn LogSink>, plugin: PluginId) -> Self {
+        Self {
+            sink: Some(sink),
+            plugin,
+            ..Self::default()
+        }
+    }
+
+    /// Most verbose level that is passed to the sink.
+    pub fn max_level(mut self, level: LogLevel) -> Self {
+        self.max_level = level;
+        self
+    }
+
+    /// Check before building expensive fields.
+    pub fn enabled(&self, level: LogLevel) -> bool {
+        self.sink.is_some() && level <= self.max_level
+    }
+
+    pub fn log(&self, level: LogLevel, message: &str, fields: &[(&str, LogValue)]) {
+        let Some(sink) = self.sink.as_ref().filter(|_| level <= self.max_level) else {
+            return;
+        };
+        sink.log(&LogRecord {
+            level,
+            plugin: self.plugin,
+            message,
+            fields,
+        });
+    }
+
+    pub fn error(&self, message: &str, fields: &[(&str, LogValue)]) {
+        self.log(LogLevel::Error, message, fields);
+    }
+
+    pub fn warn(&self, message: &str, fields: &[(&str, LogValue)]) {
+        self.log(LogLevel::Warn, message, fields);
+    }
+
+    pub fn info(&self, message: &str, fields: &[(&str, LogValue)]) {
+        self.log(LogLevel::Info, message, fields);
+    }
+
+    pub fn debug(&self, message: &str, fields: &[(&str, LogValue)]) {
+        self.log(LogLevel::Debug, message, fields);
+    }
+}
+
+/// Rate-limited debug output of a single value while tuning a plugin,
+/// usually written as [`probe!`](crate::probe).
+///
+/// Logs `probe name=value` at [`LogLevel::Debug`] on every `every`th tick.
+/// The value is only computed when it is actually logged.
+#[derive(Debug, Clone, Copy)]
+pub struct DebugProbe {
+    name: &'static str,
+    every: u64,
+}
+
+impl DebugProbe {
+    /// `every` of `0` logs on every tick.
+    pub const fn new(name: &'static str, every: u64) -> Self {
+        Self { name, every }
+    }
+
+    pub fn is_due(&self, ctx: &PluginContext) -> bool {
+        ctx.tick.is_multiple_of(self.every.max(1)) && ctx.logger.enabled(LogLevel::Debug)
+    }
+
+    pub fn record<'v, V: Into<LogValue<'v>>>(
+        &self,
+        ctx: &PluginContext,
+        value: impl FnOnce() -> V,
+    ) {
+        if self.is_due(ctx) {
+            ctx.logger.debug("probe", &[(self.name, value().into())]);
+        }
+    }
+}
+
+/// Logs a value every `every` ticks through `ctx.logger`, e.g.
+/// `probe!(ctx, "phase", self.phase, every = 100)`. Without `every` the
+/// value is logged on each tick. Nothing is evaluated unless debug logging
+/// is enabled.
+#[macro_export]
+macro_rules! probe {
+    ($ctx:expr, $name:literal, $value:expr, every = $every:expr $(,)?) => {
+        $crate::logging::DebugProbe::new($name, $every as u64).record(&$ctx, || $value)
+    };
+    ($ctx:expr, $name:literal, $value:expr $(,)?) => {
+        $crate::probe!($ctx, $name, $value, every = 1)
+    };
+}
+
+/// Forwards records to the host through the `log` entry of `RTSynHostApi`,
+/// with fields appended as `key=value`.
+pub struct HostLogSink(pub HostServices);
+
+impl LogSink for HostLogSink {
+    fn log(&self, record: &LogRecord) {
+        if record.fields.is_empty() {
+            self.0.log(record.level, record.message);
+        } else {
+            self.0.log(record.level, &record.to_string());
+        }
+    }
+}
+
+/// Emits records as `tracing` events with target `rtsyn_plugin`.
+#[cfg(feature = "tracing")]
+pub struct TracingSink;
+
+#[cfg(feature = "tracing")]
+impl LogSink for TracingSink {
+    fn log(&self, record: &LogRecord) {
+        let plugin = record.plugin.0;
+        let message = record.message;
+        let fields = FieldsDisplay(record.fields);
+        match record.level {
+            LogLevel::Error => {
+                tracing::error!(target: "rtsyn_plugin", plugin, fields = %fields, "{message}")
+            }
+            LogLevel::Warn => {
+                tracing::warn!(target: "rtsyn_plugin", plugin, fields = %fields, "{message}")
+            }
+            LogLevel::Info => {
+                tracing::info!(target: "rtsyn_plugin", plugin, fields = %fields, "{message}")
+            }
+            LogLevel::Debug => {
+                tracing::debug!(target: "rtsyn_plugin", plugin, fields = %fields, "{message}")
+            }
+            LogLevel::Trace => {
+                tracing::trace!(target: "rtsyn_plugin", plugin, fields = %fields, "{message}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+struct FieldsDisplay<'a>(&'a [(&'a str, LogValue<'a>)]);
+
+#[cfg(feature = "tracing")]
+impl fmt::Display for FieldsDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, (key, value)) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{key}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Collect(Mutex<Vec<(LogLevel, String)>>);
+
+    impl LogSink for Collect {
+        fn log(&self, record: &LogRecord) {
+            self.0
+                .lock()
+                .unwrap()
+                .push((record.level, record.to_string()));
+        }
+    }
+
+    #[test]
+    fn logger_filters_and_formats() {
+        let sink = Arc::new(Collect::default());
+        let logger = Logger::new(sink.clone(), PluginId(3)).max_level(LogLevel::Warn);
+
+        logger.warn(
+            "overrun",
+            &[("tick", 12u64.into()), ("source", "adc".into())],
+        );
+        logger.info("dropped", &[]);
+        assert!(!logger.enabled(LogLevel::Debug));
+
+        assert_eq!(
+            sink.0.lock().unwrap().as_slice(),
+            [(
+                LogLevel::Warn,
+                r#"overrun tick=12 source="adc""#.to_string()
+            )]
+        );
+        assert!(!Logger::default().enabled(LogLevel::Error));
+    }
+
+    #[test]
+    fn probes_are_rate_limited_and_lazy() {
+        let sink = Arc::new(Collect::default());
+        let mut ctx = PluginContext {
+            logger: Logger::new(sink.clone(), PluginId(1)).max_level(LogLevel::Debug),
+            ..PluginContext::default()
+        };
+        for tick in 0..250u64 {
+            ctx.tick = tick;
+            crate::probe!(ctx, "phase", tick as f64 * 0.5, every = 100);
+        }
+        let logged: Vec<_> = sink
+            .0
+            .lock()
+            .unwrap()
+            .drain(..)
+            .map(|(_, line)| line)
+            .collect();
+        assert_eq!(
+            logged,
+            ["probe phase=0", "probe phase=50", "probe phase=100"]
+        );
+
+        ctx.logger = ctx.logger.clone().max_level(LogLevel::Info);
+        let mut evaluated = false;
+        crate::probe!(ctx, "phase", {
+            evaluated = true;
+            1.0
+        });
+        assert!(!evaluated);
+        assert!(sink.0.lock().unwrap().is_empty());
+    }
+}
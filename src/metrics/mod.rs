@@ -0,0 +1,414 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+
+/// Current value of a single plugin metric.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MetricValue {
+    Counter {
+        value: u64,
+    },
+    Gauge {
+        value: f64,
+    },
+    /// Cumulative bucket counts keyed by inclusive upper bound, ascending.
+    Histogram {
+        buckets: Vec<(f64, u64)>,
+        sum: f64,
+        count: u64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricSample {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+    #[serde(flatten)]
+    pub value: MetricValue,
+}
+
+impl MetricSample {
+    pub fn counter(name: impl Into<String>, value: u64) -> Self {
+        Self::new(name, MetricValue::Counter { value })
+    }
+
+    pub fn gauge(name: impl Into<String>, value: f64) -> Self {
+        Self::new(name, MetricValue::Gauge { value })
+    }
+
+    pub fn histogram(name: impl Into<String>, buckets: Vec<(f64, u64)>, sum: f64) -> Self {
+        let count = buckets.last().map_or(0, |(_, count)| *count);
+        Self::new(
+            name,
+            MetricValue::Histogram {
+                buckets,
+                sum,
+                count,
+            },
+        )
+    }
+
+    pub fn help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    fn new(name: impl Into<String>, value: MetricValue) -> Self {
+        Self {
+            name: name.into(),
+            help: None,
+            value,
+        }
+    }
+}
+
+/// All metrics reported by one plugin instance at a point in time.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    pub samples: Vec<MetricSample>,
+}
+
+impl MetricsSnapshot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn sample(mut self, sample: MetricSample) -> Self {
+        self.samples.push(sample);
+        self
+    }
+}
+
+/// Upper bounds used by [`Metrics::histogram`], in seconds, suited to
+/// processing latencies.
+pub const DEFAULT_BUCKETS: &[f64] = &[1e-5, 1e-4, 5e-4, 1e-3, 5e-3, 1e-2, 5e-2, 0.1, 0.5, 1.0];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Counter {
+    value: u64,
+}
+
+impl Counter {
+    pub fn inc(&mut self) {
+        self.add(1);
+    }
+
+    pub fn add(&mut self, n: u64) {
+        self.value = self.value.saturating_add(n);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.value
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Gauge {
+    value: f64,
+}
+
+impl Gauge {
+    pub fn set(&mut self, value: f64) {
+        self.value = value;
+    }
+
+    pub fn add(&mut self, delta: f64) {
+        self.value += delta;
+    }
+
+    pub fn get(&self) -> f64 {
+        self.value
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    bounds: Vec<f64>,
+    // Per bucket, not cumulative; the last slot counts values above every
+    // bound.
+    counts: Vec<u64>,
+    sum: f64,
+}
+
+impl Histogram {
+    fn new(bounds: &[f64]) -> Self {
+        Self {
+            bounds: bounds.to_vec(),
+            counts: vec![0; bounds.len() + 1],
+            sum: 0.0,
+        }
+    }
+
+    pub fn record(&mut self, value: f64) {
+        let bucket = self.bounds.partition_point(|bound| *bound < value);
+        self.counts[bucket] += 1;
+        self.sum += value;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    fn value(&self) -> MetricValue {
+        let mut cumulative = 0;
+        let buckets = self
+            .bounds
+            .iter()
+            .zip(&self.counts)
+            .map(|(bound, count)| {
+                cumulative += count;
+                (*bound, cumulative)
+            })
+            .collect();
+        MetricValue::Histogram {
+            buckets,
+            sum: self.sum,
+            count: self.count(),
+        }
+    }
+
+    fn from_value(buckets: &[(f64, u64)], sum: f64, count: u64) -> Self {
+        let mut previous = 0;
+        let mut counts: Vec<u64> = buckets
+            .iter()
+            .map(|(_, cumulative)| {
+                let count = cumulative.saturating_sub(previous);
+                previous = *cumulative;
+                count
+            })
+            .collect();
+        counts.push(count.saturating_sub(previous));
+        Self {
+            bounds: buckets.iter().map(|(bound, _)| *bound).collect(),
+            counts,
+            sum,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Metric {
+    Counter(Counter),
+    Gauge(Gauge),
+    Histogram(Histogram),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    name: String,
+    help: Option<String>,
+    metric: Metric,
+}
+
+/// Metrics a plugin keeps up to date from `process` through
+/// `PluginContext::metrics`, e.g. `ctx.metrics.counter("drops").inc()`.
+///
+/// The first use of a name registers the metric and allocates; later ticks
+/// only look it up. Using a name with another kind replaces the metric.
+/// Hosts read [`Metrics::take_changed`] after each tick or
+/// [`Metrics::snapshot`] whenever they need it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metrics {
+    entries: Vec<Entry>,
+    changed: bool,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn counter(&mut self, name: &str) -> &mut Counter {
+        let metric = self.entry(
+            name,
+            |metric| matches!(metric, Metric::Counter(_)),
+            || Metric::Counter(Counter::default()),
+        );
+        match metric {
+            Metric::Counter(counter) => counter,
+            _ => unreachable!("entry returns the requested kind"),
+        }
+    }
+
+    pub fn gauge(&mut self, name: &str) -> &mut Gauge {
+        let metric = self.entry(
+            name,
+            |metric| matches!(metric, Metric::Gauge(_)),
+            || Metric::Gauge(Gauge::default()),
+        );
+        match metric {
+            Metric::Gauge(gauge) => gauge,
+            _ => unreachable!("entry returns the requested kind"),
+        }
+    }
+
+    /// A histogram with [`DEFAULT_BUCKETS`].
+    pub fn histogram(&mut self, name: &str) -> &mut Histogram {
+        self.histogram_with_buckets(name, DEFAULT_BUCKETS)
+    }
+
+    /// Like [`Metrics::histogram`] with explicit ascending upper bounds;
+    /// they only apply when the histogram is created.
+    pub fn histogram_with_buckets(&mut self, name: &str, bounds: &[f64]) -> &mut Histogram {
+        let metric = self.entry(
+            name,
+            |metric| matches!(metric, Metric::Histogram(_)),
+            || Metric::Histogram(Histogram::new(bounds)),
+        );
+        match metric {
+            Metric::Histogram(histogram) => histogram,
+            _ => unreachable!("entry returns the requested kind"),
+        }
+    }
+
+    /// Help text exported with metric `name`, if it is registered.
+    pub fn describe(&mut self, name: &str, help: impl Into<String>) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.name == name) {
+            entry.help = Some(help.into());
+        }
+    }
+
+    fn entry(
+        &mut self,
+        name: &str,
+        is_kind: impl Fn(&Metric) -> bool,
+        new: impl FnOnce() -> Metric,
+    ) -> &mut Metric {
+        self.changed = true;
+        let index = match self.entries.iter().position(|entry| entry.name == name) {
+            Some(index) => {
+                if !is_kind(&self.entries[index].metric) {
+                    self.entries[index].metric = new();
+                }
+                index
+            }
+            None => {
+                self.entries.push(Entry {
+                    name: name.to_string(),
+                    help: None,
+                    metric: new(),
+                });
+                self.entries.len() - 1
+            }
+        };
+        &mut self.entries[index].metric
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            samples: self
+                .entries
+                .iter()
+                .map(|entry| MetricSample {
+                    name: entry.name.clone(),
+                    help: entry.help.clone(),
+                    value: match &entry.metric {
+                        Metric::Counter(counter) => MetricValue::Counter {
+                            value: counter.value,
+                        },
+                        Metric::Gauge(gauge) => MetricValue::Gauge { value: gauge.value },
+                        Metric::Histogram(histogram) => histogram.value(),
+                    },
+                })
+                .collect(),
+        }
+    }
+
+    /// The snapshot if any metric was touched since the last call, for
+    /// hosts forwarding metrics once per tick.
+    pub fn take_changed(&mut self) -> Option<MetricsSnapshot> {
+        std::mem::take(&mut self.changed).then(|| self.snapshot())
+    }
+
+    /// Replaces every metric with those of `snapshot`, e.g. when a proxy
+    /// receives the metrics of an out-of-process plugin.
+    pub fn load(&mut self, snapshot: &MetricsSnapshot) {
+        self.entries = snapshot
+            .samples
+            .iter()
+            .map(|sample| Entry {
+                name: sample.name.clone(),
+                help: sample.help.clone(),
+                metric: match &sample.value {
+                    MetricValue::Counter { value } => Metric::Counter(Counter { value: *value }),
+                    MetricValue::Gauge { value } => Metric::Gauge(Gauge { value: *value }),
+                    MetricValue::Histogram {
+                        buckets,
+                        sum,
+                        count,
+                    } => Metric::Histogram(Histogram::from_value(buckets, *sum, *count)),
+                },
+            })
+            .collect();
+        self.changed = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_serialization() {
+        let snapshot = MetricsSnapshot::new()
+            .sample(MetricSample::counter("drops", 3).help("Dropped samples"))
+            .sample(MetricSample::histogram(
+                "latency",
+                vec![(0.001, 4), (0.01, 5)],
+                0.02,
+            ));
+        let json = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(
+            json["samples"][0],
+            serde_json::json!({ "name": "drops", "help": "Dropped samples", "type": "counter", "value": 3 })
+        );
+        assert_eq!(json["samples"][1]["count"], 5);
+
+        let parsed: MetricsSnapshot = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, snapshot);
+    }
+
+    #[test]
+    fn handles_update_metrics() {
+        let mut metrics = Metrics::new();
+        metrics.counter("drops").inc();
+        metrics.counter("drops").add(2);
+        metrics.gauge("level").set(0.5);
+        let latency = metrics.histogram_with_buckets("latency", &[0.001, 0.01]);
+        latency.record(0.0005);
+        latency.record(0.005);
+        latency.record(1.0);
+        metrics.describe("drops", "Dropped samples");
+
+        let snapshot = metrics.take_changed().unwrap();
+        assert_eq!(
+            snapshot.samples[0],
+            MetricSample::counter("drops", 3).help("Dropped samples")
+        );
+        assert_eq!(
+            snapshot.samples[2].value,
+            MetricValue::Histogram {
+                buckets: vec![(0.001, 1), (0.01, 2)],
+                sum: 1.0055,
+                count: 3,
+            }
+        );
+        assert_eq!(metrics.take_changed(), None);
+
+        let mut proxy = Metrics::new();
+        proxy.load(&snapshot);
+        assert_eq!(proxy.snapshot(), snapshot);
+        assert_eq!(proxy.histogram("latency").count(), 3);
+    }
+}
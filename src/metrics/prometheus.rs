@@ -0,0 +1,196 @@
+use super::{MetricSample, MetricValue, MetricsSnapshot};
+use crate::PluginId;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Prefix shared by every exported metric name.
+pub const METRIC_PREFIX: &str = "rtsyn_plugin_";
+
+/// Latest metrics of every running plugin instance, encoded on demand in the
+/// Prometheus text exposition format.
+///
+/// A plugin metric `latency` reported by instance 7 of plugin `csv_recorder`
+/// is exported as `rtsyn_plugin_latency{plugin="csv_recorder",instance="7"}`.
+/// Counters additionally get the conventional `_total` suffix.
+#[derive(Debug, Clone, Default)]
+pub struct PrometheusExporter {
+    instances: BTreeMap<u64, (String, MetricsSnapshot)>,
+}
+
+impl PrometheusExporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the stored metrics of `instance`.
+    pub fn update(
+        &mut self,
+        plugin: impl Into<String>,
+        instance: PluginId,
+        snapshot: MetricsSnapshot,
+    ) {
+        self.instances.insert(instance.0, (plugin.into(), snapshot));
+    }
+
+    pub fn remove(&mut self, instance: PluginId) {
+        self.instances.remove(&instance.0);
+    }
+
+    pub fn clear(&mut self) {
+        self.instances.clear();
+    }
+
+    /// Body for a `GET /metrics` response, served as
+    /// `text/plain; version=0.0.4`.
+    pub fn encode(&self) -> String {
+        // Every series of a metric family has to follow its HELP/TYPE header
+        let mut families: BTreeMap<String, Vec<(&str, u64, &MetricSample)>> = BTreeMap::new();
+        for (instance, (plugin, snapshot)) in &self.instances {
+            for sample in &snapshot.samples {
+                families
+                    .entry(metric_name(sample))
+                    .or_default()
+                    .push((plugin, *instance, sample));
+            }
+        }
+
+        let mut out = String::new();
+        for (name, series) in &families {
+            let (_, _, first) = series[0];
+            if let Some(help) = series.iter().find_map(|(_, _, s)| s.help.as_deref()) {
+                let _ = writeln!(out, "# HELP {name} {}", escape_help(help));
+            }
+            let kind = match first.value {
+                MetricValue::Counter { .. } => "counter",
+                MetricValue::Gauge { .. } => "gauge",
+                MetricValue::Histogram { .. } => "histogram",
+            };
+            let _ = writeln!(out, "# TYPE {name} {kind}");
+
+            for (plugin, instance, sample) in series {
+                let labels = format!(
+                    "plugin=\"{}\",instance=\"{instance}\"",
+                    escape_label(plugin)
+                );
+                match &sample.value {
+                    MetricValue::Counter { value } => {
+                        let _ = writeln!(out, "{name}{{{labels}}} {value}");
+                    }
+                    MetricValue::Gauge { value } => {
+                        let _ = writeln!(out, "{name}{{{labels}}} {}", format_float(*value));
+                    }
+                    MetricValue::Histogram {
+                        buckets,
+                        sum,
+                        count,
+                    } => {
+                        for (bound, cumulative) in buckets {
+                            let _ = writeln!(
+                                out,
+                                "{name}_bucket{{{labels},le=\"{}\"}} {cumulative}",
+                                format_float(*bound)
+                            );
+                        }
+                        let _ = writeln!(out, "{name}_bucket{{{labels},le=\"+Inf\"}} {count}");
+                        let _ = writeln!(out, "{name}_sum{{{labels}}} {}", format_float(*sum));
+                        let _ = writeln!(out, "{name}_count{{{labels}}} {count}");
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Exported name of a plugin metric: prefixed, restricted to `[a-zA-Z0-9_]`,
+/// and suffixed with `_total` for counters.
+pub fn metric_name(sample: &MetricSample) -> String {
+    let mut name = String::from(METRIC_PREFIX);
+    name.extend(sample.name.chars().map(|c| {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            c.to_ascii_lowercase()
+        } else {
+            '_'
+        }
+    }));
+    if matches!(sample.value, MetricValue::Counter { .. }) && !name.ends_with("_total") {
+        name.push_str("_total");
+    }
+    name
+}
+
+fn format_float(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn escape_help(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_sanitized() {
+        assert_eq!(
+            metric_name(&MetricSample::counter("Dropped.Samples", 0)),
+            "rtsyn_plugin_dropped_samples_total"
+        );
+        assert_eq!(
+            metric_name(&MetricSample::gauge("queue-depth", 0.0)),
+            "rtsyn_plugin_queue_depth"
+        );
+    }
+
+    #[test]
+    fn encodes_families_across_instances() {
+        let mut exporter = PrometheusExporter::new();
+        exporter.update(
+            "csv_recorder",
+            PluginId(2),
+            MetricsSnapshot::new()
+                .sample(MetricSample::counter("drops", 4).help("Dropped samples"))
+                .sample(MetricSample::histogram(
+                    "latency",
+                    vec![(0.5, 1), (1.0, 3)],
+                    1.75,
+                )),
+        );
+        exporter.update(
+            "csv_recorder",
+            PluginId(1),
+            MetricsSnapshot::new().sample(MetricSample::counter("drops", 0)),
+        );
+
+        let expected = "\
+# HELP rtsyn_plugin_drops_total Dropped samples
+# TYPE rtsyn_plugin_drops_total counter
+rtsyn_plugin_drops_total{plugin=\"csv_recorder\",instance=\"1\"} 0
+rtsyn_plugin_drops_total{plugin=\"csv_recorder\",instance=\"2\"} 4
+# TYPE rtsyn_plugin_latency histogram
+rtsyn_plugin_latency_bucket{plugin=\"csv_recorder\",instance=\"2\",le=\"0.5\"} 1
+rtsyn_plugin_latency_bucket{plugin=\"csv_recorder\",instance=\"2\",le=\"1\"} 3
+rtsyn_plugin_latency_bucket{plugin=\"csv_recorder\",instance=\"2\",le=\"+Inf\"} 3
+rtsyn_plugin_latency_sum{plugin=\"csv_recorder\",instance=\"2\"} 1.75
+rtsyn_plugin_latency_count{plugin=\"csv_recorder\",instance=\"2\"} 3
+";
+        assert_eq!(exporter.encode(), expected);
+
+        exporter.remove(PluginId(2));
+        assert!(!exporter.encode().contains("instance=\"2\""));
+    }
+}
@@ -0,0 +1,267 @@
+//! Placeholder plugins for connections that are temporarily broken, e.g.
+//! while the user is editing the graph: [`NullSource`] feeds the orphaned
+//! inputs according to a [`FillPolicy`] and [`NullSink`] absorbs the
+//! orphaned outputs.
+
+use crate::config::ConfigDelta;
+use crate::ports::port_index;
+use crate::ui::{ConfigField, FieldCondition, PluginBehavior, UISchema};
+use crate::{Plugin, PluginCategory, PluginContext, PluginError, PluginId, PluginMeta, Port};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// What a [`NullSource`] writes to its outputs on every tick.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FillPolicy {
+    #[default]
+    Zero,
+    /// The source's `fill_value`.
+    Constant,
+    /// The last value of each output, as seeded with [`NullSource::hold`].
+    HoldLast,
+    Nan,
+}
+
+/// Stand-in for a disconnected source. Configured through the `fill` and
+/// `fill_value` keys.
+pub struct NullSource {
+    id: PluginId,
+    meta: PluginMeta,
+    outputs: Vec<Port>,
+    values: Vec<f64>,
+    policy: FillPolicy,
+    fill_value: f64,
+}
+
+impl NullSource {
+    pub fn new(id: PluginId, outputs: Vec<Port>, policy: FillPolicy) -> Self {
+        let values = vec![0.0; outputs.len()];
+        Self {
+            id,
+            meta: PluginMeta::new("Null source")
+                .uid("rtsyn.null_source")
+                .category(PluginCategory::Utility),
+            outputs,
+            values,
+            policy,
+            fill_value: 0.0,
+        }
+    }
+
+    pub fn fill_value(mut self, value: f64) -> Self {
+        self.fill_value = value;
+        self
+    }
+
+    /// Seeds the outputs, typically with the last values of the source this
+    /// placeholder replaces, so `FillPolicy::HoldLast` keeps them.
+    pub fn hold(mut self, values: &[f64]) -> Self {
+        for (slot, value) in self.values.iter_mut().zip(values) {
+            *slot = *value;
+        }
+        self
+    }
+
+    pub fn policy(&self) -> FillPolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: FillPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn output(&self, index: usize) -> f64 {
+        self.values[index]
+    }
+
+    /// Unknown names read as `0.0`.
+    pub fn output_by_name(&self, name: &str) -> f64 {
+        port_index(&self.outputs, name).map_or(0.0, |index| self.values[index])
+    }
+}
+
+impl Plugin for NullSource {
+    fn id(&self) -> PluginId {
+        self.id
+    }
+
+    fn meta(&self) -> &PluginMeta {
+        &self.meta
+    }
+
+    fn inputs(&self) -> &[Port] {
+        &[]
+    }
+
+    fn outputs(&self) -> &[Port] {
+        &self.outputs
+    }
+
+    fn process(&mut self, _ctx: &mut PluginContext) -> Result<(), PluginError> {
+        let fill = match self.policy {
+            FillPolicy::Zero => 0.0,
+            FillPolicy::Constant => self.fill_value,
+            FillPolicy::Nan => f64::NAN,
+            FillPolicy::HoldLast => return Ok(()),
+        };
+        self.values.fill(fill);
+        Ok(())
+    }
+
+    fn ui_schema(&self) -> Option<UISchema> {
+        Some(
+            UISchema::new()
+                .field(
+                    ConfigField::choice("fill", "Fill")
+                        .option("zero", "Zero")
+                        .option("constant", "Constant")
+                        .option("hold_last", "Hold last value")
+                        .option("nan", "NaN")
+                        .default_value(json!("zero")),
+                )
+                .field(
+                    ConfigField::float("fill_value", "Fill value")
+                        .default_value(json!(0.0))
+                        .visible_if(FieldCondition::equals("fill", "constant")),
+                ),
+        )
+    }
+
+    fn behavior(&self) -> PluginBehavior {
+        PluginBehavior {
+            side_effects: Some(Vec::new()),
+            ..PluginBehavior::default()
+        }
+    }
+
+    fn on_config_changed(&mut self, changed: &ConfigDelta) -> Result<(), PluginError> {
+        if let Some(fill) = changed.get("fill").and_then(|c| c.new.clone()) {
+            self.policy = serde_json::from_value(fill)
+                .map_err(|e| PluginError::Config(format!("invalid fill policy: {e}")))?;
+        }
+        if let Some(value) = changed.get("fill_value").and_then(|c| c.new.as_ref()) {
+            self.fill_value = value
+                .as_f64()
+                .ok_or_else(|| PluginError::Config("fill_value must be a number".to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Stand-in for a disconnected sink. Values written to it are kept only so
+/// hosts can still display them.
+pub struct NullSink {
+    id: PluginId,
+    meta: PluginMeta,
+    inputs: Vec<Port>,
+    values: Vec<f64>,
+}
+
+impl NullSink {
+    pub fn new(id: PluginId, inputs: Vec<Port>) -> Self {
+        let values = vec![0.0; inputs.len()];
+        Self {
+            id,
+            meta: PluginMeta::new("Null sink")
+                .uid("rtsyn.null_sink")
+                .category(PluginCategory::Utility),
+            inputs,
+            values,
+        }
+    }
+
+    pub fn set_input(&mut self, index: usize, value: f64) {
+        self.values[index] = value;
+    }
+
+    /// Unknown names are ignored.
+    pub fn set_input_by_name(&mut self, name: &str, value: f64) {
+        if let Some(index) = port_index(&self.inputs, name) {
+            self.values[index] = value;
+        }
+    }
+
+    pub fn input(&self, index: usize) -> f64 {
+        self.values[index]
+    }
+}
+
+impl Plugin for NullSink {
+    fn id(&self) -> PluginId {
+        self.id
+    }
+
+    fn meta(&self) -> &PluginMeta {
+        &self.meta
+    }
+
+    fn inputs(&self) -> &[Port] {
+        &self.inputs
+    }
+
+    fn outputs(&self) -> &[Port] {
+        &[]
+    }
+
+    fn process(&mut self, _ctx: &mut PluginContext) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    fn behavior(&self) -> PluginBehavior {
+        PluginBehavior {
+            stateless: true,
+            side_effects: Some(Vec::new()),
+            ..PluginBehavior::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ports(names: &[&str]) -> Vec<Port> {
+        names.iter().map(|name| Port::new(*name)).collect()
+    }
+
+    #[test]
+    fn source_fills_per_policy() {
+        let mut ctx = PluginContext::default();
+        let mut source = NullSource::new(PluginId(1), ports(&["x", "y"]), FillPolicy::HoldLast)
+            .hold(&[1.5, -2.0]);
+        source.process(&mut ctx).unwrap();
+        assert_eq!(source.output_by_name("y"), -2.0);
+
+        source
+            .on_config_changed(&ConfigDelta::between(
+                &json!({ "fill": "hold_last" }),
+                &json!({ "fill": "constant", "fill_value": 3.0 }),
+            ))
+            .unwrap();
+        source.process(&mut ctx).unwrap();
+        assert_eq!(source.policy(), FillPolicy::Constant);
+        assert_eq!(source.output(0), 3.0);
+
+        source.set_policy(FillPolicy::Nan);
+        source.process(&mut ctx).unwrap();
+        assert!(source.output(1).is_nan());
+        assert!(source
+            .on_config_changed(&ConfigDelta::between(
+                &json!({}),
+                &json!({ "fill": "random" })
+            ))
+            .is_err());
+    }
+
+    #[test]
+    fn sink_absorbs_values() {
+        let mut sink = NullSink::new(PluginId(2), ports(&["in_0"]));
+        sink.set_input_by_name("in_0", 4.0);
+        sink.set_input_by_name("missing", 1.0);
+        sink.process(&mut PluginContext::default()).unwrap();
+        assert_eq!(sink.input(0), 4.0);
+        assert!(sink.behavior().is_pure());
+        assert!(sink.meta().uid.as_ref().unwrap().is_valid());
+    }
+}
@@ -0,0 +1,550 @@
+use crate::config::ConfigDelta;
+use crate::device::{DeviceInfo, ReconnectPolicy};
+use crate::diagnostics::{
+    DiagnosticMeasurement, MeasurableQuantity, MeasurementProgress, MeasurementReport,
+};
+use crate::event::Event;
+use crate::features::FeatureFlag;
+use crate::preset::Preset;
+use crate::preview::Preview;
+use crate::template::WorkspaceTemplate;
+use crate::ui::{ChoiceOption, ConnectionBehavior, PluginBehavior, UISchema};
+use crate::{
+    DeviceDriver, Plugin, PluginContext, PluginError, PluginId, PluginMeta, Port, ShutdownStatus,
+};
+use opentelemetry::trace::{
+    Span as _, SpanBuilder, SpanContext, SpanId, Status, TraceContextExt, TraceFlags, TraceId,
+    TraceState, Tracer,
+};
+use opentelemetry::{Context, KeyValue};
+use serde_json::Value;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::time::SystemTime;
+
+/// Plugin call covered by a span.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanKind {
+    Open,
+    Close,
+    Config,
+    Process,
+}
+
+impl SpanKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            SpanKind::Open => "rtsyn.plugin.open",
+            SpanKind::Close => "rtsyn.plugin.close",
+            SpanKind::Config => "rtsyn.plugin.config",
+            SpanKind::Process => "rtsyn.plugin.process",
+        }
+    }
+}
+
+/// Timing of one plugin call, recorded on the realtime thread.
+#[derive(Debug, Clone, Copy)]
+pub struct SpanRecord {
+    pub kind: SpanKind,
+    pub plugin: PluginId,
+    pub tick: u64,
+    pub start: SystemTime,
+    pub end: SystemTime,
+    pub ok: bool,
+    pub parent: Option<(TraceId, SpanId, TraceFlags)>,
+}
+
+/// Creates a bounded span queue.
+///
+/// The [`SpanRecorder`] side never allocates or blocks, so it can sit on the
+/// realtime thread; the [`SpanExporter`] side turns records into
+/// OpenTelemetry spans from any other thread. Only every `sample_every`-th
+/// `process` call is recorded; lifecycle and config calls are always recorded.
+pub fn span_channel(capacity: usize, sample_every: u64) -> (SpanRecorder, SpanExporter) {
+    let (sender, receiver) = sync_channel(capacity);
+    (
+        SpanRecorder {
+            sender,
+            sample_every: sample_every.max(1),
+            process_calls: 0,
+            dropped: 0,
+            parent: None,
+        },
+        SpanExporter { receiver },
+    )
+}
+
+pub struct SpanRecorder {
+    sender: SyncSender<SpanRecord>,
+    sample_every: u64,
+    process_calls: u64,
+    dropped: u64,
+    parent: Option<(TraceId, SpanId, TraceFlags)>,
+}
+
+impl SpanRecorder {
+    /// Parents subsequent spans under a span from another process, e.g. one
+    /// received through [`parse_traceparent`].
+    pub fn set_parent(&mut self, parent: Option<SpanContext>) {
+        self.parent = parent.map(|cx| (cx.trace_id(), cx.span_id(), cx.trace_flags()));
+    }
+
+    /// Records that were discarded because the exporter fell behind.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub fn should_sample(&mut self, kind: SpanKind) -> bool {
+        if kind != SpanKind::Process {
+            return true;
+        }
+        let sampled = self.process_calls.is_multiple_of(self.sample_every);
+        self.process_calls += 1;
+        sampled
+    }
+
+    /// Runs `call`, recording its timing if this call is sampled.
+    pub fn trace<T>(
+        &mut self,
+        kind: SpanKind,
+        plugin: PluginId,
+        tick: u64,
+        call: impl FnOnce() -> Result<T, PluginError>,
+    ) -> Result<T, PluginError> {
+        if !self.should_sample(kind) {
+            return call();
+        }
+        let start = SystemTime::now();
+        let result = call();
+        self.record(SpanRecord {
+            kind,
+            plugin,
+            tick,
+            start,
+            end: SystemTime::now(),
+            ok: result.is_ok(),
+            parent: self.parent,
+        });
+        result
+    }
+
+    pub fn record(&mut self, record: SpanRecord) {
+        match self.sender.try_send(record) {
+            Ok(()) | Err(TrySendError::Disconnected(_)) => {}
+            Err(TrySendError::Full(_)) => self.dropped += 1,
+        }
+    }
+}
+
+pub struct SpanExporter {
+    receiver: Receiver<SpanRecord>,
+}
+
+impl SpanExporter {
+    /// Emits every queued record through `tracer` and returns how many were
+    /// exported.
+    pub fn export<T: Tracer>(&self, tracer: &T) -> usize {
+        let mut exported = 0;
+        for record in self.receiver.try_iter() {
+            let parent = match record.parent {
+                Some((trace_id, span_id, flags)) => Context::new().with_remote_span_context(
+                    SpanContext::new(trace_id, span_id, flags, true, TraceState::default()),
+                ),
+                None => Context::new(),
+            };
+            let builder = SpanBuilder::from_name(record.kind.name())
+                .with_start_time(record.start)
+                .with_attributes([
+                    KeyValue::new("rtsyn.plugin.id", record.plugin.0 as i64),
+                    KeyValue::new("rtsyn.tick", record.tick as i64),
+                ]);
+            let mut span = tracer.build_with_context(builder, &parent);
+            if !record.ok {
+                span.set_status(Status::error("plugin call failed"));
+            }
+            span.end_with_timestamp(record.end);
+            exported += 1;
+        }
+        exported
+    }
+}
+
+/// W3C `traceparent` header value for propagating `cx` over the IPC and
+/// remote plugin protocols.
+pub fn traceparent(cx: &SpanContext) -> String {
+    format!(
+        "00-{}-{}-{:02x}",
+        cx.trace_id(),
+        cx.span_id(),
+        cx.trace_flags().to_u8()
+    )
+}
+
+pub fn parse_traceparent(header: &str) -> Option<SpanContext> {
+    let mut parts = header.trim().split('-');
+    let version = parts.next()?;
+    let trace_id = parts.next()?;
+    let span_id = parts.next()?;
+    let flags = parts.next()?;
+    if version != "00" || trace_id.len() != 32 || span_id.len() != 16 || flags.len() != 2 {
+        return None;
+    }
+    let cx = SpanContext::new(
+        TraceId::from_hex(trace_id).ok()?,
+        SpanId::from_hex(span_id).ok()?,
+        TraceFlags::new(u8::from_str_radix(flags, 16).ok()?),
+        true,
+        TraceState::default(),
+    );
+    cx.is_valid().then_some(cx)
+}
+
+/// Wraps a plugin so that `open`, `close`, config changes and `process` are
+/// recorded as spans.
+pub struct Traced<P> {
+    inner: P,
+    recorder: SpanRecorder,
+    tick: u64,
+}
+
+impl<P: Plugin> Traced<P> {
+    pub fn new(inner: P, recorder: SpanRecorder) -> Self {
+        Self {
+            inner,
+            recorder,
+            tick: 0,
+        }
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut P {
+        &mut self.inner
+    }
+
+    pub fn recorder_mut(&mut self) -> &mut SpanRecorder {
+        &mut self.recorder
+    }
+
+    pub fn into_inner(self) -> P {
+        self.inner
+    }
+}
+
+impl<P: Plugin> Plugin for Traced<P> {
+    fn id(&self) -> PluginId {
+        self.inner.id()
+    }
+
+    fn meta(&self) -> &PluginMeta {
+        self.inner.meta()
+    }
+
+    fn inputs(&self) -> &[Port] {
+        self.inner.inputs()
+    }
+
+    fn outputs(&self) -> &[Port] {
+        self.inner.outputs()
+    }
+
+    fn process(&mut self, ctx: &mut PluginContext) -> Result<(), PluginError> {
+        self.tick = ctx.tick;
+        let id = self.inner.id();
+        let inner = &mut self.inner;
+        self.recorder
+            .trace(SpanKind::Process, id, ctx.tick, || inner.process(ctx))
+    }
+
+    fn ui_schema(&self) -> Option<UISchema> {
+        self.inner.ui_schema()
+    }
+
+    fn behavior(&self) -> PluginBehavior {
+        self.inner.behavior()
+    }
+
+    fn connection_behavior(&self) -> ConnectionBehavior {
+        self.inner.connection_behavior()
+    }
+
+    fn init_params(&mut self, params: Value) -> Result<(), PluginError> {
+        self.inner.init_params(params)
+    }
+
+    fn on_input_added(&mut self, port: &str) -> Result<(), PluginError> {
+        self.inner.on_input_added(port)
+    }
+
+    fn on_input_removed(&mut self, port: &str) -> Result<(), PluginError> {
+        self.inner.on_input_removed(port)
+    }
+
+    fn on_output_added(&mut self, port: &str) -> Result<(), PluginError> {
+        self.inner.on_output_added(port)
+    }
+
+    fn on_output_removed(&mut self, port: &str) -> Result<(), PluginError> {
+        self.inner.on_output_removed(port)
+    }
+
+    fn on_config_changed(&mut self, changed: &ConfigDelta) -> Result<(), PluginError> {
+        let id = self.inner.id();
+        let inner = &mut self.inner;
+        self.recorder.trace(SpanKind::Config, id, self.tick, || {
+            inner.on_config_changed(changed)
+        })
+    }
+
+    fn save_state(&self) -> Result<Value, PluginError> {
+        self.inner.save_state()
+    }
+
+    fn restore_state(&mut self, state: Value) -> Result<(), PluginError> {
+        self.inner.restore_state(state)
+    }
+
+    fn migrate_config(&self, from_version: u32, old: Value) -> Result<Value, PluginError> {
+        self.inner.migrate_config(from_version, old)
+    }
+
+    fn options_for(&self, key: &str) -> Vec<ChoiceOption> {
+        self.inner.options_for(key)
+    }
+
+    fn action(&mut self, name: &str, args: &Value) -> Result<Option<Value>, PluginError> {
+        self.inner.action(name, args)
+    }
+
+    fn on_action(&mut self, action_id: &str) -> Result<(), PluginError> {
+        self.inner.on_action(action_id)
+    }
+
+    fn on_event(&mut self, event: &Event) -> Result<(), PluginError> {
+        self.inner.on_event(event)
+    }
+
+    fn latency_ticks(&self) -> u64 {
+        self.inner.latency_ticks()
+    }
+
+    fn features(&self) -> Vec<FeatureFlag> {
+        self.inner.features()
+    }
+
+    fn set_feature_enabled(&mut self, name: &str, enabled: bool) -> Result<(), PluginError> {
+        self.inner.set_feature_enabled(name, enabled)
+    }
+
+    fn templates(&self) -> Vec<WorkspaceTemplate> {
+        self.inner.templates()
+    }
+
+    fn presets(&self) -> Vec<Preset> {
+        self.inner.presets()
+    }
+
+    fn preview(&self) -> Option<Preview> {
+        self.inner.preview()
+    }
+
+    fn prepare(&mut self, sample_rate: f64, block_size: usize) -> Result<(), PluginError> {
+        self.inner.prepare(sample_rate, block_size)
+    }
+
+    fn on_overrun(&mut self, missed: u64) -> Result<(), PluginError> {
+        self.inner.on_overrun(missed)
+    }
+
+    fn on_start(&mut self) -> Result<(), PluginError> {
+        self.inner.on_start()
+    }
+
+    fn on_stop(&mut self) -> Result<(), PluginError> {
+        self.inner.on_stop()
+    }
+
+    fn on_pause(&mut self) -> Result<(), PluginError> {
+        self.inner.on_pause()
+    }
+
+    fn on_resume(&mut self) -> Result<(), PluginError> {
+        self.inner.on_resume()
+    }
+
+    fn reset(&mut self) -> Result<(), PluginError> {
+        self.inner.reset()
+    }
+
+    fn take_changed_outputs(&mut self) -> u64 {
+        self.inner.take_changed_outputs()
+    }
+
+    fn set_input_vec(&mut self, name: &str, values: &[f64]) -> Result<(), PluginError> {
+        self.inner.set_input_vec(name, values)
+    }
+
+    fn get_output_vec(&self, name: &str, out: &mut [f64]) -> usize {
+        self.inner.get_output_vec(name, out)
+    }
+
+    fn get_output_string(&self, name: &str) -> Option<String> {
+        self.inner.get_output_string(name)
+    }
+
+    fn begin_shutdown(&mut self) -> Result<ShutdownStatus, PluginError> {
+        self.inner.begin_shutdown()
+    }
+
+    fn poll_shutdown(&mut self) -> Result<ShutdownStatus, PluginError> {
+        self.inner.poll_shutdown()
+    }
+
+    fn duplicate(&self) -> Option<Box<dyn Plugin>> {
+        self.inner.duplicate()
+    }
+}
+
+impl<P: DeviceDriver> DeviceDriver for Traced<P> {
+    fn open(&mut self) -> Result<(), PluginError> {
+        let id = self.inner.id();
+        let inner = &mut self.inner;
+        self.recorder
+            .trace(SpanKind::Open, id, self.tick, || inner.open())
+    }
+
+    fn close(&mut self) -> Result<(), PluginError> {
+        let id = self.inner.id();
+        let inner = &mut self.inner;
+        self.recorder
+            .trace(SpanKind::Close, id, self.tick, || inner.close())
+    }
+
+    fn enumerate(&self) -> Vec<DeviceInfo> {
+        self.inner.enumerate()
+    }
+
+    fn select(&mut self, device: &DeviceInfo) -> Result<(), PluginError> {
+        self.inner.select(device)
+    }
+
+    fn on_device_lost(&mut self) -> Result<(), PluginError> {
+        self.inner.on_device_lost()
+    }
+
+    fn on_device_available(&mut self) -> Result<(), PluginError> {
+        self.inner.on_device_available()
+    }
+
+    fn reconnect_policy(&self) -> ReconnectPolicy {
+        self.inner.reconnect_policy()
+    }
+}
+
+impl<P: DiagnosticMeasurement> DiagnosticMeasurement for Traced<P> {
+    fn measurable_quantities(&self) -> Vec<MeasurableQuantity> {
+        self.inner.measurable_quantities()
+    }
+
+    fn run_measurement(
+        &mut self,
+        quantity: &str,
+        progress: &mut dyn FnMut(MeasurementProgress) -> bool,
+    ) -> Result<MeasurementReport, PluginError> {
+        self.inner.run_measurement(quantity, progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::noop::NoopTracer;
+
+    #[test]
+    fn process_calls_are_sampled() {
+        let (mut recorder, exporter) = span_channel(16, 4);
+        for tick in 0..8 {
+            recorder
+                .trace(SpanKind::Process, PluginId(1), tick, || Ok(()))
+                .unwrap();
+        }
+        recorder
+            .trace(SpanKind::Config, PluginId(1), 8, || Ok(()))
+            .unwrap();
+
+        let ticks: Vec<(SpanKind, u64)> = exporter
+            .receiver
+            .try_iter()
+            .map(|r| (r.kind, r.tick))
+            .collect();
+        assert_eq!(
+            ticks,
+            [
+                (SpanKind::Process, 0),
+                (SpanKind::Process, 4),
+                (SpanKind::Config, 8)
+            ]
+        );
+    }
+
+    #[test]
+    fn full_queue_drops_instead_of_blocking() {
+        let (mut recorder, exporter) = span_channel(1, 1);
+        for tick in 0..3 {
+            let _ = recorder.trace(SpanKind::Process, PluginId(1), tick, || {
+                Err::<(), _>(PluginError::Recoverable("x".into()))
+            });
+        }
+        assert_eq!(recorder.dropped(), 2);
+        assert_eq!(exporter.export(&NoopTracer::new()), 1);
+    }
+
+    #[test]
+    fn traceparent_round_trip() {
+        let header = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let cx = parse_traceparent(header).unwrap();
+        assert!(cx.is_sampled());
+        assert_eq!(traceparent(&cx), header);
+        assert!(
+            parse_traceparent("00-00000000000000000000000000000000-00f067aa0ba902b7-01").is_none()
+        );
+        assert!(parse_traceparent("garbage").is_none());
+    }
+
+    #[test]
+    fn remote_requests_parent_plugin_spans() {
+        use crate::null::NullSink;
+        use crate::remote::{RemotePluginServer, Request, Response};
+
+        let header = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let (recorder, exporter) = span_channel(16, 1);
+        let plugin = Traced::new(NullSink::new(PluginId(3), Vec::new()), recorder);
+        let mut server = RemotePluginServer::new(plugin, |_, _, _| {}, |_, _| 0.0)
+            .trace_with(Traced::recorder_mut);
+        let process = |traceparent: Option<&str>| Request::Process {
+            tick: 1,
+            period_seconds: 0.001,
+            connected_inputs: 0,
+            connected_outputs: 0,
+            wall_time_ns: 0,
+            monotonic_ns: 0,
+            missed_ticks: 0,
+            jitter_ns: 0,
+            automation: Vec::new(),
+            traceparent: traceparent.map(str::to_string),
+        };
+        assert!(matches!(
+            server.handle(process(Some(header))),
+            Response::Processed { .. }
+        ));
+        server.handle(process(None));
+
+        let parents: Vec<_> = exporter.receiver.try_iter().map(|r| r.parent).collect();
+        let cx = parse_traceparent(header).unwrap();
+        assert_eq!(
+            parents,
+            [Some((cx.trace_id(), cx.span_id(), cx.trace_flags())), None]
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! Several plugins wired together without a host, for integration tests and
+//! for embedding a fixed chain in an application.
+//!
+//! ```ignore
+//! let mut pipeline = Pipeline::builder()
+//!     .add("source", Sine::new(), Sine::set, Sine::get)
+//!     .add("gain", Gain::new(2.0), Gain::set, Gain::get)
+//!     .connect("source.out", "gain.in")
+//!     .build()?;
+//! pipeline.run(100)?;
+//! assert!(pipeline.output("gain.out")?.abs() <= 2.0);
+//! ```
+//!
+//! Wiring follows `composite::CompositePlugin`: endpoints are
+//! `"plugin.port"` and plugins run in dependency order, so every input sees
+//! the output it is connected to from the same tick.
+
+use crate::composite::{self, Endpoint, Graph, Node};
+use crate::event::Event;
+use crate::{Plugin, PluginContext, PluginError};
+use std::time::Duration;
+
+pub struct PipelineBuilder {
+    nodes: Vec<(String, Box<dyn Node>)>,
+    connections: Vec<(String, String)>,
+    period_seconds: f64,
+}
+
+impl PipelineBuilder {
+    /// Adds `plugin` under `name`, which must not contain a dot. As with
+    /// `testing::MockHost::io`, the pipeline writes and reads its ports with
+    /// `set_input` and `get_output`.
+    pub fn add<P: Plugin + 'static>(
+        mut self,
+        name: impl Into<String>,
+        plugin: P,
+        set_input: fn(&mut P, &str, f64),
+        get_output: fn(&P, &str) -> f64,
+    ) -> Self {
+        self.nodes
+            .push((name.into(), composite::node(plugin, set_input, get_output)));
+        self
+    }
+
+    /// Feeds output `from` into input `to`, e.g. `("a.out", "b.in")`.
+    pub fn connect(mut self, from: &str, to: &str) -> Self {
+        self.connections.push((from.to_string(), to.to_string()));
+        self
+    }
+
+    /// Seconds per tick, 1 ms by default.
+    pub fn period(mut self, seconds: f64) -> Self {
+        self.period_seconds = seconds;
+        self
+    }
+
+    /// Fails for unknown plugins or ports, inputs fed twice and cycles.
+    pub fn build(self) -> Result<Pipeline, PluginError> {
+        Ok(Pipeline {
+            graph: Graph::new(self.nodes, &self.connections)?,
+            ctx: PluginContext {
+                period_seconds: self.period_seconds,
+                ..PluginContext::default()
+            },
+            inputs: Vec::new(),
+            events: Vec::new(),
+        })
+    }
+}
+
+pub struct Pipeline {
+    graph: Graph,
+    ctx: PluginContext,
+    // Inputs set from outside, written again before every tick.
+    inputs: Vec<(Endpoint, f64)>,
+    events: Vec<Event>,
+}
+
+impl Pipeline {
+    pub fn builder() -> PipelineBuilder {
+        PipelineBuilder {
+            nodes: Vec::new(),
+            connections: Vec::new(),
+            period_seconds: 0.001,
+        }
+    }
+
+    /// Holds input `endpoint` at `value` from the next tick on.
+    pub fn set_input(&mut self, endpoint: &str, value: f64) -> Result<(), PluginError> {
+        let endpoint = self.graph.input(endpoint)?;
+        match self.inputs.iter_mut().find(|(held, _)| *held == endpoint) {
+            Some(input) => input.1 = value,
+            None => {
+                self.graph.expose(&endpoint, false);
+                self.inputs.push((endpoint, value));
+            }
+        }
+        Ok(())
+    }
+
+    /// Output `endpoint` as of the last tick.
+    pub fn output(&self, endpoint: &str) -> Result<f64, PluginError> {
+        Ok(self.graph.get_output(&self.graph.output(endpoint)?))
+    }
+
+    /// Calls `Plugin::prepare` on every plugin and records the rates in the
+    /// context.
+    pub fn prepare(&mut self, sample_rate: f64, block_size: usize) -> Result<(), PluginError> {
+        self.ctx.sample_rate = sample_rate;
+        self.ctx.block_size = block_size;
+        self.graph
+            .for_each(|_, plugin| plugin.prepare(sample_rate, block_size))
+    }
+
+    /// Writes the held inputs, routes outputs to the inputs they feed and
+    /// processes every plugin once.
+    pub fn tick(&mut self) -> Result<(), PluginError> {
+        for (endpoint, value) in &self.inputs {
+            self.graph.set_input(endpoint, *value);
+            self.graph.mark_updated(endpoint, Some(self.ctx.tick));
+        }
+        let result = self.graph.process(&mut self.ctx);
+        self.events.append(&mut self.ctx.events);
+        let elapsed = Duration::from_secs_f64(self.ctx.period_seconds.max(0.0));
+        self.ctx.tick += 1;
+        self.ctx.wall_time += elapsed;
+        self.ctx.monotonic_ns += elapsed.as_nanos() as u64;
+        result
+    }
+
+    pub fn run(&mut self, ticks: u64) -> Result<(), PluginError> {
+        for _ in 0..ticks {
+            self.tick()?;
+        }
+        Ok(())
+    }
+
+    /// Number of ticks run so far.
+    pub fn ticks(&self) -> u64 {
+        self.ctx.tick
+    }
+
+    /// Events emitted by any plugin, in emission order.
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    pub fn plugin(&self, name: &str) -> Option<&dyn Plugin> {
+        self.graph.plugin(name)
+    }
+
+    pub fn context_mut(&mut self) -> &mut PluginContext {
+        &mut self.ctx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ports::StaticPorts;
+    use crate::{PluginId, PluginMeta, Port};
+    use serde_json::Value;
+
+    // Emits its tick number, or adds its two inputs.
+    struct Node {
+        meta: PluginMeta,
+        ports: StaticPorts<2, 1>,
+        counter: bool,
+    }
+
+    impl Node {
+        fn new(counter: bool) -> Self {
+            Self {
+                meta: PluginMeta::new("node"),
+                ports: StaticPorts::new(["a", "b"], ["out"]),
+                counter,
+            }
+        }
+
+        fn set(&mut self, name: &str, value: f64) {
+            self.ports.set_input_by_name(name, value)
+        }
+
+        fn get(&self, name: &str) -> f64 {
+            self.ports.output_by_name(name)
+        }
+    }
+
+    impl Plugin for Node {
+        fn id(&self) -> PluginId {
+            PluginId(0)
+        }
+        fn meta(&self) -> &PluginMeta {
+            &self.meta
+        }
+        fn inputs(&self) -> &[Port] {
+            self.ports.inputs()
+        }
+        fn outputs(&self) -> &[Port] {
+            self.ports.outputs()
+        }
+        fn process(&mut self, ctx: &mut PluginContext) -> Result<(), PluginError> {
+            let value = if self.counter {
+                ctx.tick as f64
+            } else {
+                self.ports.input(0) + self.ports.input(1)
+            };
+            self.ports.set_output(0, value);
+            if value > 10.0 {
+                ctx.emit(Event::new(PluginId(0), "high", Value::from(value)));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn routes_outputs_within_the_tick() {
+        let mut pipeline = Pipeline::builder()
+            .add("sum", Node::new(false), Node::set, Node::get)
+            .add("count", Node::new(true), Node::set, Node::get)
+            .connect("count.out", "sum.a")
+            .build()
+            .unwrap();
+        pipeline.set_input("sum.b", 10.0).unwrap();
+        pipeline.run(3).unwrap();
+        assert_eq!(pipeline.ticks(), 3);
+        assert_eq!(pipeline.output("count.out").unwrap(), 2.0);
+        assert_eq!(pipeline.output("sum.out").unwrap(), 12.0);
+        assert_eq!(pipeline.events().len(), 2);
+
+        assert!(pipeline.set_input("sum.c", 1.0).is_err());
+        assert!(pipeline.output("nope.out").is_err());
+    }
+}
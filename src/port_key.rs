@@ -0,0 +1,129 @@
+//! Interned port names.
+//!
+//! A [`PortKey`] is a `u32` standing for a port name in a process-wide
+//! registry, so hosts can copy, compare and hash port references on every
+//! tick without touching the string. Names are interned once, typically when
+//! ports are declared or a connection is set up, and never freed; the set of
+//! distinct port names in a process is small. Keys are only meaningful inside
+//! the process that interned them, which is why they serialize as the name.
+//!
+//! Looking a key up takes a lock on the registry, so resolve keys to port
+//! indices or names during setup, as [`StaticPorts`](crate::ports::StaticPorts)
+//! does, rather than per tick. Deserializing never registers a name: data
+//! read from a file or the network must not grow the registry.
+
+use crate::PortId;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{OnceLock, RwLock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PortKey(u32);
+
+#[derive(Default)]
+struct Registry {
+    keys: HashMap<&'static str, u32>,
+    names: Vec<&'static str>,
+}
+
+fn registry() -> &'static RwLock<Registry> {
+    static REGISTRY: OnceLock<RwLock<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+impl PortKey {
+    /// The key of `name`, registering it on first use.
+    pub fn intern(name: &str) -> Self {
+        if let Some(key) = Self::get(name) {
+            return key;
+        }
+        let mut registry = registry().write().unwrap_or_else(|e| e.into_inner());
+        // Another thread may have registered it since the read above.
+        if let Some(&key) = registry.keys.get(name) {
+            return PortKey(key);
+        }
+        let name: &'static str = Box::leak(name.into());
+        let key = registry.names.len() as u32;
+        registry.names.push(name);
+        registry.keys.insert(name, key);
+        PortKey(key)
+    }
+
+    /// The key of `name` if it was interned before, without registering it.
+    pub fn get(name: &str) -> Option<Self> {
+        let registry = registry().read().unwrap_or_else(|e| e.into_inner());
+        registry.keys.get(name).copied().map(PortKey)
+    }
+
+    /// Takes a read lock; keep it out of per-tick code.
+    pub fn as_str(self) -> &'static str {
+        let registry = registry().read().unwrap_or_else(|e| e.into_inner());
+        registry.names[self.0 as usize]
+    }
+
+    /// The raw registry index, e.g. to index a host-side table.
+    pub fn index(self) -> u32 {
+        self.0
+    }
+}
+
+impl fmt::Display for PortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&str> for PortKey {
+    fn from(name: &str) -> Self {
+        PortKey::intern(name)
+    }
+}
+
+impl From<&PortId> for PortKey {
+    fn from(id: &PortId) -> Self {
+        PortKey::intern(&id.0)
+    }
+}
+
+impl From<PortKey> for PortId {
+    fn from(key: PortKey) -> Self {
+        PortId(key.as_str().to_string())
+    }
+}
+
+impl Serialize for PortKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+// Only names that were interned before, e.g. by declaring the port, are
+// accepted.
+impl<'de> Deserialize<'de> for PortKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        PortKey::get(&name).ok_or_else(|| serde::de::Error::custom(format!("unknown port {name}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interns_once() {
+        let key = PortKey::intern("port_key_test_in");
+        assert_eq!(PortKey::intern("port_key_test_in"), key);
+        assert_ne!(PortKey::intern("port_key_test_out"), key);
+        assert_eq!(key.as_str(), "port_key_test_in");
+        assert_eq!(PortKey::get("port_key_test_missing"), None);
+
+        let json = serde_json::to_string(&key).unwrap();
+        assert_eq!(json, "\"port_key_test_in\"");
+        assert_eq!(serde_json::from_str::<PortKey>(&json).unwrap(), key);
+        assert!(serde_json::from_str::<PortKey>("\"port_key_test_unknown\"").is_err());
+        assert_eq!(PortKey::get("port_key_test_unknown"), None);
+        assert_eq!(PortId::from(key).key(), key);
+    }
+}
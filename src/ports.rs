@@ -0,0 +1,736 @@
+use crate::port_key::PortKey;
+use crate::{Port, PortId, PortStatus, INVALID_PORT_HANDLE};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "derive")]
+pub use rtsyn_plugin_derive::StaticPorts;
+
+/// Fixed port set known at compile time.
+///
+/// Values are stored in plain arrays indexed by the constants generated with
+/// [`static_ports!`](crate::static_ports), so per-tick access never touches a
+/// string. Name lookups are only needed at the FFI boundary.
+#[derive(Debug, Clone)]
+pub struct StaticPorts<const IN: usize, const OUT: usize> {
+    inputs: Vec<Port>,
+    outputs: Vec<Port>,
+    input_values: [f64; IN],
+    output_values: [f64; OUT],
+    changed: ChangedOutputs,
+    // Interned ids and aliases with the index they resolve to.
+    input_keys: Vec<(PortKey, usize)>,
+    output_keys: Vec<(PortKey, usize)>,
+}
+
+impl<const IN: usize, const OUT: usize> StaticPorts<IN, OUT> {
+    pub fn new(inputs: [&str; IN], outputs: [&str; OUT]) -> Self {
+        Self {
+            inputs: inputs.iter().map(|name| Port::new(*name)).collect(),
+            outputs: outputs.iter().map(|name| Port::new(*name)).collect(),
+            input_values: [0.0; IN],
+            output_values: [0.0; OUT],
+            changed: ChangedOutputs::all(),
+            input_keys: keys(&inputs),
+            output_keys: keys(&outputs),
+        }
+    }
+
+    pub fn inputs(&self) -> &[Port] {
+        &self.inputs
+    }
+
+    pub fn outputs(&self) -> &[Port] {
+        &self.outputs
+    }
+
+    #[inline]
+    pub fn input(&self, index: usize) -> f64 {
+        self.input_values[index]
+    }
+
+    #[inline]
+    pub fn set_input(&mut self, index: usize, value: f64) {
+        self.input_values[index] = value;
+    }
+
+    #[inline]
+    pub fn output(&self, index: usize) -> f64 {
+        self.output_values[index]
+    }
+
+    #[inline]
+    pub fn set_output(&mut self, index: usize, value: f64) {
+        self.changed
+            .update(index, &mut self.output_values[index], value);
+    }
+
+    pub fn input_values(&self) -> &[f64; IN] {
+        &self.input_values
+    }
+
+    /// Direct write access to the outputs; everything is marked changed.
+    pub fn output_values_mut(&mut self) -> &mut [f64; OUT] {
+        self.changed = ChangedOutputs::all();
+        &mut self.output_values
+    }
+
+    pub fn changed_outputs(&self) -> &ChangedOutputs {
+        &self.changed
+    }
+
+    /// Returns the changed-output mask and clears it, for `Plugin::take_changed_outputs`.
+    pub fn take_changed_outputs(&mut self) -> u64 {
+        self.changed.take()
+    }
+
+    /// Renames or aliases are accepted, see [`Port::alias`]. Ids take
+    /// precedence over aliases.
+    pub fn input_index(&self, name: &str) -> Option<usize> {
+        port_index(&self.inputs, name)
+    }
+
+    pub fn output_index(&self, name: &str) -> Option<usize> {
+        port_index(&self.outputs, name)
+    }
+
+    /// Like [`StaticPorts::input_index`] without comparing strings.
+    pub fn input_index_by_key(&self, key: PortKey) -> Option<usize> {
+        find_key(&self.input_keys, key)
+    }
+
+    pub fn output_index_by_key(&self, key: PortKey) -> Option<usize> {
+        find_key(&self.output_keys, key)
+    }
+
+    /// Unknown keys are ignored.
+    pub fn set_input_by_key(&mut self, key: PortKey, value: f64) {
+        if let Some(index) = self.input_index_by_key(key) {
+            self.input_values[index] = value;
+        }
+    }
+
+    /// Unknown keys read as `0.0`.
+    pub fn output_by_key(&self, key: PortKey) -> f64 {
+        self.output_index_by_key(key)
+            .map_or(0.0, |index| self.output_values[index])
+    }
+
+    /// Adds a former name of input `index`.
+    pub fn alias_input(mut self, index: usize, old_id: &str) -> Self {
+        self.inputs[index].aliases.push(old_id.to_string());
+        self.input_keys.push((PortKey::intern(old_id), index));
+        self
+    }
+
+    pub fn alias_output(mut self, index: usize, old_id: &str) -> Self {
+        self.outputs[index].aliases.push(old_id.to_string());
+        self.output_keys.push((PortKey::intern(old_id), index));
+        self
+    }
+
+    /// Sets output `index` only when `filter` lets the value through, so the
+    /// dirty bit is raised for meaningful changes only.
+    pub fn set_output_on_change(
+        &mut self,
+        index: usize,
+        filter: &mut OnChange,
+        tick: u64,
+        value: f64,
+    ) {
+        if filter.update(tick, value) {
+            self.output_values[index] = value;
+            self.changed.mark(index);
+        }
+    }
+
+    /// Handle for the FFI `resolve_input` entry point: the input index, or
+    /// [`INVALID_PORT_HANDLE`].
+    pub fn resolve_input(&self, name: &str) -> u32 {
+        self.input_index(name)
+            .map_or(INVALID_PORT_HANDLE, |index| index as u32)
+    }
+
+    pub fn resolve_output(&self, name: &str) -> u32 {
+        self.output_index(name)
+            .map_or(INVALID_PORT_HANDLE, |index| index as u32)
+    }
+
+    /// Write for the FFI `set_input_by_handle` entry point. Invalid handles
+    /// are reported as [`PortStatus::UnknownPort`].
+    pub fn set_input_by_handle(&mut self, port: u32, value: f64) -> PortStatus {
+        match self.input_values.get_mut(port as usize) {
+            Some(slot) => {
+                *slot = value;
+                PortStatus::Ok
+            }
+            None => PortStatus::UnknownPort,
+        }
+    }
+
+    /// Read for the FFI `get_output_by_handle` entry point. Invalid handles
+    /// read as `0.0`.
+    pub fn output_by_handle(&self, port: u32) -> f64 {
+        self.output_values
+            .get(port as usize)
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// Name-based write for the FFI `set_input` entry point. Unknown names are ignored.
+    pub fn set_input_by_name(&mut self, name: &str, value: f64) {
+        if let Some(index) = self.input_index(name) {
+            self.input_values[index] = value;
+        }
+    }
+
+    /// Name-based read for the FFI `get_output` entry point. Unknown names read as `0.0`.
+    pub fn output_by_name(&self, name: &str) -> f64 {
+        self.output_index(name)
+            .map(|index| self.output_values[index])
+            .unwrap_or(0.0)
+    }
+
+    /// Write for the FFI `set_input_checked` entry point.
+    pub fn set_input_checked(&mut self, name: &str, value: f64) -> PortStatus {
+        match self.input_index(name) {
+            Some(index) => {
+                self.input_values[index] = value;
+                PortStatus::Ok
+            }
+            None => missing_port(name, &self.outputs),
+        }
+    }
+
+    /// Read for the FFI `get_output_checked` entry point.
+    pub fn output_checked(&self, name: &str) -> Result<f64, PortStatus> {
+        match self.output_index(name) {
+            Some(index) => Ok(self.output_values[index]),
+            None => Err(missing_port(name, &self.inputs)),
+        }
+    }
+}
+
+fn keys(names: &[&str]) -> Vec<(PortKey, usize)> {
+    names
+        .iter()
+        .enumerate()
+        .map(|(index, name)| (PortKey::intern(name), index))
+        .collect()
+}
+
+fn find_key(keys: &[(PortKey, usize)], key: PortKey) -> Option<usize> {
+    keys.iter()
+        .find(|(candidate, _)| *candidate == key)
+        .map(|(_, index)| *index)
+}
+
+/// Body of a `Plugin::get_output_vec` implementation: copies what fits of
+/// `values` into `out` and returns the full length.
+pub fn write_vec(values: &[f64], out: &mut [f64]) -> usize {
+    let count = values.len().min(out.len());
+    out[..count].copy_from_slice(&values[..count]);
+    values.len()
+}
+
+/// Status for a name not found among the ports it was meant for.
+pub(crate) fn missing_port(name: &str, other_direction: &[Port]) -> PortStatus {
+    if other_direction.iter().any(|p| p.matches(name)) {
+        PortStatus::WrongDirection
+    } else {
+        PortStatus::UnknownPort
+    }
+}
+
+/// Index of the port `name` refers to, see [`resolve_port`].
+pub fn port_index(ports: &[Port], name: &str) -> Option<usize> {
+    resolve_port(ports, name).map(|found| found.index)
+}
+
+/// Port found for a saved name by [`resolve_port`].
+#[derive(Debug, Clone, Copy)]
+pub struct ResolvedPort<'a> {
+    pub index: usize,
+    pub port: &'a Port,
+    /// The deprecated alias the name matched, if it was not the current id.
+    pub alias: Option<&'a str>,
+}
+
+impl ResolvedPort<'_> {
+    pub fn is_deprecated(&self) -> bool {
+        self.alias.is_some()
+    }
+
+    /// User-facing notice for connections restored through an alias.
+    pub fn deprecation_message(&self) -> Option<String> {
+        self.alias.map(|alias| {
+            format!(
+                "port \"{alias}\" was renamed to \"{}\"; re-save to update the connection",
+                self.port.id.0
+            )
+        })
+    }
+}
+
+/// Finds the port a saved connection refers to. Current ids win over
+/// aliases, so an alias can later be reused as a new port id.
+pub fn resolve_port<'a>(ports: &'a [Port], name: &str) -> Option<ResolvedPort<'a>> {
+    if let Some(index) = ports.iter().position(|p| p.id.0 == name) {
+        return Some(ResolvedPort {
+            index,
+            port: &ports[index],
+            alias: None,
+        });
+    }
+    ports.iter().enumerate().find_map(|(index, port)| {
+        let alias = port.aliases.iter().find(|alias| *alias == name)?;
+        Some(ResolvedPort {
+            index,
+            port,
+            alias: Some(alias.as_str()),
+        })
+    })
+}
+
+/// Members of bus `group` in bus order.
+pub fn bus_members<'a>(ports: &'a [Port], group: &str) -> Vec<&'a Port> {
+    let mut members: Vec<&Port> = ports
+        .iter()
+        .filter(|port| port.group.as_deref() == Some(group))
+        .collect();
+    members.sort_by_key(|port| port.group_index.unwrap_or(u32::MAX));
+    members
+}
+
+/// Names of the buses among `ports`, in order of their first member.
+pub fn bus_names(ports: &[Port]) -> Vec<&str> {
+    let mut names: Vec<&str> = Vec::new();
+    for group in ports.iter().filter_map(|port| port.group.as_deref()) {
+        if !names.contains(&group) {
+            names.push(group);
+        }
+    }
+    names
+}
+
+/// `(output, input)` pairs wiring bus `from` to bus `to` member by member,
+/// for a single "connect bus" action. `None` when either bus is missing or
+/// the two differ in width.
+pub fn connect_bus<'a>(
+    outputs: &'a [Port],
+    from: &str,
+    inputs: &'a [Port],
+    to: &str,
+) -> Option<Vec<(&'a PortId, &'a PortId)>> {
+    let sources = bus_members(outputs, from);
+    let targets = bus_members(inputs, to);
+    if sources.is_empty() || sources.len() != targets.len() {
+        return None;
+    }
+    Some(
+        sources
+            .iter()
+            .zip(&targets)
+            .map(|(source, target)| (&source.id, &target.id))
+            .collect(),
+    )
+}
+
+/// Inputs whose connection count breaks their declaration, as messages the
+/// host shows when it refuses to start the graph. `connections` gives the
+/// number of connections currently ending at a port.
+pub fn connection_problems(inputs: &[Port], connections: impl Fn(&PortId) -> usize) -> Vec<String> {
+    let mut problems = Vec::new();
+    for port in inputs {
+        let count = connections(&port.id);
+        if port.accepts_connections(count) {
+            continue;
+        }
+        if count == 0 {
+            problems.push(format!("required input {} is not connected", port.id.0));
+        } else {
+            problems.push(format!(
+                "input {} accepts at most {} connection(s) but has {count}",
+                port.id.0,
+                port.max_connections.unwrap_or(1)
+            ));
+        }
+    }
+    problems
+}
+
+/// Dirty bits for outputs written since the host last read them.
+///
+/// Bit `i` is set when output `i` changed. Outputs at index 63 and above all
+/// share the last bit, so hosts must re-read every such output when it is set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChangedOutputs {
+    mask: u64,
+}
+
+impl ChangedOutputs {
+    pub const ALL: u64 = u64::MAX;
+
+    pub fn new() -> Self {
+        Self { mask: 0 }
+    }
+
+    pub fn all() -> Self {
+        Self { mask: Self::ALL }
+    }
+
+    pub(crate) fn bit(index: usize) -> u64 {
+        1 << index.min(63)
+    }
+
+    #[inline]
+    pub fn mark(&mut self, index: usize) {
+        self.mask |= Self::bit(index);
+    }
+
+    pub fn mark_all(&mut self) {
+        self.mask = Self::ALL;
+    }
+
+    /// Writes `value` into `slot`, marking `index` only if the value differs.
+    #[inline]
+    pub fn update(&mut self, index: usize, slot: &mut f64, value: f64) {
+        if slot.to_bits() != value.to_bits() {
+            *slot = value;
+            self.mark(index);
+        }
+    }
+
+    pub fn is_changed(&self, index: usize) -> bool {
+        self.mask & Self::bit(index) != 0
+    }
+
+    pub fn mask(&self) -> u64 {
+        self.mask
+    }
+
+    pub fn clear(&mut self) {
+        self.mask = 0;
+    }
+
+    pub fn take(&mut self) -> u64 {
+        std::mem::take(&mut self.mask)
+    }
+}
+
+/// Minimum change before an output counts as changed for [`OnChange`].
+///
+/// A new value passes when it differs from the last forwarded one by more
+/// than the larger of `absolute` and `relative * |last|`, or when nothing
+/// was forwarded for `max_silence_ticks`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Deadband {
+    #[serde(default)]
+    pub absolute: f64,
+    #[serde(default)]
+    pub relative: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_silence_ticks: Option<u64>,
+}
+
+impl Deadband {
+    pub fn absolute(threshold: f64) -> Self {
+        Self {
+            absolute: threshold,
+            ..Self::default()
+        }
+    }
+
+    /// `fraction` of the last forwarded value, e.g. `0.01` for 1 %.
+    pub fn relative(fraction: f64) -> Self {
+        Self {
+            relative: fraction,
+            ..Self::default()
+        }
+    }
+
+    /// Forwards at least every `ticks` ticks, as a keep-alive.
+    pub fn max_silence_ticks(mut self, ticks: u64) -> Self {
+        self.max_silence_ticks = Some(ticks);
+        self
+    }
+
+    pub fn exceeded(&self, last: f64, value: f64) -> bool {
+        if last.is_nan() || value.is_nan() {
+            return last.is_nan() != value.is_nan();
+        }
+        let threshold = self.absolute.max(self.relative * last.abs());
+        (value - last).abs() > threshold || (threshold == 0.0 && value != last)
+    }
+}
+
+/// On-change filter for one output, e.g. in front of a telemetry uplink that
+/// pays per message. Works with [`ChangedOutputs`] through
+/// [`OnChange::update_mark`] or [`StaticPorts::set_output_on_change`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct OnChange {
+    deadband: Deadband,
+    // Last forwarded value and its tick.
+    sent: Option<(f64, u64)>,
+}
+
+impl OnChange {
+    pub fn new(deadband: Deadband) -> Self {
+        Self {
+            deadband,
+            sent: None,
+        }
+    }
+
+    pub fn deadband(&self) -> &Deadband {
+        &self.deadband
+    }
+
+    /// Last forwarded value.
+    pub fn sent(&self) -> Option<f64> {
+        self.sent.map(|(value, _)| value)
+    }
+
+    /// Whether `value` at `tick` should be forwarded; if so it becomes the
+    /// new reference. The first value always passes.
+    pub fn update(&mut self, tick: u64, value: f64) -> bool {
+        let forward = match self.sent {
+            None => true,
+            Some((last, last_tick)) => {
+                self.deadband.exceeded(last, value)
+                    || self
+                        .deadband
+                        .max_silence_ticks
+                        .is_some_and(|ticks| tick.saturating_sub(last_tick) >= ticks)
+            }
+        };
+        if forward {
+            self.sent = Some((value, tick));
+        }
+        forward
+    }
+
+    /// [`OnChange::update`], marking `index` in `changed` when forwarded.
+    pub fn update_mark(
+        &mut self,
+        changed: &mut ChangedOutputs,
+        index: usize,
+        tick: u64,
+        value: f64,
+    ) -> bool {
+        let forward = self.update(tick, value);
+        if forward {
+            changed.mark(index);
+        }
+        forward
+    }
+
+    /// Forgets the reference, so the next value passes.
+    pub fn reset(&mut self) {
+        self.sent = None;
+    }
+}
+
+/// Declares a fixed port set as a module of index constants.
+///
+/// ```
+/// rtsyn_plugin::static_ports! {
+///     pub mod ports {
+///         inputs: [IN_SIGNAL = "signal", IN_GAIN = "gain"],
+///         outputs: [OUT_VALUE = "value"],
+///     }
+/// }
+///
+/// let mut io = ports::new();
+/// io.set_input(ports::IN_GAIN, 2.0);
+/// assert_eq!(ports::IN_GAIN, 1);
+/// assert_eq!(io.inputs()[ports::IN_SIGNAL].id.0, "signal");
+/// ```
+#[macro_export]
+macro_rules! static_ports {
+    (
+        $vis:vis mod $module:ident {
+            inputs: [$($in_const:ident = $in_name:literal),* $(,)?],
+            outputs: [$($out_const:ident = $out_name:literal),* $(,)?] $(,)?
+        }
+    ) => {
+        $vis mod $module {
+            #[allow(non_camel_case_types, dead_code)]
+            enum __Inputs { $($in_const),* }
+            #[allow(non_camel_case_types, dead_code)]
+            enum __Outputs { $($out_const),* }
+
+            $(pub const $in_const: usize = __Inputs::$in_const as usize;)*
+            $(pub const $out_const: usize = __Outputs::$out_const as usize;)*
+
+            pub const INPUTS: &[&str] = &[$($in_name),*];
+            pub const OUTPUTS: &[&str] = &[$($out_name),*];
+
+            pub type Ports = $crate::ports::StaticPorts<{ INPUTS.len() }, { OUTPUTS.len() }>;
+
+            pub fn new() -> Ports {
+                $crate::ports::StaticPorts::new([$($in_name),*], [$($out_name),*])
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    crate::static_ports! {
+        mod ports {
+            inputs: [IN_A = "a", IN_B = "b"],
+            outputs: [OUT_SUM = "sum"],
+        }
+    }
+
+    #[test]
+    fn macro_generates_indices() {
+        assert_eq!(ports::IN_A, 0);
+        assert_eq!(ports::IN_B, 1);
+        assert_eq!(ports::OUT_SUM, 0);
+        assert_eq!(ports::INPUTS, &["a", "b"]);
+    }
+
+    #[test]
+    fn indexed_and_named_access() {
+        let mut io: ports::Ports = ports::new();
+        io.set_input_by_name("a", 1.5);
+        io.set_input(ports::IN_B, 2.5);
+        let sum = io.input(ports::IN_A) + io.input(ports::IN_B);
+        io.set_output(ports::OUT_SUM, sum);
+
+        assert_eq!(io.output_by_name("sum"), 4.0);
+        assert_eq!(io.output_by_name("missing"), 0.0);
+        assert_eq!(io.outputs()[ports::OUT_SUM].id.0, "sum");
+    }
+
+    #[test]
+    fn deadband_suppresses_small_changes() {
+        let mut filter = OnChange::new(Deadband::absolute(0.5).max_silence_ticks(10));
+        let forwarded: Vec<u64> = [(0, 1.0), (1, 1.2), (2, 1.6), (3, 1.7), (13, 1.7)]
+            .into_iter()
+            .filter(|(tick, value)| filter.update(*tick, *value))
+            .map(|(tick, _)| tick)
+            .collect();
+        assert_eq!(forwarded, [0, 2, 13]);
+
+        let relative = Deadband::relative(0.1);
+        assert!(!relative.exceeded(100.0, 109.0));
+        assert!(relative.exceeded(100.0, 111.0));
+        assert!(Deadband::default().exceeded(1.0, f64::NAN));
+        assert!(!Deadband::default().exceeded(2.0, 2.0));
+    }
+
+    #[test]
+    fn on_change_drives_dirty_bits() {
+        let mut io: ports::Ports = ports::new();
+        io.take_changed_outputs();
+        let mut filter = OnChange::new(Deadband::absolute(1.0));
+        io.set_output_on_change(ports::OUT_SUM, &mut filter, 0, 5.0);
+        assert_eq!(io.take_changed_outputs(), 1);
+        io.set_output_on_change(ports::OUT_SUM, &mut filter, 1, 5.5);
+        assert_eq!(io.take_changed_outputs(), 0);
+        assert_eq!(io.output(ports::OUT_SUM), 5.0);
+    }
+
+    #[test]
+    fn key_access() {
+        let mut io: ports::Ports = ports::new();
+        io = io.alias_input(ports::IN_A, "left");
+        io.set_input_by_key(PortKey::intern("left"), 2.0);
+        assert_eq!(io.input(ports::IN_A), 2.0);
+        assert_eq!(
+            io.input_index_by_key(PortKey::intern("b")),
+            Some(ports::IN_B)
+        );
+        assert_eq!(io.output_by_key(PortKey::intern("a")), 0.0);
+    }
+
+    #[test]
+    fn handle_access() {
+        let mut io: ports::Ports = ports::new();
+        let b = io.resolve_input("b");
+        assert_eq!(b, ports::IN_B as u32);
+        assert_eq!(io.resolve_input("missing"), INVALID_PORT_HANDLE);
+        assert!(io.set_input_by_handle(b, 3.0).is_ok());
+        assert_eq!(
+            io.set_input_by_handle(INVALID_PORT_HANDLE, 1.0),
+            PortStatus::UnknownPort
+        );
+        io.set_output(ports::OUT_SUM, io.input(ports::IN_B));
+
+        let sum = io.resolve_output("sum");
+        assert_eq!(io.output_by_handle(sum), 3.0);
+        assert_eq!(io.output_by_handle(INVALID_PORT_HANDLE), 0.0);
+    }
+
+    #[test]
+    fn checked_access_reports_miswiring() {
+        let mut io: ports::Ports = ports::new();
+        assert_eq!(io.set_input_checked("a", 2.0), PortStatus::Ok);
+        assert_eq!(io.set_input_checked("sum", 1.0), PortStatus::WrongDirection);
+        assert_eq!(io.set_input_checked("c", 1.0), PortStatus::UnknownPort);
+        assert_eq!(io.input(ports::IN_A), 2.0);
+
+        io.set_output(ports::OUT_SUM, 4.0);
+        assert_eq!(io.output_checked("sum"), Ok(4.0));
+        assert_eq!(io.output_checked("b"), Err(PortStatus::WrongDirection));
+        assert!(PortStatus::UnknownPort.check("c").is_err());
+    }
+
+    #[test]
+    fn aliases_resolve_with_deprecation() {
+        let ports = [Port::new("rms_out").alias("out1"), Port::new("out1_new")];
+        let resolved = resolve_port(&ports, "out1").unwrap();
+        assert_eq!(resolved.index, 0);
+        assert!(resolved.is_deprecated());
+        assert!(resolved
+            .deprecation_message()
+            .unwrap()
+            .contains("renamed to \"rms_out\""));
+        assert!(!resolve_port(&ports, "rms_out").unwrap().is_deprecated());
+        assert!(resolve_port(&ports, "missing").is_none());
+
+        let io: ports::Ports = ports::new().alias_output(ports::OUT_SUM, "total");
+        assert_eq!(io.output_index("total"), Some(ports::OUT_SUM));
+
+        // An alias never shadows a port that has the name as its id.
+        let io: ports::Ports = ports::new().alias_input(ports::IN_A, "b");
+        assert_eq!(io.input_index("b"), Some(ports::IN_B));
+        assert_eq!(
+            io.input_index_by_key(PortKey::intern("b")),
+            Some(ports::IN_B)
+        );
+    }
+
+    #[test]
+    fn set_output_tracks_changes() {
+        let mut io: ports::Ports = ports::new();
+        assert_eq!(io.take_changed_outputs(), ChangedOutputs::ALL);
+
+        io.set_output(ports::OUT_SUM, 0.0);
+        assert_eq!(io.take_changed_outputs(), 0);
+
+        io.set_output(ports::OUT_SUM, 1.0);
+        assert!(io.changed_outputs().is_changed(ports::OUT_SUM));
+        assert_eq!(io.take_changed_outputs(), 1);
+    }
+
+    #[test]
+    fn high_indices_share_last_bit() {
+        let mut changed = ChangedOutputs::new();
+        changed.mark(70);
+        assert!(changed.is_changed(63));
+        assert!(changed.is_changed(100));
+        assert!(!changed.is_changed(0));
+    }
+
+    #[test]
+    fn empty_port_set() {
+        let io = StaticPorts::<0, 0>::new([], []);
+        assert!(io.inputs().is_empty());
+        assert!(io.outputs().is_empty());
+    }
+}
@@ -1,10 +1,46 @@
 // Prelude for convenient imports
 pub use crate::{
-    DeviceDriver, EventLogger, Plugin, PluginContext, PluginError, PluginId, PluginMeta, Port,
-    PortId, ProcessingUnit,
+    Capability, DeviceDriver, DisplayMeta, ErrorSeverity, EventLogger, HostInfo, Icon, Plugin,
+    PluginCategory, PluginContext, PluginError, PluginId, PluginMeta, PluginUid, Port, PortId,
+    ProcessingUnit, ShutdownStatus,
 };
 
+pub use crate::automation::{ParamChange, ParamRamp};
+
+pub use crate::complex::{Complex, ComplexIn, ComplexOut};
+
+pub use crate::device::{DeviceInfo, DeviceKind};
+
+pub use crate::diagnostics::{
+    DiagnosticMeasurement, MeasurableQuantity, MeasurementProgress, MeasurementReport,
+};
+
+pub use crate::event::Event;
+
+pub use crate::features::{FeatureFlag, FeatureSet};
+
+pub use crate::fixed::{NumericFormat, Q15, Q31};
+
+pub use crate::host::{HostServices, LogLevel};
+
+pub use crate::i18n::Translations;
+
+pub use crate::logging::{DebugProbe, LogValue, Logger};
+
+pub use crate::metrics::{Metrics, MetricsSnapshot};
+
+pub use crate::config::{ConfigChange, ConfigDelta, ConfigHistory, ConfigTracker, UIConfig};
+
+pub use crate::port_key::PortKey;
+
+pub use crate::ports::{ChangedOutputs, Deadband, OnChange, StaticPorts};
+
+pub use crate::preview::Preview;
+
+pub use crate::template::{GraphInfo, NodeInfo, WorkspaceTemplate};
+
 pub use crate::ui::{
-    behavior::{ConnectionBehavior, ExtendableInputs, PluginBehavior},
-    schema::{ConfigField, FieldType, FileMode, UISchema},
+    behavior::{ConnectionBehavior, ExtendableInputs, ExtendableOutputs, PluginBehavior},
+    schema::{ChoiceOption, ConfigField, FieldCondition, FieldType, FileMode, UISchema},
+    validation::{ValidationError, ValidationRule},
 };
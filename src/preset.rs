@@ -0,0 +1,476 @@
+//! Named configuration presets and [`PresetBundle`]s, which carry presets
+//! for many plugins at once so a lab can share a whole rig setup.
+//!
+//! Plugins ship factory presets through `Plugin::presets`; users save their
+//! own with [`Preset::from_config`]. Both are applied with [`apply_preset`].
+
+use crate::config::ConfigDelta;
+use crate::ui::{UISchema, ValidationError};
+use crate::{Plugin, PluginError, PluginUid};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Version of the bundle file layout, not of any plugin schema.
+pub const PRESET_BUNDLE_FORMAT: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    pub values: Map<String, Value>,
+}
+
+impl Preset {
+    pub fn new(name: impl Into<String>, values: Map<String, Value>) -> Self {
+        Self {
+            name: name.into(),
+            values,
+        }
+    }
+
+    /// Keeps the keys of `config` that are value fields of `schema`.
+    pub fn from_config(name: impl Into<String>, config: &Value, schema: &UISchema) -> Self {
+        let values = schema
+            .value_fields()
+            .into_iter()
+            .filter_map(|field| {
+                let value = config.get(&field.key)?;
+                Some((field.key.clone(), value.clone()))
+            })
+            .collect();
+        Self::new(name, values)
+    }
+
+    /// `config` with the preset's values written over it. Keys the preset
+    /// does not set keep their current value.
+    pub fn apply(&self, config: &Value) -> Value {
+        let mut merged = config.as_object().cloned().unwrap_or_default();
+        merged.extend(self.values.clone());
+        Value::Object(merged)
+    }
+
+    /// Checks the preset on top of the schema defaults, since presets often
+    /// set only some fields.
+    pub fn validate(&self, schema: &UISchema) -> Vec<ValidationError> {
+        schema.validate(&self.apply(&Value::Object(schema.defaults())))
+    }
+
+    pub fn to_json(&self) -> Result<String, PluginError> {
+        serde_json::to_string_pretty(self).map_err(|e| PluginError::Fatal(e.to_string()))
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, PluginError> {
+        serde_json::from_str(text).map_err(|e| PluginError::Config(format!("invalid preset: {e}")))
+    }
+}
+
+/// Serializes a list of presets, e.g. a user's preset file for one plugin.
+pub fn presets_to_json(presets: &[Preset]) -> Result<String, PluginError> {
+    serde_json::to_string_pretty(presets).map_err(|e| PluginError::Fatal(e.to_string()))
+}
+
+pub fn presets_from_json(text: &str) -> Result<Vec<Preset>, PluginError> {
+    serde_json::from_str(text).map_err(|e| PluginError::Config(format!("invalid presets: {e}")))
+}
+
+/// Validates `preset` against the plugin's schema, applies it over `config`
+/// and delivers the change. Returns the new configuration; on error the
+/// plugin is left untouched.
+pub fn apply_preset(
+    plugin: &mut dyn Plugin,
+    config: &Value,
+    preset: &Preset,
+) -> Result<Value, PluginError> {
+    let updated = preset.apply(config);
+    if let Some(schema) = plugin.ui_schema() {
+        let errors = schema.validate(&updated);
+        if !errors.is_empty() {
+            let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+            return Err(PluginError::Config(format!(
+                "preset {}: {}",
+                preset.name,
+                errors.join("; ")
+            )));
+        }
+    }
+    plugin.on_config_changed(&ConfigDelta::between(config, &updated))?;
+    Ok(updated)
+}
+
+/// The presets of one plugin in a bundle, with the schema they were saved
+/// under.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginPresets {
+    #[serde(default)]
+    pub schema_version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugin_version: Option<String>,
+    pub presets: Vec<Preset>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresetBundle {
+    pub format: u32,
+    pub name: String,
+    pub plugins: BTreeMap<PluginUid, PluginPresets>,
+}
+
+impl PresetBundle {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            format: PRESET_BUNDLE_FORMAT,
+            name: name.into(),
+            plugins: BTreeMap::new(),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, PluginError> {
+        serde_json::to_string_pretty(self).map_err(|e| PluginError::Fatal(e.to_string()))
+    }
+
+    /// Rejects bundles written in a newer format.
+    pub fn from_json(text: &str) -> Result<Self, PluginError> {
+        let bundle: Self = serde_json::from_str(text)
+            .map_err(|e| PluginError::Config(format!("invalid preset bundle: {e}")))?;
+        if bundle.format > PRESET_BUNDLE_FORMAT {
+            return Err(PluginError::Config(format!(
+                "preset bundle format {} is newer than supported {PRESET_BUNDLE_FORMAT}",
+                bundle.format
+            )));
+        }
+        Ok(bundle)
+    }
+}
+
+/// What happened to one plugin's presets during export or import.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginOutcome {
+    pub plugin: String,
+    pub presets: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BundleReport {
+    pub outcomes: Vec<PluginOutcome>,
+}
+
+impl BundleReport {
+    fn succeeded(&mut self, plugin: &str, presets: usize) {
+        self.outcomes.push(PluginOutcome {
+            plugin: plugin.to_string(),
+            presets,
+            error: None,
+        });
+    }
+
+    fn failed(&mut self, plugin: &str, error: impl Into<String>) {
+        self.outcomes.push(PluginOutcome {
+            plugin: plugin.to_string(),
+            presets: 0,
+            error: Some(error.into()),
+        });
+    }
+
+    pub fn all_ok(&self) -> bool {
+        self.outcomes.iter().all(|outcome| outcome.error.is_none())
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &PluginOutcome> {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.error.is_some())
+    }
+}
+
+fn schema_version(plugin: &dyn Plugin) -> u32 {
+    plugin.ui_schema().map_or(0, |schema| schema.schema_version)
+}
+
+/// Bundles the presets of each plugin under its uid and current schema
+/// version. Plugins without a uid cannot be matched on import and are
+/// reported as failures.
+pub fn export_bundle<'a>(
+    name: impl Into<String>,
+    entries: impl IntoIterator<Item = (&'a dyn Plugin, Vec<Preset>)>,
+) -> (PresetBundle, BundleReport) {
+    let mut bundle = PresetBundle::new(name);
+    let mut report = BundleReport::default();
+    for (plugin, presets) in entries {
+        let meta = plugin.meta();
+        let Some(uid) = meta.uid.clone() else {
+            report.failed(&meta.name, "plugin has no uid");
+            continue;
+        };
+        report.succeeded(uid.as_str(), presets.len());
+        bundle.plugins.insert(
+            uid,
+            PluginPresets {
+                schema_version: schema_version(plugin),
+                plugin_version: meta.version.clone(),
+                presets,
+            },
+        );
+    }
+    (bundle, report)
+}
+
+/// Presets of `bundle` for the installed `plugins`, migrated to their
+/// current schema versions, keyed by uid. Presets for plugins that are
+/// missing, newer than installed or fail to migrate are left out and
+/// reported.
+pub fn import_bundle(
+    bundle: &PresetBundle,
+    plugins: &[&dyn Plugin],
+) -> (BTreeMap<PluginUid, Vec<Preset>>, BundleReport) {
+    let mut imported = BTreeMap::new();
+    let mut report = BundleReport::default();
+    for (uid, entry) in &bundle.plugins {
+        let Some(plugin) = plugins
+            .iter()
+            .find(|plugin| plugin.meta().uid.as_ref() == Some(uid))
+        else {
+            report.failed(uid.as_str(), "plugin is not installed");
+            continue;
+        };
+        match migrate_presets(*plugin, entry) {
+            Ok(presets) => {
+                report.succeeded(uid.as_str(), presets.len());
+                imported.insert(uid.clone(), presets);
+            }
+            Err(err) => report.failed(uid.as_str(), err.to_string()),
+        }
+    }
+    (imported, report)
+}
+
+fn migrate_presets(plugin: &dyn Plugin, entry: &PluginPresets) -> Result<Vec<Preset>, PluginError> {
+    let current = schema_version(plugin);
+    if entry.schema_version > current {
+        return Err(PluginError::Config(format!(
+            "presets use schema version {}, installed plugin has {current}",
+            entry.schema_version
+        )));
+    }
+    if entry.schema_version == current {
+        return Ok(entry.presets.clone());
+    }
+    entry
+        .presets
+        .iter()
+        .map(|preset| {
+            let migrated = plugin
+                .migrate_config(entry.schema_version, Value::Object(preset.values.clone()))?;
+            match migrated {
+                Value::Object(values) => Ok(Preset::new(preset.name.clone(), values)),
+                _ => Err(PluginError::Config(format!(
+                    "migrating preset {} did not return an object",
+                    preset.name
+                ))),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::ConfigField;
+    use crate::{PluginContext, PluginId, PluginMeta, Port};
+    use serde_json::json;
+
+    // Schema version 2 renamed `gain` to `gain_db`.
+    struct Amp {
+        meta: PluginMeta,
+    }
+
+    impl Plugin for Amp {
+        fn id(&self) -> PluginId {
+            PluginId(1)
+        }
+        fn meta(&self) -> &PluginMeta {
+            &self.meta
+        }
+        fn inputs(&self) -> &[Port] {
+            &[]
+        }
+        fn outputs(&self) -> &[Port] {
+            &[]
+        }
+        fn process(&mut self, _ctx: &mut PluginContext) -> Result<(), PluginError> {
+            Ok(())
+        }
+        fn ui_schema(&self) -> Option<UISchema> {
+            Some(UISchema::new().schema_version(2))
+        }
+        fn migrate_config(&self, from_version: u32, old: Value) -> Result<Value, PluginError> {
+            let Value::Object(mut values) = old else {
+                return Ok(old);
+            };
+            if from_version < 2 {
+                if let Some(gain) = values.remove("gain") {
+                    values.insert("gain_db".to_string(), gain);
+                }
+            }
+            Ok(Value::Object(values))
+        }
+    }
+
+    fn values(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn bundles_round_trip_and_migrate() {
+        let amp = Amp {
+            meta: PluginMeta::new("Amp")
+                .uid("com.example.amp")
+                .version("2.0.0"),
+        };
+        let anonymous = Amp {
+            meta: PluginMeta::new("Scratch"),
+        };
+        let (mut bundle, report) = export_bundle(
+            "EMG rig",
+            [
+                (
+                    &amp as &dyn Plugin,
+                    vec![Preset::new("loud", values(json!({ "gain_db": 12 })))],
+                ),
+                (&anonymous as &dyn Plugin, Vec::new()),
+            ],
+        );
+        assert_eq!(report.failures().count(), 1);
+        assert_eq!(
+            bundle.plugins[&PluginUid::new("com.example.amp")].schema_version,
+            2
+        );
+
+        let old = PluginPresets {
+            schema_version: 1,
+            plugin_version: None,
+            presets: vec![Preset::new("quiet", values(json!({ "gain": -6 })))],
+        };
+        bundle
+            .plugins
+            .insert(PluginUid::new("com.example.scope"), old.clone());
+        bundle
+            .plugins
+            .insert(PluginUid::new("com.example.amp"), old);
+        let bundle = PresetBundle::from_json(&bundle.to_json().unwrap()).unwrap();
+
+        let (imported, report) = import_bundle(&bundle, &[&amp]);
+        assert_eq!(
+            imported[&PluginUid::new("com.example.amp")][0].values,
+            values(json!({ "gain_db": -6 }))
+        );
+        let failures: Vec<_> = report.failures().map(|o| o.plugin.as_str()).collect();
+        assert_eq!(failures, ["com.example.scope"]);
+    }
+
+    // Factory presets over a schema with a bounded gain.
+    struct Gain {
+        meta: PluginMeta,
+        gain: f64,
+    }
+
+    impl Plugin for Gain {
+        fn id(&self) -> PluginId {
+            PluginId(2)
+        }
+        fn meta(&self) -> &PluginMeta {
+            &self.meta
+        }
+        fn inputs(&self) -> &[Port] {
+            &[]
+        }
+        fn outputs(&self) -> &[Port] {
+            &[]
+        }
+        fn process(&mut self, _ctx: &mut PluginContext) -> Result<(), PluginError> {
+            Ok(())
+        }
+        fn ui_schema(&self) -> Option<UISchema> {
+            Some(
+                UISchema::new()
+                    .field(
+                        ConfigField::float("gain", "Gain")
+                            .min_f(0.0)
+                            .max_f(10.0)
+                            .default_value(json!(1.0)),
+                    )
+                    .field(ConfigField::text("label", "Label")),
+            )
+        }
+        fn presets(&self) -> Vec<Preset> {
+            vec![
+                Preset::new("unity", values(json!({ "gain": 1.0 }))),
+                Preset::new("broken", values(json!({ "gain": 20.0 }))),
+            ]
+        }
+        fn on_config_changed(&mut self, changed: &ConfigDelta) -> Result<(), PluginError> {
+            if let Some(gain) = changed.get("gain").and_then(|c| c.new.as_ref()) {
+                self.gain = gain.as_f64().unwrap_or(1.0);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn presets_validate_and_apply() {
+        let mut gain = Gain {
+            meta: PluginMeta::new("Gain"),
+            gain: 1.0,
+        };
+        let schema = gain.ui_schema().unwrap();
+        let factory = gain.presets();
+        assert!(factory[0].validate(&schema).is_empty());
+        assert_eq!(factory[1].validate(&schema)[0].key, "gain");
+
+        let config = json!({ "gain": 2.0, "label": "left", "stale": true });
+        let saved = Preset::from_config("mine", &config, &schema);
+        assert_eq!(
+            saved.values,
+            values(json!({ "gain": 2.0, "label": "left" }))
+        );
+        let loaded = presets_from_json(&presets_to_json(&[saved]).unwrap()).unwrap();
+        assert_eq!(
+            Preset::from_json(&loaded[0].to_json().unwrap()).unwrap(),
+            loaded[0]
+        );
+
+        let current = json!({ "gain": 1.0, "label": "right" });
+        let applied = apply_preset(&mut gain, &current, &loaded[0]).unwrap();
+        assert_eq!(applied, json!({ "gain": 2.0, "label": "left" }));
+        assert_eq!(gain.gain, 2.0);
+        assert!(apply_preset(&mut gain, &applied, &factory[1]).is_err());
+        assert_eq!(gain.gain, 2.0);
+    }
+
+    #[test]
+    fn rejects_newer_formats_and_schemas() {
+        let mut bundle = PresetBundle::new("future");
+        bundle.format = PRESET_BUNDLE_FORMAT + 1;
+        assert!(PresetBundle::from_json(&bundle.to_json().unwrap()).is_err());
+
+        let amp = Amp {
+            meta: PluginMeta::new("Amp").uid("com.example.amp"),
+        };
+        let mut bundle = PresetBundle::new("rig");
+        bundle.plugins.insert(
+            PluginUid::new("com.example.amp"),
+            PluginPresets {
+                schema_version: 3,
+                plugin_version: None,
+                presets: Vec::new(),
+            },
+        );
+        let (imported, report) = import_bundle(&bundle, &[&amp]);
+        assert!(imported.is_empty());
+        assert!(!report.all_ok());
+    }
+}
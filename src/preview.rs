@@ -0,0 +1,182 @@
+use crate::{Plugin, PluginContext, PluginError};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Samples kept per trace; longer traces are truncated.
+pub const PREVIEW_MAX_SAMPLES: usize = 512;
+/// Traces kept per preview.
+pub const PREVIEW_MAX_TRACES: usize = 16;
+/// Time `Plugin::preview` may take before [`checked_preview`] discards it.
+pub const PREVIEW_TIME_BUDGET: Duration = Duration::from_millis(20);
+
+/// Short synthetic output trace a host can draw in its plugin palette,
+/// e.g. one period of a generated sine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Preview {
+    /// Time between consecutive samples.
+    pub period_seconds: f64,
+    pub traces: Vec<PreviewTrace>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PreviewTrace {
+    /// Id of the output port the samples belong to.
+    pub output: String,
+    pub samples: Vec<f64>,
+}
+
+impl Preview {
+    pub fn new(period_seconds: f64) -> Self {
+        Self {
+            period_seconds,
+            traces: Vec::new(),
+        }
+    }
+
+    pub fn trace(
+        mut self,
+        output: impl Into<String>,
+        samples: impl IntoIterator<Item = f64>,
+    ) -> Self {
+        self.traces.push(PreviewTrace {
+            output: output.into(),
+            samples: samples.into_iter().take(PREVIEW_MAX_SAMPLES).collect(),
+        });
+        self
+    }
+
+    /// Runs `plugin` for `ticks` ticks and records `outputs` after each one,
+    /// for plugins whose preview is easiest to produce by simulation. Run it
+    /// on a fresh copy, not the live instance.
+    pub fn capture<P: Plugin>(
+        plugin: &mut P,
+        outputs: &[&str],
+        ticks: usize,
+        period_seconds: f64,
+        get_output: fn(&P, &str) -> f64,
+    ) -> Result<Self, PluginError> {
+        let ticks = ticks.min(PREVIEW_MAX_SAMPLES);
+        let mut traces: Vec<PreviewTrace> = outputs
+            .iter()
+            .take(PREVIEW_MAX_TRACES)
+            .map(|output| PreviewTrace {
+                output: output.to_string(),
+                samples: Vec::with_capacity(ticks),
+            })
+            .collect();
+        let mut ctx = PluginContext {
+            period_seconds,
+            ..PluginContext::default()
+        };
+        for tick in 0..ticks {
+            ctx.tick = tick as u64;
+            plugin.process(&mut ctx)?;
+            for trace in &mut traces {
+                trace.samples.push(get_output(plugin, &trace.output));
+            }
+        }
+        Ok(Self {
+            period_seconds,
+            traces,
+        })
+    }
+
+    /// Drops what exceeds [`PREVIEW_MAX_TRACES`] and [`PREVIEW_MAX_SAMPLES`],
+    /// traces of unknown outputs and non-finite samples (as `0.0`).
+    pub fn clamp(&mut self, plugin: &dyn Plugin) {
+        self.traces.retain(|trace| {
+            plugin
+                .outputs()
+                .iter()
+                .any(|port| port.id.0 == trace.output)
+        });
+        self.traces.truncate(PREVIEW_MAX_TRACES);
+        for trace in &mut self.traces {
+            trace.samples.truncate(PREVIEW_MAX_SAMPLES);
+            for sample in &mut trace.samples {
+                if !sample.is_finite() {
+                    *sample = 0.0;
+                }
+            }
+        }
+    }
+}
+
+/// Calls `plugin.preview()` the way a palette should: results that took
+/// longer than [`PREVIEW_TIME_BUDGET`] or have no sensible period are
+/// discarded, the rest are clamped with [`Preview::clamp`].
+pub fn checked_preview(plugin: &dyn Plugin) -> Option<Preview> {
+    let started = Instant::now();
+    let mut preview = plugin.preview()?;
+    if started.elapsed() > PREVIEW_TIME_BUDGET
+        || !(preview.period_seconds.is_finite() && preview.period_seconds > 0.0)
+    {
+        return None;
+    }
+    preview.clamp(plugin);
+    Some(preview)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PluginId, PluginMeta, Port};
+
+    struct Ramp {
+        meta: PluginMeta,
+        outputs: Vec<Port>,
+        value: f64,
+    }
+
+    impl Plugin for Ramp {
+        fn id(&self) -> PluginId {
+            PluginId(1)
+        }
+        fn meta(&self) -> &PluginMeta {
+            &self.meta
+        }
+        fn inputs(&self) -> &[Port] {
+            &[]
+        }
+        fn outputs(&self) -> &[Port] {
+            &self.outputs
+        }
+        fn process(&mut self, _ctx: &mut PluginContext) -> Result<(), PluginError> {
+            self.value += 1.0;
+            Ok(())
+        }
+        fn preview(&self) -> Option<Preview> {
+            Some(
+                Preview::new(0.001)
+                    .trace("out", (0..2000).map(f64::from))
+                    .trace("gone", [1.0])
+                    .trace("out", [f64::NAN]),
+            )
+        }
+    }
+
+    fn ramp() -> Ramp {
+        Ramp {
+            meta: PluginMeta::new("ramp"),
+            outputs: vec![Port::new("out")],
+            value: 0.0,
+        }
+    }
+
+    #[test]
+    fn checked_preview_applies_bounds() {
+        let preview = checked_preview(&ramp()).unwrap();
+        assert_eq!(preview.traces.len(), 2);
+        assert_eq!(preview.traces[0].samples.len(), PREVIEW_MAX_SAMPLES);
+        assert_eq!(preview.traces[1].samples, [0.0]);
+    }
+
+    #[test]
+    fn capture_records_outputs() {
+        let mut plugin = ramp();
+        let preview =
+            Preview::capture(&mut plugin, &["out"], 3, 0.01, |p: &Ramp, _| p.value).unwrap();
+        assert_eq!(preview.traces[0].samples, [1.0, 2.0, 3.0]);
+        assert_eq!(preview.period_seconds, 0.01);
+    }
+}
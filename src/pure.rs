@@ -0,0 +1,203 @@
+use crate::ports::port_index;
+use crate::ui::{PluginBehavior, UISchema};
+use crate::{Plugin, PluginContext, PluginError, PluginId, PluginMeta, Port};
+
+/// Stateless unit mapping one frame of inputs to one frame of outputs.
+///
+/// `map` only reads `&self`, so the host may call it for different frames
+/// from several threads at once, e.g. with [`map_frames_parallel`] when
+/// reprocessing recorded data offline. Wrap it in [`PureAdapter`] to run it
+/// as a regular [`Plugin`].
+pub trait PureProcessor: Send + Sync {
+    fn meta(&self) -> &PluginMeta;
+    fn inputs(&self) -> &[Port];
+    fn outputs(&self) -> &[Port];
+
+    /// `inputs` and `outputs` have exactly as many entries as the ports.
+    fn map(&self, inputs: &[f64], outputs: &mut [f64]);
+
+    fn ui_schema(&self) -> Option<UISchema> {
+        None
+    }
+}
+
+/// Runs a [`PureProcessor`] as a [`Plugin`], holding the current port values.
+///
+/// Its behavior declares the plugin stateless and free of side effects.
+pub struct PureAdapter<T> {
+    id: PluginId,
+    processor: T,
+    inputs: Vec<f64>,
+    outputs: Vec<f64>,
+}
+
+impl<T: PureProcessor> PureAdapter<T> {
+    pub fn new(id: PluginId, processor: T) -> Self {
+        let inputs = vec![0.0; processor.inputs().len()];
+        let outputs = vec![0.0; processor.outputs().len()];
+        Self {
+            id,
+            processor,
+            inputs,
+            outputs,
+        }
+    }
+
+    pub fn processor(&self) -> &T {
+        &self.processor
+    }
+
+    pub fn set_input(&mut self, index: usize, value: f64) {
+        self.inputs[index] = value;
+    }
+
+    pub fn output(&self, index: usize) -> f64 {
+        self.outputs[index]
+    }
+
+    /// Unknown names are ignored.
+    pub fn set_input_by_name(&mut self, name: &str, value: f64) {
+        if let Some(index) = port_index(self.processor.inputs(), name) {
+            self.inputs[index] = value;
+        }
+    }
+
+    /// Unknown names read as `0.0`.
+    pub fn output_by_name(&self, name: &str) -> f64 {
+        port_index(self.processor.outputs(), name).map_or(0.0, |index| self.outputs[index])
+    }
+}
+
+impl<T: PureProcessor> Plugin for PureAdapter<T> {
+    fn id(&self) -> PluginId {
+        self.id
+    }
+
+    fn meta(&self) -> &PluginMeta {
+        self.processor.meta()
+    }
+
+    fn inputs(&self) -> &[Port] {
+        self.processor.inputs()
+    }
+
+    fn outputs(&self) -> &[Port] {
+        self.processor.outputs()
+    }
+
+    fn process(&mut self, _ctx: &mut PluginContext) -> Result<(), PluginError> {
+        self.processor.map(&self.inputs, &mut self.outputs);
+        Ok(())
+    }
+
+    fn ui_schema(&self) -> Option<UISchema> {
+        self.processor.ui_schema()
+    }
+
+    fn behavior(&self) -> PluginBehavior {
+        PluginBehavior {
+            stateless: true,
+            side_effects: Some(Vec::new()),
+            ..PluginBehavior::default()
+        }
+    }
+}
+
+/// Maps interleaved frames: `inputs` holds `n` frames of
+/// `processor.inputs().len()` values, `outputs` receives `n` frames of
+/// `processor.outputs().len()` values.
+pub fn map_frames<T: PureProcessor + ?Sized>(processor: &T, inputs: &[f64], outputs: &mut [f64]) {
+    let in_width = processor.inputs().len();
+    let out_width = processor.outputs().len();
+    if out_width == 0 {
+        return;
+    }
+    for (frame, out) in outputs.chunks_exact_mut(out_width).enumerate() {
+        let start = frame * in_width;
+        processor.map(&inputs[start..start + in_width], out);
+    }
+}
+
+/// Like [`map_frames`], split into contiguous blocks over `threads` scoped
+/// worker threads.
+pub fn map_frames_parallel<T: PureProcessor + ?Sized>(
+    processor: &T,
+    inputs: &[f64],
+    outputs: &mut [f64],
+    threads: usize,
+) {
+    let in_width = processor.inputs().len();
+    let out_width = processor.outputs().len();
+    if out_width == 0 {
+        return;
+    }
+    let frames = outputs.len() / out_width;
+    let per_thread = frames.div_ceil(threads.max(1)).max(1);
+    std::thread::scope(|scope| {
+        for (index, out) in outputs.chunks_mut(per_thread * out_width).enumerate() {
+            let start = (index * per_thread * in_width).min(inputs.len());
+            let input = &inputs[start..];
+            scope.spawn(move || map_frames(processor, input, out));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Mix {
+        meta: PluginMeta,
+        inputs: Vec<Port>,
+        outputs: Vec<Port>,
+    }
+
+    impl Mix {
+        fn new() -> Self {
+            Self {
+                meta: PluginMeta::new("mix"),
+                inputs: vec![Port::new("a"), Port::new("b")],
+                outputs: vec![Port::new("sum"), Port::new("diff")],
+            }
+        }
+    }
+
+    impl PureProcessor for Mix {
+        fn meta(&self) -> &PluginMeta {
+            &self.meta
+        }
+        fn inputs(&self) -> &[Port] {
+            &self.inputs
+        }
+        fn outputs(&self) -> &[Port] {
+            &self.outputs
+        }
+        fn map(&self, inputs: &[f64], outputs: &mut [f64]) {
+            outputs[0] = inputs[0] + inputs[1];
+            outputs[1] = inputs[0] - inputs[1];
+        }
+    }
+
+    #[test]
+    fn adapter_runs_as_plugin() {
+        let mut plugin = PureAdapter::new(PluginId(1), Mix::new());
+        plugin.set_input_by_name("a", 3.0);
+        plugin.set_input(1, 1.0);
+        plugin.process(&mut PluginContext::default()).unwrap();
+        assert_eq!(plugin.output_by_name("sum"), 4.0);
+        assert_eq!(plugin.output(1), 2.0);
+        assert!(plugin.behavior().stateless);
+        assert!(plugin.behavior().is_pure());
+    }
+
+    #[test]
+    fn parallel_matches_sequential() {
+        let inputs: Vec<f64> = (0..202).map(f64::from).collect();
+        let mut sequential = vec![0.0; 202];
+        let mut parallel = vec![0.0; 202];
+        map_frames(&Mix::new(), &inputs, &mut sequential);
+        map_frames_parallel(&Mix::new(), &inputs, &mut parallel, 4);
+        assert_eq!(sequential, parallel);
+        assert_eq!(&sequential[..4], &[1.0, -1.0, 5.0, -1.0]);
+    }
+}
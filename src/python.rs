@@ -0,0 +1,675 @@
+//! Python plugins through pyo3.
+//!
+//! [`register`] adds the `Plugin`, `PluginContext` and `UISchema` classes to
+//! a Python module; [`PyPluginAdapter`] runs an instance of a Python class as
+//! a regular [`Plugin`]. Dicts and lists cross the boundary as JSON, so every
+//! value must be JSON-serializable.
+//!
+//! ```python
+//! import rtsyn_plugin
+//!
+//! class Gain(rtsyn_plugin.Plugin):
+//!     gain = 1.0
+//!
+//!     def meta(self):
+//!         return {"name": "gain"}
+//!
+//!     def inputs(self):
+//!         return ["in"]
+//!
+//!     def outputs(self):
+//!         return ["out"]
+//!
+//!     def on_config_changed(self, changes):
+//!         self.gain = changes.get("gain", self.gain)
+//!
+//!     def process(self, ctx, inputs):
+//!         return [inputs[0] * self.gain]
+//! ```
+//!
+//! Only `meta` and `process` are required; every other method is optional.
+
+use crate::config::ConfigDelta;
+use crate::event::Event;
+use crate::features::FeatureFlag;
+use crate::host::LogLevel;
+use crate::ports::port_index;
+use crate::preset::Preset;
+use crate::preview::Preview;
+use crate::template::WorkspaceTemplate;
+use crate::ui::{ChoiceOption, PluginBehavior, UISchema};
+use crate::{Plugin, PluginContext, PluginError, PluginId, PluginMeta, Port};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyModule, PyTuple};
+use serde_json::Value;
+use std::ffi::CString;
+
+/// Base class for Python plugins. Subclassing it is optional; it documents
+/// the methods the adapter looks for and provides their defaults.
+#[pyclass(subclass, name = "Plugin", module = "rtsyn_plugin")]
+#[derive(Default)]
+pub struct PyPlugin;
+
+#[pymethods]
+impl PyPlugin {
+    #[new]
+    fn new() -> Self {
+        Self
+    }
+
+    fn inputs(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn outputs(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Snapshot of [`PluginContext`] passed to the Python `process`.
+///
+/// Messages passed to `log` are forwarded to `PluginContext::logger` once
+/// `process` returns.
+#[pyclass(name = "PluginContext", module = "rtsyn_plugin")]
+pub struct PyPluginContext {
+    #[pyo3(get)]
+    tick: u64,
+    #[pyo3(get)]
+    period_seconds: f64,
+    #[pyo3(get)]
+    monotonic_ns: u64,
+    #[pyo3(get)]
+    missed_ticks: u64,
+    #[pyo3(get)]
+    jitter_ns: i64,
+    /// Seconds since the Unix epoch.
+    #[pyo3(get)]
+    wall_time: f64,
+    #[pyo3(get)]
+    sample_rate: f64,
+    #[pyo3(get)]
+    block_size: usize,
+    #[pyo3(get)]
+    connected_inputs: u64,
+    #[pyo3(get)]
+    connected_outputs: u64,
+    logs: Vec<(LogLevel, String)>,
+}
+
+impl PyPluginContext {
+    fn new(ctx: &PluginContext) -> Self {
+        Self {
+            tick: ctx.tick,
+            period_seconds: ctx.period_seconds,
+            monotonic_ns: ctx.monotonic_ns,
+            missed_ticks: ctx.missed_ticks,
+            jitter_ns: ctx.jitter_ns,
+            wall_time: ctx.wall_time_ns() as f64 / 1e9,
+            sample_rate: ctx.sample_rate,
+            block_size: ctx.block_size,
+            connected_inputs: ctx.connected_inputs,
+            connected_outputs: ctx.connected_outputs,
+            logs: Vec::new(),
+        }
+    }
+
+    fn context(&self) -> PluginContext {
+        PluginContext {
+            tick: self.tick,
+            period_seconds: self.period_seconds,
+            sample_rate: self.sample_rate,
+            block_size: self.block_size,
+            connected_inputs: self.connected_inputs,
+            connected_outputs: self.connected_outputs,
+            ..PluginContext::default()
+        }
+    }
+}
+
+#[pymethods]
+impl PyPluginContext {
+    fn is_input_connected(&self, index: usize) -> bool {
+        self.context().is_input_connected(index)
+    }
+
+    fn is_output_connected(&self, index: usize) -> bool {
+        self.context().is_output_connected(index)
+    }
+
+    /// `level` is one of `error`, `warn`, `info`, `debug` or `trace`.
+    fn log(&mut self, level: &str, message: String) -> PyResult<()> {
+        let level = serde_json::from_value(Value::String(level.to_string()))
+            .map_err(|_| PyValueError::new_err(format!("unknown log level {level:?}")))?;
+        self.logs.push((level, message));
+        Ok(())
+    }
+}
+
+/// Python view of a [`UISchema`].
+#[pyclass(name = "UISchema", module = "rtsyn_plugin")]
+#[derive(Clone)]
+pub struct PyUISchema(pub UISchema);
+
+#[pymethods]
+impl PyUISchema {
+    #[new]
+    fn new() -> Self {
+        Self(UISchema::new())
+    }
+
+    #[staticmethod]
+    fn from_dict(schema: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let schema = serde_json::from_value(to_json(schema)?)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(Self(schema))
+    }
+
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        from_json(py, &serde_json::to_value(&self.0).unwrap_or_default())
+    }
+
+    fn defaults<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        from_json(py, &Value::Object(self.0.defaults()))
+    }
+
+    /// One message per validation error, empty when `config` is valid.
+    fn validate(&self, config: &Bound<'_, PyAny>) -> PyResult<Vec<String>> {
+        let errors = self.0.validate(&to_json(config)?);
+        Ok(errors.iter().map(ToString::to_string).collect())
+    }
+}
+
+/// Adds the Python-facing classes to `module`.
+pub fn register(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyPlugin>()?;
+    module.add_class::<PyPluginContext>()?;
+    module.add_class::<PyUISchema>()?;
+    Ok(())
+}
+
+fn to_json(value: &Bound<'_, PyAny>) -> PyResult<Value> {
+    let text: String = value
+        .py()
+        .import("json")?
+        .call_method1("dumps", (value,))?
+        .extract()?;
+    serde_json::from_str(&text).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+fn from_json<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    py.import("json")?
+        .call_method1("loads", (value.to_string(),))
+}
+
+fn py_error(e: PyErr) -> PluginError {
+    PluginError::Recoverable(format!("python: {e}"))
+}
+
+fn config_error(e: impl std::fmt::Display) -> PluginError {
+    PluginError::Config(format!("python: {e}"))
+}
+
+/// Runs a Python object as a [`Plugin`].
+///
+/// Metadata and ports are read once at construction. Python exceptions are
+/// reported as [`PluginError::Recoverable`]; malformed return values from
+/// `meta`, the port lists or `behavior` as [`PluginError::Config`].
+pub struct PyPluginAdapter {
+    id: PluginId,
+    object: Py<PyAny>,
+    meta: PluginMeta,
+    inputs: Vec<Port>,
+    outputs: Vec<Port>,
+    input_values: Vec<f64>,
+    output_values: Vec<f64>,
+}
+
+impl PyPluginAdapter {
+    pub fn new(id: PluginId, object: Py<PyAny>) -> Result<Self, PluginError> {
+        Python::attach(|py| {
+            let bound = object.bind(py);
+            let meta = to_json(&bound.call_method0("meta").map_err(py_error)?).map_err(py_error)?;
+            let meta = meta_from_json(meta)?;
+            let inputs = ports(bound, "inputs")?;
+            let outputs = ports(bound, "outputs")?;
+            Ok(Self {
+                id,
+                input_values: vec![0.0; inputs.len()],
+                output_values: vec![0.0; outputs.len()],
+                object: object.clone_ref(py),
+                meta,
+                inputs,
+                outputs,
+            })
+        })
+    }
+
+    /// Runs `source` as module `module_name`, with the `rtsyn_plugin`
+    /// classes importable, and instantiates `class_name` without arguments.
+    pub fn from_source(
+        id: PluginId,
+        source: &str,
+        module_name: &str,
+        class_name: &str,
+    ) -> Result<Self, PluginError> {
+        Python::initialize();
+        let object = Python::attach(|py| -> PyResult<Py<PyAny>> {
+            let modules = py.import("sys")?.getattr("modules")?;
+            if !modules.contains("rtsyn_plugin")? {
+                let module = PyModule::new(py, "rtsyn_plugin")?;
+                register(&module)?;
+                modules.set_item("rtsyn_plugin", module)?;
+            }
+            let code = CString::new(source).map_err(|e| PyValueError::new_err(e.to_string()))?;
+            let file = CString::new(format!("{module_name}.py")).unwrap_or_default();
+            let name =
+                CString::new(module_name).map_err(|e| PyValueError::new_err(e.to_string()))?;
+            let module = PyModule::from_code(py, &code, &file, &name)?;
+            Ok(module.getattr(class_name)?.call0()?.unbind())
+        })
+        .map_err(config_error)?;
+        Self::new(id, object)
+    }
+
+    pub fn object(&self) -> &Py<PyAny> {
+        &self.object
+    }
+
+    pub fn set_input(&mut self, index: usize, value: f64) {
+        self.input_values[index] = value;
+    }
+
+    pub fn output(&self, index: usize) -> f64 {
+        self.output_values[index]
+    }
+
+    /// Unknown names are ignored.
+    pub fn set_input_by_name(&mut self, name: &str, value: f64) {
+        if let Some(index) = port_index(&self.inputs, name) {
+            self.input_values[index] = value;
+        }
+    }
+
+    /// Unknown names read as `0.0`.
+    pub fn output_by_name(&self, name: &str) -> f64 {
+        port_index(&self.outputs, name).map_or(0.0, |index| self.output_values[index])
+    }
+
+    /// Calls `name` if the object defines it; `Ok(None)` otherwise.
+    fn call_optional(
+        &self,
+        name: &str,
+        args: impl for<'py> FnOnce(Python<'py>) -> PyResult<Vec<Bound<'py, PyAny>>>,
+    ) -> Result<Option<Value>, PluginError> {
+        Python::attach(|py| {
+            let object = self.object.bind(py);
+            if !object.hasattr(name)? {
+                return Ok(None);
+            }
+            let args = PyTuple::new(py, args(py)?)?;
+            let result = object.call_method1(name, args)?;
+            if result.is_none() {
+                return Ok(None);
+            }
+            to_json(&result).map(Some)
+        })
+        .map_err(py_error)
+    }
+}
+
+/// Keys missing from the Python dict take the defaults of `PluginMeta::new`.
+fn meta_from_json(meta: Value) -> Result<PluginMeta, PluginError> {
+    let Value::Object(fields) = meta else {
+        return Err(config_error("meta() must return a dict"));
+    };
+    let mut merged = match serde_json::to_value(PluginMeta::new("")) {
+        Ok(Value::Object(defaults)) => defaults,
+        _ => serde_json::Map::new(),
+    };
+    merged.extend(fields);
+    serde_json::from_value(Value::Object(merged)).map_err(config_error)
+}
+
+fn ports(object: &Bound<'_, PyAny>, method: &str) -> Result<Vec<Port>, PluginError> {
+    if !object.hasattr(method).map_err(py_error)? {
+        return Ok(Vec::new());
+    }
+    let value = to_json(&object.call_method0(method).map_err(py_error)?).map_err(py_error)?;
+    let Value::Array(ports) = value else {
+        return Err(config_error(format!("{method}() must return a list")));
+    };
+    ports
+        .into_iter()
+        .map(|port| match port {
+            Value::String(id) => Ok(Port::new(id)),
+            other => serde_json::from_value(other).map_err(config_error),
+        })
+        .collect()
+}
+
+impl Plugin for PyPluginAdapter {
+    fn id(&self) -> PluginId {
+        self.id
+    }
+
+    fn meta(&self) -> &PluginMeta {
+        &self.meta
+    }
+
+    fn inputs(&self) -> &[Port] {
+        &self.inputs
+    }
+
+    fn outputs(&self) -> &[Port] {
+        &self.outputs
+    }
+
+    fn process(&mut self, ctx: &mut PluginContext) -> Result<(), PluginError> {
+        let (outputs, logs) = Python::attach(|py| -> PyResult<_> {
+            let py_ctx = Py::new(py, PyPluginContext::new(ctx))?;
+            let result = self.object.call_method1(
+                py,
+                "process",
+                (py_ctx.clone_ref(py), self.input_values.clone()),
+            )?;
+            let outputs: Option<Vec<f64>> = result.extract(py)?;
+            let logs = std::mem::take(&mut py_ctx.borrow_mut(py).logs);
+            Ok((outputs, logs))
+        })
+        .map_err(py_error)?;
+        for (level, message) in logs {
+            ctx.logger.log(level, &message, &[]);
+        }
+        if let Some(outputs) = outputs {
+            if outputs.len() != self.output_values.len() {
+                return Err(PluginError::Recoverable(format!(
+                    "python: process() returned {} outputs, expected {}",
+                    outputs.len(),
+                    self.output_values.len()
+                )));
+            }
+            self.output_values = outputs;
+        }
+        Ok(())
+    }
+
+    fn ui_schema(&self) -> Option<UISchema> {
+        let schema = Python::attach(|py| -> PyResult<Option<UISchema>> {
+            let object = self.object.bind(py);
+            if !object.hasattr("ui_schema")? {
+                return Ok(None);
+            }
+            let schema = object.call_method0("ui_schema")?;
+            if schema.is_none() {
+                return Ok(None);
+            }
+            if let Ok(schema) = schema.extract::<PyUISchema>() {
+                return Ok(Some(schema.0));
+            }
+            Ok(serde_json::from_value(to_json(&schema)?).ok())
+        });
+        schema.ok().flatten()
+    }
+
+    fn behavior(&self) -> PluginBehavior {
+        self.call_optional("behavior", |_| Ok(Vec::new()))
+            .ok()
+            .flatten()
+            .and_then(|behavior| serde_json::from_value(behavior).ok())
+            .unwrap_or_default()
+    }
+
+    /// Passes `{key: new_value}`, with `None` for removed keys.
+    fn on_config_changed(&mut self, changed: &ConfigDelta) -> Result<(), PluginError> {
+        let changes: serde_json::Map<String, Value> = changed
+            .changes
+            .iter()
+            .map(|change| {
+                (
+                    change.key.clone(),
+                    change.new.clone().unwrap_or(Value::Null),
+                )
+            })
+            .collect();
+        self.call_optional("on_config_changed", |py| {
+            Ok(vec![from_json(py, &Value::Object(changes))?])
+        })
+        .map_err(|e| match e {
+            PluginError::Recoverable(message) => PluginError::Config(message),
+            other => other,
+        })?;
+        Ok(())
+    }
+
+    fn save_state(&self) -> Result<Value, PluginError> {
+        Ok(self
+            .call_optional("save_state", |_| Ok(Vec::new()))?
+            .unwrap_or(Value::Null))
+    }
+
+    fn restore_state(&mut self, state: Value) -> Result<(), PluginError> {
+        self.call_optional("restore_state", |py| Ok(vec![from_json(py, &state)?]))?;
+        Ok(())
+    }
+
+    /// `migrate_config(from_version, old)` returns the migrated dict; `None`
+    /// keeps the configuration as it is.
+    fn migrate_config(&self, from_version: u32, old: Value) -> Result<Value, PluginError> {
+        let migrated = self.call_optional("migrate_config", |py| {
+            Ok(vec![
+                from_version.into_pyobject(py)?.into_any(),
+                from_json(py, &old)?,
+            ])
+        })?;
+        Ok(migrated.unwrap_or(old))
+    }
+
+    /// `options_for(key)` returns a list of `{"value": ..., "label": ...}`
+    /// or bare strings.
+    fn options_for(&self, key: &str) -> Vec<ChoiceOption> {
+        self.call_optional("options_for", |py| {
+            Ok(vec![key.into_pyobject(py)?.into_any()])
+        })
+        .ok()
+        .flatten()
+        .and_then(|options| serde_json::from_value(options).ok())
+        .unwrap_or_default()
+    }
+
+    fn action(&mut self, name: &str, args: &Value) -> Result<Option<Value>, PluginError> {
+        self.call_optional("action", |py| {
+            Ok(vec![
+                name.into_pyobject(py)?.into_any(),
+                from_json(py, args)?,
+            ])
+        })
+    }
+
+    /// Calls `on_action(action_id)` when the object defines it, otherwise
+    /// `action` like the default implementation.
+    fn on_action(&mut self, action_id: &str) -> Result<(), PluginError> {
+        if !Python::attach(|py| self.object.bind(py).hasattr("on_action")).unwrap_or(false) {
+            return match self.action(action_id, &Value::Object(Default::default()))? {
+                Some(_) => Ok(()),
+                None => Err(PluginError::Config(format!("unknown action {action_id}"))),
+            };
+        }
+        self.call_optional("on_action", |py| {
+            Ok(vec![action_id.into_pyobject(py)?.into_any()])
+        })
+        .map(drop)
+    }
+
+    /// Passes the event as a dict with `source`, `topic`, `payload` and
+    /// `timestamp`.
+    fn on_event(&mut self, event: &Event) -> Result<(), PluginError> {
+        let event = serde_json::to_value(event).unwrap_or_default();
+        self.call_optional("on_event", |py| Ok(vec![from_json(py, &event)?]))
+            .map(drop)
+    }
+
+    fn features(&self) -> Vec<FeatureFlag> {
+        self.call_optional("features", |_| Ok(Vec::new()))
+            .ok()
+            .flatten()
+            .and_then(|features| serde_json::from_value(features).ok())
+            .unwrap_or_default()
+    }
+
+    fn set_feature_enabled(&mut self, name: &str, enabled: bool) -> Result<(), PluginError> {
+        if !Python::attach(|py| self.object.bind(py).hasattr("set_feature_enabled"))
+            .unwrap_or(false)
+        {
+            return Err(PluginError::Config(format!("unknown feature {name}")));
+        }
+        self.call_optional("set_feature_enabled", |py| {
+            Ok(vec![
+                name.into_pyobject(py)?.into_any(),
+                enabled.into_pyobject(py)?.to_owned().into_any(),
+            ])
+        })
+        .map(drop)
+    }
+
+    fn templates(&self) -> Vec<WorkspaceTemplate> {
+        self.call_optional("templates", |_| Ok(Vec::new()))
+            .ok()
+            .flatten()
+            .and_then(|templates| serde_json::from_value(templates).ok())
+            .unwrap_or_default()
+    }
+
+    /// `presets()` returns a list of `{"name": ..., "values": {...}}`.
+    fn presets(&self) -> Vec<Preset> {
+        self.call_optional("presets", |_| Ok(Vec::new()))
+            .ok()
+            .flatten()
+            .and_then(|presets| serde_json::from_value(presets).ok())
+            .unwrap_or_default()
+    }
+
+    /// `preview()` returns a dict with `period_seconds` and `traces`, a list
+    /// of `{"output": ..., "samples": [...]}`.
+    fn preview(&self) -> Option<Preview> {
+        self.call_optional("preview", |_| Ok(Vec::new()))
+            .ok()
+            .flatten()
+            .and_then(|preview| serde_json::from_value(preview).ok())
+    }
+
+    /// `get_output_string(name)` returns a str, or None for numeric outputs.
+    fn get_output_string(&self, name: &str) -> Option<String> {
+        match self.call_optional("get_output_string", |py| {
+            Ok(vec![name.into_pyobject(py)?.into_any()])
+        }) {
+            Ok(Some(Value::String(text))) => Some(text),
+            _ => None,
+        }
+    }
+
+    fn latency_ticks(&self) -> u64 {
+        match self.call_optional("latency_ticks", |_| Ok(Vec::new())) {
+            Ok(Some(latency)) => latency.as_u64().unwrap_or(0),
+            _ => self.behavior().latency_ticks,
+        }
+    }
+
+    fn prepare(&mut self, sample_rate: f64, block_size: usize) -> Result<(), PluginError> {
+        self.call_optional("prepare", |py| {
+            Ok(vec![
+                sample_rate.into_pyobject(py)?.into_any(),
+                block_size.into_pyobject(py)?.into_any(),
+            ])
+        })
+        .map(drop)
+    }
+
+    fn on_overrun(&mut self, missed: u64) -> Result<(), PluginError> {
+        self.call_optional("on_overrun", |py| {
+            Ok(vec![missed.into_pyobject(py)?.into_any()])
+        })
+        .map(drop)
+    }
+
+    fn on_start(&mut self) -> Result<(), PluginError> {
+        self.call_optional("on_start", |_| Ok(Vec::new())).map(drop)
+    }
+
+    fn on_stop(&mut self) -> Result<(), PluginError> {
+        self.call_optional("on_stop", |_| Ok(Vec::new())).map(drop)
+    }
+
+    fn on_pause(&mut self) -> Result<(), PluginError> {
+        self.call_optional("on_pause", |_| Ok(Vec::new())).map(drop)
+    }
+
+    fn on_resume(&mut self) -> Result<(), PluginError> {
+        self.call_optional("on_resume", |_| Ok(Vec::new()))
+            .map(drop)
+    }
+
+    fn reset(&mut self) -> Result<(), PluginError> {
+        self.call_optional("reset", |_| Ok(Vec::new())).map(drop)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigTracker;
+    use serde_json::json;
+
+    const SOURCE: &str = r#"
+import rtsyn_plugin
+
+class Gain(rtsyn_plugin.Plugin):
+    gain = 1.0
+
+    def meta(self):
+        return {"name": "gain"}
+
+    def inputs(self):
+        return ["in"]
+
+    def outputs(self):
+        return ["out"]
+
+    def ui_schema(self):
+        return rtsyn_plugin.UISchema.from_dict({"fields": [
+            {"key": "gain", "label": "Gain", "type": {"kind": "float", "step": 0.1}}
+        ]})
+
+    def on_config_changed(self, changes):
+        self.gain = changes.get("gain", self.gain)
+
+    def process(self, ctx, inputs):
+        if inputs[0] > 1.0:
+            ctx.log("warn", "clipping")
+        return [inputs[0] * self.gain]
+
+    def action(self, name, args):
+        return {"name": name, "gain": self.gain}
+"#;
+
+    #[test]
+    fn runs_python_class_as_plugin() {
+        let mut plugin = PyPluginAdapter::from_source(PluginId(4), SOURCE, "gain", "Gain").unwrap();
+        assert_eq!(plugin.meta().name, "gain");
+        assert_eq!(plugin.inputs()[0].id.0, "in");
+        assert!(plugin.ui_schema().is_some());
+
+        let mut config = ConfigTracker::new();
+        plugin
+            .on_config_changed(&config.apply(&json!({ "gain": 2.0 })))
+            .unwrap();
+        plugin.set_input_by_name("in", 1.5);
+        plugin.process(&mut PluginContext::default()).unwrap();
+        assert_eq!(plugin.output_by_name("out"), 3.0);
+        assert_eq!(
+            plugin.action("info", &json!({})).unwrap(),
+            Some(json!({ "name": "info", "gain": 2.0 }))
+        );
+        assert_eq!(plugin.save_state().unwrap(), Value::Null);
+    }
+}
@@ -0,0 +1,1138 @@
+//! Out-of-process plugins.
+//!
+//! A [`RemotePluginServer`] runs the plugin in its own process and answers
+//! requests from a [`RemotePluginClient`] in the host, so a crash only takes
+//! down the plugin process. Messages are newline-delimited JSON over any byte
+//! stream passed to [`RemotePluginServer::serve`] and
+//! [`RemotePluginClient::new`]. For Unix sockets either side may listen:
+//! [`RemotePluginClient::accept_unix`] pairs with
+//! [`RemotePluginServer::connect_and_serve_unix`], and
+//! [`RemotePluginClient::connect_unix`] with
+//! [`RemotePluginServer::accept_and_serve_unix`].
+
+use crate::automation::ParamChange;
+use crate::config::ConfigDelta;
+use crate::event::Event;
+use crate::features::FeatureFlag;
+use crate::host::LogLevel;
+use crate::logging::{LogRecord, LogSink, Logger};
+use crate::metrics::MetricsSnapshot;
+use crate::ports::{port_index, write_vec};
+use crate::preset::Preset;
+use crate::preview::Preview;
+use crate::template::WorkspaceTemplate;
+use crate::ui::{ChoiceOption, ConnectionBehavior, PluginBehavior, UISchema};
+use crate::{
+    ErrorSeverity, Plugin, PluginContext, PluginError, PluginId, PluginMeta, Port, ShutdownStatus,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    // `traceparent` is the host's span as a W3C header, see
+    // `otel::traceparent`, for requests a plugin's spans may be parented to.
+    Hello {
+        protocol_version: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        traceparent: Option<String>,
+    },
+    Describe,
+    SetConfig {
+        config: Value,
+    },
+    SetInput {
+        name: String,
+        value: f64,
+    },
+    Process {
+        tick: u64,
+        period_seconds: f64,
+        connected_inputs: u64,
+        connected_outputs: u64,
+        #[serde(default)]
+        wall_time_ns: u64,
+        #[serde(default)]
+        monotonic_ns: u64,
+        #[serde(default)]
+        missed_ticks: u64,
+        #[serde(default)]
+        jitter_ns: i64,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        automation: Vec<ParamChange>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        traceparent: Option<String>,
+    },
+    GetOutput {
+        name: String,
+    },
+    SetInputVec {
+        name: String,
+        values: Vec<f64>,
+    },
+    GetOutputVec {
+        name: String,
+    },
+    GetOutputString {
+        name: String,
+    },
+    SaveState,
+    RestoreState {
+        state: Value,
+    },
+    Action {
+        name: String,
+        #[serde(default)]
+        args: Value,
+    },
+    InvokeAction {
+        action_id: String,
+    },
+    MigrateConfig {
+        from_version: u32,
+        config: Value,
+    },
+    Options {
+        key: String,
+    },
+    Prepare {
+        sample_rate: f64,
+        block_size: usize,
+    },
+    Overrun {
+        missed: u64,
+    },
+    Lifecycle {
+        event: LifecycleEvent,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        traceparent: Option<String>,
+    },
+    Event {
+        event: Event,
+    },
+    Latency,
+    Preview,
+    SetFeature {
+        name: String,
+        enabled: bool,
+    },
+    AddOutput {
+        port: String,
+    },
+    RemoveOutput {
+        port: String,
+    },
+    Shutdown,
+}
+
+impl Request {
+    /// `Some` for requests that carry a `traceparent`, holding the header
+    /// if the host sent one.
+    pub fn traceparent(&self) -> Option<Option<&str>> {
+        match self {
+            Request::Hello { traceparent, .. }
+            | Request::Process { traceparent, .. }
+            | Request::Lifecycle { traceparent, .. } => Some(traceparent.as_deref()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleEvent {
+    Start,
+    Stop,
+    Pause,
+    Resume,
+    Reset,
+}
+
+/// Everything the host caches about a remote plugin.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Description {
+    pub meta: PluginMeta,
+    pub inputs: Vec<Port>,
+    pub outputs: Vec<Port>,
+    pub behavior: PluginBehavior,
+    #[serde(default)]
+    pub connection_dependent: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ui_schema: Option<UISchema>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub features: Vec<FeatureFlag>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub templates: Vec<WorkspaceTemplate>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub presets: Vec<Preset>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RemoteEvent {
+    Log { level: LogLevel, message: String },
+    Emitted { event: Event },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    Hello {
+        protocol_version: u32,
+    },
+    Description(Box<Description>),
+    Processed {
+        changed_outputs: u64,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        events: Vec<RemoteEvent>,
+        /// Only sent on ticks that touched a metric.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        metrics: Option<MetricsSnapshot>,
+    },
+    Output {
+        value: f64,
+    },
+    OutputVec {
+        values: Vec<f64>,
+    },
+    Value {
+        value: Option<Value>,
+    },
+    Ok,
+    Error {
+        severity: ErrorSeverity,
+        message: String,
+    },
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum RemoteError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("malformed message: {0}")]
+    Protocol(#[from] serde_json::Error),
+    #[error("protocol version {0} is not supported")]
+    Version(u32),
+    #[error("unexpected response: {0:?}")]
+    Unexpected(Response),
+    #[error("connection closed")]
+    Closed,
+}
+
+fn send<T: Serialize>(writer: &mut impl Write, message: &T) -> Result<(), RemoteError> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn receive<T: for<'de> Deserialize<'de>>(reader: &mut impl BufRead) -> Result<T, RemoteError> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(RemoteError::Closed);
+    }
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[derive(Default)]
+struct EventBuffer(Mutex<Vec<RemoteEvent>>);
+
+impl LogSink for EventBuffer {
+    fn log(&self, record: &LogRecord) {
+        if let Ok(mut events) = self.0.lock() {
+            events.push(RemoteEvent::Log {
+                level: record.level,
+                message: record.to_string(),
+            });
+        }
+    }
+}
+
+/// Plugin-process side of the protocol.
+///
+/// As with `testing::MockHost`, the caller supplies how port values are
+/// written and read, since the `Plugin` trait has no value accessors.
+pub struct RemotePluginServer<P: Plugin> {
+    plugin: P,
+    ctx: PluginContext,
+    set_input: fn(&mut P, &str, f64),
+    get_output: fn(&P, &str) -> f64,
+    config: Value,
+    events: Arc<EventBuffer>,
+    #[cfg(feature = "otel")]
+    spans: Option<fn(&mut P) -> &mut crate::otel::SpanRecorder>,
+}
+
+impl<P: Plugin> RemotePluginServer<P> {
+    pub fn new(
+        plugin: P,
+        set_input: fn(&mut P, &str, f64),
+        get_output: fn(&P, &str) -> f64,
+    ) -> Self {
+        let events = Arc::new(EventBuffer::default());
+        let ctx = PluginContext {
+            logger: Logger::new(events.clone(), plugin.id()).max_level(LogLevel::Debug),
+            ..PluginContext::default()
+        };
+        Self {
+            plugin,
+            ctx,
+            set_input,
+            get_output,
+            config: Value::Object(Default::default()),
+            events,
+            #[cfg(feature = "otel")]
+            spans: None,
+        }
+    }
+
+    /// Parents the spans of the recorder returned by `spans`, e.g.
+    /// `otel::Traced::recorder_mut`, under the host span each request
+    /// carries in its `traceparent`.
+    #[cfg(feature = "otel")]
+    pub fn trace_with(mut self, spans: fn(&mut P) -> &mut crate::otel::SpanRecorder) -> Self {
+        self.spans = Some(spans);
+        self
+    }
+
+    /// Answers requests until `Shutdown` or until the host disconnects.
+    pub fn serve(&mut self, reader: impl Read, mut writer: impl Write) -> Result<(), RemoteError> {
+        let mut reader = BufReader::new(reader);
+        loop {
+            let request = match receive::<Request>(&mut reader) {
+                Ok(request) => request,
+                Err(RemoteError::Closed) => return Ok(()),
+                Err(e) => return Err(e),
+            };
+            let shutdown = matches!(request, Request::Shutdown);
+            let response = self.handle(request);
+            send(&mut writer, &response)?;
+            if shutdown {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Connects to a host listening on `path`, see
+    /// [`RemotePluginClient::accept_unix`], and serves it.
+    #[cfg(unix)]
+    pub fn connect_and_serve_unix(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), RemoteError> {
+        let stream = std::os::unix::net::UnixStream::connect(path)?;
+        self.serve(stream.try_clone()?, stream)
+    }
+
+    /// Waits on `listener` for the host to connect, see
+    /// [`RemotePluginClient::connect_unix`], and serves it.
+    #[cfg(unix)]
+    pub fn accept_and_serve_unix(
+        &mut self,
+        listener: &std::os::unix::net::UnixListener,
+    ) -> Result<(), RemoteError> {
+        let (stream, _) = listener.accept()?;
+        self.serve(stream.try_clone()?, stream)
+    }
+
+    pub fn handle(&mut self, request: Request) -> Response {
+        #[cfg(feature = "otel")]
+        self.follow_trace(&request);
+        match self.try_handle(request) {
+            Ok(response) => response,
+            Err(e) => Response::Error {
+                severity: e.severity(),
+                message: e.to_string(),
+            },
+        }
+    }
+
+    #[cfg(feature = "otel")]
+    fn follow_trace(&mut self, request: &Request) {
+        let (Some(spans), Some(traceparent)) = (self.spans, request.traceparent()) else {
+            return;
+        };
+        spans(&mut self.plugin).set_parent(traceparent.and_then(crate::otel::parse_traceparent));
+    }
+
+    fn try_handle(&mut self, request: Request) -> Result<Response, PluginError> {
+        let plugin = &mut self.plugin;
+        Ok(match request {
+            Request::Hello {
+                protocol_version, ..
+            } => {
+                if protocol_version != PROTOCOL_VERSION {
+                    return Err(PluginError::Fatal(format!(
+                        "protocol version {protocol_version} is not supported"
+                    )));
+                }
+                Response::Hello {
+                    protocol_version: PROTOCOL_VERSION,
+                }
+            }
+            Request::Describe => Response::Description(Box::new(Description {
+                meta: plugin.meta().clone(),
+                inputs: plugin.inputs().to_vec(),
+                outputs: plugin.outputs().to_vec(),
+                behavior: plugin.behavior(),
+                connection_dependent: plugin.connection_behavior().dependent,
+                ui_schema: plugin.ui_schema(),
+                features: plugin.features(),
+                templates: plugin.templates(),
+                presets: plugin.presets(),
+            })),
+            Request::SetConfig { config } => {
+                let mut merged = self.config.clone();
+                if let (Some(merged), Some(update)) = (merged.as_object_mut(), config.as_object()) {
+                    merged.extend(update.clone());
+                }
+                plugin.on_config_changed(&ConfigDelta::between(&self.config, &merged))?;
+                self.config = merged;
+                Response::Ok
+            }
+            Request::SetInput { name, value } => {
+                (self.set_input)(plugin, &name, value);
+                if let Some(index) = port_index(plugin.inputs(), &name) {
+                    self.ctx.mark_input_updated(index);
+                }
+                Response::Ok
+            }
+            Request::Process {
+                tick,
+                period_seconds,
+                connected_inputs,
+                connected_outputs,
+                wall_time_ns,
+                monotonic_ns,
+                missed_ticks,
+                jitter_ns,
+                automation,
+                ..
+            } => {
+                self.ctx.tick = tick;
+                self.ctx.period_seconds = period_seconds;
+                self.ctx.wall_time = UNIX_EPOCH + Duration::from_nanos(wall_time_ns);
+                self.ctx.monotonic_ns = monotonic_ns;
+                self.ctx.missed_ticks = missed_ticks;
+                self.ctx.jitter_ns = jitter_ns;
+                self.ctx.connected_inputs = connected_inputs;
+                self.ctx.connected_outputs = connected_outputs;
+                self.ctx.automation = automation;
+                let result = plugin.process(&mut self.ctx);
+                self.ctx.automation.clear();
+                let mut events = self
+                    .events
+                    .0
+                    .lock()
+                    .map(|mut events| std::mem::take(&mut *events))
+                    .unwrap_or_default();
+                events.extend(
+                    self.ctx
+                        .events
+                        .drain(..)
+                        .map(|event| RemoteEvent::Emitted { event }),
+                );
+                result?;
+                Response::Processed {
+                    changed_outputs: plugin.take_changed_outputs(),
+                    events,
+                    metrics: self.ctx.metrics.take_changed(),
+                }
+            }
+            Request::GetOutput { name } => Response::Output {
+                value: (self.get_output)(plugin, &name),
+            },
+            Request::SetInputVec { name, values } => {
+                plugin.set_input_vec(&name, &values)?;
+                if let Some(index) = port_index(plugin.inputs(), &name) {
+                    self.ctx.mark_input_updated(index);
+                }
+                Response::Ok
+            }
+            Request::GetOutputVec { name } => {
+                let mut values = vec![0.0; plugin.get_output_vec(&name, &mut [])];
+                let len = plugin.get_output_vec(&name, &mut values);
+                values.truncate(len);
+                Response::OutputVec { values }
+            }
+            Request::GetOutputString { name } => Response::Value {
+                value: plugin.get_output_string(&name).map(Value::String),
+            },
+            Request::SaveState => Response::Value {
+                value: Some(plugin.save_state()?),
+            },
+            Request::RestoreState { state } => {
+                plugin.restore_state(state)?;
+                Response::Ok
+            }
+            Request::Action { name, args } => Response::Value {
+                value: plugin.action(&name, &args)?,
+            },
+            Request::InvokeAction { action_id } => {
+                plugin.on_action(&action_id)?;
+                Response::Ok
+            }
+            Request::MigrateConfig {
+                from_version,
+                config,
+            } => Response::Value {
+                value: Some(plugin.migrate_config(from_version, config)?),
+            },
+            Request::Options { key } => Response::Value {
+                value: serde_json::to_value(plugin.options_for(&key)).ok(),
+            },
+            Request::Prepare {
+                sample_rate,
+                block_size,
+            } => {
+                self.ctx.sample_rate = sample_rate;
+                self.ctx.block_size = block_size;
+                plugin.prepare(sample_rate, block_size)?;
+                Response::Ok
+            }
+            Request::Overrun { missed } => {
+                plugin.on_overrun(missed)?;
+                Response::Ok
+            }
+            Request::Lifecycle { event, .. } => {
+                match event {
+                    LifecycleEvent::Start => plugin.on_start()?,
+                    LifecycleEvent::Stop => plugin.on_stop()?,
+                    LifecycleEvent::Pause => plugin.on_pause()?,
+                    LifecycleEvent::Resume => plugin.on_resume()?,
+                    LifecycleEvent::Reset => plugin.reset()?,
+                }
+                Response::Ok
+            }
+            Request::Event { event } => {
+                plugin.on_event(&event)?;
+                Response::Ok
+            }
+            Request::AddOutput { port } => {
+                plugin.on_output_added(&port)?;
+                Response::Ok
+            }
+            Request::RemoveOutput { port } => {
+                plugin.on_output_removed(&port)?;
+                Response::Ok
+            }
+            Request::SetFeature { name, enabled } => {
+                plugin.set_feature_enabled(&name, enabled)?;
+                Response::Ok
+            }
+            Request::Latency => Response::Value {
+                value: Some(plugin.latency_ticks().into()),
+            },
+            Request::Preview => Response::Value {
+                value: plugin
+                    .preview()
+                    .and_then(|preview| serde_json::to_value(preview).ok()),
+            },
+            Request::Shutdown => {
+                drive_shutdown(plugin)?;
+                Response::Ok
+            }
+        })
+    }
+
+    pub fn into_inner(self) -> P {
+        self.plugin
+    }
+}
+
+// The server exits right after `Shutdown`, so shutdown is driven to
+// completion here instead of being polled by the host.
+fn drive_shutdown(plugin: &mut impl Plugin) -> Result<(), PluginError> {
+    if plugin.begin_shutdown()?.is_done() {
+        return Ok(());
+    }
+    while !plugin.poll_shutdown()?.is_done() {
+        std::thread::yield_now();
+    }
+    Ok(())
+}
+
+/// Host side of the protocol; implements [`Plugin`] by forwarding every call.
+///
+/// Transport failures are reported as [`PluginError::Fatal`], so a crashed
+/// plugin process is unloaded like any other fatal plugin.
+pub struct RemotePluginClient<R: Read, W: Write> {
+    id: PluginId,
+    // `save_state` only gets `&self` but still needs a round trip.
+    connection: RefCell<(BufReader<R>, W)>,
+    description: Description,
+    traceparent: Option<String>,
+    // Accumulated from `Response::Processed` until `take_changed_outputs`.
+    changed_outputs: u64,
+}
+
+impl<R: Read, W: Write> RemotePluginClient<R, W> {
+    /// Performs the handshake and caches the plugin description.
+    pub fn new(id: PluginId, reader: R, writer: W) -> Result<Self, RemoteError> {
+        Self::new_traced(id, reader, writer, None)
+    }
+
+    /// Like [`RemotePluginClient::new`], sending `traceparent` with the
+    /// handshake and every later request that carries one.
+    pub fn new_traced(
+        id: PluginId,
+        reader: R,
+        writer: W,
+        traceparent: Option<String>,
+    ) -> Result<Self, RemoteError> {
+        let mut reader = BufReader::new(reader);
+        let mut writer = writer;
+        send(
+            &mut writer,
+            &Request::Hello {
+                protocol_version: PROTOCOL_VERSION,
+                traceparent: traceparent.clone(),
+            },
+        )?;
+        match receive(&mut reader)? {
+            Response::Hello { protocol_version } if protocol_version == PROTOCOL_VERSION => {}
+            Response::Hello { protocol_version } => {
+                return Err(RemoteError::Version(protocol_version))
+            }
+            other => return Err(RemoteError::Unexpected(other)),
+        }
+        send(&mut writer, &Request::Describe)?;
+        let description = match receive(&mut reader)? {
+            Response::Description(description) => *description,
+            other => return Err(RemoteError::Unexpected(other)),
+        };
+        Ok(Self {
+            id,
+            connection: RefCell::new((reader, writer)),
+            description,
+            traceparent,
+            changed_outputs: u64::MAX,
+        })
+    }
+
+    pub fn description(&self) -> &Description {
+        &self.description
+    }
+
+    /// The host span, as a W3C `traceparent` header, that the plugin's
+    /// process and lifecycle spans are parented to.
+    pub fn set_traceparent(&mut self, traceparent: Option<String>) {
+        self.traceparent = traceparent;
+    }
+
+    pub fn request(&self, request: &Request) -> Result<Response, PluginError> {
+        let (reader, writer) = &mut *self.connection.borrow_mut();
+        let result = send(writer, request).and_then(|()| receive(reader));
+        match result {
+            Ok(Response::Error { severity, message }) => Err(match severity {
+                ErrorSeverity::Recoverable => PluginError::Recoverable(message),
+                ErrorSeverity::Reconfigure => PluginError::Config(message),
+                ErrorSeverity::Fatal => PluginError::Fatal(message),
+            }),
+            Ok(response) => Ok(response),
+            Err(e) => Err(PluginError::Fatal(format!("remote plugin failed: {e}"))),
+        }
+    }
+
+    fn expect_ok(&self, request: &Request) -> Result<(), PluginError> {
+        match self.request(request)? {
+            Response::Ok => Ok(()),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub fn set_config(&mut self, config: &Value) -> Result<(), PluginError> {
+        self.expect_ok(&Request::SetConfig {
+            config: config.clone(),
+        })
+    }
+
+    pub fn set_input(&mut self, name: &str, value: f64) -> Result<(), PluginError> {
+        self.expect_ok(&Request::SetInput {
+            name: name.to_string(),
+            value,
+        })
+    }
+
+    pub fn get_output(&mut self, name: &str) -> Result<f64, PluginError> {
+        match self.request(&Request::GetOutput {
+            name: name.to_string(),
+        })? {
+            Response::Output { value } => Ok(value),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub fn output_vec(&self, name: &str) -> Result<Vec<f64>, PluginError> {
+        match self.request(&Request::GetOutputVec {
+            name: name.to_string(),
+        })? {
+            Response::OutputVec { values } => Ok(values),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    fn describe(&mut self) -> Result<(), PluginError> {
+        match self.request(&Request::Describe)? {
+            Response::Description(description) => {
+                self.description = *description;
+                Ok(())
+            }
+            other => Err(unexpected(other)),
+        }
+    }
+
+    fn lifecycle(&mut self, event: LifecycleEvent) -> Result<(), PluginError> {
+        self.expect_ok(&Request::Lifecycle {
+            event,
+            traceparent: self.traceparent.clone(),
+        })
+    }
+}
+
+#[cfg(unix)]
+impl RemotePluginClient<std::os::unix::net::UnixStream, std::os::unix::net::UnixStream> {
+    /// Waits on `listener` for the plugin process to connect.
+    pub fn accept_unix(
+        id: PluginId,
+        listener: &std::os::unix::net::UnixListener,
+    ) -> Result<Self, RemoteError> {
+        let (stream, _) = listener.accept()?;
+        Self::new(id, stream.try_clone()?, stream)
+    }
+
+    /// Connects to a plugin process listening on `path`.
+    pub fn connect_unix(
+        id: PluginId,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<Self, RemoteError> {
+        let stream = std::os::unix::net::UnixStream::connect(path)?;
+        Self::new(id, stream.try_clone()?, stream)
+    }
+}
+
+fn unexpected(response: Response) -> PluginError {
+    PluginError::Fatal(format!(
+        "unexpected response from remote plugin: {response:?}"
+    ))
+}
+
+impl<R: Read + Send, W: Write + Send> Plugin for RemotePluginClient<R, W> {
+    fn id(&self) -> PluginId {
+        self.id
+    }
+
+    fn meta(&self) -> &PluginMeta {
+        &self.description.meta
+    }
+
+    fn inputs(&self) -> &[Port] {
+        &self.description.inputs
+    }
+
+    fn outputs(&self) -> &[Port] {
+        &self.description.outputs
+    }
+
+    fn process(&mut self, ctx: &mut PluginContext) -> Result<(), PluginError> {
+        let response = self.request(&Request::Process {
+            tick: ctx.tick,
+            period_seconds: ctx.period_seconds,
+            connected_inputs: ctx.connected_inputs,
+            connected_outputs: ctx.connected_outputs,
+            wall_time_ns: ctx.wall_time_ns(),
+            monotonic_ns: ctx.monotonic_ns,
+            missed_ticks: ctx.missed_ticks,
+            jitter_ns: ctx.jitter_ns,
+            automation: ctx.automation.clone(),
+            traceparent: self.traceparent.clone(),
+        })?;
+        let Response::Processed {
+            changed_outputs,
+            events,
+            metrics,
+        } = response
+        else {
+            return Err(unexpected(response));
+        };
+        self.changed_outputs |= changed_outputs;
+        if let Some(metrics) = metrics {
+            ctx.metrics.load(&metrics);
+        }
+        for event in events {
+            match event {
+                RemoteEvent::Log { level, message } => ctx.logger.log(level, &message, &[]),
+                RemoteEvent::Emitted { event } => ctx.emit(event),
+            }
+        }
+        Ok(())
+    }
+
+    fn ui_schema(&self) -> Option<UISchema> {
+        self.description.ui_schema.clone()
+    }
+
+    // Every call is a round trip to another process.
+    fn behavior(&self) -> PluginBehavior {
+        PluginBehavior {
+            may_block: true,
+            realtime_safe: false,
+            ..self.description.behavior.clone()
+        }
+    }
+
+    fn connection_behavior(&self) -> ConnectionBehavior {
+        ConnectionBehavior {
+            dependent: self.description.connection_dependent,
+        }
+    }
+
+    fn take_changed_outputs(&mut self) -> u64 {
+        std::mem::take(&mut self.changed_outputs)
+    }
+
+    fn on_config_changed(&mut self, changed: &ConfigDelta) -> Result<(), PluginError> {
+        // Removed keys cannot be expressed as a merge update; the server
+        // keeps their previous value, as hosts do for partial updates.
+        let update: serde_json::Map<String, Value> = changed
+            .iter()
+            .filter_map(|change| Some((change.key.clone(), change.new.clone()?)))
+            .collect();
+        self.set_config(&Value::Object(update))
+    }
+
+    fn save_state(&self) -> Result<Value, PluginError> {
+        match self.request(&Request::SaveState)? {
+            Response::Value { value } => Ok(value.unwrap_or(Value::Null)),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    fn restore_state(&mut self, state: Value) -> Result<(), PluginError> {
+        self.expect_ok(&Request::RestoreState { state })
+    }
+
+    fn migrate_config(&self, from_version: u32, old: Value) -> Result<Value, PluginError> {
+        match self.request(&Request::MigrateConfig {
+            from_version,
+            config: old,
+        })? {
+            Response::Value { value } => Ok(value.unwrap_or(Value::Null)),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    fn options_for(&self, key: &str) -> Vec<ChoiceOption> {
+        match self.request(&Request::Options {
+            key: key.to_string(),
+        }) {
+            Ok(Response::Value { value: Some(value) }) => {
+                serde_json::from_value(value).unwrap_or_default()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn action(&mut self, name: &str, args: &Value) -> Result<Option<Value>, PluginError> {
+        match self.request(&Request::Action {
+            name: name.to_string(),
+            args: args.clone(),
+        })? {
+            Response::Value { value } => Ok(value),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    fn on_action(&mut self, action_id: &str) -> Result<(), PluginError> {
+        self.expect_ok(&Request::InvokeAction {
+            action_id: action_id.to_string(),
+        })
+    }
+
+    fn on_event(&mut self, event: &Event) -> Result<(), PluginError> {
+        self.expect_ok(&Request::Event {
+            event: event.clone(),
+        })
+    }
+
+    fn features(&self) -> Vec<FeatureFlag> {
+        self.description.features.clone()
+    }
+
+    fn set_feature_enabled(&mut self, name: &str, enabled: bool) -> Result<(), PluginError> {
+        self.expect_ok(&Request::SetFeature {
+            name: name.to_string(),
+            enabled,
+        })
+    }
+
+    // The cached description is refreshed so `outputs` shows the change.
+    fn on_output_added(&mut self, port: &str) -> Result<(), PluginError> {
+        self.expect_ok(&Request::AddOutput {
+            port: port.to_string(),
+        })?;
+        self.describe()
+    }
+
+    fn on_output_removed(&mut self, port: &str) -> Result<(), PluginError> {
+        self.expect_ok(&Request::RemoveOutput {
+            port: port.to_string(),
+        })?;
+        self.describe()
+    }
+
+    fn templates(&self) -> Vec<WorkspaceTemplate> {
+        self.description.templates.clone()
+    }
+
+    fn presets(&self) -> Vec<Preset> {
+        self.description.presets.clone()
+    }
+
+    fn preview(&self) -> Option<Preview> {
+        match self.request(&Request::Preview) {
+            Ok(Response::Value { value: Some(value) }) => serde_json::from_value(value).ok(),
+            _ => None,
+        }
+    }
+
+    // Falls back to the described behavior when the round trip fails.
+    fn latency_ticks(&self) -> u64 {
+        match self.request(&Request::Latency) {
+            Ok(Response::Value { value: Some(value) }) => value.as_u64(),
+            _ => None,
+        }
+        .unwrap_or(self.description.behavior.latency_ticks)
+    }
+
+    fn prepare(&mut self, sample_rate: f64, block_size: usize) -> Result<(), PluginError> {
+        self.expect_ok(&Request::Prepare {
+            sample_rate,
+            block_size,
+        })
+    }
+
+    fn on_overrun(&mut self, missed: u64) -> Result<(), PluginError> {
+        self.expect_ok(&Request::Overrun { missed })
+    }
+
+    fn on_start(&mut self) -> Result<(), PluginError> {
+        self.lifecycle(LifecycleEvent::Start)
+    }
+
+    fn on_stop(&mut self) -> Result<(), PluginError> {
+        self.lifecycle(LifecycleEvent::Stop)
+    }
+
+    fn on_pause(&mut self) -> Result<(), PluginError> {
+        self.lifecycle(LifecycleEvent::Pause)
+    }
+
+    fn on_resume(&mut self) -> Result<(), PluginError> {
+        self.lifecycle(LifecycleEvent::Resume)
+    }
+
+    fn reset(&mut self) -> Result<(), PluginError> {
+        self.lifecycle(LifecycleEvent::Reset)
+    }
+
+    fn set_input_vec(&mut self, name: &str, values: &[f64]) -> Result<(), PluginError> {
+        self.expect_ok(&Request::SetInputVec {
+            name: name.to_string(),
+            values: values.to_vec(),
+        })
+    }
+
+    // A failed round trip reads as an empty output.
+    fn get_output_vec(&self, name: &str, out: &mut [f64]) -> usize {
+        let values = self.output_vec(name).unwrap_or_default();
+        write_vec(&values, out)
+    }
+
+    fn get_output_string(&self, name: &str) -> Option<String> {
+        match self.request(&Request::GetOutputString {
+            name: name.to_string(),
+        }) {
+            Ok(Response::Value {
+                value: Some(Value::String(text)),
+            }) => Some(text),
+            _ => None,
+        }
+    }
+
+    // The server finishes shutting the plugin down before it answers, then
+    // stops serving.
+    fn begin_shutdown(&mut self) -> Result<ShutdownStatus, PluginError> {
+        self.expect_ok(&Request::Shutdown)?;
+        Ok(ShutdownStatus::Done)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::ports::StaticPorts;
+    use std::os::unix::net::UnixStream;
+
+    struct Doubler {
+        meta: PluginMeta,
+        ports: StaticPorts<1, 1>,
+        frame: Vec<f64>,
+    }
+
+    impl Plugin for Doubler {
+        fn id(&self) -> PluginId {
+            PluginId(9)
+        }
+        fn meta(&self) -> &PluginMeta {
+            &self.meta
+        }
+        fn inputs(&self) -> &[Port] {
+            self.ports.inputs()
+        }
+        fn outputs(&self) -> &[Port] {
+            self.ports.outputs()
+        }
+        fn process(&mut self, ctx: &mut PluginContext) -> Result<(), PluginError> {
+            let value = self.ports.input(0) * 2.0;
+            if value < 0.0 {
+                return Err(PluginError::Config("negative input".to_string()));
+            }
+            self.ports.set_output(0, value);
+            ctx.logger.info("processed", &[("tick", ctx.tick.into())]);
+            if value > 2.0 {
+                ctx.emit(Event::new(PluginId(1), "above", Value::from(value)));
+            }
+            Ok(())
+        }
+        fn save_state(&self) -> Result<Value, PluginError> {
+            Ok(Value::from(self.ports.output(0)))
+        }
+        fn take_changed_outputs(&mut self) -> u64 {
+            self.ports.take_changed_outputs()
+        }
+        fn set_input_vec(&mut self, name: &str, values: &[f64]) -> Result<(), PluginError> {
+            if name != "frame" {
+                return Err(PluginError::Config(format!("{name} is not a vector")));
+            }
+            self.frame = values.iter().map(|value| value * 2.0).collect();
+            Ok(())
+        }
+        fn get_output_vec(&self, _name: &str, out: &mut [f64]) -> usize {
+            write_vec(&self.frame, out)
+        }
+        fn get_output_string(&self, name: &str) -> Option<String> {
+            (name == "status").then(|| format!("{} samples", self.frame.len()))
+        }
+        fn options_for(&self, key: &str) -> Vec<ChoiceOption> {
+            match key {
+                "port" => vec![ChoiceOption::new("/dev/ttyUSB0", "USB serial")],
+                _ => Vec::new(),
+            }
+        }
+    }
+
+    #[test]
+    fn client_drives_plugin_in_server() {
+        let (host_side, plugin_side) = UnixStream::pair().unwrap();
+        let server = std::thread::spawn(move || {
+            doubler_server()
+                .serve(plugin_side.try_clone().unwrap(), plugin_side)
+                .unwrap();
+        });
+
+        let mut client =
+            RemotePluginClient::new(PluginId(1), host_side.try_clone().unwrap(), host_side)
+                .unwrap();
+        assert_eq!(client.meta().name, "doubler");
+        assert_eq!(client.outputs()[0].id.0, "out");
+        assert!(client.behavior().may_block);
+
+        client.set_input("in", 1.5).unwrap();
+        let mut ctx = PluginContext {
+            tick: 4,
+            ..PluginContext::default()
+        };
+        client.process(&mut ctx).unwrap();
+        assert_eq!(client.take_changed_outputs(), u64::MAX);
+        client.process(&mut ctx).unwrap();
+        assert_eq!(client.take_changed_outputs(), 0);
+        assert_eq!(client.get_output("out").unwrap(), 3.0);
+        assert_eq!(ctx.events[0].topic, "above");
+        assert_eq!(ctx.events[0].payload, Value::from(3.0));
+        assert_eq!(client.save_state().unwrap(), Value::from(3.0));
+
+        client.set_input_vec("frame", &[1.0, 2.0, 3.0]).unwrap();
+        let mut frame = [0.0; 2];
+        assert_eq!(client.get_output_vec("frame", &mut frame), 3);
+        assert_eq!(frame, [2.0, 4.0]);
+        assert!(client.set_input_vec("in", &[1.0]).is_err());
+        assert_eq!(
+            client.get_output_string("status").as_deref(),
+            Some("3 samples")
+        );
+        assert_eq!(client.get_output_string("out"), None);
+        assert_eq!(client.options_for("port")[0].value, "/dev/ttyUSB0");
+        assert!(client.options_for("rate").is_empty());
+
+        client.set_input("in", -1.0).unwrap();
+        let err = client.process(&mut ctx).unwrap_err();
+        assert!(matches!(err, PluginError::Config(_)));
+
+        assert!(client.begin_shutdown().unwrap().is_done());
+        server.join().unwrap();
+        assert!(client.get_output("out").unwrap_err().is_fatal());
+    }
+
+    fn doubler_server() -> RemotePluginServer<Doubler> {
+        RemotePluginServer::new(
+            Doubler {
+                meta: PluginMeta::new("doubler"),
+                ports: StaticPorts::new(["in"], ["out"]),
+                frame: Vec::new(),
+            },
+            |p, name, v| p.ports.set_input_by_name(name, v),
+            |p, name| p.ports.output_by_name(name),
+        )
+    }
+
+    #[test]
+    fn either_side_may_listen() {
+        use std::os::unix::net::UnixListener;
+
+        let dir = std::env::temp_dir();
+        let host_path = dir.join(format!("rtsyn-remote-host-{}.sock", std::process::id()));
+        let plugin_path = dir.join(format!("rtsyn-remote-plugin-{}.sock", std::process::id()));
+
+        let _ = std::fs::remove_file(&host_path);
+        let _ = std::fs::remove_file(&plugin_path);
+
+        let listener = UnixListener::bind(&host_path).unwrap();
+        let path = host_path.clone();
+        let server =
+            std::thread::spawn(move || doubler_server().connect_and_serve_unix(path).unwrap());
+        let mut client = RemotePluginClient::accept_unix(PluginId(1), &listener).unwrap();
+        assert_eq!(client.meta().name, "doubler");
+        assert!(client.begin_shutdown().unwrap().is_done());
+        server.join().unwrap();
+
+        let listener = UnixListener::bind(&plugin_path).unwrap();
+        let server =
+            std::thread::spawn(move || doubler_server().accept_and_serve_unix(&listener).unwrap());
+        let mut client = RemotePluginClient::connect_unix(PluginId(1), &plugin_path).unwrap();
+        assert_eq!(client.meta().name, "doubler");
+        assert!(client.begin_shutdown().unwrap().is_done());
+        server.join().unwrap();
+
+        std::fs::remove_file(host_path).unwrap();
+        std::fs::remove_file(plugin_path).unwrap();
+    }
+}
@@ -0,0 +1,203 @@
+//! Offline runs over recorded input data.
+//!
+//! Recordings are sparse: a tick without a sample for an input is a gap.
+//! [`Replay`] fills every gap according to the [`GapPolicy`] of the input
+//! port before the first tick, so `MockHost::replay`, `LoadedPlugin::replay`
+//! and [`run`] all treat gaps the same way.
+
+use crate::ports::resolve_port;
+use crate::{Plugin, PluginContext, PluginError, Port};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Value an input takes on ticks the recording has no sample for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GapPolicy {
+    /// The previous sample, or the first one for leading gaps.
+    #[default]
+    Hold,
+    /// Straight line between the samples around the gap; gaps at either end
+    /// hold the nearest sample.
+    Linear,
+    Nan,
+}
+
+impl GapPolicy {
+    pub fn is_hold(&self) -> bool {
+        *self == GapPolicy::Hold
+    }
+}
+
+/// One value per tick, with `None` for gaps. Inputs without any sample are
+/// filled with NaN whatever the policy.
+pub fn fill_gaps(samples: &[Option<f64>], policy: GapPolicy) -> Vec<f64> {
+    match policy {
+        GapPolicy::Nan => samples.iter().map(|s| s.unwrap_or(f64::NAN)).collect(),
+        GapPolicy::Hold => {
+            let mut last = samples.iter().flatten().next().copied().unwrap_or(f64::NAN);
+            samples
+                .iter()
+                .map(|sample| {
+                    if let Some(value) = sample {
+                        last = *value;
+                    }
+                    last
+                })
+                .collect()
+        }
+        GapPolicy::Linear => {
+            let mut out = Vec::with_capacity(samples.len());
+            let mut prev: Option<(usize, f64)> = None;
+            for (index, sample) in samples.iter().enumerate() {
+                let Some(value) = *sample else {
+                    out.push(f64::NAN);
+                    continue;
+                };
+                let start = prev.map_or(0, |(p, _)| p + 1);
+                for (gap, slot) in out[start..].iter_mut().enumerate() {
+                    *slot = match prev {
+                        Some((p, before)) => {
+                            let t = (start + gap - p) as f64 / (index - p) as f64;
+                            before + (value - before) * t
+                        }
+                        None => value,
+                    };
+                }
+                out.push(value);
+                prev = Some((index, value));
+            }
+            if let Some((p, last)) = prev {
+                out[p + 1..].fill(last);
+            }
+            out
+        }
+    }
+}
+
+/// Recorded inputs with their gaps filled, ready to be fed tick by tick.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Replay {
+    tracks: Vec<(String, Vec<f64>)>,
+    ticks: usize,
+}
+
+impl Replay {
+    /// Fills each track with the gap policy of the input it names, which may
+    /// be an alias. Tracks shorter than the longest one end in a gap.
+    pub fn new<'a>(
+        inputs: &[Port],
+        tracks: impl IntoIterator<Item = (&'a str, &'a [Option<f64>])>,
+    ) -> Result<Self, PluginError> {
+        let tracks: Vec<_> = tracks.into_iter().collect();
+        let ticks = tracks
+            .iter()
+            .map(|(_, samples)| samples.len())
+            .max()
+            .unwrap_or(0);
+        let mut filled = Vec::with_capacity(tracks.len());
+        for (name, samples) in tracks {
+            let port = resolve_port(inputs, name)
+                .map(|found| found.port)
+                .ok_or_else(|| PluginError::Config(format!("unknown input {name}")))?;
+            let mut samples = samples.to_vec();
+            samples.resize(ticks, None);
+            filled.push((port.id.0.clone(), fill_gaps(&samples, port.gap)));
+        }
+        Ok(Self {
+            tracks: filled,
+            ticks,
+        })
+    }
+
+    pub fn ticks(&self) -> usize {
+        self.ticks
+    }
+
+    /// The value of every track on `tick`, by port id.
+    pub fn inputs_at(&self, tick: usize) -> impl Iterator<Item = (&str, f64)> {
+        self.tracks
+            .iter()
+            .map(move |(port, values)| (port.as_str(), values[tick]))
+    }
+
+    pub fn track(&self, port: &str) -> Option<&[f64]> {
+        self.tracks
+            .iter()
+            .find(|(id, _)| id == port)
+            .map(|(_, values)| values.as_slice())
+    }
+}
+
+/// Drives `plugin` through every tick of `replay`, writing the inputs with
+/// `set_input` before `process` and calling `after_tick` after it. The
+/// context advances by one period per tick.
+pub fn run<P: Plugin + ?Sized>(
+    plugin: &mut P,
+    replay: &Replay,
+    ctx: &mut PluginContext,
+    mut set_input: impl FnMut(&mut P, &str, f64),
+    mut after_tick: impl FnMut(&P, &PluginContext),
+) -> Result<(), PluginError> {
+    let period = Duration::from_secs_f64(ctx.period_seconds.max(0.0));
+    for tick in 0..replay.ticks() {
+        for (port, value) in replay.inputs_at(tick) {
+            set_input(plugin, port, value);
+        }
+        plugin.process(ctx)?;
+        after_tick(plugin, ctx);
+        ctx.tick += 1;
+        ctx.wall_time += period;
+        ctx.monotonic_ns += period.as_nanos() as u64;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_gaps_per_policy() {
+        let samples = [None, Some(1.0), None, None, Some(4.0), None];
+        assert_eq!(
+            fill_gaps(&samples, GapPolicy::Hold),
+            [1.0, 1.0, 1.0, 1.0, 4.0, 4.0]
+        );
+        assert_eq!(
+            fill_gaps(&samples, GapPolicy::Linear),
+            [1.0, 1.0, 2.0, 3.0, 4.0, 4.0]
+        );
+        let nan = fill_gaps(&samples, GapPolicy::Nan);
+        assert!(nan[0].is_nan() && nan[3].is_nan());
+        assert_eq!(nan[4], 4.0);
+        assert!(fill_gaps(&[None, None], GapPolicy::Linear)[1].is_nan());
+    }
+
+    #[test]
+    fn replay_uses_port_policies() {
+        let inputs = [
+            Port::new("emg").gap(GapPolicy::Linear).alias("raw"),
+            Port::new("marker").gap(GapPolicy::Nan),
+        ];
+        let replay = Replay::new(
+            &inputs,
+            [
+                ("raw", &[Some(0.0), None, Some(1.0)][..]),
+                ("marker", &[Some(5.0)][..]),
+            ],
+        )
+        .unwrap();
+        assert_eq!(replay.ticks(), 3);
+        assert_eq!(replay.track("emg"), Some(&[0.0, 0.5, 1.0][..]));
+        assert!(replay.track("marker").unwrap()[2].is_nan());
+        assert!(Replay::new(&inputs, [("missing", &[None][..])]).is_err());
+
+        let json = serde_json::to_value(&inputs[0]).unwrap();
+        assert_eq!(json["gap"], "linear");
+        assert!(serde_json::to_value(Port::new("x"))
+            .unwrap()
+            .get("gap")
+            .is_none());
+    }
+}
@@ -0,0 +1,278 @@
+//! Data files installed next to a plugin library.
+//!
+//! A plugin `libfir.so` (or `fir.dll`) finds its resources in the first of
+//! these directories that exists:
+//!
+//! 1. `$RTSYN_PLUGIN_RESOURCES/fir`, for each entry of the path list
+//! 2. `<dir>/fir.resources`
+//! 3. `<dir>/resources/fir`
+//! 4. `<dir>/../share/rtsyn/fir`, for FHS-style installs
+//! 5. `<dir>/resources`
+//!
+//! where `<dir>` is the directory containing the library. A root may carry a
+//! `resources.json` [`ResourceManifest`] with the SHA-256 of each file, which
+//! [`ResourceBundle::verify`] checks.
+//!
+//! Plugins do not search themselves; they ask the host through
+//! `HostServices::resource_path`, and the host answers from the bundle of
+//! the library the plugin was loaded from.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+pub const RESOURCE_PATH_ENV: &str = "RTSYN_PLUGIN_RESOURCES";
+pub const MANIFEST_FILE: &str = "resources.json";
+
+#[derive(thiserror::Error, Debug)]
+pub enum ResourceError {
+    #[error("resource name {0:?} must be a relative path without `..`")]
+    InvalidName(String),
+    #[error("resource {0} not found")]
+    NotFound(String),
+    #[error("resource {0} is not listed in {MANIFEST_FILE}")]
+    NotListed(String),
+    #[error("resource {name} is corrupt: expected sha256 {expected}, found {actual}")]
+    Mismatch {
+        name: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("invalid {MANIFEST_FILE}: {0}")]
+    Manifest(#[from] serde_json::Error),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceEntry {
+    pub sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+}
+
+/// Checksums of the files in one resource root, keyed by resource name with
+/// `/` separators.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceManifest {
+    pub files: BTreeMap<String, ResourceEntry>,
+}
+
+impl ResourceManifest {
+    /// `None` when `root` has no manifest.
+    pub fn load(root: &Path) -> Result<Option<Self>, ResourceError> {
+        match std::fs::read(root.join(MANIFEST_FILE)) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Hashes `names` below `root`, e.g. from a packaging script.
+    pub fn generate<'a>(
+        root: &Path,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self, ResourceError> {
+        let mut files = BTreeMap::new();
+        for name in names {
+            let path = root.join(relative_path(name)?);
+            files.insert(
+                name.to_string(),
+                ResourceEntry {
+                    sha256: sha256_file(&path)?,
+                    size: Some(std::fs::metadata(&path)?.len()),
+                },
+            );
+        }
+        Ok(Self { files })
+    }
+
+    pub fn save(&self, root: &Path) -> Result<(), ResourceError> {
+        std::fs::write(root.join(MANIFEST_FILE), serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Lowercase hex SHA-256 of a file's contents.
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 8192];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+fn relative_path(name: &str) -> Result<PathBuf, ResourceError> {
+    let path = Path::new(name);
+    let valid = !name.is_empty()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+    if valid {
+        Ok(path.to_path_buf())
+    } else {
+        Err(ResourceError::InvalidName(name.to_string()))
+    }
+}
+
+/// Candidate resource directories of one plugin library, in search order.
+#[derive(Debug, Clone, Default)]
+pub struct ResourceBundle {
+    roots: Vec<PathBuf>,
+}
+
+impl ResourceBundle {
+    /// Roots for the library at `library`; see the module docs. Only
+    /// directories that exist are kept.
+    pub fn for_library(library: &Path) -> Self {
+        let stem = library
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        let stem = if cfg!(windows) {
+            stem
+        } else {
+            stem.strip_prefix("lib").unwrap_or(stem)
+        };
+        let dir = library.parent().unwrap_or(Path::new("."));
+
+        let mut candidates = Vec::new();
+        if let Some(paths) = std::env::var_os(RESOURCE_PATH_ENV) {
+            candidates.extend(std::env::split_paths(&paths).map(|path| path.join(stem)));
+        }
+        candidates.push(dir.join(format!("{stem}.resources")));
+        candidates.push(dir.join("resources").join(stem));
+        candidates.push(dir.join("..").join("share").join("rtsyn").join(stem));
+        candidates.push(dir.join("resources"));
+
+        Self {
+            roots: candidates
+                .into_iter()
+                .filter(|root| root.is_dir())
+                .collect(),
+        }
+    }
+
+    /// Searched after the existing roots.
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.roots.push(root.into());
+        self
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// Path of `name` in the first root containing it.
+    pub fn resolve(&self, name: &str) -> Result<PathBuf, ResourceError> {
+        self.locate(name).map(|(_, path)| path)
+    }
+
+    /// Like [`ResourceBundle::resolve`], and checks the file against the
+    /// manifest of its root. Files without a manifest entry are rejected.
+    pub fn verify(&self, name: &str) -> Result<PathBuf, ResourceError> {
+        let (root, path) = self.locate(name)?;
+        let manifest = ResourceManifest::load(root)?.unwrap_or_default();
+        let entry = manifest
+            .files
+            .get(name)
+            .ok_or_else(|| ResourceError::NotListed(name.to_string()))?;
+        let actual = sha256_file(&path)?;
+        if !actual.eq_ignore_ascii_case(&entry.sha256) {
+            return Err(ResourceError::Mismatch {
+                name: name.to_string(),
+                expected: entry.sha256.clone(),
+                actual,
+            });
+        }
+        Ok(path)
+    }
+
+    fn locate(&self, name: &str) -> Result<(&Path, PathBuf), ResourceError> {
+        let relative = relative_path(name)?;
+        self.roots
+            .iter()
+            .map(|root| (root.as_path(), root.join(&relative)))
+            .find(|(_, path)| path.is_file())
+            .ok_or_else(|| ResourceError::NotFound(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("rtsyn-resources-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn resolves_across_layouts_and_verifies() {
+        let dir = temp_dir("layouts");
+        let bundled = dir.join("fir.resources");
+        let shared = dir.join("resources");
+        std::fs::create_dir_all(bundled.join("coeffs")).unwrap();
+        std::fs::create_dir_all(&shared).unwrap();
+        std::fs::write(bundled.join("coeffs/lowpass.bin"), b"\x01\x02").unwrap();
+        std::fs::write(shared.join("window.bin"), b"hann").unwrap();
+
+        let bundle = ResourceBundle::for_library(&dir.join("libfir.so"));
+        assert_eq!(bundle.roots(), [bundled.clone(), shared.clone()]);
+        assert_eq!(
+            bundle.resolve("coeffs/lowpass.bin").unwrap(),
+            bundled.join("coeffs/lowpass.bin")
+        );
+        assert_eq!(
+            bundle.resolve("window.bin").unwrap(),
+            shared.join("window.bin")
+        );
+        assert!(matches!(
+            bundle.resolve("../secret"),
+            Err(ResourceError::InvalidName(_))
+        ));
+
+        assert!(matches!(
+            bundle.verify("coeffs/lowpass.bin"),
+            Err(ResourceError::NotListed(_))
+        ));
+        ResourceManifest::generate(&bundled, ["coeffs/lowpass.bin"])
+            .unwrap()
+            .save(&bundled)
+            .unwrap();
+        assert!(bundle.verify("coeffs/lowpass.bin").is_ok());
+
+        std::fs::write(bundled.join("coeffs/lowpass.bin"), b"\x01\x03").unwrap();
+        assert!(matches!(
+            bundle.verify("coeffs/lowpass.bin"),
+            Err(ResourceError::Mismatch { .. })
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn sha256_matches_known_digest() {
+        let dir = temp_dir("sha");
+        std::fs::write(dir.join("abc"), b"abc").unwrap();
+        assert_eq!(
+            sha256_file(&dir.join("abc")).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
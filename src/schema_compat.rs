@@ -0,0 +1,503 @@
+//! Compatibility of a plugin's [`UISchema`] across releases, so a plugin
+//! repository can fail CI before shipping a schema that breaks saved
+//! configurations:
+//!
+//! ```ignore
+//! #[test]
+//! fn schema_stays_compatible() {
+//!     let old: UISchema = serde_json::from_str(include_str!("schema-1.4.json")).unwrap();
+//!     let report = schema_compat::check(&old, &MyPlugin::default().ui_schema().unwrap());
+//!     assert!(report.is_compatible(), "{report}");
+//! }
+//! ```
+
+use crate::ui::{ConfigField, FieldType, UISchema, ValidationRule};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How a change affects configurations saved under the old schema, from
+/// harmless to fatal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompatLevel {
+    /// Every old configuration stays valid.
+    Additive,
+    /// Old configurations stay valid unless they use values the new schema
+    /// no longer accepts.
+    Narrowing,
+    /// Old configurations are rejected or misread.
+    Breaking,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaChange {
+    pub key: String,
+    pub level: CompatLevel,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CompatReport {
+    pub changes: Vec<SchemaChange>,
+    /// Whether the new schema has a higher `schema_version`, i.e. promises
+    /// a `Plugin::migrate_config` for the old configurations.
+    pub version_bumped: bool,
+}
+
+impl CompatReport {
+    /// The most severe change, or `None` if the schemas accept the same
+    /// configurations.
+    pub fn level(&self) -> Option<CompatLevel> {
+        self.changes.iter().map(|change| change.level).max()
+    }
+
+    pub fn at_least(&self, level: CompatLevel) -> impl Iterator<Item = &SchemaChange> {
+        self.changes
+            .iter()
+            .filter(move |change| change.level >= level)
+    }
+
+    /// No breaking changes, or breaking changes behind a bumped schema
+    /// version.
+    pub fn is_compatible(&self) -> bool {
+        self.version_bumped || self.level() < Some(CompatLevel::Breaking)
+    }
+
+    fn push(&mut self, key: &str, level: CompatLevel, message: impl Into<String>) {
+        self.changes.push(SchemaChange {
+            key: key.to_string(),
+            level,
+            message: message.into(),
+        });
+    }
+}
+
+impl fmt::Display for CompatReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.changes.is_empty() {
+            return write!(f, "no schema changes");
+        }
+        for change in &self.changes {
+            writeln!(f, "{:?} {}: {}", change.level, change.key, change.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// Classifies every difference between the value fields of `old` and `new`.
+/// Labels, hints, layout and defaults of existing fields are not compared.
+pub fn check(old: &UISchema, new: &UISchema) -> CompatReport {
+    let mut report = CompatReport {
+        version_bumped: new.schema_version > old.schema_version,
+        ..CompatReport::default()
+    };
+    compare_fields(&mut report, "", &old.value_fields(), &new.value_fields());
+    report
+}
+
+// `prefix` is prepended to the keys in the report, e.g. `"channels[]."` for
+// the fields of structured list items.
+fn compare_fields(
+    report: &mut CompatReport,
+    prefix: &str,
+    old_fields: &[&ConfigField],
+    new_fields: &[&ConfigField],
+) {
+    for old_field in old_fields {
+        let key = &format!("{prefix}{}", old_field.key);
+        let Some(new_field) = new_fields.iter().find(|f| f.key == old_field.key) else {
+            report.push(key, CompatLevel::Breaking, "field removed");
+            continue;
+        };
+        compare_types(report, key, &old_field.field_type, &new_field.field_type);
+        for rule in new_field
+            .rules
+            .iter()
+            .filter(|rule| !old_field.rules.contains(rule))
+        {
+            report.push(
+                key,
+                CompatLevel::Narrowing,
+                format!("rule added: {}", rule_name(rule)),
+            );
+        }
+        for rule in old_field
+            .rules
+            .iter()
+            .filter(|rule| !new_field.rules.contains(rule))
+        {
+            report.push(
+                key,
+                CompatLevel::Additive,
+                format!("rule removed: {}", rule_name(rule)),
+            );
+        }
+    }
+    for new_field in new_fields {
+        if old_fields.iter().any(|f| f.key == new_field.key) {
+            continue;
+        }
+        let key = format!("{prefix}{}", new_field.key);
+        if new_field.rules.contains(&ValidationRule::Required) && new_field.default.is_none() {
+            report.push(
+                &key,
+                CompatLevel::Breaking,
+                "required field added without a default",
+            );
+        } else {
+            report.push(&key, CompatLevel::Additive, "field added");
+        }
+    }
+}
+
+fn compare_types(report: &mut CompatReport, key: &str, old: &FieldType, new: &FieldType) {
+    match (old, new) {
+        (
+            FieldType::Integer { min, max, .. },
+            FieldType::Integer {
+                min: new_min,
+                max: new_max,
+                ..
+            },
+        ) => {
+            compare_bound(report, key, "minimum", min.map(|v| -v), new_min.map(|v| -v));
+            compare_bound(report, key, "maximum", *max, *new_max);
+        }
+        (
+            FieldType::Float { min, max, .. },
+            FieldType::Float {
+                min: new_min,
+                max: new_max,
+                ..
+            },
+        ) => {
+            compare_bound(report, key, "minimum", min.map(|v| -v), new_min.map(|v| -v));
+            compare_bound(report, key, "maximum", *max, *new_max);
+        }
+        // Every integer is a valid number, so only the bounds matter.
+        (
+            FieldType::Integer { min, max, .. },
+            FieldType::Float {
+                min: new_min,
+                max: new_max,
+                ..
+            },
+        ) => {
+            report.push(key, CompatLevel::Additive, "integer widened to float");
+            let as_f64 = |v: &Option<i64>| v.map(|v| v as f64);
+            compare_bound(
+                report,
+                key,
+                "minimum",
+                as_f64(min).map(|v| -v),
+                new_min.map(|v| -v),
+            );
+            compare_bound(report, key, "maximum", as_f64(max), *new_max);
+        }
+        (
+            FieldType::Text { max_length, .. },
+            FieldType::Text {
+                max_length: new_max_length,
+                ..
+            },
+        ) => compare_bound(report, key, "maximum length", *max_length, *new_max_length),
+        (
+            FieldType::Choice {
+                options,
+                options_source,
+            },
+            FieldType::Choice {
+                options: new_options,
+                options_source: new_options_source,
+            },
+        ) if options_source.is_static() && new_options_source.is_static() => {
+            for option in options
+                .iter()
+                .filter(|o| !new_options.iter().any(|n| n.value == o.value))
+            {
+                report.push(
+                    key,
+                    CompatLevel::Narrowing,
+                    format!("option {:?} removed", option.value),
+                );
+            }
+            for option in new_options
+                .iter()
+                .filter(|n| !options.iter().any(|o| o.value == n.value))
+            {
+                report.push(
+                    key,
+                    CompatLevel::Additive,
+                    format!("option {:?} added", option.value),
+                );
+            }
+        }
+        (
+            FieldType::DynamicList {
+                item_type,
+                min_items,
+                max_items,
+                ..
+            },
+            FieldType::DynamicList {
+                item_type: new_item_type,
+                min_items: new_min_items,
+                max_items: new_max_items,
+                ..
+            },
+        ) => {
+            compare_types(report, &format!("{key}[]"), item_type, new_item_type);
+            let negated = |v: &Option<usize>| v.map(|v| -(v as i64));
+            compare_bound(
+                report,
+                key,
+                "minimum items",
+                negated(min_items),
+                negated(new_min_items),
+            );
+            compare_bound(report, key, "maximum items", *max_items, *new_max_items);
+        }
+        // Cells are positional, so any change to the column layout breaks
+        // saved rows.
+        (
+            FieldType::Table {
+                columns,
+                min_rows,
+                max_rows,
+            },
+            FieldType::Table {
+                columns: new_columns,
+                min_rows: new_min_rows,
+                max_rows: new_max_rows,
+            },
+        ) => {
+            let same_layout = columns.len() == new_columns.len()
+                && columns.iter().zip(new_columns).all(|(a, b)| a.key == b.key);
+            if !same_layout {
+                report.push(key, CompatLevel::Breaking, "columns changed");
+            } else {
+                for (column, new_column) in columns.iter().zip(new_columns) {
+                    compare_types(
+                        report,
+                        &format!("{key}.{}", column.key),
+                        &column.cell_type,
+                        &new_column.cell_type,
+                    );
+                }
+            }
+            let negated = |v: &Option<usize>| v.map(|v| -(v as i64));
+            compare_bound(
+                report,
+                key,
+                "minimum rows",
+                negated(min_rows),
+                negated(new_min_rows),
+            );
+            compare_bound(report, key, "maximum rows", *max_rows, *new_max_rows);
+        }
+        // Only reachable as the item type of a structured list.
+        (
+            FieldType::Group { fields, .. },
+            FieldType::Group {
+                fields: new_fields, ..
+            },
+        ) => {
+            let old_fields: Vec<&ConfigField> = fields.iter().collect();
+            let new_fields: Vec<&ConfigField> = new_fields.iter().collect();
+            compare_fields(report, &format!("{key}."), &old_fields, &new_fields);
+        }
+        (
+            FieldType::Duration { unit, min, max },
+            FieldType::Duration {
+                unit: new_unit,
+                min: new_min,
+                max: new_max,
+            },
+        ) => {
+            if unit != new_unit {
+                report.push(
+                    key,
+                    CompatLevel::Breaking,
+                    format!(
+                        "unit changed from {} to {}",
+                        unit.symbol(),
+                        new_unit.symbol()
+                    ),
+                );
+            } else {
+                let negated = |v: &Option<u64>| v.map(|v| -(v as i128));
+                compare_bound(report, key, "minimum", negated(min), negated(new_min));
+                compare_bound(report, key, "maximum", *max, *new_max);
+            }
+        }
+        (FieldType::Boolean, FieldType::Boolean)
+        | (FieldType::Secret, FieldType::Secret)
+        | (FieldType::DateTime, FieldType::DateTime)
+        | (FieldType::FilePath { .. }, FieldType::FilePath { .. }) => {}
+        _ => report.push(
+            key,
+            CompatLevel::Breaking,
+            format!("type changed from {} to {}", kind(old), kind(new)),
+        ),
+    }
+}
+
+// Compares upper bounds; lower bounds are passed negated. `None` is
+// unbounded.
+fn compare_bound<T: PartialOrd + fmt::Debug>(
+    report: &mut CompatReport,
+    key: &str,
+    what: &str,
+    old: Option<T>,
+    new: Option<T>,
+) {
+    let level = match (&old, &new) {
+        (Some(old), Some(new)) if new < old => CompatLevel::Narrowing,
+        (None, Some(_)) => CompatLevel::Narrowing,
+        (Some(old), Some(new)) if new > old => CompatLevel::Additive,
+        (Some(_), None) => CompatLevel::Additive,
+        _ => return,
+    };
+    report.push(key, level, format!("{what} changed"));
+}
+
+fn kind(field_type: &FieldType) -> &'static str {
+    match field_type {
+        FieldType::Integer { .. } => "integer",
+        FieldType::Float { .. } => "float",
+        FieldType::Text { .. } => "text",
+        FieldType::Boolean => "boolean",
+        FieldType::Secret => "secret",
+        FieldType::Duration { .. } => "duration",
+        FieldType::DateTime => "datetime",
+        FieldType::Action { .. } => "action",
+        FieldType::Computed { .. } => "computed",
+        FieldType::Info { .. } => "info",
+        FieldType::FilePath { .. } => "filepath",
+        FieldType::DynamicList { .. } => "dynamiclist",
+        FieldType::Choice { .. } => "choice",
+        FieldType::Table { .. } => "table",
+        FieldType::Group { .. } => "group",
+    }
+}
+
+fn rule_name(rule: &ValidationRule) -> &'static str {
+    match rule {
+        ValidationRule::Required => "required",
+        ValidationRule::Regex { .. } => "regex",
+        ValidationRule::Range { .. } => "range",
+        ValidationRule::OneOf { .. } => "one_of",
+        ValidationRule::MinLength { .. } => "min_length",
+        ValidationRule::Custom { .. } => "custom",
+        ValidationRule::RequiresWhen { .. } => "requires_when",
+        ValidationRule::LessThan { .. } => "less_than",
+        ValidationRule::GreaterThan { .. } => "greater_than",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::ConfigField;
+    use serde_json::json;
+
+    fn v1() -> UISchema {
+        UISchema::new()
+            .field(ConfigField::integer("rate", "Rate").min(1).max(1000))
+            .field(
+                ConfigField::choice("mode", "Mode")
+                    .option("lp", "Low-pass")
+                    .option("hp", "High-pass"),
+            )
+            .field(ConfigField::text("name", "Name").max_length(32))
+            .field(ConfigField::boolean("legacy", "Legacy"))
+    }
+
+    #[test]
+    fn classifies_changes() {
+        assert_eq!(check(&v1(), &v1()).level(), None);
+
+        let widened = UISchema::new()
+            .field(ConfigField::float("rate", "Rate").min_f(0.5).max_f(1000.0))
+            .field(
+                ConfigField::choice("mode", "Mode")
+                    .option("lp", "Low-pass")
+                    .option("hp", "High-pass")
+                    .option("bp", "Band-pass"),
+            )
+            .field(ConfigField::text("name", "Name"))
+            .field(ConfigField::boolean("legacy", "Legacy"))
+            .field(
+                ConfigField::boolean("verbose", "Verbose")
+                    .required()
+                    .default_value(json!(false)),
+            );
+        let report = check(&v1(), &widened);
+        assert_eq!(report.level(), Some(CompatLevel::Additive));
+        assert_eq!(report.changes.len(), 5, "{report}");
+
+        let mut narrowed = v1()
+            .field(ConfigField::text("unit", "Unit").rule(ValidationRule::MinLength { min: 1 }));
+        narrowed.fields[0] = ConfigField::integer("rate", "Rate").min(10).max(1000);
+        narrowed.fields[1] = ConfigField::choice("mode", "Mode").option("lp", "Low-pass");
+        let report = check(&v1(), &narrowed);
+        assert_eq!(report.level(), Some(CompatLevel::Narrowing));
+        assert!(report.is_compatible());
+        let keys: Vec<_> = report
+            .at_least(CompatLevel::Narrowing)
+            .map(|c| c.key.as_str())
+            .collect();
+        assert_eq!(keys, ["rate", "mode"]);
+    }
+
+    #[test]
+    fn breaking_changes_need_a_version_bump() {
+        let mut broken = v1();
+        broken.fields.pop();
+        broken.fields[2] = ConfigField::integer("name", "Name");
+        let broken = broken.field(ConfigField::text("owner", "Owner").required());
+        let report = check(&v1(), &broken);
+        assert_eq!(
+            report.at_least(CompatLevel::Breaking).count(),
+            3,
+            "{report}"
+        );
+        assert!(!report.is_compatible());
+
+        assert!(check(&v1(), &broken.schema_version(1)).is_compatible());
+    }
+
+    #[test]
+    fn compares_structured_list_items() {
+        let rows = |fields: Vec<ConfigField>| {
+            UISchema::new().field(
+                ConfigField::dynamic_list("channels", "Channels")
+                    .item_fields(fields)
+                    .max_items(8),
+            )
+        };
+        let old = rows(vec![
+            ConfigField::integer("channel", "Channel"),
+            ConfigField::float("gain", "Gain"),
+        ]);
+        assert_eq!(check(&old, &old).level(), None);
+
+        let new = rows(vec![
+            ConfigField::integer("channel", "Channel"),
+            ConfigField::text("label", "Label"),
+        ]);
+        let report = check(&old, &new);
+        let changes: Vec<_> = report
+            .changes
+            .iter()
+            .map(|c| (c.key.as_str(), c.level))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("channels[].gain", CompatLevel::Breaking),
+                ("channels[].label", CompatLevel::Additive),
+            ]
+        );
+    }
+}
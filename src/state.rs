@@ -0,0 +1,433 @@
+//! Portable binary container for plugin state, presets and checkpoints.
+//!
+//! Every integer is little-endian and every value carries a one-byte type
+//! tag, so a blob written on one platform restores bit-identically on any
+//! other, independent of the native byte order or struct layout.
+//!
+//! Layout, version 1:
+//!
+//! | bytes | content                                         |
+//! |-------|-------------------------------------------------|
+//! | 4     | magic `RTSB`                                    |
+//! | 2     | format version, `u16`                           |
+//! | 1     | [`BlobKind`]                                    |
+//! | 1     | reserved, `0`                                   |
+//! | 2 + n | plugin uid, `u16` length + UTF-8, empty if none |
+//! | 2 + n | plugin version, same encoding                   |
+//! | 4     | payload length, `u32`                           |
+//! | n     | payload: one tagged value                       |
+//!
+//! Tagged values are `0x00` null, `0x01` false, `0x02` true, `0x03` `i64`,
+//! `0x04` `u64`, `0x05` `f64` (IEEE 754 bits), `0x06` string (`u32` length +
+//! UTF-8), `0x07` array (`u32` count + values) and `0x08` object (`u32` count
+//! + string key and value per entry, in key order).
+
+use crate::{Plugin, PluginError, PluginMeta, PluginUid};
+use serde_json::{Map, Number, Value};
+
+pub const STATE_MAGIC: [u8; 4] = *b"RTSB";
+pub const STATE_FORMAT_VERSION: u16 = 1;
+
+const TAG_NULL: u8 = 0x00;
+const TAG_FALSE: u8 = 0x01;
+const TAG_TRUE: u8 = 0x02;
+const TAG_I64: u8 = 0x03;
+const TAG_U64: u8 = 0x04;
+const TAG_F64: u8 = 0x05;
+const TAG_STRING: u8 = 0x06;
+const TAG_ARRAY: u8 = 0x07;
+const TAG_OBJECT: u8 = 0x08;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlobKind {
+    State = 1,
+    Preset = 2,
+    Checkpoint = 3,
+}
+
+impl BlobKind {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(BlobKind::State),
+            2 => Some(BlobKind::Preset),
+            3 => Some(BlobKind::Checkpoint),
+            _ => None,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum StateFormatError {
+    #[error("not a state blob")]
+    BadMagic,
+    #[error("state format version {0} is not supported")]
+    UnsupportedVersion(u16),
+    #[error("unknown blob kind {0}")]
+    UnknownKind(u8),
+    #[error("unknown value tag {0:#04x}")]
+    UnknownTag(u8),
+    #[error("blob is truncated")]
+    Truncated,
+    #[error("{0} trailing bytes after payload")]
+    TrailingBytes(usize),
+    #[error("invalid UTF-8 in string")]
+    InvalidUtf8,
+    #[error("{0} is too long to encode")]
+    TooLong(&'static str),
+    #[error("blob is for {found}, expected {expected}")]
+    WrongPlugin { expected: String, found: String },
+}
+
+impl From<StateFormatError> for PluginError {
+    fn from(e: StateFormatError) -> Self {
+        PluginError::Config(e.to_string())
+    }
+}
+
+/// A decoded blob: header fields and the value they describe.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateBlob {
+    pub kind: BlobKind,
+    pub plugin: Option<PluginUid>,
+    pub plugin_version: Option<String>,
+    pub value: Value,
+}
+
+impl StateBlob {
+    pub fn new(kind: BlobKind, value: Value) -> Self {
+        Self {
+            kind,
+            plugin: None,
+            plugin_version: None,
+            value,
+        }
+    }
+
+    /// Takes uid and version from `meta`.
+    pub fn for_plugin(kind: BlobKind, meta: &PluginMeta, value: Value) -> Self {
+        Self {
+            plugin: meta.uid.clone(),
+            plugin_version: meta.version.clone(),
+            ..Self::new(kind, value)
+        }
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, StateFormatError> {
+        let mut payload = Vec::new();
+        encode_value(&self.value, &mut payload)?;
+
+        let mut out = Vec::with_capacity(payload.len() + 32);
+        out.extend_from_slice(&STATE_MAGIC);
+        out.extend_from_slice(&STATE_FORMAT_VERSION.to_le_bytes());
+        out.push(self.kind as u8);
+        out.push(0);
+        write_short_str(
+            self.plugin.as_ref().map_or("", |uid| uid.as_str()),
+            &mut out,
+        )?;
+        write_short_str(self.plugin_version.as_deref().unwrap_or(""), &mut out)?;
+        out.extend_from_slice(&len_u32(payload.len(), "payload")?.to_le_bytes());
+        out.extend_from_slice(&payload);
+        Ok(out)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, StateFormatError> {
+        let mut reader = Reader { bytes, pos: 0 };
+        if reader.take(4)? != STATE_MAGIC {
+            return Err(StateFormatError::BadMagic);
+        }
+        let version = reader.u16()?;
+        if version != STATE_FORMAT_VERSION {
+            return Err(StateFormatError::UnsupportedVersion(version));
+        }
+        let kind = reader.u8()?;
+        let kind = BlobKind::from_u8(kind).ok_or(StateFormatError::UnknownKind(kind))?;
+        reader.u8()?;
+        let plugin = reader.short_str()?;
+        let plugin_version = reader.short_str()?;
+        let len = reader.u32()? as usize;
+        let mut payload = Reader {
+            bytes: reader.take(len)?,
+            pos: 0,
+        };
+        let value = payload.value()?;
+        if payload.remaining() > 0 {
+            return Err(StateFormatError::TrailingBytes(payload.remaining()));
+        }
+        if reader.remaining() > 0 {
+            return Err(StateFormatError::TrailingBytes(reader.remaining()));
+        }
+        Ok(Self {
+            kind,
+            plugin: (!plugin.is_empty()).then(|| PluginUid::new(plugin)),
+            plugin_version: (!plugin_version.is_empty()).then_some(plugin_version),
+            value,
+        })
+    }
+}
+
+/// Encodes `plugin.save_state()` as a [`BlobKind::State`] blob.
+pub fn save_state(plugin: &dyn Plugin) -> Result<Vec<u8>, PluginError> {
+    let blob = StateBlob::for_plugin(BlobKind::State, plugin.meta(), plugin.save_state()?);
+    Ok(blob.encode()?)
+}
+
+/// Decodes `bytes` and passes the value to `plugin.restore_state`.
+///
+/// Blobs written by a plugin with a different uid are rejected; blobs
+/// without a uid are accepted by any plugin.
+pub fn restore_state(plugin: &mut dyn Plugin, bytes: &[u8]) -> Result<(), PluginError> {
+    let blob = StateBlob::decode(bytes)?;
+    if let (Some(found), Some(expected)) = (&blob.plugin, &plugin.meta().uid) {
+        if found != expected {
+            return Err(StateFormatError::WrongPlugin {
+                expected: expected.as_str().to_string(),
+                found: found.as_str().to_string(),
+            }
+            .into());
+        }
+    }
+    plugin.restore_state(blob.value)
+}
+
+fn len_u32(len: usize, what: &'static str) -> Result<u32, StateFormatError> {
+    u32::try_from(len).map_err(|_| StateFormatError::TooLong(what))
+}
+
+fn write_short_str(value: &str, out: &mut Vec<u8>) -> Result<(), StateFormatError> {
+    let len = u16::try_from(value.len()).map_err(|_| StateFormatError::TooLong("header string"))?;
+    out.extend_from_slice(&len.to_le_bytes());
+    out.extend_from_slice(value.as_bytes());
+    Ok(())
+}
+
+fn write_str(value: &str, out: &mut Vec<u8>) -> Result<(), StateFormatError> {
+    out.extend_from_slice(&len_u32(value.len(), "string")?.to_le_bytes());
+    out.extend_from_slice(value.as_bytes());
+    Ok(())
+}
+
+/// Appends the tagged encoding of `value`.
+pub fn encode_value(value: &Value, out: &mut Vec<u8>) -> Result<(), StateFormatError> {
+    match value {
+        Value::Null => out.push(TAG_NULL),
+        Value::Bool(false) => out.push(TAG_FALSE),
+        Value::Bool(true) => out.push(TAG_TRUE),
+        Value::Number(number) => {
+            if let Some(v) = number.as_u64() {
+                out.push(TAG_U64);
+                out.extend_from_slice(&v.to_le_bytes());
+            } else if let Some(v) = number.as_i64() {
+                out.push(TAG_I64);
+                out.extend_from_slice(&v.to_le_bytes());
+            } else {
+                out.push(TAG_F64);
+                let v = number.as_f64().unwrap_or_default();
+                out.extend_from_slice(&v.to_bits().to_le_bytes());
+            }
+        }
+        Value::String(s) => {
+            out.push(TAG_STRING);
+            write_str(s, out)?;
+        }
+        Value::Array(items) => {
+            out.push(TAG_ARRAY);
+            out.extend_from_slice(&len_u32(items.len(), "array")?.to_le_bytes());
+            for item in items {
+                encode_value(item, out)?;
+            }
+        }
+        Value::Object(map) => {
+            out.push(TAG_OBJECT);
+            out.extend_from_slice(&len_u32(map.len(), "object")?.to_le_bytes());
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            for (key, item) in entries {
+                write_str(key, out)?;
+                encode_value(item, out)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Decodes one tagged value occupying all of `bytes`.
+pub fn decode_value(bytes: &[u8]) -> Result<Value, StateFormatError> {
+    let mut reader = Reader { bytes, pos: 0 };
+    let value = reader.value()?;
+    match reader.remaining() {
+        0 => Ok(value),
+        n => Err(StateFormatError::TrailingBytes(n)),
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], StateFormatError> {
+        if self.remaining() < len {
+            return Err(StateFormatError::Truncated);
+        }
+        let slice = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], StateFormatError> {
+        let mut out = [0; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> Result<u8, StateFormatError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, StateFormatError> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, StateFormatError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, StateFormatError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn utf8(&mut self, len: usize) -> Result<String, StateFormatError> {
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| StateFormatError::InvalidUtf8)
+    }
+
+    fn short_str(&mut self) -> Result<String, StateFormatError> {
+        let len = self.u16()? as usize;
+        self.utf8(len)
+    }
+
+    fn str(&mut self) -> Result<String, StateFormatError> {
+        let len = self.u32()? as usize;
+        self.utf8(len)
+    }
+
+    fn value(&mut self) -> Result<Value, StateFormatError> {
+        Ok(match self.u8()? {
+            TAG_NULL => Value::Null,
+            TAG_FALSE => Value::Bool(false),
+            TAG_TRUE => Value::Bool(true),
+            TAG_I64 => Value::Number((self.u64()? as i64).into()),
+            TAG_U64 => Value::Number(self.u64()?.into()),
+            TAG_F64 => {
+                Number::from_f64(f64::from_bits(self.u64()?)).map_or(Value::Null, Value::Number)
+            }
+            TAG_STRING => Value::String(self.str()?),
+            TAG_ARRAY => {
+                let count = self.u32()? as usize;
+                // Every value takes at least one byte, which bounds the
+                // allocation for corrupt counts.
+                let mut items = Vec::with_capacity(count.min(self.remaining()));
+                for _ in 0..count {
+                    items.push(self.value()?);
+                }
+                Value::Array(items)
+            }
+            TAG_OBJECT => {
+                let count = self.u32()? as usize;
+                let mut map = Map::new();
+                for _ in 0..count {
+                    let key = self.str()?;
+                    map.insert(key, self.value()?);
+                }
+                Value::Object(map)
+            }
+            tag => return Err(StateFormatError::UnknownTag(tag)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn encoding_is_little_endian_and_stable() {
+        let blob = StateBlob {
+            kind: BlobKind::Preset,
+            plugin: Some(PluginUid::new("a.b")),
+            plugin_version: None,
+            value: json!({ "n": 258, "x": -1.5 }),
+        };
+        let bytes = blob.encode().unwrap();
+        #[rustfmt::skip]
+        let expected: &[u8] = &[
+            b'R', b'T', b'S', b'B', 1, 0, 2, 0,
+            3, 0, b'a', b'.', b'b',
+            0, 0,
+            33, 0, 0, 0,
+            TAG_OBJECT, 2, 0, 0, 0,
+            1, 0, 0, 0, b'n', TAG_U64, 0x02, 0x01, 0, 0, 0, 0, 0, 0,
+            1, 0, 0, 0, b'x', TAG_F64, 0, 0, 0, 0, 0, 0, 0xf8, 0xbf,
+        ];
+        assert_eq!(bytes, expected);
+        assert_eq!(StateBlob::decode(expected).unwrap(), blob);
+    }
+
+    #[test]
+    fn round_trips_every_value_type() {
+        let value = json!({
+            "null": null,
+            "flags": [true, false],
+            "neg": i64::MIN,
+            "big": u64::MAX,
+            "pi": std::f64::consts::PI,
+            "text": "grüße",
+            "nested": { "empty": [], "obj": {} },
+        });
+        let bytes = StateBlob::new(BlobKind::Checkpoint, value.clone())
+            .encode()
+            .unwrap();
+        let blob = StateBlob::decode(&bytes).unwrap();
+        assert_eq!(blob.kind, BlobKind::Checkpoint);
+        assert_eq!(blob.value, value);
+    }
+
+    #[test]
+    fn rejects_malformed_blobs() {
+        let bytes = StateBlob::new(BlobKind::State, json!([1, 2]))
+            .encode()
+            .unwrap();
+        assert_eq!(
+            StateBlob::decode(&bytes[..bytes.len() - 1]),
+            Err(StateFormatError::Truncated)
+        );
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert_eq!(
+            StateBlob::decode(&longer),
+            Err(StateFormatError::TrailingBytes(1))
+        );
+        let mut version = bytes.clone();
+        version[4] = 9;
+        assert_eq!(
+            StateBlob::decode(&version),
+            Err(StateFormatError::UnsupportedVersion(9))
+        );
+        assert_eq!(
+            StateBlob::decode(b"JSON{}"),
+            Err(StateFormatError::BadMagic)
+        );
+        assert_eq!(
+            decode_value(&[0x42]),
+            Err(StateFormatError::UnknownTag(0x42))
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! Ready-made workspace snippets contributed by plugins, e.g. "EMG
+//! quick-start: driver + filter + logger wired up", which hosts offer in a
+//! "new from template" menu.
+
+use crate::PluginUid;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// One plugin instance of a [`GraphInfo`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeInfo {
+    /// Identifies the node within the template only.
+    pub key: String,
+    pub plugin: PluginUid,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Configuration applied after creation, merged over the defaults.
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub config: Value,
+}
+
+impl NodeInfo {
+    pub fn new(key: impl Into<String>, plugin: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            plugin: PluginUid::new(plugin),
+            label: None,
+            config: Value::Null,
+        }
+    }
+
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn config(mut self, config: Value) -> Self {
+        self.config = config;
+        self
+    }
+}
+
+/// A port of a node, addressed by node key and port id.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PortRef {
+    pub node: String,
+    pub port: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ConnectionInfo {
+    pub from: PortRef,
+    pub to: PortRef,
+}
+
+/// Plugins and connections of a sub-graph.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GraphInfo {
+    pub nodes: Vec<NodeInfo>,
+    #[serde(default)]
+    pub connections: Vec<ConnectionInfo>,
+}
+
+impl GraphInfo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn node(mut self, node: NodeInfo) -> Self {
+        self.nodes.push(node);
+        self
+    }
+
+    /// Connects output `from_port` of node `from` to input `to_port` of
+    /// node `to`.
+    pub fn connect(mut self, from: &str, from_port: &str, to: &str, to_port: &str) -> Self {
+        self.connections.push(ConnectionInfo {
+            from: PortRef {
+                node: from.to_string(),
+                port: from_port.to_string(),
+            },
+            to: PortRef {
+                node: to.to_string(),
+                port: to_port.to_string(),
+            },
+        });
+        self
+    }
+
+    pub fn find_node(&self, key: &str) -> Option<&NodeInfo> {
+        self.nodes.iter().find(|node| node.key == key)
+    }
+
+    /// Structural problems: duplicate node keys, invalid uids and
+    /// connections to unknown nodes. Port ids are not checked, since that
+    /// needs the plugins themselves.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut keys = HashSet::new();
+        for node in &self.nodes {
+            if !keys.insert(node.key.as_str()) {
+                problems.push(format!("duplicate node {}", node.key));
+            }
+            if !node.plugin.is_valid() {
+                problems.push(format!(
+                    "node {} has invalid plugin uid {}",
+                    node.key, node.plugin
+                ));
+            }
+        }
+        for connection in &self.connections {
+            for end in [&connection.from, &connection.to] {
+                if !keys.contains(end.node.as_str()) {
+                    problems.push(format!("connection to unknown node {}", end.node));
+                }
+            }
+        }
+        problems
+    }
+}
+
+/// A named [`GraphInfo`] returned from `Plugin::templates`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceTemplate {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    pub graph: GraphInfo,
+}
+
+impl WorkspaceTemplate {
+    pub fn new(name: impl Into<String>, graph: GraphInfo) -> Self {
+        Self {
+            name: name.into(),
+            description: String::new(),
+            graph,
+        }
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn emg() -> WorkspaceTemplate {
+        WorkspaceTemplate::new(
+            "EMG quick-start",
+            GraphInfo::new()
+                .node(NodeInfo::new("amp", "com.example.emg_amp"))
+                .node(
+                    NodeInfo::new("filter", "com.example.bandpass")
+                        .config(json!({ "low_hz": 20.0, "high_hz": 450.0 })),
+                )
+                .node(NodeInfo::new("log", "com.example.csv_logger").label("Recording"))
+                .connect("amp", "ch0", "filter", "in")
+                .connect("filter", "out", "log", "in_0"),
+        )
+        .description("Amplifier, band-pass and CSV logger")
+    }
+
+    #[test]
+    fn template_round_trips() {
+        let template = emg();
+        assert!(template.graph.validate().is_empty());
+        let json = serde_json::to_value(&template).unwrap();
+        assert_eq!(json["graph"]["connections"][0]["from"]["node"], "amp");
+        assert!(json["graph"]["nodes"][0].get("config").is_none());
+        let back: WorkspaceTemplate = serde_json::from_value(json).unwrap();
+        assert_eq!(back, template);
+    }
+
+    #[test]
+    fn validate_reports_broken_graphs() {
+        let graph = emg()
+            .graph
+            .node(NodeInfo::new("amp", "not a uid"))
+            .connect("filter", "out", "scope", "in");
+        assert_eq!(
+            graph.validate(),
+            [
+                "duplicate node amp",
+                "node amp has invalid plugin uid not a uid",
+                "connection to unknown node scope",
+            ]
+        );
+    }
+}
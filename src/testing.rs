@@ -0,0 +1,535 @@
+use crate::automation::ParamChange;
+use crate::config::{ConfigDelta, ConfigTracker};
+use crate::event::Event;
+use crate::host::LogLevel;
+use crate::logging::{LogRecord, LogSink, Logger};
+use crate::metrics::MetricsSnapshot;
+use crate::replay::Replay;
+use crate::ui::{ComparisonMode, ExtendableInputs, ExtendableOutputs, FieldType};
+use crate::{ports, Plugin, PluginContext, PluginError, Port};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Output values of one tick, in the order of `Plugin::outputs`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TickRecord {
+    pub tick: u64,
+    pub outputs: Vec<(String, f64)>,
+}
+
+impl TickRecord {
+    pub fn output(&self, name: &str) -> Option<f64> {
+        self.outputs
+            .iter()
+            .find(|(port, _)| port == name)
+            .map(|(_, value)| *value)
+    }
+}
+
+#[derive(Default)]
+struct CollectLogs(Mutex<Vec<(LogLevel, String)>>);
+
+impl LogSink for CollectLogs {
+    fn log(&self, record: &LogRecord) {
+        if let Ok(mut logs) = self.0.lock() {
+            logs.push((record.level, record.to_string()));
+        }
+    }
+}
+
+/// Minimal host for plugin unit tests.
+///
+/// The `Plugin` trait has no value accessors, so the test supplies how inputs
+/// are written and outputs read with [`MockHost::io`]; without it, ticks are
+/// still driven but no outputs are recorded.
+///
+/// ```ignore
+/// let mut host = MockHost::new(Gain::new())
+///     .period(0.001)
+///     .io(|p, name, v| p.ports.set_input_by_name(name, v), |p, name| p.ports.output_by_name(name));
+/// host.configure(&json!({ "gain": 2.0 }))?;
+/// host.set_input("in", 1.5);
+/// host.run(10)?;
+/// assert_eq!(host.output_series("out"), vec![3.0; 10]);
+/// ```
+pub struct MockHost<P: Plugin> {
+    plugin: P,
+    ctx: PluginContext,
+    config: ConfigTracker,
+    inputs: Vec<(String, f64)>,
+    set_input: Option<fn(&mut P, &str, f64)>,
+    get_output: Option<fn(&P, &str) -> f64>,
+    records: Vec<TickRecord>,
+    events: Vec<Event>,
+    logs: Arc<CollectLogs>,
+}
+
+impl<P: Plugin> MockHost<P> {
+    pub fn new(plugin: P) -> Self {
+        let logs = Arc::new(CollectLogs::default());
+        let ctx = PluginContext {
+            period_seconds: 0.001,
+            logger: Logger::new(logs.clone(), plugin.id()).max_level(LogLevel::Trace),
+            ..PluginContext::default()
+        };
+        Self {
+            plugin,
+            ctx,
+            config: ConfigTracker::new(),
+            inputs: Vec::new(),
+            set_input: None,
+            get_output: None,
+            records: Vec::new(),
+            events: Vec::new(),
+            logs,
+        }
+    }
+
+    pub fn period(mut self, seconds: f64) -> Self {
+        self.ctx.period_seconds = seconds;
+        self
+    }
+
+    pub fn io(mut self, set_input: fn(&mut P, &str, f64), get_output: fn(&P, &str) -> f64) -> Self {
+        self.set_input = Some(set_input);
+        self.get_output = Some(get_output);
+        self
+    }
+
+    /// Validates `config` against the plugin's UI schema, merges it into the
+    /// current configuration and delivers the delta.
+    pub fn configure(&mut self, config: &Value) -> Result<ConfigDelta, PluginError> {
+        if let Some(schema) = self.plugin.ui_schema() {
+            let mut merged = self.config.current().clone();
+            if let Some(update) = config.as_object() {
+                merged.extend(update.clone());
+            }
+            let errors = schema.validate(&Value::Object(merged));
+            if !errors.is_empty() {
+                let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+                return Err(PluginError::Config(errors.join("; ")));
+            }
+        }
+        let delta = self.config.apply(config);
+        self.plugin.on_config_changed(&delta)?;
+        Ok(delta)
+    }
+
+    /// Value written to input `name` before every following tick.
+    pub fn set_input(&mut self, name: &str, value: f64) {
+        match self.inputs.iter_mut().find(|(port, _)| port == name) {
+            Some(input) => input.1 = value,
+            None => self.inputs.push((name.to_string(), value)),
+        }
+    }
+
+    /// Ramps parameter `key` to `target` over `ramp_ticks`, starting on the
+    /// next tick run.
+    pub fn automate(&mut self, key: &str, target: f64, ramp_ticks: u64) {
+        self.ctx
+            .automation
+            .push(ParamChange::new(key, target, ramp_ticks));
+    }
+
+    /// Sets the sample rate and block size of the context and calls
+    /// `Plugin::prepare`, as a host does before the first tick.
+    pub fn prepare(&mut self, sample_rate: f64, block_size: usize) -> Result<(), PluginError> {
+        self.ctx.sample_rate = sample_rate;
+        self.ctx.block_size = block_size;
+        self.plugin.prepare(sample_rate, block_size)
+    }
+
+    pub fn connect_all(&mut self) {
+        let all = |ports: &[Port]| match ports.len() {
+            0 => 0,
+            n if n >= 64 => u64::MAX,
+            n => (1u64 << n) - 1,
+        };
+        self.ctx.connected_inputs = all(self.plugin.inputs());
+        self.ctx.connected_outputs = all(self.plugin.outputs());
+    }
+
+    /// Drives `ticks` calls to `process`, recording outputs after each.
+    pub fn run(&mut self, ticks: u64) -> Result<&[TickRecord], PluginError> {
+        let start = self.records.len();
+        for _ in 0..ticks {
+            if let Some(set_input) = self.set_input {
+                for (name, value) in &self.inputs {
+                    set_input(&mut self.plugin, name, *value);
+                    if let Some(index) = self.plugin.inputs().iter().position(|p| &p.id.0 == name) {
+                        self.ctx.mark_input_updated(index);
+                    }
+                }
+            }
+            self.plugin.process(&mut self.ctx)?;
+            self.events.append(&mut self.ctx.events);
+            let outputs = match self.get_output {
+                Some(get_output) => self
+                    .plugin
+                    .outputs()
+                    .iter()
+                    .map(|port| (port.id.0.clone(), get_output(&self.plugin, &port.id.0)))
+                    .collect(),
+                None => Vec::new(),
+            };
+            self.records.push(TickRecord {
+                tick: self.ctx.tick,
+                outputs,
+            });
+            self.ctx.missed_ticks = 0;
+            self.ctx.automation.clear();
+            self.advance(1);
+        }
+        Ok(&self.records[start..])
+    }
+
+    /// Feeds recorded inputs, one tick per sample, with gaps filled by the
+    /// inputs' `GapPolicy`. See [`Replay`].
+    pub fn replay<'a>(
+        &mut self,
+        tracks: impl IntoIterator<Item = (&'a str, &'a [Option<f64>])>,
+    ) -> Result<&[TickRecord], PluginError> {
+        let replay = Replay::new(self.plugin.inputs(), tracks)?;
+        let start = self.records.len();
+        for tick in 0..replay.ticks() {
+            for (port, value) in replay.inputs_at(tick) {
+                self.set_input(port, value);
+            }
+            self.run(1)?;
+        }
+        Ok(&self.records[start..])
+    }
+
+    /// Simulates the host falling behind: skips `missed` ticks without
+    /// processing them and calls `Plugin::on_overrun`. The next tick run
+    /// sees `missed_ticks` in its context.
+    pub fn skip_ticks(&mut self, missed: u64) -> Result<(), PluginError> {
+        self.advance(missed);
+        self.ctx.missed_ticks = missed;
+        self.plugin.on_overrun(missed)
+    }
+
+    // Simulated clocks advance by exactly one period per tick.
+    fn advance(&mut self, ticks: u64) {
+        let elapsed = Duration::from_secs_f64(self.ctx.period_seconds.max(0.0) * ticks as f64);
+        self.ctx.tick += ticks;
+        self.ctx.wall_time += elapsed;
+        self.ctx.monotonic_ns += elapsed.as_nanos() as u64;
+    }
+
+    pub fn records(&self) -> &[TickRecord] {
+        &self.records
+    }
+
+    /// Recorded values of output `name`, one per tick.
+    pub fn output_series(&self, name: &str) -> Vec<f64> {
+        self.records
+            .iter()
+            .filter_map(|record| record.output(name))
+            .collect()
+    }
+
+    /// Every event the plugin emitted, in order.
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Delivers `event` to the plugin as the host would between ticks.
+    pub fn send_event(&mut self, event: &Event) -> Result<(), PluginError> {
+        self.plugin.on_event(event)
+    }
+
+    /// Everything the plugin logged through `PluginContext::logger`.
+    pub fn logs(&self) -> Vec<(LogLevel, String)> {
+        self.logs
+            .0
+            .lock()
+            .map(|logs| logs.clone())
+            .unwrap_or_default()
+    }
+
+    /// Metrics the plugin recorded in its context so far.
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.ctx.metrics.snapshot()
+    }
+
+    /// Presses the action button with field key `key` like a host would.
+    pub fn press(&mut self, key: &str) -> Result<(), PluginError> {
+        let schema = self.plugin.ui_schema().unwrap_or_default();
+        let Some(FieldType::Action { action_id, .. }) = schema
+            .action_fields()
+            .into_iter()
+            .find(|field| field.key == key)
+            .map(|field| &field.field_type)
+        else {
+            return Err(PluginError::Config(format!("no action field {key}")));
+        };
+        self.plugin.on_action(action_id)
+    }
+
+    pub fn context_mut(&mut self) -> &mut PluginContext {
+        &mut self.ctx
+    }
+
+    pub fn plugin(&self) -> &P {
+        &self.plugin
+    }
+
+    pub fn plugin_mut(&mut self) -> &mut P {
+        &mut self.plugin
+    }
+
+    pub fn into_inner(self) -> P {
+        self.plugin
+    }
+
+    /// Panics with every problem found by [`check_consistency`].
+    pub fn assert_consistent(&self) {
+        let problems = check_consistency(&self.plugin);
+        assert!(
+            problems.is_empty(),
+            "plugin is inconsistent:\n{}",
+            problems.join("\n")
+        );
+    }
+}
+
+/// Checks that metadata, ports, UI schema and behavior agree with each other.
+pub fn check_consistency(plugin: &dyn Plugin) -> Vec<String> {
+    let mut problems = Vec::new();
+    let meta = plugin.meta();
+    if meta.name.is_empty() {
+        problems.push("meta name is empty".to_string());
+    }
+    if let Some(uid) = meta.uid.as_ref().filter(|uid| !uid.is_valid()) {
+        problems.push(format!("uid {uid} is not a reverse-domain name"));
+    }
+    for (kind, ports) in [("input", plugin.inputs()), ("output", plugin.outputs())] {
+        let mut seen = HashSet::new();
+        for port in ports {
+            if !seen.insert(&port.id.0) {
+                problems.push(format!("duplicate {kind} port {}", port.id.0));
+            }
+            if let Some((min, max)) = port.range.filter(|(min, max)| min > max) {
+                problems.push(format!(
+                    "{kind} port {} has an empty range {min}..={max}",
+                    port.id.0
+                ));
+            }
+            if port.max_connections == Some(0) {
+                problems.push(format!("{kind} port {} accepts no connections", port.id.0));
+            }
+        }
+        for group in ports::bus_names(ports) {
+            let mut seen = HashSet::new();
+            let members = ports::bus_members(ports, group);
+            for index in members.iter().filter_map(|port| port.group_index) {
+                if !seen.insert(index) {
+                    problems.push(format!("{kind} bus {group} repeats index {index}"));
+                }
+            }
+        }
+    }
+
+    if let Some(schema) = plugin.ui_schema() {
+        let mut seen = HashSet::new();
+        for field in schema.value_fields() {
+            if !seen.insert(field.key.as_str()) {
+                problems.push(format!("duplicate config key {}", field.key));
+            }
+        }
+        for field in schema.action_fields() {
+            if let FieldType::Action { action_id, .. } = &field.field_type {
+                if action_id.is_empty() {
+                    problems.push(format!("action field {} has no action id", field.key));
+                }
+            }
+        }
+        let fields = schema.value_fields();
+        for field in &fields {
+            for other in field.rules.iter().filter_map(|rule| rule.other_field()) {
+                if !fields.iter().any(|f| f.key == other) {
+                    problems.push(format!(
+                        "rule of {} refers to unknown field {other}",
+                        field.key
+                    ));
+                }
+            }
+        }
+        for field in schema.computed_fields() {
+            if let FieldType::Computed { source_output } = &field.field_type {
+                if !plugin
+                    .outputs()
+                    .iter()
+                    .any(|port| &port.id.0 == source_output)
+                {
+                    problems.push(format!(
+                        "computed field {} shows unknown output {source_output}",
+                        field.key
+                    ));
+                }
+            }
+        }
+        for error in schema.validate(&Value::Object(schema.defaults())) {
+            if error.message != "missing required key" {
+                problems.push(format!("default of {error}"));
+            }
+        }
+    }
+
+    let behavior = plugin.behavior();
+    if let ExtendableInputs::Auto { pattern, .. } = &behavior.extendable_inputs {
+        if !pattern.contains("{}") {
+            problems.push(format!("auto input pattern {pattern:?} has no {{}}"));
+        }
+    }
+    if let (Some(min), Some(max)) = behavior.extendable_inputs.limits() {
+        if min > max {
+            problems.push(format!("extendable inputs allow {min} to {max} inputs"));
+        }
+    }
+    if let ExtendableOutputs::Auto { pattern } = &behavior.extendable_outputs {
+        if !pattern.contains("{}") {
+            problems.push(format!("auto output pattern {pattern:?} has no {{}}"));
+        }
+    }
+    if behavior.realtime_safe && (behavior.may_block || behavior.allocates_in_process) {
+        problems.push("realtime_safe contradicts may_block/allocates_in_process".to_string());
+    }
+    if behavior.max_shutdown_seconds.is_some_and(|s| s < 0.0) {
+        problems.push("max_shutdown_seconds is negative".to_string());
+    }
+    let mut seen = HashSet::new();
+    for flag in plugin.features() {
+        if !seen.insert(flag.name.clone()) {
+            problems.push(format!("duplicate feature {}", flag.name));
+        }
+    }
+    for template in plugin.templates() {
+        for problem in template.graph.validate() {
+            problems.push(format!("template {}: {problem}", template.name));
+        }
+    }
+    if let Some(schema) = plugin.ui_schema() {
+        for preset in plugin.presets() {
+            for error in preset.validate(&schema) {
+                problems.push(format!("preset {}: {error}", preset.name));
+            }
+        }
+    }
+    if behavior.comparison == ComparisonMode::Shadow && plugin.duplicate().is_none() {
+        problems.push("comparison is shadow but duplicate() returns None".to_string());
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ports::StaticPorts;
+    use crate::ui::{ConfigField, UISchema};
+    use crate::{PluginId, PluginMeta};
+    use serde_json::json;
+
+    struct Gain {
+        meta: PluginMeta,
+        ports: StaticPorts<1, 1>,
+        gain: f64,
+    }
+
+    impl Plugin for Gain {
+        fn id(&self) -> PluginId {
+            PluginId(1)
+        }
+        fn meta(&self) -> &PluginMeta {
+            &self.meta
+        }
+        fn inputs(&self) -> &[Port] {
+            self.ports.inputs()
+        }
+        fn outputs(&self) -> &[Port] {
+            self.ports.outputs()
+        }
+        fn process(&mut self, ctx: &mut PluginContext) -> Result<(), PluginError> {
+            let value = self.ports.input(0) * self.gain;
+            self.ports.set_output(0, value);
+            if value > 2.0 {
+                ctx.logger.warn("clipping", &[("value", value.into())]);
+            }
+            Ok(())
+        }
+        fn ui_schema(&self) -> Option<UISchema> {
+            Some(UISchema::new().field(ConfigField::float("gain", "Gain").min_f(0.0)))
+        }
+        fn on_config_changed(&mut self, changed: &ConfigDelta) -> Result<(), PluginError> {
+            if let Some(gain) = changed.get("gain").and_then(|c| c.new.as_ref()) {
+                self.gain = gain.as_f64().unwrap_or(1.0);
+            }
+            Ok(())
+        }
+    }
+
+    fn host() -> MockHost<Gain> {
+        MockHost::new(Gain {
+            meta: PluginMeta::new("gain"),
+            ports: StaticPorts::new(["in"], ["out"]),
+            gain: 1.0,
+        })
+        .io(
+            |p, name, v| p.ports.set_input_by_name(name, v),
+            |p, name| p.ports.output_by_name(name),
+        )
+    }
+
+    #[test]
+    fn drives_ticks_and_records_outputs() {
+        let mut host = host();
+        host.assert_consistent();
+        host.configure(&json!({ "gain": 3.0 })).unwrap();
+        host.set_input("in", 0.5);
+        host.run(2).unwrap();
+        host.set_input("in", 1.0);
+        let records = host.run(1).unwrap();
+        assert_eq!(records[0].tick, 2);
+
+        assert_eq!(host.output_series("out"), [1.5, 1.5, 3.0]);
+        assert_eq!(host.context_mut().last_update_tick(0), Some(2));
+        assert_eq!(host.context_mut().monotonic_ns, 3_000_000);
+        assert_eq!(
+            host.logs(),
+            [(LogLevel::Warn, "clipping value=3".to_string())]
+        );
+
+        host.skip_ticks(2).unwrap();
+        let records = host.run(1).unwrap();
+        assert_eq!(records[0].tick, 5);
+        assert_eq!(host.context_mut().missed_ticks, 0);
+    }
+
+    #[test]
+    fn replays_sparse_recordings() {
+        let mut host = host();
+        let records = host
+            .replay([("in", &[None, Some(0.5), None, Some(2.0)][..])])
+            .unwrap();
+        assert_eq!(records.len(), 4);
+        assert_eq!(host.output_series("out"), [0.5, 0.5, 0.5, 2.0]);
+        assert!(host.replay([("gain", &[Some(1.0)][..])]).is_err());
+    }
+
+    #[test]
+    fn configure_rejects_invalid_config() {
+        let mut host = host();
+        let err = host.configure(&json!({ "gain": -1.0 })).unwrap_err();
+        assert!(matches!(err, PluginError::Config(_)));
+        assert_eq!(host.plugin().gain, 1.0);
+    }
+
+    #[test]
+    fn press_requires_an_action_field() {
+        let mut host = host();
+        assert!(matches!(host.press("gain"), Err(PluginError::Config(_))));
+    }
+}
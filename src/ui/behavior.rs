@@ -5,7 +5,77 @@ pub struct PluginBehavior {
     pub supports_start_stop: bool,
     pub supports_restart: bool,
     pub extendable_inputs: ExtendableInputs,
+    /// Outputs users may add, e.g. the channels of a demultiplexer.
+    #[serde(default, skip_serializing_if = "ExtendableOutputs::is_none")]
+    pub extendable_outputs: ExtendableOutputs,
     pub loads_started: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_shutdown_seconds: Option<f64>,
+    #[serde(default, skip_serializing_if = "ComparisonMode::is_none")]
+    pub comparison: ComparisonMode,
+    #[serde(default, skip_serializing_if = "ThreadingModel::is_any")]
+    pub threading: ThreadingModel,
+    /// Effects of `process` beyond writing outputs. `None` means undeclared,
+    /// and hosts must treat the plugin as having every side effect; an empty
+    /// list declares it pure.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub side_effects: Option<Vec<SideEffect>>,
+    /// Outputs depend only on the current inputs and configuration, so
+    /// frames may be processed in any order and on several threads.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stateless: bool,
+    /// Ticks by which outputs lag the inputs they were computed from, e.g.
+    /// the group delay of a linear-phase FIR. See `Plugin::latency_ticks`.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub latency_ticks: u64,
+    /// `process` has bounded execution time and may run on the realtime
+    /// thread. Plugins that clear it, or set either flag below, are moved
+    /// to a worker pool by hosts that have one.
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub realtime_safe: bool,
+    /// `process` may wait on locks, IO or devices, e.g. a file logger.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub may_block: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allocates_in_process: bool,
+    /// Miswired port names are errors: hosts use the checked port calls
+    /// and report `PortStatus` failures instead of dropping the value.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strict_ports: bool,
+    /// Ramps parameters passed in `PluginContext::automation`; hosts
+    /// otherwise change them through the configuration.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub supports_automation: bool,
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+impl PluginBehavior {
+    /// Pure plugins may be run in parallel, reordered or replayed offline.
+    pub fn is_pure(&self) -> bool {
+        self.side_effects.as_ref().is_some_and(Vec::is_empty)
+    }
+
+    pub fn has_side_effect(&self, effect: SideEffect) -> bool {
+        self.side_effects
+            .as_ref()
+            .is_none_or(|effects| effects.contains(&effect))
+    }
+
+    /// Whether the host should keep the plugin off the realtime thread.
+    pub fn needs_worker_thread(&self) -> bool {
+        !self.realtime_safe || self.may_block || self.allocates_in_process
+    }
 }
 
 impl Default for PluginBehavior {
@@ -14,30 +84,156 @@ impl Default for PluginBehavior {
             supports_start_stop: true,
             supports_restart: true,
             extendable_inputs: ExtendableInputs::None,
+            extendable_outputs: ExtendableOutputs::None,
             loads_started: true,
+            max_shutdown_seconds: None,
+            comparison: ComparisonMode::None,
+            threading: ThreadingModel::Any,
+            side_effects: None,
+            stateless: false,
+            latency_ticks: 0,
+            realtime_safe: true,
+            may_block: false,
+            allocates_in_process: false,
+            strict_ports: false,
+            supports_automation: false,
         }
     }
 }
 
+/// Whether the host may run an A/B comparison against a shadow copy of the
+/// plugin (see `comparison::AbComparison`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComparisonMode {
+    #[default]
+    None,
+    /// A second instance, e.g. from `Plugin::duplicate`, can run alongside
+    /// the original with a different configuration.
+    Shadow,
+}
+
+impl ComparisonMode {
+    pub fn is_none(&self) -> bool {
+        *self == ComparisonMode::None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SideEffect {
+    Filesystem,
+    Network,
+    /// Talks to devices, e.g. DAQ cards or serial ports.
+    Hardware,
+}
+
+/// Which threads may call into a plugin instance.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThreadingModel {
+    /// Calls may come from any thread, one at a time.
+    #[default]
+    Any,
+    /// Every call must come from the thread that created the instance, e.g.
+    /// because the plugin uses thread-local state or a thread-affine driver.
+    Pinned,
+}
+
+impl ThreadingModel {
+    pub fn is_any(&self) -> bool {
+        *self == ThreadingModel::Any
+    }
+}
+
+/// Whether users may add inputs. `min` and `max` bound the total number of
+/// inputs, including the declared ones, so editors can disable the add and
+/// remove buttons at the limits.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum ExtendableInputs {
     None,
-    Manual,
-    Auto { pattern: String },
+    Manual {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<usize>,
+    },
+    Auto {
+        pattern: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<usize>,
+    },
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct ConnectionBehavior {
-    pub dependent: bool,
+impl ExtendableInputs {
+    /// Manual inputs without limits.
+    pub fn manual() -> Self {
+        ExtendableInputs::Manual {
+            min: None,
+            max: None,
+        }
+    }
+
+    /// Inputs named after `pattern`, with `{}` replaced by their index.
+    pub fn auto(pattern: impl Into<String>) -> Self {
+        ExtendableInputs::Auto {
+            pattern: pattern.into(),
+            min: None,
+            max: None,
+        }
+    }
+
+    /// Lowest and highest number of inputs, if limited.
+    pub fn limits(&self) -> (Option<usize>, Option<usize>) {
+        match self {
+            ExtendableInputs::None => (None, None),
+            ExtendableInputs::Manual { min, max } | ExtendableInputs::Auto { min, max, .. } => {
+                (*min, *max)
+            }
+        }
+    }
+
+    /// Whether an input may be added to a plugin that has `count` inputs.
+    pub fn can_add(&self, count: usize) -> bool {
+        let (_, max) = self.limits();
+        *self != ExtendableInputs::None && max.is_none_or(|max| count < max)
+    }
+
+    /// Whether an input may be removed from a plugin that has `count`
+    /// inputs.
+    pub fn can_remove(&self, count: usize) -> bool {
+        let (min, _) = self.limits();
+        *self != ExtendableInputs::None && count > min.unwrap_or(0)
+    }
 }
 
-impl Default for ConnectionBehavior {
-    fn default() -> Self {
-        Self { dependent: false }
+/// Counterpart of [`ExtendableInputs`] for outputs, announced to the plugin
+/// through `Plugin::on_output_added` and `Plugin::on_output_removed`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ExtendableOutputs {
+    #[default]
+    None,
+    Manual,
+    Auto {
+        pattern: String,
+    },
+}
+
+impl ExtendableOutputs {
+    pub fn is_none(&self) -> bool {
+        *self == ExtendableOutputs::None
     }
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionBehavior {
+    pub dependent: bool,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -49,6 +245,11 @@ mod tests {
         assert!(behavior.supports_restart);
         assert_eq!(behavior.extendable_inputs, ExtendableInputs::None);
         assert!(behavior.loads_started);
+        assert_eq!(behavior.max_shutdown_seconds, None);
+        assert_eq!(behavior.comparison, ComparisonMode::None);
+        assert_eq!(behavior.threading, ThreadingModel::Any);
+        assert!(behavior.realtime_safe);
+        assert!(!behavior.needs_worker_thread());
     }
 
     #[test]
@@ -57,15 +258,20 @@ mod tests {
         let json = serde_json::to_string(&none).unwrap();
         assert_eq!(json, r#"{"type":"none"}"#);
 
-        let manual = ExtendableInputs::Manual;
+        let manual = ExtendableInputs::manual();
         let json = serde_json::to_string(&manual).unwrap();
         assert_eq!(json, r#"{"type":"manual"}"#);
 
-        let auto = ExtendableInputs::Auto {
-            pattern: "in_{}".to_string(),
-        };
+        let auto = ExtendableInputs::auto("in_{}");
         let json = serde_json::to_string(&auto).unwrap();
         assert_eq!(json, r#"{"type":"auto","pattern":"in_{}"}"#);
+
+        let mixer = ExtendableInputs::Manual {
+            min: Some(2),
+            max: Some(16),
+        };
+        let json = serde_json::to_string(&mixer).unwrap();
+        assert_eq!(json, r#"{"type":"manual","min":2,"max":16}"#);
     }
 
     #[test]
@@ -76,14 +282,85 @@ mod tests {
 
         let json = r#"{"type":"auto","pattern":"in_{}"}"#;
         let result: ExtendableInputs = serde_json::from_str(json).unwrap();
+        assert_eq!(result, ExtendableInputs::auto("in_{}"));
+    }
+
+    #[test]
+    fn extendable_input_limits() {
+        let mixer = ExtendableInputs::Manual {
+            min: Some(2),
+            max: Some(16),
+        };
+        assert!(mixer.can_add(15));
+        assert!(!mixer.can_add(16));
+        assert!(mixer.can_remove(3));
+        assert!(!mixer.can_remove(2));
+
+        let open = ExtendableInputs::auto("in_{}");
+        assert!(open.can_add(500));
+        assert!(open.can_remove(1));
+        assert!(!open.can_remove(0));
+        assert!(!ExtendableInputs::None.can_add(0));
+    }
+
+    #[test]
+    fn extendable_outputs_are_omitted_when_fixed() {
+        let behavior = PluginBehavior::default();
+        let json = serde_json::to_value(&behavior).unwrap();
+        assert!(json.get("extendable_outputs").is_none());
+
+        let behavior = PluginBehavior {
+            extendable_outputs: ExtendableOutputs::Auto {
+                pattern: "out_{}".to_string(),
+            },
+            ..PluginBehavior::default()
+        };
+        let json = serde_json::to_value(&behavior).unwrap();
         assert_eq!(
-            result,
-            ExtendableInputs::Auto {
-                pattern: "in_{}".to_string()
-            }
+            json["extendable_outputs"],
+            serde_json::json!({ "type": "auto", "pattern": "out_{}" })
         );
     }
 
+    #[test]
+    fn behavior_without_shutdown_deadline_deserializes() {
+        let json = r#"{"supports_start_stop":true,"supports_restart":true,"extendable_inputs":{"type":"none"},"loads_started":true}"#;
+        let behavior: PluginBehavior = serde_json::from_str(json).unwrap();
+        assert_eq!(behavior, PluginBehavior::default());
+    }
+
+    #[test]
+    fn side_effects_default_to_undeclared() {
+        let mut behavior = PluginBehavior::default();
+        assert!(!behavior.is_pure());
+        assert!(behavior.has_side_effect(SideEffect::Network));
+
+        behavior.side_effects = Some(Vec::new());
+        assert!(behavior.is_pure());
+
+        behavior.side_effects = Some(vec![SideEffect::Hardware]);
+        assert!(behavior.has_side_effect(SideEffect::Hardware));
+        assert!(!behavior.has_side_effect(SideEffect::Network));
+        let json = serde_json::to_value(&behavior).unwrap();
+        assert_eq!(json["side_effects"], serde_json::json!(["hardware"]));
+    }
+
+    #[test]
+    fn blocking_plugins_need_worker_thread() {
+        let behavior = PluginBehavior {
+            may_block: true,
+            ..PluginBehavior::default()
+        };
+        assert!(behavior.needs_worker_thread());
+        let json = serde_json::to_value(&behavior).unwrap();
+        assert_eq!(json["may_block"], true);
+        assert!(json.get("realtime_safe").is_none());
+
+        let json = r#"{"supports_start_stop":true,"supports_restart":true,"extendable_inputs":{"type":"none"},"loads_started":true,"realtime_safe":false}"#;
+        let behavior: PluginBehavior = serde_json::from_str(json).unwrap();
+        assert!(behavior.needs_worker_thread());
+    }
+
     #[test]
     fn connection_behavior_default() {
         let behavior = ConnectionBehavior::default();
@@ -97,8 +374,22 @@ mod tests {
             supports_restart: true,
             extendable_inputs: ExtendableInputs::Auto {
                 pattern: "input_{}".to_string(),
+                min: Some(1),
+                max: Some(8),
             },
+            extendable_outputs: ExtendableOutputs::Manual,
             loads_started: false,
+            max_shutdown_seconds: Some(5.0),
+            comparison: ComparisonMode::Shadow,
+            threading: ThreadingModel::Pinned,
+            side_effects: Some(vec![SideEffect::Filesystem]),
+            stateless: true,
+            latency_ticks: 64,
+            realtime_safe: false,
+            may_block: true,
+            allocates_in_process: true,
+            strict_ports: true,
+            supports_automation: true,
         };
 
         let json = serde_json::to_string(&behavior).unwrap();
@@ -1,4 +1,11 @@
-use crate::ui::{ConfigField, ExtendableInputs, FileMode, PluginBehavior, UISchema};
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+use super::status::{guard, guard_status, RtsynStatus};
+use crate::ui::{
+    ChoiceOption, ConfigField, ExtendableInputs, FieldType, FileMode, OptionsSource,
+    PluginBehavior, SliderScale, UISchema, WidgetHint,
+};
+use serde::Serialize;
 use serde_json::Value;
 use std::ffi::{CStr, CString};
 use std::os::raw::{c_char, c_int};
@@ -27,54 +34,95 @@ pub const RTSYN_FIELD_TEXT: c_int = 2;
 pub const RTSYN_FIELD_BOOLEAN: c_int = 3;
 pub const RTSYN_FIELD_FILEPATH: c_int = 4;
 pub const RTSYN_FIELD_DYNAMIC_LIST: c_int = 5;
+pub const RTSYN_FIELD_CHOICE: c_int = 6;
+pub const RTSYN_FIELD_GROUP: c_int = 7;
+
+// Widget hint enum for C
+pub const RTSYN_WIDGET_SLIDER: c_int = 0;
+pub const RTSYN_WIDGET_LOG_SLIDER: c_int = 1;
+pub const RTSYN_WIDGET_KNOB: c_int = 2;
+pub const RTSYN_WIDGET_LOG_KNOB: c_int = 3;
+pub const RTSYN_WIDGET_STEPPER: c_int = 4;
+
+/// Borrows a required C string.
+unsafe fn required_str<'a>(ptr: *const c_char) -> Result<&'a str, RtsynStatus> {
+    if ptr.is_null() {
+        return Err(RtsynStatus::InvalidArgument);
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|_| RtsynStatus::Utf8Error)
+}
+
+/// Borrows a C string that may be null.
+unsafe fn optional_str<'a>(ptr: *const c_char) -> Result<Option<&'a str>, RtsynStatus> {
+    if ptr.is_null() {
+        Ok(None)
+    } else {
+        required_str(ptr).map(Some)
+    }
+}
+
+fn json_string(value: &impl Serialize) -> Result<*mut c_char, RtsynStatus> {
+    let json = serde_json::to_string(value).map_err(|_| RtsynStatus::SerializationError)?;
+    CString::new(json)
+        .map(CString::into_raw)
+        .map_err(|_| RtsynStatus::SerializationError)
+}
+
+fn into_field(field: ConfigField) -> *mut RTSynConfigField {
+    Box::into_raw(Box::new(field)) as *mut RTSynConfigField
+}
 
 // === UI Schema Functions ===
 
 #[no_mangle]
 pub extern "C" fn rtsyn_ui_schema_new() -> *mut RTSynUISchema {
-    let schema = Box::new(UISchema::new());
-    Box::into_raw(schema) as *mut RTSynUISchema
+    guard(ptr::null_mut(), || {
+        Ok(Box::into_raw(Box::new(UISchema::new())) as *mut RTSynUISchema)
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn rtsyn_ui_schema_free(schema: *mut RTSynUISchema) {
-    if !schema.is_null() {
-        unsafe {
-            let _ = Box::from_raw(schema as *mut UISchema);
+    guard((), || {
+        if !schema.is_null() {
+            unsafe {
+                let _ = Box::from_raw(schema as *mut UISchema);
+            }
         }
-    }
+        Ok(())
+    })
 }
 
+/// Consumes `field`, also when the call fails.
 #[no_mangle]
 pub extern "C" fn rtsyn_ui_schema_add_field(
     schema: *mut RTSynUISchema,
     field: *mut RTSynConfigField,
-) {
-    if schema.is_null() || field.is_null() {
-        return;
-    }
-    unsafe {
-        let schema = &mut *(schema as *mut UISchema);
-        let field = Box::from_raw(field as *mut ConfigField);
+) -> i32 {
+    guard_status(|| {
+        if field.is_null() {
+            return Err(RtsynStatus::InvalidArgument);
+        }
+        let field = unsafe { Box::from_raw(field as *mut ConfigField) };
+        if schema.is_null() {
+            return Err(RtsynStatus::InvalidArgument);
+        }
+        let schema = unsafe { &mut *(schema as *mut UISchema) };
         schema.fields.push(*field);
-    }
+        Ok(())
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn rtsyn_ui_schema_to_json(schema: *const RTSynUISchema) -> *mut c_char {
-    if schema.is_null() {
-        return ptr::null_mut();
-    }
-    unsafe {
-        let schema = &*(schema as *const UISchema);
-        match serde_json::to_string(schema) {
-            Ok(json) => match CString::new(json) {
-                Ok(cstr) => cstr.into_raw(),
-                Err(_) => ptr::null_mut(),
-            },
-            Err(_) => ptr::null_mut(),
+    guard(ptr::null_mut(), || {
+        if schema.is_null() {
+            return Err(RtsynStatus::InvalidArgument);
         }
-    }
+        json_string(unsafe { &*(schema as *const UISchema) })
+    })
 }
 
 // === Config Field Functions ===
@@ -85,29 +133,79 @@ pub extern "C" fn rtsyn_ui_field_text(
     label: *const c_char,
     default_value: *const c_char,
 ) -> *mut RTSynConfigField {
-    if key.is_null() || label.is_null() {
-        return ptr::null_mut();
-    }
-    unsafe {
-        let key = match CStr::from_ptr(key).to_str() {
-            Ok(s) => s,
-            Err(_) => return ptr::null_mut(),
-        };
-        let label = match CStr::from_ptr(label).to_str() {
-            Ok(s) => s,
-            Err(_) => return ptr::null_mut(),
-        };
-        
-        let mut field = ConfigField::text(key, label);
-        
-        if !default_value.is_null() {
-            if let Ok(s) = CStr::from_ptr(default_value).to_str() {
-                field = field.default_value(Value::String(s.to_string()));
-            }
+    guard(ptr::null_mut(), || unsafe {
+        let mut field = ConfigField::text(required_str(key)?, required_str(label)?);
+        if let Some(default_value) = optional_str(default_value)? {
+            field = field.default_value(Value::String(default_value.to_string()));
         }
-        
-        Box::into_raw(Box::new(field)) as *mut RTSynConfigField
-    }
+        Ok(into_field(field))
+    })
+}
+
+/// Masked in host UIs and redacted from logs; secrets take no default.
+#[no_mangle]
+pub extern "C" fn rtsyn_ui_field_secret(
+    key: *const c_char,
+    label: *const c_char,
+) -> *mut RTSynConfigField {
+    guard(ptr::null_mut(), || unsafe {
+        let field = ConfigField::secret(required_str(key)?, required_str(label)?);
+        Ok(into_field(field))
+    })
+}
+
+/// `label` may be null for a block without a heading.
+#[no_mangle]
+pub extern "C" fn rtsyn_ui_field_info(
+    key: *const c_char,
+    label: *const c_char,
+    markdown: *const c_char,
+) -> *mut RTSynConfigField {
+    guard(ptr::null_mut(), || unsafe {
+        let field = ConfigField::info(
+            required_str(key)?,
+            optional_str(label)?.unwrap_or_default(),
+            required_str(markdown)?,
+        );
+        Ok(into_field(field))
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn rtsyn_ui_field_computed(
+    key: *const c_char,
+    label: *const c_char,
+    source_output: *const c_char,
+) -> *mut RTSynConfigField {
+    guard(ptr::null_mut(), || unsafe {
+        let field = ConfigField::computed(
+            required_str(key)?,
+            required_str(label)?,
+            required_str(source_output)?,
+        );
+        Ok(into_field(field))
+    })
+}
+
+/// `confirm` may be null for actions that need no confirmation.
+#[no_mangle]
+pub extern "C" fn rtsyn_ui_field_action(
+    key: *const c_char,
+    label: *const c_char,
+    action_id: *const c_char,
+    confirm: *const c_char,
+) -> *mut RTSynConfigField {
+    guard(ptr::null_mut(), || unsafe {
+        let mut field = ConfigField::action(
+            required_str(key)?,
+            required_str(label)?,
+            required_str(action_id)?,
+        );
+        if let Some(confirm) = optional_str(confirm)? {
+            field = field.confirm(confirm);
+        }
+        Ok(into_field(field))
+    })
 }
 
 #[no_mangle]
@@ -118,26 +216,13 @@ pub extern "C" fn rtsyn_ui_field_integer(
     min: i64,
     max: i64,
 ) -> *mut RTSynConfigField {
-    if key.is_null() || label.is_null() {
-        return ptr::null_mut();
-    }
-    unsafe {
-        let key = match CStr::from_ptr(key).to_str() {
-            Ok(s) => s,
-            Err(_) => return ptr::null_mut(),
-        };
-        let label = match CStr::from_ptr(label).to_str() {
-            Ok(s) => s,
-            Err(_) => return ptr::null_mut(),
-        };
-        
-        let field = ConfigField::integer(key, label)
+    guard(ptr::null_mut(), || unsafe {
+        let field = ConfigField::integer(required_str(key)?, required_str(label)?)
             .min(min)
             .max(max)
             .default_value(Value::from(default_value));
-        
-        Box::into_raw(Box::new(field)) as *mut RTSynConfigField
-    }
+        Ok(into_field(field))
+    })
 }
 
 #[no_mangle]
@@ -148,26 +233,92 @@ pub extern "C" fn rtsyn_ui_field_float(
     min: f64,
     max: f64,
 ) -> *mut RTSynConfigField {
-    if key.is_null() || label.is_null() {
-        return ptr::null_mut();
-    }
-    unsafe {
-        let key = match CStr::from_ptr(key).to_str() {
-            Ok(s) => s,
-            Err(_) => return ptr::null_mut(),
-        };
-        let label = match CStr::from_ptr(label).to_str() {
-            Ok(s) => s,
-            Err(_) => return ptr::null_mut(),
-        };
-        
-        let field = ConfigField::float(key, label)
+    guard(ptr::null_mut(), || unsafe {
+        let field = ConfigField::float(required_str(key)?, required_str(label)?)
             .min_f(min)
             .max_f(max)
             .default_value(Value::from(default_value));
-        
-        Box::into_raw(Box::new(field)) as *mut RTSynConfigField
-    }
+        Ok(into_field(field))
+    })
+}
+
+/// `RTSYN_STATUS_INVALID_ARGUMENT` when `field` is not numeric or `widget`
+/// is not a `RTSYN_WIDGET_*` constant.
+#[no_mangle]
+pub extern "C" fn rtsyn_ui_field_set_widget(field: *mut RTSynConfigField, widget: c_int) -> i32 {
+    guard_status(|| unsafe {
+        let widget = match widget {
+            RTSYN_WIDGET_SLIDER => WidgetHint::slider(),
+            RTSYN_WIDGET_LOG_SLIDER => WidgetHint::log_slider(),
+            RTSYN_WIDGET_KNOB => WidgetHint::Knob {
+                scale: SliderScale::Linear,
+            },
+            RTSYN_WIDGET_LOG_KNOB => WidgetHint::Knob {
+                scale: SliderScale::Log,
+            },
+            RTSYN_WIDGET_STEPPER => WidgetHint::Stepper,
+            _ => return Err(RtsynStatus::InvalidArgument),
+        };
+        if field.is_null() {
+            return Err(RtsynStatus::InvalidArgument);
+        }
+        let field = &mut *(field as *mut ConfigField);
+        if !matches!(
+            field.field_type,
+            FieldType::Integer { .. } | FieldType::Float { .. }
+        ) {
+            return Err(RtsynStatus::InvalidArgument);
+        }
+        field.widget = Some(widget);
+        Ok(())
+    })
+}
+
+/// `unit` may be null to clear it; a negative `display_precision` leaves it
+/// to the host and is ignored for integer fields.
+/// `RTSYN_STATUS_INVALID_ARGUMENT` when `field` is not numeric.
+#[no_mangle]
+pub extern "C" fn rtsyn_ui_field_set_unit(
+    field: *mut RTSynConfigField,
+    unit: *const c_char,
+    display_precision: c_int,
+) -> i32 {
+    guard_status(|| unsafe {
+        let unit = optional_str(unit)?.map(str::to_string);
+        if field.is_null() {
+            return Err(RtsynStatus::InvalidArgument);
+        }
+        let field = &mut *(field as *mut ConfigField);
+        match field.field_type {
+            FieldType::Integer {
+                unit: ref mut u, ..
+            } => *u = unit,
+            FieldType::Float {
+                unit: ref mut u,
+                display_precision: ref mut p,
+                ..
+            } => {
+                *u = unit;
+                *p = u32::try_from(display_precision).ok();
+            }
+            _ => return Err(RtsynStatus::InvalidArgument),
+        }
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn rtsyn_ui_field_set_read_only(
+    field: *mut RTSynConfigField,
+    read_only: c_int,
+) -> i32 {
+    guard_status(|| unsafe {
+        if field.is_null() {
+            return Err(RtsynStatus::InvalidArgument);
+        }
+        (*(field as *mut ConfigField)).read_only = read_only != 0;
+        Ok(())
+    })
 }
 
 #[no_mangle]
@@ -176,24 +327,11 @@ pub extern "C" fn rtsyn_ui_field_boolean(
     label: *const c_char,
     default_value: c_int,
 ) -> *mut RTSynConfigField {
-    if key.is_null() || label.is_null() {
-        return ptr::null_mut();
-    }
-    unsafe {
-        let key = match CStr::from_ptr(key).to_str() {
-            Ok(s) => s,
-            Err(_) => return ptr::null_mut(),
-        };
-        let label = match CStr::from_ptr(label).to_str() {
-            Ok(s) => s,
-            Err(_) => return ptr::null_mut(),
-        };
-        
-        let field = ConfigField::boolean(key, label)
+    guard(ptr::null_mut(), || unsafe {
+        let field = ConfigField::boolean(required_str(key)?, required_str(label)?)
             .default_value(Value::Bool(default_value != 0));
-        
-        Box::into_raw(Box::new(field)) as *mut RTSynConfigField
-    }
+        Ok(into_field(field))
+    })
 }
 
 #[no_mangle]
@@ -203,45 +341,131 @@ pub extern "C" fn rtsyn_ui_field_filepath(
     default_path: *const c_char,
     mode: c_int,
 ) -> *mut RTSynConfigField {
-    if key.is_null() || label.is_null() {
-        return ptr::null_mut();
-    }
-    unsafe {
-        let key = match CStr::from_ptr(key).to_str() {
-            Ok(s) => s,
-            Err(_) => return ptr::null_mut(),
-        };
-        let label = match CStr::from_ptr(label).to_str() {
-            Ok(s) => s,
-            Err(_) => return ptr::null_mut(),
-        };
-        
+    guard(ptr::null_mut(), || unsafe {
         let file_mode = match mode {
             RTSYN_FILE_MODE_OPEN => FileMode::OpenFile,
             RTSYN_FILE_MODE_SAVE => FileMode::SaveFile,
             RTSYN_FILE_MODE_FOLDER => FileMode::SelectFolder,
             _ => FileMode::OpenFile,
         };
-        
-        let mut field = ConfigField::filepath(key, label).mode(file_mode);
-        
-        if !default_path.is_null() {
-            if let Ok(s) = CStr::from_ptr(default_path).to_str() {
-                field = field.default_value(Value::String(s.to_string()));
-            }
+        let mut field =
+            ConfigField::filepath(required_str(key)?, required_str(label)?).mode(file_mode);
+        if let Some(default_path) = optional_str(default_path)? {
+            field = field.default_value(Value::String(default_path.to_string()));
         }
-        
-        Box::into_raw(Box::new(field)) as *mut RTSynConfigField
-    }
+        Ok(into_field(field))
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn rtsyn_ui_field_choice(
+    key: *const c_char,
+    label: *const c_char,
+    default_value: *const c_char,
+) -> *mut RTSynConfigField {
+    guard(ptr::null_mut(), || unsafe {
+        let mut field = ConfigField::choice(required_str(key)?, required_str(label)?);
+        if let Some(default_value) = optional_str(default_value)? {
+            field = field.default_value(Value::String(default_value.to_string()));
+        }
+        Ok(into_field(field))
+    })
+}
+
+/// `RTSYN_STATUS_INVALID_ARGUMENT` when `field` is not a choice field.
+#[no_mangle]
+pub extern "C" fn rtsyn_ui_field_choice_add_option(
+    field: *mut RTSynConfigField,
+    value: *const c_char,
+    label: *const c_char,
+) -> i32 {
+    guard_status(|| unsafe {
+        let value = required_str(value)?;
+        let label = optional_str(label)?.unwrap_or(value);
+        if field.is_null() {
+            return Err(RtsynStatus::InvalidArgument);
+        }
+        let field = &mut *(field as *mut ConfigField);
+        let FieldType::Choice {
+            ref mut options, ..
+        } = field.field_type
+        else {
+            return Err(RtsynStatus::InvalidArgument);
+        };
+        options.push(ChoiceOption::new(value, label));
+        Ok(())
+    })
+}
+
+/// `RTSYN_STATUS_INVALID_ARGUMENT` when `field` is not a choice field.
+#[no_mangle]
+pub extern "C" fn rtsyn_ui_field_choice_options_from_plugin(field: *mut RTSynConfigField) -> i32 {
+    guard_status(|| unsafe {
+        if field.is_null() {
+            return Err(RtsynStatus::InvalidArgument);
+        }
+        let field = &mut *(field as *mut ConfigField);
+        let FieldType::Choice {
+            ref mut options_source,
+            ..
+        } = field.field_type
+        else {
+            return Err(RtsynStatus::InvalidArgument);
+        };
+        *options_source = OptionsSource::Plugin;
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn rtsyn_ui_field_group(
+    key: *const c_char,
+    label: *const c_char,
+    collapsed: c_int,
+) -> *mut RTSynConfigField {
+    guard(ptr::null_mut(), || unsafe {
+        let mut field = ConfigField::group(required_str(key)?, required_str(label)?);
+        if collapsed != 0 {
+            field = field.collapsed();
+        }
+        Ok(into_field(field))
+    })
+}
+
+/// Consumes `field`, also when the call fails. `RTSYN_STATUS_INVALID_ARGUMENT`
+/// when `group` is not a group field.
+#[no_mangle]
+pub extern "C" fn rtsyn_ui_group_add_field(
+    group: *mut RTSynConfigField,
+    field: *mut RTSynConfigField,
+) -> i32 {
+    guard_status(|| unsafe {
+        if field.is_null() {
+            return Err(RtsynStatus::InvalidArgument);
+        }
+        let field = Box::from_raw(field as *mut ConfigField);
+        if group.is_null() {
+            return Err(RtsynStatus::InvalidArgument);
+        }
+        let group = &mut *(group as *mut ConfigField);
+        let FieldType::Group { ref mut fields, .. } = group.field_type else {
+            return Err(RtsynStatus::InvalidArgument);
+        };
+        fields.push(*field);
+        Ok(())
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn rtsyn_ui_field_free(field: *mut RTSynConfigField) {
-    if !field.is_null() {
-        unsafe {
-            let _ = Box::from_raw(field as *mut ConfigField);
+    guard((), || {
+        if !field.is_null() {
+            unsafe {
+                let _ = Box::from_raw(field as *mut ConfigField);
+            }
         }
-    }
+        Ok(())
+    })
 }
 
 // === Behavior Functions ===
@@ -255,62 +479,49 @@ pub extern "C" fn rtsyn_behavior_to_json(
     loads_started: c_int,
     connection_dependent: c_int,
 ) -> *mut c_char {
-    let extendable_inputs = match extendable_inputs_type {
-        0 => ExtendableInputs::None,
-        1 => ExtendableInputs::Manual,
-        2 => {
-            if extendable_inputs_pattern.is_null() {
-                ExtendableInputs::Auto {
-                    pattern: "in_{}".to_string(),
-                }
-            } else {
-                unsafe {
-                    let pattern = match CStr::from_ptr(extendable_inputs_pattern).to_str() {
-                        Ok(s) => s.to_string(),
-                        Err(_) => "in_{}".to_string(),
-                    };
-                    ExtendableInputs::Auto { pattern }
-                }
-            }
-        }
-        _ => ExtendableInputs::None,
-    };
-
-    let behavior = PluginBehavior {
-        supports_start_stop: supports_start_stop != 0,
-        supports_restart: supports_restart != 0,
-        extendable_inputs,
-        loads_started: loads_started != 0,
-    };
-
-    let combined = serde_json::json!({
-        "behavior": behavior,
-        "connection_dependent": connection_dependent != 0,
-    });
-
-    match serde_json::to_string(&combined) {
-        Ok(json) => match CString::new(json) {
-            Ok(cstr) => cstr.into_raw(),
-            Err(_) => ptr::null_mut(),
-        },
-        Err(_) => ptr::null_mut(),
-    }
+    guard(ptr::null_mut(), || {
+        let extendable_inputs = match extendable_inputs_type {
+            0 => ExtendableInputs::None,
+            1 => ExtendableInputs::manual(),
+            2 => ExtendableInputs::auto(
+                unsafe { optional_str(extendable_inputs_pattern)? }.unwrap_or("in_{}"),
+            ),
+            _ => ExtendableInputs::None,
+        };
+
+        let behavior = PluginBehavior {
+            supports_start_stop: supports_start_stop != 0,
+            supports_restart: supports_restart != 0,
+            extendable_inputs,
+            loads_started: loads_started != 0,
+            ..PluginBehavior::default()
+        };
+
+        json_string(&serde_json::json!({
+            "behavior": behavior,
+            "connection_dependent": connection_dependent != 0,
+        }))
+    })
 }
 
 // === String Management ===
 
 #[no_mangle]
 pub extern "C" fn rtsyn_string_free(s: *mut c_char) {
-    if !s.is_null() {
-        unsafe {
-            let _ = CString::from_raw(s);
+    guard((), || {
+        if !s.is_null() {
+            unsafe {
+                let _ = CString::from_raw(s);
+            }
         }
-    }
+        Ok(())
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ui::ffi::status::*;
 
     #[test]
     fn test_ui_schema_lifecycle() {
@@ -340,25 +551,97 @@ mod tests {
         rtsyn_ui_field_free(field);
     }
 
+    #[test]
+    fn test_field_choice() {
+        let key = CString::new("mode").unwrap();
+        let label = CString::new("Mode").unwrap();
+        let value = CString::new("lp").unwrap();
+        let option_label = CString::new("Low-pass").unwrap();
+
+        let field = rtsyn_ui_field_choice(key.as_ptr(), label.as_ptr(), value.as_ptr());
+        assert!(!field.is_null());
+        rtsyn_ui_field_choice_add_option(field, value.as_ptr(), option_label.as_ptr());
+        rtsyn_ui_field_choice_add_option(field, key.as_ptr(), ptr::null());
+
+        unsafe {
+            let field = &*(field as *const ConfigField);
+            if let FieldType::Choice { options, .. } = &field.field_type {
+                assert_eq!(options[0], ChoiceOption::new("lp", "Low-pass"));
+                assert_eq!(options[1], ChoiceOption::new("mode", "mode"));
+            } else {
+                panic!("Expected Choice field type");
+            }
+        }
+        rtsyn_ui_field_free(field);
+    }
+
+    #[test]
+    fn test_field_group() {
+        let key = CString::new("advanced").unwrap();
+        let label = CString::new("Advanced").unwrap();
+        let child_key = CString::new("retries").unwrap();
+
+        let group = rtsyn_ui_field_group(key.as_ptr(), label.as_ptr(), 1);
+        assert!(!group.is_null());
+        let child = rtsyn_ui_field_integer(child_key.as_ptr(), child_key.as_ptr(), 3, 0, 10);
+        rtsyn_ui_group_add_field(group, child);
+
+        unsafe {
+            let group = &*(group as *const ConfigField);
+            if let FieldType::Group {
+                collapsed, fields, ..
+            } = &group.field_type
+            {
+                assert!(*collapsed);
+                assert_eq!(fields[0].key, "retries");
+            } else {
+                panic!("Expected Group field type");
+            }
+        }
+        rtsyn_ui_field_free(group);
+    }
+
+    #[test]
+    fn test_statuses() {
+        let key = CString::new("mode").unwrap();
+        assert!(rtsyn_ui_field_text(ptr::null(), key.as_ptr(), ptr::null()).is_null());
+        assert_eq!(rtsyn_last_status(), RTSYN_STATUS_INVALID_ARGUMENT);
+
+        let invalid = [0xffu8, 0];
+        let field = rtsyn_ui_field_text(key.as_ptr(), invalid.as_ptr().cast(), ptr::null());
+        assert!(field.is_null());
+        assert_eq!(rtsyn_last_status(), RTSYN_STATUS_UTF8_ERROR);
+
+        let field = rtsyn_ui_field_boolean(key.as_ptr(), key.as_ptr(), 1);
+        assert_eq!(rtsyn_last_status(), RTSYN_STATUS_OK);
+        assert_eq!(
+            rtsyn_ui_field_choice_add_option(field, key.as_ptr(), ptr::null()),
+            RTSYN_STATUS_INVALID_ARGUMENT
+        );
+        let schema = rtsyn_ui_schema_new();
+        assert_eq!(rtsyn_ui_schema_add_field(schema, field), RTSYN_STATUS_OK);
+        rtsyn_ui_schema_free(schema);
+    }
+
     #[test]
     fn test_schema_to_json() {
         let schema = rtsyn_ui_schema_new();
-        
+
         let key = CString::new("name").unwrap();
         let label = CString::new("Name").unwrap();
         let field = rtsyn_ui_field_text(key.as_ptr(), label.as_ptr(), ptr::null());
-        
+
         rtsyn_ui_schema_add_field(schema, field);
-        
+
         let json = rtsyn_ui_schema_to_json(schema);
         assert!(!json.is_null());
-        
+
         unsafe {
             let json_str = CStr::from_ptr(json).to_str().unwrap();
             assert!(json_str.contains("name"));
             assert!(json_str.contains("Name"));
         }
-        
+
         rtsyn_string_free(json);
         rtsyn_ui_schema_free(schema);
     }
@@ -368,13 +651,13 @@ mod tests {
         let pattern = CString::new("in_{}").unwrap();
         let json = rtsyn_behavior_to_json(1, 0, 2, pattern.as_ptr(), 0, 1);
         assert!(!json.is_null());
-        
+
         unsafe {
             let json_str = CStr::from_ptr(json).to_str().unwrap();
             assert!(json_str.contains("behavior"));
             assert!(json_str.contains("connection_dependent"));
         }
-        
+
         rtsyn_string_free(json);
     }
 }
@@ -1,3 +1,5 @@
 pub mod c_api;
+pub mod status;
 
 pub use c_api::*;
+pub use status::*;
@@ -0,0 +1,270 @@
+//! Status codes shared by every `rtsyn_*` entry point and the fallible
+//! entries of [`PluginApi`](crate::PluginApi), mirrored as the
+//! `RTSYN_STATUS_*` constants of `include/rtsyn_plugin_ui.h`.
+//!
+//! Functions without a result return the code directly. Functions returning
+//! a pointer return null on failure and leave the code for
+//! [`rtsyn_last_status`], which every call on the thread overwrites.
+//!
+//! A plugin error code only carries the error kind. Plugins that export
+//! `last_error_json` hand the message and device code over as
+//! [`ErrorDetails`].
+
+use crate::PluginError;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+
+pub const RTSYN_STATUS_OK: i32 = 0;
+pub const RTSYN_STATUS_INVALID_ARGUMENT: i32 = 1;
+pub const RTSYN_STATUS_UTF8_ERROR: i32 = 2;
+pub const RTSYN_STATUS_SERIALIZATION_ERROR: i32 = 3;
+pub const RTSYN_STATUS_PANIC: i32 = 4;
+pub const RTSYN_STATUS_UNKNOWN_PORT: i32 = 5;
+pub const RTSYN_STATUS_WRONG_DIRECTION: i32 = 6;
+/// Plugin errors are reported as this base plus a `RTSYN_PLUGIN_ERROR_*`
+/// kind.
+pub const RTSYN_STATUS_PLUGIN_ERROR: i32 = 100;
+
+pub const RTSYN_PLUGIN_ERROR_CONFIG: i32 = 0;
+pub const RTSYN_PLUGIN_ERROR_IO: i32 = 1;
+pub const RTSYN_PLUGIN_ERROR_DEVICE: i32 = 2;
+pub const RTSYN_PLUGIN_ERROR_FATAL: i32 = 3;
+pub const RTSYN_PLUGIN_ERROR_RECOVERABLE: i32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RtsynStatus {
+    Ok,
+    /// A required pointer was null or a value was out of range.
+    InvalidArgument,
+    Utf8Error,
+    SerializationError,
+    /// A `RTSYN_PLUGIN_ERROR_*` kind.
+    PluginError(i32),
+    /// The call panicked; the panic did not cross the FFI boundary.
+    Panic,
+    /// See [`PortStatus::UnknownPort`](crate::PortStatus::UnknownPort).
+    UnknownPort,
+    /// See [`PortStatus::WrongDirection`](crate::PortStatus::WrongDirection).
+    WrongDirection,
+}
+
+impl RtsynStatus {
+    pub fn code(self) -> i32 {
+        match self {
+            RtsynStatus::Ok => RTSYN_STATUS_OK,
+            RtsynStatus::InvalidArgument => RTSYN_STATUS_INVALID_ARGUMENT,
+            RtsynStatus::Utf8Error => RTSYN_STATUS_UTF8_ERROR,
+            RtsynStatus::SerializationError => RTSYN_STATUS_SERIALIZATION_ERROR,
+            RtsynStatus::Panic => RTSYN_STATUS_PANIC,
+            RtsynStatus::UnknownPort => RTSYN_STATUS_UNKNOWN_PORT,
+            RtsynStatus::WrongDirection => RTSYN_STATUS_WRONG_DIRECTION,
+            RtsynStatus::PluginError(kind) => RTSYN_STATUS_PLUGIN_ERROR + kind,
+        }
+    }
+
+    /// Codes this version does not know are reported as `InvalidArgument`.
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            RTSYN_STATUS_OK => RtsynStatus::Ok,
+            RTSYN_STATUS_UTF8_ERROR => RtsynStatus::Utf8Error,
+            RTSYN_STATUS_SERIALIZATION_ERROR => RtsynStatus::SerializationError,
+            RTSYN_STATUS_PANIC => RtsynStatus::Panic,
+            RTSYN_STATUS_UNKNOWN_PORT => RtsynStatus::UnknownPort,
+            RTSYN_STATUS_WRONG_DIRECTION => RtsynStatus::WrongDirection,
+            code if code >= RTSYN_STATUS_PLUGIN_ERROR => {
+                RtsynStatus::PluginError(code - RTSYN_STATUS_PLUGIN_ERROR)
+            }
+            _ => RtsynStatus::InvalidArgument,
+        }
+    }
+
+    pub fn is_ok(self) -> bool {
+        self == RtsynStatus::Ok
+    }
+
+    /// `Ok` for `RtsynStatus::Ok`, otherwise the matching [`PluginError`]
+    /// with `context` as its message; for the port codes `context` is the
+    /// port name. Plugin error kinds this version does not know become
+    /// `PluginError::Recoverable`.
+    pub fn into_result(self, context: &str) -> Result<(), PluginError> {
+        Err(match self {
+            RtsynStatus::Ok => return Ok(()),
+            RtsynStatus::InvalidArgument => {
+                PluginError::Config(format!("{context}: invalid argument"))
+            }
+            RtsynStatus::Utf8Error => PluginError::Config(format!("{context}: invalid UTF-8")),
+            RtsynStatus::SerializationError => {
+                PluginError::Recoverable(format!("{context}: serialization failed"))
+            }
+            RtsynStatus::Panic => PluginError::Fatal(format!("{context}: panicked")),
+            RtsynStatus::UnknownPort => PluginError::Config(format!("unknown port {context}")),
+            RtsynStatus::WrongDirection => {
+                PluginError::Config(format!("port {context} is used in the wrong direction"))
+            }
+            RtsynStatus::PluginError(kind) => ErrorDetails::new(context).into_error(kind),
+        })
+    }
+
+    /// Like [`RtsynStatus::into_result`], but plugin errors are rebuilt
+    /// from the plugin's own `details` when it provided them.
+    pub fn into_result_with(
+        self,
+        context: &str,
+        details: Option<ErrorDetails>,
+    ) -> Result<(), PluginError> {
+        match (self, details) {
+            (RtsynStatus::PluginError(kind), Some(details)) => Err(details.into_error(kind)),
+            (status, _) => status.into_result(context),
+        }
+    }
+}
+
+/// Message and device code of the last failed call of a plugin instance,
+/// returned as JSON by the `last_error_json` entry of
+/// [`PluginApi`](crate::PluginApi). The error kind is in the status code.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorDetails {
+    pub message: String,
+    /// Code of a `PluginError::Device`, `0` otherwise.
+    #[serde(default)]
+    pub code: i32,
+}
+
+impl ErrorDetails {
+    pub fn new(message: impl Into<String>) -> Self {
+        ErrorDetails {
+            message: message.into(),
+            code: 0,
+        }
+    }
+
+    /// The [`PluginError`] of the `RTSYN_PLUGIN_ERROR_*` `kind`.
+    pub fn into_error(self, kind: i32) -> PluginError {
+        let ErrorDetails { message, code } = self;
+        match kind {
+            RTSYN_PLUGIN_ERROR_CONFIG => PluginError::Config(message),
+            RTSYN_PLUGIN_ERROR_IO => PluginError::Io(std::io::Error::other(message)),
+            RTSYN_PLUGIN_ERROR_DEVICE => PluginError::Device { code, message },
+            RTSYN_PLUGIN_ERROR_FATAL => PluginError::Fatal(message),
+            _ => PluginError::Recoverable(message),
+        }
+    }
+}
+
+impl From<&PluginError> for ErrorDetails {
+    fn from(error: &PluginError) -> Self {
+        match error {
+            PluginError::Config(message)
+            | PluginError::Fatal(message)
+            | PluginError::Recoverable(message) => ErrorDetails::new(message.as_str()),
+            PluginError::Io(err) => ErrorDetails::new(err.to_string()),
+            PluginError::Device { code, message } => ErrorDetails {
+                message: message.clone(),
+                code: *code,
+            },
+        }
+    }
+}
+
+impl From<&PluginError> for RtsynStatus {
+    fn from(error: &PluginError) -> Self {
+        RtsynStatus::PluginError(match error {
+            PluginError::Config(_) => RTSYN_PLUGIN_ERROR_CONFIG,
+            PluginError::Io(_) => RTSYN_PLUGIN_ERROR_IO,
+            PluginError::Device { .. } => RTSYN_PLUGIN_ERROR_DEVICE,
+            PluginError::Fatal(_) => RTSYN_PLUGIN_ERROR_FATAL,
+            PluginError::Recoverable(_) => RTSYN_PLUGIN_ERROR_RECOVERABLE,
+        })
+    }
+}
+
+impl From<PluginError> for RtsynStatus {
+    fn from(error: PluginError) -> Self {
+        RtsynStatus::from(&error)
+    }
+}
+
+thread_local! {
+    static LAST_STATUS: Cell<i32> = const { Cell::new(RTSYN_STATUS_OK) };
+}
+
+/// Status of the last `rtsyn_*` call made on this thread.
+#[no_mangle]
+pub extern "C" fn rtsyn_last_status() -> i32 {
+    LAST_STATUS.with(Cell::get)
+}
+
+/// Runs the body of an entry point: records its status for
+/// [`rtsyn_last_status`] and turns errors and panics into `on_error`.
+pub(crate) fn guard<T>(on_error: T, body: impl FnOnce() -> Result<T, RtsynStatus>) -> T {
+    let (status, value) = match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(value)) => (RtsynStatus::Ok, value),
+        Ok(Err(status)) => (status, on_error),
+        Err(_) => (RtsynStatus::Panic, on_error),
+    };
+    LAST_STATUS.with(|last| last.set(status.code()));
+    value
+}
+
+/// [`guard`] for entry points that return the status code itself.
+pub(crate) fn guard_status(body: impl FnOnce() -> Result<(), RtsynStatus>) -> i32 {
+    guard((), body);
+    rtsyn_last_status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_roundtrip() {
+        let statuses = [
+            RtsynStatus::Ok,
+            RtsynStatus::InvalidArgument,
+            RtsynStatus::Utf8Error,
+            RtsynStatus::SerializationError,
+            RtsynStatus::Panic,
+            RtsynStatus::UnknownPort,
+            RtsynStatus::WrongDirection,
+            RtsynStatus::PluginError(RTSYN_PLUGIN_ERROR_DEVICE),
+        ];
+        for status in statuses {
+            assert_eq!(RtsynStatus::from_code(status.code()), status);
+        }
+        assert_eq!(RtsynStatus::from_code(-1), RtsynStatus::InvalidArgument);
+
+        let fatal = RtsynStatus::from(PluginError::Fatal("gone".into()));
+        assert_eq!(fatal.code(), 103);
+        assert!(fatal.into_result("process").unwrap_err().is_fatal());
+        assert!(RtsynStatus::Ok.into_result("process").is_ok());
+    }
+
+    #[test]
+    fn details_rebuild_plugin_errors() {
+        let error = PluginError::Device {
+            code: 7,
+            message: "bus timeout".into(),
+        };
+        let status = RtsynStatus::from(&error);
+        let details = ErrorDetails::from(&error);
+        let json = serde_json::to_string(&details).unwrap();
+        let details: ErrorDetails = serde_json::from_str(&json).unwrap();
+        let rebuilt = status
+            .into_result_with("process", Some(details))
+            .unwrap_err();
+        assert_eq!(rebuilt.to_string(), "device error 7: bus timeout");
+
+        let bare = status.into_result_with("process", None).unwrap_err();
+        assert_eq!(bare.to_string(), "device error 0: process");
+    }
+
+    #[test]
+    fn guard_records_panics() {
+        assert_eq!(guard(0, || Ok(1)), 1);
+        assert_eq!(rtsyn_last_status(), RTSYN_STATUS_OK);
+        let value = guard(0, || -> Result<i32, RtsynStatus> { panic!("boom") });
+        assert_eq!(value, 0);
+        assert_eq!(rtsyn_last_status(), RTSYN_STATUS_PANIC);
+    }
+}
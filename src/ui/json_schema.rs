@@ -0,0 +1,333 @@
+//! Export of a [`UISchema`] as draft-07 JSON Schema, so configurations can be
+//! checked by external tools and frontends outside rtsyn can reuse the field
+//! definitions.
+//!
+//! Types, bounds, static choices and `Required` and `RequiresWhen` rules
+//! carry over. What JSON Schema cannot express is left out: fields with a
+//! `visible_if` condition are never listed as required, `Custom`,
+//! `LessThan` and `GreaterThan` rules are dropped, and choices filled by
+//! `Plugin::options_for` accept any string.
+
+use super::schema::{ConfigField, FieldType, UISchema};
+use super::validation::ValidationRule;
+use serde_json::{json, Map, Value};
+
+const DRAFT_07: &str = "http://json-schema.org/draft-07/schema#";
+
+impl UISchema {
+    /// The configuration layout as a draft-07 JSON Schema. Fields inside
+    /// groups appear at the top level, as in the configuration itself.
+    pub fn to_json_schema(&self) -> Value {
+        let mut schema = Map::new();
+        schema.insert("$schema".to_string(), DRAFT_07.into());
+        schema.extend(object_schema(&self.value_fields()));
+        Value::Object(schema)
+    }
+}
+
+fn object_schema(fields: &[&ConfigField]) -> Map<String, Value> {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    let mut conditions = Vec::new();
+    for field in fields {
+        properties.insert(field.key.clone(), field_schema(field));
+        if field.visible_if.is_some() {
+            continue;
+        }
+        for rule in &field.rules {
+            match rule {
+                ValidationRule::Required => required.push(Value::from(field.key.clone())),
+                ValidationRule::RequiresWhen {
+                    field: other,
+                    equals,
+                } => conditions.push(json!({
+                    "if": {
+                        "properties": { other: { "const": equals } },
+                        "required": [other],
+                    },
+                    "then": { "required": [field.key] },
+                })),
+                _ => {}
+            }
+        }
+    }
+    let mut schema = Map::new();
+    schema.insert("type".to_string(), "object".into());
+    schema.insert("properties".to_string(), Value::Object(properties));
+    if !required.is_empty() {
+        schema.insert("required".to_string(), Value::Array(required));
+    }
+    schema.insert("additionalProperties".to_string(), false.into());
+    if !conditions.is_empty() {
+        schema.insert("allOf".to_string(), Value::Array(conditions));
+    }
+    schema
+}
+
+fn field_schema(field: &ConfigField) -> Value {
+    let mut schema = type_schema(&field.field_type);
+    schema.insert("title".to_string(), field.label.clone().into());
+    if let Some(hint) = &field.hint {
+        schema.insert("description".to_string(), hint.clone().into());
+    }
+    if let Some(default) = &field.default {
+        schema.insert("default".to_string(), default.clone());
+    }
+    for rule in &field.rules {
+        apply_rule(&mut schema, rule);
+    }
+    Value::Object(schema)
+}
+
+fn type_schema(field_type: &FieldType) -> Map<String, Value> {
+    let mut schema = Map::new();
+    let mut set = |key: &str, value: Value| {
+        schema.insert(key.to_string(), value);
+    };
+    match field_type {
+        FieldType::Integer { min, max, .. } => {
+            set("type", "integer".into());
+            if let Some(min) = min {
+                set("minimum", (*min).into());
+            }
+            if let Some(max) = max {
+                set("maximum", (*max).into());
+            }
+        }
+        FieldType::Float { min, max, .. } => {
+            set("type", "number".into());
+            if let Some(min) = min {
+                set("minimum", (*min).into());
+            }
+            if let Some(max) = max {
+                set("maximum", (*max).into());
+            }
+        }
+        FieldType::Duration { min, max, .. } => {
+            set("type", "integer".into());
+            set("minimum", min.unwrap_or(0).into());
+            if let Some(max) = max {
+                set("maximum", (*max).into());
+            }
+        }
+        FieldType::Boolean => set("type", "boolean".into()),
+        FieldType::Text { max_length, .. } => {
+            set("type", "string".into());
+            if let Some(max) = max_length {
+                set("maxLength", (*max).into());
+            }
+        }
+        FieldType::Secret => {
+            set("type", "string".into());
+            set("writeOnly", true.into());
+        }
+        FieldType::DateTime => {
+            set("type", "string".into());
+            set("format", "date-time".into());
+        }
+        FieldType::FilePath { .. } => set("type", "string".into()),
+        FieldType::Choice {
+            options,
+            options_source,
+        } => {
+            set("type", "string".into());
+            if options_source.is_static() {
+                let values = options.iter().map(|o| Value::from(o.value.clone()));
+                set("enum", values.collect());
+            }
+        }
+        FieldType::DynamicList {
+            item_type,
+            min_items,
+            max_items,
+            ..
+        } => {
+            let items = match &**item_type {
+                FieldType::Group { fields, .. } => {
+                    object_schema(&fields.iter().collect::<Vec<_>>())
+                }
+                item_type => type_schema(item_type),
+            };
+            set("type", "array".into());
+            set("items", Value::Object(items));
+            if let Some(min) = min_items {
+                set("minItems", (*min).into());
+            }
+            if let Some(max) = max_items {
+                set("maxItems", (*max).into());
+            }
+        }
+        FieldType::Table {
+            columns,
+            min_rows,
+            max_rows,
+        } => {
+            let cells: Vec<Value> = columns
+                .iter()
+                .map(|column| {
+                    let mut cell = type_schema(&column.cell_type);
+                    cell.insert("title".to_string(), column.label.clone().into());
+                    Value::Object(cell)
+                })
+                .collect();
+            set("type", "array".into());
+            set(
+                "items",
+                json!({
+                    "type": "array",
+                    "items": cells,
+                    "minItems": columns.len(),
+                    "maxItems": columns.len(),
+                }),
+            );
+            if let Some(min) = min_rows {
+                set("minItems", (*min).into());
+            }
+            if let Some(max) = max_rows {
+                set("maxItems", (*max).into());
+            }
+        }
+        // None of these holds a value, so none appears in a configuration.
+        FieldType::Group { .. }
+        | FieldType::Action { .. }
+        | FieldType::Computed { .. }
+        | FieldType::Info { .. } => {}
+    }
+    schema
+}
+
+fn apply_rule(schema: &mut Map<String, Value>, rule: &ValidationRule) {
+    match rule {
+        ValidationRule::Regex { pattern } => {
+            schema.insert("pattern".to_string(), pattern.clone().into());
+        }
+        ValidationRule::Range { min, max } => {
+            if let Some(min) = min {
+                tighten(schema, "minimum", *min, |rule, bound| rule > bound);
+            }
+            if let Some(max) = max {
+                tighten(schema, "maximum", *max, |rule, bound| rule < bound);
+            }
+        }
+        ValidationRule::OneOf { values } => {
+            let values = match schema.get("enum").and_then(Value::as_array) {
+                Some(declared) => values
+                    .iter()
+                    .filter(|value| declared.contains(value))
+                    .cloned()
+                    .collect(),
+                None => values.clone(),
+            };
+            schema.insert("enum".to_string(), Value::Array(values));
+        }
+        ValidationRule::MinLength { min } => {
+            let key = match schema.get("type").and_then(Value::as_str) {
+                Some("array") => "minItems",
+                _ => "minLength",
+            };
+            schema.insert(key.to_string(), (*min).into());
+        }
+        ValidationRule::Required
+        | ValidationRule::Custom { .. }
+        | ValidationRule::RequiresWhen { .. }
+        | ValidationRule::LessThan { .. }
+        | ValidationRule::GreaterThan { .. } => {}
+    }
+}
+
+// Keeps the stricter of a field bound and a `Range` rule.
+fn tighten(schema: &mut Map<String, Value>, key: &str, rule: f64, stricter: fn(f64, f64) -> bool) {
+    let bound = schema.get(key).and_then(Value::as_f64);
+    if bound.is_none_or(|bound| stricter(rule, bound)) {
+        schema.insert(key.to_string(), rule.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ui::{ColumnSpec, ConfigField, UISchema, ValidationRule};
+    use serde_json::json;
+
+    #[test]
+    fn exports_draft_07() {
+        let schema = UISchema::new()
+            .field(
+                ConfigField::integer("rate", "Sample rate")
+                    .min(1)
+                    .max(96_000)
+                    .hint("Samples per second")
+                    .rule(ValidationRule::Required)
+                    .rule(ValidationRule::Range {
+                        min: Some(8000.0),
+                        max: Some(192_000.0),
+                    }),
+            )
+            .field(
+                ConfigField::choice("filter", "Filter")
+                    .option("lp", "Low-pass")
+                    .option("hp", "High-pass"),
+            )
+            .field(
+                ConfigField::group("advanced", "Advanced")
+                    .child(ConfigField::secret("token", "Token")),
+            )
+            .field(
+                ConfigField::table("gains", "Gains")
+                    .column(ColumnSpec::text("channel", "Channel"))
+                    .column(ColumnSpec::float("gain", "Gain"))
+                    .max_rows(8),
+            );
+
+        assert_eq!(
+            schema.to_json_schema(),
+            json!({
+                "$schema": "http://json-schema.org/draft-07/schema#",
+                "type": "object",
+                "properties": {
+                    "rate": {
+                        "type": "integer",
+                        "title": "Sample rate",
+                        "description": "Samples per second",
+                        "minimum": 8000.0,
+                        "maximum": 96_000,
+                    },
+                    "filter": { "type": "string", "title": "Filter", "enum": ["lp", "hp"] },
+                    "token": { "type": "string", "title": "Token", "writeOnly": true },
+                    "gains": {
+                        "type": "array",
+                        "title": "Gains",
+                        "items": {
+                            "type": "array",
+                            "items": [
+                                { "type": "string", "title": "Channel" },
+                                { "type": "number", "title": "Gain" },
+                            ],
+                            "minItems": 2,
+                            "maxItems": 2,
+                        },
+                        "maxItems": 8,
+                    },
+                },
+                "required": ["rate"],
+                "additionalProperties": false,
+            })
+        );
+    }
+
+    #[test]
+    fn conditional_requirements_become_if_then() {
+        let schema = UISchema::new()
+            .field(ConfigField::text("transport", "Transport"))
+            .field(ConfigField::integer("port", "Port").required_when("transport", "udp"));
+        assert_eq!(
+            schema.to_json_schema()["allOf"],
+            json!([{
+                "if": {
+                    "properties": { "transport": { "const": "udp" } },
+                    "required": ["transport"],
+                },
+                "then": { "required": ["port"] },
+            }])
+        );
+    }
+}
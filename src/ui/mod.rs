@@ -1,6 +1,15 @@
 pub mod behavior;
 pub mod ffi;
+mod json_schema;
 pub mod schema;
+pub mod validation;
 
-pub use behavior::{ConnectionBehavior, DisplaySchema, ExtendableInputs, PluginBehavior};
-pub use schema::{ConfigField, FieldType, FileMode, UISchema, Validator};
+pub use behavior::{
+    ComparisonMode, ConnectionBehavior, DisplaySchema, ExtendableInputs, ExtendableOutputs,
+    PluginBehavior, SideEffect, ThreadingModel,
+};
+pub use schema::{
+    ChoiceOption, ColumnSpec, ConfigField, FieldCondition, FieldType, FileMode, OptionsSource,
+    SliderScale, TimeUnit, UISchema, Validator, WidgetHint, REDACTED,
+};
+pub use validation::{ValidationError, ValidationRule};
@@ -1,20 +1,162 @@
+use super::validation::ValidationRule;
+use crate::i18n::Translations;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{Map, Value};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UISchema {
     pub fields: Vec<ConfigField>,
+    /// Bumped whenever fields are renamed or restructured, so saved
+    /// configurations can be upgraded with `Plugin::migrate_config`.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub schema_version: u32,
+    /// Labels and hints in other languages, see [`UISchema::localized`].
+    #[serde(default, skip_serializing_if = "Translations::is_empty")]
+    pub translations: Translations,
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
 }
 
 impl UISchema {
     pub fn new() -> Self {
-        Self { fields: Vec::new() }
+        Self {
+            fields: Vec::new(),
+            schema_version: 0,
+            translations: Translations::new(),
+        }
+    }
+
+    pub fn schema_version(mut self, version: u32) -> Self {
+        self.schema_version = version;
+        self
     }
 
     pub fn field(mut self, field: ConfigField) -> Self {
         self.fields.push(field);
         self
     }
+
+    pub fn section(
+        mut self,
+        key: impl Into<String>,
+        label: impl Into<String>,
+        fields: impl IntoIterator<Item = ConfigField>,
+    ) -> Self {
+        let mut group = ConfigField::group(key, label);
+        if let FieldType::Group {
+            fields: ref mut f, ..
+        } = group.field_type
+        {
+            f.extend(fields);
+        }
+        self.fields.push(group);
+        self
+    }
+
+    /// Every value-carrying field, with groups flattened in declaration order.
+    pub fn value_fields(&self) -> Vec<&ConfigField> {
+        self.fields_where(|field_type| {
+            !matches!(
+                field_type,
+                FieldType::Action { .. } | FieldType::Computed { .. } | FieldType::Info { .. }
+            )
+        })
+    }
+
+    /// Every action button, with groups flattened in declaration order.
+    pub fn action_fields(&self) -> Vec<&ConfigField> {
+        self.fields_where(|field_type| matches!(field_type, FieldType::Action { .. }))
+    }
+
+    /// Every computed display field, with groups flattened in declaration
+    /// order.
+    pub fn computed_fields(&self) -> Vec<&ConfigField> {
+        self.fields_where(|field_type| matches!(field_type, FieldType::Computed { .. }))
+    }
+
+    fn fields_where(&self, keep: impl Fn(&FieldType) -> bool) -> Vec<&ConfigField> {
+        fn collect<'a>(
+            fields: &'a [ConfigField],
+            keep: &impl Fn(&FieldType) -> bool,
+            out: &mut Vec<&'a ConfigField>,
+        ) {
+            for field in fields {
+                match &field.field_type {
+                    FieldType::Group { fields, .. } => collect(fields, keep, out),
+                    field_type if keep(field_type) => out.push(field),
+                    _ => {}
+                }
+            }
+        }
+        let mut out = Vec::new();
+        collect(&self.fields, &keep, &mut out);
+        out
+    }
+
+    /// Starting configuration built from field defaults.
+    ///
+    /// Fields inside groups are included at the top level. Lists and tables
+    /// without a default start empty; other fields without a default are
+    /// left out.
+    pub fn defaults(&self) -> Map<String, Value> {
+        let mut config = Map::new();
+        for field in self.value_fields() {
+            match (&field.default, &field.field_type) {
+                (Some(default), _) => {
+                    config.insert(field.key.clone(), default.clone());
+                }
+                (None, FieldType::DynamicList { .. } | FieldType::Table { .. }) => {
+                    config.insert(field.key.clone(), Value::Array(Vec::new()));
+                }
+                (None, _) => {}
+            }
+        }
+        config
+    }
+
+    /// `config` with the values of secret fields, including those inside
+    /// structured list items, replaced by [`REDACTED`]. Hosts use this
+    /// before logging a configuration or keeping it in a history.
+    pub fn redact(&self, config: &Value) -> Value {
+        let fields = self.value_fields();
+        redact_fields(&fields, config)
+    }
+
+    pub fn find_field(&self, key: &str) -> Option<&ConfigField> {
+        self.value_fields().into_iter().find(|f| f.key == key)
+    }
+}
+
+/// Stands in for secret values in redacted configurations.
+pub const REDACTED: &str = "********";
+
+fn redact_fields(fields: &[&ConfigField], config: &Value) -> Value {
+    let mut config = config.clone();
+    let Some(object) = config.as_object_mut() else {
+        return config;
+    };
+    for field in fields {
+        let Some(value) = object.get_mut(&field.key) else {
+            continue;
+        };
+        match &field.field_type {
+            FieldType::Secret if !value.is_null() => *value = Value::from(REDACTED),
+            FieldType::DynamicList { item_type, .. } => {
+                if let (FieldType::Group { fields, .. }, Some(items)) =
+                    (&**item_type, value.as_array_mut())
+                {
+                    let fields: Vec<&ConfigField> = fields.iter().collect();
+                    for item in items {
+                        *item = redact_fields(&fields, item);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    config
 }
 
 impl Default for UISchema {
@@ -33,6 +175,19 @@ pub struct ConfigField {
     pub default: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub hint: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<ValidationRule>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible_if: Option<FieldCondition>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled_if: Option<FieldCondition>,
+    /// Preferred editor for numeric fields; hosts fall back to a spin box.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub widget: Option<WidgetHint>,
+    /// Shown but not editable, e.g. a serial number read from the device.
+    /// The value stays part of the configuration.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub read_only: bool,
 }
 
 impl ConfigField {
@@ -43,6 +198,11 @@ impl ConfigField {
             field_type,
             default: None,
             hint: None,
+            rules: Vec::new(),
+            visible_if: None,
+            enabled_if: None,
+            widget: None,
+            read_only: false,
         }
     }
 
@@ -65,6 +225,7 @@ impl ConfigField {
                 min: None,
                 max: None,
                 step: 1,
+                unit: None,
             },
         )
     }
@@ -77,6 +238,8 @@ impl ConfigField {
                 min: None,
                 max: None,
                 step: 0.1,
+                unit: None,
+                display_precision: None,
             },
         )
     }
@@ -85,6 +248,74 @@ impl ConfigField {
         Self::new(key, label, FieldType::Boolean)
     }
 
+    pub fn secret(key: impl Into<String>, label: impl Into<String>) -> Self {
+        Self::new(key, label, FieldType::Secret)
+    }
+
+    pub fn duration(key: impl Into<String>, label: impl Into<String>, unit: TimeUnit) -> Self {
+        Self::new(
+            key,
+            label,
+            FieldType::Duration {
+                unit,
+                min: None,
+                max: None,
+            },
+        )
+    }
+
+    pub fn datetime(key: impl Into<String>, label: impl Into<String>) -> Self {
+        Self::new(key, label, FieldType::DateTime)
+    }
+
+    /// Block of Markdown text, e.g. usage notes or a wiring warning. An
+    /// empty `label` shows the text without a heading.
+    pub fn info(
+        key: impl Into<String>,
+        label: impl Into<String>,
+        markdown: impl Into<String>,
+    ) -> Self {
+        Self::new(
+            key,
+            label,
+            FieldType::Info {
+                markdown: markdown.into(),
+            },
+        )
+    }
+
+    /// Live display of output `source_output`, e.g. the measured sample
+    /// rate next to the requested one.
+    pub fn computed(
+        key: impl Into<String>,
+        label: impl Into<String>,
+        source_output: impl Into<String>,
+    ) -> Self {
+        Self::new(
+            key,
+            label,
+            FieldType::Computed {
+                source_output: source_output.into(),
+            },
+        )
+    }
+
+    /// A button labelled `label` that invokes `action_id`.
+    pub fn action(
+        key: impl Into<String>,
+        label: impl Into<String>,
+        action_id: impl Into<String>,
+    ) -> Self {
+        Self::new(
+            key,
+            label,
+            FieldType::Action {
+                action_id: action_id.into(),
+                confirm: None,
+            },
+        )
+    }
+
     pub fn filepath(key: impl Into<String>, label: impl Into<String>) -> Self {
         Self::new(
             key,
@@ -106,6 +337,44 @@ impl ConfigField {
                     max_length: None,
                 }),
                 add_label: "Add".to_string(),
+                min_items: None,
+                max_items: None,
+            },
+        )
+    }
+
+    pub fn group(key: impl Into<String>, label: impl Into<String>) -> Self {
+        let label = label.into();
+        Self::new(
+            key,
+            label.clone(),
+            FieldType::Group {
+                label,
+                collapsed: false,
+                fields: Vec::new(),
+            },
+        )
+    }
+
+    pub fn table(key: impl Into<String>, label: impl Into<String>) -> Self {
+        Self::new(
+            key,
+            label,
+            FieldType::Table {
+                columns: Vec::new(),
+                min_rows: None,
+                max_rows: None,
+            },
+        )
+    }
+
+    pub fn choice(key: impl Into<String>, label: impl Into<String>) -> Self {
+        Self::new(
+            key,
+            label,
+            FieldType::Choice {
+                options: Vec::new(),
+                options_source: OptionsSource::Static,
             },
         )
     }
@@ -120,36 +389,104 @@ impl ConfigField {
         self
     }
 
+    pub fn required(mut self) -> Self {
+        self.rules.push(ValidationRule::Required);
+        self
+    }
+
+    /// Required while `field` holds `equals`.
+    pub fn required_when(mut self, field: impl Into<String>, equals: impl Into<Value>) -> Self {
+        self.rules.push(ValidationRule::RequiresWhen {
+            field: field.into(),
+            equals: equals.into(),
+        });
+        self
+    }
+
+    pub fn rule(mut self, rule: ValidationRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn visible_if(mut self, condition: FieldCondition) -> Self {
+        self.visible_if = Some(condition);
+        self
+    }
+
+    pub fn enabled_if(mut self, condition: FieldCondition) -> Self {
+        self.enabled_if = Some(condition);
+        self
+    }
+
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Only applies to integer and float fields.
+    pub fn widget(mut self, widget: WidgetHint) -> Self {
+        if matches!(
+            self.field_type,
+            FieldType::Integer { .. } | FieldType::Float { .. }
+        ) {
+            self.widget = Some(widget);
+        }
+        self
+    }
+
+    pub fn is_visible(&self, config: &Value) -> bool {
+        self.visible_if.as_ref().is_none_or(|c| c.evaluate(config))
+    }
+
+    pub fn is_enabled(&self, config: &Value) -> bool {
+        self.enabled_if.as_ref().is_none_or(|c| c.evaluate(config))
+    }
+
     pub fn max_length(mut self, max: usize) -> Self {
-        if let FieldType::Text { ref mut max_length, .. } = self.field_type {
+        if let FieldType::Text {
+            ref mut max_length, ..
+        } = self.field_type
+        {
             *max_length = Some(max);
         }
         self
     }
 
     pub fn multiline(mut self) -> Self {
-        if let FieldType::Text { ref mut multiline, .. } = self.field_type {
+        if let FieldType::Text {
+            ref mut multiline, ..
+        } = self.field_type
+        {
             *multiline = true;
         }
         self
     }
 
+    /// Also bounds duration fields, in their unit; negative values are
+    /// treated as 0 there.
     pub fn min(mut self, min: i64) -> Self {
-        if let FieldType::Integer { min: ref mut m, .. } = self.field_type {
-            *m = Some(min);
+        match self.field_type {
+            FieldType::Integer { min: ref mut m, .. } => *m = Some(min),
+            FieldType::Duration { min: ref mut m, .. } => *m = Some(min.max(0) as u64),
+            _ => {}
         }
         self
     }
 
     pub fn max(mut self, max: i64) -> Self {
-        if let FieldType::Integer { max: ref mut m, .. } = self.field_type {
-            *m = Some(max);
+        match self.field_type {
+            FieldType::Integer { max: ref mut m, .. } => *m = Some(max),
+            FieldType::Duration { max: ref mut m, .. } => *m = Some(max.max(0) as u64),
+            _ => {}
         }
         self
     }
 
     pub fn step(mut self, step: i64) -> Self {
-        if let FieldType::Integer { step: ref mut s, .. } = self.field_type {
+        if let FieldType::Integer {
+            step: ref mut s, ..
+        } = self.field_type
+        {
             *s = step;
         }
         self
@@ -170,39 +507,220 @@ impl ConfigField {
     }
 
     pub fn step_f(mut self, step: f64) -> Self {
-        if let FieldType::Float { step: ref mut s, .. } = self.field_type {
+        if let FieldType::Float {
+            step: ref mut s, ..
+        } = self.field_type
+        {
             *s = step;
         }
         self
     }
 
+    /// Unit of an integer or float field, e.g. `"Hz"`, `"V"` or `"dB"`.
+    pub fn unit(mut self, unit: impl Into<String>) -> Self {
+        match self.field_type {
+            FieldType::Integer {
+                unit: ref mut u, ..
+            }
+            | FieldType::Float {
+                unit: ref mut u, ..
+            } => {
+                *u = Some(unit.into());
+            }
+            _ => {}
+        }
+        self
+    }
+
+    pub fn display_precision(mut self, precision: u32) -> Self {
+        if let FieldType::Float {
+            ref mut display_precision,
+            ..
+        } = self.field_type
+        {
+            *display_precision = Some(precision);
+        }
+        self
+    }
+
+    /// `value` as a host should show it, e.g. `"440.0 Hz"`. Values that do
+    /// not fit the field type are shown as JSON.
+    pub fn display_value(&self, value: &Value) -> String {
+        let (number, unit) = match (&self.field_type, value.as_f64()) {
+            (FieldType::Integer { unit, .. }, Some(_)) => (value.to_string(), unit.as_deref()),
+            (FieldType::Duration { unit, .. }, Some(_)) => (value.to_string(), Some(unit.symbol())),
+            (
+                FieldType::Float {
+                    unit,
+                    display_precision,
+                    ..
+                },
+                Some(number),
+            ) => match display_precision {
+                Some(precision) => (
+                    format!("{number:.0$}", *precision as usize),
+                    unit.as_deref(),
+                ),
+                None => (format!("{number:?}"), unit.as_deref()),
+            },
+            _ => return value.to_string(),
+        };
+        match unit {
+            Some(unit) => format!("{number} {unit}"),
+            None => number,
+        }
+    }
+
+    pub fn confirm(mut self, question: impl Into<String>) -> Self {
+        if let FieldType::Action {
+            ref mut confirm, ..
+        } = self.field_type
+        {
+            *confirm = Some(question.into());
+        }
+        self
+    }
+
     pub fn mode(mut self, mode: FileMode) -> Self {
-        if let FieldType::FilePath { mode: ref mut m, .. } = self.field_type {
+        if let FieldType::FilePath {
+            mode: ref mut m, ..
+        } = self.field_type
+        {
             *m = mode;
         }
         self
     }
 
     pub fn filter(mut self, name: impl Into<String>, pattern: impl Into<String>) -> Self {
-        if let FieldType::FilePath { ref mut filters, .. } = self.field_type {
+        if let FieldType::FilePath {
+            ref mut filters, ..
+        } = self.field_type
+        {
             filters.push((name.into(), pattern.into()));
         }
         self
     }
 
     pub fn item_type(mut self, item_type: FieldType) -> Self {
-        if let FieldType::DynamicList { item_type: ref mut it, .. } = self.field_type {
-            *it = Box::new(item_type);
+        if let FieldType::DynamicList {
+            item_type: ref mut it,
+            ..
+        } = self.field_type
+        {
+            **it = item_type;
+        }
+        self
+    }
+
+    /// Makes the list structured: every item is an object with `fields`.
+    pub fn item_fields(self, fields: impl IntoIterator<Item = ConfigField>) -> Self {
+        self.item_type(FieldType::Group {
+            label: String::new(),
+            collapsed: false,
+            fields: fields.into_iter().collect(),
+        })
+    }
+
+    pub fn min_items(mut self, min: usize) -> Self {
+        if let FieldType::DynamicList {
+            ref mut min_items, ..
+        } = self.field_type
+        {
+            *min_items = Some(min);
+        }
+        self
+    }
+
+    pub fn max_items(mut self, max: usize) -> Self {
+        if let FieldType::DynamicList {
+            ref mut max_items, ..
+        } = self.field_type
+        {
+            *max_items = Some(max);
+        }
+        self
+    }
+
+    pub fn column(mut self, column: ColumnSpec) -> Self {
+        if let FieldType::Table {
+            ref mut columns, ..
+        } = self.field_type
+        {
+            columns.push(column);
+        }
+        self
+    }
+
+    pub fn min_rows(mut self, min: usize) -> Self {
+        if let FieldType::Table {
+            ref mut min_rows, ..
+        } = self.field_type
+        {
+            *min_rows = Some(min);
+        }
+        self
+    }
+
+    pub fn max_rows(mut self, max: usize) -> Self {
+        if let FieldType::Table {
+            ref mut max_rows, ..
+        } = self.field_type
+        {
+            *max_rows = Some(max);
         }
         self
     }
 
     pub fn add_label(mut self, label: impl Into<String>) -> Self {
-        if let FieldType::DynamicList { add_label: ref mut al, .. } = self.field_type {
+        if let FieldType::DynamicList {
+            add_label: ref mut al,
+            ..
+        } = self.field_type
+        {
             *al = label.into();
         }
         self
     }
+
+    pub fn collapsed(mut self) -> Self {
+        if let FieldType::Group {
+            ref mut collapsed, ..
+        } = self.field_type
+        {
+            *collapsed = true;
+        }
+        self
+    }
+
+    pub fn child(mut self, field: ConfigField) -> Self {
+        if let FieldType::Group { ref mut fields, .. } = self.field_type {
+            fields.push(field);
+        }
+        self
+    }
+
+    pub fn option(mut self, value: impl Into<String>, label: impl Into<String>) -> Self {
+        if let FieldType::Choice {
+            ref mut options, ..
+        } = self.field_type
+        {
+            options.push(ChoiceOption::new(value, label));
+        }
+        self
+    }
+
+    /// Makes a choice list its options at runtime through
+    /// `Plugin::options_for`.
+    pub fn options_from_plugin(mut self) -> Self {
+        if let FieldType::Choice {
+            ref mut options_source,
+            ..
+        } = self.field_type
+        {
+            *options_source = OptionsSource::Plugin;
+        }
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -214,6 +732,9 @@ pub enum FieldType {
         #[serde(skip_serializing_if = "Option::is_none")]
         max: Option<i64>,
         step: i64,
+        /// Shown after the value, e.g. `"ms"`; keep it out of the label.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        unit: Option<String>,
     },
     Float {
         #[serde(skip_serializing_if = "Option::is_none")]
@@ -221,6 +742,11 @@ pub enum FieldType {
         #[serde(skip_serializing_if = "Option::is_none")]
         max: Option<f64>,
         step: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        unit: Option<String>,
+        /// Decimal places to display; hosts pick their own when unset.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        display_precision: Option<u32>,
     },
     Text {
         multiline: bool,
@@ -228,19 +754,340 @@ pub enum FieldType {
         max_length: Option<usize>,
     },
     Boolean,
+    /// A password or token: a string hosts render masked and keep out of
+    /// logs, see [`UISchema::redact`].
+    Secret,
+    /// A whole number of `unit`s, e.g. a recording length in seconds.
+    Duration {
+        unit: TimeUnit,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<u64>,
+    },
+    /// An RFC 3339 timestamp such as `"2024-05-01T08:30:00Z"`.
+    DateTime,
+    /// A button, e.g. "Calibrate" or "Test connection". Pressing it makes the
+    /// host call `Plugin::on_action` with `action_id`. Holds no value of its
+    /// own.
+    Action {
+        action_id: String,
+        /// Question to confirm before invoking, for irreversible actions.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        confirm: Option<String>,
+    },
+    /// Read-only display of an output port's current value, e.g. the
+    /// measured sample rate or the last error. Holds no value of its own;
+    /// hosts read it with `Plugin::get_output` or, for text outputs,
+    /// `Plugin::get_output_string`.
+    Computed {
+        source_output: String,
+    },
+    /// Static CommonMark text shown in the dialog, for notes that do not fit
+    /// a one-line hint. Links are fine; hosts do not render raw HTML.
+    Info {
+        markdown: String,
+    },
     FilePath {
         mode: FileMode,
         filters: Vec<(String, String)>,
     },
+    /// An item type of `Group` makes each item an object holding the
+    /// group's fields, e.g. one `{channel, gain, label}` row per item.
     DynamicList {
         item_type: Box<FieldType>,
         add_label: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_items: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_items: Option<usize>,
     },
+    /// With `options_source: "plugin"` the host asks `Plugin::options_for`
+    /// for the entries each time it shows the dropdown, e.g. to list the
+    /// serial ports attached right now; `options` is then only a fallback.
     Choice {
-        options: Vec<String>,
+        options: Vec<ChoiceOption>,
+        #[serde(default, skip_serializing_if = "OptionsSource::is_static")]
+        options_source: OptionsSource,
+    },
+    /// Editable grid, e.g. a routing matrix or a calibration table. The
+    /// value is a list of rows, each a list with one cell per column.
+    Table {
+        columns: Vec<ColumnSpec>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_rows: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_rows: Option<usize>,
+    },
+    /// Presentational container; child keys stay top-level in the config.
+    Group {
+        label: String,
+        collapsed: bool,
+        fields: Vec<ConfigField>,
     },
 }
 
+/// One column of a [`FieldType::Table`]. Cells hold scalar values; list,
+/// table and group cell types are rejected by validation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnSpec {
+    pub key: String,
+    pub label: String,
+    #[serde(rename = "type")]
+    pub cell_type: FieldType,
+}
+
+impl ColumnSpec {
+    pub fn new(key: impl Into<String>, label: impl Into<String>, cell_type: FieldType) -> Self {
+        Self {
+            key: key.into(),
+            label: label.into(),
+            cell_type,
+        }
+    }
+
+    /// Column of unbounded integers; for bounds or a unit, build the
+    /// cell type with a [`ConfigField`] constructor and pass it to `new`.
+    pub fn integer(key: impl Into<String>, label: impl Into<String>) -> Self {
+        let cell_type = ConfigField::integer("", "").field_type;
+        Self::new(key, label, cell_type)
+    }
+
+    pub fn float(key: impl Into<String>, label: impl Into<String>) -> Self {
+        let cell_type = ConfigField::float("", "").field_type;
+        Self::new(key, label, cell_type)
+    }
+
+    pub fn text(key: impl Into<String>, label: impl Into<String>) -> Self {
+        let cell_type = ConfigField::text("", "").field_type;
+        Self::new(key, label, cell_type)
+    }
+
+    pub fn boolean(key: impl Into<String>, label: impl Into<String>) -> Self {
+        Self::new(key, label, FieldType::Boolean)
+    }
+}
+
+/// A dropdown entry: `value` is what gets stored in the config, `label` is
+/// what the user sees. Bare strings deserialize with the same value and label.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "ChoiceOptionRepr")]
+pub struct ChoiceOption {
+    pub value: String,
+    pub label: String,
+}
+
+impl ChoiceOption {
+    pub fn new(value: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+            label: label.into(),
+        }
+    }
+}
+
+/// Where a choice field gets its options from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OptionsSource {
+    /// The `options` declared in the schema.
+    #[default]
+    Static,
+    /// `Plugin::options_for`, queried whenever the options are needed.
+    Plugin,
+}
+
+impl OptionsSource {
+    pub fn is_static(&self) -> bool {
+        *self == OptionsSource::Static
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ChoiceOptionRepr {
+    Bare(String),
+    Full { value: String, label: String },
+}
+
+impl From<ChoiceOptionRepr> for ChoiceOption {
+    fn from(repr: ChoiceOptionRepr) -> Self {
+        match repr {
+            ChoiceOptionRepr::Bare(value) => Self::new(value.clone(), value),
+            ChoiceOptionRepr::Full { value, label } => Self::new(value, label),
+        }
+    }
+}
+
+/// Condition on the current value of other fields, evaluated by the host
+/// while the form is edited.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum FieldCondition {
+    Equals { field: String, value: Value },
+    NotEquals { field: String, value: Value },
+    OneOf { field: String, values: Vec<Value> },
+    IsTrue { field: String },
+    All { conditions: Vec<FieldCondition> },
+    Any { conditions: Vec<FieldCondition> },
+    Not { condition: Box<FieldCondition> },
+}
+
+impl FieldCondition {
+    pub fn equals(field: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::Equals {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn not_equals(field: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::NotEquals {
+            field: field.into(),
+            value: value.into(),
+        }
+    }
+
+    pub fn one_of(field: impl Into<String>, values: Vec<Value>) -> Self {
+        Self::OneOf {
+            field: field.into(),
+            values,
+        }
+    }
+
+    pub fn is_true(field: impl Into<String>) -> Self {
+        Self::IsTrue {
+            field: field.into(),
+        }
+    }
+
+    pub fn evaluate(&self, config: &Value) -> bool {
+        let get = |field: &str| config.get(field).unwrap_or(&Value::Null);
+        match self {
+            FieldCondition::Equals { field, value } => get(field) == value,
+            FieldCondition::NotEquals { field, value } => get(field) != value,
+            FieldCondition::OneOf { field, values } => values.contains(get(field)),
+            FieldCondition::IsTrue { field } => get(field).as_bool().unwrap_or(false),
+            FieldCondition::All { conditions } => conditions.iter().all(|c| c.evaluate(config)),
+            FieldCondition::Any { conditions } => conditions.iter().any(|c| c.evaluate(config)),
+            FieldCondition::Not { condition } => !condition.evaluate(config),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum WidgetHint {
+    Slider {
+        #[serde(default)]
+        scale: SliderScale,
+    },
+    Knob {
+        #[serde(default)]
+        scale: SliderScale,
+    },
+    /// Plus and minus buttons moving by the field's step.
+    Stepper,
+}
+
+impl WidgetHint {
+    pub fn slider() -> Self {
+        Self::Slider {
+            scale: SliderScale::Linear,
+        }
+    }
+
+    /// For ranges spanning decades, e.g. a frequency from 0.1 Hz to 10 kHz.
+    pub fn log_slider() -> Self {
+        Self::Slider {
+            scale: SliderScale::Log,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SliderScale {
+    #[default]
+    Linear,
+    /// Equal travel per decade. Needs a positive `min`; otherwise it behaves
+    /// as `Linear`.
+    Log,
+}
+
+impl SliderScale {
+    /// Position of `value` along the control, from 0 at `min` to 1 at `max`.
+    pub fn position(self, value: f64, min: f64, max: f64) -> f64 {
+        let position = if self.is_log(min, max) {
+            (value.max(min) / min).ln() / (max / min).ln()
+        } else {
+            (value - min) / (max - min)
+        };
+        if position.is_finite() {
+            position.clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Inverse of [`SliderScale::position`].
+    pub fn value_at(self, position: f64, min: f64, max: f64) -> f64 {
+        let position = position.clamp(0.0, 1.0);
+        if self.is_log(min, max) {
+            min * (max / min).powf(position)
+        } else {
+            min + (max - min) * position
+        }
+    }
+
+    fn is_log(self, min: f64, max: f64) -> bool {
+        self == SliderScale::Log && min > 0.0 && max > min
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TimeUnit {
+    #[serde(rename = "ns")]
+    Nanoseconds,
+    #[serde(rename = "us")]
+    Microseconds,
+    #[serde(rename = "ms")]
+    Milliseconds,
+    #[serde(rename = "s")]
+    Seconds,
+    #[serde(rename = "min")]
+    Minutes,
+    #[serde(rename = "h")]
+    Hours,
+}
+
+impl TimeUnit {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            TimeUnit::Nanoseconds => "ns",
+            TimeUnit::Microseconds => "us",
+            TimeUnit::Milliseconds => "ms",
+            TimeUnit::Seconds => "s",
+            TimeUnit::Minutes => "min",
+            TimeUnit::Hours => "h",
+        }
+    }
+
+    /// `amount` of this unit, e.g. to read a duration field:
+    /// `unit.duration(config["length"].as_u64().unwrap_or(0))`.
+    pub fn duration(self, amount: u64) -> std::time::Duration {
+        use std::time::Duration;
+        match self {
+            TimeUnit::Nanoseconds => Duration::from_nanos(amount),
+            TimeUnit::Microseconds => Duration::from_micros(amount),
+            TimeUnit::Milliseconds => Duration::from_millis(amount),
+            TimeUnit::Seconds => Duration::from_secs(amount),
+            TimeUnit::Minutes => Duration::from_secs(amount.saturating_mul(60)),
+            TimeUnit::Hours => Duration::from_secs(amount.saturating_mul(3600)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum FileMode {
@@ -296,7 +1143,7 @@ mod tests {
             .step(1)
             .default_value(Value::from(10));
 
-        if let FieldType::Integer { min, max, step } = field.field_type {
+        if let FieldType::Integer { min, max, step, .. } = field.field_type {
             assert_eq!(min, Some(0));
             assert_eq!(max, Some(99));
             assert_eq!(step, 1);
@@ -312,7 +1159,7 @@ mod tests {
             .max_f(10.0)
             .step_f(0.1);
 
-        if let FieldType::Float { min, max, step } = field.field_type {
+        if let FieldType::Float { min, max, step, .. } = field.field_type {
             assert_eq!(min, Some(0.0));
             assert_eq!(max, Some(10.0));
             assert_eq!(step, 0.1);
@@ -347,7 +1194,12 @@ mod tests {
             })
             .add_label("Add column");
 
-        if let FieldType::DynamicList { item_type, add_label } = field.field_type {
+        if let FieldType::DynamicList {
+            item_type,
+            add_label,
+            ..
+        } = field.field_type
+        {
             assert_eq!(add_label, "Add column");
             if let FieldType::Text { max_length, .. } = *item_type {
                 assert_eq!(max_length, Some(50));
@@ -359,12 +1211,149 @@ mod tests {
         }
     }
 
+    #[test]
+    fn config_field_choice() {
+        let field = ConfigField::choice("mode", "Mode")
+            .option("lp", "Low-pass")
+            .option("hp", "High-pass")
+            .default_value(Value::String("lp".to_string()));
+
+        if let FieldType::Choice { options, .. } = &field.field_type {
+            assert_eq!(options.len(), 2);
+            assert_eq!(options[0], ChoiceOption::new("lp", "Low-pass"));
+            assert_eq!(options[1].value, "hp");
+        } else {
+            panic!("Expected Choice field type");
+        }
+    }
+
+    #[test]
+    fn choice_options_accept_bare_strings() {
+        let json = r#"{"kind":"choice","options":["a",{"value":"b","label":"Bee"}]}"#;
+        let field_type: FieldType = serde_json::from_str(json).unwrap();
+        if let FieldType::Choice { options, .. } = field_type {
+            assert_eq!(options[0], ChoiceOption::new("a", "a"));
+            assert_eq!(options[1], ChoiceOption::new("b", "Bee"));
+        } else {
+            panic!("Expected Choice field type");
+        }
+
+        let json = serde_json::to_string(&ChoiceOption::new("b", "Bee")).unwrap();
+        assert_eq!(json, r#"{"value":"b","label":"Bee"}"#);
+    }
+
+    #[test]
+    fn config_field_conditions() {
+        let field = ConfigField::filepath("path", "Path")
+            .visible_if(FieldCondition::equals("output_mode", "file"))
+            .enabled_if(FieldCondition::Not {
+                condition: Box::new(FieldCondition::is_true("locked")),
+            });
+
+        let config = serde_json::json!({ "output_mode": "file", "locked": false });
+        assert!(field.is_visible(&config));
+        assert!(field.is_enabled(&config));
+
+        let config = serde_json::json!({ "output_mode": "stdout", "locked": true });
+        assert!(!field.is_visible(&config));
+        assert!(!field.is_enabled(&config));
+
+        assert!(ConfigField::text("name", "Name").is_visible(&Value::Null));
+    }
+
+    #[test]
+    fn field_condition_serialization() {
+        let condition = FieldCondition::Any {
+            conditions: vec![
+                FieldCondition::equals("mode", "file"),
+                FieldCondition::one_of("mode", vec![Value::from("a"), Value::from("b")]),
+            ],
+        };
+        let json = serde_json::to_value(&condition).unwrap();
+        assert_eq!(json["op"], "any");
+        assert_eq!(
+            json["conditions"][0],
+            serde_json::json!({ "op": "equals", "field": "mode", "value": "file" })
+        );
+        let parsed: FieldCondition = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, condition);
+
+        let field = ConfigField::text("name", "Name");
+        let json = serde_json::to_string(&field).unwrap();
+        assert!(!json.contains("visible_if"));
+    }
+
+    #[test]
+    fn ui_schema_defaults() {
+        let schema = UISchema::new()
+            .field(ConfigField::integer("count", "Count").default_value(Value::from(3)))
+            .field(ConfigField::text("name", "Name"))
+            .section(
+                "advanced",
+                "Advanced",
+                [ConfigField::boolean("verbose", "Verbose").default_value(Value::Bool(false))],
+            )
+            .field(ConfigField::dynamic_list("columns", "Columns"));
+
+        let defaults = schema.defaults();
+        assert_eq!(
+            Value::Object(defaults),
+            serde_json::json!({ "count": 3, "verbose": false, "columns": [] })
+        );
+    }
+
+    #[test]
+    fn ui_schema_sections() {
+        let schema = UISchema::new()
+            .field(ConfigField::text("name", "Name"))
+            .section(
+                "advanced",
+                "Advanced",
+                [
+                    ConfigField::integer("retries", "Retries"),
+                    ConfigField::boolean("verbose", "Verbose"),
+                ],
+            )
+            .field(
+                ConfigField::group("output", "Output")
+                    .collapsed()
+                    .child(ConfigField::filepath("path", "Path")),
+            );
+
+        assert_eq!(schema.fields.len(), 3);
+        if let FieldType::Group {
+            label,
+            collapsed,
+            fields,
+        } = &schema.fields[2].field_type
+        {
+            assert_eq!(label, "Output");
+            assert!(*collapsed);
+            assert_eq!(fields[0].key, "path");
+        } else {
+            panic!("Expected Group field type");
+        }
+
+        let keys: Vec<_> = schema
+            .value_fields()
+            .iter()
+            .map(|f| f.key.as_str())
+            .collect();
+        assert_eq!(keys, ["name", "retries", "verbose", "path"]);
+        assert!(schema.find_field("verbose").is_some());
+        assert!(schema.find_field("advanced").is_none());
+
+        let json = serde_json::to_value(&schema).unwrap();
+        assert_eq!(json["fields"][1]["type"]["kind"], "group");
+        let parsed: UISchema = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.value_fields().len(), 4);
+    }
+
     #[test]
     fn ui_schema_serialization() {
         let schema = UISchema::new()
             .field(
-                ConfigField::text("name", "Name")
-                    .default_value(Value::String("test".to_string())),
+                ConfigField::text("name", "Name").default_value(Value::String("test".to_string())),
             )
             .field(ConfigField::boolean("enabled", "Enabled"));
 
@@ -382,6 +1371,7 @@ mod tests {
             min: Some(0),
             max: Some(100),
             step: 5,
+            unit: None,
         };
 
         let json = serde_json::to_string(&field_type).unwrap();
@@ -391,7 +1381,7 @@ mod tests {
         assert!(json.contains(r#""step":5"#));
 
         let deserialized: FieldType = serde_json::from_str(&json).unwrap();
-        if let FieldType::Integer { min, max, step } = deserialized {
+        if let FieldType::Integer { min, max, step, .. } = deserialized {
             assert_eq!(min, Some(0));
             assert_eq!(max, Some(100));
             assert_eq!(step, 5);
@@ -409,4 +1399,182 @@ mod tests {
         let deserialized: FileMode = serde_json::from_str(&json).unwrap();
         assert_eq!(deserialized, FileMode::SaveFile);
     }
+
+    #[test]
+    fn log_slider_spreads_decades_evenly() {
+        let field = ConfigField::float("cutoff", "Cutoff (Hz)")
+            .min_f(0.1)
+            .max_f(10_000.0)
+            .widget(WidgetHint::log_slider());
+        let json = serde_json::to_value(&field).unwrap();
+        assert_eq!(
+            json["widget"],
+            serde_json::json!({ "kind": "slider", "scale": "log" })
+        );
+
+        let scale = SliderScale::Log;
+        assert!((scale.position(1.0, 0.1, 10_000.0) - 0.2).abs() < 1e-12);
+        assert!((scale.value_at(0.6, 0.1, 10_000.0) - 100.0).abs() < 1e-9);
+        assert_eq!(SliderScale::Linear.position(5.0, 0.0, 10.0), 0.5);
+        assert_eq!(scale.position(5.0, 0.0, 10.0), 0.5);
+
+        let text = ConfigField::text("name", "Name").widget(WidgetHint::Stepper);
+        assert_eq!(text.widget, None);
+    }
+
+    #[test]
+    fn numeric_display_uses_unit_and_precision() {
+        let frequency = ConfigField::float("frequency", "Frequency")
+            .unit("Hz")
+            .display_precision(1);
+        assert_eq!(frequency.display_value(&Value::from(440)), "440.0 Hz");
+        assert_eq!(
+            ConfigField::float("gain", "Gain").display_value(&Value::from(2.0)),
+            "2.0"
+        );
+        let delay = ConfigField::integer("delay", "Delay").unit("ms");
+        assert_eq!(delay.display_value(&Value::from(20)), "20 ms");
+        assert_eq!(delay.display_value(&Value::from("soon")), "\"soon\"");
+
+        let json = serde_json::to_value(&frequency.field_type).unwrap();
+        assert_eq!(json["unit"], "Hz");
+        assert_eq!(json["display_precision"], 1);
+        assert!(serde_json::to_value(&delay.field_type)
+            .unwrap()
+            .get("display_precision")
+            .is_none());
+    }
+
+    #[test]
+    fn redact_hides_secrets() {
+        let schema = UISchema::new()
+            .field(ConfigField::text("broker", "Broker"))
+            .field(ConfigField::secret("password", "Password"))
+            .field(
+                ConfigField::dynamic_list("accounts", "Accounts").item_fields([
+                    ConfigField::text("user", "User"),
+                    ConfigField::secret("token", "Token"),
+                ]),
+            );
+        let config = serde_json::json!({
+            "broker": "mqtt://lab",
+            "password": "hunter2",
+            "accounts": [{ "user": "daq", "token": "abc" }],
+        });
+        assert_eq!(
+            schema.redact(&config),
+            serde_json::json!({
+                "broker": "mqtt://lab",
+                "password": REDACTED,
+                "accounts": [{ "user": "daq", "token": REDACTED }],
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(FieldType::Secret).unwrap(),
+            serde_json::json!({ "kind": "secret" })
+        );
+    }
+
+    #[test]
+    fn duration_and_datetime_fields() {
+        let length = ConfigField::duration("length", "Recording length", TimeUnit::Seconds)
+            .min(1)
+            .max(3600);
+        assert_eq!(
+            serde_json::to_value(&length.field_type).unwrap(),
+            serde_json::json!({ "kind": "duration", "unit": "s", "min": 1, "max": 3600 })
+        );
+        assert_eq!(length.display_value(&Value::from(30)), "30 s");
+        assert_eq!(
+            TimeUnit::Minutes.duration(2),
+            std::time::Duration::from_secs(120)
+        );
+
+        let start = ConfigField::datetime("start", "Start at");
+        assert_eq!(
+            serde_json::to_value(&start.field_type).unwrap(),
+            serde_json::json!({ "kind": "datetime" })
+        );
+    }
+
+    #[test]
+    fn choice_options_from_plugin() {
+        let port = ConfigField::choice("port", "Serial port").options_from_plugin();
+        assert_eq!(
+            serde_json::to_value(&port.field_type).unwrap(),
+            serde_json::json!({ "kind": "choice", "options": [], "options_source": "plugin" })
+        );
+        let schema = UISchema::new().field(port);
+        assert!(schema
+            .validate(&serde_json::json!({ "port": "/dev/ttyACM0" }))
+            .is_empty());
+
+        let field_type: FieldType =
+            serde_json::from_value(serde_json::json!({ "kind": "choice", "options": ["a"] }))
+                .unwrap();
+        assert!(matches!(
+            field_type,
+            FieldType::Choice {
+                options_source: OptionsSource::Static,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn read_only_and_computed_fields() {
+        let schema = UISchema::new()
+            .field(
+                ConfigField::text("serial", "Serial number")
+                    .default_value(Value::from("A-17"))
+                    .read_only(),
+            )
+            .field(ConfigField::computed(
+                "measured_rate",
+                "Measured rate",
+                "rate",
+            ));
+        let json = serde_json::to_value(&schema.fields).unwrap();
+        assert_eq!(json[0]["read_only"], true);
+        assert_eq!(
+            json[1]["type"],
+            serde_json::json!({ "kind": "computed", "source_output": "rate" })
+        );
+        assert!(json[1].get("read_only").is_none());
+
+        assert_eq!(schema.value_fields().len(), 1);
+        assert_eq!(schema.computed_fields()[0].key, "measured_rate");
+
+        let notes = ConfigField::info("wiring", "", "Connect **GND** first.");
+        assert_eq!(
+            serde_json::to_value(&notes.field_type).unwrap(),
+            serde_json::json!({ "kind": "info", "markdown": "Connect **GND** first." })
+        );
+        assert!(UISchema::new().field(notes).value_fields().is_empty());
+        assert!(schema
+            .validate(&Value::Object(schema.defaults()))
+            .is_empty());
+    }
+
+    #[test]
+    fn action_fields_hold_no_value() {
+        let schema = UISchema::new()
+            .field(ConfigField::float("gain", "Gain").default_value(Value::from(1.0)))
+            .field(
+                ConfigField::group("maintenance", "Maintenance").child(
+                    ConfigField::action("zero", "Zero sensor", "calibrate")
+                        .confirm("Overwrite the stored offset?"),
+                ),
+            );
+        assert_eq!(
+            serde_json::to_value(&schema.action_fields()[0].field_type).unwrap(),
+            serde_json::json!({
+                "kind": "action",
+                "action_id": "calibrate",
+                "confirm": "Overwrite the stored offset?",
+            })
+        );
+        assert_eq!(schema.value_fields().len(), 1);
+        assert!(!schema.defaults().contains_key("zero"));
+    }
 }
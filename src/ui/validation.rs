@@ -0,0 +1,581 @@
+use super::schema::{ColumnSpec, ConfigField, FieldType, UISchema, Validator};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Declarative check attached to a [`ConfigField`].
+///
+/// Unlike [`Validator`], rules serialize with the schema, so a host can
+/// validate a configuration without loading the plugin.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum ValidationRule {
+    Required,
+    Regex {
+        pattern: String,
+    },
+    Range {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<f64>,
+    },
+    OneOf {
+        values: Vec<Value>,
+    },
+    /// Minimum number of characters of a string, or items of a list.
+    MinLength {
+        min: usize,
+    },
+    /// Named check supplied by whoever runs the validation.
+    Custom {
+        key: String,
+    },
+    /// Required while `field` holds `equals`, e.g. a port number once the
+    /// transport is `"udp"`.
+    RequiresWhen {
+        field: String,
+        equals: Value,
+    },
+    /// Numerically below the value of `other_field`. Skipped while the other
+    /// field is unset.
+    LessThan {
+        other_field: String,
+    },
+    /// Numerically above the value of `other_field`, e.g. a high cutoff
+    /// above the low cutoff. Skipped while the other field is unset.
+    GreaterThan {
+        other_field: String,
+    },
+}
+
+impl ValidationRule {
+    /// The other field a cross-field rule refers to.
+    pub fn other_field(&self) -> Option<&str> {
+        match self {
+            ValidationRule::RequiresWhen { field, .. } => Some(field),
+            ValidationRule::LessThan { other_field }
+            | ValidationRule::GreaterThan { other_field } => Some(other_field),
+            _ => None,
+        }
+    }
+
+    fn requires(&self, config: &Value) -> bool {
+        match self {
+            ValidationRule::Required => true,
+            ValidationRule::RequiresWhen { field, equals } => config.get(field) == Some(equals),
+            _ => false,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+#[error("{key}: {message}")]
+pub struct ValidationError {
+    pub key: String,
+    pub message: String,
+}
+
+impl UISchema {
+    /// Checks value types, field bounds and rules of every field, and flags
+    /// keys the schema does not know. Hidden fields are never required.
+    ///
+    /// `Custom` rules are skipped; use [`UISchema::validate_with`] to run them.
+    pub fn validate(&self, config: &Value) -> Vec<ValidationError> {
+        self.validate_with(config, &HashMap::new())
+    }
+
+    /// Like [`UISchema::validate`], running `Custom` rules through the
+    /// validator registered under their key. Unregistered keys are skipped.
+    pub fn validate_with(
+        &self,
+        config: &Value,
+        custom: &HashMap<String, Validator>,
+    ) -> Vec<ValidationError> {
+        let fields = self.value_fields();
+        let mut errors = Vec::new();
+        for field in &fields {
+            if let Err(message) = validate_field(field, config, custom) {
+                errors.push(ValidationError {
+                    key: field.key.clone(),
+                    message,
+                });
+            }
+        }
+        if let Some(object) = config.as_object() {
+            for key in object.keys() {
+                if !fields.iter().any(|f| &f.key == key) {
+                    errors.push(ValidationError {
+                        key: key.clone(),
+                        message: "unknown key".to_string(),
+                    });
+                }
+            }
+        }
+        errors
+    }
+}
+
+fn validate_field(
+    field: &ConfigField,
+    config: &Value,
+    custom: &HashMap<String, Validator>,
+) -> Result<(), String> {
+    let Some(value) = config.get(&field.key) else {
+        if field.rules.iter().any(|rule| rule.requires(config)) && field.is_visible(config) {
+            return Err("missing required key".to_string());
+        }
+        return Ok(());
+    };
+    check_type(&field.field_type, value, custom)?;
+    for rule in &field.rules {
+        check_rule(rule, value, config, custom)?;
+    }
+    Ok(())
+}
+
+fn check_type(
+    field_type: &FieldType,
+    value: &Value,
+    custom: &HashMap<String, Validator>,
+) -> Result<(), String> {
+    match field_type {
+        FieldType::Integer { min, max, unit, .. } => {
+            let value = value.as_i64().ok_or("expected an integer")?;
+            if min.is_some_and(|min| value < min) || max.is_some_and(|max| value > max) {
+                return Err(out_of_range(value, *min, *max, unit.as_deref()));
+            }
+        }
+        FieldType::Float { min, max, unit, .. } => {
+            let value = value.as_f64().ok_or("expected a number")?;
+            if min.is_some_and(|min| value < min) || max.is_some_and(|max| value > max) {
+                return Err(out_of_range(value, *min, *max, unit.as_deref()));
+            }
+        }
+        FieldType::Boolean => {
+            value.as_bool().ok_or("expected a boolean")?;
+        }
+        FieldType::Text { max_length, .. } => {
+            let value = value.as_str().ok_or("expected a string")?;
+            if let Some(max) = max_length.filter(|&max| value.chars().count() > max) {
+                return Err(format!("longer than {max} characters"));
+            }
+        }
+        FieldType::FilePath { .. } => {
+            value.as_str().ok_or("expected a path")?;
+        }
+        FieldType::Secret => {
+            value.as_str().ok_or("expected a string")?;
+        }
+        FieldType::Duration { unit, min, max } => {
+            let value = value
+                .as_u64()
+                .ok_or_else(|| format!("expected a whole number of {}", unit.symbol()))?;
+            if min.is_some_and(|min| value < min) || max.is_some_and(|max| value > max) {
+                return Err(out_of_range(value, *min, *max, Some(unit.symbol())));
+            }
+        }
+        FieldType::DateTime => {
+            let value = value.as_str().ok_or("expected a timestamp")?;
+            if !is_rfc3339(value) {
+                return Err(format!("{value:?} is not an RFC 3339 timestamp"));
+            }
+        }
+        FieldType::Choice {
+            options,
+            options_source,
+        } => {
+            let value = value.as_str().ok_or("expected a string")?;
+            // Runtime options can change after the schema was read, so only
+            // the plugin can tell whether the value is still available.
+            if options_source.is_static() && !options.iter().any(|o| o.value == value) {
+                let values: Vec<&str> = options.iter().map(|o| o.value.as_str()).collect();
+                return Err(format!("expected one of {}", values.join(", ")));
+            }
+        }
+        FieldType::DynamicList {
+            item_type,
+            min_items,
+            max_items,
+            ..
+        } => {
+            let items = value.as_array().ok_or("expected a list")?;
+            if let Some(min) = min_items.filter(|&min| items.len() < min) {
+                return Err(format!("too few items (minimum {min})"));
+            }
+            if let Some(max) = max_items.filter(|&max| items.len() > max) {
+                return Err(format!("too many items (maximum {max})"));
+            }
+            for (index, item) in items.iter().enumerate() {
+                let result = match &**item_type {
+                    FieldType::Group { fields, .. } => check_item(fields, item, custom),
+                    item_type => check_type(item_type, item, custom),
+                };
+                result.map_err(|e| format!("item {index}: {e}"))?;
+            }
+        }
+        FieldType::Table {
+            columns,
+            min_rows,
+            max_rows,
+        } => {
+            let rows = value.as_array().ok_or("expected a list of rows")?;
+            if let Some(min) = min_rows.filter(|&min| rows.len() < min) {
+                return Err(format!("too few rows (minimum {min})"));
+            }
+            if let Some(max) = max_rows.filter(|&max| rows.len() > max) {
+                return Err(format!("too many rows (maximum {max})"));
+            }
+            for (index, row) in rows.iter().enumerate() {
+                check_row(columns, row, custom).map_err(|e| format!("row {index}: {e}"))?;
+            }
+        }
+        FieldType::Group { .. } => return Err("groups do not hold values".to_string()),
+        FieldType::Action { .. } => return Err("actions do not hold values".to_string()),
+        FieldType::Computed { .. } => return Err("computed fields do not hold values".to_string()),
+        FieldType::Info { .. } => return Err("info blocks do not hold values".to_string()),
+    }
+    Ok(())
+}
+
+// An item of a structured list, checked like a configuration of its own.
+fn check_item(
+    fields: &[ConfigField],
+    item: &Value,
+    custom: &HashMap<String, Validator>,
+) -> Result<(), String> {
+    let object = item.as_object().ok_or("expected an object")?;
+    for field in fields {
+        validate_field(field, item, custom).map_err(|e| format!("{}: {e}", field.key))?;
+    }
+    match object
+        .keys()
+        .find(|key| !fields.iter().any(|f| &&f.key == key))
+    {
+        Some(key) => Err(format!("{key}: unknown key")),
+        None => Ok(()),
+    }
+}
+
+fn check_row(
+    columns: &[ColumnSpec],
+    row: &Value,
+    custom: &HashMap<String, Validator>,
+) -> Result<(), String> {
+    let cells = row.as_array().ok_or("expected a list of cells")?;
+    if cells.len() != columns.len() {
+        return Err(format!(
+            "expected {} cells, got {}",
+            columns.len(),
+            cells.len()
+        ));
+    }
+    for (column, cell) in columns.iter().zip(cells) {
+        if let FieldType::DynamicList { .. }
+        | FieldType::Table { .. }
+        | FieldType::Group { .. }
+        | FieldType::Action { .. }
+        | FieldType::Computed { .. }
+        | FieldType::Info { .. } = column.cell_type
+        {
+            return Err(format!("{}: columns hold scalar values", column.key));
+        }
+        check_type(&column.cell_type, cell, custom).map_err(|e| format!("{}: {e}", column.key))?;
+    }
+    Ok(())
+}
+
+pub(crate) fn is_rfc3339(value: &str) -> bool {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let pattern = PATTERN.get_or_init(|| {
+        Regex::new(
+            r"^\d{4}-(0[1-9]|1[0-2])-(0[1-9]|[12]\d|3[01])[Tt ]([01]\d|2[0-3]):[0-5]\d:([0-5]\d|60)(\.\d+)?([Zz]|[+-]([01]\d|2[0-3]):[0-5]\d)$",
+        )
+        .expect("valid pattern")
+    });
+    pattern.is_match(value)
+}
+
+// e.g. "25000 Hz is out of range 20..=20000 Hz"; open bounds are left out.
+fn out_of_range<T: std::fmt::Display>(
+    value: T,
+    min: Option<T>,
+    max: Option<T>,
+    unit: Option<&str>,
+) -> String {
+    let unit = unit.map(|unit| format!(" {unit}")).unwrap_or_default();
+    let min = min.map(|min| min.to_string()).unwrap_or_default();
+    let max = match max {
+        Some(max) => format!("={max}"),
+        None => String::new(),
+    };
+    format!("{value}{unit} is out of range {min}..{max}{unit}")
+}
+
+// `config` holds the other fields of cross-field rules: the whole
+// configuration, or the item for fields of structured list items.
+fn check_rule(
+    rule: &ValidationRule,
+    value: &Value,
+    config: &Value,
+    custom: &HashMap<String, Validator>,
+) -> Result<(), String> {
+    match rule {
+        ValidationRule::Required | ValidationRule::RequiresWhen { .. } => {}
+        ValidationRule::Regex { pattern } => {
+            let regex =
+                Regex::new(pattern).map_err(|e| format!("invalid pattern {pattern:?}: {e}"))?;
+            let text = value.as_str().ok_or("expected a string")?;
+            if !regex.is_match(text) {
+                return Err(format!("does not match {pattern:?}"));
+            }
+        }
+        ValidationRule::Range { min, max } => {
+            let number = value.as_f64().ok_or("expected a number")?;
+            if min.is_some_and(|min| number < min) || max.is_some_and(|max| number > max) {
+                return Err(out_of_range(number, *min, *max, None));
+            }
+        }
+        ValidationRule::OneOf { values } => {
+            if !values.contains(value) {
+                return Err(format!("{value} is not an allowed value"));
+            }
+        }
+        ValidationRule::MinLength { min } => {
+            let length = match value {
+                Value::String(s) => s.chars().count(),
+                Value::Array(items) => items.len(),
+                _ => return Err("expected a string or a list".to_string()),
+            };
+            if length < *min {
+                return Err(format!("shorter than {min}"));
+            }
+        }
+        ValidationRule::Custom { key } => {
+            if let Some(validator) = custom.get(key) {
+                (validator.validate_fn)(value)?;
+            }
+        }
+        ValidationRule::LessThan { other_field } | ValidationRule::GreaterThan { other_field } => {
+            let Some(other) = config.get(other_field).and_then(Value::as_f64) else {
+                return Ok(());
+            };
+            let number = value.as_f64().ok_or("expected a number")?;
+            let (holds, relation) = match rule {
+                ValidationRule::LessThan { .. } => (number < other, "less"),
+                _ => (number > other, "greater"),
+            };
+            if !holds {
+                return Err(format!("must be {relation} than {other_field} ({other})"));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::TimeUnit;
+    use serde_json::json;
+
+    fn schema() -> UISchema {
+        UISchema::new()
+            .field(
+                ConfigField::text("name", "Name")
+                    .required()
+                    .rule(ValidationRule::Regex {
+                        pattern: "^[a-z_]+$".into(),
+                    }),
+            )
+            .field(ConfigField::integer("count", "Count").min(0).max(10))
+            .field(
+                ConfigField::dynamic_list("columns", "Columns")
+                    .rule(ValidationRule::MinLength { min: 1 }),
+            )
+            .field(
+                ConfigField::text("device", "Device").rule(ValidationRule::Custom {
+                    key: "device_exists".into(),
+                }),
+            )
+    }
+
+    #[test]
+    fn reports_every_error() {
+        let errors = schema().validate(&json!({ "count": 11, "columns": [], "extra": 1 }));
+        let keys: Vec<&str> = errors.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, ["name", "count", "columns", "extra"]);
+        assert_eq!(errors[0].to_string(), "name: missing required key");
+
+        let errors = schema().validate(&json!({ "name": "Bad Name", "columns": ["x"] }));
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("does not match"));
+
+        let errors = schema().validate(&json!({ "name": "ok", "columns": ["x", 1] }));
+        assert_eq!(errors[0].message, "item 1: expected a string");
+
+        let cutoff = UISchema::new().field(
+            ConfigField::float("cutoff", "Cutoff")
+                .min_f(20.0)
+                .max_f(20_000.0)
+                .unit("Hz"),
+        );
+        let errors = cutoff.validate(&json!({ "cutoff": 25_000.0 }));
+        assert_eq!(errors[0].message, "25000 Hz is out of range 20..=20000 Hz");
+    }
+
+    #[test]
+    fn validates_durations_and_timestamps() {
+        let schema = UISchema::new()
+            .field(ConfigField::duration("length", "Length", TimeUnit::Seconds).max(60))
+            .field(ConfigField::datetime("start", "Start"));
+        let config = json!({ "length": 30, "start": "2024-05-01T08:30:00.5+02:00" });
+        assert!(schema.validate(&config).is_empty());
+
+        let message = |config: Value| schema.validate(&config)[0].message.clone();
+        assert_eq!(
+            message(json!({ "length": 90 })),
+            "90 s is out of range ..=60 s"
+        );
+        assert_eq!(
+            message(json!({ "length": 1.5 })),
+            "expected a whole number of s"
+        );
+        assert_eq!(
+            message(json!({ "start": "2024-13-01T00:00:00Z" })),
+            "\"2024-13-01T00:00:00Z\" is not an RFC 3339 timestamp"
+        );
+    }
+
+    #[test]
+    fn validates_table_rows() {
+        let schema = UISchema::new().field(
+            ConfigField::table("calibration", "Calibration")
+                .column(ColumnSpec::integer("raw", "Raw"))
+                .column(ColumnSpec::float("volts", "Volts"))
+                .max_rows(3),
+        );
+        assert_eq!(schema.defaults()["calibration"], json!([]));
+        let config = json!({ "calibration": [[0, 0.0], [4095, 3.3]] });
+        assert!(schema.validate(&config).is_empty());
+
+        let message = |config: Value| schema.validate(&config)[0].message.clone();
+        assert_eq!(
+            message(json!({ "calibration": [[0, 0.0], [4095]] })),
+            "row 1: expected 2 cells, got 1"
+        );
+        assert_eq!(
+            message(json!({ "calibration": [[0, "low"]] })),
+            "row 0: volts: expected a number"
+        );
+        assert_eq!(
+            message(json!({ "calibration": [[0, 0.0], [1, 0.1], [2, 0.2], [3, 0.3]] })),
+            "too many rows (maximum 3)"
+        );
+    }
+
+    #[test]
+    fn validates_structured_list_items() {
+        let schema = UISchema::new().field(
+            ConfigField::dynamic_list("channels", "Channels")
+                .item_fields([
+                    ConfigField::integer("channel", "Channel").min(0).required(),
+                    ConfigField::float("gain", "Gain"),
+                    ConfigField::text("label", "Label"),
+                ])
+                .min_items(1)
+                .max_items(2),
+        );
+        let rows = json!([{ "channel": 0, "gain": 1.5 }, { "channel": 3, "label": "aux" }]);
+        assert!(schema.validate(&json!({ "channels": rows })).is_empty());
+
+        let message = |config: Value| schema.validate(&config)[0].message.clone();
+        assert_eq!(
+            message(json!({ "channels": [] })),
+            "too few items (minimum 1)"
+        );
+        assert_eq!(
+            message(json!({ "channels": [{ "channel": 0 }, { "gain": 1.0 }] })),
+            "item 1: channel: missing required key"
+        );
+        assert_eq!(
+            message(json!({ "channels": [{ "channel": -1 }] })),
+            "item 0: channel: -1 is out of range 0.."
+        );
+        assert_eq!(
+            message(json!({ "channels": [{ "channel": 0, "mute": true }] })),
+            "item 0: mute: unknown key"
+        );
+    }
+
+    #[test]
+    fn custom_rules_use_registered_validators() {
+        let config = json!({ "name": "ok", "device": "/dev/null0" });
+        assert!(schema().validate(&config).is_empty());
+
+        let mut custom = HashMap::new();
+        custom.insert(
+            "device_exists".to_string(),
+            Validator {
+                validate_fn: |v| match v.as_str() {
+                    Some("/dev/null") => Ok(()),
+                    _ => Err("no such device".to_string()),
+                },
+            },
+        );
+        let errors = schema().validate_with(&config, &custom);
+        assert_eq!(errors[0].to_string(), "device: no such device");
+    }
+
+    #[test]
+    fn cross_field_rules() {
+        let schema = UISchema::new()
+            .field(
+                ConfigField::choice("transport", "Transport")
+                    .option("tcp", "TCP")
+                    .option("udp", "UDP"),
+            )
+            .field(ConfigField::integer("port", "Port").required_when("transport", "udp"))
+            .field(ConfigField::float("low_cutoff", "Low cutoff"))
+            .field(ConfigField::float("high_cutoff", "High cutoff").rule(
+                ValidationRule::GreaterThan {
+                    other_field: "low_cutoff".into(),
+                },
+            ));
+
+        assert!(schema.validate(&json!({ "transport": "tcp" })).is_empty());
+        assert_eq!(
+            schema.validate(&json!({ "transport": "udp" }))[0].to_string(),
+            "port: missing required key"
+        );
+        assert!(schema.validate(&json!({ "high_cutoff": 100.0 })).is_empty());
+        assert!(schema
+            .validate(&json!({ "low_cutoff": 20.0, "high_cutoff": 100.0 }))
+            .is_empty());
+        assert_eq!(
+            schema.validate(&json!({ "low_cutoff": 200.0, "high_cutoff": 100.0 }))[0].to_string(),
+            "high_cutoff: must be greater than low_cutoff (200)"
+        );
+    }
+
+    #[test]
+    fn rule_serialization() {
+        let json = serde_json::to_value(ValidationRule::Range {
+            min: Some(0.0),
+            max: None,
+        })
+        .unwrap();
+        assert_eq!(json, json!({ "rule": "range", "min": 0.0 }));
+
+        let field: ConfigField = serde_json::from_value(json!({
+            "key": "k",
+            "label": "K",
+            "type": { "kind": "boolean" },
+            "rules": [{ "rule": "required" }, { "rule": "custom", "key": "check" }],
+        }))
+        .unwrap();
+        assert_eq!(field.rules.len(), 2);
+    }
+}
@@ -0,0 +1,540 @@
+//! Plugins compiled to WebAssembly.
+//!
+//! A guest built for `wasm32-unknown-unknown` with the `wasm` feature exports
+//! its plugin with [`export_wasm_plugin!`]. The host loads it with
+//! [`WasmPluginLoader`] (feature `wasmtime`). Both sides speak the JSON
+//! messages of [`crate::remote`], passed through linear memory instead of a
+//! socket, so a [`WasmPlugin`] behaves like any other remote plugin.
+//!
+//! Guest exports, all without imports:
+//!
+//! - `rtsyn_wasm_abi() -> u32`: [`WASM_ABI_VERSION`]
+//! - `rtsyn_alloc(len: u32) -> u32`, `rtsyn_free(ptr: u32, len: u32)`
+//! - `rtsyn_create(id: u64) -> u32`: `0` on success
+//! - `rtsyn_call(ptr: u32, len: u32) -> u64`: takes one JSON `Request` and
+//!   returns the JSON `Response` packed as `ptr << 32 | len`, to be released
+//!   with `rtsyn_free`
+//!
+//! Every call into the guest runs under a deadline, see
+//! [`WasmPluginLoader::call_timeout`], so a guest stuck in a loop fails the
+//! call instead of hanging the host.
+
+pub const WASM_ABI_VERSION: u32 = 1;
+
+/// Helpers behind [`export_wasm_plugin!`]; not meant to be called directly.
+#[cfg(feature = "wasm")]
+#[doc(hidden)]
+pub mod guest {
+    use crate::remote::{RemotePluginServer, Request, Response};
+    use crate::{ErrorSeverity, Plugin};
+    use std::sync::Mutex;
+
+    pub fn alloc(len: usize) -> *mut u8 {
+        if len == 0 {
+            return std::ptr::null_mut();
+        }
+        Box::into_raw(vec![0u8; len].into_boxed_slice()) as *mut u8
+    }
+
+    /// # Safety
+    ///
+    /// `ptr` must come from [`alloc`] or [`pack`] with the same `len`.
+    pub unsafe fn free(ptr: *mut u8, len: usize) {
+        if !ptr.is_null() {
+            drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len)));
+        }
+    }
+
+    /// Leaks `bytes` and returns them as `ptr << 32 | len`.
+    pub fn pack(bytes: Vec<u8>) -> u64 {
+        let len = bytes.len();
+        let ptr = Box::into_raw(bytes.into_boxed_slice()) as *mut u8;
+        ((ptr as usize as u64) << 32) | len as u64
+    }
+
+    pub struct Instance<P: Plugin>(Mutex<Option<RemotePluginServer<P>>>);
+
+    impl<P: Plugin> Instance<P> {
+        pub const fn new() -> Self {
+            Self(Mutex::new(None))
+        }
+
+        pub fn create(&self, server: RemotePluginServer<P>) -> u32 {
+            match self.0.lock() {
+                Ok(mut slot) => {
+                    *slot = Some(server);
+                    0
+                }
+                Err(_) => 1,
+            }
+        }
+
+        /// Answers one JSON request; malformed requests and calls before
+        /// `rtsyn_create` are answered with a fatal error.
+        pub fn call(&self, request: &[u8]) -> Vec<u8> {
+            let response = match (self.0.lock(), serde_json::from_slice::<Request>(request)) {
+                (Ok(mut slot), Ok(request)) => match slot.as_mut() {
+                    Some(server) => server.handle(request),
+                    None => fatal("plugin was not created".to_string()),
+                },
+                (Err(_), _) => fatal("plugin panicked".to_string()),
+                (_, Err(e)) => fatal(format!("malformed request: {e}")),
+            };
+            serde_json::to_vec(&response).unwrap_or_default()
+        }
+    }
+
+    impl<P: Plugin> Default for Instance<P> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    fn fatal(message: String) -> Response {
+        Response::Error {
+            severity: ErrorSeverity::Fatal,
+            message,
+        }
+    }
+}
+
+/// Exports a plugin from a `wasm32-unknown-unknown` cdylib.
+///
+/// Arguments are the plugin type, a `fn(PluginId) -> P` constructor and the
+/// input/output accessors taken by
+/// [`RemotePluginServer::new`](crate::remote::RemotePluginServer::new).
+///
+/// ```ignore
+/// rtsyn_plugin::export_wasm_plugin!(
+///     Gain,
+///     Gain::new,
+///     |p, name, v| p.ports.set_input_by_name(name, v),
+///     |p, name| p.ports.output_by_name(name)
+/// );
+/// ```
+#[cfg(feature = "wasm")]
+#[macro_export]
+macro_rules! export_wasm_plugin {
+    ($plugin:ty, $new:expr, $set_input:expr, $get_output:expr) => {
+        static RTSYN_WASM_INSTANCE: $crate::wasm::guest::Instance<$plugin> =
+            $crate::wasm::guest::Instance::new();
+
+        #[no_mangle]
+        pub extern "C" fn rtsyn_wasm_abi() -> u32 {
+            $crate::wasm::WASM_ABI_VERSION
+        }
+
+        #[no_mangle]
+        pub extern "C" fn rtsyn_alloc(len: usize) -> *mut u8 {
+            $crate::wasm::guest::alloc(len)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn rtsyn_free(ptr: *mut u8, len: usize) {
+            $crate::wasm::guest::free(ptr, len)
+        }
+
+        #[no_mangle]
+        pub extern "C" fn rtsyn_create(id: u64) -> u32 {
+            let new: fn($crate::PluginId) -> $plugin = $new;
+            let set_input: fn(&mut $plugin, &str, f64) = $set_input;
+            let get_output: fn(&$plugin, &str) -> f64 = $get_output;
+            let server = $crate::remote::RemotePluginServer::new(
+                new($crate::PluginId(id)),
+                set_input,
+                get_output,
+            );
+            RTSYN_WASM_INSTANCE.create(server)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn rtsyn_call(ptr: *const u8, len: usize) -> u64 {
+            let request = if ptr.is_null() {
+                &[][..]
+            } else {
+                ::std::slice::from_raw_parts(ptr, len)
+            };
+            $crate::wasm::guest::pack(RTSYN_WASM_INSTANCE.call(request))
+        }
+    };
+}
+
+#[cfg(feature = "wasmtime")]
+pub use host::{WasmChannel, WasmError, WasmPlugin, WasmPluginLoader};
+
+#[cfg(feature = "wasmtime")]
+mod host {
+    use super::WASM_ABI_VERSION;
+    use crate::remote::{RemoteError, RemotePluginClient};
+    use crate::PluginId;
+    use std::io::{self, Read, Write};
+    use std::path::Path;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use wasmtime::{Config, Engine, Instance, Memory, Module, Store, TypedFunc};
+
+    // Granularity of call deadlines: the engine epoch advances this often.
+    const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+    #[derive(thiserror::Error, Debug)]
+    pub enum WasmError {
+        #[error("wasm runtime error: {0}")]
+        Runtime(#[from] wasmtime::Error),
+        #[error("module does not export {0}")]
+        MissingExport(&'static str),
+        #[error("wasm ABI version {0} is not supported")]
+        Version(u32),
+        #[error("rtsyn_create failed with code {0}")]
+        Create(u32),
+        #[error(transparent)]
+        Remote(#[from] RemoteError),
+    }
+
+    /// A WASM plugin instance; see [`WasmPluginLoader::instantiate`].
+    pub type WasmPlugin = RemotePluginClient<WasmChannel, WasmChannel>;
+
+    /// Compiled guest module, instantiated once per plugin.
+    pub struct WasmPluginLoader {
+        engine: Engine,
+        module: Module,
+        ticker: Arc<EpochTicker>,
+        deadline_ticks: u64,
+    }
+
+    impl WasmPluginLoader {
+        pub fn from_bytes(bytes: &[u8]) -> Result<Self, WasmError> {
+            let engine = engine()?;
+            let module = Module::from_binary(&engine, bytes)?;
+            Self::new(engine, module)
+        }
+
+        pub fn from_file(path: impl AsRef<Path>) -> Result<Self, WasmError> {
+            let engine = engine()?;
+            let module = Module::from_file(&engine, path)?;
+            Self::new(engine, module)
+        }
+
+        fn new(engine: Engine, module: Module) -> Result<Self, WasmError> {
+            Ok(Self {
+                ticker: EpochTicker::start(&engine)?,
+                engine,
+                module,
+                deadline_ticks: 100,
+            })
+        }
+
+        /// How long one call into the guest may run before it traps, one
+        /// second by default. Applies to instances created afterwards.
+        pub fn call_timeout(mut self, timeout: Duration) -> Self {
+            self.deadline_ticks = (timeout.as_millis() / EPOCH_TICK.as_millis()).max(1) as u64;
+            self
+        }
+
+        /// Creates a fresh module instance with its own memory and runs the
+        /// handshake, so each plugin is isolated from the others.
+        pub fn instantiate(&self, id: PluginId) -> Result<WasmPlugin, WasmError> {
+            let mut store = Store::new(&self.engine, ());
+            store.set_epoch_deadline(self.deadline_ticks);
+            let instance = Instance::new(&mut store, &self.module, &[])?;
+            let memory = instance
+                .get_memory(&mut store, "memory")
+                .ok_or(WasmError::MissingExport("memory"))?;
+            let abi: TypedFunc<(), u32> = func(&instance, &mut store, "rtsyn_wasm_abi")?;
+            let version = abi.call(&mut store, ())?;
+            if version != WASM_ABI_VERSION {
+                return Err(WasmError::Version(version));
+            }
+            let create: TypedFunc<u64, u32> = func(&instance, &mut store, "rtsyn_create")?;
+            let code = create.call(&mut store, id.0)?;
+            if code != 0 {
+                return Err(WasmError::Create(code));
+            }
+            let guest = Guest {
+                alloc: func(&instance, &mut store, "rtsyn_alloc")?,
+                free: func(&instance, &mut store, "rtsyn_free")?,
+                call: func(&instance, &mut store, "rtsyn_call")?,
+                store,
+                memory,
+                request: Vec::new(),
+                response: io::Cursor::new(Vec::new()),
+                deadline_ticks: self.deadline_ticks,
+                _ticker: self.ticker.clone(),
+            };
+            let channel = WasmChannel(Arc::new(Mutex::new(guest)));
+            Ok(RemotePluginClient::new(id, channel.clone(), channel)?)
+        }
+    }
+
+    fn engine() -> Result<Engine, WasmError> {
+        let mut config = Config::new();
+        config.epoch_interruption(true);
+        Ok(Engine::new(&config)?)
+    }
+
+    // Advances the engine epoch until the loader and all its instances are
+    // gone.
+    struct EpochTicker(Arc<AtomicBool>);
+
+    impl EpochTicker {
+        fn start(engine: &Engine) -> Result<Arc<Self>, WasmError> {
+            let stop = Arc::new(AtomicBool::new(false));
+            let (engine, stopped) = (engine.clone(), stop.clone());
+            std::thread::Builder::new()
+                .name("rtsyn-wasm-epoch".to_string())
+                .spawn(move || {
+                    while !stopped.load(Ordering::Relaxed) {
+                        std::thread::sleep(EPOCH_TICK);
+                        engine.increment_epoch();
+                    }
+                })
+                .map_err(RemoteError::from)?;
+            Ok(Arc::new(Self(stop)))
+        }
+    }
+
+    impl Drop for EpochTicker {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn func<P, R>(
+        instance: &Instance,
+        store: &mut Store<()>,
+        name: &'static str,
+    ) -> Result<TypedFunc<P, R>, WasmError>
+    where
+        P: wasmtime::WasmParams,
+        R: wasmtime::WasmResults,
+    {
+        instance
+            .get_typed_func(store, name)
+            .map_err(|_| WasmError::MissingExport(name))
+    }
+
+    struct Guest {
+        store: Store<()>,
+        memory: Memory,
+        alloc: TypedFunc<u32, u32>,
+        free: TypedFunc<(u32, u32), ()>,
+        call: TypedFunc<(u32, u32), u64>,
+        request: Vec<u8>,
+        response: io::Cursor<Vec<u8>>,
+        deadline_ticks: u64,
+        _ticker: Arc<EpochTicker>,
+    }
+
+    impl Guest {
+        /// Passes the buffered request line to `rtsyn_call` and buffers the
+        /// response line for reading.
+        fn exchange(&mut self) -> Result<(), wasmtime::Error> {
+            self.store.set_epoch_deadline(self.deadline_ticks);
+            let request = std::mem::take(&mut self.request);
+            let len = request.len() as u32;
+            let ptr = self.alloc.call(&mut self.store, len)?;
+            self.memory.write(&mut self.store, ptr as usize, &request)?;
+            let packed = self.call.call(&mut self.store, (ptr, len));
+            self.free.call(&mut self.store, (ptr, len))?;
+            let packed = packed?;
+            let (ptr, len) = ((packed >> 32) as u32, packed as u32);
+            if ptr as usize + len as usize > self.memory.data_size(&self.store) {
+                return Err(wasmtime::Error::msg(format!(
+                    "response at {ptr:#x} with {len} bytes lies outside guest memory"
+                )));
+            }
+            let mut response = vec![0; len as usize];
+            self.memory.read(&self.store, ptr as usize, &mut response)?;
+            self.free.call(&mut self.store, (ptr, len))?;
+            response.push(b'\n');
+            self.response = io::Cursor::new(response);
+            Ok(())
+        }
+    }
+
+    /// Byte stream into a guest instance: each line written is one call to
+    /// `rtsyn_call`, whose response is then available for reading.
+    #[derive(Clone)]
+    pub struct WasmChannel(Arc<Mutex<Guest>>);
+
+    impl WasmChannel {
+        fn guest(&self) -> io::Result<std::sync::MutexGuard<'_, Guest>> {
+            self.0
+                .lock()
+                .map_err(|_| io::Error::other("wasm instance poisoned"))
+        }
+    }
+
+    impl Write for WasmChannel {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.guest()?.request.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            let mut guest = self.guest()?;
+            let Some(end) = guest.request.iter().position(|b| *b == b'\n') else {
+                return Ok(());
+            };
+            guest.request.truncate(end);
+            // A trap leaves the instance in an unknown state; reporting it as
+            // an io error makes the client fail with a fatal error.
+            guest.exchange().map_err(io::Error::other)
+        }
+    }
+
+    impl Read for WasmChannel {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.guest()?.response.read(buf)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn guest_answers_protocol_messages() {
+        use super::guest::{self, Instance};
+        use crate::ports::StaticPorts;
+        use crate::remote::{RemotePluginServer, Response};
+        use crate::{Plugin, PluginContext, PluginError, PluginId, PluginMeta, Port};
+
+        struct Gain {
+            meta: PluginMeta,
+            ports: StaticPorts<1, 1>,
+        }
+
+        impl Plugin for Gain {
+            fn id(&self) -> PluginId {
+                PluginId(1)
+            }
+            fn meta(&self) -> &PluginMeta {
+                &self.meta
+            }
+            fn inputs(&self) -> &[Port] {
+                self.ports.inputs()
+            }
+            fn outputs(&self) -> &[Port] {
+                self.ports.outputs()
+            }
+            fn process(&mut self, _ctx: &mut PluginContext) -> Result<(), PluginError> {
+                self.ports.set_output(0, self.ports.input(0) * 2.0);
+                Ok(())
+            }
+        }
+
+        let instance = Instance::new();
+        let response: Response =
+            serde_json::from_slice(&instance.call(br#"{"type":"describe"}"#)).unwrap();
+        assert!(matches!(response, Response::Error { .. }));
+
+        let gain = Gain {
+            meta: PluginMeta::new("gain"),
+            ports: StaticPorts::new(["in"], ["out"]),
+        };
+        let server = RemotePluginServer::new(
+            gain,
+            |p, name, v| p.ports.set_input_by_name(name, v),
+            |p, name| p.ports.output_by_name(name),
+        );
+        assert_eq!(instance.create(server), 0);
+        instance.call(br#"{"type":"set_input","name":"in","value":1.5}"#);
+        instance.call(br#"{"type":"process","tick":0,"period_seconds":0.001,"connected_inputs":1,"connected_outputs":1}"#);
+        let response: Response =
+            serde_json::from_slice(&instance.call(br#"{"type":"get_output","name":"out"}"#))
+                .unwrap();
+        assert!(matches!(response, Response::Output { value } if value == 3.0));
+
+        let ptr = guest::alloc(16);
+        unsafe { guest::free(ptr, 16) };
+    }
+
+    #[cfg(feature = "wasmtime")]
+    #[test]
+    fn loader_rejects_invalid_module() {
+        assert!(super::WasmPluginLoader::from_bytes(b"not wasm").is_err());
+    }
+
+    // A guest module with one page of memory whose `rtsyn_call` body is
+    // `call`; the other exports succeed trivially.
+    #[cfg(feature = "wasmtime")]
+    fn guest_module(call: &[u8]) -> Vec<u8> {
+        fn section(id: u8, contents: &[u8]) -> Vec<u8> {
+            let mut section = vec![id, contents.len() as u8];
+            section.extend_from_slice(contents);
+            section
+        }
+        let mut types = vec![5];
+        types.extend_from_slice(&[0x60, 0, 1, 0x7f]); // () -> i32
+        types.extend_from_slice(&[0x60, 1, 0x7f, 1, 0x7f]); // (i32) -> i32
+        types.extend_from_slice(&[0x60, 2, 0x7f, 0x7f, 0]); // (i32, i32)
+        types.extend_from_slice(&[0x60, 1, 0x7e, 1, 0x7f]); // (i64) -> i32
+        types.extend_from_slice(&[0x60, 2, 0x7f, 0x7f, 1, 0x7e]); // (i32, i32) -> i64
+        let mut exports = vec![6];
+        for (index, name) in [
+            "memory",
+            "rtsyn_wasm_abi",
+            "rtsyn_alloc",
+            "rtsyn_free",
+            "rtsyn_create",
+            "rtsyn_call",
+        ]
+        .iter()
+        .enumerate()
+        {
+            exports.push(name.len() as u8);
+            exports.extend_from_slice(name.as_bytes());
+            // The memory is export 0, functions 0..5 follow it.
+            exports.extend_from_slice(&match index {
+                0 => [2, 0],
+                _ => [0, index as u8 - 1],
+            });
+        }
+        let mut code = vec![5];
+        for body in [
+            &[0, 0x41, 1, 0x0b][..], // abi: 1
+            &[0, 0x41, 0, 0x0b],     // alloc: 0
+            &[0, 0x0b],              // free
+            &[0, 0x41, 0, 0x0b],     // create: 0
+            call,
+        ] {
+            code.push(body.len() as u8);
+            code.extend_from_slice(body);
+        }
+        let mut module = b"\0asm\x01\0\0\0".to_vec();
+        module.extend(section(1, &types));
+        module.extend(section(3, &[5, 0, 1, 2, 3, 4]));
+        module.extend(section(5, &[1, 0, 1]));
+        module.extend(section(7, &exports));
+        module.extend(section(10, &code));
+        module
+    }
+
+    #[cfg(feature = "wasmtime")]
+    #[test]
+    fn looping_guest_times_out() {
+        use std::time::{Duration, Instant};
+
+        // loop { br 0 } i64.const 0
+        let module = guest_module(&[0, 0x03, 0x40, 0x0c, 0, 0x0b, 0x42, 0, 0x0b]);
+        let loader = super::WasmPluginLoader::from_bytes(&module)
+            .unwrap()
+            .call_timeout(Duration::from_millis(50));
+        let started = Instant::now();
+        assert!(loader.instantiate(crate::PluginId(1)).is_err());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(feature = "wasmtime")]
+    #[test]
+    fn response_outside_memory_is_rejected() {
+        // i64.const 0xffff_ffff: 4 GiB at address 0
+        let module = guest_module(&[0, 0x42, 0xff, 0xff, 0xff, 0xff, 0x0f, 0x0b]);
+        let loader = super::WasmPluginLoader::from_bytes(&module).unwrap();
+        let Err(err) = loader.instantiate(crate::PluginId(1)) else {
+            panic!("guest response outside memory accepted");
+        };
+        assert!(err.to_string().contains("outside guest memory"), "{err}");
+    }
+}
@@ -1,4 +1,10 @@
-use rtsyn_plugin::{Plugin, PluginContext, PluginError, PluginId, PluginMeta, Port, PortId};
+use rtsyn_plugin::config::{ConfigDelta, ConfigTracker};
+use rtsyn_plugin::i18n::Translations;
+use rtsyn_plugin::ports;
+use rtsyn_plugin::{
+    Capability, DisplayMeta, ErrorSeverity, HostInfo, Icon, Plugin, PluginCategory, PluginContext,
+    PluginError, PluginId, PluginMeta, PluginUid, Port, PortId,
+};
 use serde_json::json;
 
 struct DummyPlugin {
@@ -15,15 +21,22 @@ impl DummyPlugin {
             id: PluginId(id),
             meta: PluginMeta {
                 name: "dummy".to_string(),
+                uid: None,
                 fixed_vars: vec![("fixed".to_string(), json!(1))],
                 default_vars: vec![("default".to_string(), json!(2))],
+                version: None,
+                author: None,
+                description: None,
+                license: None,
+                homepage: None,
+                category: PluginCategory::Other,
+                tags: Vec::new(),
+                capabilities: Vec::new(),
+                display: None,
+                translations: Translations::new(),
             },
-            inputs: vec![Port {
-                id: PortId("in".to_string()),
-            }],
-            outputs: vec![Port {
-                id: PortId("out".to_string()),
-            }],
+            inputs: vec![Port::new("in")],
+            outputs: vec![Port::new("out")],
             calls: 0,
         }
     }
@@ -50,6 +63,30 @@ impl Plugin for DummyPlugin {
         self.calls += 1;
         Ok(())
     }
+
+    fn save_state(&self) -> Result<serde_json::Value, PluginError> {
+        Ok(json!({ "calls": self.calls }))
+    }
+
+    fn restore_state(&mut self, state: serde_json::Value) -> Result<(), PluginError> {
+        self.calls = state["calls"]
+            .as_u64()
+            .ok_or_else(|| PluginError::Config("missing calls".to_string()))?
+            as usize;
+        Ok(())
+    }
+
+    fn on_config_changed(&mut self, changed: &ConfigDelta) -> Result<(), PluginError> {
+        if changed.changed("calls") {
+            self.calls = 0;
+        }
+        Ok(())
+    }
+
+    fn reset(&mut self) -> Result<(), PluginError> {
+        self.calls = 0;
+        Ok(())
+    }
 }
 
 #[test]
@@ -69,3 +106,331 @@ fn plugin_process_is_called() {
     plugin.process(&mut ctx).unwrap();
     assert_eq!(plugin.calls, 2);
 }
+
+#[test]
+fn plugin_state_roundtrip() {
+    let mut plugin = DummyPlugin::new(3);
+    let mut ctx = PluginContext::default();
+    plugin.process(&mut ctx).unwrap();
+    let state = plugin.save_state().unwrap();
+
+    let mut reloaded = DummyPlugin::new(3);
+    reloaded.restore_state(state).unwrap();
+    assert_eq!(reloaded.calls, 1);
+    assert!(reloaded.restore_state(json!({})).is_err());
+}
+
+#[test]
+fn plugin_reset_clears_state() {
+    let mut plugin = DummyPlugin::new(4);
+    let mut ctx = PluginContext::default();
+    plugin.on_start().unwrap();
+    plugin.process(&mut ctx).unwrap();
+    plugin.reset().unwrap();
+    assert_eq!(plugin.calls, 0);
+}
+
+#[test]
+fn plugin_meta_descriptive_fields() {
+    let meta = PluginMeta::new("lowpass")
+        .category(PluginCategory::Filter)
+        .tag("Audio")
+        .version("1.2.0")
+        .author("RTSyn")
+        .license("MIT");
+    assert!(meta.version_at_least(1, 1, 9));
+    assert!(!meta.version_at_least(2, 0, 0));
+    assert!(!PluginMeta::new("bare").version_at_least(0, 0, 0));
+
+    let json = serde_json::to_value(&meta).unwrap();
+    assert_eq!(json["version"], "1.2.0");
+    assert!(json.get("homepage").is_none());
+    assert_eq!(json["category"], "filter");
+    assert!(meta.has_tag("audio"));
+
+    let parsed: PluginMeta =
+        serde_json::from_value(json!({ "name": "old", "fixed_vars": [], "default_vars": [] }))
+            .unwrap();
+    assert_eq!(parsed.description, None);
+    assert_eq!(parsed.category, PluginCategory::Other);
+
+    let future: PluginMeta = serde_json::from_value(
+        json!({ "name": "new", "fixed_vars": [], "default_vars": [], "category": "quantum" }),
+    )
+    .unwrap();
+    assert_eq!(future.category, PluginCategory::Other);
+}
+
+#[test]
+fn plugin_meta_capabilities() {
+    let meta = PluginMeta::new("uploader")
+        .capability(Capability::FileRead)
+        .capability(Capability::Network)
+        .capability(Capability::Network);
+    assert_eq!(
+        meta.capabilities,
+        [Capability::FileRead, Capability::Network]
+    );
+    assert!(meta
+        .excess_capabilities(&[Capability::FileRead, Capability::Network])
+        .is_empty());
+    assert_eq!(
+        meta.excess_capabilities(&[Capability::FileRead]),
+        [Capability::Network]
+    );
+
+    let json = serde_json::to_value(&meta).unwrap();
+    assert_eq!(json["capabilities"], json!(["file_read", "network"]));
+    assert!(serde_json::to_value(PluginMeta::new("bare"))
+        .unwrap()
+        .get("capabilities")
+        .is_none());
+}
+
+#[test]
+fn plugin_meta_display() {
+    let meta = PluginMeta::new("usb_daq").display(
+        DisplayMeta::new()
+            .icon(Icon::Glyph { name: "usb".into() })
+            .color("#1f77b4"),
+    );
+    let json = serde_json::to_value(&meta).unwrap();
+    assert_eq!(
+        json["display"],
+        json!({ "icon": { "kind": "glyph", "name": "usb" }, "color": "#1f77b4" })
+    );
+    let parsed: PluginMeta = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed.display, meta.display);
+
+    assert_eq!(DisplayMeta::new().color("blue").color, None);
+}
+
+#[test]
+fn plugin_uid_is_reverse_domain() {
+    assert!(PluginUid::new("com.example.lowpass").is_valid());
+    assert!(PluginUid::new("org.rtsyn.csv-recorder").is_valid());
+    assert!(!PluginUid::new("lowpass").is_valid());
+    assert!(!PluginUid::new("com..lowpass").is_valid());
+    assert!(!PluginUid::new("com.9lives").is_valid());
+
+    let meta = PluginMeta::new("Lowpass").uid("com.example.lowpass");
+    let json = serde_json::to_value(&meta).unwrap();
+    assert_eq!(json["uid"], "com.example.lowpass");
+    let parsed: PluginMeta = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed.uid, Some(PluginUid::new("com.example.lowpass")));
+}
+
+#[test]
+fn host_info_version_and_features() {
+    let host = HostInfo::new("rtsyn", "1.4.2").feature("block_api");
+    assert!(host.version_at_least(1, 4, 0));
+    assert!(!host.version_at_least(1, 5, 0));
+    assert!(host.has_feature("block_api"));
+    assert!(!host.has_feature("gpu"));
+    assert!(!host.platform.is_empty());
+
+    let ctx = PluginContext {
+        host,
+        ..PluginContext::default()
+    };
+    assert_eq!(ctx.host.name, "rtsyn");
+}
+
+#[test]
+fn context_connection_masks() {
+    let ctx = PluginContext {
+        connected_inputs: 0b101,
+        connected_outputs: 1 << 63,
+        ..PluginContext::default()
+    };
+    assert_eq!(ctx.connected_input_count(), 2);
+    assert!(ctx.is_input_connected(2));
+    assert!(!ctx.is_input_connected(1));
+    assert!(ctx.is_output_connected(70));
+    assert_eq!(PluginContext::default().connected_output_count(), 0);
+}
+
+#[test]
+fn context_sample_rate_falls_back_to_tick_rate() {
+    let mut ctx = PluginContext {
+        period_seconds: 0.001,
+        ..PluginContext::default()
+    };
+    assert_eq!(ctx.effective_sample_rate(), 1000.0);
+    ctx.sample_rate = 48_000.0;
+    ctx.block_size = 64;
+    assert_eq!(ctx.effective_sample_rate(), 48_000.0);
+    assert_eq!(PluginContext::default().effective_sample_rate(), 0.0);
+}
+
+#[test]
+fn context_clock_stamps() {
+    let mut ctx = PluginContext::default();
+    assert_eq!(ctx.wall_time_ns(), 0);
+    ctx.stamp_now();
+    let first = ctx.monotonic_ns;
+    assert!(ctx.wall_time_ns() > 0);
+    ctx.stamp_now();
+    assert!(ctx.monotonic_ns >= first);
+}
+
+#[test]
+fn context_input_staleness() {
+    let mut ctx = PluginContext {
+        period_seconds: 0.01,
+        ..PluginContext::default()
+    };
+    ctx.tick = 5;
+    ctx.mark_input_updated(1);
+    ctx.tick = 15;
+
+    assert_eq!(ctx.last_update_tick(1), Some(5));
+    assert_eq!(ctx.input_age_ticks(1), Some(10));
+    assert!(!ctx.is_input_stale(1, 0.1));
+    assert!(ctx.is_input_stale(1, 0.05));
+    assert_eq!(ctx.last_update_tick(0), None);
+    assert!(ctx.is_input_stale(0, 1.0));
+}
+
+#[test]
+fn plugin_error_severity() {
+    assert_eq!(
+        PluginError::Config("bad".to_string()).severity(),
+        ErrorSeverity::Reconfigure
+    );
+    assert_eq!(
+        PluginError::Device {
+            code: 5,
+            message: "timeout".to_string()
+        }
+        .to_string(),
+        "device error 5: timeout"
+    );
+    let io: PluginError = std::io::Error::other("disk full").into();
+    assert_eq!(io.severity(), ErrorSeverity::Recoverable);
+    assert!(PluginError::Fatal("gone".to_string()).is_fatal());
+    assert!(!PluginError::Recoverable("busy".to_string()).is_fatal());
+}
+
+#[test]
+fn plugin_config_changed_receives_delta() {
+    let mut plugin = DummyPlugin::new(5);
+    let mut ctx = PluginContext::default();
+    plugin.process(&mut ctx).unwrap();
+
+    let mut tracker = ConfigTracker::new();
+    let delta = tracker.apply(&json!({ "other": 1 }));
+    plugin.on_config_changed(&delta).unwrap();
+    assert_eq!(plugin.calls, 1);
+
+    let delta = tracker.apply(&json!({ "calls": 0 }));
+    plugin.on_config_changed(&delta).unwrap();
+    assert_eq!(plugin.calls, 0);
+}
+
+#[test]
+fn port_clock_domain_json() {
+    use rtsyn_plugin::clock::{ClockDomain, Resampling};
+
+    let plain = serde_json::to_value(Port::new("in")).unwrap();
+    assert_eq!(plain, json!({ "id": "in" }));
+
+    let port = Port::new("frame")
+        .clock(ClockDomain::new("camera", 30.0))
+        .resampling(Resampling::Interpolate);
+    let value = serde_json::to_value(&port).unwrap();
+    assert_eq!(value["clock"], json!({ "name": "camera", "rate_hz": 30.0 }));
+    assert_eq!(value["resampling"], json!({ "type": "interpolate" }));
+
+    let parsed: Port = serde_json::from_value(json!({ "id": "legacy" })).unwrap();
+    assert!(parsed.clock.is_none());
+}
+
+#[test]
+fn port_metadata_json() {
+    let sensor = Port::new("temperature")
+        .description("Probe temperature")
+        .unit("°C")
+        .range(-40.0, 125.0);
+    let value = serde_json::to_value(&sensor).unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "id": "temperature",
+            "description": "Probe temperature",
+            "unit": "°C",
+            "range": [-40.0, 125.0],
+        })
+    );
+    let parsed: Port = serde_json::from_value(value).unwrap();
+    assert_eq!(parsed.range, Some((-40.0, 125.0)));
+
+    let alarm = Port::new("in").range(0.0, 100.0);
+    assert!(!sensor.range_fits(&alarm));
+    assert!(Port::new("out").range(10.0, 90.0).range_fits(&alarm));
+    assert!(sensor.range_fits(&Port::new("in")));
+}
+
+#[test]
+fn buses_connect_member_by_member() {
+    let outputs = [
+        Port::new("az").group("accel", 2),
+        Port::new("ax").group("accel", 0),
+        Port::new("temp"),
+        Port::new("ay").group("accel", 1),
+    ];
+    let inputs = [
+        Port::new("x").group("vec", 0),
+        Port::new("y").group("vec", 1),
+        Port::new("z").group("vec", 2),
+        Port::new("l").group("stereo", 0),
+        Port::new("r").group("stereo", 1),
+    ];
+    assert_eq!(ports::bus_names(&inputs), ["vec", "stereo"]);
+    let wired: Vec<(&str, &str)> = ports::connect_bus(&outputs, "accel", &inputs, "vec")
+        .unwrap()
+        .into_iter()
+        .map(|(from, to)| (from.0.as_str(), to.0.as_str()))
+        .collect();
+    assert_eq!(wired, [("ax", "x"), ("ay", "y"), ("az", "z")]);
+    assert!(ports::connect_bus(&outputs, "accel", &inputs, "stereo").is_none());
+    assert!(ports::connect_bus(&outputs, "gyro", &inputs, "vec").is_none());
+
+    let json = serde_json::to_value(&outputs[1]).unwrap();
+    assert_eq!(
+        json,
+        json!({ "id": "ax", "group": "accel", "group_index": 0 })
+    );
+}
+
+#[test]
+fn connection_requirements() {
+    let inputs = [
+        Port::new("signal").required(),
+        Port::new("trigger"),
+        Port::new("sum").max_connections(8),
+    ];
+    let wired = |id: &PortId| match id.0.as_str() {
+        "signal" => 1,
+        "sum" => 8,
+        _ => 0,
+    };
+    assert!(ports::connection_problems(&inputs, wired).is_empty());
+    let overloaded = |id: &PortId| match id.0.as_str() {
+        "trigger" => 2,
+        "sum" => 9,
+        _ => 0,
+    };
+    assert_eq!(
+        ports::connection_problems(&inputs, overloaded),
+        [
+            "required input signal is not connected",
+            "input trigger accepts at most 1 connection(s) but has 2",
+            "input sum accepts at most 8 connection(s) but has 9",
+        ]
+    );
+    assert_eq!(
+        serde_json::to_value(&inputs[0]).unwrap(),
+        json!({ "id": "signal", "required": true })
+    );
+}
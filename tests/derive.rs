@@ -0,0 +1,132 @@
+#![cfg(feature = "derive")]
+
+use rtsyn_plugin::prelude::*;
+use serde_json::json;
+use std::path::PathBuf;
+
+#[derive(Debug, UIConfig)]
+struct LoggerConfig {
+    #[ui(label = "Output File", hint = "Where samples are written")]
+    path: PathBuf,
+    #[ui(label = "Rate", min = 1, max = 1000, default = 100)]
+    rate: u32,
+    #[ui(label = "Gain", min = 0, max = 10.5, step = 0.5, default = 1.0)]
+    gain: f64,
+    #[ui(label = "Separator", max_length = 1, default = ",", read_only)]
+    separator: String,
+    enabled: bool,
+    columns: Vec<String>,
+    #[ui(skip)]
+    written: usize,
+}
+
+#[derive(Debug, UIConfig)]
+#[ui(schema_version = 2)]
+struct GainConfig {
+    #[ui(label = "Gain", unit = "dB", precision = 1, default = 0.0)]
+    gain_db: f64,
+}
+
+#[test]
+fn derive_generates_schema() {
+    let schema = LoggerConfig::ui_schema();
+    let keys: Vec<_> = schema.fields.iter().map(|f| f.key.as_str()).collect();
+    assert_eq!(
+        keys,
+        ["path", "rate", "gain", "separator", "enabled", "columns"]
+    );
+
+    assert_eq!(schema.fields[0].label, "Output File");
+    assert!(matches!(
+        schema.fields[0].field_type,
+        FieldType::FilePath { .. }
+    ));
+    assert_eq!(schema.fields[4].label, "enabled");
+    assert!(schema.fields[3].read_only);
+    assert!(!schema.fields[4].read_only);
+
+    if let FieldType::Integer { min, max, .. } = schema.fields[1].field_type {
+        assert_eq!(min, Some(1));
+        assert_eq!(max, Some(1000));
+    } else {
+        panic!("Expected Integer field type");
+    }
+    assert_eq!(schema.fields[1].default, Some(json!(100)));
+
+    if let FieldType::Float { max, step, .. } = schema.fields[2].field_type {
+        assert_eq!(max, Some(10.5));
+        assert_eq!(step, 0.5);
+    } else {
+        panic!("Expected Float field type");
+    }
+    assert!(matches!(
+        schema.fields[5].field_type,
+        FieldType::DynamicList { .. }
+    ));
+}
+
+#[test]
+fn derive_from_config_uses_defaults() {
+    let config = LoggerConfig::from_config(&json!({
+        "path": "/tmp/out.csv",
+        "enabled": true,
+        "columns": ["a", "b"],
+    }))
+    .unwrap();
+
+    assert_eq!(config.path, PathBuf::from("/tmp/out.csv"));
+    assert_eq!(config.rate, 100);
+    assert_eq!(config.gain, 1.0);
+    assert_eq!(config.separator, ",");
+    assert!(config.enabled);
+    assert_eq!(config.columns, ["a", "b"]);
+    assert_eq!(config.written, 0);
+}
+
+#[test]
+fn derive_from_config_reports_bad_fields() {
+    let err = LoggerConfig::from_config(&json!({ "rate": "fast" })).unwrap_err();
+    assert!(matches!(err, PluginError::Config(ref msg) if msg.starts_with("rate")));
+}
+
+#[test]
+fn derive_sets_schema_version() {
+    assert_eq!(LoggerConfig::ui_schema().schema_version, 0);
+    assert_eq!(GainConfig::ui_schema().schema_version, 2);
+    let gain = &GainConfig::ui_schema().fields[0];
+    assert_eq!(gain.display_value(&json!(-3)), "-3.0 dB");
+    assert_eq!(GainConfig::from_config(&json!({})).unwrap().gain_db, 0.0);
+}
+
+#[derive(Debug, Default, StaticPorts)]
+struct MixerPorts {
+    #[port(input)]
+    left: f64,
+    #[port(input, name = "right in")]
+    right: f64,
+    #[port(output)]
+    mix: f64,
+    gain: f64,
+}
+
+#[test]
+fn derive_generates_port_indices() {
+    assert_eq!(MixerPorts::IN_LEFT, 0);
+    assert_eq!(MixerPorts::IN_RIGHT, 1);
+    assert_eq!(MixerPorts::OUT_MIX, 0);
+    assert_eq!(MixerPorts::INPUTS, &["left", "right in"]);
+    assert_eq!(MixerPorts::OUTPUTS, &["mix"]);
+
+    let mut ports = MixerPorts::static_ports();
+    ports.set_input_by_name("right in", 2.0);
+    ports.set_input(MixerPorts::IN_LEFT, 1.0);
+
+    let mut io = MixerPorts {
+        gain: 0.5,
+        ..MixerPorts::default()
+    };
+    io.read_inputs(&ports);
+    io.mix = (io.left + io.right) * io.gain;
+    io.write_outputs(&mut ports);
+    assert_eq!(ports.output_by_name("mix"), 1.5);
+}
@@ -14,15 +14,22 @@ impl TestPlugin {
             id: PluginId(id),
             meta: PluginMeta {
                 name: "Test Plugin".to_string(),
+                uid: None,
                 fixed_vars: vec![],
                 default_vars: vec![("test_var".to_string(), Value::from(42))],
+                version: None,
+                author: None,
+                description: None,
+                license: None,
+                homepage: None,
+                category: PluginCategory::Other,
+                tags: Vec::new(),
+                capabilities: Vec::new(),
+                display: None,
+                translations: Translations::new(),
             },
-            inputs: vec![Port {
-                id: PortId("in_0".to_string()),
-            }],
-            outputs: vec![Port {
-                id: PortId("out_0".to_string()),
-            }],
+            inputs: vec![Port::new("in_0")],
+            outputs: vec![Port::new("out_0")],
         }
     }
 }
@@ -84,8 +91,12 @@ impl Plugin for TestPlugin {
             supports_restart: false,
             extendable_inputs: ExtendableInputs::Auto {
                 pattern: "in_{}".to_string(),
+                min: Some(1),
+                max: Some(4),
             },
+            extendable_outputs: ExtendableOutputs::Manual,
             loads_started: false,
+            ..PluginBehavior::default()
         }
     }
 
@@ -94,9 +105,7 @@ impl Plugin for TestPlugin {
     }
 
     fn on_input_added(&mut self, port: &str) -> Result<(), PluginError> {
-        self.inputs.push(Port {
-            id: PortId(port.to_string()),
-        });
+        self.inputs.push(Port::new(port));
         Ok(())
     }
 
@@ -104,6 +113,16 @@ impl Plugin for TestPlugin {
         self.inputs.retain(|p| p.id.0 != port);
         Ok(())
     }
+
+    fn on_output_added(&mut self, port: &str) -> Result<(), PluginError> {
+        self.outputs.push(Port::new(port));
+        Ok(())
+    }
+
+    fn on_output_removed(&mut self, port: &str) -> Result<(), PluginError> {
+        self.outputs.retain(|p| p.id.0 != port);
+        Ok(())
+    }
 }
 
 #[test]
@@ -169,7 +188,9 @@ fn plugin_behavior() {
     assert_eq!(
         behavior.extendable_inputs,
         ExtendableInputs::Auto {
-            pattern: "in_{}".to_string()
+            pattern: "in_{}".to_string(),
+            min: Some(1),
+            max: Some(4),
         }
     );
     assert!(!behavior.loads_started);
@@ -205,6 +226,23 @@ fn plugin_dynamic_inputs() {
     assert_eq!(plugin.inputs()[1].id.0, "in_2");
 }
 
+#[test]
+fn plugin_dynamic_outputs() {
+    let mut plugin = TestPlugin::new(1);
+    assert_eq!(
+        plugin.behavior().extendable_outputs,
+        ExtendableOutputs::Manual
+    );
+
+    plugin.on_output_added("out_1").unwrap();
+    assert_eq!(plugin.outputs().len(), 2);
+    assert_eq!(plugin.outputs()[1].id.0, "out_1");
+
+    plugin.on_output_removed("out_0").unwrap();
+    assert_eq!(plugin.outputs().len(), 1);
+    assert_eq!(plugin.outputs()[0].id.0, "out_1");
+}
+
 #[test]
 fn ui_schema_json_serialization() {
     let plugin = TestPlugin::new(1);
@@ -239,9 +277,7 @@ fn prelude_imports() {
     // Test that prelude brings everything into scope
     let _id = PluginId(1);
     let _port_id = PortId("test".to_string());
-    let _port = Port {
-        id: PortId("test".to_string()),
-    };
+    let _port = Port::new("test");
     let _ctx = PluginContext::default();
     let _behavior = PluginBehavior::default();
     let _conn_behavior = ConnectionBehavior::default();
@@ -260,8 +296,19 @@ fn default_plugin_behavior() {
         fn meta(&self) -> &PluginMeta {
             static META: PluginMeta = PluginMeta {
                 name: String::new(),
+                uid: None,
                 fixed_vars: Vec::new(),
                 default_vars: Vec::new(),
+                version: None,
+                author: None,
+                description: None,
+                license: None,
+                homepage: None,
+                category: PluginCategory::Other,
+                tags: Vec::new(),
+                capabilities: Vec::new(),
+                display: None,
+                translations: Translations::new(),
             };
             &META
         }
@@ -292,6 +339,21 @@ fn default_plugin_behavior() {
 
     // Test default lifecycle hooks
     let mut plugin = MinimalPlugin;
+    assert!(plugin.init_params(Value::Null).is_ok());
     assert!(plugin.on_input_added("test").is_ok());
     assert!(plugin.on_input_removed("test").is_ok());
+    assert!(plugin.on_output_added("test").is_ok());
+    assert!(plugin.on_output_removed("test").is_ok());
+    assert!(plugin.on_config_changed(&ConfigDelta::default()).is_ok());
+
+    // Test default state hooks
+    assert_eq!(plugin.save_state().unwrap(), Value::Null);
+    assert!(plugin.restore_state(Value::from(1)).is_ok());
+    assert!(plugin.on_start().is_ok());
+    assert!(plugin.on_pause().is_ok());
+    assert!(plugin.on_resume().is_ok());
+    assert!(plugin.on_stop().is_ok());
+    assert!(plugin.reset().is_ok());
+    assert_eq!(plugin.begin_shutdown().unwrap(), ShutdownStatus::Done);
+    assert!(plugin.poll_shutdown().unwrap().is_done());
 }
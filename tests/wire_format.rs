@@ -0,0 +1,111 @@
+use rtsyn_plugin::api_report::{self, ApiReport, TypeShape};
+use rtsyn_plugin::clock::{ClockDomain, Resampling};
+use rtsyn_plugin::ui::{ConfigField, FieldCondition, PluginBehavior, UISchema, ValidationRule};
+use rtsyn_plugin::{HostInfo, PluginMeta, Port};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+const REPORT_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/api/report.json");
+
+fn assert_struct_matches_report<T: Serialize>(name: &str, value: &T) {
+    let report = api_report::current();
+    let Some(TypeShape::Struct { fields }) = report.types.get(name) else {
+        panic!("{name} is not a struct in the api report");
+    };
+    let json = serde_json::to_value(value).unwrap();
+    let object = json.as_object().unwrap();
+    for key in object.keys() {
+        assert!(fields.contains_key(key), "{name}.{key} missing from report");
+    }
+    for (key, shape) in fields {
+        assert!(
+            shape.optional || object.contains_key(key),
+            "{name}.{key} is required in the report but not serialized"
+        );
+    }
+}
+
+#[test]
+fn report_matches_serialization() {
+    let meta = PluginMeta::new("lowpass")
+        .version("1.0.0")
+        .author("a")
+        .description("d")
+        .license("MIT")
+        .homepage("https://example.com");
+    assert_struct_matches_report("PluginMeta", &meta);
+    assert_struct_matches_report("PluginMeta", &PluginMeta::new("bare"));
+
+    let port = Port::new("in")
+        .clock(ClockDomain::new("daq", 1000.0))
+        .resampling(Resampling::SampleHold);
+    assert_struct_matches_report("Port", &port);
+    assert_struct_matches_report("HostInfo", &HostInfo::new("rtsyn", "1.0.0"));
+    assert_struct_matches_report("PluginBehavior", &PluginBehavior::default());
+
+    let field = ConfigField::text("name", "Name")
+        .default_value(json!("x"))
+        .hint("h")
+        .required()
+        .visible_if(FieldCondition::is_true("enabled"))
+        .enabled_if(FieldCondition::is_true("enabled"));
+    assert_struct_matches_report("ConfigField", &field);
+}
+
+#[test]
+fn wire_format_is_stable() {
+    let schema = UISchema::new().field(
+        ConfigField::integer("count", "Count")
+            .min(0)
+            .default_value(json!(1))
+            .rule(ValidationRule::Required),
+    );
+    assert_eq!(
+        serde_json::to_value(&schema).unwrap(),
+        json!({
+            "fields": [{
+                "key": "count",
+                "label": "Count",
+                "type": { "kind": "integer", "min": 0, "step": 1 },
+                "default": 1,
+                "rules": [{ "rule": "required" }],
+            }]
+        })
+    );
+
+    assert_eq!(
+        serde_json::to_value(PluginBehavior::default()).unwrap(),
+        json!({
+            "supports_start_stop": true,
+            "supports_restart": true,
+            "extendable_inputs": { "type": "none" },
+            "loads_started": true,
+        })
+    );
+
+    let port: Value = serde_json::to_value(Port::new("out")).unwrap();
+    assert_eq!(port, json!({ "id": "out" }));
+}
+
+// `api/report.json` describes the last release. Fails when the public wire
+// format changes in a way the crate version does not allow; bump the version
+// accordingly, and regenerate the report with
+// `RTSYN_UPDATE_API_REPORT=1 cargo test` when releasing.
+#[test]
+fn api_report_is_up_to_date() {
+    let current = api_report::current();
+    if std::env::var_os("RTSYN_UPDATE_API_REPORT").is_some() {
+        let json = serde_json::to_string_pretty(&current).unwrap();
+        std::fs::write(REPORT_PATH, json + "\n").unwrap();
+        return;
+    }
+
+    let saved: ApiReport =
+        serde_json::from_str(&std::fs::read_to_string(REPORT_PATH).unwrap()).unwrap();
+    let changes = api_report::unversioned_changes(&saved, &current);
+    assert!(
+        changes.is_empty(),
+        "public API changed without a matching version bump from {}: {changes:#?}",
+        saved.crate_version
+    );
+}